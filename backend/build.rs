@@ -39,6 +39,8 @@ fn main() {
             build.file("src/cpp/src/windows/native_api.cpp");
             build.file("src/cpp/src/windows/file_api.cpp");
             build.file("src/cpp/src/windows/debugger.cpp");
+            build.file("src/cpp/src/windows/guarded_copy.cpp");
+            build.file("src/cpp/src/windows/diagnostics.cpp");
         }
         "macos" => {
             println!("cargo:rustc-link-arg=-lc++");
@@ -55,7 +57,9 @@ fn main() {
             build.file("src/cpp/src/darwin/native_api.mm");
             build.file("src/cpp/src/darwin/file_api.mm");
             build.file("src/cpp/src/darwin/debugger.mm");
+            build.file("src/cpp/src/darwin/diagnostics.mm");
             build.file("src/cpp/src/common/util.cpp");
+            build.file("src/cpp/src/common/guarded_copy.cpp");
         }
         "ios" => {
             println!("cargo:rustc-link-arg=-lc++");
@@ -74,7 +78,9 @@ fn main() {
             build.file("src/cpp/src/darwin/native_api.mm");
             build.file("src/cpp/src/darwin/file_api.mm");
             build.file("src/cpp/src/darwin/debugger.mm");
+            build.file("src/cpp/src/darwin/diagnostics.mm");
             build.file("src/cpp/src/common/util.cpp");
+            build.file("src/cpp/src/common/guarded_copy.cpp");
         }
 
         "android" => {
@@ -86,6 +92,8 @@ fn main() {
             build.file("src/cpp/src/linux/native_api.cpp");
             build.file("src/cpp/src/linux/file_api.cpp");
             build.file("src/cpp/src/linux/debugger.cpp");
+            build.file("src/cpp/src/linux/diagnostics.cpp");
+            build.file("src/cpp/src/common/guarded_copy.cpp");
         }
 
         "linux" => {
@@ -94,6 +102,8 @@ fn main() {
             build.file("src/cpp/src/linux/native_api.cpp");
             build.file("src/cpp/src/linux/file_api.cpp");
             build.file("src/cpp/src/linux/debugger.cpp");
+            build.file("src/cpp/src/linux/diagnostics.cpp");
+            build.file("src/cpp/src/common/guarded_copy.cpp");
         }
 
         _ => {
@@ -0,0 +1,21 @@
+// Arbitrary bytes as an unknown-value scan file after its status flag. Parsing never panics,
+// and writing the chunks back out gives the input up to where a truncated chunk begins.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Each target uses only part of the module
+#[allow(dead_code)]
+#[path = "../../src/scan_file.rs"]
+mod scan_file;
+
+fuzz_target!(|data: &[u8]| {
+    let mut encoded = Vec::new();
+    for chunk in scan_file::parse_dump_chunks(data) {
+        encoded.extend(chunk.address.to_le_bytes());
+        encoded.extend(chunk.compressed.len().to_le_bytes());
+        encoded.extend(chunk.uncompressed_size.to_le_bytes());
+        encoded.extend(chunk.compressed);
+    }
+    assert_eq!(encoded, data[..encoded.len()]);
+});
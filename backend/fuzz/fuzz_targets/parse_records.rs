@@ -0,0 +1,23 @@
+// Arbitrary bytes as filtered results of a scan, the first byte choosing the value size.
+// Parsing never panics, and re-encoding the records gives the input up to a torn last one.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Each target uses only part of the module
+#[allow(dead_code)]
+#[path = "../../src/scan_file.rs"]
+mod scan_file;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&size, data)) = data.split_first() else {
+        return;
+    };
+    let size = size as usize % 64;
+    let mut encoded = Vec::new();
+    for (address, value) in scan_file::parse_records(data, size) {
+        scan_file::encode_record(&mut encoded, address, value);
+    }
+    assert_eq!(encoded, data[..encoded.len()]);
+    assert!(data.len() - encoded.len() < std::mem::size_of::<usize>() + size);
+});
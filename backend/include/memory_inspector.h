@@ -0,0 +1,26 @@
+#ifndef MEMORY_INSPECTOR_H
+#define MEMORY_INSPECTOR_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Entry points of the embedded server library (libmemory_inspector). The library starts a
+// server on port 3030 when loaded; set MEMORY_SERVER_NO_AUTOSTART before loading it to start
+// the server through these functions instead.
+
+// Starts the server, restarting it if one is running. config_json may be NULL or an object
+// with "host", "backend", "driver_path", "write_log" and "proxy".
+// Returns 0 on success, -1 for an invalid config, -2 when the port cannot be bound.
+int32_t start_server(uint16_t port, const char *config_json);
+
+// Returns 0 once the server has stopped, 1 when none was running.
+int32_t stop_server(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
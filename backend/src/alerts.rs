@@ -0,0 +1,216 @@
+// Pushes events to outside sinks as they happen, so unattended automation can page someone
+// instead of waiting for a client to poll /events: a webhook receiving each event as a JSON
+// POST, or in embedded mode the host app's callback, e.g. to post a local notification.
+// Delivery runs on a thread of its own so a slow sink never holds up the event source.
+use lazy_static::lazy_static;
+use libc::c_char;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::ffi::CString;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+#[serde(tag = "sink", rename_all = "snake_case")]
+pub enum Sink {
+    // Plain http only; put a relay in front for https endpoints
+    Webhook { url: String },
+    // The callback the host app registered with set_alert_callback
+    Local,
+}
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct AlertRule {
+    pub id: String,
+    #[serde(flatten)]
+    pub sink: Sink,
+    // Event types sent, e.g. ["trigger_fired", "mapping_appeared"]; all when omitted
+    pub events: Option<Vec<String>>,
+    // Events arriving sooner than this after the last one sent are dropped, for hot
+    // watchpoints
+    pub min_interval_ms: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct RemoveAlertRequest {
+    pub id: String,
+}
+
+#[derive(Default)]
+struct Delivery {
+    sent: u64,
+    dropped: u64,
+    failed: u64,
+    last_sent: Option<i64>,
+    last_error: Option<String>,
+}
+
+struct BoundRule {
+    rule: AlertRule,
+    delivery: Delivery,
+}
+
+type Callback = extern "C" fn(*const c_char);
+
+lazy_static! {
+    static ref RULES: Mutex<BTreeMap<String, BoundRule>> = Mutex::new(BTreeMap::new());
+    static ref CALLBACK: Mutex<Option<Callback>> = Mutex::new(None);
+    static ref WORKER: Mutex<Option<Sender<(String, Sink, Value)>>> = Mutex::new(None);
+}
+
+/// Registers the function that receives alerts of "local" rules as a JSON string, or removes
+/// it when null. The string is only valid during the call.
+#[no_mangle]
+pub extern "C" fn set_alert_callback(callback: Option<Callback>) {
+    *CALLBACK.lock().unwrap() = callback;
+}
+
+fn to_json(bound: &BoundRule) -> Value {
+    let mut value = serde_json::to_value(&bound.rule).unwrap_or(Value::Null);
+    let delivery = &bound.delivery;
+    value["delivery"] = json!({
+        "sent": delivery.sent,
+        "dropped": delivery.dropped,
+        "failed": delivery.failed,
+        "last_sent": delivery.last_sent,
+        "last_error": delivery.last_error,
+    });
+    value
+}
+
+pub fn list() -> Vec<Value> {
+    RULES.lock().unwrap().values().map(to_json).collect()
+}
+
+pub fn add(rule: AlertRule) -> Result<Value, String> {
+    if rule.id.is_empty() {
+        return Err("id must not be empty".to_string());
+    }
+    if let Sink::Webhook { url } = &rule.sink {
+        let uri: hyper::Uri = url.parse().map_err(|e| format!("Invalid url: {}", e))?;
+        if uri.scheme_str() != Some("http") {
+            return Err("Only http webhook urls are supported".to_string());
+        }
+    }
+    let bound = BoundRule {
+        rule,
+        delivery: Delivery::default(),
+    };
+    let value = to_json(&bound);
+    RULES.lock().unwrap().insert(bound.rule.id.clone(), bound);
+    Ok(value)
+}
+
+pub fn remove(id: &str) -> bool {
+    RULES.lock().unwrap().remove(id).is_some()
+}
+
+fn record(id: &str, result: Result<(), String>) {
+    if let Some(bound) = RULES.lock().unwrap().get_mut(id) {
+        match result {
+            Ok(()) => bound.delivery.sent += 1,
+            Err(e) => {
+                bound.delivery.failed += 1;
+                bound.delivery.last_error = Some(e);
+            }
+        }
+    }
+}
+
+async fn post(
+    client: &hyper::Client<hyper::client::HttpConnector>,
+    url: &str,
+    event: &Value,
+) -> Result<(), String> {
+    let request = hyper::Request::post(url)
+        .header("Content-Type", "application/json")
+        .body(hyper::Body::from(event.to_string()))
+        .map_err(|e| e.to_string())?;
+    let response = tokio::time::timeout(WEBHOOK_TIMEOUT, client.request(request))
+        .await
+        .map_err(|_| format!("{} timed out", url))?
+        .map_err(|e| format!("{} unreachable: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned {}", url, response.status()));
+    }
+    Ok(())
+}
+
+fn deliver_local(event: &Value) -> Result<(), String> {
+    let callback = (*CALLBACK.lock().unwrap()).ok_or("No alert callback registered")?;
+    let text = CString::new(event.to_string()).map_err(|e| e.to_string())?;
+    callback(text.as_ptr());
+    Ok(())
+}
+
+fn worker() -> Sender<(String, Sink, Value)> {
+    let mut worker = WORKER.lock().unwrap();
+    if let Some(sender) = worker.as_ref() {
+        return sender.clone();
+    }
+    let (sender, receiver) = mpsc::channel::<(String, Sink, Value)>();
+    thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let client = hyper::Client::new();
+        for (id, sink, event) in receiver {
+            let result = match sink {
+                Sink::Webhook { url } => runtime.block_on(post(&client, &url, &event)),
+                Sink::Local => deliver_local(&event),
+            };
+            record(&id, result);
+        }
+    });
+    *worker = Some(sender.clone());
+    sender
+}
+
+// Hands the event to every rule that wants its type; called by events::push_event and for
+// watchpoint hits
+pub fn notify(event: &Value) {
+    let mut rules = RULES.lock().unwrap();
+    if rules.is_empty() {
+        return;
+    }
+    let event_type = event["type"].as_str().unwrap_or("");
+    let now = chrono::Local::now().timestamp_millis();
+    let mut queued = Vec::new();
+    for (id, bound) in rules.iter_mut() {
+        let wanted = bound
+            .rule
+            .events
+            .as_ref()
+            .is_none_or(|events| events.iter().any(|name| name == event_type));
+        if !wanted {
+            continue;
+        }
+        let too_soon = bound.rule.min_interval_ms.is_some_and(|interval| {
+            bound
+                .delivery
+                .last_sent
+                .is_some_and(|last| now - last < interval as i64)
+        });
+        if too_soon {
+            bound.delivery.dropped += 1;
+            continue;
+        }
+        bound.delivery.last_sent = Some(now);
+        queued.push((id.clone(), bound.rule.sink.clone(), event.clone()));
+    }
+    drop(rules);
+    if queued.is_empty() {
+        return;
+    }
+    let sender = worker();
+    for item in queued {
+        let _ = sender.send(item);
+    }
+}
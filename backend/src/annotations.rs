@@ -0,0 +1,122 @@
+use crate::util;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct Annotation {
+    #[serde(default)]
+    pub id: u64,
+    pub address: usize,
+    #[serde(default = "default_size")]
+    pub size: usize,
+    #[serde(default)]
+    pub label: String,
+    pub color: Option<String>,
+    pub note: Option<String>,
+}
+
+fn default_size() -> usize {
+    1
+}
+
+impl Annotation {
+    fn overlaps(&self, start: usize, end: usize) -> bool {
+        self.address < end && self.address + self.size.max(1) > start
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "address": self.address,
+            "size": self.size,
+            "label": self.label,
+            "color": self.color,
+            "note": self.note,
+        })
+    }
+}
+
+lazy_static! {
+    // Serializes the read-modify-write cycles on the annotation files
+    static ref FILE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+fn annotation_path(pid: i32) -> PathBuf {
+    let mut path = util::get_data_directory(pid);
+    path.push("annotations");
+    path.push(format!("{}.json", pid));
+    path
+}
+
+fn load(pid: i32) -> Vec<Annotation> {
+    fs::read_to_string(annotation_path(pid))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save(pid: i32, annotations: &[Annotation]) -> Result<(), String> {
+    let path = annotation_path(pid);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create annotation directory: {}", e))?;
+    }
+    let raw = serde_json::to_string_pretty(annotations).map_err(|e| e.to_string())?;
+    fs::write(&path, raw).map_err(|e| format!("Failed to save annotations: {}", e))
+}
+
+// All annotations of the process, sorted by address.
+pub fn list(pid: i32) -> Vec<Annotation> {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut annotations = load(pid);
+    annotations.sort_by_key(|annotation| annotation.address);
+    annotations
+}
+
+// Adds an annotation, or replaces the one with the same id when `id` is set.
+pub fn upsert(pid: i32, mut annotation: Annotation) -> Result<Annotation, String> {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut annotations = load(pid);
+    match annotations
+        .iter_mut()
+        .find(|a| annotation.id != 0 && a.id == annotation.id)
+    {
+        Some(existing) => *existing = annotation.clone(),
+        None if annotation.id != 0 => {
+            return Err(format!("Unknown annotation id {}", annotation.id))
+        }
+        None => {
+            annotation.id = annotations.iter().map(|a| a.id).max().unwrap_or(0) + 1;
+            annotations.push(annotation.clone());
+        }
+    }
+    save(pid, &annotations)?;
+    Ok(annotation)
+}
+
+pub fn remove(pid: i32, id: u64) -> Result<bool, String> {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut annotations = load(pid);
+    let before = annotations.len();
+    annotations.retain(|annotation| annotation.id != id);
+    if annotations.len() == before {
+        return Ok(false);
+    }
+    save(pid, &annotations)?;
+    Ok(true)
+}
+
+// Annotations overlapping [start, end) out of a list returned by `list`.
+pub fn overlapping(annotations: &[Annotation], start: usize, end: usize) -> Vec<Value> {
+    annotations
+        .iter()
+        .take_while(|annotation| annotation.address < end)
+        .filter(|annotation| annotation.overlaps(start, end))
+        .map(Annotation::to_json)
+        .collect()
+}
@@ -1,10 +1,9 @@
-use byteorder::{ByteOrder, LittleEndian};
+use aho_corasick::AhoCorasick;
 use hex;
 use lazy_static::lazy_static;
 use libc::{self, c_char, c_int, c_void};
 use lz4_flex::block::compress_prepend_size;
 
-use memchr::memmem;
 use percent_encoding::percent_decode_str;
 use rayon::prelude::*;
 use regex::bytes::Regex;
@@ -12,7 +11,9 @@ use serde::Serialize;
 use serde_json::json;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::collections::HashSet;
 
+use futures_util::{SinkExt, StreamExt};
 use log::{debug, error, info, trace, warn};
 
 use std::collections::VecDeque;
@@ -23,7 +24,6 @@ use std::fs::{self, File, OpenOptions};
 use std::io::Read;
 use std::io::Write;
 use std::io::{BufRead, BufReader, BufWriter};
-use std::mem::size_of;
 use std::panic;
 use std::path::{Path, PathBuf};
 use std::process;
@@ -32,13 +32,64 @@ use std::str;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::RwLock;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use warp::hyper::Body;
 use warp::{http::Response, http::StatusCode, Filter, Rejection, Reply};
 
+use crate::alerts;
+use crate::annotations;
+use crate::batch;
+use crate::callers;
+use crate::conditions;
+use crate::crash;
+use crate::device_throttle;
+use crate::devices;
+use crate::driver;
+use crate::events;
+use crate::exclusions;
+use crate::gameguardian;
+use crate::heatmap;
+use crate::input;
+use crate::logger;
+use crate::mapping_watch;
+use crate::memory_backend::{self, MemoryBackend, SnapshotBackend};
 use crate::native_bridge;
+use crate::openapi;
+use crate::plugins;
+use crate::profiles;
 use crate::ptrscan;
+use crate::recipes;
+use crate::recorder;
+use crate::region_dump;
+use crate::region_monitor;
 use crate::request;
+use crate::result_archive;
+use crate::scan_engine;
+use crate::scan_estimate;
+use crate::scan_file;
+use crate::scan_priority;
+use crate::scan_sessions;
+use crate::scan_stats;
+use crate::scan_validation;
+use crate::scheduler;
+use crate::screen;
+use crate::screen_diff;
+use crate::selftest;
+use crate::session_gc;
+use crate::snapshot;
+use crate::stacks;
+use crate::structs;
+use crate::table;
+use crate::text_search;
+use crate::timestamps;
+use crate::tls;
+use crate::tracer;
+use crate::triggers;
 use crate::util;
+use crate::value_format;
+use crate::vec3;
+use crate::wasm_predicates;
+use crate::write_history;
 
 lazy_static! {
     static ref GLOBAL_POSITIONS: RwLock<HashMap<String, Vec<(usize, String)>>> =
@@ -66,11 +117,19 @@ pub extern "C" fn native_log(level: c_int, message: *const c_char) {
 }
 
 #[no_mangle]
-pub extern "C" fn send_register_json(register_json: *const c_char, pid: i32) {
+// Returns false when the hit fails its watchpoint condition, so the debugger skips the
+// hit action
+pub extern "C" fn send_register_json(register_json: *const c_char, pid: i32) -> bool {
     let c_str = unsafe { CStr::from_ptr(register_json) };
     let rust_str = c_str.to_str().unwrap();
 
     let mut json_value: Value = serde_json::from_str(rust_str).unwrap();
+    if heatmap::record_access(&json_value) || callers::record_hit(&json_value) {
+        return true;
+    }
+    if !conditions::passes(pid, &json_value) {
+        return false;
+    }
 
     let pc_address_hex = json_value["pc"]
         .as_str()
@@ -79,20 +138,21 @@ pub extern "C" fn send_register_json(register_json: *const c_char, pid: i32) {
     let pc_address = u64::from_str_radix(pc_address_hex.trim_start_matches("0x"), 16).unwrap();
 
     let mut buffer = [0u8; 4];
-    native_bridge::read_process_memory(
-        pid,
-        pc_address as *mut libc::c_void,
-        buffer.len(),
-        &mut buffer as &mut [u8],
-    )
-    .unwrap();
+    memory_backend::for_pid(pid)
+        .read(pid, pc_address as usize, &mut buffer)
+        .unwrap();
 
     let disassembled = util::disassemble(buffer.as_ptr(), buffer.len(), pc_address);
 
     json_value["instruction"] = json!(disassembled);
+    if tracer::record_step(&json_value) {
+        return true;
+    }
 
+    events::publish("watchpoint_hit", json_value.clone());
     let mut queue = JSON_QUEUE.lock().unwrap();
     queue.push_back(json_value.to_string());
+    true
 }
 
 pub fn with_state(
@@ -120,6 +180,11 @@ struct ServerInfo {
     arch: String,
     pid: u32,
     mode: String,
+    backend: String,
+    // Embedded mode only inspects its own process; see util::capabilities
+    restricted: bool,
+    capabilities: Vec<&'static str>,
+    device_throttle: device_throttle::Status,
 }
 
 pub async fn server_info_handler() -> Result<impl warp::Reply, warp::Rejection> {
@@ -146,18 +211,109 @@ pub async fn server_info_handler() -> Result<impl warp::Reply, warp::Rejection>
         arch: arch.to_string(),
         pid: pid,
         mode: std::env::var("MEMORY_SERVER_RUNNING_MODE").unwrap_or_else(|_| "unknown".to_string()),
+        backend: driver::backend_name(),
+        restricted: util::is_embedded(),
+        capabilities: util::capabilities(),
+        device_throttle: device_throttle::status(),
     };
 
     Ok(warp::reply::json(&server_info))
 }
 
+pub async fn openapi_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(openapi::document()))
+}
+
 pub async fn open_process_handler(
     pid_state: Arc<Mutex<Option<i32>>>,
     open_process: request::OpenProcessRequest,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    let mut pid = pid_state.lock().unwrap();
-    *pid = Some(open_process.pid);
-    Ok(warp::reply::with_status("OK", warp::http::StatusCode::OK))
+    let mut requested = 0;
+    for name in open_process.stealth.iter().flatten() {
+        match native_bridge::stealth_option(name) {
+            Some(option) => requested |= option,
+            None => {
+                return Ok(warp::reply::with_status(
+                    format!("Unknown stealth option: {}", name),
+                    StatusCode::BAD_REQUEST,
+                )
+                .into_response())
+            }
+        }
+    }
+    let applied = native_bridge::set_stealth_options(requested);
+    if applied != requested {
+        return Ok(warp::reply::with_status(
+            "Requested stealth options are not supported on this platform".to_string(),
+            StatusCode::BAD_REQUEST,
+        )
+        .into_response());
+    }
+
+    if util::is_embedded() && open_process.pid != process::id() as i32 {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&json!({
+                "error": "The embedded server can only inspect its own process",
+                "pid": process::id(),
+                "capabilities": util::capabilities(),
+            })),
+            StatusCode::FORBIDDEN,
+        )
+        .into_response());
+    }
+
+    // The driver backend bypasses the OS permission checks the diagnosis is about
+    if !driver::is_enabled() {
+        if let Ok(diagnosis) = native_bridge::diagnose(open_process.pid) {
+            if diagnosis["attachable"] == false {
+                let message = diagnosis["problems"][0]["message"]
+                    .as_str()
+                    .unwrap_or("Process cannot be inspected")
+                    .to_string();
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": message, "diagnosis": diagnosis })),
+                    StatusCode::FORBIDDEN,
+                )
+                .into_response());
+            }
+        }
+    }
+
+    // Other handlers wait on this lock, so it is not held through the attach work below
+    *pid_state.lock().unwrap() = Some(open_process.pid);
+    util::set_target_pointer_size(native_bridge::pointer_size(open_process.pid));
+    profiles::apply_on_attach(open_process.pid);
+    info!(
+        "Opened process {} through the {} backend",
+        open_process.pid,
+        memory_backend::for_pid(open_process.pid).name()
+    );
+    Ok(warp::reply::with_status("OK".to_string(), warp::http::StatusCode::OK).into_response())
+}
+
+pub async fn diagnose_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    diagnose_request: request::DiagnoseRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = match diagnose_request.pid.or(*pid_state.lock().unwrap()) {
+        Some(pid) => pid,
+        None => {
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": "Pid not set" })),
+                StatusCode::BAD_REQUEST,
+            ))
+        }
+    };
+    match native_bridge::diagnose(pid) {
+        Ok(diagnosis) => Ok(warp::reply::with_status(
+            warp::reply::json(&diagnosis),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": e })),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
 }
 
 pub async fn resolve_addr_handler(
@@ -206,12 +362,7 @@ pub async fn read_memory_handler(
 
     if let Some(pid) = *pid {
         let mut buffer: Vec<u8> = vec![0; read_memory.size];
-        let nread = native_bridge::read_process_memory(
-            pid,
-            read_memory.address as *mut libc::c_void,
-            read_memory.size,
-            &mut buffer,
-        );
+        let nread = memory_backend::for_pid(pid).read(pid, read_memory.address, &mut buffer);
         match nread {
             Ok(_) => {
                 let response = Response::builder()
@@ -244,32 +395,33 @@ pub async fn read_memory_multiple_handler(
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let pid = pid_state.lock().unwrap();
     if let Some(pid) = *pid {
+        // Per range: status (0 nothing read, 1 complete, 2 partial), bytes read, offset of
+        // the first unreadable byte (the size when complete), compressed size, then the
+        // lz4 block with unreadable bytes zeroed; status 0 sends no block
         let compressed_buffers: Vec<Vec<u8>> = read_memory_requests
             .par_iter()
             .map(|request| {
-                let mut buffer: Vec<u8> = vec![0; request.size];
-                let nread = native_bridge::read_process_memory(
-                    pid,
-                    request.address as *mut libc::c_void,
-                    request.size,
-                    &mut buffer,
-                );
-                match nread {
-                    Ok(_) => {
-                        let compressed_buffer = compress_prepend_size(&buffer);
-                        let mut result_buffer = Vec::with_capacity(8 + compressed_buffer.len());
-                        let compresed_buffer_size: u32 = compressed_buffer.len() as u32;
-                        result_buffer.extend_from_slice(&1u32.to_le_bytes());
-                        result_buffer.extend_from_slice(&compresed_buffer_size.to_le_bytes());
-                        result_buffer.extend_from_slice(&compressed_buffer);
-                        result_buffer
-                    }
-                    Err(_) => {
-                        let mut result_buffer = Vec::with_capacity(4);
-                        result_buffer.extend_from_slice(&0u32.to_le_bytes());
-                        result_buffer
-                    }
-                }
+                let read = util::read_sparse(pid, request.address, request.size);
+                let status: u32 = if read.bytes_read == 0 {
+                    0
+                } else if read.first_failure.is_none() {
+                    1
+                } else {
+                    2
+                };
+                let compressed_buffer = if status == 0 {
+                    Vec::new()
+                } else {
+                    compress_prepend_size(&read.buffer)
+                };
+                let first_failure = read.first_failure.unwrap_or(request.size);
+                let mut result_buffer = Vec::with_capacity(16 + compressed_buffer.len());
+                result_buffer.extend_from_slice(&status.to_le_bytes());
+                result_buffer.extend_from_slice(&(read.bytes_read as u32).to_le_bytes());
+                result_buffer.extend_from_slice(&(first_failure as u32).to_le_bytes());
+                result_buffer.extend_from_slice(&(compressed_buffer.len() as u32).to_le_bytes());
+                result_buffer.extend_from_slice(&compressed_buffer);
+                result_buffer
             })
             .collect();
 
@@ -292,27 +444,118 @@ pub async fn read_memory_multiple_handler(
     }
 }
 
+pub async fn read_region_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    read_region: request::ReadRegionRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+    if let Some(pid) = *pid {
+        let dump = match region_dump::RegionDump::new(pid, &read_region) {
+            Ok(dump) => dump,
+            Err(e) => {
+                let response = Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(hyper::Body::from(e))
+                    .unwrap();
+                return Ok(response);
+            }
+        };
+        let start = dump.start;
+        let size = dump.size;
+
+        let (mut sender, body) = hyper::Body::channel();
+        let handle = tokio::runtime::Handle::current();
+        tokio::task::spawn_blocking(move || {
+            for frame in dump {
+                // The client hung up
+                if handle.block_on(sender.send_data(frame.into())).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let response = Response::builder()
+            .header("Content-Type", "application/octet-stream")
+            .header("X-Region-Start", start.to_string())
+            .header("X-Region-Size", size.to_string())
+            .body(body)
+            .unwrap();
+        Ok(response)
+    } else {
+        let response = Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(hyper::Body::from("Pid not set"))
+            .unwrap();
+        Ok(response)
+    }
+}
+
 pub async fn write_memory_handler(
     pid_state: Arc<Mutex<Option<i32>>>,
     write_memory: request::WriteMemoryRequest,
+    session: String,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let pid = pid_state.lock().unwrap();
 
     if let Some(pid) = *pid {
-        let nwrite = native_bridge::write_process_memory(
-            pid,
-            write_memory.address as *mut libc::c_void,
-            write_memory.buffer.len(),
-            &write_memory.buffer,
-        );
+        let original =
+            write_history::read_original(pid, write_memory.address, write_memory.buffer.len());
+        let nwrite = if write_memory.stealth.unwrap_or(false) {
+            memory_backend::for_pid(pid).write_stealth(
+                pid,
+                write_memory.address,
+                &write_memory.buffer,
+            )
+        } else {
+            memory_backend::for_pid(pid).write(pid, write_memory.address, &write_memory.buffer)
+        };
         match nwrite {
             Ok(_) => {
+                write_history::record(
+                    pid,
+                    write_memory.address,
+                    original,
+                    &write_memory.buffer,
+                    "memory",
+                    &session,
+                );
                 let response = Response::builder()
                     .header("Content-Type", "text/plain")
                     .body(hyper::Body::from("Memory successfully written"))
                     .unwrap();
                 return Ok(response);
             }
+            Err(_) if write_memory.unprotect.unwrap_or(false) => {
+                let response = match util::write_with_protection_fallback(
+                    pid,
+                    write_memory.address,
+                    &write_memory.buffer,
+                    write_memory.stealth.unwrap_or(false),
+                ) {
+                    Ok(report) => {
+                        write_history::record(
+                            pid,
+                            write_memory.address,
+                            original,
+                            &write_memory.buffer,
+                            "memory",
+                            &session,
+                        );
+                        Response::builder()
+                            .header("Content-Type", "application/json")
+                            .body(hyper::Body::from(report.to_string()))
+                            .unwrap()
+                    }
+                    Err(e) => Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(hyper::Body::from(format!(
+                            "WriteProcessMemory error: {}",
+                            e
+                        )))
+                        .unwrap(),
+                };
+                Ok(response)
+            }
             Err(_) => {
                 let response = Response::builder()
                     .status(StatusCode::BAD_REQUEST)
@@ -320,7 +563,7 @@ pub async fn write_memory_handler(
                     .unwrap();
                 return Ok(response);
             }
-        };
+        }
     } else {
         let response = Response::builder()
             .status(StatusCode::BAD_REQUEST)
@@ -330,254 +573,735 @@ pub async fn write_memory_handler(
     }
 }
 
-pub async fn memory_scan_handler(
-    pid_state: Arc<Mutex<Option<i32>>>,
-    scan_request: request::MemoryScanRequest,
-) -> Result<impl warp::Reply, warp::Rejection> {
-    let pid = pid_state.lock().unwrap();
-
-    let mut is_suspend_success: bool = false;
-    let do_suspend = scan_request.do_suspend;
-    if let Some(pid) = *pid {
-        if do_suspend {
-            unsafe {
-                is_suspend_success = native_bridge::suspend_process(pid);
+// Adds the hex result value decoded as `data_type`, plus the other signedness for integers.
+// Types without a numeric decoding (aob, regex) get every plausible interpretation instead.
+fn add_decoded_value(entry: &mut Value, data_type: &str, value: &str, scale: Option<u32>) {
+    let Ok(bytes) = hex::decode(value) else {
+        return;
+    };
+    match data_type {
+        "aob" | "regex" => {
+            entry["decodings"] = json!(util::guess_value_types(&bytes, &[]));
+        }
+        "decimal32" | "decimal64" => {
+            if let Some(decoded) = util::decode_decimal(data_type, &bytes, scale) {
+                entry["decoded"] = json!(decoded);
             }
         }
-        // Clear global_positions for the given scan_id
-        {
-            let mut global_positions = GLOBAL_POSITIONS.write().unwrap();
-            if let Some(positions) = global_positions.get_mut(&scan_request.scan_id) {
-                positions.clear();
+        _ => {
+            if let Some(decoded) = util::decode_value(data_type, &bytes) {
+                entry["decoded"] = decoded;
             }
-            let mut global_memory = GLOBAL_MEMORY.write().unwrap();
-            if let Some(memory) = global_memory.get_mut(&scan_request.scan_id) {
-                memory.clear();
-            } else {
+            if let Some(other) = util::other_signedness(data_type) {
+                if let Some(alternate) = util::decode_value(other, &bytes) {
+                    if alternate != entry["decoded"] {
+                        entry["decoded_".to_string() + other] = alternate;
+                    }
+                }
             }
-            let mut global_scan_option = GLOBAL_SCAN_OPTION.write().unwrap();
-            global_scan_option.insert(scan_request.scan_id.clone(), scan_request.clone());
         }
-        // memory-server-data-dir/Scan_xxx cleanup and create
-        let mut scan_folder_path = PathBuf::from("");
-        let mode =
-            std::env::var("MEMORY_SERVER_RUNNING_MODE").unwrap_or_else(|_| "unknown".to_string());
-        if mode == "embedded" {
-            let cache_directory = util::get_cache_directory(pid);
-            scan_folder_path = PathBuf::from(&cache_directory);
+    }
+}
+
+// Region and module lists for the fields that need them, read once per response
+fn result_locator(pid: i32, fields: &Option<Vec<String>>) -> Option<util::AddressLocator> {
+    fields
+        .as_ref()?
+        .iter()
+        .any(|field| field == "module" || field == "region")
+        .then(|| util::AddressLocator::new(pid))
+}
+
+// Keeps only the requested columns of a JSON result entry, adding module and region
+fn project_entry(
+    entry: Value,
+    fields: &Option<Vec<String>>,
+    locator: Option<&util::AddressLocator>,
+) -> Value {
+    let Some(fields) = fields else {
+        return entry;
+    };
+    let address = entry["address"].as_u64().unwrap_or(0) as usize;
+    let mut projected = json!({ "address": entry["address"] });
+    for field in fields {
+        match field.as_str() {
+            "address" => {}
+            "module" => projected["module"] = json!(locator.and_then(|l| l.module(address))),
+            "region" => projected["region"] = json!(locator.and_then(|l| l.region(address))),
+            other => {
+                if let Some(value) = entry.get(other) {
+                    projected[other] = value.clone();
+                }
+            }
         }
-        let sanitized_scan_id = scan_request.scan_id.trim().replace(" ", "_");
-        scan_folder_path.push("memory-server-data-dir");
-        scan_folder_path.push(&sanitized_scan_id);
-        let scan_folder = Path::new(&scan_folder_path);
+    }
+    projected
+}
 
-        if scan_folder.exists() {
-            fs::remove_dir_all(&scan_folder).expect("Failed to remove directory");
+// Adds the value before the filter pass and, for numeric types, how far it moved since
+fn add_value_delta(
+    entry: &mut Value,
+    data_type: &str,
+    previous: &str,
+    current: &str,
+    scale: Option<u32>,
+) {
+    entry["previous"] = json!(previous);
+    let as_number = |value: &str| {
+        let bytes = hex::decode(value).ok()?;
+        match data_type {
+            "decimal32" | "decimal64" => util::decode_decimal(data_type, &bytes, scale),
+            _ => util::decode_value(data_type, &bytes)?.as_f64(),
         }
-        fs::create_dir_all(&scan_folder_path).expect("Failed to create directory");
+    };
+    if let (Some(before), Some(after)) = (as_number(previous), as_number(current)) {
+        entry["delta"] = json!(after - before);
+    }
+}
+
+// Scans and filters read live memory unless a saved snapshot or dump path is given.
+fn open_scan_source(
+    pid: i32,
+    from_snapshot: &Option<String>,
+    from_dump: &Option<String>,
+) -> Result<Option<SnapshotBackend>, String> {
+    let chunks = match (from_snapshot, from_dump) {
+        (Some(_), Some(_)) => return Err("from_snapshot and from_dump are exclusive".to_string()),
+        (Some(name), None) => snapshot::open(pid, name)?,
+        (None, Some(path)) => snapshot::open_path(Path::new(path))?,
+        (None, None) => return Ok(None),
+    };
+    Ok(Some(SnapshotBackend::new(chunks)))
+}
+
+// Fills as much of `buffer` as the source has at `address`, -1 when nothing could be read
+fn read_scan_source(
+    pid: i32,
+    source: &Option<SnapshotBackend>,
+    address: usize,
+    buffer: &mut [u8],
+) -> isize {
+    let backend: &dyn MemoryBackend = match source {
+        Some(snapshot) => snapshot,
+        None => memory_backend::for_pid(pid),
+    };
+    backend
+        .read(pid, address, buffer)
+        .map_or(-1, |nread| nread as isize)
+}
+
+// The address ranges a scan reads, and the snapshot to read them from if not live memory
+type ScanRanges = (Vec<(usize, usize)>, Option<SnapshotBackend>);
+
+// Ranges a scan reads after the exclusions and the allocation, static, TLS, residency and
+// snapshot restrictions, with the snapshot chunks to read from instead of live memory
+fn resolve_scan_ranges(
+    pid: i32,
+    scan_request: &request::MemoryScanRequest,
+) -> Result<ScanRanges, String> {
+    let address_ranges = exclusions::apply(
+        &scan_request.address_ranges,
+        scan_request.excluded_ranges.as_deref().unwrap_or_default(),
+    );
+    let address_ranges = match scan_request.allocation_size {
+        Some(allocation_size) => util::restrict_to_allocations(
+            &address_ranges,
+            &native_bridge::enum_allocations(pid, usize::MAX)?,
+            allocation_size,
+            scan_request.allocation_tolerance.unwrap_or(0),
+        ),
+        None => address_ranges,
+    };
+    let address_ranges = if scan_request.static_only.unwrap_or(false) {
+        util::intersect_ranges(&address_ranges, &util::static_ranges(pid))
+    } else {
+        address_ranges
+    };
+    let address_ranges = if scan_request.tls_only.unwrap_or(false) {
+        util::intersect_ranges(&address_ranges, &tls::ranges(pid)?)
+    } else {
+        address_ranges
+    };
+    let address_ranges = if scan_request.skip_nonresident.unwrap_or(false) {
+        util::resident_ranges(pid, &address_ranges)
+    } else {
+        address_ranges
+    };
+    let snapshot_source =
+        open_scan_source(pid, &scan_request.from_snapshot, &scan_request.from_dump)?;
+    let address_ranges = match &snapshot_source {
+        Some(snapshot) => snapshot::covered_ranges(snapshot.chunks(), &address_ranges),
+        None => address_ranges,
+    };
+    Ok((address_ranges, snapshot_source))
+}
 
-        let is_number = match scan_request.data_type.as_str() {
-            "int16" | "uint16" | "int32" | "uint32" | "float" | "int64" | "uint64" | "double" => {
-                true
+// Builds one automaton over every pattern of a multi-pattern scan, returned with the
+// hex encoding of each pattern so matches can be tagged with their pattern index.
+fn multi_pattern_matcher(
+    scan_request: &request::MemoryScanRequest,
+    patterns: &[String],
+) -> Result<(AhoCorasick, Vec<String>), String> {
+    if scan_request.find_type != "exact" {
+        return Err("patterns requires find_type exact".to_string());
+    }
+    if patterns.is_empty() {
+        return Err("patterns must not be empty".to_string());
+    }
+    let null_terminated = scan_request.null_terminated.unwrap_or(false);
+    let encoded = patterns
+        .iter()
+        .map(|pattern| match scan_request.data_type.as_str() {
+            "utf-8" | "utf-16" => {
+                util::encode_text(&scan_request.data_type, pattern, null_terminated)
             }
-            _ => false,
-        };
-        let found_count = Arc::new(AtomicUsize::new(0));
-        let scan_align = scan_request.align;
-        let is_error_occurred = Arc::new(Mutex::new(false));
-        let error_message = Arc::new(Mutex::new(String::new()));
+            _ => hex::decode(pattern).map_err(|e| format!("Invalid pattern {}: {}", pattern, e)),
+        })
+        .collect::<Result<Vec<Vec<u8>>, String>>()?;
+    if encoded.iter().any(|bytes| bytes.is_empty()) {
+        return Err("patterns must not contain an empty pattern".to_string());
+    }
+    let automaton = AhoCorasick::new(&encoded);
+    Ok((automaton, encoded.iter().map(hex::encode).collect()))
+}
 
-        let thread_results: Vec<Vec<(usize, String)>> = scan_request
-            .address_ranges
-            .par_iter()
-            .enumerate()
-            .flat_map(|(index, &(ref start_address, ref end_address))| {
-                let found_count = Arc::clone(&found_count);
-                let size = end_address - start_address;
-                let chunk_size = 1024 * 1024 * 16; // 16MB
-                let num_chunks = (size + chunk_size - 1) / chunk_size;
-
-                (0..num_chunks)
-                    .map(|i| {
-                        let mut error_occurred = is_error_occurred.lock().unwrap();
-                        let mut error_msg = error_message.lock().unwrap();
-
-                        if *error_occurred == true {
-                            return vec![];
-                        }
-                        let chunk_start = start_address + i * chunk_size;
-                        let chunk_end = std::cmp::min(chunk_start + chunk_size, *end_address);
-                        let chunk_size_actual = chunk_end - chunk_start;
-                        let mut buffer: Vec<u8> = vec![0; chunk_size_actual];
+// 422 listing every invalid field of a scan or filter request
+fn invalid_fields_response(errors: Vec<scan_validation::FieldError>) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::UNPROCESSABLE_ENTITY)
+        .header("Content-Type", "application/json")
+        .body(Body::from(
+            json!({ "error": "Invalid parameters", "fields": errors }).to_string(),
+        ))
+        .unwrap()
+}
 
-                        let mut local_positions = vec![];
-                        let mut local_values = vec![];
+// Stops the other threads of a scan or filter at their next chunk. The locks are only held
+// here, so threads that have not failed never wait on each other.
+fn record_error(is_error_occurred: &Mutex<bool>, error_message: &Mutex<String>, message: String) {
+    *is_error_occurred.lock().unwrap() = true;
+    *error_message.lock().unwrap() = message;
+}
 
-                        let nread = match native_bridge::read_process_memory(
-                            pid,
-                            chunk_start as *mut libc::c_void,
-                            chunk_size_actual,
-                            &mut buffer,
-                        ) {
-                            Ok(nread) => nread,
-                            Err(_) => -1,
-                        };
+// What the threads of a scan share, borrowed from memory_scan_handler
+#[derive(Clone, Copy)]
+struct ScanJob<'a> {
+    pid: i32,
+    scan_request: &'a request::MemoryScanRequest,
+    address_ranges: &'a [(usize, usize)],
+    snapshot_source: &'a Option<SnapshotBackend>,
+    snapshot_target: &'a Option<PathBuf>,
+    scan_folder_path: &'a Path,
+    scan_align: usize,
+    text_matcher: &'a Option<text_search::TextMatcher>,
+    multi_pattern: &'a Option<(AhoCorasick, Vec<String>)>,
+    bit_mask: &'a Option<Vec<u8>>,
+    nearby: &'a Option<(Vec<u8>, usize)>,
+    predicate: &'a Option<wasm_predicates::Predicate>,
+    predicate_size: Option<usize>,
+    found_count: &'a Arc<AtomicUsize>,
+    is_error_occurred: &'a Mutex<bool>,
+    error_message: &'a Mutex<String>,
+    stats: &'a scan_stats::ScanStats,
+    priority: &'a scan_priority::Priority,
+}
 
-                        if nread != -1 {
-                            if scan_request.find_type == "exact" {
-                                if scan_request.data_type == "regex" {
-                                    let regex_pattern = &scan_request.pattern;
-                                    let re = match Regex::new(regex_pattern) {
-                                        Ok(re) => re,
-                                        Err(_) => return vec![],
-                                    };
-
-                                    for cap in re.captures_iter(&buffer) {
-                                        let start = cap.get(0).unwrap().start();
-                                        if (chunk_start + start) % scan_align == 0 {
-                                            let end = cap.get(0).unwrap().end();
-                                            let value = hex::encode(&buffer[start..end]);
-                                            local_positions.push(chunk_start + start);
-                                            local_values.push(value);
-                                            found_count.fetch_add(1, Ordering::SeqCst);
-                                        }
+// Reads and matches every range in chunks, returning the matches each thread kept back
+fn scan_ranges(job: ScanJob) -> Vec<Vec<(usize, String)>> {
+    let ScanJob {
+        pid,
+        scan_request,
+        address_ranges,
+        snapshot_source,
+        snapshot_target,
+        scan_folder_path,
+        scan_align,
+        text_matcher,
+        multi_pattern,
+        bit_mask,
+        nearby,
+        predicate,
+        predicate_size,
+        found_count,
+        is_error_occurred,
+        error_message,
+        stats,
+        priority,
+    } = job;
+    let fail = |message| record_error(is_error_occurred, error_message, message);
+    address_ranges
+        .par_iter()
+        .enumerate()
+        .flat_map(|(index, &(ref start_address, ref end_address))| {
+            let found_count = Arc::clone(found_count);
+            let size = end_address - start_address;
+            let chunk_size = scan_priority::chunk_size();
+            let num_chunks = (size + chunk_size - 1) / chunk_size;
+
+            (0..num_chunks)
+                .map(|i| {
+                    if *is_error_occurred.lock().unwrap() {
+                        return vec![];
+                    }
+                    let chunk_start = start_address + i * chunk_size;
+                    let chunk_end = std::cmp::min(chunk_start + chunk_size, *end_address);
+                    let chunk_size_actual = chunk_end - chunk_start;
+                    let mut buffer: Vec<u8> = vec![0; chunk_size_actual];
+
+                    let mut local_positions = vec![];
+                    let mut local_values = vec![];
+
+                    let read_started = Instant::now();
+                    let nread = read_scan_source(pid, snapshot_source, chunk_start, &mut buffer);
+                    if nread != -1 {
+                        if let Some(dir) = &snapshot_target {
+                            let data = &buffer[..nread as usize];
+                            if let Err(e) = snapshot::append_chunk(dir, index, chunk_start, data) {
+                                fail(e);
+                                return vec![];
+                            }
+                        }
+                    }
+                    stats.record_read(
+                        (nread != -1).then_some(nread as usize),
+                        read_started.elapsed(),
+                    );
+
+                    if nread != -1 {
+                        let match_started = Instant::now();
+                        if scan_request.find_type == "exact" {
+                            if let Some((automaton, _)) = &multi_pattern {
+                                for found in automaton.find_overlapping_iter(&buffer) {
+                                    if (chunk_start + found.start()) % scan_align == 0 {
+                                        local_positions.push(chunk_start + found.start());
+                                        local_values
+                                            .push(hex::encode(&buffer[found.start()..found.end()]));
+                                        found_count.fetch_add(1, Ordering::SeqCst);
                                     }
-                                } else {
-                                    let search_bytes = match hex::decode(&scan_request.pattern) {
-                                        Ok(bytes) => bytes,
-                                        Err(_) => return vec![],
-                                    };
-
-                                    let mut buffer_offset = 0;
-                                    for pos in memmem::find_iter(&buffer, &search_bytes) {
-                                        let start = chunk_start + buffer_offset + pos;
-                                        if start % scan_align == 0 {
-                                            let value = scan_request.pattern.clone();
-                                            if is_number {
-                                                local_positions.push(start);
-                                                local_values.push(value);
-                                            } else {
-                                                local_positions.push(start);
-                                                local_values.push(value);
-                                            }
-                                            found_count.fetch_add(1, Ordering::SeqCst);
-                                        }
-                                        buffer_offset += pos + 1;
+                                }
+                            } else if let Some(matcher) = &text_matcher {
+                                for (start, end) in matcher.find_iter(&buffer) {
+                                    if (chunk_start + start) % scan_align == 0 {
+                                        local_positions.push(chunk_start + start);
+                                        local_values.push(hex::encode(&buffer[start..end]));
+                                        found_count.fetch_add(1, Ordering::SeqCst);
                                     }
                                 }
-                            } else if scan_request.find_type == "unknown" {
-                                let alignment = match scan_request.data_type.as_str() {
-                                    "int16" | "uint16" => 2,
-                                    "int32" | "uint32" | "float" => 4,
-                                    "int64" | "uint64" | "double" => 8,
-                                    _ => 1,
+                            } else if scan_request.data_type == "regex" {
+                                let regex_pattern = &scan_request.pattern;
+                                let re = match Regex::new(regex_pattern) {
+                                    Ok(re) => re,
+                                    Err(_) => return vec![],
                                 };
 
-                                let mut file_path = scan_folder_path.clone();
-                                file_path.push(format!("{}.dump", index));
-                                let file_exists = file_path.exists();
-
-                                let file = match OpenOptions::new()
-                                    .create(true)
-                                    .append(true)
-                                    .open(file_path)
-                                {
-                                    Ok(file) => file,
-                                    Err(e) => {
-                                        *error_occurred = true;
-                                        *error_msg = format!("Failed to open file: {}", e);
-                                        return vec![];
+                                for cap in re.captures_iter(&buffer) {
+                                    let start = cap.get(0).unwrap().start();
+                                    if (chunk_start + start) % scan_align == 0 {
+                                        let end = cap.get(0).unwrap().end();
+                                        let value = hex::encode(&buffer[start..end]);
+                                        local_positions.push(chunk_start + start);
+                                        local_values.push(value);
+                                        found_count.fetch_add(1, Ordering::SeqCst);
                                     }
+                                }
+                            } else if let Some(mask) = &bit_mask {
+                                let search_bytes = match hex::decode(&scan_request.pattern) {
+                                    Ok(bytes) => bytes,
+                                    Err(_) => return vec![],
                                 };
-
-                                let mut writer = BufWriter::new(file);
-
-                                if !file_exists {
-                                    // status flag
-                                    let zero_bytes = [0x00, 0x00, 0x00, 0x00];
-                                    if let Err(e) = writer.write_all(&zero_bytes) {
-                                        *error_occurred = true;
-                                        *error_msg = format!("Failed to write 4 zero bytes: {}", e);
-                                        return vec![];
-                                    }
+                                for pos in scan_engine::find_masked(
+                                    &buffer,
+                                    chunk_start,
+                                    &search_bytes,
+                                    mask,
+                                    scan_align,
+                                ) {
+                                    local_positions.push(chunk_start + pos);
+                                    local_values.push(hex::encode(&buffer[pos..pos + mask.len()]));
+                                    found_count.fetch_add(1, Ordering::SeqCst);
                                 }
+                            } else {
+                                let search_bytes = match hex::decode(&scan_request.pattern) {
+                                    Ok(bytes) => bytes,
+                                    Err(_) => return vec![],
+                                };
 
-                                if let Err(e) = writer.write_all(&chunk_start.to_le_bytes()) {
-                                    *error_occurred = true;
-                                    *error_msg = format!("Failed to write chunk_start: {}", e);
+                                for pos in scan_engine::find_exact(
+                                    &buffer,
+                                    chunk_start,
+                                    &search_bytes,
+                                    scan_align,
+                                ) {
+                                    local_positions.push(chunk_start + pos);
+                                    local_values.push(scan_request.pattern.clone());
+                                    found_count.fetch_add(1, Ordering::SeqCst);
+                                }
+                            }
+                            if let Some((needle, within)) = &nearby {
+                                let matched = local_positions.len();
+                                (local_positions, local_values) = local_positions
+                                    .into_iter()
+                                    .zip(local_values)
+                                    .filter(|(position, _)| {
+                                        util::has_nearby(
+                                            &buffer,
+                                            position - chunk_start,
+                                            needle,
+                                            *within,
+                                        )
+                                    })
+                                    .unzip();
+                                found_count
+                                    .fetch_sub(matched - local_positions.len(), Ordering::SeqCst);
+                            }
+                            if let Some(predicate) = &predicate {
+                                let matched = local_positions.len();
+                                (local_positions, local_values) = local_positions
+                                    .into_iter()
+                                    .zip(local_values)
+                                    .filter(|(position, value)| {
+                                        let start = position - chunk_start;
+                                        buffer
+                                            .get(start..start + value.len() / 2)
+                                            .is_some_and(|bytes| predicate.matches(bytes))
+                                    })
+                                    .unzip();
+                                found_count
+                                    .fetch_sub(matched - local_positions.len(), Ordering::SeqCst);
+                            }
+                        } else if scan_request.find_type == "predicate" {
+                            if let (Some(predicate), Some(size)) = (&predicate, predicate_size) {
+                                for pos in scan_engine::find_matching(
+                                    &buffer,
+                                    chunk_start,
+                                    size,
+                                    scan_align,
+                                    |value| predicate.matches(value),
+                                ) {
+                                    local_positions.push(chunk_start + pos);
+                                    local_values.push(hex::encode(&buffer[pos..pos + size]));
+                                    found_count.fetch_add(1, Ordering::SeqCst);
+                                }
+                            }
+                        } else if scan_request.find_type == "unknown" {
+                            let alignment = scan_engine::element_size(&scan_request.data_type);
+
+                            let mut file_path = scan_folder_path.to_path_buf();
+                            file_path.push(format!("{}.dump", index));
+                            let file_exists = file_path.exists();
+
+                            let file = match OpenOptions::new()
+                                .create(true)
+                                .append(true)
+                                .open(file_path)
+                            {
+                                Ok(file) => file,
+                                Err(e) => {
+                                    fail(format!("Failed to open file: {}", e));
                                     return vec![];
                                 }
+                            };
 
-                                let compressed_buffer = lz4_flex::block::compress(&buffer);
+                            let mut writer = BufWriter::new(file);
 
-                                if let Err(e) = writer
-                                    .write_all(&(compressed_buffer.len() as u64).to_le_bytes())
-                                {
-                                    *error_occurred = true;
-                                    *error_msg =
-                                        format!("Failed to write compressed buffer length: {}", e);
+                            if !file_exists {
+                                // status flag
+                                let zero_bytes = [0x00, 0x00, 0x00, 0x00];
+                                if let Err(e) = writer.write_all(&zero_bytes) {
+                                    fail(format!("Failed to write 4 zero bytes: {}", e));
                                     return vec![];
                                 }
+                            }
 
-                                if let Err(e) =
-                                    writer.write_all(&(buffer.len() as u64).to_le_bytes())
-                                {
-                                    *error_occurred = true;
-                                    *error_msg = format!(
-                                        "Failed to write uncompressed buffer length: {}",
-                                        e
-                                    );
-                                    return vec![];
-                                }
+                            if let Err(e) = writer.write_all(&chunk_start.to_le_bytes()) {
+                                fail(format!("Failed to write chunk_start: {}", e));
+                                return vec![];
+                            }
 
-                                if let Err(e) = writer.write_all(&compressed_buffer) {
-                                    *error_occurred = true;
-                                    *error_msg = format!("Failed to write buffer data: {}", e);
-                                    return vec![];
-                                }
+                            let compressed_buffer = lz4_flex::block::compress(&buffer);
 
-                                if let Err(e) = writer.flush() {
-                                    *error_occurred = true;
-                                    *error_msg = format!("Failed to flush buffer: {}", e);
-                                    return vec![];
-                                }
-                                found_count.fetch_add(buffer.len() / alignment, Ordering::SeqCst);
+                            if let Err(e) =
+                                writer.write_all(&(compressed_buffer.len() as u64).to_le_bytes())
+                            {
+                                fail(format!("Failed to write compressed buffer length: {}", e));
+                                return vec![];
                             }
-                            // Check if local_positions exceed MAX_RESULTS and insert into global_positions
-                            if local_positions.len() > MAX_RESULTS {
-                                let mut global_positions = GLOBAL_POSITIONS.write().unwrap();
-                                let combined: Vec<(usize, String)> = local_positions
-                                    .into_iter()
-                                    .zip(local_values.into_iter())
-                                    .collect();
-                                if let Some(positions) =
-                                    global_positions.get_mut(&scan_request.scan_id)
-                                {
-                                    positions.extend(combined);
-                                } else {
-                                    global_positions.insert(scan_request.scan_id.clone(), combined);
-                                }
-                                local_positions = vec![];
-                                local_values = vec![];
+
+                            if let Err(e) = writer.write_all(&(buffer.len() as u64).to_le_bytes()) {
+                                fail(format!("Failed to write uncompressed buffer length: {}", e));
+                                return vec![];
+                            }
+
+                            if let Err(e) = writer.write_all(&compressed_buffer) {
+                                fail(format!("Failed to write buffer data: {}", e));
+                                return vec![];
+                            }
+
+                            if let Err(e) = writer.flush() {
+                                fail(format!("Failed to flush buffer: {}", e));
+                                return vec![];
+                            }
+                            found_count.fetch_add(buffer.len() / alignment, Ordering::SeqCst);
+                        }
+                        // Check if local_positions exceed MAX_RESULTS and insert into global_positions
+                        if local_positions.len() > MAX_RESULTS {
+                            let mut global_positions = GLOBAL_POSITIONS.write().unwrap();
+                            let combined: Vec<(usize, String)> = local_positions
+                                .into_iter()
+                                .zip(local_values.into_iter())
+                                .collect();
+                            if let Some(positions) = global_positions.get_mut(&scan_request.scan_id)
+                            {
+                                positions.extend(combined);
+                            } else {
+                                global_positions.insert(scan_request.scan_id.clone(), combined);
                             }
+                            local_positions = vec![];
+                            local_values = vec![];
                         }
+                        stats.record_match(match_started.elapsed());
+                    }
+                    priority.pause();
 
-                        let combined: Vec<(usize, String)> = local_positions
-                            .into_iter()
-                            .zip(local_values.into_iter())
-                            .collect();
-                        combined
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .collect();
-        let mut do_play = GLOBAL_PROCESS_STATE.write().unwrap();
-        if do_suspend && is_suspend_success && *do_play {
-            unsafe {
-                native_bridge::resume_process(pid);
+                    let combined: Vec<(usize, String)> = local_positions
+                        .into_iter()
+                        .zip(local_values.into_iter())
+                        .collect();
+                    combined
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+pub async fn memory_scan_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    mut scan_request: request::MemoryScanRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    profiles::apply_scan_defaults(&mut scan_request);
+    let errors = scan_validation::validate_scan(&scan_request);
+    if !errors.is_empty() {
+        return Ok(invalid_fields_response(errors));
+    }
+    let pid = pid_state.lock().unwrap();
+
+    let mut text_matcher = None;
+    if let Some(text) = &scan_request.text {
+        let null_terminated = scan_request.null_terminated.unwrap_or(false);
+        let case_insensitive = scan_request.case_insensitive.unwrap_or(false);
+        let normalize = scan_request.normalize.unwrap_or(false);
+        let prepared =
+            util::encode_text(&scan_request.data_type, text, null_terminated).and_then(|bytes| {
+                scan_request.pattern = hex::encode(bytes);
+                if !case_insensitive && !normalize {
+                    return Ok(None);
+                }
+                text_search::TextMatcher::new(
+                    &scan_request.data_type,
+                    text,
+                    case_insensitive,
+                    normalize,
+                    null_terminated,
+                )
+                .map(Some)
+            });
+        match prepared {
+            Ok(matcher) => text_matcher = matcher,
+            Err(e) => {
+                let response = Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(hyper::Body::from(e))
+                    .unwrap();
+                return Ok(response);
             }
         }
-        // println!("{}", found_count.load(Ordering::SeqCst));
+    } else if scan_request.case_insensitive.is_some() || scan_request.normalize.is_some() {
+        let response = Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(hyper::Body::from(
+                "case_insensitive and normalize require text",
+            ))
+            .unwrap();
+        return Ok(response);
+    }
+
+    if let Some(display_value) = &scan_request.display_value {
+        match util::encode_decimal(&scan_request.data_type, display_value, scan_request.scale) {
+            Ok(bytes) => scan_request.pattern = hex::encode(bytes),
+            Err(e) => {
+                let response = Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(hyper::Body::from(e))
+                    .unwrap();
+                return Ok(response);
+            }
+        }
+    }
+
+    let multi_pattern = match &scan_request.patterns {
+        Some(patterns) => match multi_pattern_matcher(&scan_request, patterns) {
+            Ok(matcher) => Some(matcher),
+            Err(e) => {
+                let response = Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(hyper::Body::from(e))
+                    .unwrap();
+                return Ok(response);
+            }
+        },
+        None => None,
+    };
+
+    let nearby = match &scan_request.nearby {
+        Some(nearby) => match hex::decode(&nearby.pattern) {
+            Ok(bytes) if !bytes.is_empty() && scan_request.find_type == "exact" => {
+                Some((bytes, nearby.within))
+            }
+            _ => {
+                let response = Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(hyper::Body::from(
+                        "nearby requires a non-empty hex pattern and find_type exact",
+                    ))
+                    .unwrap();
+                return Ok(response);
+            }
+        },
+        None => None,
+    };
+
+    // Checked by validate_scan, like the window size of find_type "predicate"
+    let predicate = scan_request
+        .predicate
+        .as_deref()
+        .and_then(wasm_predicates::get);
+    let predicate_size = util::data_type_size(&scan_request.data_type)
+        .or_else(|| predicate.as_ref().and_then(|predicate| predicate.size()));
+
+    let bit_mask =
+        match util::parse_bit_mask(&scan_request.data_type, scan_request.bit_mask.as_deref()) {
+            Ok(bit_mask) => bit_mask,
+            Err(e) => {
+                let response = Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(hyper::Body::from(e))
+                    .unwrap();
+                return Ok(response);
+            }
+        };
+
+    let mut is_suspend_success: bool = false;
+    let do_suspend = scan_request.do_suspend;
+    if let Some(pid) = *pid {
+        let (address_ranges, snapshot_source) = match resolve_scan_ranges(pid, &scan_request) {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                let response = Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(hyper::Body::from(e))
+                    .unwrap();
+                return Ok(response);
+            }
+        };
+        let snapshot_target = match &scan_request.save_snapshot {
+            Some(name) => match snapshot::create(pid, name) {
+                Ok(dir) => Some(dir),
+                Err(e) => {
+                    let response = Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(hyper::Body::from(e))
+                        .unwrap();
+                    return Ok(response);
+                }
+            },
+            None => None,
+        };
+        if do_suspend {
+            unsafe {
+                is_suspend_success = native_bridge::suspend_process(pid);
+            }
+        }
+        // Clear global_positions for the given scan_id
+        {
+            let mut global_positions = GLOBAL_POSITIONS.write().unwrap();
+            if let Some(positions) = global_positions.get_mut(&scan_request.scan_id) {
+                positions.clear();
+            }
+            let mut global_memory = GLOBAL_MEMORY.write().unwrap();
+            if let Some(memory) = global_memory.get_mut(&scan_request.scan_id) {
+                memory.clear();
+            } else {
+            }
+            let mut global_scan_option = GLOBAL_SCAN_OPTION.write().unwrap();
+            global_scan_option.insert(scan_request.scan_id.clone(), scan_request.clone());
+            scan_sessions::touch(&scan_request.scan_id);
+        }
+        // memory-server-data-dir/Scan_xxx cleanup and create
+        let mut scan_folder_path = PathBuf::from("");
+        let mode =
+            std::env::var("MEMORY_SERVER_RUNNING_MODE").unwrap_or_else(|_| "unknown".to_string());
+        if mode == "embedded" {
+            let cache_directory = util::get_cache_directory(pid);
+            scan_folder_path = PathBuf::from(&cache_directory);
+        }
+        let sanitized_scan_id = scan_request.scan_id.trim().replace(" ", "_");
+        scan_folder_path.push("memory-server-data-dir");
+        scan_folder_path.push(&sanitized_scan_id);
+        let scan_folder = Path::new(&scan_folder_path);
+
+        if scan_folder.exists() {
+            fs::remove_dir_all(&scan_folder).expect("Failed to remove directory");
+        }
+        fs::create_dir_all(&scan_folder_path).expect("Failed to create directory");
+
+        let found_count = Arc::new(AtomicUsize::new(0));
+        let scan_align = scan_request.align;
+        let is_error_occurred = Arc::new(Mutex::new(false));
+        let error_message = Arc::new(Mutex::new(String::new()));
+        let stats = scan_stats::ScanStats::new(&scan_request.address_ranges, &address_ranges);
+        // Already checked by validate_scan
+        let priority = scan_priority::Priority::parse(scan_request.priority.as_deref()).unwrap();
+
+        let job = ScanJob {
+            pid,
+            scan_request: &scan_request,
+            address_ranges: &address_ranges,
+            snapshot_source: &snapshot_source,
+            snapshot_target: &snapshot_target,
+            scan_folder_path: &scan_folder_path,
+            scan_align,
+            text_matcher: &text_matcher,
+            multi_pattern: &multi_pattern,
+            bit_mask: &bit_mask,
+            nearby: &nearby,
+            predicate: &predicate,
+            predicate_size,
+            found_count: &found_count,
+            is_error_occurred: &is_error_occurred,
+            error_message: &error_message,
+            stats: &stats,
+            priority: &priority,
+        };
+        let thread_results = priority.run(|| scan_ranges(job));
+        let mut do_play = GLOBAL_PROCESS_STATE.write().unwrap();
+        if do_suspend && is_suspend_success && *do_play {
+            unsafe {
+                native_bridge::resume_process(pid);
+            }
+        }
+        // println!("{}", found_count.load(Ordering::SeqCst));
+        stats.remember_throughput();
+        let stats = stats.report();
+        if scan_request.log_stats.unwrap_or(false) {
+            info!("Scan {} finished: {}", scan_request.scan_id, stats);
+        }
+        scan_sessions::start(
+            &scan_request.scan_id,
+            Some(pid),
+            "scan",
+            &scan_request,
+            found_count.load(Ordering::SeqCst),
+        );
 
         let flattened_results: Vec<(usize, String)> =
             thread_results.into_iter().flatten().collect();
@@ -605,19 +1329,49 @@ pub async fn memory_scan_handler(
                 } else {
                     is_rounded = limited_positions.len() != positions.len();
                 }
+                let notes = annotations::list(pid);
+                let static_ranges = util::static_ranges(pid);
+                let decode_values = scan_request.decode_values.unwrap_or(false);
+                let locator = result_locator(pid, &scan_request.fields);
                 let matched_addresses: Vec<serde_json::Value> = limited_positions
                     .into_iter()
                     .map(|(address, value)| {
-                        json!({
+                        let mut entry = json!({
                             "address": address,
-                            "value": value
-                        })
+                            "value": value,
+                            "static": util::in_ranges(&static_ranges, *address)
+                        });
+                        let matching =
+                            annotations::overlapping(&notes, *address, address + value.len() / 2);
+                        if !matching.is_empty() {
+                            entry["annotations"] = json!(matching);
+                        }
+                        if decode_values {
+                            add_decoded_value(
+                                &mut entry,
+                                &scan_request.data_type,
+                                value,
+                                scan_request.scale,
+                            );
+                        }
+                        if let Some((_, encoded)) = &multi_pattern {
+                            entry["pattern_index"] = json!(encoded.iter().position(|p| p == value));
+                        }
+                        if let Some(struct_name) = &scan_request.struct_name {
+                            let base =
+                                address.wrapping_sub(scan_request.struct_offset.unwrap_or(0));
+                            entry["struct"] = structs::read_struct(pid, base, struct_name)
+                                .map(|decoded| decoded["fields"].clone())
+                                .unwrap_or(Value::Null);
+                        }
+                        project_entry(entry, &scan_request.fields, locator.as_ref())
                     })
                     .collect();
                 let result = json!({
                     "matched_addresses": matched_addresses,
                     "found":count,
-                    "is_rounded":is_rounded
+                    "is_rounded":is_rounded,
+                    "stats": stats
                 });
                 let result_string = result.to_string();
                 let response = Response::builder()
@@ -636,7 +1390,7 @@ pub async fn memory_scan_handler(
             let global_positions = GLOBAL_POSITIONS.read().unwrap();
             if let Some(_positions) = global_positions.get(&scan_request.scan_id) {
                 let count = found_count.load(Ordering::SeqCst);
-                let result_string = json!({ "found": count }).to_string();
+                let result_string = json!({ "found": count, "stats": stats }).to_string();
                 let response = Response::builder()
                     .header("Content-Type", "application/json")
                     .body(hyper::Body::from(result_string))
@@ -659,26 +1413,274 @@ pub async fn memory_scan_handler(
     }
 }
 
-macro_rules! compare_values {
-    ($val:expr, $old_val:expr, $filter_method:expr) => {
-        match $filter_method {
-            "changed" => $val != $old_val,
-            "unchanged" => $val == $old_val,
-            "increased" => $val > $old_val,
-            "decreased" => $val < $old_val,
-            _ => false,
+pub async fn scan_estimate_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    scan_request: request::MemoryScanRequest,
+) -> Result<Response<Body>, warp::Rejection> {
+    let errors = scan_validation::validate_scan(&scan_request);
+    if !errors.is_empty() {
+        return Ok(invalid_fields_response(errors));
+    }
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let response = match resolve_scan_ranges(pid, &scan_request) {
+            Ok((address_ranges, snapshot_source)) => {
+                let estimate = scan_estimate::estimate(
+                    pid,
+                    &scan_request.address_ranges,
+                    &address_ranges,
+                    snapshot_source.is_some(),
+                );
+                Response::builder()
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(estimate.to_string()))
+                    .unwrap()
+            }
+            Err(e) => Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from(e))
+                .unwrap(),
+        };
+        Ok(response)
+    } else {
+        Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(Body::from("Pid not set"))
+            .unwrap())
+    }
+}
+
+// What the threads of a filter share, borrowed from memory_filter_handler
+#[derive(Clone, Copy)]
+struct FilterJob<'a> {
+    pid: i32,
+    filter_request: &'a request::MemoryFilterRequest,
+    snapshot_source: &'a Option<SnapshotBackend>,
+    size: usize,
+    bit_mask: &'a Option<Vec<u8>>,
+    filter_predicate: &'a Option<wasm_predicates::Predicate>,
+    plugin_filter: bool,
+    found_count: &'a Arc<AtomicUsize>,
+    is_error_occurred: &'a Mutex<bool>,
+    error_message: &'a Mutex<String>,
+    priority: &'a scan_priority::Priority,
+}
+
+// Filters the values an unknown scan saved to `paths`, rewriting each file with the records
+// that pass
+fn filter_dump_files(
+    job: FilterJob,
+    paths: &[PathBuf],
+    scan_align: usize,
+    passes: &(impl Fn(&[u8], &[u8]) -> bool + Sync),
+) {
+    let FilterJob {
+        pid,
+        snapshot_source,
+        size,
+        found_count,
+        is_error_occurred,
+        error_message,
+        priority,
+        ..
+    } = job;
+    let fail = |message| record_error(is_error_occurred, error_message, message);
+    paths.par_iter().for_each(|file_path| {
+        priority.pause();
+        if *is_error_occurred.lock().unwrap() {
+            return;
         }
-    };
+        let mut serialized_data: Vec<u8> = Vec::new();
+        if let Ok(file) = File::open(file_path) {
+            let mut reader = BufReader::new(file);
+            let mut data_buffer: Vec<u8> = Vec::new();
+            if let Err(e) = reader.read_to_end(&mut data_buffer) {
+                fail(format!("Failed to read file: {}", e));
+                return;
+            }
+            let Some((status_flag, records)) = data_buffer.split_first_chunk::<4>() else {
+                fail(format!("Truncated scan file: {:?}", file_path));
+                return;
+            };
+            if *status_flag == [0x00, 0x00, 0x00, 0x00] {
+                for chunk in scan_file::parse_dump_chunks(records) {
+                    let decompressed_data = match lz4_flex::block::decompress(
+                        chunk.compressed,
+                        chunk.uncompressed_size,
+                    ) {
+                        Ok(data) => data,
+                        Err(e) => {
+                            fail(format!("Failed to decompress data: {}", e));
+                            return;
+                        }
+                    };
+
+                    let address = chunk.address;
+                    let mut buffer: Vec<u8> = vec![0; decompressed_data.len()];
+                    let nread = read_scan_source(pid, snapshot_source, address, &mut buffer);
+                    if nread == -1 {
+                        return;
+                    }
+                    for offset in scan_engine::aligned_offsets(
+                        address,
+                        decompressed_data.len(),
+                        size,
+                        scan_align,
+                    ) {
+                        let old_val = &decompressed_data[offset..offset + size];
+                        let new_val = &buffer[offset..offset + size];
+                        if passes(new_val, old_val) {
+                            scan_file::encode_record(
+                                &mut serialized_data,
+                                address + offset,
+                                new_val,
+                            );
+                            found_count.fetch_add(1, Ordering::SeqCst);
+                        }
+                    }
+                }
+            } else {
+                for (address, old_val) in scan_file::parse_records(records, size) {
+                    let mut new_val: Vec<u8> = vec![0; size];
+                    let nread = read_scan_source(pid, snapshot_source, address, &mut new_val);
+                    if nread != size as isize {
+                        println!("Incomplete read at address {:x}", address);
+                        continue;
+                    }
+                    if passes(&new_val, old_val) {
+                        scan_file::encode_record(&mut serialized_data, address, &new_val);
+                        found_count.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            }
+        }
+
+        // rewrite file
+        let mut file = match OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(file_path)
+        {
+            Ok(file) => file,
+            Err(e) => {
+                fail(format!("Failed to open file for writing: {}", e));
+                return;
+            }
+        };
+
+        let number: u32 = 0x00000001;
+        if let Err(e) = file.write_all(&number.to_le_bytes()) {
+            fail(format!("Failed to write status flag: {}", e));
+            return;
+        }
+
+        if let Err(e) = file.write_all(&serialized_data) {
+            fail(format!("Failed to write data: {}", e));
+            return;
+        }
+    })
+}
+
+// Filters the results of an exact scan, re-reading each address
+fn filter_positions(
+    job: FilterJob,
+    positions: &[(usize, String)],
+) -> Result<Vec<Option<(usize, String)>>, &'static str> {
+    let FilterJob {
+        pid,
+        filter_request,
+        snapshot_source,
+        bit_mask,
+        filter_predicate,
+        plugin_filter,
+        found_count,
+        ..
+    } = job;
+    positions
+        .par_iter()
+        .map(|(address, value)| {
+            let mut buffer: Vec<u8> = vec![0; (value.len() / 2) as usize];
+            let _nread = read_scan_source(pid, snapshot_source, *address, &mut buffer);
+
+            if _nread == -1 {
+                return Ok(None);
+            }
+
+            if filter_request.data_type == "regex" {
+                let regex_pattern = &filter_request.pattern;
+                let re = match Regex::new(regex_pattern) {
+                    Ok(re) => re,
+                    Err(_) => return Ok(None),
+                };
+                if re.is_match(&buffer) {
+                    found_count.fetch_add(1, Ordering::SeqCst);
+                    return Ok(Some((*address, hex::encode(&buffer))));
+                }
+            } else {
+                if filter_request.filter_method == "exact" {
+                    let result = hex::decode(&filter_request.pattern);
+                    let bytes = match result {
+                        Ok(bytes) => bytes,
+                        Err(_) => return Err("Invalid hex pattern"),
+                    };
+                    let matched = scan_engine::matches_exact(&buffer, &bytes, bit_mask.as_deref());
+                    if matched {
+                        found_count.fetch_add(1, Ordering::SeqCst);
+                        return Ok(Some((*address, hex::encode(&buffer))));
+                    }
+                } else {
+                    let result = hex::decode(&value);
+                    let bytes = match result {
+                        Ok(bytes) => bytes,
+                        Err(_) => return Err("Invalid hex pattern"),
+                    };
+                    let pass_filter = match &filter_predicate {
+                        Some(predicate) => predicate.matches(&buffer),
+                        None if plugin_filter => plugins::compare(
+                            &filter_request.filter_method,
+                            &filter_request.data_type,
+                            &buffer,
+                            &bytes,
+                        ),
+                        None => scan_engine::compare(
+                            &filter_request.data_type,
+                            &filter_request.filter_method,
+                            &buffer,
+                            &bytes,
+                            bit_mask.as_deref(),
+                        ),
+                    };
+
+                    if pass_filter {
+                        found_count.fetch_add(1, Ordering::SeqCst);
+                        return Ok(Some((*address, hex::encode(&buffer))));
+                    }
+                }
+            }
+            Ok(None)
+        })
+        .collect()
 }
 
 pub async fn memory_filter_handler(
     pid_state: Arc<Mutex<Option<i32>>>,
-    filter_request: request::MemoryFilterRequest,
+    mut filter_request: request::MemoryFilterRequest,
 ) -> Result<impl warp::Reply, warp::Rejection> {
+    let scan_exists = GLOBAL_SCAN_OPTION
+        .read()
+        .unwrap()
+        .contains_key(&filter_request.scan_id);
+    let errors = scan_validation::validate_filter(&filter_request, scan_exists);
+    if !errors.is_empty() {
+        return Ok(invalid_fields_response(errors));
+    }
     let pid = pid_state.lock().unwrap();
 
     let mut is_suspend_success: bool = false;
     let do_suspend = filter_request.do_suspend;
+    // Keeps the stale session collection away from a scan that is in use
+    scan_sessions::touch(&filter_request.scan_id);
     if let Some(pid) = *pid {
         let mut new_positions = Vec::new();
         let mut global_positions = GLOBAL_POSITIONS.write().unwrap();
@@ -689,14 +1691,62 @@ pub async fn memory_filter_handler(
             .unwrap()
             .clone();
         let found_count = Arc::new(AtomicUsize::new(0));
-        let size = match filter_request.data_type.as_str() {
-            "int16" | "uint16" => 2,
-            "int32" | "uint32" | "float" => 4,
-            "int64" | "uint64" | "double" => 8,
-            _ => 1,
+        let size = scan_engine::element_size(&filter_request.data_type);
+        if let Some(display_value) = &filter_request.display_value {
+            let scale = filter_request.scale.or(scan_option.scale);
+            match util::encode_decimal(&filter_request.data_type, display_value, scale) {
+                Ok(bytes) => filter_request.pattern = hex::encode(bytes),
+                Err(e) => {
+                    let response = Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(hyper::Body::from(e))
+                        .unwrap();
+                    return Ok(response);
+                }
+            }
+        }
+        let filter_predicate = if filter_request.filter_method == "predicate" {
+            filter_request
+                .predicate
+                .as_deref()
+                .and_then(wasm_predicates::get)
+        } else {
+            None
+        };
+        // Plugin data types and filter methods are compared by the plugin
+        let plugin_filter =
+            plugins::handles_filter(&filter_request.filter_method, &filter_request.data_type);
+        let bit_mask = match util::parse_bit_mask(
+            &filter_request.data_type,
+            filter_request.bit_mask.as_deref(),
+        ) {
+            Ok(bit_mask) => bit_mask,
+            Err(e) => {
+                let response = Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(hyper::Body::from(e))
+                    .unwrap();
+                return Ok(response);
+            }
         };
+        // Already checked by validate_filter
+        let priority = scan_priority::Priority::parse(filter_request.priority.as_deref()).unwrap();
         let is_error_occurred = Arc::new(Mutex::new(false));
         let error_message = Arc::new(Mutex::new(String::new()));
+        let snapshot_source = match open_scan_source(
+            pid,
+            &filter_request.from_snapshot,
+            &filter_request.from_dump,
+        ) {
+            Ok(source) => source,
+            Err(e) => {
+                let response = Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(hyper::Body::from(e))
+                    .unwrap();
+                return Ok(response);
+            }
+        };
 
         let mut scan_folder_path = PathBuf::from("");
         let mode =
@@ -709,6 +1759,20 @@ pub async fn memory_filter_handler(
         scan_folder_path.push("memory-server-data-dir");
         scan_folder_path.push(&sanitized_scan_id);
 
+        let job = FilterJob {
+            pid,
+            filter_request: &filter_request,
+            snapshot_source: &snapshot_source,
+            size,
+            bit_mask: &bit_mask,
+            filter_predicate: &filter_predicate,
+            plugin_filter,
+            found_count: &found_count,
+            is_error_occurred: &is_error_occurred,
+            error_message: &error_message,
+            priority: &priority,
+        };
+
         // unknown search
         if scan_option.find_type == "unknown" {
             if do_suspend {
@@ -722,15 +1786,23 @@ pub async fn memory_filter_handler(
                     .filter_map(|entry| entry.ok().map(|e| e.path()))
                     .collect::<Vec<_>>(),
                 Err(e) => {
-                    let mut error_occurred = is_error_occurred.lock().unwrap();
-                    let mut error_msg = error_message.lock().unwrap();
-                    *error_occurred = true;
-                    *error_msg = format!("Failed to read directory: {}", e);
+                    record_error(
+                        &is_error_occurred,
+                        &error_message,
+                        format!("Failed to read directory: {}", e),
+                    );
                     vec![]
                 }
             };
 
             let scan_align = scan_option.align;
+            // Drops NaN, infinities, denormals and extreme magnitudes from float candidates
+            let simple_values = (scan_option.simple_values.unwrap_or(false)
+                || filter_request.simple_values.unwrap_or(false))
+                && matches!(
+                    filter_request.data_type.as_str(),
+                    "float" | "double" | "float16" | "bfloat16"
+                );
 
             let mut exact_bytes: Vec<u8> = vec![];
             if filter_request.filter_method.as_str() == "exact" {
@@ -740,207 +1812,29 @@ pub async fn memory_filter_handler(
                 };
             }
 
-            if !*is_error_occurred.lock().unwrap() {
-                paths.par_iter().for_each(|file_path| {
-                    let mut error_occurred = is_error_occurred.lock().unwrap();
-                    let mut error_msg = error_message.lock().unwrap();
-                    if *error_occurred {
-                        return;
-                    }
-                    let mut serialized_data: Vec<u8> = Vec::new();
-                    if let Ok(file) = File::open(file_path) {
-                        let mut reader = BufReader::new(file);
-                        let mut data_buffer: Vec<u8> = Vec::new();
-                        if let Err(e) = reader.read_to_end(&mut data_buffer) {
-                            *error_occurred = true;
-                            *error_msg = format!("Failed to read file: {}", e);
-                            return;
-                        }
-                        let status_flag: [u8; 4] = match data_buffer[0..4].try_into() {
-                            Ok(flag) => flag,
-                            Err(e) => {
-                                *error_occurred = true;
-                                *error_msg = format!("Invalid address format: {}", e);
-                                return;
-                            }
-                        };
-                        let mut offset = 4;
-                        let usize_size = size_of::<usize>();
-                        if status_flag == [0x00, 0x00, 0x00, 0x00] {
-                            while offset + 3 * usize_size <= data_buffer.len() {
-                                let address = usize::from_le_bytes(
-                                    data_buffer[offset..offset + usize_size]
-                                        .try_into()
-                                        .expect("Invalid address format"),
-                                );
-
-                                offset += usize_size;
-
-                                let compressed_data_size = usize::from_le_bytes(
-                                    data_buffer[offset..offset + usize_size]
-                                        .try_into()
-                                        .expect("Invalid length format"),
-                                );
-                                offset += usize_size;
-
-                                let uncompressed_data_size = usize::from_le_bytes(
-                                    data_buffer[offset..offset + usize_size]
-                                        .try_into()
-                                        .expect("Invalid length format"),
-                                );
-                                offset += usize_size;
-
-                                if offset + compressed_data_size <= data_buffer.len() {
-                                    let compressed_data =
-                                        &data_buffer[offset..offset + compressed_data_size];
-                                    offset += compressed_data_size;
-                                    let decompressed_data = match lz4_flex::block::decompress(
-                                        &compressed_data,
-                                        uncompressed_data_size,
-                                    ) {
-                                        Ok(data) => data,
-                                        Err(e) => {
-                                            *error_occurred = true;
-                                            *error_msg =
-                                                format!("Failed to decompress data: {}", e);
-                                            return;
-                                        }
-                                    };
-
-                                    let mut buffer: Vec<u8> =
-                                        vec![0; (decompressed_data.len()) as usize];
-                                    let _nread = match native_bridge::read_process_memory(
-                                        pid,
-                                        address as *mut libc::c_void,
-                                        decompressed_data.len(),
-                                        &mut buffer,
-                                    ) {
-                                        Ok(nread) => nread,
-                                        Err(_err) => -1,
-                                    };
-
-                                    if _nread == -1 {
-                                        return;
-                                    }
-                                    for offset in (0..decompressed_data.len()).step_by(1) {
-                                        if (address + offset) % scan_align != 0 {
-                                            continue;
-                                        }
-                                        if offset + size > decompressed_data.len() {
-                                            break;
-                                        }
-                                        let old_val = &decompressed_data[offset..offset + size];
-                                        let new_val = &buffer[offset..offset + size];
-
-                                        let mut pass_filter: bool = false;
-                                        if filter_request.filter_method.as_str() == "exact" {
-                                            if exact_bytes == new_val {
-                                                pass_filter = true;
-                                            }
-                                        } else {
-                                            pass_filter = match filter_request.data_type.as_str() {
-                                                _ => compare_values!(
-                                                    new_val,
-                                                    old_val,
-                                                    filter_request.filter_method.as_str()
-                                                ),
-                                            };
-                                        }
-                                        if pass_filter {
-                                            serialized_data.extend_from_slice(
-                                                &(address + offset).to_le_bytes(),
-                                            );
-                                            serialized_data.extend_from_slice(new_val);
-                                            found_count.fetch_add(1, Ordering::SeqCst);
-                                        }
-                                    }
-                                } else {
-                                    break;
-                                }
-                            }
-                        } else {
-                            while offset + usize_size + size <= data_buffer.len() {
-                                let address = match data_buffer.get(offset..offset + usize_size) {
-                                    Some(slice) => usize::from_le_bytes(
-                                        slice.try_into().expect("Invalid address format"),
-                                    ),
-                                    None => break,
-                                };
-                                offset += usize_size;
-
-                                let old_val = &data_buffer[offset..offset + size];
-                                offset += size;
-
-                                let mut new_val_vec: Vec<u8> = vec![0; size];
-                                let nread = match native_bridge::read_process_memory(
-                                    pid,
-                                    address as *mut libc::c_void,
-                                    size,
-                                    &mut new_val_vec,
-                                ) {
-                                    Ok(nread) => nread,
-                                    Err(_) => {
-                                        continue;
-                                    }
-                                };
-
-                                if nread != size as isize {
-                                    println!("Incomplete read at address {:x}", address);
-                                    continue;
-                                }
-                                let new_val: &[u8] = &new_val_vec;
-
-                                let mut pass_filter: bool = false;
-                                if filter_request.filter_method.as_str() == "exact" {
-                                    if exact_bytes == new_val {
-                                        pass_filter = true;
-                                    }
-                                } else {
-                                    pass_filter = match filter_request.data_type.as_str() {
-                                        _ => compare_values!(
-                                            new_val,
-                                            old_val,
-                                            filter_request.filter_method.as_str()
-                                        ),
-                                    };
-                                }
-
-                                if pass_filter {
-                                    serialized_data.extend_from_slice(&address.to_le_bytes());
-                                    serialized_data.extend_from_slice(&new_val);
-                                    found_count.fetch_add(1, Ordering::SeqCst);
-                                }
-                            }
-                        }
-                    }
-
-                    // rewrite file
-                    let mut file = match OpenOptions::new()
-                        .write(true)
-                        .truncate(true)
-                        .open(file_path)
-                    {
-                        Ok(file) => file,
-                        Err(e) => {
-                            *error_occurred = true;
-                            *error_msg = format!("Failed to open file for writing: {}", e);
-                            return;
-                        }
-                    };
-
-                    let number: u32 = 0x00000001;
-                    if let Err(e) = file.write_all(&number.to_le_bytes()) {
-                        *error_occurred = true;
-                        *error_msg = format!("Failed to write status flag: {}", e);
-                        return;
-                    }
+            let filter_method = filter_request.filter_method.as_str();
+            let data_type = filter_request.data_type.as_str();
+            let passes = |new_val: &[u8], old_val: &[u8]| {
+                let pass = if filter_method == "exact" {
+                    scan_engine::matches_exact(new_val, &exact_bytes, bit_mask.as_deref())
+                } else if let Some(predicate) = &filter_predicate {
+                    predicate.matches(new_val)
+                } else if plugin_filter {
+                    plugins::compare(filter_method, data_type, new_val, old_val)
+                } else {
+                    scan_engine::compare(
+                        data_type,
+                        filter_method,
+                        new_val,
+                        old_val,
+                        bit_mask.as_deref(),
+                    )
+                };
+                pass && (!simple_values || util::is_simple_float(data_type, new_val))
+            };
 
-                    if let Err(e) = file.write_all(&serialized_data) {
-                        *error_occurred = true;
-                        *error_msg = format!("Failed to write data: {}", e);
-                        return;
-                    }
-                });
+            if !*is_error_occurred.lock().unwrap() {
+                priority.run(|| filter_dump_files(job, &paths, scan_align, &passes));
             }
 
             new_positions = if found_count.load(Ordering::SeqCst) < 1_000_000 {
@@ -971,21 +1865,10 @@ pub async fn memory_filter_handler(
                             return Vec::new();
                         }
 
-                        let mut local_results = Vec::new();
-                        let mut offset = 0;
-                        while offset + std::mem::size_of::<usize>() + size <= data.len() {
-                            let address = usize::from_le_bytes(
-                                data[offset..offset + std::mem::size_of::<usize>()]
-                                    .try_into()
-                                    .unwrap(),
-                            );
-                            offset += std::mem::size_of::<usize>();
-                            let value = hex::encode(&data[offset..offset + size]);
-                            offset += size;
-                            local_results.push((address, value));
-                        }
-
-                        local_results
+                        scan_file::parse_records(&data, size)
+                            .into_iter()
+                            .map(|(address, value)| (address, hex::encode(value)))
+                            .collect::<Vec<_>>()
                     })
                     .collect();
                 results
@@ -999,226 +1882,23 @@ pub async fn memory_filter_handler(
                     is_suspend_success = native_bridge::suspend_process(pid);
                 }
             }
-            let results: Result<Vec<_>, _> = positions
-                .par_iter()
-                .map(|(address, value)| {
-                    let mut buffer: Vec<u8> = vec![0; (value.len() / 2) as usize];
-                    let _nread = match native_bridge::read_process_memory(
-                        pid,
-                        *address as *mut libc::c_void,
-                        filter_request.pattern.len(),
-                        &mut buffer,
-                    ) {
-                        Ok(nread) => nread,
-                        Err(_err) => -1,
-                    };
-
-                    if _nread == -1 {
-                        return Ok(None);
-                    }
-
-                    if filter_request.data_type == "regex" {
-                        let regex_pattern = &filter_request.pattern;
-                        let re = match Regex::new(regex_pattern) {
-                            Ok(re) => re,
-                            Err(_) => return Ok(None),
-                        };
-                        if re.is_match(&buffer) {
-                            found_count.fetch_add(1, Ordering::SeqCst);
-                            return Ok(Some((*address, hex::encode(&buffer))));
-                        }
-                    } else {
-                        if filter_request.filter_method == "exact" {
-                            let result = hex::decode(&filter_request.pattern);
-                            let bytes = match result {
-                                Ok(bytes) => bytes,
-                                Err(_) => {
-                                    let response = Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(hyper::Body::from("Invalid hex pattern"))
-                                        .unwrap();
-                                    return Err(response);
-                                }
-                            };
-                            if buffer == bytes {
-                                found_count.fetch_add(1, Ordering::SeqCst);
-                                return Ok(Some((*address, hex::encode(&buffer))));
-                            }
-                        } else {
-                            let result = hex::decode(&value);
-                            let bytes = match result {
-                                Ok(bytes) => bytes,
-                                Err(_) => {
-                                    let response = Response::builder()
-                                        .status(StatusCode::BAD_REQUEST)
-                                        .body(hyper::Body::from("Invalid hex pattern"))
-                                        .unwrap();
-                                    return Err(response);
-                                }
-                            };
-                            let pass_filter: bool;
-
-                            pass_filter = match filter_request.data_type.as_str() {
-                                "int8" => {
-                                    let old_val = i8::from_le_bytes(bytes.try_into().unwrap());
-                                    let val = i8::from_le_bytes(buffer.clone().try_into().unwrap());
-                                    compare_values!(
-                                        val,
-                                        old_val,
-                                        filter_request.filter_method.as_str()
-                                    )
-                                }
-                                "uint8" => {
-                                    let old_val = u8::from_le_bytes(bytes.try_into().unwrap());
-                                    let val = u8::from_le_bytes(buffer.clone().try_into().unwrap());
-                                    compare_values!(
-                                        val,
-                                        old_val,
-                                        filter_request.filter_method.as_str()
-                                    )
-                                }
-                                "int16" => {
-                                    let old_val = i16::from_le_bytes(bytes.try_into().unwrap());
-                                    let val =
-                                        i16::from_le_bytes(buffer.clone().try_into().unwrap());
-                                    compare_values!(
-                                        val,
-                                        old_val,
-                                        filter_request.filter_method.as_str()
-                                    )
-                                }
-                                "uint16" => {
-                                    let old_val = u16::from_le_bytes(bytes.try_into().unwrap());
-                                    let val =
-                                        u16::from_le_bytes(buffer.clone().try_into().unwrap());
-                                    compare_values!(
-                                        val,
-                                        old_val,
-                                        filter_request.filter_method.as_str()
-                                    )
-                                }
-                                "int32" => {
-                                    let old_val = i32::from_le_bytes(bytes.try_into().unwrap());
-                                    let val =
-                                        i32::from_le_bytes(buffer.clone().try_into().unwrap());
-                                    compare_values!(
-                                        val,
-                                        old_val,
-                                        filter_request.filter_method.as_str()
-                                    )
-                                }
-                                "uint32" => {
-                                    let old_val = u32::from_le_bytes(bytes.try_into().unwrap());
-                                    let val =
-                                        u32::from_le_bytes(buffer.clone().try_into().unwrap());
-                                    compare_values!(
-                                        val,
-                                        old_val,
-                                        filter_request.filter_method.as_str()
-                                    )
-                                }
-                                "int64" => {
-                                    let old_val = i64::from_le_bytes(bytes.try_into().unwrap());
-                                    let val =
-                                        i64::from_le_bytes(buffer.clone().try_into().unwrap());
-                                    compare_values!(
-                                        val,
-                                        old_val,
-                                        filter_request.filter_method.as_str()
-                                    )
-                                }
-                                "uint64" => {
-                                    let old_val = u64::from_le_bytes(bytes.try_into().unwrap());
-                                    let val =
-                                        u64::from_le_bytes(buffer.clone().try_into().unwrap());
-                                    compare_values!(
-                                        val,
-                                        old_val,
-                                        filter_request.filter_method.as_str()
-                                    )
-                                }
-                                "float" => {
-                                    let old_val = LittleEndian::read_f32(&bytes);
-                                    let val = LittleEndian::read_f32(&buffer.clone());
-                                    compare_values!(
-                                        val,
-                                        old_val,
-                                        filter_request.filter_method.as_str()
-                                    )
-                                }
-                                "double" => {
-                                    let old_val = LittleEndian::read_f64(&bytes);
-                                    let val = LittleEndian::read_f64(&buffer.clone());
-                                    compare_values!(
-                                        val,
-                                        old_val,
-                                        filter_request.filter_method.as_str()
-                                    )
-                                }
-                                "utf-8" => {
-                                    let old_val = str::from_utf8(&bytes).unwrap_or("");
-                                    let val = str::from_utf8(&buffer).unwrap_or("");
-                                    match filter_request.filter_method.as_str() {
-                                        "changed" => val != old_val,
-                                        "unchanged" => val == old_val,
-                                        _ => false,
-                                    }
-                                }
-                                "utf-16" => {
-                                    let buffer_u16: Vec<u16> = buffer
-                                        .clone()
-                                        .chunks_exact(2)
-                                        .map(|b| u16::from_ne_bytes([b[0], b[1]]))
-                                        .collect();
-                                    match filter_request.filter_method.as_str() {
-                                        "changed" => {
-                                            let old_value: Vec<u16> = hex::decode(&value)
-                                                .unwrap()
-                                                .chunks_exact(2)
-                                                .map(|b| u16::from_ne_bytes([b[0], b[1]]))
-                                                .collect();
-                                            buffer_u16 != old_value
-                                        }
-                                        "unchanged" => {
-                                            let old_value: Vec<u16> = hex::decode(&value)
-                                                .unwrap()
-                                                .chunks_exact(2)
-                                                .map(|b| u16::from_ne_bytes([b[0], b[1]]))
-                                                .collect();
-                                            buffer_u16 == old_value
-                                        }
-                                        _ => false,
-                                    }
-                                }
-                                "aob" => match filter_request.filter_method.as_str() {
-                                    "changed" => buffer != bytes,
-                                    "unchanged" => buffer == bytes,
-                                    _ => false,
-                                },
-                                _ => false,
-                            };
-
-                            if pass_filter {
-                                found_count.fetch_add(1, Ordering::SeqCst);
-                                return Ok(Some((*address, hex::encode(&buffer))));
-                            }
-                        }
-                    }
-                    Ok(None)
-                })
-                .collect();
+            let results = priority.run(|| filter_positions(job, positions));
 
             match results {
                 Ok(results) => {
                     new_positions = results.into_iter().filter_map(|x| x).collect();
                 }
-                Err(response) => {
+                Err(e) => {
                     let mut do_play = GLOBAL_PROCESS_STATE.write().unwrap();
                     if do_suspend && is_suspend_success && *do_play {
                         unsafe {
                             native_bridge::resume_process(pid);
                         }
                     }
+                    let response = Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(hyper::Body::from(e))
+                        .unwrap();
                     return Ok(response);
                 }
             }
@@ -1235,11 +1915,34 @@ pub async fn memory_filter_handler(
                 native_bridge::resume_process(pid);
             }
         }
-        global_positions.insert(filter_request.scan_id.clone(), new_positions.clone());
+        let static_ranges = util::static_ranges(pid);
+        if filter_request.static_only.unwrap_or(false) {
+            let before = new_positions.len();
+            new_positions.retain(|(address, _)| util::in_ranges(&static_ranges, *address));
+            found_count.fetch_sub(before - new_positions.len(), Ordering::SeqCst);
+        }
+        let previous_positions =
+            global_positions.insert(filter_request.scan_id.clone(), new_positions.clone());
+        scan_sessions::record(
+            &filter_request.scan_id,
+            "filter",
+            &filter_request,
+            found_count.load(Ordering::SeqCst),
+        );
 
         if filter_request.return_as_json {
             let limited_positions =
                 &new_positions[..std::cmp::min(MAX_RESULTS, new_positions.len())];
+            // Values from before this pass, for the listed addresses only
+            let listed: HashSet<usize> = limited_positions
+                .iter()
+                .map(|&(address, _)| address)
+                .collect();
+            let previous_values: HashMap<usize, String> = previous_positions
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|(address, _)| listed.contains(address))
+                .collect();
             let is_rounded: bool;
             let count = found_count.load(Ordering::SeqCst);
             if scan_option.find_type == "unknown" {
@@ -1251,13 +1954,39 @@ pub async fn memory_filter_handler(
             } else {
                 is_rounded = limited_positions.len() != new_positions.len();
             }
+            let notes = annotations::list(pid);
+            let decode_values = filter_request
+                .decode_values
+                .or(scan_option.decode_values)
+                .unwrap_or(false);
+            let scale = filter_request.scale.or(scan_option.scale);
+            let locator = result_locator(pid, &filter_request.fields);
             let matched_addresses: Vec<serde_json::Value> = limited_positions
                 .iter()
                 .map(|(address, value)| {
-                    json!({
+                    let mut entry = json!({
                         "address": address,
-                        "value": value
-                    })
+                        "value": value,
+                        "static": util::in_ranges(&static_ranges, *address)
+                    });
+                    let matching =
+                        annotations::overlapping(&notes, *address, address + value.len() / 2);
+                    if !matching.is_empty() {
+                        entry["annotations"] = json!(matching);
+                    }
+                    if decode_values {
+                        add_decoded_value(&mut entry, &filter_request.data_type, value, scale);
+                    }
+                    if let Some(previous) = previous_values.get(address) {
+                        add_value_delta(
+                            &mut entry,
+                            &filter_request.data_type,
+                            previous,
+                            value,
+                            scale,
+                        );
+                    }
+                    project_entry(entry, &filter_request.fields, locator.as_ref())
                 })
                 .collect();
 
@@ -1299,276 +2028,3217 @@ struct Region {
     file_path: Option<String>,
 }
 
-pub async fn enumerate_regions_handler(
+// Registers `positions` as the results of an exact scan of `data_type`, so filters can
+// narrow down results that did not come from /memoryscan
+fn store_scan_results(scan_id: &str, data_type: &str, positions: Vec<(usize, String)>) {
+    let scan_option = request::MemoryScanRequest {
+        pattern: String::new(),
+        address_ranges: Vec::new(),
+        find_type: "exact".to_string(),
+        data_type: data_type.to_string(),
+        scan_id: scan_id.to_string(),
+        align: 1,
+        return_as_json: false,
+        do_suspend: false,
+        struct_name: None,
+        struct_offset: None,
+        allocation_size: None,
+        allocation_tolerance: None,
+        skip_nonresident: None,
+        log_stats: None,
+        save_snapshot: None,
+        from_snapshot: None,
+        from_dump: None,
+        excluded_ranges: None,
+        simple_values: None,
+        decode_values: None,
+        text: None,
+        null_terminated: None,
+        case_insensitive: None,
+        normalize: None,
+        patterns: None,
+        nearby: None,
+        static_only: None,
+        tls_only: None,
+        bit_mask: None,
+        display_value: None,
+        scale: None,
+        fields: None,
+        priority: None,
+        predicate: None,
+    };
+    GLOBAL_MEMORY.write().unwrap().remove(scan_id);
+    GLOBAL_SCAN_OPTION
+        .write()
+        .unwrap()
+        .insert(scan_id.to_string(), scan_option);
+    GLOBAL_POSITIONS
+        .write()
+        .unwrap()
+        .insert(scan_id.to_string(), positions);
+}
+
+// Creates a scan_id from externally computed candidates so they can be narrowed down
+// with /memoryfilter like the results of an exact scan.
+pub async fn import_scan_handler(
     pid_state: Arc<Mutex<Option<i32>>>,
+    import_request: request::ImportScanRequest,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let pid = pid_state.lock().unwrap();
 
     if let Some(pid) = *pid {
-        let mut buffer = vec![0u8; 1024 * 1024];
-
-        unsafe {
-            native_bridge::enumerate_regions_to_buffer(pid, buffer.as_mut_ptr(), buffer.len());
-        }
-        let buffer_cstring = unsafe { CString::from_vec_unchecked(buffer) };
-        let buffer_string = buffer_cstring.into_string().unwrap();
-        let buffer_reader = BufReader::new(buffer_string.as_bytes());
-
-        let mut regions = Vec::new();
-
-        for line in buffer_reader.lines() {
-            if let Ok(line) = line {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-
-                if parts.len() >= 5 {
-                    let addresses: Vec<&str> = parts[0].split('-').collect();
-                    if addresses.len() == 2 {
-                        let region = Region {
-                            start_address: addresses[0].to_string(),
-                            end_address: addresses[1].to_string(),
-                            protection: parts[1].to_string(),
-                            file_path: if parts.len() > 5 {
-                                Some(parts[5..].join(" "))
-                            } else {
-                                None
-                            },
-                        };
-                        regions.push(region);
+        let size = import_request
+            .size
+            .or_else(|| util::data_type_size(&import_request.data_type));
+        let mut positions = Vec::with_capacity(import_request.entries.len());
+        let mut unreadable = Vec::new();
+        for entry in &import_request.entries {
+            let value = match &entry.value {
+                Some(value) => match hex::decode(value) {
+                    Ok(bytes) if size.is_none_or(|size| size == bytes.len()) => bytes,
+                    _ => {
+                        return Ok(warp::reply::with_status(
+                            warp::reply::json(&json!({
+                                "error": format!("Invalid value for 0x{:x}", entry.address)
+                            })),
+                            StatusCode::BAD_REQUEST,
+                        ))
+                    }
+                },
+                None => {
+                    let Some(size) = size else {
+                        return Ok(warp::reply::with_status(
+                            warp::reply::json(&json!({
+                                "error": "size is required to read values of this data type"
+                            })),
+                            StatusCode::BAD_REQUEST,
+                        ));
+                    };
+                    let mut buffer = vec![0u8; size];
+                    match memory_backend::for_pid(pid).read(pid, entry.address, &mut buffer) {
+                        Ok(nread) if nread == size => buffer,
+                        _ => {
+                            unreadable.push(entry.address);
+                            continue;
+                        }
                     }
                 }
-            }
+            };
+            positions.push((entry.address, hex::encode(value)));
         }
+        positions.sort_by_key(|&(address, _)| address);
+        positions.dedup_by_key(|(address, _)| *address);
+
+        let found = positions.len();
+        store_scan_results(
+            &import_request.scan_id,
+            &import_request.data_type,
+            positions,
+        );
+        scan_sessions::start(
+            &import_request.scan_id,
+            Some(pid),
+            "import",
+            &import_request,
+            found,
+        );
 
-        let result = json!({ "regions": regions });
-        let result_string = result.to_string();
-        let response = Response::builder()
-            .header("Content-Type", "application/json")
-            .body(hyper::Body::from(result_string))
-            .unwrap();
-        Ok(response)
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({
+                "found": found,
+                "unreadable": unreadable,
+            })),
+            StatusCode::OK,
+        ))
     } else {
-        let response = Response::builder()
-            .status(StatusCode::BAD_REQUEST)
-            .body(hyper::Body::from("Pid not set"))
-            .unwrap();
-        Ok(response)
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
     }
 }
 
-pub async fn enumerate_process_handler() -> Result<impl Reply, Rejection> {
-    let mut count: usize = 0;
-    let process_info_ptr = unsafe { native_bridge::enumprocess_native(&mut count) };
-    let process_info_slice = unsafe { std::slice::from_raw_parts(process_info_ptr, count) };
+pub async fn timestamp_scan_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    timestamp_request: request::TimestampScanRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
 
-    let mut json_array = Vec::new();
-    for i in 0..count {
-        let process_name = unsafe {
-            CStr::from_ptr(process_info_slice[i].processname)
-                .to_string_lossy()
-                .into_owned()
-        };
-        json_array.push(json!({
-            "pid": process_info_slice[i].pid,
-            "processname": process_name
-        }));
-        unsafe { libc::free(process_info_slice[i].processname as *mut libc::c_void) };
+    if let Some(pid) = *pid {
+        match timestamps::scan(pid, &timestamp_request) {
+            Ok(result) => {
+                let max_results = timestamp_request.max_results.unwrap_or(MAX_RESULTS);
+                let matched_addresses: Vec<Value> = result
+                    .positions
+                    .iter()
+                    .take(max_results)
+                    .map(|(address, value)| {
+                        let mut entry = json!({ "address": address, "value": value });
+                        add_decoded_value(&mut entry, &result.data_type, value, None);
+                        entry
+                    })
+                    .collect();
+                let found = result.positions.len();
+                store_scan_results(
+                    &timestamp_request.scan_id,
+                    &result.data_type,
+                    result.positions,
+                );
+                scan_sessions::start(
+                    &timestamp_request.scan_id,
+                    Some(pid),
+                    "timestamps",
+                    &timestamp_request,
+                    found,
+                );
+                Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({
+                        "found": found,
+                        "now": result.now,
+                        "data_type": result.data_type,
+                        "matched_addresses": matched_addresses,
+                    })),
+                    StatusCode::OK,
+                ))
+            }
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::BAD_REQUEST,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
     }
+}
 
-    // for cdylib
-    if count == 0 {
-        let pid = unsafe { native_bridge::get_pid_native() };
-        json_array.push(json!({
-            "pid": pid,
-            "processname": "self".to_string()
-        }));
-    } else {
-        unsafe {
-            libc::free(process_info_ptr as *mut libc::c_void);
+pub async fn vec3_scan_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    vec3_request: request::Vec3ScanRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        match vec3::scan(pid, &vec3_request) {
+            Ok(matches) => {
+                let max_results = vec3_request.max_results.unwrap_or(MAX_RESULTS);
+                let matched_addresses: Vec<Value> = matches
+                    .iter()
+                    .take(max_results)
+                    .map(|found| {
+                        let [x, y, z] = found.coordinates;
+                        json!({ "address": found.address, "x": x, "y": y, "z": z })
+                    })
+                    .collect();
+                let found = matches.len();
+                // The whole x..z span is kept, so changed/unchanged filters track all three
+                let positions = matches
+                    .into_iter()
+                    .map(|found| (found.address, hex::encode(found.bytes)))
+                    .collect();
+                store_scan_results(&vec3_request.scan_id, "aob", positions);
+                scan_sessions::start(
+                    &vec3_request.scan_id,
+                    Some(pid),
+                    "vec3",
+                    &vec3_request,
+                    found,
+                );
+                Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({
+                        "found": found,
+                        "matched_addresses": matched_addresses,
+                    })),
+                    StatusCode::OK,
+                ))
+            }
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::BAD_REQUEST,
+            )),
         }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
     }
-
-    let json_response = warp::reply::json(&json_array);
-    Ok(json_response)
 }
 
-pub async fn enummodule_handler(
+pub async fn stack_scan_handler(
     pid_state: Arc<Mutex<Option<i32>>>,
+    stack_scan_request: request::StackScanRequest,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let pid = pid_state.lock().unwrap();
+
     if let Some(pid) = *pid {
-        let modules = native_bridge::enum_modules(pid).unwrap();
-        let result = json!({ "modules": modules });
-        let result_string = result.to_string();
-        let response = Response::builder()
-            .header("Content-Type", "application/json")
-            .body(hyper::Body::from(result_string))
-            .unwrap();
-        Ok(response)
+        match stacks::scan(pid, &stack_scan_request) {
+            Ok(result) => Ok(warp::reply::with_status(
+                warp::reply::json(&result),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::BAD_REQUEST,
+            )),
+        }
     } else {
-        let response = Response::builder()
-            .status(StatusCode::BAD_REQUEST)
-            .body(hyper::Body::from("Pid not set"))
-            .unwrap();
-        Ok(response)
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": "Pid not set" })),
+            StatusCode::BAD_REQUEST,
+        ))
     }
 }
 
-pub async fn explore_directory_handler(
-    req: request::ExploreDirectoryRequest,
-) -> Result<impl Reply, Rejection> {
-    let decoded_path = percent_decode_str(&req.path)
-        .decode_utf8_lossy()
-        .into_owned();
+pub async fn export_results_handler(
+    export_request: request::ExportResultsRequest,
+) -> Result<Response<Body>, warp::Rejection> {
+    let scan_option = GLOBAL_SCAN_OPTION
+        .read()
+        .unwrap()
+        .get(&export_request.scan_id)
+        .cloned();
+    let global_positions = GLOBAL_POSITIONS.read().unwrap();
+    let (Some(scan_option), Some(positions)) =
+        (scan_option, global_positions.get(&export_request.scan_id))
+    else {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from("Unknown scan_id"))
+            .unwrap());
+    };
+    // Unknown scans keep raw memory until the first filter, which has no per-address form
+    if positions.is_empty()
+        && GLOBAL_MEMORY
+            .read()
+            .unwrap()
+            .contains_key(&export_request.scan_id)
+    {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(Body::from("Filter an unknown scan before exporting it"))
+            .unwrap());
+    }
+    let archive = result_archive::encode(&scan_option, positions);
+    scan_sessions::touch(&export_request.scan_id);
+    let file_name = export_request.scan_id.replace(['"', '/', '\\'], "_");
+    Ok(Response::builder()
+        .header("Content-Type", "application/octet-stream")
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename=\"{}.fyrs\"", file_name),
+        )
+        .body(Body::from(archive))
+        .unwrap())
+}
 
-    let c_path = match CString::new(decoded_path.clone()) {
-        Ok(path) => path,
-        Err(_) => {
-            return Ok(warp::reply::with_status(
-                warp::reply::json(&json!({
-                    "error": "Invalid path: contains null byte",
-                    "path": decoded_path,
-                    "max_depth": req.max_depth
-                })),
-                warp::http::StatusCode::BAD_REQUEST,
+pub async fn import_results_handler(
+    import_request: request::ImportResultsRequest,
+    archive: hyper::body::Bytes,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match result_archive::decode(&archive) {
+        Ok((mut scan_option, positions)) => {
+            let scan_id = import_request
+                .scan_id
+                .clone()
+                .unwrap_or_else(|| scan_option.scan_id.clone());
+            scan_option.scan_id = scan_id.clone();
+            let found = positions.len();
+            GLOBAL_MEMORY.write().unwrap().remove(&scan_id);
+            GLOBAL_SCAN_OPTION
+                .write()
+                .unwrap()
+                .insert(scan_id.clone(), scan_option);
+            GLOBAL_POSITIONS
+                .write()
+                .unwrap()
+                .insert(scan_id.clone(), positions);
+            // The archive may come from another machine, so no process is recorded
+            scan_sessions::start(&scan_id, None, "archive", &import_request, found);
+            Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "scan_id": scan_id, "found": found })),
+                StatusCode::OK,
             ))
         }
-    };
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": e })),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
 
-    let result = panic::catch_unwind(|| unsafe {
-        let result_ptr = native_bridge::explore_directory(c_path.as_ptr(), req.max_depth as c_int);
-        if result_ptr.is_null() {
-            return Err("Null pointer returned from explore_directory");
-        }
-        let result_str = CStr::from_ptr(result_ptr).to_string_lossy().into_owned();
-        libc::free(result_ptr as *mut libc::c_void);
-        Ok(result_str)
-    });
+pub fn scan_ids() -> Vec<String> {
+    GLOBAL_SCAN_OPTION.read().unwrap().keys().cloned().collect()
+}
 
-    let result = match result {
-        Ok(Ok(result)) => result,
-        Ok(Err(err)) => {
-            return Ok(warp::reply::with_status(
-                warp::reply::json(&json!({
-                    "error": err,
-                    "path": decoded_path,
-                    "max_depth": req.max_depth
-                })),
-                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-            ))
+// Frees everything kept for a scan_id, including the unknown scan files on disk
+pub fn drop_scan(pid: i32, scan_id: &str) {
+    GLOBAL_POSITIONS.write().unwrap().remove(scan_id);
+    GLOBAL_MEMORY.write().unwrap().remove(scan_id);
+    GLOBAL_SCAN_OPTION.write().unwrap().remove(scan_id);
+    scan_sessions::remove(scan_id);
+    let scan_folder = util::get_data_directory(pid).join(scan_id.trim().replace(' ', "_"));
+    if scan_folder.is_dir() {
+        let _ = fs::remove_dir_all(scan_folder);
+    }
+}
+
+pub async fn list_scans_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    let scan_options = GLOBAL_SCAN_OPTION.read().unwrap();
+    let global_positions = GLOBAL_POSITIONS.read().unwrap();
+    let mut scans: Vec<Value> = scan_options
+        .iter()
+        .map(|(scan_id, scan_option)| {
+            let results = global_positions
+                .get(scan_id)
+                .map_or(0, |positions| positions.len());
+            let mut entry = json!({
+                "scan_id": scan_id,
+                "data_type": scan_option.data_type,
+                "find_type": scan_option.find_type,
+                "results": results,
+            });
+            if let Some(session) = scan_sessions::get(scan_id) {
+                entry["name"] = json!(session.name);
+                entry["notes"] = json!(session.notes);
+                entry["pid"] = json!(session.pid);
+                entry["process_name"] = json!(session.process_name);
+                entry["created"] = json!(session.created);
+                entry["last_used"] = json!(session.last_used);
+                entry["history"] = json!(session.history);
+            }
+            entry
+        })
+        .collect();
+    scans.sort_by_key(|scan| scan["created"].as_i64().unwrap_or(0));
+    Ok(warp::reply::json(&json!({ "scans": scans })))
+}
+
+pub async fn scan_metadata_handler(
+    metadata_request: request::ScanMetadataRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if !GLOBAL_SCAN_OPTION
+        .read()
+        .unwrap()
+        .contains_key(&metadata_request.scan_id)
+    {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": "Unknown scan_id" })),
+            StatusCode::NOT_FOUND,
+        ));
+    }
+    scan_sessions::annotate(
+        &metadata_request.scan_id,
+        metadata_request.name,
+        metadata_request.notes,
+    );
+    Ok(warp::reply::with_status(
+        warp::reply::json(&json!(scan_sessions::get(&metadata_request.scan_id))),
+        StatusCode::OK,
+    ))
+}
+
+pub async fn gc_status_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&session_gc::status()))
+}
+
+// Applies the given limits and collects right away
+pub async fn gc_handler(
+    gc_request: session_gc::GcConfigRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let config = session_gc::configure(gc_request);
+    let report = tokio::task::spawn_blocking(session_gc::collect)
+        .await
+        .unwrap_or(Value::Null);
+    Ok(warp::reply::json(
+        &json!({ "config": config, "last_run": report }),
+    ))
+}
+
+// Groups the results of a scan by the mapping and protection they fall in, busiest first.
+pub async fn result_summary_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    summary_request: request::ResultSummaryRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let global_positions = GLOBAL_POSITIONS.read().unwrap();
+        let Some(positions) = global_positions.get(&summary_request.scan_id) else {
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": "Unknown scan_id" })),
+                StatusCode::NOT_FOUND,
+            ));
+        };
+
+        let mut regions: Vec<(usize, usize, String, String)> = native_bridge::enum_regions(pid)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|region| {
+                let start =
+                    usize::from_str_radix(region["start_address"].as_str().unwrap_or(""), 16)
+                        .ok()?;
+                let end =
+                    usize::from_str_radix(region["end_address"].as_str().unwrap_or(""), 16).ok()?;
+                Some((
+                    start,
+                    end,
+                    region["protection"].as_str().unwrap_or("").to_string(),
+                    region["file_path"].as_str().unwrap_or("").to_string(),
+                ))
+            })
+            .collect();
+        regions.sort_by_key(|region| region.0);
+        let mut modules: Vec<(usize, usize, String)> = native_bridge::enum_modules(pid)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|module| {
+                let base = module["base"].as_u64()? as usize;
+                let size = module["size"].as_u64()? as usize;
+                Some((
+                    base,
+                    base + size,
+                    module["modulename"].as_str()?.to_string(),
+                ))
+            })
+            .collect();
+        modules.sort_by_key(|module| module.0);
+
+        // (mapping, protection) -> (count, regions seen, first address)
+        let mut groups: HashMap<(String, String), (usize, Vec<usize>, usize)> = HashMap::new();
+        let mut unmapped = 0;
+        for (address, _) in positions {
+            let index = regions.partition_point(|region| region.0 <= *address);
+            let Some(region) = index
+                .checked_sub(1)
+                .map(|index| &regions[index])
+                .filter(|region| *address < region.1)
+            else {
+                unmapped += 1;
+                continue;
+            };
+            let group = groups
+                .entry((region.3.clone(), region.2.clone()))
+                .or_insert((0, Vec::new(), *address));
+            group.0 += 1;
+            if group.1.last() != Some(&region.0) {
+                group.1.push(region.0);
+            }
+            group.2 = group.2.min(*address);
         }
-        Err(_) => {
+
+        let total = positions.len();
+        let mut summary: Vec<Value> = groups
+            .into_iter()
+            .map(|((mapping, protection), (count, seen, first_address))| {
+                // A module may span several mappings of its file, not only the first one
+                let module_index = modules.partition_point(|module| module.0 <= first_address);
+                let module = modules
+                    .iter()
+                    .find(|module| !mapping.is_empty() && module.2 == mapping)
+                    .or_else(|| {
+                        module_index
+                            .checked_sub(1)
+                            .map(|index| &modules[index])
+                            .filter(|module| first_address < module.1)
+                    })
+                    .map(|module| {
+                        let name = module.2.as_str();
+                        name.rsplit(['/', '\\']).next().unwrap_or(name).to_string()
+                    });
+                json!({
+                    "mapping": if mapping.is_empty() { "[anonymous]".to_string() } else { mapping },
+                    "module": module,
+                    "protection": protection,
+                    "count": count,
+                    "percent": count as f64 * 100.0 / total as f64,
+                    "regions": seen.len(),
+                    "first_address": first_address,
+                })
+            })
+            .collect();
+        summary.sort_by(|a, b| {
+            b["count"].as_u64().cmp(&a["count"].as_u64()).then_with(|| {
+                a["first_address"]
+                    .as_u64()
+                    .cmp(&b["first_address"].as_u64())
+            })
+        });
+
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({
+                "scan_id": summary_request.scan_id,
+                "total": total,
+                "unmapped": unmapped,
+                "groups": summary,
+            })),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+const DEFAULT_REPEAT_INTERVAL_MS: u64 = 2000;
+const DEFAULT_REPEAT_DURATION_MS: u64 = 30_000;
+const MAX_REPEAT_ITERATIONS: usize = 1000;
+
+// Re-runs a filter on an interval, e.g. "unchanged" every 2s for 30s, and returns the
+// surviving results from the last pass along with the count after every pass.
+pub async fn repeat_filter_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    repeat_request: request::RepeatFilterRequest,
+) -> Result<Response<Body>, warp::Rejection> {
+    if !GLOBAL_SCAN_OPTION
+        .read()
+        .unwrap()
+        .contains_key(&repeat_request.filter.scan_id)
+    {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(Body::from("Unknown scan_id"))
+            .unwrap());
+    }
+    let interval = Duration::from_millis(
+        repeat_request
+            .interval_ms
+            .unwrap_or(DEFAULT_REPEAT_INTERVAL_MS),
+    );
+    let deadline = Instant::now()
+        + Duration::from_millis(
+            repeat_request
+                .duration_ms
+                .unwrap_or(DEFAULT_REPEAT_DURATION_MS),
+        );
+    let max_iterations = repeat_request
+        .iterations
+        .unwrap_or(MAX_REPEAT_ITERATIONS)
+        .clamp(1, MAX_REPEAT_ITERATIONS);
+
+    let started = Instant::now();
+    let mut passes = Vec::new();
+    loop {
+        // Only the last pass needs the full result list
+        let last = passes.len() + 1 >= max_iterations || Instant::now() + interval >= deadline;
+        let mut filter_request = repeat_request.filter.clone();
+        filter_request.return_as_json = repeat_request.filter.return_as_json && last;
+        let response = memory_filter_handler(pid_state.clone(), filter_request)
+            .await?
+            .into_response();
+        if !response.status().is_success() {
+            return Ok(response);
+        }
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap_or_default();
+        let mut result: Value = serde_json::from_slice(&body).unwrap_or_else(|_| json!({}));
+        let found = result["found"].as_u64().unwrap_or(0);
+        passes.push(json!({
+            "iteration": passes.len() + 1,
+            "found": found,
+            "elapsed_ms": started.elapsed().as_millis() as u64,
+        }));
+
+        if last || found == 0 {
+            result["iterations"] = json!(passes);
+            return Ok(Response::builder()
+                .header("Content-Type", "application/json")
+                .body(Body::from(result.to_string()))
+                .unwrap());
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+pub async fn ocr_scan_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    ocr_request: request::OcrScanRequest,
+) -> Result<Response<Body>, warp::Rejection> {
+    let bad_request = |message: String| {
+        Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(Body::from(message))
+            .unwrap())
+    };
+    let Some(pid) = *pid_state.lock().unwrap() else {
+        return bad_request("Pid not set".to_string());
+    };
+
+    let (text, value) = match &ocr_request.value {
+        Some(value) => (None, value.trim().to_string()),
+        None => match screen::capture(ocr_request.region.as_ref())
+            .and_then(|image| screen::read_number(&image))
+        {
+            Ok((text, number)) => (Some(text), number),
+            Err(e) => return bad_request(e),
+        },
+    };
+    // Decimal types are encoded from the displayed value by the scan itself
+    let data_type = ocr_request.data_type.as_str();
+    let display_value = data_type.starts_with("decimal").then(|| value.clone());
+    let pattern = if display_value.is_some() {
+        String::new()
+    } else {
+        match util::encode_value(data_type, &json!(value)) {
+            Ok(bytes) => hex::encode(bytes),
+            Err(e) => return bad_request(e),
+        }
+    };
+
+    let is_filter = GLOBAL_SCAN_OPTION
+        .read()
+        .unwrap()
+        .contains_key(&ocr_request.scan_id);
+    let response = if is_filter {
+        let filter_request: request::MemoryFilterRequest = serde_json::from_value(json!({
+            "pattern": pattern,
+            "data_type": data_type,
+            "scan_id": ocr_request.scan_id,
+            "filter_method": "exact",
+            "return_as_json": true,
+            "do_suspend": false,
+            "display_value": display_value,
+        }))
+        .unwrap();
+        memory_filter_handler(pid_state, filter_request)
+            .await?
+            .into_response()
+    } else {
+        let address_ranges = match &ocr_request.address_ranges {
+            Some(address_ranges) => address_ranges.clone(),
+            None => match util::writable_ranges(pid) {
+                Ok(address_ranges) => address_ranges,
+                Err(e) => return bad_request(e),
+            },
+        };
+        let align = ocr_request
+            .align
+            .or_else(|| util::data_type_size(data_type))
+            .unwrap_or(1);
+        let scan_request: request::MemoryScanRequest = serde_json::from_value(json!({
+            "pattern": pattern,
+            "address_ranges": address_ranges,
+            "find_type": "exact",
+            "data_type": data_type,
+            "scan_id": ocr_request.scan_id,
+            "align": align,
+            "return_as_json": true,
+            "do_suspend": false,
+            "display_value": display_value,
+        }))
+        .unwrap();
+        memory_scan_handler(pid_state, scan_request)
+            .await?
+            .into_response()
+    };
+    if !response.status().is_success() {
+        return Ok(response);
+    }
+
+    let body = hyper::body::to_bytes(response.into_body())
+        .await
+        .unwrap_or_default();
+    let mut result: Value = serde_json::from_slice(&body).unwrap_or_else(|_| json!({}));
+    result["ocr"] = json!({
+        "text": text,
+        "value": value,
+        "pass": if is_filter { "filter" } else { "scan" },
+    });
+    Ok(Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Body::from(result.to_string()))
+        .unwrap())
+}
+
+pub async fn list_predicates_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(
+        &json!({ "predicates": wasm_predicates::list() }),
+    ))
+}
+
+pub async fn add_predicate_handler(
+    name: String,
+    query: wasm_predicates::AddPredicateQuery,
+    wasm: hyper::body::Bytes,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    // Compiling a large module takes a while
+    let added = tokio::task::spawn_blocking(move || wasm_predicates::add(&name, query.size, &wasm))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    match added {
+        Ok(summary) => Ok(warp::reply::with_status(
+            warp::reply::json(&summary),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": e })),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+pub async fn remove_predicate_handler(
+    remove_request: wasm_predicates::RemovePredicateRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if wasm_predicates::remove(&remove_request.name) {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "removed": remove_request.name })),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": "Unknown predicate" })),
+            StatusCode::NOT_FOUND,
+        ))
+    }
+}
+
+pub async fn list_plugins_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&json!({ "plugins": plugins::list() })))
+}
+
+pub async fn plugin_route_handler(
+    name: String,
+    route: String,
+    body: hyper::body::Bytes,
+) -> Result<Response<Body>, warp::Rejection> {
+    // Plugins may block, e.g. on their own I/O, so keep them off the async workers
+    let handled = tokio::task::spawn_blocking(move || plugins::handle(&name, &route, &body))
+        .await
+        .ok()
+        .flatten();
+    match handled {
+        Some((status, body)) => Ok(Response::builder()
+            .status(StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))
+            .header("Content-Type", "application/json")
+            .body(Body::from(body))
+            .unwrap()),
+        None => Err(warp::reject::not_found()),
+    }
+}
+
+pub async fn list_recipes_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&json!({ "recipes": recipes::list() })))
+}
+
+pub async fn save_recipe_handler(
+    recipe: recipes::Recipe,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match recipes::upsert(recipe) {
+        Ok(recipe) => Ok(warp::reply::with_status(
+            warp::reply::json(&recipe),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": e })),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+pub async fn remove_recipe_handler(
+    remove_request: recipes::RemoveRecipeRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match recipes::remove(&remove_request.name) {
+        Ok(true) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "removed": remove_request.name })),
+            StatusCode::OK,
+        )),
+        Ok(false) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": "Unknown recipe" })),
+            StatusCode::NOT_FOUND,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": e })),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+// Runs the steps in order through the scan and filter handlers and stops early once nothing
+// is left to filter
+pub async fn run_recipe_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    run_request: recipes::RunRecipeRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bad_request = |message: String| {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": message })),
+            StatusCode::BAD_REQUEST,
+        ))
+    };
+    let Some(pid) = *pid_state.lock().unwrap() else {
+        return bad_request("Pid not set".to_string());
+    };
+    let Some(recipe) = recipes::get(&run_request.name) else {
+        return bad_request(format!("Unknown recipe: {}", run_request.name));
+    };
+    let parameters = match recipes::resolve_parameters(&recipe, &run_request.parameters) {
+        Ok(parameters) => parameters,
+        Err(e) => return bad_request(e),
+    };
+    let scan_id = run_request
+        .scan_id
+        .clone()
+        .unwrap_or_else(|| format!("recipe-{}", recipe.name));
+
+    let Some(last) = recipe.steps.len().checked_sub(1) else {
+        return bad_request(format!("Recipe {} has no steps", recipe.name));
+    };
+    let mut steps = Vec::new();
+    let mut result = json!({});
+    for (index, step) in recipe.steps.iter().enumerate() {
+        let return_as_json = index == last && run_request.return_as_json.unwrap_or(true);
+        let (kind, mut step_request) = match step {
+            recipes::RecipeStep::Wait { ms } => {
+                tokio::time::sleep(Duration::from_millis(*ms)).await;
+                steps.push(json!({ "step": index, "kind": "wait", "ms": ms }));
+                continue;
+            }
+            recipes::RecipeStep::Scan { request } => {
+                ("scan", recipes::expand(request, &parameters))
+            }
+            recipes::RecipeStep::Filter { request } => {
+                ("filter", recipes::expand(request, &parameters))
+            }
+        };
+        recipes::complete_request(&mut step_request, &scan_id, return_as_json);
+
+        let response = if kind == "scan" {
+            if step_request.get("address_ranges").is_none() {
+                match util::writable_ranges(pid) {
+                    Ok(ranges) => step_request["address_ranges"] = json!(ranges),
+                    Err(e) => return bad_request(e),
+                }
+            }
+            if step_request.get("align").is_none() {
+                let data_type = step_request["data_type"].as_str().unwrap_or_default();
+                step_request["align"] = json!(util::data_type_size(data_type).unwrap_or(1));
+            }
+            match serde_json::from_value::<request::MemoryScanRequest>(step_request) {
+                Ok(scan_request) => memory_scan_handler(pid_state.clone(), scan_request)
+                    .await?
+                    .into_response(),
+                Err(e) => return bad_request(format!("Step {}: {}", index, e)),
+            }
+        } else {
+            match serde_json::from_value::<request::MemoryFilterRequest>(step_request) {
+                Ok(filter_request) => memory_filter_handler(pid_state.clone(), filter_request)
+                    .await?
+                    .into_response(),
+                Err(e) => return bad_request(format!("Step {}: {}", index, e)),
+            }
+        };
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap_or_default();
+        if !status.is_success() {
             return Ok(warp::reply::with_status(
                 warp::reply::json(&json!({
-                    "error": "Process panicked during directory exploration",
-                    "path": decoded_path,
-                    "max_depth": req.max_depth
+                    "error": format!("Step {} failed: {}", index, String::from_utf8_lossy(&body)),
+                    "steps": steps,
                 })),
-                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-            ))
+                status,
+            ));
+        }
+        result = serde_json::from_slice(&body).unwrap_or_else(|_| json!({}));
+        let found = result["found"].as_u64().unwrap_or(0);
+        steps.push(json!({ "step": index, "kind": kind, "found": found }));
+        if found == 0 {
+            break;
+        }
+    }
+
+    result["recipe"] = json!(recipe.name);
+    result["scan_id"] = json!(scan_id);
+    result["steps"] = json!(steps);
+    Ok(warp::reply::with_status(
+        warp::reply::json(&result),
+        StatusCode::OK,
+    ))
+}
+
+pub async fn screen_diff_start_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    start_request: screen_diff::ScreenDiffStartRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let Some(pid) = *pid_state.lock().unwrap() else {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ));
+    };
+    // The baseline sleeps, so keep it off the async workers
+    let result = tokio::task::spawn_blocking(move || screen_diff::start(pid, start_request))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    match result {
+        Ok(result) => Ok(warp::reply::with_status(
+            warp::reply::json(&result),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": e })),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+pub async fn screen_diff_finish_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    finish_request: screen_diff::ScreenDiffFinishRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let Some(pid) = *pid_state.lock().unwrap() else {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ));
+    };
+    let result = tokio::task::spawn_blocking(move || screen_diff::finish(pid, finish_request))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    match result {
+        Ok(result) => Ok(warp::reply::with_status(
+            warp::reply::json(&result),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": e })),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+pub async fn enumerate_regions_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let mut buffer = vec![0u8; 1024 * 1024];
+
+        unsafe {
+            native_bridge::enumerate_regions_to_buffer(pid, buffer.as_mut_ptr(), buffer.len());
+        }
+        let buffer_cstring = unsafe { CString::from_vec_unchecked(buffer) };
+        let buffer_string = buffer_cstring.into_string().unwrap();
+        let buffer_reader = BufReader::new(buffer_string.as_bytes());
+
+        let mut regions = Vec::new();
+
+        for line in buffer_reader.lines() {
+            if let Ok(line) = line {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+
+                if parts.len() >= 5 {
+                    let addresses: Vec<&str> = parts[0].split('-').collect();
+                    if addresses.len() == 2 {
+                        let region = Region {
+                            start_address: addresses[0].to_string(),
+                            end_address: addresses[1].to_string(),
+                            protection: parts[1].to_string(),
+                            file_path: if parts.len() > 5 {
+                                Some(parts[5..].join(" "))
+                            } else {
+                                None
+                            },
+                        };
+                        regions.push(region);
+                    }
+                }
+            }
+        }
+
+        let result = json!({ "regions": regions });
+        let result_string = result.to_string();
+        let response = Response::builder()
+            .header("Content-Type", "application/json")
+            .body(hyper::Body::from(result_string))
+            .unwrap();
+        Ok(response)
+    } else {
+        let response = Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(hyper::Body::from("Pid not set"))
+            .unwrap();
+        Ok(response)
+    }
+}
+
+pub async fn enumerate_process_handler() -> Result<impl Reply, Rejection> {
+    if util::is_embedded() {
+        let name = env::current_exe()
+            .ok()
+            .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "self".to_string());
+        return Ok(warp::reply::json(
+            &json!([{ "pid": process::id(), "processname": name }]),
+        ));
+    }
+
+    let mut count: usize = 0;
+    let process_info_ptr = unsafe { native_bridge::enumprocess_native(&mut count) };
+    let process_info_slice = unsafe { std::slice::from_raw_parts(process_info_ptr, count) };
+
+    let mut json_array = Vec::new();
+    for i in 0..count {
+        let process_name = unsafe {
+            CStr::from_ptr(process_info_slice[i].processname)
+                .to_string_lossy()
+                .into_owned()
+        };
+        json_array.push(json!({
+            "pid": process_info_slice[i].pid,
+            "processname": process_name
+        }));
+        unsafe { libc::free(process_info_slice[i].processname as *mut libc::c_void) };
+    }
+
+    // for cdylib
+    if count == 0 {
+        let pid = unsafe { native_bridge::get_pid_native() };
+        json_array.push(json!({
+            "pid": pid,
+            "processname": "self".to_string()
+        }));
+    } else {
+        unsafe {
+            libc::free(process_info_ptr as *mut libc::c_void);
+        }
+    }
+
+    let json_response = warp::reply::json(&json_array);
+    Ok(json_response)
+}
+
+pub async fn enummodule_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+    if let Some(pid) = *pid {
+        let modules = native_bridge::enum_modules(pid).unwrap();
+        let result = json!({ "modules": modules });
+        let result_string = result.to_string();
+        let response = Response::builder()
+            .header("Content-Type", "application/json")
+            .body(hyper::Body::from(result_string))
+            .unwrap();
+        Ok(response)
+    } else {
+        let response = Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(hyper::Body::from("Pid not set"))
+            .unwrap();
+        Ok(response)
+    }
+}
+
+pub async fn enumerate_threads_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+    if let Some(pid) = *pid {
+        match native_bridge::enum_threads(pid) {
+            Ok(threads) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "threads": threads })),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": "Pid not set" })),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn enumerate_tls_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    tls_request: request::TlsRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+    if let Some(pid) = *pid {
+        let block_size = tls_request.block_size.unwrap_or(tls::DEFAULT_BLOCK_SIZE);
+        match tls::enumerate(pid, block_size) {
+            Ok(blocks) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "threads": tls::report(&blocks) })),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": "Pid not set" })),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn explore_directory_handler(
+    req: request::ExploreDirectoryRequest,
+) -> Result<impl Reply, Rejection> {
+    let decoded_path = percent_decode_str(&req.path)
+        .decode_utf8_lossy()
+        .into_owned();
+
+    let c_path = match CString::new(decoded_path.clone()) {
+        Ok(path) => path,
+        Err(_) => {
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&json!({
+                    "error": "Invalid path: contains null byte",
+                    "path": decoded_path,
+                    "max_depth": req.max_depth
+                })),
+                warp::http::StatusCode::BAD_REQUEST,
+            ))
+        }
+    };
+
+    let result = panic::catch_unwind(|| unsafe {
+        let result_ptr = native_bridge::explore_directory(c_path.as_ptr(), req.max_depth as c_int);
+        if result_ptr.is_null() {
+            return Err("Null pointer returned from explore_directory");
+        }
+        let result_str = CStr::from_ptr(result_ptr).to_string_lossy().into_owned();
+        libc::free(result_ptr as *mut libc::c_void);
+        Ok(result_str)
+    });
+
+    let result = match result {
+        Ok(Ok(result)) => result,
+        Ok(Err(err)) => {
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&json!({
+                    "error": err,
+                    "path": decoded_path,
+                    "max_depth": req.max_depth
+                })),
+                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            ))
+        }
+        Err(_) => {
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&json!({
+                    "error": "Process panicked during directory exploration",
+                    "path": decoded_path,
+                    "max_depth": req.max_depth
+                })),
+                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            ))
+        }
+    };
+
+    if result.starts_with("Error:") {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&json!({
+                "error": result,
+                "path": decoded_path,
+                "max_depth": req.max_depth
+            })),
+            warp::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    match panic::catch_unwind(|| util::parse_directory_structure(&result)) {
+        Ok(items) => Ok(warp::reply::with_status(
+            warp::reply::json(&items),
+            warp::http::StatusCode::OK,
+        )),
+        Err(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({
+                "error": "Process panicked during parsing of directory structure",
+                "path": decoded_path,
+                "max_depth": req.max_depth
+            })),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+pub async fn read_file_handler(req: request::ReadFileRequest) -> Result<Response<Body>, Rejection> {
+    let decoded_path = percent_decode_str(&req.path)
+        .decode_utf8_lossy()
+        .into_owned();
+
+    let c_path = CString::new(decoded_path.clone()).unwrap();
+    let mut size: usize = 0;
+    let mut error_ptr: *mut c_char = std::ptr::null_mut();
+
+    let data_ptr = unsafe {
+        native_bridge::read_file(
+            c_path.as_ptr(),
+            &mut size as *mut usize,
+            &mut error_ptr as *mut *mut c_char,
+        )
+    };
+
+    if !error_ptr.is_null() {
+        let error_message = unsafe { CStr::from_ptr(error_ptr).to_string_lossy().into_owned() };
+        unsafe { libc::free(error_ptr as *mut c_void) };
+        return Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from(error_message))
+            .unwrap());
+    }
+
+    if data_ptr.is_null() || size == 0 {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from("File not found or empty"))
+            .unwrap());
+    }
+
+    let data = unsafe { slice::from_raw_parts(data_ptr as *const u8, size) }.to_vec();
+    unsafe { libc::free(data_ptr as *mut c_void) };
+
+    Ok(Response::builder()
+        .header("Content-Type", "application/octet-stream")
+        .body(Body::from(data))
+        .unwrap())
+}
+
+pub async fn get_app_info_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+    if let Some(pid) = *pid {
+        let result = native_bridge::get_application_info(pid);
+        let message = match result {
+            Ok(message) => message,
+            Err(e) => {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({
+                        "message": e.to_string()
+                    })),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                ));
+            }
+        };
+        let parsed_result: Value = match serde_json::from_str(&message) {
+            Ok(json) => json,
+            Err(e) => {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({
+                        "message": format!("Failed to parse application info: {}", e)
+                    })),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                ));
+            }
+        };
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "info": parsed_result })),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn set_watchpoint_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    watchpoint: request::SetWatchPointRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let _type = match watchpoint._type.as_str() {
+            "r" => 1,
+            "w" => 2,
+            "a" => 3,
+            _ => {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&request::SetWatchPointResponse {
+                        success: false,
+                        message: format!("Unknown type"),
+                    }),
+                    StatusCode::BAD_REQUEST,
+                ))
+            }
+        };
+        let page_mode = match watchpoint.mode.as_deref() {
+            Some("hardware") => false,
+            Some("page") => true,
+            None => !matches!(watchpoint.size, 1 | 2 | 4 | 8),
+            Some(_) => {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&request::SetWatchPointResponse {
+                        success: false,
+                        message: "Unknown mode".to_string(),
+                    }),
+                    StatusCode::BAD_REQUEST,
+                ))
+            }
+        };
+        let threads = watchpoint.threads.unwrap_or_default();
+        let thread_filter = watchpoint.thread_filter.filter(|f| !f.is_empty());
+        if page_mode && (!threads.is_empty() || thread_filter.is_some()) {
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&request::SetWatchPointResponse {
+                    success: false,
+                    message: "Page watchpoints cannot be scoped to threads".to_string(),
+                }),
+                StatusCode::BAD_REQUEST,
+            ));
+        }
+        if page_mode && watchpoint.on_hit.is_some() {
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&request::SetWatchPointResponse {
+                    success: false,
+                    message: "Hit actions need a hardware watchpoint".to_string(),
+                }),
+                StatusCode::BAD_REQUEST,
+            ));
+        }
+        // Stored before arming so the first hit is already filtered
+        if let Err(e) = conditions::set(
+            pid,
+            watchpoint.address,
+            watchpoint.size,
+            watchpoint.condition.as_deref(),
+        ) {
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&request::SetWatchPointResponse {
+                    success: false,
+                    message: e,
+                }),
+                StatusCode::BAD_REQUEST,
+            ));
+        }
+        let result = if page_mode {
+            native_bridge::set_page_watchpoint(pid, watchpoint.address, watchpoint.size, _type)
+        } else {
+            native_bridge::set_watchpoint(
+                pid,
+                watchpoint.address,
+                watchpoint.size,
+                _type,
+                &threads,
+                thread_filter.as_deref(),
+            )
+        };
+        // The action attaches to the armed slot, so a rejected action disarms it again
+        if let (Ok(_), Some(action)) = (&result, &watchpoint.on_hit) {
+            if let Err(e) = native_bridge::set_hit_action(
+                pid,
+                watchpoint.address,
+                false,
+                action.register.as_deref(),
+                action.value,
+                action.skip,
+            ) {
+                let _ = native_bridge::remove_watchpoint(watchpoint.address);
+                conditions::remove(watchpoint.address);
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&request::SetWatchPointResponse {
+                        success: false,
+                        message: e,
+                    }),
+                    StatusCode::BAD_REQUEST,
+                ));
+            }
+        }
+
+        let ret = match result {
+            Ok(_) => Ok(warp::reply::with_status(
+                warp::reply::json(&request::SetWatchPointResponse {
+                    success: true,
+                    message: "Watchpoint set successfully".to_string(),
+                }),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&request::SetWatchPointResponse {
+                    success: false,
+                    message: format!("Failed to set watchpoint. Error: {}", e),
+                }),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        };
+        return ret;
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&request::SetWatchPointResponse {
+                success: false,
+                message: format!("Pid not set"),
+            }),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn remove_watchpoint_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    watchpoint: request::RemoveWatchPointRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(_pid) = *pid {
+        let result = native_bridge::remove_watchpoint(watchpoint.address);
+        conditions::remove(watchpoint.address);
+
+        let ret = match result {
+            Ok(_) => Ok(warp::reply::with_status(
+                warp::reply::json(&request::RemoveWatchPointResponse {
+                    success: true,
+                    message: "Remove Watchpoint set successfully".to_string(),
+                }),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&request::RemoveWatchPointResponse {
+                    success: false,
+                    message: format!("Failed to remove watchpoint. Error: {}", e),
+                }),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        };
+        return ret;
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&request::RemoveWatchPointResponse {
+                success: false,
+                message: format!("Pid not set"),
+            }),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn set_breakpoint_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    breakpoint: request::SetBreakPointRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+    if let Some(pid) = *pid {
+        let result = native_bridge::set_breakpoint(pid, breakpoint.address, breakpoint.hit_count);
+        if let (Ok(_), Some(action)) = (&result, &breakpoint.on_hit) {
+            if let Err(e) = native_bridge::set_hit_action(
+                pid,
+                breakpoint.address,
+                true,
+                action.register.as_deref(),
+                action.value,
+                action.skip,
+            ) {
+                let _ = native_bridge::remove_breakpoint(breakpoint.address);
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&request::SetBreakPointResponse {
+                        success: false,
+                        message: e,
+                    }),
+                    StatusCode::BAD_REQUEST,
+                ));
+            }
+        }
+        let ret = match result {
+            Ok(_) => Ok(warp::reply::with_status(
+                warp::reply::json(&request::SetBreakPointResponse {
+                    success: true,
+                    message: "Breakpoint set successfully".to_string(),
+                }),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&request::SetBreakPointResponse {
+                    success: false,
+                    message: format!("Failed to set breakpoint. Error: {}", e),
+                }),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        };
+        return ret;
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&request::SetBreakPointResponse {
+                success: false,
+                message: format!("Pid not set"),
+            }),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn trace_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    trace_request: tracer::TraceRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    // Copied out so the lock is not held while the trace runs
+    let pid = *pid_state.lock().unwrap();
+
+    if let Some(pid) = pid {
+        match tracer::run(pid, trace_request).await {
+            Ok(trace) => Ok(warp::reply::with_status(
+                warp::reply::json(&trace),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::BAD_REQUEST,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn callers_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    caller_request: callers::CallerRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    // Copied out so the lock is not held while the watch runs
+    let pid = *pid_state.lock().unwrap();
+
+    if let Some(pid) = pid {
+        match callers::run(pid, caller_request).await {
+            Ok(report) => Ok(warp::reply::with_status(
+                warp::reply::json(&report),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::BAD_REQUEST,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn remove_breakpoint_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    breakpoint: request::RemoveBreakPointRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+    if let Some(_pid) = *pid {
+        let result = native_bridge::remove_breakpoint(breakpoint.address);
+        let ret = match result {
+            Ok(_) => Ok(warp::reply::with_status(
+                warp::reply::json(&request::RemoveBreakPointResponse {
+                    success: true,
+                    message: "Breakpoint removed successfully".to_string(),
+                }),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&request::RemoveBreakPointResponse {
+                    success: false,
+                    message: format!("Failed to remove breakpoint. Error: {}", e),
+                }),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        };
+        return ret;
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&request::RemoveBreakPointResponse {
+                success: false,
+                message: format!("Pid not set"),
+            }),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn change_process_state_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    state_request: request::ChangeProcessStateRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if util::is_embedded() {
+        // Suspending the own task would stop the server along with it
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&request::ChangeProcessStateResponse {
+                success: false,
+                message: "The embedded server cannot suspend its own process".to_string(),
+            }),
+            StatusCode::FORBIDDEN,
+        ));
+    }
+
+    if let Some(_pid) = *pid {
+        let result = if state_request.do_play {
+            unsafe { native_bridge::resume_process(_pid) }
+        } else {
+            unsafe { native_bridge::suspend_process(_pid) }
+        };
+
+        let ret = match result {
+            true => Ok(warp::reply::with_status(
+                warp::reply::json(&request::ChangeProcessStateResponse {
+                    success: true,
+                    message: format!(
+                        "Process {} successfully",
+                        if state_request.do_play {
+                            "resumed"
+                        } else {
+                            "suspend"
+                        }
+                    ),
+                }),
+                StatusCode::OK,
+            )),
+            false => Ok(warp::reply::with_status(
+                warp::reply::json(&request::ChangeProcessStateResponse {
+                    success: false,
+                    message: format!("Failed to change process state. Error"),
+                }),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        };
+        return ret;
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&request::ChangeProcessStateResponse {
+                success: false,
+                message: "Pid not set".to_string(),
+            }),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn pointermap_generate_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    request: request::PointerMapGenerateRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let result = ptrscan::generate_pointermap(pid);
+
+        match result {
+            Ok(binary_data) => {
+                let response = Response::builder()
+                    .header("Content-Type", "application/octet-stream")
+                    .body(hyper::Body::from(binary_data))
+                    .unwrap();
+                Ok(response)
+            }
+            Err(_) => {
+                let empty_buffer = Vec::new();
+                let response = Response::builder()
+                    .header("Content-Type", "application/octet-stream")
+                    .body(hyper::Body::from(empty_buffer))
+                    .unwrap();
+                Ok(response)
+            }
+        }
+    } else {
+        let response = Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(hyper::Body::from("Pid not set"))
+            .unwrap();
+        Ok(response)
+    }
+}
+
+// A hexdump is a view of a few screens of memory; more than this is a client error
+const MAX_HEXDUMP_SIZE: usize = 4 * 1024 * 1024;
+
+pub async fn hexdump_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    hexdump_request: request::HexDumpRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        if hexdump_request.size > MAX_HEXDUMP_SIZE {
+            let response = Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(hyper::Body::from(format!(
+                    "size must be at most {} bytes",
+                    MAX_HEXDUMP_SIZE
+                )))
+                .unwrap();
+            return Ok(response);
+        }
+        let mut buffer: Vec<u8> = vec![0; hexdump_request.size];
+        let nread =
+            match memory_backend::for_pid(pid).read(pid, hexdump_request.address, &mut buffer) {
+                Ok(nread) if nread > 0 => nread,
+                _ => {
+                    let response = Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(hyper::Body::from("Failed to read memory"))
+                        .unwrap();
+                    return Ok(response);
+                }
+            };
+        buffer.truncate(nread);
+
+        let start = hexdump_request.address;
+        let end = start + buffer.len();
+        let width = hexdump_request.width.unwrap_or(16).max(1);
+        let lines = util::format_hexdump(start, &buffer, width);
+        let mut annotations: Vec<Value> = Vec::new();
+
+        // Scan results overlapping the range
+        {
+            let global_positions = GLOBAL_POSITIONS.read().unwrap();
+            for (scan_id, positions) in global_positions.iter() {
+                if let Some(filter_id) = &hexdump_request.scan_id {
+                    if filter_id != scan_id {
+                        continue;
+                    }
+                }
+                for (address, value) in positions {
+                    let size = std::cmp::max(value.len() / 2, 1);
+                    if *address < end && address + size > start {
+                        annotations.push(json!({
+                            "kind": "scan_result",
+                            "address": address,
+                            "size": size,
+                            "label": scan_id
+                        }));
+                    }
+                }
+            }
+        }
+
+        // User annotations overlapping the range
+        for note in annotations::overlapping(&annotations::list(pid), start, end) {
+            annotations.push(json!({
+                "kind": "annotation",
+                "address": note["address"],
+                "size": note["size"],
+                "label": note["label"],
+                "color": note["color"],
+                "note": note["note"]
+            }));
+        }
+
+        // Module boundaries inside the range
+        if let Ok(modules) = native_bridge::enum_modules(pid) {
+            for module in &modules {
+                let base = module["base"].as_u64().unwrap_or(0) as usize;
+                let size = module["size"].as_i64().unwrap_or(0) as usize;
+                let name = module["modulename"].as_str().unwrap_or("");
+                if base >= start && base < end {
+                    annotations.push(json!({
+                        "kind": "module_start",
+                        "address": base,
+                        "size": 0,
+                        "label": name
+                    }));
+                }
+                if base + size >= start && base + size < end {
+                    annotations.push(json!({
+                        "kind": "module_end",
+                        "address": base + size,
+                        "size": 0,
+                        "label": name
+                    }));
+                }
+            }
+        }
+
+        // Aligned values that point into a readable region
+        if let Ok(regions) = native_bridge::enum_regions(pid) {
+            let ranges = util::parse_regions(&regions);
+            let pointer_size = util::pointer_size();
+            let aligned_start = (start + pointer_size - 1) & !(pointer_size - 1);
+            let mut offset = aligned_start - start;
+            while offset + pointer_size <= buffer.len() {
+                let value = util::pointer_from_bytes(&buffer[offset..]).unwrap_or(0);
+                if value != 0 {
+                    if let Some(idx) = util::find_region(&ranges, value) {
+                        if ranges[idx].2.contains('r') {
+                            annotations.push(json!({
+                                "kind": "pointer",
+                                "address": start + offset,
+                                "size": pointer_size,
+                                "label": format!("0x{:x}", value)
+                            }));
+                        }
+                    }
+                }
+                offset += pointer_size;
+            }
+        }
+
+        let result = json!({
+            "lines": lines,
+            "annotations": annotations
+        });
+        let response = Response::builder()
+            .header("Content-Type", "application/json")
+            .body(hyper::Body::from(result.to_string()))
+            .unwrap();
+        Ok(response)
+    } else {
+        let response = Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(hyper::Body::from("Pid not set"))
+            .unwrap();
+        Ok(response)
+    }
+}
+
+pub async fn register_struct_handler(
+    layout: structs::StructLayout,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match structs::register_layout(layout) {
+        Ok(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&request::RegisterStructResponse {
+                success: true,
+                message: "Struct registered successfully".to_string(),
+            }),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&request::RegisterStructResponse {
+                success: false,
+                message: format!("Failed to register struct. Error: {}", e),
+            }),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+pub async fn list_structs_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(
+        &json!({ "structs": structs::list_layouts() }),
+    ))
+}
+
+pub async fn remove_struct_handler(
+    remove_request: request::RemoveStructRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if structs::remove_layout(&remove_request.name) {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&request::RegisterStructResponse {
+                success: true,
+                message: "Struct removed successfully".to_string(),
+            }),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&request::RegisterStructResponse {
+                success: false,
+                message: format!("Unknown struct '{}'", remove_request.name),
+            }),
+            StatusCode::NOT_FOUND,
+        ))
+    }
+}
+
+pub async fn read_struct_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    read_request: request::ReadStructRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        match structs::read_struct(pid, read_request.address, &read_request.name) {
+            Ok(result) => Ok(warp::reply::with_status(
+                warp::reply::json(&result),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::BAD_REQUEST,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+// Bounds on the client-supplied count, so a large one cannot overflow or exhaust memory
+const MAX_ARRAY_COUNT: usize = 1024 * 1024;
+const MAX_ARRAY_SIZE: usize = 16 * 1024 * 1024;
+
+pub async fn read_array_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    read_request: request::ReadArrayRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let element_size = match util::data_type_size(&read_request.data_type) {
+            Some(size) => size,
+            None => {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({
+                        "error": format!("Unsupported data type: {}", read_request.data_type)
+                    })),
+                    StatusCode::BAD_REQUEST,
+                ))
+            }
+        };
+        let size = match element_size.checked_mul(read_request.count) {
+            Some(size) if read_request.count <= MAX_ARRAY_COUNT && size <= MAX_ARRAY_SIZE => size,
+            _ => {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({
+                        "error": format!(
+                            "count must be at most {} and cover at most {} bytes",
+                            MAX_ARRAY_COUNT, MAX_ARRAY_SIZE
+                        )
+                    })),
+                    StatusCode::BAD_REQUEST,
+                ))
+            }
+        };
+        let mut buffer: Vec<u8> = vec![0; size];
+        match memory_backend::for_pid(pid).read(pid, read_request.address, &mut buffer) {
+            Ok(nread) if nread > 0 => buffer.truncate(nread),
+            _ => {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": "Failed to read memory" })),
+                    StatusCode::BAD_REQUEST,
+                ))
+            }
+        }
+        let values: Vec<Value> = buffer
+            .chunks_exact(element_size)
+            .map(|chunk| util::decode_value(&read_request.data_type, chunk).unwrap_or(Value::Null))
+            .collect();
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({
+                "address": read_request.address,
+                "data_type": read_request.data_type,
+                "values": values
+            })),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn write_array_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    write_request: request::WriteArrayRequest,
+    session: String,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        if util::data_type_size(&write_request.data_type).is_none() {
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&json!({
+                    "error": format!("Unsupported data type: {}", write_request.data_type)
+                })),
+                StatusCode::BAD_REQUEST,
+            ));
+        }
+        let mut buffer: Vec<u8> = Vec::new();
+        for (index, value) in write_request.values.iter().enumerate() {
+            match util::encode_value(&write_request.data_type, value) {
+                Ok(bytes) => buffer.extend_from_slice(&bytes),
+                Err(e) => {
+                    return Ok(warp::reply::with_status(
+                        warp::reply::json(&json!({
+                            "error": format!("Invalid value at index {}: {}", index, e)
+                        })),
+                        StatusCode::BAD_REQUEST,
+                    ))
+                }
+            }
+        }
+        let original = write_history::read_original(pid, write_request.address, buffer.len());
+        match memory_backend::for_pid(pid).write(pid, write_request.address, &buffer) {
+            Ok(_) => {
+                write_history::record(
+                    pid,
+                    write_request.address,
+                    original,
+                    &buffer,
+                    "writearray",
+                    &session,
+                );
+                Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({
+                        "address": write_request.address,
+                        "written": buffer.len()
+                    })),
+                    StatusCode::OK,
+                ))
+            }
+            Err(_) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": "WriteProcessMemory error" })),
+                StatusCode::BAD_REQUEST,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn format_value_handler(
+    format_request: request::FormatValueRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match value_format::format_value(&format_request) {
+        Ok(result) => Ok(warp::reply::with_status(
+            warp::reply::json(&result),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": e })),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+pub async fn guess_type_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    guess_request: request::GuessTypeRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        // Read a small window so text guesses can look past the first word
+        const WINDOW_SIZE: usize = 32;
+        let mut buffer: Vec<u8> = vec![0; WINDOW_SIZE];
+        match memory_backend::for_pid(pid).read(pid, guess_request.address, &mut buffer) {
+            Ok(nread) if nread > 0 => buffer.truncate(nread),
+            _ => {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": "Failed to read memory" })),
+                    StatusCode::BAD_REQUEST,
+                ))
+            }
+        }
+        let ranges = native_bridge::enum_regions(pid)
+            .map(|regions| util::parse_regions(&regions))
+            .unwrap_or_default();
+        let guesses = util::guess_value_types(&buffer, &ranges);
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({
+                "address": guess_request.address,
+                "bytes": hex::encode(&buffer),
+                "guesses": guesses
+            })),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn pointermap_save_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    save_request: request::PointerMapSaveRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        match ptrscan::save_pointermap(pid, &save_request.name) {
+            Ok(size) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "name": save_request.name, "size": size })),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn pointermap_list_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "pointermaps": ptrscan::list_pointermaps(pid) })),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn pointermap_compare_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    compare_request: request::PointerMapCompareRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let maps = ptrscan::load_pointermap(pid, &compare_request.map_a).and_then(|map_a| {
+            ptrscan::load_pointermap(pid, &compare_request.map_b).map(|map_b| (map_a, map_b))
+        });
+        let (map_a, map_b) = match maps {
+            Ok(maps) => maps,
+            Err(e) => {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": e })),
+                    StatusCode::BAD_REQUEST,
+                ))
+            }
+        };
+        let paths = ptrscan::compare_pointermaps(
+            &map_a,
+            compare_request.target_a,
+            &map_b,
+            compare_request.target_b,
+            compare_request.max_depth.unwrap_or(4),
+            compare_request.max_offset.unwrap_or(0x1000),
+            compare_request.max_results.unwrap_or(1000),
+        );
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "count": paths.len(), "paths": paths })),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn pointer_path_validate_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    validate_request: request::PointerPathValidateRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let base = match (&validate_request.module, validate_request.base_address) {
+            (Some(module), None) => native_bridge::enum_modules(pid).and_then(|modules| {
+                modules
+                    .iter()
+                    .find(|entry| {
+                        let name = entry["modulename"].as_str().unwrap_or("");
+                        name == module || name.rsplit(['/', '\\']).next() == Some(module)
+                    })
+                    .and_then(|entry| entry["base"].as_u64())
+                    .map(|base| base + validate_request.module_offset.unwrap_or(0))
+                    .ok_or_else(|| format!("Module {} not found", module))
+            }),
+            (None, Some(base_address)) => Ok(base_address),
+            _ => Err("Exactly one of module or base_address is required".to_string()),
+        };
+        let base = match base {
+            Ok(base) => base,
+            Err(e) => {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": e })),
+                    StatusCode::BAD_REQUEST,
+                ))
+            }
+        };
+
+        let live = ptrscan::validate_pointer_path(
+            base,
+            &validate_request.offsets,
+            validate_request.expected,
+            |address| {
+                let mut buffer = vec![0u8; util::pointer_size()];
+                match memory_backend::for_pid(pid).read(pid, address as usize, &mut buffer) {
+                    Ok(nread) if nread == buffer.len() => {
+                        Ok(util::pointer_from_bytes(&buffer).unwrap_or(0) as u64)
+                    }
+                    _ => Err(format!("Failed to read 0x{:x}", address)),
+                }
+            },
+        );
+        let mut result = json!({ "base": base, "live": live });
+
+        if let Some(name) = &validate_request.snapshot {
+            let chunks = match snapshot::open(pid, name) {
+                Ok(chunks) => chunks,
+                Err(e) => {
+                    return Ok(warp::reply::with_status(
+                        warp::reply::json(&json!({ "error": e })),
+                        StatusCode::BAD_REQUEST,
+                    ))
+                }
+            };
+            result["snapshot"] = ptrscan::validate_pointer_path(
+                base,
+                &validate_request.offsets,
+                validate_request.expected,
+                |address| {
+                    let mut buffer = vec![0u8; util::pointer_size()];
+                    match snapshot::read(&chunks, address as usize, &mut buffer)? {
+                        nread if nread == buffer.len() => {
+                            Ok(util::pointer_from_bytes(&buffer).unwrap_or(0) as u64)
+                        }
+                        _ => Err(format!("0x{:x} is truncated in the snapshot", address)),
+                    }
+                },
+            );
+        }
+
+        Ok(warp::reply::with_status(
+            warp::reply::json(&result),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn table_list_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(
+        &json!({ "entries": table::list_entries() }),
+    ))
+}
+
+pub async fn table_add_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    entry: table::TableEntry,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+    let entry = table::add_entry(*pid, entry);
+    Ok(warp::reply::with_status(
+        warp::reply::json(&entry),
+        StatusCode::OK,
+    ))
+}
+
+pub async fn table_remove_handler(
+    remove_request: request::RemoveTableEntryRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if table::remove_entry(remove_request.id) {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "success": true })),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({
+                "success": false,
+                "message": format!("Unknown entry id {}", remove_request.id)
+            })),
+            StatusCode::NOT_FOUND,
+        ))
+    }
+}
+
+pub async fn table_set_preset_handler(
+    preset_request: request::SetPresetRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let preset = table::Preset {
+        name: preset_request.name,
+        buffer: preset_request.buffer,
+    };
+    match table::set_preset(preset_request.id, preset) {
+        Ok(entry) => Ok(warp::reply::with_status(
+            warp::reply::json(&entry),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "success": false, "message": e })),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+pub async fn table_remove_preset_handler(
+    preset_request: request::PresetRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match table::remove_preset(preset_request.id, &preset_request.name) {
+        Ok(entry) => Ok(warp::reply::with_status(
+            warp::reply::json(&entry),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "success": false, "message": e })),
+            StatusCode::NOT_FOUND,
+        )),
+    }
+}
+
+pub async fn table_apply_preset_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    preset_request: request::PresetRequest,
+    session: String,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let (address, buffer) =
+            match table::preset_write(pid, preset_request.id, &preset_request.name) {
+                Ok(target) => target,
+                Err(e) => {
+                    return Ok(warp::reply::with_status(
+                        warp::reply::json(&json!({ "success": false, "message": e })),
+                        StatusCode::NOT_FOUND,
+                    ))
+                }
+            };
+        let original = write_history::read_original(pid, address, buffer.len());
+        match memory_backend::for_pid(pid).write(pid, address, &buffer) {
+            Ok(_) => {
+                write_history::record(pid, address, original, &buffer, "preset", &session);
+                Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({
+                        "success": true,
+                        "address": address,
+                        "preset": preset_request.name,
+                        "written": buffer.len()
+                    })),
+                    StatusCode::OK,
+                ))
+            }
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({
+                    "success": false,
+                    "message": format!("WriteProcessMemory error: {}", e)
+                })),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn table_set_value_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    set_request: request::SetTableValueRequest,
+    session: String,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let target = table::find_entry(set_request.id, set_request.description.as_deref())
+            .and_then(|entry| {
+                let buffer = util::parse_typed_value(&entry.data_type, &set_request.value)?;
+                let address = table::resolve_address(pid, &entry)?;
+                Ok((entry, address, buffer))
+            });
+        let (entry, address, buffer) = match target {
+            Ok(target) => target,
+            Err(e) => {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({ "success": false, "message": e })),
+                    StatusCode::BAD_REQUEST,
+                ))
+            }
+        };
+        let original = write_history::read_original(pid, address, buffer.len());
+        match memory_backend::for_pid(pid).write(pid, address, &buffer) {
+            Ok(_) => {
+                write_history::record(pid, address, original, &buffer, "table_value", &session);
+                Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({
+                        "success": true,
+                        "id": entry.id,
+                        "description": entry.description,
+                        "address": address,
+                        "data_type": entry.data_type,
+                        "bytes": hex::encode(&buffer),
+                    })),
+                    StatusCode::OK,
+                ))
+            }
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({
+                    "success": false,
+                    "message": format!("WriteProcessMemory error: {}", e)
+                })),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn table_import_gameguardian_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    import_request: request::GameGuardianImportRequest,
+    session: String,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = *pid_state.lock().unwrap();
+    let apply_freezes = import_request.apply_freezes.unwrap_or(false);
+    if apply_freezes && pid.is_none() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let (items, skipped) = gameguardian::parse_list(&import_request.content);
+    let imported: Vec<Value> = items
+        .into_iter()
+        .map(|item| {
+            let entry = table::add_entry(pid, item.entry);
+            let mut result = json!({ "entry": entry, "frozen": item.frozen });
+            if let (true, true, Some(pid)) = (apply_freezes, item.frozen, pid) {
+                let freeze = scheduler::ScheduledWrite {
+                    address: entry.address,
+                    buffer: entry.presets[0].buffer.clone(),
+                    sequence: None,
+                    interval_ms: Some(batch::DEFAULT_FREEZE_INTERVAL_MS),
+                    duration_ms: None,
+                    restore_after_ms: None,
+                };
+                match scheduler::schedule(pid, freeze, &session) {
+                    Ok(id) => result["job_id"] = json!(id),
+                    Err(e) => result["freeze_error"] = json!(e),
+                }
+            }
+            result
+        })
+        .collect();
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&json!({ "imported": imported, "skipped": skipped })),
+        StatusCode::OK,
+    ))
+}
+
+pub async fn table_rebase_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let report = table::rebase_entries(pid);
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "entries": report })),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn table_relocate_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    relocate_request: request::TableRelocateRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let report = table::relocate_entries(pid, relocate_request.ids.as_deref());
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "entries": report })),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn table_rebase_report_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(
+        &json!({ "entries": table::last_rebase_report() }),
+    ))
+}
+
+pub async fn schedule_write_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    schedule_request: scheduler::ScheduledWrite,
+    session: String,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        match scheduler::schedule(pid, schedule_request, &session) {
+            Ok(id) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "id": id })),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::BAD_REQUEST,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn list_scheduled_writes_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(
+        &json!({ "jobs": scheduler::list_jobs() }),
+    ))
+}
+
+pub async fn cancel_scheduled_write_handler(
+    cancel_request: request::CancelScheduledWriteRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if scheduler::cancel(cancel_request.id) {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "success": true })),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({
+                "success": false,
+                "message": format!("Unknown job id {}", cancel_request.id)
+            })),
+            StatusCode::NOT_FOUND,
+        ))
+    }
+}
+
+pub async fn events_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&events::drain_events()))
+}
+
+// Comment lines sent while idle, so a client that hung up is noticed and unsubscribed
+const EVENT_STREAM_KEEPALIVE: Duration = Duration::from_secs(15);
+
+// Server-sent events. Every event carries "seq", numbering the events for this client, so a
+// gap means some were dropped or coalesced under the overflow policy.
+pub async fn event_stream_handler(
+    query: events::StreamQuery,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let subscriber = events::subscribe(&query);
+    let (mut sender, body) = hyper::Body::channel();
+    tokio::spawn(async move {
+        loop {
+            let frame = tokio::select! {
+                event = subscriber.next() => match event {
+                    Some(event) => format!("id: {}\ndata: {}\n\n", event["seq"], event),
+                    None => break,
+                },
+                _ = tokio::time::sleep(EVENT_STREAM_KEEPALIVE) => ": keepalive\n\n".to_string(),
+            };
+            // The client hung up
+            if sender.send_data(frame.into()).await.is_err() {
+                break;
+            }
+        }
+        events::unsubscribe(subscriber.id());
+    });
+
+    let response = Response::builder()
+        .header("Content-Type", "text/event-stream")
+        .header("Cache-Control", "no-cache")
+        .body(body)
+        .unwrap();
+    Ok(response)
+}
+
+// The same events as /events/stream, one JSON text message each. The client narrows the
+// stream by sending a filter such as {"types": "watchpoint_hit", "address": 4096}.
+pub async fn event_socket_handler(
+    query: events::StreamQuery,
+    ws: warp::ws::Ws,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(ws.on_upgrade(move |socket| async move {
+        let subscriber = events::subscribe(&query);
+        let (mut outgoing, mut incoming) = socket.split();
+        loop {
+            tokio::select! {
+                event = subscriber.next() => {
+                    let message = match event {
+                        Some(event) => warp::ws::Message::text(event.to_string()),
+                        None => warp::ws::Message::close(),
+                    };
+                    let closing = message.is_close();
+                    if outgoing.send(message).await.is_err() || closing {
+                        break;
+                    }
+                }
+                message = incoming.next() => match message {
+                    Some(Ok(message)) if !message.is_close() => {
+                        // A text message carrying a filter replaces the subscription
+                        let Ok(text) = message.to_str() else {
+                            continue;
+                        };
+                        let reply = match serde_json::from_str::<events::EventFilter>(text) {
+                            Ok(filter) => {
+                                subscriber.set_filter(filter.clone());
+                                json!({ "type": "subscribed", "filter": filter })
+                            }
+                            Err(e) => json!({ "type": "subscribe_error", "error": e.to_string() }),
+                        };
+                        if outgoing.send(warp::ws::Message::text(reply.to_string())).await.is_err() {
+                            break;
+                        }
+                    }
+                    _ => break,
+                },
+            }
+        }
+        events::unsubscribe(subscriber.id());
+    }))
+}
+
+pub async fn event_subscribers_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&json!({
+        "subscribers": events::subscribers()
+    })))
+}
+
+pub async fn logs_handler(query: logger::LogQuery) -> Result<impl warp::Reply, warp::Rejection> {
+    match logger::tail(&query) {
+        Ok(entries) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "logs": entries })),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": e })),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+pub async fn log_levels_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&logger::levels()))
+}
+
+pub async fn set_log_levels_handler(
+    level_request: logger::LogLevelRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match logger::set_levels(&level_request.spec) {
+        Ok(levels) => Ok(warp::reply::with_status(
+            warp::reply::json(&levels),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": e })),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+pub async fn crashes_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&json!({ "crashes": crash::reports() })))
+}
+
+pub async fn clear_crashes_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&json!({ "cleared": crash::clear() })))
+}
+
+pub async fn selftest_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    query: selftest::SelfTestQuery,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let opened = *pid_state.lock().unwrap();
+    let report = tokio::task::spawn_blocking(move || selftest::run(&query, opened))
+        .await
+        .unwrap_or(Value::Null);
+    Ok(warp::reply::json(&report))
+}
+
+pub async fn region_monitor_start_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    monitor_request: region_monitor::RegionMonitorRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        match region_monitor::start(pid, monitor_request) {
+            Ok(_) => Ok(warp::reply::with_status(
+                warp::reply::json(&region_monitor::status()),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::BAD_REQUEST,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn add_mapping_watch_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    watch_request: mapping_watch::MappingWatchRequest,
+    session: String,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        match mapping_watch::add(pid, watch_request, &session) {
+            Ok(watch) => Ok(warp::reply::with_status(
+                warp::reply::json(&watch),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::BAD_REQUEST,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn remove_mapping_watch_handler(
+    remove_request: request::RemoveMappingWatchRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if mapping_watch::remove(remove_request.id) {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "removed": remove_request.id })),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": "Unknown mapping watch id" })),
+            StatusCode::NOT_FOUND,
+        ))
+    }
+}
+
+pub async fn list_mapping_watches_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(
+        &json!({ "watches": mapping_watch::list() }),
+    ))
+}
+
+pub async fn region_monitor_stop_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(
+        &json!({ "success": region_monitor::stop() }),
+    ))
+}
+
+pub async fn region_monitor_status_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&region_monitor::status()))
+}
+
+pub async fn heatmap_start_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    heatmap_request: heatmap::HeatmapRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        match heatmap::start(pid, heatmap_request) {
+            Ok(_) => Ok(warp::reply::with_status(
+                warp::reply::json(&heatmap::report()),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::BAD_REQUEST,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn heatmap_stop_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    heatmap::stop();
+    Ok(warp::reply::json(&heatmap::report()))
+}
+
+pub async fn heatmap_report_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&heatmap::report()))
+}
+
+pub async fn enumerate_allocations_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    allocations_request: request::EnumerateAllocationsRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let max_count = allocations_request.max_count.unwrap_or(MAX_RESULTS);
+        match native_bridge::enum_allocations(pid, max_count) {
+            Ok(allocations) => {
+                let min_size = allocations_request.min_size.unwrap_or(0);
+                let max_size = allocations_request.max_size.unwrap_or(usize::MAX);
+                let allocations: Vec<Value> = allocations
+                    .into_iter()
+                    .filter(|allocation| {
+                        let size = allocation["size"].as_u64().unwrap_or(0) as usize;
+                        size >= min_size && size <= max_size
+                    })
+                    .collect();
+                Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({
+                        "count": allocations.len(),
+                        "allocations": allocations
+                    })),
+                    StatusCode::OK,
+                ))
+            }
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn page_info_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    page_info_request: request::PageInfoRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        match native_bridge::query_page_info(pid, page_info_request.address, page_info_request.size)
+        {
+            Ok((page_size, flags)) => {
+                let first_page = page_info_request.address / page_size * page_size;
+                let pages: Vec<Value> = flags
+                    .iter()
+                    .enumerate()
+                    .map(|(index, page_flags)| {
+                        json!({
+                            "address": first_page + index * page_size,
+                            "resident": page_flags & native_bridge::PAGE_INFO_RESIDENT != 0,
+                            "dirty": page_flags & native_bridge::PAGE_INFO_DIRTY != 0,
+                            "swapped": page_flags & native_bridge::PAGE_INFO_SWAPPED != 0
+                        })
+                    })
+                    .collect();
+                let count = |flag: u8| flags.iter().filter(|f| *f & flag != 0).count();
+                Ok(warp::reply::with_status(
+                    warp::reply::json(&json!({
+                        "page_size": page_size,
+                        "resident_count": count(native_bridge::PAGE_INFO_RESIDENT),
+                        "dirty_count": count(native_bridge::PAGE_INFO_DIRTY),
+                        "swapped_count": count(native_bridge::PAGE_INFO_SWAPPED),
+                        "pages": pages
+                    })),
+                    StatusCode::OK,
+                ))
+            }
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
         }
-    };
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
 
-    if result.starts_with("Error:") {
-        return Ok(warp::reply::with_status(
-            warp::reply::json(&json!({
-                "error": result,
-                "path": decoded_path,
-                "max_depth": req.max_depth
-            })),
-            warp::http::StatusCode::BAD_REQUEST,
-        ));
+// Reads every range once and throws the data away so the pages are resident
+// before a timed scan or filter.
+pub async fn prefetch_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    prefetch_request: request::PrefetchRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let chunk_size = prefetch_request
+            .chunk_size
+            .unwrap_or(1024 * 1024 * 16)
+            .max(4096);
+        let ranges = &prefetch_request.address_ranges;
+        let stats = scan_stats::ScanStats::new(ranges, ranges);
+        ranges.par_iter().for_each(|&(start, end)| {
+            let mut buffer = vec![0u8; chunk_size.min(end.saturating_sub(start))];
+            let mut chunk_start = start;
+            while chunk_start < end {
+                let size = chunk_size.min(end - chunk_start);
+                let read_started = Instant::now();
+                let nread =
+                    memory_backend::for_pid(pid).read(pid, chunk_start, &mut buffer[..size]);
+                stats.record_read(nread.ok(), read_started.elapsed());
+                chunk_start += size;
+            }
+        });
+        Ok(warp::reply::with_status(
+            warp::reply::json(&stats.report()),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
     }
+}
 
-    match panic::catch_unwind(|| util::parse_directory_structure(&result)) {
-        Ok(items) => Ok(warp::reply::with_status(
-            warp::reply::json(&items),
-            warp::http::StatusCode::OK,
+pub async fn register_device_handler(
+    device: devices::Device,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match devices::register(device) {
+        Ok(_) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "success": true })),
+            StatusCode::OK,
         )),
-        Err(_) => Ok(warp::reply::with_status(
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "success": false, "message": e })),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+pub async fn list_devices_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&json!({ "devices": devices::list() })))
+}
+
+pub async fn remove_device_handler(
+    remove_request: request::RemoveDeviceRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if devices::remove(&remove_request.name) {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "success": true })),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
             warp::reply::json(&json!({
-                "error": "Process panicked during parsing of directory structure",
-                "path": decoded_path,
-                "max_depth": req.max_depth
+                "success": false,
+                "message": format!("Unknown device '{}'", remove_request.name)
             })),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::NOT_FOUND,
+        ))
+    }
+}
+
+pub async fn devices_fan_out_handler(
+    path: &'static str,
+    fan_out_request: devices::FanOutRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match devices::fan_out(path, fan_out_request).await {
+        Ok(result) => Ok(warp::reply::with_status(
+            warp::reply::json(&result),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": e })),
+            StatusCode::BAD_REQUEST,
         )),
     }
 }
 
-pub async fn read_file_handler(req: request::ReadFileRequest) -> Result<Response<Body>, Rejection> {
-    let decoded_path = percent_decode_str(&req.path)
-        .decode_utf8_lossy()
-        .into_owned();
+pub async fn record_start_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    recorder::start();
+    Ok(warp::reply::json(&json!({ "success": true })))
+}
 
-    let c_path = CString::new(decoded_path.clone()).unwrap();
-    let mut size: usize = 0;
-    let mut error_ptr: *mut c_char = std::ptr::null_mut();
+pub async fn record_stop_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&recorder::stop()))
+}
 
-    let data_ptr = unsafe {
-        native_bridge::read_file(
-            c_path.as_ptr(),
-            &mut size as *mut usize,
-            &mut error_ptr as *mut *mut c_char,
-        )
-    };
+pub async fn record_status_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&recorder::status()))
+}
 
-    if !error_ptr.is_null() {
-        let error_message = unsafe { CStr::from_ptr(error_ptr).to_string_lossy().into_owned() };
-        unsafe { libc::free(error_ptr as *mut c_void) };
-        return Ok(Response::builder()
-            .status(StatusCode::INTERNAL_SERVER_ERROR)
-            .body(Body::from(error_message))
-            .unwrap());
+pub async fn replay_handler(
+    replay_request: recorder::ReplayRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match recorder::replay(replay_request).await {
+        Ok(results) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "results": results })),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": e })),
+            StatusCode::BAD_REQUEST,
+        )),
     }
+}
 
-    if data_ptr.is_null() || size == 0 {
-        return Ok(Response::builder()
-            .status(StatusCode::NOT_FOUND)
-            .body(Body::from("File not found or empty"))
-            .unwrap());
+pub async fn take_snapshot_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    snapshot_request: request::TakeSnapshotRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let ranges = match snapshot_request.address_ranges {
+            Some(ranges) => ranges,
+            None => {
+                let protection = snapshot_request.protection.as_deref().unwrap_or("rw");
+                let regions = native_bridge::enum_regions(pid).unwrap_or_default();
+                util::parse_regions(&regions)
+                    .into_iter()
+                    .filter(|(_, _, region_protection)| {
+                        protection.chars().all(|c| region_protection.contains(c))
+                    })
+                    .map(|(start, end, _)| (start, end))
+                    .collect()
+            }
+        };
+        match snapshot::take(pid, &snapshot_request.name, &ranges) {
+            Ok(summary) => Ok(warp::reply::with_status(
+                warp::reply::json(&summary),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
     }
+}
 
-    let data = unsafe { slice::from_raw_parts(data_ptr as *const u8, size) }.to_vec();
-    unsafe { libc::free(data_ptr as *mut c_void) };
+pub async fn list_snapshots_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
 
-    Ok(Response::builder()
-        .header("Content-Type", "application/octet-stream")
-        .body(Body::from(data))
-        .unwrap())
+    if let Some(pid) = *pid {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "snapshots": snapshot::list(pid) })),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
 }
 
-pub async fn get_app_info_handler(
+pub async fn remove_snapshot_handler(
     pid_state: Arc<Mutex<Option<i32>>>,
+    remove_request: request::RemoveSnapshotRequest,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let pid = pid_state.lock().unwrap();
+
     if let Some(pid) = *pid {
-        let result = native_bridge::get_application_info(pid);
-        let message = match result {
-            Ok(message) => message,
-            Err(e) => {
-                return Ok(warp::reply::with_status(
-                    warp::reply::json(&serde_json::json!({
-                        "message": e.to_string()
-                    })),
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                ));
-            }
-        };
-        let parsed_result: Value = match serde_json::from_str(&message) {
-            Ok(json) => json,
+        match snapshot::remove(pid, &remove_request.name) {
+            Ok(_) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "success": true })),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "success": false, "message": e })),
+                StatusCode::NOT_FOUND,
+            )),
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+fn compare_options(
+    data_type: Option<String>,
+    align: Option<usize>,
+    merge_gap: Option<usize>,
+    max_results: Option<usize>,
+    max_bytes: Option<usize>,
+) -> Result<snapshot::CompareOptions, String> {
+    let value_size = data_type.as_deref().and_then(util::data_type_size);
+    if data_type.is_some() && value_size.is_none() {
+        return Err("Unsupported data type".to_string());
+    }
+    Ok(snapshot::CompareOptions {
+        align: align.or(value_size).unwrap_or(1),
+        data_type,
+        merge_gap: merge_gap.unwrap_or(0),
+        max_results: max_results.unwrap_or(10_000),
+        max_bytes: max_bytes.unwrap_or(64),
+    })
+}
+
+pub async fn snapshot_changed_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    changed_request: request::SnapshotChangedRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let options = match compare_options(
+            changed_request.data_type,
+            changed_request.align,
+            changed_request.merge_gap,
+            changed_request.max_results,
+            changed_request.max_bytes,
+        ) {
+            Ok(options) => options,
             Err(e) => {
                 return Ok(warp::reply::with_status(
-                    warp::reply::json(&serde_json::json!({
-                        "message": format!("Failed to parse application info: {}", e)
-                    })),
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                ));
+                    warp::reply::json(&json!({ "error": e })),
+                    StatusCode::BAD_REQUEST,
+                ))
             }
         };
-        Ok(warp::reply::with_status(
-            warp::reply::json(&json!({ "info": parsed_result })),
-            StatusCode::OK,
-        ))
+        match snapshot::changed_since(pid, &changed_request.name, &options) {
+            Ok(result) => Ok(warp::reply::with_status(
+                warp::reply::json(&result),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::BAD_REQUEST,
+            )),
+        }
     } else {
         Ok(warp::reply::with_status(
             warp::reply::json(&json!({"error": "Pid not set"})),
@@ -1577,243 +5247,362 @@ pub async fn get_app_info_handler(
     }
 }
 
-pub async fn set_watchpoint_handler(
+pub async fn snapshot_diff_handler(
     pid_state: Arc<Mutex<Option<i32>>>,
-    watchpoint: request::SetWatchPointRequest,
+    diff_request: request::SnapshotDiffRequest,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let pid = pid_state.lock().unwrap();
 
     if let Some(pid) = *pid {
-        let _type = match watchpoint._type.as_str() {
-            "r" => 1,
-            "w" => 2,
-            "a" => 3,
-            _ => {
+        let options = match compare_options(
+            diff_request.data_type,
+            diff_request.align,
+            diff_request.merge_gap,
+            diff_request.max_results,
+            diff_request.max_bytes,
+        ) {
+            Ok(options) => options,
+            Err(e) => {
                 return Ok(warp::reply::with_status(
-                    warp::reply::json(&request::SetWatchPointResponse {
-                        success: false,
-                        message: format!("Unknown type"),
-                    }),
+                    warp::reply::json(&json!({ "error": e })),
                     StatusCode::BAD_REQUEST,
                 ))
             }
         };
-        let result = native_bridge::set_watchpoint(pid, watchpoint.address, watchpoint.size, _type);
-
-        let ret = match result {
-            Ok(_) => Ok(warp::reply::with_status(
-                warp::reply::json(&request::SetWatchPointResponse {
-                    success: true,
-                    message: "Watchpoint set successfully".to_string(),
-                }),
+        match snapshot::diff(pid, &diff_request.old, &diff_request.new, &options) {
+            Ok(result) => Ok(warp::reply::with_status(
+                warp::reply::json(&result),
                 StatusCode::OK,
             )),
             Err(e) => Ok(warp::reply::with_status(
-                warp::reply::json(&request::SetWatchPointResponse {
-                    success: false,
-                    message: format!("Failed to set watchpoint. Error: {}", e),
-                }),
-                StatusCode::INTERNAL_SERVER_ERROR,
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::BAD_REQUEST,
             )),
-        };
-        return ret;
+        }
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
+
+pub async fn list_annotations_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    list_request: request::ListAnnotationsRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        let notes = annotations::overlapping(
+            &annotations::list(pid),
+            list_request.start.unwrap_or(0),
+            list_request.end.unwrap_or(usize::MAX),
+        );
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "annotations": notes })),
+            StatusCode::OK,
+        ))
     } else {
         Ok(warp::reply::with_status(
-            warp::reply::json(&request::SetWatchPointResponse {
-                success: false,
-                message: format!("Pid not set"),
-            }),
+            warp::reply::json(&json!({"error": "Pid not set"})),
             StatusCode::BAD_REQUEST,
         ))
     }
 }
 
-pub async fn remove_watchpoint_handler(
+pub async fn add_annotation_handler(
     pid_state: Arc<Mutex<Option<i32>>>,
-    watchpoint: request::RemoveWatchPointRequest,
+    annotation: annotations::Annotation,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let pid = pid_state.lock().unwrap();
 
-    if let Some(_pid) = *pid {
-        let result = native_bridge::remove_watchpoint(watchpoint.address);
-
-        let ret = match result {
-            Ok(_) => Ok(warp::reply::with_status(
-                warp::reply::json(&request::RemoveWatchPointResponse {
-                    success: true,
-                    message: "Remove Watchpoint set successfully".to_string(),
-                }),
+    if let Some(pid) = *pid {
+        match annotations::upsert(pid, annotation) {
+            Ok(annotation) => Ok(warp::reply::with_status(
+                warp::reply::json(&annotation.to_json()),
                 StatusCode::OK,
             )),
             Err(e) => Ok(warp::reply::with_status(
-                warp::reply::json(&request::RemoveWatchPointResponse {
-                    success: false,
-                    message: format!("Failed to remove watchpoint. Error: {}", e),
-                }),
-                StatusCode::INTERNAL_SERVER_ERROR,
+                warp::reply::json(&json!({ "error": e })),
+                StatusCode::BAD_REQUEST,
             )),
-        };
-        return ret;
+        }
     } else {
         Ok(warp::reply::with_status(
-            warp::reply::json(&request::RemoveWatchPointResponse {
-                success: false,
-                message: format!("Pid not set"),
-            }),
+            warp::reply::json(&json!({"error": "Pid not set"})),
             StatusCode::BAD_REQUEST,
         ))
     }
 }
 
-pub async fn set_breakpoint_handler(
+pub async fn remove_annotation_handler(
     pid_state: Arc<Mutex<Option<i32>>>,
-    breakpoint: request::SetBreakPointRequest,
+    remove_request: request::RemoveAnnotationRequest,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let pid = pid_state.lock().unwrap();
+
     if let Some(pid) = *pid {
-        let result = native_bridge::set_breakpoint(pid, breakpoint.address, breakpoint.hit_count);
-        let ret = match result {
-            Ok(_) => Ok(warp::reply::with_status(
-                warp::reply::json(&request::SetBreakPointResponse {
-                    success: true,
-                    message: "Breakpoint set successfully".to_string(),
-                }),
+        match annotations::remove(pid, remove_request.id) {
+            Ok(true) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "success": true })),
                 StatusCode::OK,
             )),
+            Ok(false) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({
+                    "success": false,
+                    "message": format!("Unknown annotation id {}", remove_request.id)
+                })),
+                StatusCode::NOT_FOUND,
+            )),
             Err(e) => Ok(warp::reply::with_status(
-                warp::reply::json(&request::SetBreakPointResponse {
-                    success: false,
-                    message: format!("Failed to set breakpoint. Error: {}", e),
-                }),
+                warp::reply::json(&json!({ "success": false, "message": e })),
                 StatusCode::INTERNAL_SERVER_ERROR,
             )),
-        };
-        return ret;
+        }
     } else {
         Ok(warp::reply::with_status(
-            warp::reply::json(&request::SetBreakPointResponse {
-                success: false,
-                message: format!("Pid not set"),
-            }),
+            warp::reply::json(&json!({"error": "Pid not set"})),
             StatusCode::BAD_REQUEST,
         ))
     }
 }
 
-pub async fn remove_breakpoint_handler(
+pub async fn batch_handler(
     pid_state: Arc<Mutex<Option<i32>>>,
-    breakpoint: request::RemoveBreakPointRequest,
+    batch_request: batch::BatchRequest,
+    session: String,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let pid = pid_state.lock().unwrap();
-    if let Some(_pid) = *pid {
-        let result = native_bridge::remove_breakpoint(breakpoint.address);
-        let ret = match result {
-            Ok(_) => Ok(warp::reply::with_status(
-                warp::reply::json(&request::RemoveBreakPointResponse {
-                    success: true,
-                    message: "Breakpoint removed successfully".to_string(),
-                }),
-                StatusCode::OK,
-            )),
-            Err(e) => Ok(warp::reply::with_status(
-                warp::reply::json(&request::RemoveBreakPointResponse {
-                    success: false,
-                    message: format!("Failed to remove breakpoint. Error: {}", e),
-                }),
-                StatusCode::INTERNAL_SERVER_ERROR,
-            )),
-        };
-        return ret;
+
+    if let Some(pid) = *pid {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&batch::run(pid, &batch_request, &session)),
+            StatusCode::OK,
+        ))
     } else {
         Ok(warp::reply::with_status(
-            warp::reply::json(&request::RemoveBreakPointResponse {
-                success: false,
-                message: format!("Pid not set"),
-            }),
+            warp::reply::json(&json!({"error": "Pid not set"})),
             StatusCode::BAD_REQUEST,
         ))
     }
 }
 
-pub async fn change_process_state_handler(
+pub async fn list_triggers_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&json!({ "triggers": triggers::list() })))
+}
+
+pub async fn bind_trigger_handler(
+    trigger: triggers::Trigger,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match triggers::bind(trigger) {
+        Ok(trigger) => Ok(warp::reply::with_status(
+            warp::reply::json(&trigger),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": e })),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+pub async fn unbind_trigger_handler(
+    unbind_request: request::UnbindTriggerRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if triggers::unbind(&unbind_request.id) {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "removed": unbind_request.id })),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": "Unknown trigger id" })),
+            StatusCode::NOT_FOUND,
+        ))
+    }
+}
+
+pub async fn input_handler(
+    input_request: input::InputRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match input::send_all(&input_request) {
+        Ok(sent) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "sent": sent })),
+            StatusCode::OK,
+        )),
+        Err((sent, e)) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "sent": sent, "error": e })),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+pub async fn list_alerts_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&json!({ "alerts": alerts::list() })))
+}
+
+pub async fn add_alert_handler(
+    rule: alerts::AlertRule,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match alerts::add(rule) {
+        Ok(rule) => Ok(warp::reply::with_status(
+            warp::reply::json(&rule),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": e })),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+pub async fn remove_alert_handler(
+    remove_request: alerts::RemoveAlertRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if alerts::remove(&remove_request.id) {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "removed": remove_request.id })),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": "Unknown alert id" })),
+            StatusCode::NOT_FOUND,
+        ))
+    }
+}
+
+pub async fn list_profiles_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&profiles::list()))
+}
+
+pub async fn save_profile_handler(
+    profile: profiles::Profile,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match profiles::upsert(profile) {
+        Ok(profile) => Ok(warp::reply::with_status(
+            warp::reply::json(&profile),
+            StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": e })),
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+pub async fn remove_profile_handler(
+    remove_request: profiles::RemoveProfileRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match profiles::remove(&remove_request.process) {
+        Ok(true) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "removed": remove_request.process })),
+            StatusCode::OK,
+        )),
+        Ok(false) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": "Unknown profile" })),
+            StatusCode::NOT_FOUND,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({ "error": e })),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+pub async fn fire_trigger_handler(
     pid_state: Arc<Mutex<Option<i32>>>,
-    state_request: request::ChangeProcessStateRequest,
+    id: String,
+    session: String,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let pid = pid_state.lock().unwrap();
 
-    if let Some(_pid) = *pid {
-        let result = if state_request.do_play {
-            unsafe { native_bridge::resume_process(_pid) }
-        } else {
-            unsafe { native_bridge::suspend_process(_pid) }
-        };
-
-        let ret = match result {
-            true => Ok(warp::reply::with_status(
-                warp::reply::json(&request::ChangeProcessStateResponse {
-                    success: true,
-                    message: format!(
-                        "Process {} successfully",
-                        if state_request.do_play {
-                            "resumed"
-                        } else {
-                            "suspend"
-                        }
-                    ),
-                }),
-                StatusCode::OK,
-            )),
-            false => Ok(warp::reply::with_status(
-                warp::reply::json(&request::ChangeProcessStateResponse {
-                    success: false,
-                    message: format!("Failed to change process state. Error"),
-                }),
-                StatusCode::INTERNAL_SERVER_ERROR,
+    if let Some(pid) = *pid {
+        match triggers::fire(pid, &id, &session) {
+            Ok(result) => {
+                let fired = json!({ "id": id, "result": result });
+                events::push_event("trigger_fired", fired.clone());
+                Ok(warp::reply::with_status(
+                    warp::reply::json(&fired),
+                    StatusCode::OK,
+                ))
+            }
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&json!({ "id": id, "error": e })),
+                StatusCode::BAD_REQUEST,
             )),
-        };
-        return ret;
+        }
     } else {
         Ok(warp::reply::with_status(
-            warp::reply::json(&request::ChangeProcessStateResponse {
-                success: false,
-                message: "Pid not set".to_string(),
-            }),
+            warp::reply::json(&json!({"error": "Pid not set"})),
             StatusCode::BAD_REQUEST,
         ))
     }
 }
 
-pub async fn pointermap_generate_handler(
+pub async fn list_exclusions_handler() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&json!({ "ranges": exclusions::list() })))
+}
+
+pub async fn add_exclusions_handler(
+    exclusion_request: request::ExclusionRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let ranges = exclusions::add(&exclusion_request.ranges.unwrap_or_default());
+    Ok(warp::reply::json(&json!({ "ranges": ranges })))
+}
+
+pub async fn remove_exclusions_handler(
+    exclusion_request: request::ExclusionRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let ranges = exclusions::remove(exclusion_request.ranges.as_deref());
+    Ok(warp::reply::json(&json!({ "ranges": ranges })))
+}
+
+pub async fn undo_write_handler(
     pid_state: Arc<Mutex<Option<i32>>>,
-    request: request::PointerMapGenerateRequest,
+    undo_request: request::UndoWriteRequest,
+    session: String,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let pid = pid_state.lock().unwrap();
 
     if let Some(pid) = *pid {
-        let result = ptrscan::generate_pointermap(pid);
+        let count = undo_request.count.unwrap_or(1);
+        Ok(warp::reply::with_status(
+            warp::reply::json(&write_history::undo(pid, count, &session)),
+            StatusCode::OK,
+        ))
+    } else {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
+    }
+}
 
-        match result {
-            Ok(binary_data) => {
-                let response = Response::builder()
-                    .header("Content-Type", "application/octet-stream")
-                    .body(hyper::Body::from(binary_data))
-                    .unwrap();
-                Ok(response)
-            }
-            Err(_) => {
-                let empty_buffer = Vec::new();
-                let response = Response::builder()
-                    .header("Content-Type", "application/octet-stream")
-                    .body(hyper::Body::from(empty_buffer))
-                    .unwrap();
-                Ok(response)
-            }
-        }
+pub async fn undo_all_writes_handler(
+    pid_state: Arc<Mutex<Option<i32>>>,
+    session: String,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pid = pid_state.lock().unwrap();
+
+    if let Some(pid) = *pid {
+        Ok(warp::reply::with_status(
+            warp::reply::json(&write_history::undo(pid, usize::MAX, &session)),
+            StatusCode::OK,
+        ))
     } else {
-        let response = Response::builder()
-            .status(StatusCode::BAD_REQUEST)
-            .body(hyper::Body::from("Pid not set"))
-            .unwrap();
-        Ok(response)
+        Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": "Pid not set"})),
+            StatusCode::BAD_REQUEST,
+        ))
     }
 }
+
+pub async fn write_log_handler(
+    query: write_history::WriteLogQuery,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(
+        &json!({ "writes": write_history::query(&query) }),
+    ))
+}
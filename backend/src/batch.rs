@@ -0,0 +1,193 @@
+// Runs an ordered list of operations in one request, so high-latency clients do not pay a
+// round trip per read or write.
+use crate::native_bridge;
+use crate::scheduler;
+use crate::util;
+use crate::write_history;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+pub const DEFAULT_FREEZE_INTERVAL_MS: u64 = 100;
+const MAX_READ_SIZE: usize = 1024 * 1024;
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Operation {
+    Read {
+        address: Option<usize>,
+        query: Option<String>,
+        size: usize,
+    },
+    Write {
+        address: Option<usize>,
+        query: Option<String>,
+        buffer: Vec<u8>,
+    },
+    Resolve {
+        query: String,
+    },
+    // Keeps rewriting the value until an unfreeze with the returned id
+    Freeze {
+        address: Option<usize>,
+        query: Option<String>,
+        buffer: Vec<u8>,
+        interval_ms: Option<u64>,
+    },
+    Unfreeze {
+        id: u64,
+    },
+}
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct BatchRequest {
+    pub operations: Vec<Operation>,
+    // Skips the remaining operations after the first failure
+    pub stop_on_error: Option<bool>,
+}
+
+// Operations take either a numeric address or a symbolic query like "libgame.so+0x1234"
+fn target_address(
+    pid: i32,
+    modules: &mut Option<Vec<Value>>,
+    address: Option<usize>,
+    query: &Option<String>,
+) -> Result<usize, String> {
+    match (address, query) {
+        (Some(address), None) => Ok(address),
+        (None, Some(query)) => resolve(pid, modules, query),
+        _ => Err("Exactly one of address or query is required".to_string()),
+    }
+}
+
+fn resolve(pid: i32, modules: &mut Option<Vec<Value>>, query: &str) -> Result<usize, String> {
+    if modules.is_none() {
+        *modules = Some(native_bridge::enum_modules(pid)?);
+    }
+    util::resolve_symbolic_address(pid, query, modules.as_ref().unwrap())
+        .map_err(|e| format!("Failed to resolve address: {}", e))
+}
+
+fn run_operation(
+    pid: i32,
+    modules: &mut Option<Vec<Value>>,
+    operation: &Operation,
+    session: &str,
+) -> Result<Value, String> {
+    match operation {
+        Operation::Read {
+            address,
+            query,
+            size,
+        } => {
+            if *size > MAX_READ_SIZE {
+                return Err(format!("size must not exceed {}", MAX_READ_SIZE));
+            }
+            let address = target_address(pid, modules, *address, query)?;
+            let mut buffer = vec![0u8; *size];
+            let nread = native_bridge::read_process_memory(
+                pid,
+                address as *mut libc::c_void,
+                *size,
+                &mut buffer,
+            )
+            .map_err(|e| format!("ReadProcessMemory error: {}", e))?;
+            buffer.truncate(nread.max(0) as usize);
+            Ok(json!({ "address": address, "data": hex::encode(&buffer) }))
+        }
+        Operation::Write {
+            address,
+            query,
+            buffer,
+        } => {
+            let address = target_address(pid, modules, *address, query)?;
+            let original = write_history::read_original(pid, address, buffer.len());
+            let written = native_bridge::write_process_memory(
+                pid,
+                address as *mut libc::c_void,
+                buffer.len(),
+                buffer,
+            )
+            .map_err(|e| format!("WriteProcessMemory error: {}", e))?;
+            write_history::record(pid, address, original, buffer, "batch", session);
+            Ok(json!({ "address": address, "written": written }))
+        }
+        Operation::Resolve { query } => Ok(json!({ "address": resolve(pid, modules, query)? })),
+        Operation::Freeze {
+            address,
+            query,
+            buffer,
+            interval_ms,
+        } => {
+            let address = target_address(pid, modules, *address, query)?;
+            let id = scheduler::schedule(
+                pid,
+                scheduler::ScheduledWrite {
+                    address,
+                    buffer: buffer.clone(),
+                    sequence: None,
+                    interval_ms: Some(interval_ms.unwrap_or(DEFAULT_FREEZE_INTERVAL_MS)),
+                    duration_ms: None,
+                    restore_after_ms: None,
+                },
+                session,
+            )?;
+            Ok(json!({ "address": address, "id": id }))
+        }
+        Operation::Unfreeze { id } => {
+            if scheduler::cancel(*id) {
+                Ok(json!({ "id": id }))
+            } else {
+                Err(format!("Unknown job id {}", id))
+            }
+        }
+    }
+}
+
+fn operation_name(operation: &Operation) -> &'static str {
+    match operation {
+        Operation::Read { .. } => "read",
+        Operation::Write { .. } => "write",
+        Operation::Resolve { .. } => "resolve",
+        Operation::Freeze { .. } => "freeze",
+        Operation::Unfreeze { .. } => "unfreeze",
+    }
+}
+
+pub fn run(pid: i32, request: &BatchRequest, session: &str) -> Value {
+    let stop_on_error = request.stop_on_error.unwrap_or(false);
+    // Module list is fetched once, on the first symbolic address
+    let mut modules = None;
+    let mut failed = 0;
+    let mut results = Vec::with_capacity(request.operations.len());
+
+    for (index, operation) in request.operations.iter().enumerate() {
+        if stop_on_error && failed > 0 {
+            results.push(json!({
+                "index": index,
+                "op": operation_name(operation),
+                "success": false,
+                "skipped": true,
+            }));
+            continue;
+        }
+        let mut result = match run_operation(pid, &mut modules, operation, session) {
+            Ok(result) => result,
+            Err(e) => {
+                failed += 1;
+                json!({ "success": false, "error": e })
+            }
+        };
+        result["index"] = json!(index);
+        result["op"] = json!(operation_name(operation));
+        if result.get("success").is_none() {
+            result["success"] = json!(true);
+        }
+        results.push(result);
+    }
+
+    json!({
+        "results": results,
+        "failed": failed,
+    })
+}
@@ -0,0 +1,382 @@
+// `--benchmark`: how fast this device reads memory, matches each data type and compresses scan
+// chunks, with the --scan-chunk-size and --scan-threads that make the most of it. Reads go to
+// a buffer in the server's own process, through the backend other processes are read with.
+use crate::driver;
+use crate::memory_backend::{DriverBackend, MemoryBackend, NativeBackend};
+use crate::scan_engine;
+use crate::scan_priority::{self, Priority};
+use rayon::prelude::*;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const BUFFER_SIZE: usize = 64 * 1024 * 1024;
+const MATCH_SIZE: usize = 16 * 1024 * 1024;
+const CHUNK_SIZES: &[usize] = &[
+    64 * 1024,
+    256 * 1024,
+    1024 * 1024,
+    4 * 1024 * 1024,
+    16 * 1024 * 1024,
+];
+const DATA_TYPES: &[&str] = &[
+    "int8", "int16", "int32", "int64", "float", "double", "utf-8", "utf-16", "aob",
+];
+// Each measurement repeats until it has run this long
+const MIN_DURATION: Duration = Duration::from_millis(250);
+// Settings this close to the fastest count as just as good, so the cheaper one is recommended
+const GOOD_ENOUGH: f64 = 0.9;
+
+// Zero runs, small integers, pointer-like values and noise, compressing roughly like real
+// process memory rather than like all zeros or all noise
+fn memory_like(size: usize) -> Vec<u8> {
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut next = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+    let mut buffer = Vec::with_capacity(size + 8);
+    while buffer.len() < size {
+        let word = next();
+        let value = match word % 4 {
+            0 => 0,
+            1 => word >> 56,
+            2 => 0x7f00_0000_0000 | (word >> 40),
+            _ => word,
+        };
+        buffer.extend_from_slice(&value.to_le_bytes());
+    }
+    buffer.truncate(size);
+    buffer
+}
+
+// MB/s of `run`, which handles `bytes` bytes per call
+fn measure(bytes: usize, mut run: impl FnMut()) -> f64 {
+    let started = Instant::now();
+    let mut runs = 0;
+    while runs == 0 || started.elapsed() < MIN_DURATION {
+        run();
+        runs += 1;
+    }
+    (bytes * runs) as f64 / started.elapsed().as_secs_f64() / (1024.0 * 1024.0)
+}
+
+fn read_all(backend: &dyn MemoryBackend, pid: i32, base: usize, size: usize, chunk_size: usize) {
+    let mut chunk = vec![0u8; chunk_size];
+    for offset in (0..size).step_by(chunk_size) {
+        let length = chunk_size.min(size - offset);
+        let _ = backend.read(pid, base + offset, &mut chunk[..length]);
+    }
+}
+
+// Reads the chunks in parallel, each into a buffer of its own, as a scan of many ranges does
+fn read_parallel(
+    backend: &dyn MemoryBackend,
+    pid: i32,
+    base: usize,
+    size: usize,
+    chunk_size: usize,
+) {
+    (0..size.div_ceil(chunk_size))
+        .into_par_iter()
+        .for_each(|index| {
+            let offset = index * chunk_size;
+            let mut chunk = vec![0u8; chunk_size.min(size - offset)];
+            let _ = backend.read(pid, base + offset, &mut chunk);
+        });
+}
+
+// A busy thread per core standing in for the target, e.g. a game. Returns the loop iterations
+// per second all of them managed while `work` ran.
+fn with_busy_target(cores: usize, work: impl FnOnce() -> f64) -> (f64, f64) {
+    let stop = AtomicBool::new(false);
+    thread::scope(|scope| {
+        let spinners: Vec<_> = (0..cores)
+            .map(|_| {
+                scope.spawn(|| {
+                    let started = Instant::now();
+                    let mut iterations = 0u64;
+                    while !stop.load(Ordering::Relaxed) {
+                        iterations = std::hint::black_box(iterations + 1);
+                    }
+                    iterations as f64 / started.elapsed().as_secs_f64()
+                })
+            })
+            .collect();
+        let result = work();
+        stop.store(true, Ordering::Relaxed);
+        let rate = spinners
+            .into_iter()
+            .map(|spinner| spinner.join().unwrap_or(0.0))
+            .sum();
+        (result, rate)
+    })
+}
+
+// Scan read throughput at each priority level next to a busy target, and the share of its
+// unhindered speed the target keeps
+fn priority_impact(
+    backend: &dyn MemoryBackend,
+    pid: i32,
+    base: usize,
+    chunk_size: usize,
+    cores: usize,
+) -> Vec<Value> {
+    let (_, alone) = with_busy_target(cores, || {
+        thread::sleep(MIN_DURATION);
+        0.0
+    });
+    scan_priority::LEVELS
+        .iter()
+        .filter_map(|&level| {
+            let priority = Priority::parse(Some(level)).ok()?;
+            let (speed, rate) = with_busy_target(cores, || {
+                priority.run(|| {
+                    measure(BUFFER_SIZE, || {
+                        (0..BUFFER_SIZE.div_ceil(chunk_size))
+                            .into_par_iter()
+                            .for_each(|index| {
+                                let offset = index * chunk_size;
+                                let mut chunk = vec![0u8; chunk_size.min(BUFFER_SIZE - offset)];
+                                let _ = backend.read(pid, base + offset, &mut chunk);
+                                priority.pause();
+                            });
+                    })
+                })
+            });
+            Some(json!({
+                "priority": level,
+                "mb_per_s": speed,
+                "target_share": rate / alone,
+            }))
+        })
+        .collect()
+}
+
+// 1, 2, 4, ... up to and including the number of cores
+fn thread_counts(cores: usize) -> Vec<usize> {
+    let mut counts: Vec<usize> = std::iter::successors(Some(1), |&n| Some(n * 2))
+        .take_while(|&n| n < cores)
+        .collect();
+    counts.push(cores.max(1));
+    counts
+}
+
+// The first, i.e. cheapest, setting that is nearly as fast as the fastest
+fn recommend(results: &[(usize, f64)]) -> usize {
+    let best = results.iter().map(|&(_, speed)| speed).fold(0.0, f64::max);
+    results
+        .iter()
+        .find(|&&(_, speed)| speed >= best * GOOD_ENOUGH)
+        .map_or(0, |&(setting, _)| setting)
+}
+
+fn match_throughput(data_type: &str, new: &[u8], old: &[u8]) -> Value {
+    let size = scan_engine::element_size(data_type);
+    let text = matches!(data_type, "utf-8" | "utf-16" | "aob");
+    let align = if text { 1 } else { size };
+    // A value present in the buffer, so the scan does the work of reporting a match
+    let pattern = if text {
+        new[..8].to_vec()
+    } else {
+        new[..size].to_vec()
+    };
+    let exact = measure(new.len(), || {
+        std::hint::black_box(scan_engine::find_exact(new, 0, &pattern, align));
+    });
+    let method = if text { "changed" } else { "increased" };
+    let compare = measure(new.len(), || {
+        let matched = scan_engine::aligned_offsets(0, new.len(), size, align)
+            .filter(|&offset| {
+                scan_engine::compare(
+                    data_type,
+                    method,
+                    &new[offset..offset + size],
+                    &old[offset..offset + size],
+                    None,
+                )
+            })
+            .count();
+        std::hint::black_box(matched);
+    });
+    json!({
+        "data_type": data_type,
+        "exact_mb_per_s": exact,
+        "compare_mb_per_s": compare,
+    })
+}
+
+pub fn run() -> Value {
+    let backend: &dyn MemoryBackend = if driver::is_enabled() {
+        &DriverBackend
+    } else {
+        &NativeBackend
+    };
+    let pid = std::process::id() as i32;
+    let buffer = memory_like(BUFFER_SIZE);
+    let base = buffer.as_ptr() as usize;
+
+    let reads: Vec<(usize, f64)> = CHUNK_SIZES
+        .iter()
+        .map(|&chunk_size| {
+            let speed = measure(BUFFER_SIZE, || {
+                read_all(backend, pid, base, BUFFER_SIZE, chunk_size)
+            });
+            (chunk_size, speed)
+        })
+        .collect();
+    let chunk_size = recommend(&reads);
+
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let threads: Vec<(usize, f64)> = thread_counts(cores)
+        .into_iter()
+        .filter_map(|threads| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .ok()?;
+            let speed = pool.install(|| {
+                measure(BUFFER_SIZE, || {
+                    read_parallel(backend, pid, base, BUFFER_SIZE, chunk_size)
+                })
+            });
+            Some((threads, speed))
+        })
+        .collect();
+
+    let priorities = priority_impact(backend, pid, base, chunk_size, cores);
+
+    let new = &buffer[..MATCH_SIZE];
+    let old = &buffer[MATCH_SIZE..MATCH_SIZE * 2];
+    let matching: Vec<Value> = DATA_TYPES
+        .iter()
+        .map(|data_type| match_throughput(data_type, new, old))
+        .collect();
+
+    let chunk = &buffer[..chunk_size];
+    let compressed = lz4_flex::block::compress(chunk);
+    let compress = measure(chunk.len(), || {
+        std::hint::black_box(lz4_flex::block::compress(chunk));
+    });
+    let decompress = measure(chunk.len(), || {
+        let _ = std::hint::black_box(lz4_flex::block::decompress(&compressed, chunk.len()));
+    });
+
+    json!({
+        "backend": backend.name(),
+        "cores": cores,
+        "read": reads
+            .iter()
+            .map(|&(chunk_size, speed)| json!({ "chunk_size": chunk_size, "mb_per_s": speed }))
+            .collect::<Vec<_>>(),
+        "threads": threads
+            .iter()
+            .map(|&(threads, speed)| json!({ "threads": threads, "mb_per_s": speed }))
+            .collect::<Vec<_>>(),
+        "priority": priorities,
+        "match": matching,
+        "compression": {
+            "codec": "lz4",
+            "chunk_size": chunk_size,
+            "ratio": chunk.len() as f64 / compressed.len() as f64,
+            "compress_mb_per_s": compress,
+            "decompress_mb_per_s": decompress,
+        },
+        "recommended": {
+            "scan_chunk_size": chunk_size,
+            "scan_threads": recommend(&threads),
+        },
+    })
+}
+
+fn size_label(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{} MiB", bytes / (1024 * 1024))
+    } else {
+        format!("{} KiB", bytes / 1024)
+    }
+}
+
+pub fn print(report: &Value) {
+    let number = |value: &Value| value.as_f64().unwrap_or(0.0);
+    println!(
+        "Backend {}, {} cores",
+        report["backend"].as_str().unwrap_or("unknown"),
+        report["cores"]
+    );
+
+    println!("\nRead throughput by chunk size (one thread)");
+    for read in report["read"].as_array().into_iter().flatten() {
+        println!(
+            "  {:>8}  {:>10.1} MB/s",
+            size_label(read["chunk_size"].as_u64().unwrap_or(0)),
+            number(&read["mb_per_s"])
+        );
+    }
+
+    println!("\nRead throughput by thread count");
+    for threads in report["threads"].as_array().into_iter().flatten() {
+        println!(
+            "  {:>8}  {:>10.1} MB/s",
+            threads["threads"].as_u64().unwrap_or(0),
+            number(&threads["mb_per_s"])
+        );
+    }
+
+    println!("\nRead throughput by scan priority next to a busy target, and the target's speed");
+    for priority in report["priority"].as_array().into_iter().flatten() {
+        println!(
+            "  {:>10}  {:>10.1} MB/s  {:>5.0}%",
+            priority["priority"].as_str().unwrap_or(""),
+            number(&priority["mb_per_s"]),
+            number(&priority["target_share"]) * 100.0
+        );
+    }
+
+    println!("\nMatch throughput (exact scan / filter compare)");
+    for matching in report["match"].as_array().into_iter().flatten() {
+        println!(
+            "  {:>8}  {:>10.1} MB/s  {:>10.1} MB/s",
+            matching["data_type"].as_str().unwrap_or(""),
+            number(&matching["exact_mb_per_s"]),
+            number(&matching["compare_mb_per_s"])
+        );
+    }
+
+    let compression = &report["compression"];
+    println!(
+        "\nlz4 on {} chunks: ratio {:.2}, compress {:.1} MB/s, decompress {:.1} MB/s",
+        size_label(compression["chunk_size"].as_u64().unwrap_or(0)),
+        number(&compression["ratio"]),
+        number(&compression["compress_mb_per_s"]),
+        number(&compression["decompress_mb_per_s"])
+    );
+
+    let recommended = &report["recommended"];
+    println!(
+        "\nRecommended: --scan-chunk-size {} --scan-threads {}",
+        recommended["scan_chunk_size"], recommended["scan_threads"]
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recommends_the_cheapest_setting_near_the_fastest() {
+        assert_eq!(recommend(&[(1, 100.0), (2, 190.0), (4, 200.0)]), 2);
+        assert_eq!(recommend(&[(1, 100.0), (2, 50.0)]), 1);
+    }
+
+    #[test]
+    fn thread_counts_end_at_the_core_count() {
+        assert_eq!(thread_counts(1), [1]);
+        assert_eq!(thread_counts(6), [1, 2, 4, 6]);
+        assert_eq!(thread_counts(8), [1, 2, 4, 8]);
+    }
+}
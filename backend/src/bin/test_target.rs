@@ -0,0 +1,188 @@
+// A harmless process with one known value of every supported data type, for checking that
+// the server can read, scan, filter, write and freeze on this platform. On start it prints a
+// JSON manifest line with the address and little-endian hex of every value, then answers
+// commands on stdin, one per line:
+//   values  prints the current bytes of every value, as the target itself sees them
+//   tick    adds one to `counter` and prints the values
+//   exit    exits, as does closing stdin
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+
+const ALIGNMENT: usize = 16;
+
+struct Entry {
+    name: &'static str,
+    data_type: &'static str,
+    offset: usize,
+    size: usize,
+}
+
+struct Target {
+    memory: &'static mut [u8],
+    entries: Vec<Entry>,
+}
+
+fn initial_values() -> Vec<(&'static str, &'static str, Vec<u8>)> {
+    vec![
+        ("int8", "int8", (-7i8).to_le_bytes().to_vec()),
+        ("uint8", "uint8", 0xa5u8.to_le_bytes().to_vec()),
+        ("int16", "int16", (-12345i16).to_le_bytes().to_vec()),
+        ("uint16", "uint16", 0xbeefu16.to_le_bytes().to_vec()),
+        ("int32", "int32", (-123456789i32).to_le_bytes().to_vec()),
+        ("uint32", "uint32", 0x5ca1ab1eu32.to_le_bytes().to_vec()),
+        ("int64", "int64", (-1234567890123i64).to_le_bytes().to_vec()),
+        (
+            "uint64",
+            "uint64",
+            0x0123456789abcdefu64.to_le_bytes().to_vec(),
+        ),
+        ("int24", "int24", vec![0x56, 0x34, 0x12]),
+        (
+            "uint128",
+            "uint128",
+            0x0f1e2d3c4b5a69788796a5b4c3d2e1f0u128
+                .to_le_bytes()
+                .to_vec(),
+        ),
+        ("float", "float", 1234.5625f32.to_le_bytes().to_vec()),
+        ("double", "double", 98765.4321f64.to_le_bytes().to_vec()),
+        // 1.5 and -2.5
+        ("float16", "float16", 0x3e00u16.to_le_bytes().to_vec()),
+        ("bfloat16", "bfloat16", 0xc020u16.to_le_bytes().to_vec()),
+        // 12.34 at the default scale of 100
+        ("decimal32", "decimal32", 1234i32.to_le_bytes().to_vec()),
+        ("flags8", "flags8", vec![0b1010_0101]),
+        ("utf-8", "utf-8", b"Feiyan test target\0".to_vec()),
+        (
+            "utf-16",
+            "utf-16",
+            "Feiyan test target\0"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes)
+                .collect(),
+        ),
+        (
+            "aob",
+            "aob",
+            vec![0xde, 0xad, 0x13, 0x37, 0xc0, 0xde, 0xf0, 0x0d],
+        ),
+        // Changed by `tick`, for increased/changed filters and freeze checks
+        ("counter", "int32", 100i32.to_le_bytes().to_vec()),
+    ]
+}
+
+impl Target {
+    // Every value starts on its own 16-byte boundary of one heap block, so any alignment a
+    // scan asks for finds it
+    fn new() -> Self {
+        let values = initial_values();
+        let pointer_size = std::mem::size_of::<usize>();
+        let total = (values.len() + 2) * ALIGNMENT * 2;
+        let memory: &'static mut [u8] = Box::leak(vec![0u8; total + ALIGNMENT].into_boxed_slice());
+        let padding = (ALIGNMENT - memory.as_ptr() as usize % ALIGNMENT) % ALIGNMENT;
+        let memory = &mut memory[padding..padding + total];
+
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        for (name, data_type, bytes) in values {
+            memory[offset..offset + bytes.len()].copy_from_slice(&bytes);
+            entries.push(Entry {
+                name,
+                data_type,
+                offset,
+                size: bytes.len(),
+            });
+            offset += bytes.len().div_ceil(ALIGNMENT) * ALIGNMENT;
+        }
+        // Points at int32
+        let int32 = memory.as_ptr() as usize + entries[4].offset;
+        memory[offset..offset + pointer_size].copy_from_slice(&int32.to_le_bytes());
+        entries.push(Entry {
+            name: "pointer",
+            data_type: "pointer",
+            offset,
+            size: pointer_size,
+        });
+        // Points at pointer, so [[pointer_chain]] is int32
+        let pointer = memory.as_ptr() as usize + offset;
+        offset += ALIGNMENT;
+        memory[offset..offset + pointer_size].copy_from_slice(&pointer.to_le_bytes());
+        entries.push(Entry {
+            name: "pointer_chain",
+            data_type: "pointer",
+            offset,
+            size: pointer_size,
+        });
+        Target { memory, entries }
+    }
+
+    fn address(&self, entry: &Entry) -> usize {
+        self.memory.as_ptr() as usize + entry.offset
+    }
+
+    // Volatile, since the server changes these bytes behind the compiler's back
+    fn bytes(&self, entry: &Entry) -> Vec<u8> {
+        (0..entry.size)
+            .map(|i| unsafe { std::ptr::read_volatile(self.memory.as_ptr().add(entry.offset + i)) })
+            .collect()
+    }
+
+    fn values(&self) -> Value {
+        let values: Vec<Value> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                json!({
+                    "name": entry.name,
+                    "data_type": entry.data_type,
+                    "address": self.address(entry),
+                    "size": entry.size,
+                    "hex": hex::encode(self.bytes(entry)),
+                })
+            })
+            .collect();
+        json!({
+            "pid": std::process::id(),
+            "pointer_size": std::mem::size_of::<usize>(),
+            "values": values,
+        })
+    }
+
+    fn tick(&mut self) {
+        let entry = self.entries.iter().find(|e| e.name == "counter").unwrap();
+        let offset = entry.offset;
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&self.bytes(entry));
+        let next = i32::from_le_bytes(bytes).wrapping_add(1).to_le_bytes();
+        for (i, byte) in next.iter().enumerate() {
+            unsafe { std::ptr::write_volatile(self.memory.as_mut_ptr().add(offset + i), *byte) };
+        }
+    }
+}
+
+fn main() {
+    let mut target = Target::new();
+    let mut stdout = io::stdout();
+    let _ = writeln!(stdout, "{}", target.values());
+    let _ = stdout.flush();
+
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        let reply = match line.trim() {
+            "values" => target.values(),
+            "tick" => {
+                target.tick();
+                target.values()
+            }
+            "exit" => break,
+            "" => continue,
+            other => json!({ "error": format!("Unknown command: {}", other) }),
+        };
+        if writeln!(stdout, "{}", reply)
+            .and_then(|_| stdout.flush())
+            .is_err()
+        {
+            break;
+        }
+    }
+}
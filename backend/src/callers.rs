@@ -0,0 +1,287 @@
+// Which function owns a value: watch it for a short while and, on every hit, harvest the
+// return addresses from the hitting thread's stack while it is still stopped. Addresses
+// that keep showing up across hits, close to the top of the stack, are the likely owners.
+use crate::native_bridge;
+use crate::stacks;
+use crate::util;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const DEFAULT_SIZE: usize = 4;
+const DEFAULT_DURATION_MS: u64 = 2000;
+const MAX_DURATION_MS: u64 = 60_000;
+const DEFAULT_STACK_BYTES: usize = 4096;
+const MAX_STACK_BYTES: usize = 64 * 1024;
+const DEFAULT_MAX_HITS: usize = 256;
+const MAX_CANDIDATES: usize = 64;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct CallerRequest {
+    pub address: usize,
+    pub size: Option<usize>,
+    // "r", "w" or "a" as for /setwatchpoint; defaults to "a"
+    pub _type: Option<String>,
+    pub duration_ms: Option<u64>,
+    // How far above the stack pointer to look for return addresses
+    pub stack_bytes: Option<usize>,
+    // Stops early once this many hits were harvested
+    pub max_hits: Option<usize>,
+}
+
+#[derive(Default)]
+struct Candidate {
+    hits: u64,
+    min_depth: usize,
+    threads: BTreeSet<u64>,
+}
+
+struct Harvest {
+    pid: i32,
+    address: usize,
+    size: usize,
+    stack_bytes: usize,
+    max_hits: usize,
+    hits: usize,
+    mappings: Vec<(usize, usize, String)>,
+    accessors: BTreeMap<usize, u64>,
+    candidates: HashMap<usize, Candidate>,
+}
+
+lazy_static! {
+    static ref HARVEST: Mutex<Option<Harvest>> = Mutex::new(None);
+}
+
+fn parse_hex(value: &Value) -> Option<usize> {
+    usize::from_str_radix(value.as_str()?.trim_start_matches("0x"), 16).ok()
+}
+
+fn read(pid: i32, address: usize, size: usize) -> Option<Vec<u8>> {
+    let mut buffer = vec![0u8; size];
+    let nread =
+        native_bridge::read_process_memory(pid, address as *mut libc::c_void, size, &mut buffer)
+            .ok()?;
+    buffer.truncate(nread.max(0) as usize);
+    Some(buffer)
+}
+
+// Stack slots also hold stale code pointers and function pointers, so an address only
+// counts when the instruction right before it is a call.
+fn follows_call(pid: i32, return_address: usize, arm: bool) -> bool {
+    if arm {
+        return return_address >= 4
+            && read(pid, return_address - 4, 4)
+                .and_then(|bytes| bytes.try_into().ok())
+                .map(u32::from_le_bytes)
+                .is_some_and(|instruction| {
+                    // BL imm26, or BLR Xn
+                    instruction & 0xFC00_0000 == 0x9400_0000
+                        || instruction & 0xFFFF_FC1F == 0xD63F_0000
+                });
+    }
+    const LOOKBACK: usize = 7;
+    if return_address < LOOKBACK {
+        return false;
+    }
+    let Some(bytes) = read(pid, return_address - LOOKBACK, LOOKBACK) else {
+        return false;
+    };
+    if bytes.len() < LOOKBACK {
+        return false;
+    }
+    // call rel32, or an indirect call (FF /2) through a register or memory operand
+    bytes[LOOKBACK - 5] == 0xE8
+        || [2, 3, 4, 6, 7].iter().any(|&length| {
+            bytes[LOOKBACK - length] == 0xFF && (bytes[LOOKBACK - length + 1] >> 3) & 7 == 2
+        })
+}
+
+// Called for every exception reported by the debugger, while the hitting thread is still
+// stopped. Returns true when the hit belongs to the running harvest.
+pub fn record_hit(exception: &Value) -> bool {
+    let Some(memory) = parse_hex(&exception["memory"]) else {
+        return false;
+    };
+    let mut harvest = HARVEST.lock().unwrap();
+    let Some(harvest) = harvest.as_mut() else {
+        return false;
+    };
+    // Some debuggers report the start of the accessed doubleword rather than the address
+    if memory < harvest.address & !7 || memory >= harvest.address + harvest.size {
+        return false;
+    }
+    // Hits after the limit arrive until the watchpoint is removed and are dropped
+    if harvest.hits >= harvest.max_hits {
+        return true;
+    }
+    harvest.hits += 1;
+    if let Some(pc) = parse_hex(&exception["pc"]) {
+        *harvest.accessors.entry(pc).or_default() += 1;
+    }
+
+    let arm = exception.get("lr").is_some();
+    let Some(sp) = ["sp", "rsp", "esp"]
+        .iter()
+        .find_map(|name| parse_hex(&exception[*name]))
+    else {
+        return true;
+    };
+    let thread_id = parse_hex(&exception["thread_id"]).unwrap_or(0) as u64;
+    let pointer_size = util::pointer_size();
+
+    // The link register holds the innermost return address on ARM
+    let mut slots: Vec<usize> = Vec::new();
+    if let Some(lr) = parse_hex(&exception["lr"]) {
+        slots.push(lr);
+    }
+    if let Some(stack) = read(harvest.pid, sp, harvest.stack_bytes) {
+        slots.extend(
+            stack
+                .chunks_exact(pointer_size)
+                .filter_map(util::pointer_from_bytes),
+        );
+    }
+
+    let mut depth = 0;
+    let mut seen = BTreeSet::new();
+    for slot in slots {
+        if !harvest
+            .mappings
+            .iter()
+            .any(|&(start, end, _)| slot >= start && slot < end)
+            || !follows_call(harvest.pid, slot, arm)
+        {
+            continue;
+        }
+        // Recursion can put the same address on the stack repeatedly; count it once per hit
+        if seen.insert(slot) {
+            let candidate = harvest.candidates.entry(slot).or_insert(Candidate {
+                min_depth: depth,
+                ..Default::default()
+            });
+            candidate.hits += 1;
+            candidate.min_depth = candidate.min_depth.min(depth);
+            candidate.threads.insert(thread_id);
+        }
+        depth += 1;
+    }
+    true
+}
+
+fn annotate(mappings: &[(usize, usize, String)], address: usize, mut entry: Value) -> Value {
+    if let Some(location) = stacks::locate(mappings, address) {
+        entry["module"] = location["module"].clone();
+        entry["offset"] = location["offset"].clone();
+    }
+    entry
+}
+
+pub async fn run(pid: i32, request: CallerRequest) -> Result<Value, String> {
+    let size = request.size.unwrap_or(DEFAULT_SIZE);
+    let type_ = match request._type.as_deref().unwrap_or("a") {
+        "r" => 1,
+        "w" => 2,
+        "a" => 3,
+        other => return Err(format!("Unknown watch type '{}'", other)),
+    };
+    let duration = Duration::from_millis(
+        request
+            .duration_ms
+            .unwrap_or(DEFAULT_DURATION_MS)
+            .min(MAX_DURATION_MS),
+    );
+    let mappings = stacks::file_mappings(pid);
+    if mappings.is_empty() {
+        return Err("No file-backed mappings to resolve return addresses against".to_string());
+    }
+    {
+        let mut harvest = HARVEST.lock().unwrap();
+        if harvest.is_some() {
+            return Err("A caller harvest is already running".to_string());
+        }
+        *harvest = Some(Harvest {
+            pid,
+            address: request.address,
+            size,
+            stack_bytes: request
+                .stack_bytes
+                .unwrap_or(DEFAULT_STACK_BYTES)
+                .clamp(util::pointer_size(), MAX_STACK_BYTES),
+            max_hits: request.max_hits.unwrap_or(DEFAULT_MAX_HITS).max(1),
+            hits: 0,
+            mappings,
+            accessors: BTreeMap::new(),
+            candidates: HashMap::new(),
+        });
+    }
+
+    if let Err(e) = native_bridge::set_watchpoint(pid, request.address, size, type_, &[], None) {
+        HARVEST.lock().unwrap().take();
+        return Err(format!("Failed to set watchpoint: {}", e));
+    }
+
+    let started = Instant::now();
+    loop {
+        let done = HARVEST
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_none_or(|h| h.hits >= h.max_hits);
+        if done || started.elapsed() >= duration {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    if let Err(e) = native_bridge::remove_watchpoint(request.address) {
+        log::error!("Failed to remove caller harvest watch: {}", e);
+    }
+    let Some(harvest) = HARVEST.lock().unwrap().take() else {
+        return Err("The caller harvest was lost".to_string());
+    };
+
+    let mut candidates: Vec<(usize, Candidate)> = harvest.candidates.into_iter().collect();
+    candidates.sort_by(|(a_address, a), (b_address, b)| {
+        b.hits
+            .cmp(&a.hits)
+            .then(a.min_depth.cmp(&b.min_depth))
+            .then(a_address.cmp(b_address))
+    });
+    let truncated = candidates.len() > MAX_CANDIDATES;
+    candidates.truncate(MAX_CANDIDATES);
+
+    let hits = harvest.hits;
+    Ok(json!({
+        "address": harvest.address,
+        "size": harvest.size,
+        "hits": hits,
+        "elapsed_ms": started.elapsed().as_millis() as u64,
+        "accessors": harvest
+            .accessors
+            .iter()
+            .map(|(&pc, &count)| annotate(
+                &harvest.mappings,
+                pc,
+                json!({ "pc": format!("0x{:x}", pc), "count": count }),
+            ))
+            .collect::<Vec<_>>(),
+        "candidates": candidates
+            .iter()
+            .map(|(address, candidate)| annotate(
+                &harvest.mappings,
+                *address,
+                json!({
+                    "return_address": format!("0x{:x}", address),
+                    "hits": candidate.hits,
+                    "share": candidate.hits as f64 / hits.max(1) as f64,
+                    "min_depth": candidate.min_depth,
+                    "threads": candidate.threads,
+                }),
+            ))
+            .collect::<Vec<_>>(),
+        "truncated": truncated,
+    }))
+}
@@ -0,0 +1,361 @@
+// Conditions attached to watchpoints, evaluated on every hit before it reaches the exception
+// queue so hot addresses only report the accesses that matter. A condition compares
+// registers, the accessed address and the watched value, for example
+//
+//     value > 1000 && pc in "libgame.so"
+//     x0 == 0x10 || !(address == 0x1000)
+//
+// `value` is the watched memory as a signed little-endian integer of the watch size, read
+// when the hit is reported: after the write on x86, before it on ARM64.
+use crate::native_bridge;
+use lazy_static::lazy_static;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Number(i128),
+    Value,
+    // A register, or `address` for the accessed address
+    Name(String),
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, Comparison, Operand),
+    // Module ranges are resolved when the condition is set
+    InModule(Operand, Vec<(u64, u64)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i128),
+    Name(String),
+    Text(String),
+    Compare(Comparison),
+    And,
+    Or,
+    Not,
+    In,
+    Open,
+    Close,
+}
+
+struct Watch {
+    size: usize,
+    expr: Expr,
+}
+
+lazy_static! {
+    static ref CONDITIONS: Mutex<HashMap<usize, Watch>> = Mutex::new(HashMap::new());
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let (token, length) = match (c, next) {
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('=', Some('=')) => (Token::Compare(Comparison::Equal), 2),
+            ('!', Some('=')) => (Token::Compare(Comparison::NotEqual), 2),
+            ('<', Some('=')) => (Token::Compare(Comparison::LessEqual), 2),
+            ('>', Some('=')) => (Token::Compare(Comparison::GreaterEqual), 2),
+            ('<', _) => (Token::Compare(Comparison::Less), 1),
+            ('>', _) => (Token::Compare(Comparison::Greater), 1),
+            ('!', _) => (Token::Not, 1),
+            ('(', _) => (Token::Open, 1),
+            (')', _) => (Token::Close, 1),
+            ('"', _) => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&c| c == '"')
+                    .ok_or("Unterminated string")?;
+                let text: String = chars[i + 1..i + 1 + end].iter().collect();
+                (Token::Text(text), end + 2)
+            }
+            (c, _) if c.is_ascii_digit() || c == '-' => {
+                let length = chars[i + 1..]
+                    .iter()
+                    .position(|c| !c.is_ascii_alphanumeric())
+                    .unwrap_or(chars.len() - i - 1)
+                    + 1;
+                let text: String = chars[i..i + length].iter().collect();
+                let (negative, digits) = match text.strip_prefix('-') {
+                    Some(digits) => (true, digits),
+                    None => (false, text.as_str()),
+                };
+                let number = match digits.strip_prefix("0x") {
+                    Some(hex) => i128::from_str_radix(hex, 16),
+                    None => digits.parse(),
+                }
+                .map_err(|_| format!("Invalid number '{}'", text))?;
+                (
+                    Token::Number(if negative { -number } else { number }),
+                    length,
+                )
+            }
+            (c, _) if c.is_ascii_alphabetic() || c == '_' => {
+                let length = chars[i..]
+                    .iter()
+                    .position(|c| !c.is_ascii_alphanumeric() && *c != '_')
+                    .unwrap_or(chars.len() - i);
+                let name: String = chars[i..i + length].iter().collect();
+                let token = match name.as_str() {
+                    "in" => Token::In,
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Name(name.to_lowercase()),
+                };
+                (token, length)
+            }
+            _ => return Err(format!("Unexpected character '{}'", c)),
+        };
+        tokens.push(token);
+        i += length;
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    modules: &'a [Value],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.next();
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            Some(Token::Open) => {
+                self.next();
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err("Missing ')'".to_string()),
+                }
+            }
+            _ => self.comparison(),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Operand::Number(number)),
+            Some(Token::Name(name)) if name == "value" => Ok(Operand::Value),
+            Some(Token::Name(name)) => Ok(Operand::Name(name)),
+            other => Err(format!("Expected a value, found {:?}", other)),
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.operand()?;
+        match self.next() {
+            Some(Token::Compare(comparison)) => {
+                Ok(Expr::Compare(left, comparison, self.operand()?))
+            }
+            Some(Token::In) => match self.next() {
+                Some(Token::Text(name)) => Ok(Expr::InModule(left, self.module_ranges(&name)?)),
+                _ => Err("Expected a quoted module name after 'in'".to_string()),
+            },
+            other => Err(format!("Expected a comparison, found {:?}", other)),
+        }
+    }
+
+    // Every loaded module whose path ends with the name, so "libc.so" matches "/system/lib/libc.so"
+    fn module_ranges(&self, name: &str) -> Result<Vec<(u64, u64)>, String> {
+        let ranges: Vec<(u64, u64)> = self
+            .modules
+            .iter()
+            .filter(|module| {
+                module["modulename"]
+                    .as_str()
+                    .is_some_and(|path| path == name || path.ends_with(&format!("/{}", name)))
+            })
+            .filter_map(|module| {
+                let base = module["base"].as_u64()?;
+                Some((base, base + module["size"].as_u64()?))
+            })
+            .collect();
+        if ranges.is_empty() {
+            return Err(format!("Module '{}' is not loaded", name));
+        }
+        Ok(ranges)
+    }
+}
+
+fn parse(source: &str, modules: &[Value]) -> Result<Expr, String> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+        modules,
+    };
+    let expr = parser.or()?;
+    if let Some(token) = parser.peek() {
+        return Err(format!("Unexpected {:?}", token));
+    }
+    Ok(expr)
+}
+
+fn parse_hex(value: &Value) -> Option<u64> {
+    u64::from_str_radix(value.as_str()?.trim_start_matches("0x"), 16).ok()
+}
+
+struct Hit<'a> {
+    registers: &'a Value,
+    value: Option<i128>,
+}
+
+impl Hit<'_> {
+    fn resolve(&self, operand: &Operand) -> Option<i128> {
+        match operand {
+            Operand::Number(number) => Some(*number),
+            Operand::Value => self.value,
+            Operand::Name(name) if name == "address" => {
+                parse_hex(&self.registers["memory"]).map(i128::from)
+            }
+            Operand::Name(name) => parse_hex(&self.registers[name.as_str()]).map(i128::from),
+        }
+    }
+
+    // Unknown registers and unreadable values make the comparison false
+    fn evaluate(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Or(left, right) => self.evaluate(left) || self.evaluate(right),
+            Expr::And(left, right) => self.evaluate(left) && self.evaluate(right),
+            Expr::Not(inner) => !self.evaluate(inner),
+            Expr::Compare(left, comparison, right) => {
+                let (Some(left), Some(right)) = (self.resolve(left), self.resolve(right)) else {
+                    return false;
+                };
+                match comparison {
+                    Comparison::Equal => left == right,
+                    Comparison::NotEqual => left != right,
+                    Comparison::Less => left < right,
+                    Comparison::LessEqual => left <= right,
+                    Comparison::Greater => left > right,
+                    Comparison::GreaterEqual => left >= right,
+                }
+            }
+            Expr::InModule(operand, ranges) => self.resolve(operand).is_some_and(|address| {
+                ranges
+                    .iter()
+                    .any(|&(start, end)| address >= start as i128 && address < end as i128)
+            }),
+        }
+    }
+}
+
+fn read_value(pid: i32, address: usize, size: usize) -> Option<i128> {
+    let size = size.clamp(1, 8);
+    let mut buffer = [0u8; 8];
+    let nread = native_bridge::read_process_memory(
+        pid,
+        address as *mut libc::c_void,
+        size,
+        &mut buffer[..size],
+    )
+    .ok()?;
+    if nread as usize != size {
+        return None;
+    }
+    // Sign-extend from the watch size
+    let shift = 64 - size as u32 * 8;
+    Some(((i64::from_le_bytes(buffer) << shift) >> shift) as i128)
+}
+
+// Checks and stores the condition of the watchpoint at `address`; `None` removes it
+pub fn set(pid: i32, address: usize, size: usize, condition: Option<&str>) -> Result<(), String> {
+    let Some(condition) = condition.filter(|c| !c.trim().is_empty()) else {
+        remove(address);
+        return Ok(());
+    };
+    // Modules are only enumerated when the condition names one
+    let modules = if tokenize(condition)?.contains(&Token::In) {
+        native_bridge::enum_modules(pid)?
+    } else {
+        Vec::new()
+    };
+    let expr = parse(condition, &modules).map_err(|e| format!("Invalid condition: {}", e))?;
+    CONDITIONS
+        .lock()
+        .unwrap()
+        .insert(address, Watch { size, expr });
+    Ok(())
+}
+
+pub fn remove(address: usize) {
+    CONDITIONS.lock().unwrap().remove(&address);
+}
+
+// Called for every reported exception. Returns false when the hit belongs to a watchpoint
+// whose condition does not hold, so the event is dropped.
+pub fn passes(pid: i32, exception: &Value) -> bool {
+    let Some(accessed) = parse_hex(&exception["memory"]) else {
+        return true;
+    };
+    let conditions = CONDITIONS.lock().unwrap();
+    let Some((&address, watch)) = conditions.iter().find(|(&address, watch)| {
+        accessed >= address as u64 && accessed < (address + watch.size.max(1)) as u64
+    }) else {
+        return true;
+    };
+    let hit = Hit {
+        registers: exception,
+        value: read_value(pid, address, watch.size),
+    };
+    hit.evaluate(&watch.expr)
+}
@@ -0,0 +1,88 @@
+#include "guarded_copy.h"
+
+#include <errno.h>
+#include <setjmp.h>
+#include <signal.h>
+#include <string.h>
+#include <unistd.h>
+
+#include <algorithm>
+#include <mutex>
+
+static thread_local sigjmp_buf *active_guard = nullptr;
+static struct sigaction previous_segv;
+static struct sigaction previous_bus;
+
+static void chain(int signal, siginfo_t *info, void *context)
+{
+    struct sigaction *previous = signal == SIGSEGV ? &previous_segv : &previous_bus;
+    if (previous->sa_flags & SA_SIGINFO)
+    {
+        previous->sa_sigaction(signal, info, context);
+    }
+    else if (previous->sa_handler == SIG_DFL)
+    {
+        // Returning re-executes the faulting access, which now gets the default action
+        sigaction(signal, previous, nullptr);
+    }
+    else if (previous->sa_handler != SIG_IGN)
+    {
+        previous->sa_handler(signal);
+    }
+}
+
+static void fault_handler(int signal, siginfo_t *info, void *context)
+{
+    if (active_guard != nullptr)
+    {
+        siglongjmp(*active_guard, 1);
+    }
+    chain(signal, info, context);
+}
+
+static void install_handlers()
+{
+    struct sigaction action;
+    memset(&action, 0, sizeof(action));
+    action.sa_sigaction = fault_handler;
+    action.sa_flags = SA_SIGINFO | SA_NODEFER;
+    sigemptyset(&action.sa_mask);
+    sigaction(SIGSEGV, &action, &previous_segv);
+    sigaction(SIGBUS, &action, &previous_bus);
+}
+
+static size_t to_page_end(uintptr_t address, size_t page_size)
+{
+    return page_size - (address & (page_size - 1));
+}
+
+ssize_t guarded_copy_native(void *destination, const void *source, size_t size)
+{
+    static std::once_flag installed;
+    std::call_once(installed, install_handlers);
+    static const size_t page_size = static_cast<size_t>(sysconf(_SC_PAGESIZE));
+
+    volatile size_t copied = 0;
+    sigjmp_buf guard;
+    if (sigsetjmp(guard, 1) == 0)
+    {
+        active_guard = &guard;
+        while (copied < size)
+        {
+            uintptr_t from = reinterpret_cast<uintptr_t>(source) + copied;
+            uintptr_t to = reinterpret_cast<uintptr_t>(destination) + copied;
+            // One page at a time, so a fault only loses the page it happened in
+            size_t chunk = std::min({size - copied, to_page_end(from, page_size),
+                                     to_page_end(to, page_size)});
+            memcpy(reinterpret_cast<void *>(to), reinterpret_cast<const void *>(from), chunk);
+            copied = copied + chunk;
+        }
+    }
+    active_guard = nullptr;
+
+    if (copied == 0 && size > 0)
+    {
+        return -EFAULT;
+    }
+    return static_cast<ssize_t>(copied);
+}
@@ -0,0 +1,11 @@
+#ifndef GUARDED_COPY_H
+#define GUARDED_COPY_H
+
+#include <stddef.h>
+#include <sys/types.h>
+
+// Copies memory of the current process, stopping at the first page that faults. Returns the
+// number of bytes copied, or -EFAULT when not even the first byte was accessible.
+extern "C" ssize_t guarded_copy_native(void *destination, const void *source, size_t size);
+
+#endif
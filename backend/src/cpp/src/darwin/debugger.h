@@ -9,10 +9,13 @@
 #include <mach/vm_map.h>
 #include <unistd.h>
 
+#include <chrono>
 #include <cstdint>
 #include <cstring>
 #include <iostream>
 #include <map>
+#include <mutex>
+#include <set>
 #include <string>
 #include <thread>
 #include <vector>
@@ -29,6 +32,41 @@ enum class WatchpointType
     READWRITE = 3
 };
 
+// Threads a watchpoint is armed on: listed thread ids plus threads whose name contains the
+// filter. An empty scope keeps the default of arming the first thread only.
+struct ThreadScope
+{
+    std::vector<uint64_t> thread_ids;
+    std::string name_filter;
+
+    bool is_empty() const
+    {
+        return thread_ids.empty() && name_filter.empty();
+    }
+
+    bool matches(uint64_t thread_id, const std::string& name) const
+    {
+        for (uint64_t id : thread_ids)
+        {
+            if (id == thread_id)
+            {
+                return true;
+            }
+        }
+        return !name_filter.empty() && name.find(name_filter) != std::string::npos;
+    }
+};
+
+// Applied to the stopped thread when a watchpoint or breakpoint at the address is hit
+struct HitAction
+{
+    // -1 leaves the registers alone
+    int register_index = -1;
+    uint64_t value = 0;
+    // Bytes the pc moves forward, skipping the instruction
+    size_t skip_length = 0;
+};
+
 class Debugger
 {
 public:
@@ -36,8 +74,16 @@ public:
     ~Debugger();
     bool initialize();
     void run();
-    kern_return_t set_watchpoint(mach_vm_address_t address, int size, WatchpointType type);
+    kern_return_t set_watchpoint(mach_vm_address_t address, int size, WatchpointType type,
+                                 const ThreadScope& scope);
     kern_return_t remove_watchpoint(mach_vm_address_t address);
+    void refresh_scoped_watchpoints();
+    kern_return_t set_hit_action(mach_vm_address_t address, const char* register_name,
+                                 uint64_t value, size_t skip_length);
+    void remove_hit_action(mach_vm_address_t address);
+    kern_return_t set_page_watch(mach_vm_address_t address, mach_vm_size_t size,
+                                 WatchpointType type);
+    kern_return_t remove_page_watch(mach_vm_address_t address);
     kern_return_t set_breakpoint(mach_vm_address_t address, int hit_count);
     kern_return_t remove_breakpoint(mach_vm_address_t address);
     kern_return_t handle_exception(mach_port_t exception_port, mach_port_t thread, mach_port_t task,
@@ -53,16 +99,38 @@ private:
     std::vector<bool> watchpoint_used;
     std::vector<mach_vm_address_t> watchpoint_addresses;
     std::vector<int> watchpoint_sizes;
+    std::vector<WatchpointType> watchpoint_types;
+    std::vector<ThreadScope> watchpoint_scopes;
+    // Thread ids each scoped watchpoint is armed on
+    std::vector<std::set<uint64_t>> watchpoint_threads;
+    std::mutex watchpoint_mutex_;
+    std::map<mach_vm_address_t, HitAction> hit_actions;
+    std::mutex hit_action_mutex_;
     std::vector<bool> breakpoint_used;
     std::vector<mach_vm_address_t> breakpoint_addresses;
     std::vector<int> breakpoint_hit_counts;
     std::vector<int> breakpoint_target_counts;
 
+    // Page-protection watch covering an arbitrary sized buffer
+    struct PageWatch
+    {
+        mach_vm_address_t address;
+        mach_vm_size_t size;
+        WatchpointType type;
+        mach_vm_address_t page_start;
+        mach_vm_size_t page_length;
+        vm_prot_t original_protection;
+        vm_prot_t watch_protection;
+    };
+    std::vector<PageWatch> page_watches;
+    mach_vm_address_t stepping_page_watch_address = 0;
+
     enum class SingleStepMode
     {
         None,
         Watchpoint,
-        Breakpoint
+        Breakpoint,
+        PageWatch
     };
 
     SingleStepMode single_step_mode = SingleStepMode::None;
@@ -88,13 +156,25 @@ private:
                                         arm_thread_state64_t& thread_state,
                                         arm_exception_state64_t& exception_state,
                                         int breakpoint_index);
+    kern_return_t handle_page_fault(mach_port_t thread, arm_debug_state64_t& debug_state,
+                                    arm_exception_state64_t& exception_state,
+                                    std::vector<std::map<std::string, uint64_t>>& map_vector);
+    kern_return_t complete_page_watch_single_step(mach_port_t thread,
+                                                  arm_debug_state64_t& debug_state);
     int find_free_watchpoint();
+    int find_page_watch_index(mach_vm_address_t address);
+    int find_page_watch_for_fault(mach_vm_address_t fault_address);
     int find_watchpoint_index(mach_vm_address_t address);
     int find_free_breakpoint();
     int find_breakpoint_index(mach_vm_address_t address);
     int get_available_watchpoints(mach_port_t thread);
     kern_return_t set_watchpoint_on_thread(mach_port_t thread, mach_vm_address_t address, int size,
                                            WatchpointType type, int index);
+    void apply_hit_action(mach_port_t thread, arm_thread_state64_t& thread_state,
+                          mach_vm_address_t address);
+    static int register_index(const std::string& name);
+    static uint64_t get_thread_id(mach_port_t thread);
+    static std::string get_thread_name(mach_port_t thread);
     static std::string kern_return_to_string(kern_return_t kr);
 };
 
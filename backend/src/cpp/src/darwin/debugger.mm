@@ -26,6 +26,9 @@ Debugger::Debugger(pid_t pid)
       watchpoint_used(MAX_WATCHPOINTS, false),
       watchpoint_addresses(MAX_WATCHPOINTS, 0),
       watchpoint_sizes(MAX_WATCHPOINTS, 0),
+      watchpoint_types(MAX_WATCHPOINTS, WatchpointType::READ),
+      watchpoint_scopes(MAX_WATCHPOINTS),
+      watchpoint_threads(MAX_WATCHPOINTS),
       breakpoint_used(MAX_BREAKPOINTS, false),
       breakpoint_addresses(MAX_BREAKPOINTS, 0),
       breakpoint_hit_counts(MAX_BREAKPOINTS, 0),
@@ -49,7 +52,17 @@ bool Debugger::initialize()
 {
     kern_return_t kr;
 
-    kr = task_for_pid(mach_task_self(), pid_, &task_port_);
+    if (pid_ == getpid())
+    {
+        // In-process (jailed) mode has no task_for_pid; take an extra reference to the own
+        // task port so the destructor's deallocation stays balanced
+        task_port_ = mach_task_self();
+        kr = mach_port_mod_refs(mach_task_self(), task_port_, MACH_PORT_RIGHT_SEND, 1);
+    }
+    else
+    {
+        kr = task_for_pid(mach_task_self(), pid_, &task_port_);
+    }
     if (kr != KERN_SUCCESS)
     {
         debug_log(LOG_ERROR, "task_for_pid failed: %s", kern_return_to_string(kr).c_str());
@@ -72,7 +85,12 @@ bool Debugger::initialize()
         return false;
     }
 
-    kr = task_set_exception_ports(task_port_, EXC_MASK_ALL, exception_port_, EXCEPTION_DEFAULT,
+    // The minimal mask leaves every exception type the debugger does not handle with the
+    // target's own handlers, which is what most exception port checks look at
+    exception_mask_t mask = (get_stealth_options() & STEALTH_MINIMAL_EXCEPTION_PORTS)
+                                ? (EXC_MASK_BREAKPOINT | EXC_MASK_BAD_ACCESS)
+                                : EXC_MASK_ALL;
+    kr = task_set_exception_ports(task_port_, mask, exception_port_, EXCEPTION_DEFAULT,
                                   ARM_THREAD_STATE64);
     if (kr != KERN_SUCCESS)
     {
@@ -99,8 +117,10 @@ void Debugger::run()
     }
 }
 
-kern_return_t Debugger::set_watchpoint(mach_vm_address_t address, int size, WatchpointType type)
+kern_return_t Debugger::set_watchpoint(mach_vm_address_t address, int size, WatchpointType type,
+                                       const ThreadScope& scope)
 {
+    std::lock_guard<std::mutex> lock(watchpoint_mutex_);
     thread_act_array_t thread_list;
     mach_msg_type_number_t thread_count;
     kern_return_t kr;
@@ -119,13 +139,40 @@ kern_return_t Debugger::set_watchpoint(mach_vm_address_t address, int size, Watc
         return KERN_NO_SPACE;
     }
 
-    kr = set_watchpoint_on_thread(thread_list[0], address, size, type, index);
+    std::set<uint64_t> armed;
+    if (scope.is_empty())
+    {
+        kr = set_watchpoint_on_thread(thread_list[0], address, size, type, index);
+    }
+    else
+    {
+        // Only threads in the scope; the others never see the watchpoint
+        for (mach_msg_type_number_t i = 0; i < thread_count; i++)
+        {
+            uint64_t thread_id = get_thread_id(thread_list[i]);
+            if (scope.matches(thread_id, get_thread_name(thread_list[i])) &&
+                set_watchpoint_on_thread(thread_list[i], address, size, type, index) ==
+                    KERN_SUCCESS)
+            {
+                armed.insert(thread_id);
+            }
+        }
+        // A name filter may match threads that do not exist yet
+        kr = armed.empty() && scope.name_filter.empty() ? KERN_INVALID_ARGUMENT : KERN_SUCCESS;
+    }
     if (kr == KERN_SUCCESS)
     {
         watchpoint_used[index] = true;
         watchpoint_addresses[index] = address;
         watchpoint_sizes[index] = size;
+        watchpoint_types[index] = type;
+        watchpoint_scopes[index] = scope;
+        watchpoint_threads[index] = armed;
         debug_log(LOG_INFO, "Watchpoint set successfully at address 0x%llx", address);
+        if (!scope.is_empty())
+        {
+            debug_log(LOG_INFO, "Watchpoint armed on %zu threads in scope", armed.size());
+        }
     }
 
     for (mach_msg_type_number_t i = 0; i < thread_count; i++)
@@ -139,6 +186,12 @@ kern_return_t Debugger::set_watchpoint(mach_vm_address_t address, int size, Watc
 
 kern_return_t Debugger::remove_watchpoint(mach_vm_address_t address)
 {
+    if (find_page_watch_index(address) != -1)
+    {
+        return remove_page_watch(address);
+    }
+
+    std::lock_guard<std::mutex> lock(watchpoint_mutex_);
     thread_act_array_t thread_list;
     mach_msg_type_number_t thread_count;
     kern_return_t kr;
@@ -157,22 +210,37 @@ kern_return_t Debugger::remove_watchpoint(mach_vm_address_t address)
         return KERN_INVALID_ARGUMENT;
     }
 
-    arm_debug_state64_t debug_state = {0};
-    mach_msg_type_number_t count = ARM_DEBUG_STATE64_COUNT;
-    kr = thread_get_state(thread_list[0], ARM_DEBUG_STATE64, (thread_state_t)&debug_state, &count);
-    if (kr != KERN_SUCCESS)
+    // Scoped watchpoints may be armed on any thread, so the slot is cleared everywhere
+    bool scoped = !watchpoint_scopes[index].is_empty();
+    for (mach_msg_type_number_t i = 0; i < (scoped ? thread_count : 1); i++)
     {
-        debug_log(LOG_ERROR, "Failed to get debug state: %s", kern_return_to_string(kr).c_str());
-        return kr;
-    }
+        arm_debug_state64_t debug_state = {0};
+        mach_msg_type_number_t count = ARM_DEBUG_STATE64_COUNT;
+        kr = thread_get_state(thread_list[i], ARM_DEBUG_STATE64, (thread_state_t)&debug_state,
+                              &count);
+        if (kr != KERN_SUCCESS)
+        {
+            debug_log(LOG_ERROR, "Failed to get debug state: %s",
+                      kern_return_to_string(kr).c_str());
+            break;
+        }
 
-    debug_state.__wcr[index] = 0;  // Disable the watchpoint
-    kr = thread_set_state(thread_list[0], ARM_DEBUG_STATE64, (thread_state_t)&debug_state, count);
+        debug_state.__wcr[index] = 0;  // Disable the watchpoint
+        kr = thread_set_state(thread_list[i], ARM_DEBUG_STATE64, (thread_state_t)&debug_state,
+                              count);
+        if (kr != KERN_SUCCESS)
+        {
+            break;
+        }
+    }
     if (kr == KERN_SUCCESS)
     {
         watchpoint_used[index] = false;
         watchpoint_addresses[index] = 0;
         watchpoint_sizes[index] = 0;
+        watchpoint_scopes[index] = ThreadScope();
+        watchpoint_threads[index].clear();
+        remove_hit_action(address);
         debug_log(LOG_INFO, "Watchpoint removed successfully from address 0x%llx", address);
     }
     else
@@ -189,6 +257,259 @@ kern_return_t Debugger::remove_watchpoint(mach_vm_address_t address)
     return kr;
 }
 
+// Arms scoped watchpoints on threads that started, or were renamed into the filter, since
+// the watchpoint was set. Runs periodically because Mach reports no thread creation.
+void Debugger::refresh_scoped_watchpoints()
+{
+    std::lock_guard<std::mutex> lock(watchpoint_mutex_);
+    bool any_filter = false;
+    for (int i = 0; i < MAX_WATCHPOINTS; i++)
+    {
+        any_filter = any_filter || (watchpoint_used[i] && !watchpoint_scopes[i].name_filter.empty());
+    }
+    if (!any_filter)
+    {
+        return;
+    }
+
+    thread_act_array_t thread_list;
+    mach_msg_type_number_t thread_count;
+    if (task_threads(task_port_, &thread_list, &thread_count) != KERN_SUCCESS)
+    {
+        return;
+    }
+    for (mach_msg_type_number_t t = 0; t < thread_count; t++)
+    {
+        uint64_t thread_id = get_thread_id(thread_list[t]);
+        std::string name;
+        bool named = false;
+        for (int i = 0; i < MAX_WATCHPOINTS; i++)
+        {
+            if (!watchpoint_used[i] || watchpoint_scopes[i].name_filter.empty() ||
+                watchpoint_threads[i].count(thread_id) > 0)
+            {
+                continue;
+            }
+            if (!named)
+            {
+                name = get_thread_name(thread_list[t]);
+                named = true;
+            }
+            if (watchpoint_scopes[i].matches(thread_id, name) &&
+                set_watchpoint_on_thread(thread_list[t], watchpoint_addresses[i],
+                                         watchpoint_sizes[i], watchpoint_types[i],
+                                         i) == KERN_SUCCESS)
+            {
+                watchpoint_threads[i].insert(thread_id);
+                debug_log(LOG_INFO, "Watchpoint 0x%llx armed on new thread %llu (%s)",
+                          watchpoint_addresses[i], thread_id, name.c_str());
+            }
+        }
+    }
+    for (mach_msg_type_number_t t = 0; t < thread_count; t++)
+    {
+        mach_port_deallocate(mach_task_self(), thread_list[t]);
+    }
+    vm_deallocate(mach_task_self(), (vm_address_t)thread_list, thread_count * sizeof(thread_act_t));
+}
+
+kern_return_t Debugger::set_hit_action(mach_vm_address_t address, const char* register_name,
+                                       uint64_t value, size_t skip_length)
+{
+    HitAction action;
+    if (register_name != nullptr)
+    {
+        action.register_index = register_index(register_name);
+        if (action.register_index < 0)
+        {
+            debug_log(LOG_ERROR, "Unknown register: %s", register_name);
+            return KERN_INVALID_ARGUMENT;
+        }
+    }
+    action.value = value;
+    // ARM64 instructions are always four bytes
+    action.skip_length = skip_length > 0 ? 4 : 0;
+
+    std::lock_guard<std::mutex> lock(hit_action_mutex_);
+    hit_actions[address] = action;
+    return KERN_SUCCESS;
+}
+
+void Debugger::remove_hit_action(mach_vm_address_t address)
+{
+    std::lock_guard<std::mutex> lock(hit_action_mutex_);
+    hit_actions.erase(address);
+}
+
+// Runs the action of a hit before the thread resumes: overwrites a register and/or moves
+// the pc past the instruction so it never executes
+void Debugger::apply_hit_action(mach_port_t thread, arm_thread_state64_t& thread_state,
+                                mach_vm_address_t address)
+{
+    HitAction action;
+    {
+        std::lock_guard<std::mutex> lock(hit_action_mutex_);
+        auto it = hit_actions.find(address);
+        if (it == hit_actions.end())
+        {
+            return;
+        }
+        action = it->second;
+    }
+
+    if (action.register_index >= 0 && action.register_index < 29)
+    {
+        thread_state.__x[action.register_index] = action.value;
+    }
+    else if (action.register_index == 29)
+    {
+        thread_state.__fp = action.value;
+    }
+    else if (action.register_index == 30)
+    {
+        thread_state.__lr = action.value;
+    }
+    else if (action.register_index == 31)
+    {
+        thread_state.__sp = action.value;
+    }
+    else if (action.register_index == 32)
+    {
+        thread_state.__pc = action.value;
+    }
+    thread_state.__pc += action.skip_length;
+
+    kern_return_t kr = thread_set_state(thread, ARM_THREAD_STATE64, (thread_state_t)&thread_state,
+                                        ARM_THREAD_STATE64_COUNT);
+    if (kr != KERN_SUCCESS)
+    {
+        debug_log(LOG_ERROR, "Failed to apply hit action: %s", kern_return_to_string(kr).c_str());
+    }
+}
+
+// x0-x28, fp (x29), lr (x30), sp and pc as indices 0-32, or -1
+int Debugger::register_index(const std::string& name)
+{
+    if (name == "fp" || name == "x29") return 29;
+    if (name == "lr" || name == "x30") return 30;
+    if (name == "sp") return 31;
+    if (name == "pc") return 32;
+    if (name.size() >= 2 && name.size() <= 3 && name[0] == 'x' &&
+        name.find_first_not_of("0123456789", 1) == std::string::npos)
+    {
+        int index = std::stoi(name.substr(1));
+        return index < 29 ? index : -1;
+    }
+    return -1;
+}
+
+uint64_t Debugger::get_thread_id(mach_port_t thread)
+{
+    thread_identifier_info_data_t info;
+    mach_msg_type_number_t count = THREAD_IDENTIFIER_INFO_COUNT;
+    if (thread_info(thread, THREAD_IDENTIFIER_INFO, (thread_info_t)&info, &count) != KERN_SUCCESS)
+    {
+        return 0;
+    }
+    return info.thread_id;
+}
+
+std::string Debugger::get_thread_name(mach_port_t thread)
+{
+    thread_extended_info_data_t info;
+    mach_msg_type_number_t count = THREAD_EXTENDED_INFO_COUNT;
+    if (thread_info(thread, THREAD_EXTENDED_INFO, (thread_info_t)&info, &count) != KERN_SUCCESS)
+    {
+        return "";
+    }
+    return std::string(info.pth_name, strnlen(info.pth_name, sizeof(info.pth_name)));
+}
+
+kern_return_t Debugger::set_page_watch(mach_vm_address_t address, mach_vm_size_t size,
+                                       WatchpointType type)
+{
+    if (size == 0)
+    {
+        return KERN_INVALID_ARGUMENT;
+    }
+
+    mach_vm_address_t page_start = trunc_page(address);
+    mach_vm_size_t page_length = round_page(address + size) - page_start;
+
+    for (const auto& watch : page_watches)
+    {
+        if (page_start < watch.page_start + watch.page_length &&
+            watch.page_start < page_start + page_length)
+        {
+            debug_log(LOG_ERROR, "Page watch at 0x%llx overlaps an existing watch at 0x%llx",
+                      address, watch.address);
+            return KERN_INVALID_ARGUMENT;
+        }
+    }
+
+    mach_vm_address_t region_address = page_start;
+    mach_vm_size_t region_size = 0;
+    vm_region_basic_info_data_64_t info;
+    mach_msg_type_number_t info_count = VM_REGION_BASIC_INFO_COUNT_64;
+    mach_port_t object_name;
+    kern_return_t kr = mach_vm_region(task_port_, &region_address, &region_size,
+                                      VM_REGION_BASIC_INFO_64, (vm_region_info_t)&info,
+                                      &info_count, &object_name);
+    if (kr != KERN_SUCCESS || region_address > page_start)
+    {
+        debug_log(LOG_ERROR, "Failed to query protection at 0x%llx: %s", page_start,
+                  kern_return_to_string(kr).c_str());
+        return kr != KERN_SUCCESS ? kr : KERN_INVALID_ADDRESS;
+    }
+
+    // Writes are caught by dropping write access, reads need every access removed
+    vm_prot_t watch_protection = type == WatchpointType::WRITE
+                                     ? (info.protection & ~VM_PROT_WRITE)
+                                     : VM_PROT_NONE;
+
+    kr = mach_vm_protect(task_port_, page_start, page_length, false, watch_protection);
+    if (kr != KERN_SUCCESS)
+    {
+        debug_log(LOG_ERROR, "Failed to protect pages for watch: %s",
+                  kern_return_to_string(kr).c_str());
+        return kr;
+    }
+
+    page_watches.push_back({address, size, type, page_start, page_length, info.protection,
+                            watch_protection});
+    debug_log(LOG_INFO, "Page watch set at 0x%llx (%llu bytes, %llu bytes of pages)", address,
+              size, page_length);
+    return KERN_SUCCESS;
+}
+
+kern_return_t Debugger::remove_page_watch(mach_vm_address_t address)
+{
+    int index = find_page_watch_index(address);
+    if (index == -1)
+    {
+        debug_log(LOG_ERROR, "Page watch not found for address: 0x%llx", address);
+        return KERN_INVALID_ARGUMENT;
+    }
+
+    PageWatch watch = page_watches[index];
+    kern_return_t kr = mach_vm_protect(task_port_, watch.page_start, watch.page_length, false,
+                                       watch.original_protection);
+    if (kr != KERN_SUCCESS)
+    {
+        debug_log(LOG_ERROR, "Failed to restore page protection: %s",
+                  kern_return_to_string(kr).c_str());
+        return kr;
+    }
+
+    page_watches.erase(page_watches.begin() + index);
+    if (stepping_page_watch_address == address)
+    {
+        stepping_page_watch_address = 0;
+    }
+    debug_log(LOG_INFO, "Page watch removed successfully from address 0x%llx", address);
+    return KERN_SUCCESS;
+}
+
 kern_return_t Debugger::set_breakpoint(mach_vm_address_t address, int hit_count)
 {
     thread_act_array_t thread_list;
@@ -282,6 +603,7 @@ kern_return_t Debugger::remove_breakpoint(mach_vm_address_t address)
         breakpoint_addresses[index] = 0;
         breakpoint_hit_counts[index] = 0;
         breakpoint_target_counts[index] = 0;
+        remove_hit_action(address);
         debug_log(LOG_INFO, "Breakpoint removed successfully from address 0x%llx", address);
     }
     else
@@ -303,7 +625,7 @@ kern_return_t Debugger::handle_exception(mach_port_t exception_port, mach_port_t
                                          mach_exception_data_t code,
                                          mach_msg_type_number_t code_count)
 {
-    if (exception != EXC_BREAKPOINT && exception != EXC_GUARD)
+    if (exception != EXC_BREAKPOINT && exception != EXC_GUARD && exception != EXC_BAD_ACCESS)
     {
         return KERN_FAILURE;
     }
@@ -348,6 +670,17 @@ kern_return_t Debugger::handle_exception(mach_port_t exception_port, mach_port_t
     map_vector.push_back({{"sp", thread_state.__sp}});
     map_vector.push_back({{"pc", thread_state.__pc}});
     map_vector.push_back({{"cpsr", thread_state.__cpsr}});
+    map_vector.push_back({{"thread_id", get_thread_id(thread)}});
+
+    if (exception == EXC_BAD_ACCESS)
+    {
+        return handle_page_fault(thread, debug_state, exception_state, map_vector);
+    }
+
+    if (single_step_mode == SingleStepMode::PageWatch)
+    {
+        return complete_page_watch_single_step(thread, debug_state);
+    }
 
     if (single_step_mode != SingleStepMode::None)
     {
@@ -370,7 +703,10 @@ kern_return_t Debugger::handle_exception(mach_port_t exception_port, mach_port_t
                 far < watchpoint_addresses[i] + watchpoint_sizes[i])
             {
                 std::string register_json = map_vector_to_json_string(map_vector);
-                send_register_json(register_json.c_str(), pid_);
+                if (send_register_json(register_json.c_str(), pid_))
+                {
+                    apply_hit_action(thread, thread_state, watchpoint_addresses[i]);
+                }
                 return handle_watchpoint_hit(thread, debug_state, thread_state, exception_state, i);
             }
         }
@@ -382,7 +718,10 @@ kern_return_t Debugger::handle_exception(mach_port_t exception_port, mach_port_t
             if (breakpoint_used[i] && thread_state.__pc == breakpoint_addresses[i])
             {
                 std::string register_json = map_vector_to_json_string(map_vector);
-                send_register_json(register_json.c_str(), pid_);
+                if (send_register_json(register_json.c_str(), pid_))
+                {
+                    apply_hit_action(thread, thread_state, breakpoint_addresses[i]);
+                }
                 // onetime breakpoint
                 debug_state.__bcr[i] = 0;  // Disable the breakpoint
                 return handle_breakpoint_hit(thread, debug_state, thread_state, exception_state, i);
@@ -486,6 +825,87 @@ kern_return_t Debugger::handle_watchpoint_hit(mach_port_t thread, arm_debug_stat
     return KERN_SUCCESS;
 }
 
+kern_return_t Debugger::handle_page_fault(
+    mach_port_t thread, arm_debug_state64_t& debug_state, arm_exception_state64_t& exception_state,
+    std::vector<std::map<std::string, uint64_t>>& map_vector)
+{
+    uint64_t far = exception_state.__far;
+    int index = find_page_watch_for_fault(far);
+    if (index == -1)
+    {
+        // Not one of ours, let the process crash as it normally would
+        return KERN_FAILURE;
+    }
+
+    PageWatch& watch = page_watches[index];
+    bool is_write = (exception_state.__esr >> 6) & 1;  // WnR bit of the data abort ISS
+    bool wanted = watch.type == WatchpointType::READWRITE ||
+                  (watch.type == WatchpointType::WRITE && is_write) ||
+                  (watch.type == WatchpointType::READ && !is_write);
+
+    // Accesses elsewhere on the watched pages are stepped over silently
+    if (wanted && far >= watch.address && far < watch.address + watch.size)
+    {
+        map_vector.push_back({{"memory", far}});
+        map_vector.push_back({{"write", is_write ? 1ULL : 0ULL}});
+        std::string register_json = map_vector_to_json_string(map_vector);
+        send_register_json(register_json.c_str(), pid_);
+    }
+
+    // Let the faulting instruction through, the protection is re-applied after one step
+    kern_return_t kr = mach_vm_protect(task_port_, watch.page_start, watch.page_length, false,
+                                       watch.original_protection);
+    if (kr != KERN_SUCCESS)
+    {
+        debug_log(LOG_ERROR, "Failed to lift page protection: %s", mach_error_string(kr));
+        return kr;
+    }
+
+    debug_state.__mdscr_el1 |= 1ULL;
+    kr = thread_set_state(thread, ARM_DEBUG_STATE64, (thread_state_t)&debug_state,
+                          ARM_DEBUG_STATE64_COUNT);
+    if (kr != KERN_SUCCESS)
+    {
+        debug_log(LOG_ERROR, "Failed to set single-step mode: %s", mach_error_string(kr));
+        return kr;
+    }
+
+    stepping_page_watch_address = watch.address;
+    single_step_mode = SingleStepMode::PageWatch;
+    return KERN_SUCCESS;
+}
+
+kern_return_t Debugger::complete_page_watch_single_step(mach_port_t thread,
+                                                        arm_debug_state64_t& debug_state)
+{
+    debug_state.__mdscr_el1 &= ~1ULL;
+    kern_return_t kr = thread_set_state(thread, ARM_DEBUG_STATE64, (thread_state_t)&debug_state,
+                                        ARM_DEBUG_STATE64_COUNT);
+    if (kr != KERN_SUCCESS)
+    {
+        debug_log(LOG_ERROR, "Failed to restore debug state: %s", mach_error_string(kr));
+        return kr;
+    }
+    single_step_mode = SingleStepMode::None;
+
+    // The watch may have been removed while the thread was stepping
+    int index = find_page_watch_index(stepping_page_watch_address);
+    stepping_page_watch_address = 0;
+    if (index == -1)
+    {
+        return KERN_SUCCESS;
+    }
+
+    const PageWatch& watch = page_watches[index];
+    kr = mach_vm_protect(task_port_, watch.page_start, watch.page_length, false,
+                         watch.watch_protection);
+    if (kr != KERN_SUCCESS)
+    {
+        debug_log(LOG_ERROR, "Failed to re-protect watched pages: %s", mach_error_string(kr));
+    }
+    return kr;
+}
+
 kern_return_t Debugger::handle_breakpoint_hit(mach_port_t thread, arm_debug_state64_t& debug_state,
                                               arm_thread_state64_t& thread_state,
                                               arm_exception_state64_t& exception_state,
@@ -541,6 +961,32 @@ int Debugger::find_watchpoint_index(mach_vm_address_t address)
     return -1;
 }
 
+int Debugger::find_page_watch_index(mach_vm_address_t address)
+{
+    for (size_t i = 0; i < page_watches.size(); i++)
+    {
+        if (page_watches[i].address == address)
+        {
+            return (int)i;
+        }
+    }
+    return -1;
+}
+
+int Debugger::find_page_watch_for_fault(mach_vm_address_t fault_address)
+{
+    for (size_t i = 0; i < page_watches.size(); i++)
+    {
+        const PageWatch& watch = page_watches[i];
+        if (fault_address >= watch.page_start &&
+            fault_address < watch.page_start + watch.page_length)
+        {
+            return (int)i;
+        }
+    }
+    return -1;
+}
+
 int Debugger::find_free_breakpoint()
 {
     for (int i = 0; i < MAX_BREAKPOINTS; i++)
@@ -672,6 +1118,13 @@ extern "C"
             if (g_debugger->initialize())
             {
                 std::thread([&]() { g_debugger->run(); }).detach();
+                std::thread([]() {
+                    while (g_debugger)
+                    {
+                        std::this_thread::sleep_for(std::chrono::milliseconds(250));
+                        g_debugger->refresh_scoped_watchpoints();
+                    }
+                }).detach();
                 return true;
             }
             else
@@ -684,11 +1137,16 @@ extern "C"
         return true;
     }
 
-    kern_return_t set_watchpoint_native(mach_vm_address_t address, int size, WatchpointType type)
+    kern_return_t set_watchpoint_native(mach_vm_address_t address, int size, WatchpointType type,
+                                        const uint64_t* thread_ids, size_t thread_count,
+                                        const char* thread_filter)
     {
         if (g_debugger)
         {
-            return g_debugger->set_watchpoint(address, size, type);
+            ThreadScope scope;
+            scope.thread_ids.assign(thread_ids, thread_ids + thread_count);
+            scope.name_filter = thread_filter ? thread_filter : "";
+            return g_debugger->set_watchpoint(address, size, type, scope);
         }
         return KERN_FAILURE;
     }
@@ -702,6 +1160,16 @@ extern "C"
         return KERN_FAILURE;
     }
 
+    kern_return_t set_page_watchpoint_native(mach_vm_address_t address, mach_vm_size_t size,
+                                             WatchpointType type)
+    {
+        if (g_debugger)
+        {
+            return g_debugger->set_page_watch(address, size, type);
+        }
+        return KERN_FAILURE;
+    }
+
     kern_return_t set_breakpoint_native(mach_vm_address_t address, int hit_count)
     {
         if (g_debugger)
@@ -719,4 +1187,15 @@ extern "C"
         }
         return KERN_FAILURE;
     }
+
+    kern_return_t set_hit_action_native(mach_vm_address_t address, int is_breakpoint,
+                                        const char* register_name, uint64_t value,
+                                        size_t skip_length)
+    {
+        if (g_debugger)
+        {
+            return g_debugger->set_hit_action(address, register_name, value, skip_length);
+        }
+        return KERN_FAILURE;
+    }
 }
\ No newline at end of file
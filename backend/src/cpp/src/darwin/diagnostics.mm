@@ -0,0 +1,200 @@
+#include <arpa/inet.h>
+#include <dlfcn.h>
+#include <mach/mach.h>
+#include <mach/mach_error.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#include <string>
+
+#include "native_api.h"
+
+// Explains why the server cannot inspect a process: task_for_pid needs root or the debugger
+// entitlement, SIP hides protected and platform binaries, and the hardened runtime refuses
+// task ports to anything but a debugger unless the target is signed with get-task-allow.
+
+extern "C" int csops(pid_t pid, unsigned int ops, void *useraddr, size_t usersize);
+
+namespace
+{
+const unsigned int CS_OPS_STATUS = 0;
+const unsigned int CS_OPS_ENTITLEMENTS_BLOB = 7;
+
+const uint32_t CS_GET_TASK_ALLOW = 0x00000004;
+const uint32_t CS_RESTRICT = 0x00000800;
+const uint32_t CS_RUNTIME = 0x00010000;
+const uint32_t CS_PLATFORM_BINARY = 0x04000000;
+
+const uint32_t CSR_ALLOW_TASK_FOR_PID = 1 << 2;
+
+typedef int (*CSR_GET_ACTIVE_CONFIG)(uint32_t *config);
+
+std::string quote(const std::string &value)
+{
+    std::string quoted = "\"";
+    for (char c : value)
+    {
+        if (c == '"' || c == '\\')
+        {
+            quoted += '\\';
+        }
+        if (static_cast<unsigned char>(c) >= 0x20)
+        {
+            quoted += c;
+        }
+    }
+    return quoted + "\"";
+}
+
+const char *boolean(bool value)
+{
+    return value ? "true" : "false";
+}
+
+// The entitlements of the server itself, as the raw plist text
+std::string own_entitlements()
+{
+    // The blob starts with an 8-byte header: magic and big-endian length
+    uint32_t header[2] = {0, 0};
+    csops(getpid(), CS_OPS_ENTITLEMENTS_BLOB, header, sizeof(header));
+    uint32_t length = ntohl(header[1]);
+    if (length <= sizeof(header) || length > 1024 * 1024)
+    {
+        return "";
+    }
+    std::string blob(length, '\0');
+    if (csops(getpid(), CS_OPS_ENTITLEMENTS_BLOB, &blob[0], blob.size()) != 0)
+    {
+        return "";
+    }
+    return blob.substr(sizeof(header));
+}
+
+bool has_entitlement(const std::string &entitlements, const char *name)
+{
+    std::string key = std::string("<key>") + name + "</key>";
+    size_t position = entitlements.find(key);
+    if (position == std::string::npos)
+    {
+        return false;
+    }
+    size_t value = entitlements.find_first_not_of(" \t\r\n", position + key.size());
+    return value != std::string::npos && entitlements.compare(value, 7, "<true/>") == 0;
+}
+
+void add_problem(std::string &problems, const char *code, const std::string &message,
+                 const std::string &fix)
+{
+    if (problems.size() > 1)
+    {
+        problems += ",";
+    }
+    problems += "{\"code\":" + quote(code) + ",\"message\":" + quote(message) +
+                ",\"fix\":" + quote(fix) + "}";
+}
+}  // namespace
+
+char *diagnose_native(int pid)
+{
+    bool is_root = geteuid() == 0;
+
+    kern_return_t kr = KERN_SUCCESS;
+    if (pid != getpid())
+    {
+        task_t task = MACH_PORT_NULL;
+        kr = task_for_pid(mach_task_self(), pid, &task);
+        if (kr == KERN_SUCCESS)
+        {
+            mach_port_deallocate(mach_task_self(), task);
+        }
+    }
+
+    bool sip_known = false;
+    uint32_t sip_config = 0;
+    CSR_GET_ACTIVE_CONFIG csr_get_active_config =
+        (CSR_GET_ACTIVE_CONFIG)dlsym(RTLD_DEFAULT, "csr_get_active_config");
+    if (csr_get_active_config != nullptr && csr_get_active_config(&sip_config) == 0)
+    {
+        sip_known = true;
+    }
+    bool sip_blocks_task_for_pid = sip_known && (sip_config & CSR_ALLOW_TASK_FOR_PID) == 0;
+
+    std::string entitlements = own_entitlements();
+    bool debugger_entitlement = has_entitlement(entitlements, "com.apple.security.cs.debugger");
+    bool task_for_pid_allow = has_entitlement(entitlements, "task_for_pid-allow");
+
+    uint32_t target_flags = 0;
+    bool target_known = csops(pid, CS_OPS_STATUS, &target_flags, sizeof(target_flags)) == 0;
+    bool hardened_runtime = target_known && (target_flags & CS_RUNTIME) != 0;
+    bool get_task_allow = target_known && (target_flags & CS_GET_TASK_ALLOW) != 0;
+    bool platform_binary = target_known && (target_flags & CS_PLATFORM_BINARY) != 0;
+    bool restricted = target_known && (target_flags & CS_RESTRICT) != 0;
+
+    std::string problems = "[";
+    if (kr != KERN_SUCCESS)
+    {
+        if (!target_known)
+        {
+            add_problem(problems, "no_such_process",
+                        "Process " + std::to_string(pid) + " does not exist",
+                        "Refresh the process list and pick a running process");
+        }
+        if (!is_root && !debugger_entitlement && !task_for_pid_allow)
+        {
+            add_problem(problems, "not_root",
+                        "task_for_pid needs root or the com.apple.security.cs.debugger "
+                        "entitlement",
+                        "Run the server with sudo");
+        }
+        if (platform_binary && sip_blocks_task_for_pid)
+        {
+            add_problem(problems, "sip_enabled",
+                        "System Integrity Protection denies task ports of Apple platform "
+                        "binaries",
+                        "Pick a third-party process, or disable SIP with 'csrutil disable' "
+                        "from recovery mode");
+        }
+        if (hardened_runtime && !get_task_allow && !is_root)
+        {
+            add_problem(problems, "hardened_runtime",
+                        "The target uses the hardened runtime without get-task-allow",
+                        "Run the server as root, or re-sign the target with the "
+                        "com.apple.security.get-task-allow entitlement");
+        }
+        if (restricted && !platform_binary)
+        {
+            add_problem(problems, "restricted",
+                        "The target is a restricted binary, which refuses debuggers",
+                        "Re-sign the target without the restrict flag");
+        }
+        if (problems.size() == 1)
+        {
+            add_problem(problems, "task_for_pid_failed",
+                        std::string("task_for_pid failed: ") + mach_error_string(kr),
+                        "Check the system log for taskgated or AMFI denials");
+        }
+    }
+    problems += "]";
+
+    std::string json = "{";
+    json += "\"platform\":\"macos\"";
+    json += ",\"pid\":" + std::to_string(pid);
+    json += std::string(",\"attachable\":") + boolean(kr == KERN_SUCCESS);
+    json += ",\"task_for_pid\":{\"ok\":" + std::string(boolean(kr == KERN_SUCCESS)) +
+            ",\"code\":" + std::to_string(kr) + ",\"error\":" + quote(mach_error_string(kr)) + "}";
+    json += std::string(",\"root\":") + boolean(is_root);
+    json += std::string(",\"sip\":{\"known\":") + boolean(sip_known) +
+            ",\"config\":" + std::to_string(sip_config) +
+            ",\"allows_task_for_pid\":" + boolean(!sip_blocks_task_for_pid) + "}";
+    json += std::string(",\"entitlements\":{\"debugger\":") + boolean(debugger_entitlement) +
+            ",\"task_for_pid_allow\":" + boolean(task_for_pid_allow) + "}";
+    json += std::string(",\"target\":{\"known\":") + boolean(target_known) +
+            ",\"hardened_runtime\":" + boolean(hardened_runtime) +
+            ",\"get_task_allow\":" + boolean(get_task_allow) +
+            ",\"platform_binary\":" + boolean(platform_binary) +
+            ",\"restricted\":" + boolean(restricted) + "}";
+    json += ",\"problems\":" + problems;
+    json += "}";
+    return strdup(json.c_str());
+}
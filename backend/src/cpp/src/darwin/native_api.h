@@ -18,6 +18,19 @@ enum LogLevel
     LOG_ERROR
 };
 
+enum PageInfoFlags
+{
+    PAGE_INFO_RESIDENT = 1,
+    PAGE_INFO_DIRTY = 2,
+    PAGE_INFO_SWAPPED = 4
+};
+
+enum StealthOptions
+{
+    STEALTH_NO_PTRACE = 1,
+    STEALTH_MINIMAL_EXCEPTION_PORTS = 2
+};
+
 enum ServerMode
 {
     NORMAL,
@@ -63,7 +76,12 @@ extern "C" kern_return_t mach_vm_region(vm_map_t, mach_vm_address_t *, mach_vm_s
                                         vm_region_flavor_t, vm_region_info_t,
                                         mach_msg_type_number_t *, mach_port_t *);
 
+extern "C" kern_return_t mach_vm_page_query(vm_map_t, mach_vm_offset_t, integer_t *, integer_t *);
+
 extern "C" int native_init(int mode);
+extern "C" int get_pointer_size_native(int pid);
+extern "C" char *diagnose_native(int pid);
+extern "C" void device_condition_native(int *thermal_state, int *battery_percent, int *charging);
 
 extern "C" pid_t get_pid_native();
 
@@ -73,6 +91,9 @@ extern "C" ssize_t read_memory_native(int pid, mach_vm_address_t address, mach_v
 extern "C" ssize_t write_memory_native(int pid, mach_vm_address_t address, mach_vm_size_t size,
                                        unsigned char *buffer);
 
+extern "C" ssize_t write_memory_stealth_native(int pid, mach_vm_address_t address,
+                                               mach_vm_size_t size, unsigned char *buffer);
+
 extern "C" void enumerate_regions_to_buffer(pid_t pid, char *buffer, size_t buffer_size);
 
 extern "C" ProcessInfo *enumprocess_native(size_t *count);
@@ -83,11 +104,26 @@ extern "C" bool resume_process(pid_t pid);
 
 extern "C" ModuleInfo *enummodule_native(pid_t pid, size_t *count);
 
+extern "C" char *enumerate_allocations_native(int pid, size_t max_count);
+
+extern "C" char *enumerate_threads_native(int pid);
+
+extern "C" char *enumerate_thread_stacks_native(int pid);
+
+extern "C" char *enumerate_tls_blocks_native(int pid, size_t block_size);
+
+extern "C" int query_page_info_native(int pid, uintptr_t address, size_t page_count,
+                                      unsigned char *flags, size_t *page_size);
+extern "C" int set_stealth_options_native(int options);
+extern "C" int set_protection_native(int pid, uintptr_t address, size_t size, int protection);
+int get_stealth_options();
+
 int debug_log(LogLevel level, const char *format, ...);
 
 // Rust functions
 extern "C" void native_log(int level, const char *message);
-extern "C" void send_register_json(const char *register_json, pid_t pid);
+// Returns false when the hit fails its watchpoint condition
+extern "C" bool send_register_json(const char *register_json, pid_t pid);
 char *disassemble(const uint8_t *bytecode, size_t length);
 void free_string(char *s);
 #endif
@@ -1,15 +1,21 @@
 #include "native_api.h"
 #include <Foundation/Foundation.h>
+#include <TargetConditionals.h>
+#if TARGET_OS_IPHONE
+#include <UIKit/UIKit.h>
+#endif
 #include <dlfcn.h>
 #include <errno.h>
 #include <mach-o/dyld_images.h>
 #include <mach-o/fat.h>
 #include <mach-o/loader.h>
+#include <malloc/malloc.h>
 #include <stdio.h>
 #include <stdlib.h>
 #include <sys/queue.h>
 #include <sys/sysctl.h>
 #include <iostream>
+#include <mutex>
 #include <string>
 #include <vector>
 
@@ -173,6 +179,34 @@ ssize_t write_memory_native(int pid, mach_vm_address_t address, mach_vm_size_t s
     return static_cast<ssize_t>(size);
 }
 
+// Writes with mach_vm_write alone: no suspend and no protection changes, so the write
+// fails on pages that are not already writable.
+ssize_t write_memory_stealth_native(int pid, mach_vm_address_t address, mach_vm_size_t size,
+                                    unsigned char *buffer)
+{
+    task_t task = mach_task_self();
+    kern_return_t err;
+    if (pid != getpid())
+    {
+        err = task_for_pid(mach_task_self(), pid, &task);
+        if (err != KERN_SUCCESS)
+        {
+            debug_log(LOG_ERROR, "task_for_pid failed with error %d (%s)\n", err,
+                      mach_error_string(err));
+            return -1;
+        }
+    }
+
+    err = mach_vm_write(task, address, (vm_offset_t)buffer, size);
+    if (err != KERN_SUCCESS)
+    {
+        debug_log(LOG_ERROR, "mach_vm_write failed with error %d (%s) at address 0x%llx\n", err,
+                  mach_error_string(err), address);
+        return -1;
+    }
+    return static_cast<ssize_t>(size);
+}
+
 void enumerate_regions_to_buffer(pid_t pid, char *buffer, size_t buffer_size)
 {
     task_t task;
@@ -606,6 +640,227 @@ ModuleInfo *enummodule_native(pid_t pid, size_t *count)
     return result;
 }
 
+// Copies of remote memory handed to the malloc introspection callbacks. They stay alive until the
+// enumeration finishes because the zone enumerators keep pointers into them.
+static std::vector<void *> allocation_reader_buffers;
+static std::mutex allocation_reader_mutex;
+
+static kern_return_t allocation_memory_reader(task_t task, vm_address_t address, vm_size_t size,
+                                              void **local_memory)
+{
+    if (task == mach_task_self())
+    {
+        *local_memory = reinterpret_cast<void *>(address);
+        return KERN_SUCCESS;
+    }
+
+    void *buffer = malloc(size);
+    if (buffer == nullptr)
+    {
+        return KERN_NO_SPACE;
+    }
+    mach_vm_size_t out_size;
+    kern_return_t kr =
+        mach_vm_read_overwrite(task, address, size, (mach_vm_address_t)buffer, &out_size);
+    if (kr != KERN_SUCCESS)
+    {
+        free(buffer);
+        return kr;
+    }
+    allocation_reader_buffers.push_back(buffer);
+    *local_memory = buffer;
+    return KERN_SUCCESS;
+}
+
+typedef struct
+{
+    std::string *out;
+    size_t count;
+    size_t max_count;
+    const char *zone_name;
+} AllocationContext;
+
+static void allocation_recorder(task_t task, void *context, unsigned type, vm_range_t *ranges,
+                                unsigned count)
+{
+    AllocationContext *ctx = static_cast<AllocationContext *>(context);
+    for (unsigned i = 0; i < count && ctx->count < ctx->max_count; i++)
+    {
+        char line[256];
+        snprintf(line, sizeof(line), "%llx %llx %s\n",
+                 static_cast<unsigned long long>(ranges[i].address),
+                 static_cast<unsigned long long>(ranges[i].size), ctx->zone_name);
+        *ctx->out += line;
+        ctx->count++;
+    }
+}
+
+char *enumerate_allocations_native(int pid, size_t max_count)
+{
+    task_t task;
+    kern_return_t err;
+    if (pid == getpid())
+    {
+        task = mach_task_self();
+    }
+    else
+    {
+        err = task_for_pid(mach_task_self(), pid, &task);
+        if (err != KERN_SUCCESS)
+        {
+            debug_log(LOG_ERROR, "task_for_pid failed with error %d (%s)\n", err,
+                      mach_error_string(err));
+            return nullptr;
+        }
+    }
+
+    std::lock_guard<std::mutex> lock(allocation_reader_mutex);
+    std::string out;
+    AllocationContext context = {&out, 0, max_count, "malloc"};
+
+    vm_address_t *zones = nullptr;
+    unsigned zone_count = 0;
+    err = malloc_get_all_zones(task, allocation_memory_reader, &zones, &zone_count);
+    if (err != KERN_SUCCESS)
+    {
+        debug_log(LOG_ERROR, "malloc_get_all_zones failed with error %d (%s)\n", err,
+                  mach_error_string(err));
+    }
+    else
+    {
+        std::vector<vm_address_t> zone_addresses(zones, zones + zone_count);
+        for (vm_address_t zone_address : zone_addresses)
+        {
+            malloc_zone_t *zone = nullptr;
+            if (allocation_memory_reader(task, zone_address, sizeof(malloc_zone_t),
+                                         reinterpret_cast<void **>(&zone)) != KERN_SUCCESS ||
+                zone->introspect == nullptr)
+            {
+                continue;
+            }
+            malloc_introspection_t *introspect = nullptr;
+            if (allocation_memory_reader(task, (vm_address_t)zone->introspect,
+                                         sizeof(malloc_introspection_t),
+                                         reinterpret_cast<void **>(&introspect)) != KERN_SUCCESS)
+            {
+                continue;
+            }
+
+            char zone_name[64] = "malloc";
+            char *remote_name = nullptr;
+            if (zone->zone_name != nullptr &&
+                allocation_memory_reader(task, (vm_address_t)zone->zone_name, sizeof(zone_name),
+                                         reinterpret_cast<void **>(&remote_name)) == KERN_SUCCESS)
+            {
+                strlcpy(zone_name, remote_name, sizeof(zone_name));
+            }
+            context.zone_name = zone_name;
+
+            // The introspection functions live in the shared cache, so the local addresses are
+            // valid for the target as well
+            introspect->enumerator(task, &context, MALLOC_PTR_IN_USE_RANGE_TYPE, zone_address,
+                                   allocation_memory_reader, allocation_recorder);
+            if (context.count >= max_count) break;
+        }
+    }
+
+    for (void *buffer : allocation_reader_buffers)
+    {
+        free(buffer);
+    }
+    allocation_reader_buffers.clear();
+
+    return strdup(out.c_str());
+}
+
+int query_page_info_native(int pid, uintptr_t address, size_t page_count, unsigned char *flags,
+                           size_t *page_size)
+{
+    task_t task;
+    kern_return_t err;
+    *page_size = vm_page_size;
+    address -= address % vm_page_size;
+    if (pid == getpid())
+    {
+        task = mach_task_self();
+    }
+    else
+    {
+        err = task_for_pid(mach_task_self(), pid, &task);
+        if (err != KERN_SUCCESS)
+        {
+            debug_log(LOG_ERROR, "task_for_pid failed with error %d (%s)\n", err,
+                      mach_error_string(err));
+            return -1;
+        }
+    }
+
+    for (size_t i = 0; i < page_count; i++)
+    {
+        integer_t disposition = 0;
+        integer_t ref_count = 0;
+        err = mach_vm_page_query(task, address + i * vm_page_size, &disposition, &ref_count);
+        if (err != KERN_SUCCESS)
+        {
+            debug_log(LOG_DEBUG, "mach_vm_page_query failed with error %d (%s)\n", err,
+                      mach_error_string(err));
+            return static_cast<int>(i);
+        }
+        unsigned char page_flags = 0;
+        if (disposition & VM_PAGE_QUERY_PAGE_PRESENT) page_flags |= PAGE_INFO_RESIDENT;
+        if (disposition & VM_PAGE_QUERY_PAGE_DIRTY) page_flags |= PAGE_INFO_DIRTY;
+        if (disposition & VM_PAGE_QUERY_PAGE_PAGED_OUT) page_flags |= PAGE_INFO_SWAPPED;
+        flags[i] = page_flags;
+    }
+    return static_cast<int>(page_count);
+}
+
+static int stealth_options = 0;
+
+// protection uses the VM_PROT_READ/VM_PROT_WRITE/VM_PROT_EXECUTE bit values
+int set_protection_native(int pid, uintptr_t address, size_t size, int protection)
+{
+    task_t task = mach_task_self();
+    kern_return_t err;
+    if (pid != getpid())
+    {
+        err = task_for_pid(mach_task_self(), pid, &task);
+        if (err != KERN_SUCCESS)
+        {
+            debug_log(LOG_ERROR, "task_for_pid failed with error %d (%s)\n", err,
+                      mach_error_string(err));
+            return -1;
+        }
+    }
+
+    err = mach_vm_protect(task, address, size, false, protection);
+    if (err != KERN_SUCCESS)
+    {
+        debug_log(LOG_ERROR, "mach_vm_protect failed with error %d (%s)\n", err,
+                  mach_error_string(err));
+        return -1;
+    }
+    return 0;
+}
+
+int set_stealth_options_native(int options)
+{
+    // Only affects debugger instances created afterwards
+    stealth_options = options & STEALTH_MINIMAL_EXCEPTION_PORTS;
+    return stealth_options;
+}
+
+int get_stealth_options()
+{
+    return stealth_options;
+}
+
+int get_pointer_size_native(int pid)
+{
+    // Current macOS and iOS no longer run 32-bit processes
+    return sizeof(void *);
+}
+
 int native_init(int mode)
 {
     global_server_state.mode = mode;
@@ -647,3 +902,211 @@ int native_init(int mode)
     }
     return 1;
 }
+
+// One "thread_id name" line per thread; ids are the 64-bit ids of pthread_threadid_np
+char *enumerate_threads_native(int pid)
+{
+    task_t task;
+    if (pid == getpid())
+    {
+        task = mach_task_self();
+    }
+    else
+    {
+        kern_return_t err = task_for_pid(mach_task_self(), pid, &task);
+        if (err != KERN_SUCCESS)
+        {
+            debug_log(LOG_ERROR, "task_for_pid failed with error %d (%s)\n", err,
+                      mach_error_string(err));
+            return nullptr;
+        }
+    }
+
+    thread_act_array_t thread_list;
+    mach_msg_type_number_t thread_count;
+    kern_return_t err = task_threads(task, &thread_list, &thread_count);
+    if (err != KERN_SUCCESS)
+    {
+        debug_log(LOG_ERROR, "task_threads failed with error %d (%s)\n", err,
+                  mach_error_string(err));
+        return nullptr;
+    }
+
+    std::string out;
+    for (mach_msg_type_number_t i = 0; i < thread_count; i++)
+    {
+        thread_identifier_info_data_t identifier;
+        mach_msg_type_number_t count = THREAD_IDENTIFIER_INFO_COUNT;
+        if (thread_info(thread_list[i], THREAD_IDENTIFIER_INFO, (thread_info_t)&identifier,
+                        &count) == KERN_SUCCESS)
+        {
+            thread_extended_info_data_t extended;
+            count = THREAD_EXTENDED_INFO_COUNT;
+            std::string name;
+            if (thread_info(thread_list[i], THREAD_EXTENDED_INFO, (thread_info_t)&extended,
+                            &count) == KERN_SUCCESS)
+            {
+                name.assign(extended.pth_name, strnlen(extended.pth_name, sizeof(extended.pth_name)));
+            }
+            out += std::to_string(identifier.thread_id) + " " + name + "\n";
+        }
+        mach_port_deallocate(mach_task_self(), thread_list[i]);
+    }
+    vm_deallocate(mach_task_self(), (vm_address_t)thread_list, thread_count * sizeof(thread_act_t));
+    return strdup(out.c_str());
+}
+
+// One "thread_id sp start end" line (hex after the id) per thread; the stack is the region
+// holding the stack pointer
+char *enumerate_thread_stacks_native(int pid)
+{
+    task_t task;
+    if (pid == getpid())
+    {
+        task = mach_task_self();
+    }
+    else
+    {
+        kern_return_t err = task_for_pid(mach_task_self(), pid, &task);
+        if (err != KERN_SUCCESS)
+        {
+            debug_log(LOG_ERROR, "task_for_pid failed with error %d (%s)\n", err,
+                      mach_error_string(err));
+            return nullptr;
+        }
+    }
+
+    thread_act_array_t thread_list;
+    mach_msg_type_number_t thread_count;
+    kern_return_t err = task_threads(task, &thread_list, &thread_count);
+    if (err != KERN_SUCCESS)
+    {
+        debug_log(LOG_ERROR, "task_threads failed with error %d (%s)\n", err,
+                  mach_error_string(err));
+        return nullptr;
+    }
+
+    std::string out;
+    for (mach_msg_type_number_t i = 0; i < thread_count; i++)
+    {
+        thread_identifier_info_data_t identifier;
+        mach_msg_type_number_t count = THREAD_IDENTIFIER_INFO_COUNT;
+        arm_thread_state64_t state;
+        mach_msg_type_number_t state_count = ARM_THREAD_STATE64_COUNT;
+        if (thread_info(thread_list[i], THREAD_IDENTIFIER_INFO, (thread_info_t)&identifier,
+                        &count) == KERN_SUCCESS &&
+            thread_get_state(thread_list[i], ARM_THREAD_STATE64, (thread_state_t)&state,
+                             &state_count) == KERN_SUCCESS)
+        {
+            mach_vm_address_t region_address = arm_thread_state64_get_sp(state);
+            mach_vm_size_t region_size = 0;
+            vm_region_basic_info_data_64_t info;
+            mach_msg_type_number_t info_count = VM_REGION_BASIC_INFO_COUNT_64;
+            mach_port_t object_name;
+            if (mach_vm_region(task, &region_address, &region_size, VM_REGION_BASIC_INFO_64,
+                               (vm_region_info_t)&info, &info_count,
+                               &object_name) == KERN_SUCCESS &&
+                region_address <= arm_thread_state64_get_sp(state))
+            {
+                char line[96];
+                snprintf(line, sizeof(line), "%llu %llx %llx %llx\n", identifier.thread_id,
+                         (unsigned long long)arm_thread_state64_get_sp(state), region_address,
+                         region_address + region_size);
+                out += line;
+            }
+        }
+        mach_port_deallocate(mach_task_self(), thread_list[i]);
+    }
+    vm_deallocate(mach_task_self(), (vm_address_t)thread_list, thread_count * sizeof(thread_act_t));
+    return strdup(out.c_str());
+}
+
+// "tid pthread start end" per thread. The thread handle is the thread's pthread structure,
+// whose TSD slots hold pthread_getspecific values and the per-thread __thread storage.
+char *enumerate_tls_blocks_native(int pid, size_t block_size)
+{
+    task_t task;
+    if (pid == getpid())
+    {
+        task = mach_task_self();
+    }
+    else
+    {
+        kern_return_t err = task_for_pid(mach_task_self(), pid, &task);
+        if (err != KERN_SUCCESS)
+        {
+            debug_log(LOG_ERROR, "task_for_pid failed with error %d (%s)\n", err,
+                      mach_error_string(err));
+            return nullptr;
+        }
+    }
+
+    thread_act_array_t thread_list;
+    mach_msg_type_number_t thread_count;
+    kern_return_t err = task_threads(task, &thread_list, &thread_count);
+    if (err != KERN_SUCCESS)
+    {
+        debug_log(LOG_ERROR, "task_threads failed with error %d (%s)\n", err,
+                  mach_error_string(err));
+        return nullptr;
+    }
+
+    std::string out;
+    for (mach_msg_type_number_t i = 0; i < thread_count; i++)
+    {
+        thread_identifier_info_data_t identifier;
+        mach_msg_type_number_t count = THREAD_IDENTIFIER_INFO_COUNT;
+        if (thread_info(thread_list[i], THREAD_IDENTIFIER_INFO, (thread_info_t)&identifier,
+                        &count) == KERN_SUCCESS &&
+            identifier.thread_handle != 0)
+        {
+            mach_vm_address_t region_address = identifier.thread_handle;
+            mach_vm_size_t region_size = 0;
+            vm_region_basic_info_data_64_t info;
+            mach_msg_type_number_t info_count = VM_REGION_BASIC_INFO_COUNT_64;
+            mach_port_t object_name;
+            if (mach_vm_region(task, &region_address, &region_size, VM_REGION_BASIC_INFO_64,
+                               (vm_region_info_t)&info, &info_count,
+                               &object_name) == KERN_SUCCESS &&
+                region_address <= identifier.thread_handle)
+            {
+                mach_vm_address_t end = identifier.thread_handle + block_size;
+                if (end > region_address + region_size)
+                {
+                    end = region_address + region_size;
+                }
+                char line[96];
+                snprintf(line, sizeof(line), "%llu %llx %llx %llx\n", identifier.thread_id,
+                         identifier.thread_handle, identifier.thread_handle,
+                         (unsigned long long)end);
+                out += line;
+            }
+        }
+        mach_port_deallocate(mach_task_self(), thread_list[i]);
+    }
+    vm_deallocate(mach_task_self(), (vm_address_t)thread_list, thread_count * sizeof(thread_act_t));
+    return strdup(out.c_str());
+}
+
+// Thermal state from 0 (nominal) to 3 (critical), battery level in percent or -1 when unknown,
+// and 1 while charging or full
+void device_condition_native(int *thermal_state, int *battery_percent, int *charging)
+{
+    @autoreleasepool
+    {
+        *thermal_state = (int)[[NSProcessInfo processInfo] thermalState];
+        *battery_percent = -1;
+        *charging = 0;
+#if TARGET_OS_IPHONE
+        UIDevice *device = [UIDevice currentDevice];
+        device.batteryMonitoringEnabled = YES;
+        float level = device.batteryLevel;
+        if (level >= 0)
+        {
+            *battery_percent = (int)(level * 100);
+        }
+        UIDeviceBatteryState state = device.batteryState;
+        *charging = state == UIDeviceBatteryStateCharging || state == UIDeviceBatteryStateFull;
+#endif
+    }
+}
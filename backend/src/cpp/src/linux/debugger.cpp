@@ -7,7 +7,9 @@ extern "C"
         return true;
     }
 
-    int set_watchpoint_native(uint64_t address, int size, WatchpointType type)
+    int set_watchpoint_native(uint64_t address, int size, WatchpointType type,
+                              const uint64_t *thread_ids, size_t thread_count,
+                              const char *thread_filter)
     {
         return 0;
     }
@@ -17,6 +19,11 @@ extern "C"
         return 0;
     }
 
+    int set_page_watchpoint_native(uint64_t address, size_t size, WatchpointType type)
+    {
+        return 0;
+    }
+
     int set_breakpoint_native(uint64_t address, int hit_count)
     {
         return 0;
@@ -26,4 +33,10 @@ extern "C"
     {
         return 0;
     }
+
+    int set_hit_action_native(uint64_t address, int is_breakpoint, const char *register_name,
+                              uint64_t value, size_t skip_length)
+    {
+        return 0;
+    }
 }
\ No newline at end of file
@@ -0,0 +1,269 @@
+#include <errno.h>
+#include <fcntl.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#include <fstream>
+#include <string>
+
+#include "native_api.h"
+
+// Explains why the server cannot inspect a process: every read method needs the ptrace
+// access check, which Yama's ptrace_scope and non-root users restrict, and on Android
+// SELinux additionally confines which domains may read which (isolated services above all).
+
+namespace
+{
+#ifdef TARGET_IS_ANDROID
+// Android's AID_ISOLATED_START..AID_ISOLATED_END, which includes app zygote children
+const int ISOLATED_UID_START = 90000;
+const int ISOLATED_UID_END = 99999;
+#endif
+
+std::string quote(const std::string &value)
+{
+    std::string quoted = "\"";
+    for (char c : value)
+    {
+        if (c == '"' || c == '\\')
+        {
+            quoted += '\\';
+        }
+        if (static_cast<unsigned char>(c) >= 0x20)
+        {
+            quoted += c;
+        }
+    }
+    return quoted + "\"";
+}
+
+const char *boolean(bool value)
+{
+    return value ? "true" : "false";
+}
+
+int read_int_file(const char *path, int fallback)
+{
+    std::ifstream file(path);
+    int value;
+    if (file >> value)
+    {
+        return value;
+    }
+    return fallback;
+}
+
+std::string read_text_file(const std::string &path)
+{
+    std::ifstream file(path);
+    std::string text;
+    std::getline(file, text, '\0');
+    while (!text.empty() && (text.back() == '\n' || text.back() == '\0'))
+    {
+        text.pop_back();
+    }
+    return text;
+}
+
+// First number of a "Name:" line in /proc/pid/status, or -1
+int status_field(int pid, const char *name)
+{
+    std::ifstream status("/proc/" + std::to_string(pid) + "/status");
+    std::string line;
+    size_t length = strlen(name);
+    while (std::getline(status, line))
+    {
+        if (line.compare(0, length, name) == 0)
+        {
+            return atoi(line.c_str() + length);
+        }
+    }
+    return -1;
+}
+
+// Start of the first readable mapping, for probing the read methods
+uintptr_t first_readable_address(int pid)
+{
+    std::ifstream maps("/proc/" + std::to_string(pid) + "/maps");
+    std::string line;
+    while (std::getline(maps, line))
+    {
+        size_t space = line.find(' ');
+        if (space != std::string::npos && line.size() > space + 1 && line[space + 1] == 'r' &&
+            line.find("[vvar]") == std::string::npos)
+        {
+            return strtoull(line.c_str(), nullptr, 16);
+        }
+    }
+    return 0;
+}
+
+bool is_denied(ssize_t result)
+{
+    return result == -EPERM || result == -EACCES || result == -ENOSYS;
+}
+
+void add_problem(std::string &problems, const char *code, const std::string &message,
+                 const std::string &fix)
+{
+    if (problems.size() > 1)
+    {
+        problems += ",";
+    }
+    problems += "{\"code\":" + quote(code) + ",\"message\":" + quote(message) +
+                ",\"fix\":" + quote(fix) + "}";
+}
+}  // namespace
+
+char *diagnose_native(int pid)
+{
+    bool is_root = geteuid() == 0;
+    std::string proc = "/proc/" + std::to_string(pid);
+    bool exists = access(proc.c_str(), F_OK) == 0;
+
+    // -1 when Yama or SELinux is not built in
+    int ptrace_scope = read_int_file("/proc/sys/kernel/yama/ptrace_scope", -1);
+    int selinux_enforcing = read_int_file("/sys/fs/selinux/enforce", -1);
+    std::string own_context = read_text_file("/proc/self/attr/current");
+    std::string target_context = exists ? read_text_file(proc + "/attr/current") : "";
+    int tracer = exists ? status_field(pid, "TracerPid:") : 0;
+    int target_uid = exists ? status_field(pid, "Uid:") : -1;
+#ifdef TARGET_IS_ANDROID
+    bool isolated = target_uid >= ISOLATED_UID_START && target_uid <= ISOLATED_UID_END;
+#else
+    bool isolated = false;
+#endif
+
+    // Probe every method on a mapped page. ptrace stops the target, so it only runs when
+    // the others are denied.
+    uintptr_t probe = exists ? first_readable_address(pid) : 0;
+    ssize_t results[READ_METHOD_COUNT];
+    bool tested[READ_METHOD_COUNT] = {};
+    bool any_permitted = false;
+    bool any_denied = false;
+    for (int method = 0; method < READ_METHOD_COUNT; method++)
+    {
+        results[method] = 0;
+        if (probe == 0 || (method == READ_PTRACE && any_permitted))
+        {
+            continue;
+        }
+        unsigned char byte;
+        results[method] = read_memory_with(static_cast<ReadMethod>(method), pid, probe, 1, &byte);
+        tested[method] = true;
+        any_permitted = any_permitted || results[method] >= 0;
+        any_denied = any_denied || is_denied(results[method]);
+    }
+    bool attachable = any_permitted;
+
+    std::string problems = "[";
+    if (!exists)
+    {
+        add_problem(problems, "no_such_process",
+                    "Process " + std::to_string(pid) + " does not exist",
+                    "Refresh the process list and pick a running process");
+    }
+    else if (probe == 0)
+    {
+        add_problem(problems, "maps_unreadable",
+                    "The memory map of process " + std::to_string(pid) + " cannot be read",
+                    is_root ? "Check the kernel log for LSM denials" : "Run the server as root");
+    }
+    else if (!attachable)
+    {
+        if (!is_root && ptrace_scope >= 1)
+        {
+            add_problem(problems, "ptrace_scope",
+                        "Yama ptrace_scope is " + std::to_string(ptrace_scope) +
+                            ", which only allows root to inspect other processes",
+                        "Run the server as root, or set kernel.yama.ptrace_scope to 0");
+        }
+        else if (!is_root)
+        {
+#ifdef TARGET_IS_ANDROID
+            add_problem(problems, "not_root", "Reading other apps requires root",
+                        "Start the server through su on a rooted device");
+#else
+            add_problem(problems, "not_root",
+                        "The process belongs to another user or is not dumpable",
+                        "Run the server as root");
+#endif
+        }
+        else if (isolated)
+        {
+            add_problem(problems, "isolated_process",
+                        "The target is an isolated service (uid " + std::to_string(target_uid) +
+                            "), which SELinux keeps apart from other domains",
+                        "Inspect the app's main process, or run the server in a context "
+                        "allowed to read isolated_app");
+        }
+        else if (selinux_enforcing == 1 && any_denied)
+        {
+            add_problem(problems, "selinux_denied",
+                        "SELinux denies " + own_context + " access to " + target_context,
+                        "Start the server from an unrestricted su context (such as u:r:su:s0 or "
+                        "magisk), or run 'setenforce 0' while testing");
+        }
+        if (problems.size() == 1)
+        {
+            add_problem(problems, "read_failed",
+                        std::string("Every read method failed, process_vm_readv with: ") +
+                            strerror(static_cast<int>(-results[READ_PROCESS_VM_READV])),
+                        "Check the kernel log for LSM or seccomp denials");
+        }
+    }
+    if (tracer > 0 && tracer != getpid())
+    {
+        add_problem(problems, "already_traced",
+                    "Process is traced by " + std::to_string(tracer) +
+                        ", so watchpoints and breakpoints cannot attach",
+                    "Detach the other debugger");
+    }
+    problems += "]";
+
+    std::string methods = "{";
+    for (int method = 0; method < READ_METHOD_COUNT; method++)
+    {
+        if (method > 0)
+        {
+            methods += ",";
+        }
+        methods += quote(read_method_name(method)) + ":";
+        if (!tested[method])
+        {
+            methods += "\"untested\"";
+        }
+        else if (results[method] >= 0)
+        {
+            methods += "\"ok\"";
+        }
+        else
+        {
+            methods += quote(strerror(static_cast<int>(-results[method])));
+        }
+    }
+    methods += "}";
+
+    std::string json = "{";
+#ifdef TARGET_IS_ANDROID
+    json += "\"platform\":\"android\"";
+#else
+    json += "\"platform\":\"linux\"";
+#endif
+    json += ",\"pid\":" + std::to_string(pid);
+    json += std::string(",\"attachable\":") + boolean(attachable);
+    json += std::string(",\"root\":") + boolean(is_root);
+    json += ",\"ptrace_scope\":" + std::to_string(ptrace_scope);
+    json += ",\"tracer_pid\":" + std::to_string(tracer);
+    json += ",\"target_uid\":" + std::to_string(target_uid);
+    json += std::string(",\"isolated\":") + boolean(isolated);
+    json += ",\"selinux\":{\"enforcing\":" + std::to_string(selinux_enforcing) +
+            ",\"context\":" + quote(own_context) +
+            ",\"target_context\":" + quote(target_context) + "}";
+    json += ",\"read_methods\":" + methods;
+    json += ",\"read_method\":" + quote(read_method_name(current_read_method()));
+    json += ",\"problems\":" + problems;
+    json += "}";
+    return strdup(json.c_str());
+}
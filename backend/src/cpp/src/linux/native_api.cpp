@@ -16,6 +16,8 @@ static process_vm_writev_func PROCESS_VM_WRITEV = nullptr;
 
 unsigned char EI_MAGIC[] = {0x7F, 'E', 'L', 'F'};
 
+static int stealth_options = 0;
+
 int debug_log(LogLevel level, const char *format, ...)
 {
     va_list args;
@@ -42,7 +44,8 @@ pid_t get_pid_native()
     return getpid();
 }
 
-ssize_t read_memory_native(int pid, uintptr_t address, size_t size, unsigned char *buffer)
+static ssize_t read_memory_vm_readv(int pid, uintptr_t address, size_t size,
+                                    unsigned char *buffer)
 {
     struct iovec local_iov;
     struct iovec remote_iov;
@@ -53,17 +56,145 @@ ssize_t read_memory_native(int pid, uintptr_t address, size_t size, unsigned cha
     remote_iov.iov_len = size;
 
 #ifdef TARGET_IS_ANDROID
+    if (PROCESS_VM_READV == nullptr)
+    {
+        return -ENOSYS;
+    }
     ssize_t nread = PROCESS_VM_READV(pid, &local_iov, 1, &remote_iov, 1, 0);
 #else
     ssize_t nread = process_vm_readv(pid, &local_iov, 1, &remote_iov, 1, 0);
 #endif
+    return nread < 0 ? -errno : nread;
+}
+
+static ssize_t read_memory_procfs(int pid, uintptr_t address, size_t size, unsigned char *buffer)
+{
+    char path[64];
+    snprintf(path, sizeof(path), "/proc/%d/mem", pid);
+    int fd = open(path, O_RDONLY);
+    if (fd < 0)
+    {
+        return -errno;
+    }
+    ssize_t nread = pread64(fd, buffer, size, static_cast<off64_t>(address));
+    int error = errno;
+    close(fd);
+    return nread < 0 ? -error : nread;
+}
+
+// Last resort for kernels or policies that forbid both of the above: attaches, peeks word by
+// word and detaches. Stops the target briefly and is visible as TracerPid while it runs.
+static ssize_t read_memory_ptrace(int pid, uintptr_t address, size_t size, unsigned char *buffer)
+{
+    if (stealth_options & STEALTH_NO_PTRACE)
+    {
+        return -EPERM;
+    }
+    if (ptrace(PTRACE_ATTACH, pid, NULL, NULL) == -1)
+    {
+        return -errno;
+    }
+    waitpid(pid, NULL, 0);
+
+    size_t nread = 0;
+    int error = 0;
+    while (nread < size)
+    {
+        errno = 0;
+        long word = ptrace(PTRACE_PEEKDATA, pid, reinterpret_cast<void *>(address + nread), NULL);
+        if (errno != 0)
+        {
+            error = errno;
+            break;
+        }
+        size_t chunk = std::min(sizeof(long), size - nread);
+        std::memcpy(buffer + nread, &word, chunk);
+        nread += chunk;
+    }
+    ptrace(PTRACE_DETACH, pid, NULL, NULL);
+    return nread == 0 && error != 0 ? -error : static_cast<ssize_t>(nread);
+}
+
+ssize_t read_memory_with(ReadMethod method, int pid, uintptr_t address, size_t size,
+                         unsigned char *buffer)
+{
+    switch (method)
+    {
+        case READ_PROCESS_VM_READV:
+            return read_memory_vm_readv(pid, address, size, buffer);
+        case READ_PROC_MEM:
+            return read_memory_procfs(pid, address, size, buffer);
+        case READ_PTRACE:
+            return read_memory_ptrace(pid, address, size, buffer);
+        default:
+            return -EINVAL;
+    }
+}
+
+const char *read_method_name(int method)
+{
+    switch (method)
+    {
+        case READ_PROCESS_VM_READV:
+            return "process_vm_readv";
+        case READ_PROC_MEM:
+            return "proc_mem";
+        case READ_PTRACE:
+            return "ptrace";
+        default:
+            return "unknown";
+    }
+}
+
+// The method that last succeeded, so a denied syscall is only paid for once
+static std::atomic<int> read_method(READ_PROCESS_VM_READV);
+
+int current_read_method()
+{
+    return read_method.load();
+}
+
+// SELinux denials, seccomp filters and missing syscalls; bad addresses are not among them
+static bool is_denied(ssize_t result)
+{
+    return result == -EPERM || result == -EACCES || result == -ENOSYS;
+}
+
+ssize_t read_memory_native(int pid, uintptr_t address, size_t size, unsigned char *buffer)
+{
+    int preferred = read_method.load();
+    ssize_t nread = read_memory_with(static_cast<ReadMethod>(preferred), pid, address, size, buffer);
+    if (is_denied(nread))
+    {
+        for (int method = 0; method < READ_METHOD_COUNT; method++)
+        {
+            if (method == preferred)
+            {
+                continue;
+            }
+            ssize_t result =
+                read_memory_with(static_cast<ReadMethod>(method), pid, address, size, buffer);
+            if (is_denied(result))
+            {
+                continue;
+            }
+            if (result >= 0)
+            {
+                debug_log(LOG_INFO, "%s is not permitted, reading with %s instead\n",
+                          read_method_name(preferred), read_method_name(method));
+                read_method.store(method);
+            }
+            nread = result;
+            break;
+        }
+    }
 
     if (nread < 0)
     {
         debug_log(LOG_DEBUG,
                   "Failed to read memory from process %d at address 0x%lx. Error: %d (%s)\n", pid,
-                  address, errno, strerror(errno));
-        return -errno;
+                  address, static_cast<int>(-nread), strerror(static_cast<int>(-nread)));
+        return nread;
     }
 
     if (static_cast<size_t>(nread) < size)
@@ -75,6 +206,30 @@ ssize_t read_memory_native(int pid, uintptr_t address, size_t size, unsigned cha
     return nread;
 }
 
+// Writes through /proc/pid/mem, which needs ptrace access rights but never attaches,
+// so the target's TracerPid stays 0 and it receives no SIGSTOP.
+static ssize_t write_memory_procfs(int pid, void *address, size_t size, unsigned char *buffer)
+{
+    char path[64];
+    snprintf(path, sizeof(path), "/proc/%d/mem", pid);
+    int fd = open(path, O_RDWR);
+    if (fd < 0)
+    {
+        debug_log(LOG_ERROR, "Failed to open %s. Error: %d (%s)\n", path, errno, strerror(errno));
+        return -1;
+    }
+
+    off64_t offset = static_cast<off64_t>(reinterpret_cast<uintptr_t>(address));
+    ssize_t written = pwrite64(fd, buffer, size, offset);
+    if (written < 0)
+    {
+        debug_log(LOG_ERROR, "pwrite to %s failed. Error: %d (%s)\n", path, errno,
+                  strerror(errno));
+    }
+    close(fd);
+    return written;
+}
+
 ssize_t write_memory_native(int pid, void *address, size_t size, unsigned char *buffer)
 {
     if (pid == get_pid_native())
@@ -113,6 +268,10 @@ ssize_t write_memory_native(int pid, void *address, size_t size, unsigned char *
         debug_log(LOG_DEBUG, "Successfully wrote %zd bytes to own process memory\n", written);
         return written;
     }
+    else if (stealth_options & STEALTH_NO_PTRACE)
+    {
+        return write_memory_procfs(pid, address, size, buffer);
+    }
     else
     {
         // Writing to another process
@@ -176,6 +335,11 @@ ssize_t write_memory_native(int pid, void *address, size_t size, unsigned char *
     }
 }
 
+ssize_t write_memory_stealth_native(int pid, void *address, size_t size, unsigned char *buffer)
+{
+    return write_memory_procfs(pid, address, size, buffer);
+}
+
 void enumerate_regions_to_buffer(pid_t pid, char *buffer, size_t buffer_size)
 {
     char maps_file_path[64];
@@ -440,6 +604,191 @@ ModuleInfo *enummodule_native(pid_t pid, size_t *count)
     return result;
 }
 
+static void append_allocation(std::string &out, uintptr_t address, size_t size, const char *kind)
+{
+    char line[128];
+    snprintf(line, sizeof(line), "%lx %zx %s\n", static_cast<unsigned long>(address), size, kind);
+    out += line;
+}
+
+// Walks glibc chunks of the main arena. Chunks cached in tcache or fastbins keep their in-use bit
+// set and are reported as allocations.
+static size_t walk_glibc_heap(int pid, uintptr_t start, uintptr_t end, size_t max_count,
+                              std::string &out)
+{
+    const size_t header_size = 2 * sizeof(size_t);
+    const size_t min_chunk_size = 2 * header_size;
+    std::vector<unsigned char> heap(end - start);
+    ssize_t nread = read_memory_native(pid, start, heap.size(), heap.data());
+    if (nread != static_cast<ssize_t>(heap.size()))
+    {
+        debug_log(LOG_WARN, "Failed to read heap region %lx-%lx\n",
+                  static_cast<unsigned long>(start), static_cast<unsigned long>(end));
+        return 0;
+    }
+
+    size_t count = 0;
+    size_t offset = 0;
+    while (offset + header_size <= heap.size() && count < max_count)
+    {
+        size_t chunk_size;
+        memcpy(&chunk_size, heap.data() + offset + sizeof(size_t), sizeof(size_t));
+        chunk_size &= ~static_cast<size_t>(0x7);
+        if (chunk_size < min_chunk_size || offset + chunk_size > heap.size())
+        {
+            break;
+        }
+
+        size_t next = offset + chunk_size;
+        if (next + header_size > heap.size())
+        {
+            // The last chunk is the top chunk and is always free
+            break;
+        }
+        size_t next_size;
+        memcpy(&next_size, heap.data() + next + sizeof(size_t), sizeof(size_t));
+        if (next_size & 0x1)
+        {
+            append_allocation(out, start + offset + header_size, chunk_size - header_size, "heap");
+            count++;
+        }
+        offset = next;
+    }
+    return count;
+}
+
+char *enumerate_allocations_native(int pid, size_t max_count)
+{
+    char maps_file_path[64];
+    snprintf(maps_file_path, sizeof(maps_file_path), "/proc/%d/maps", pid);
+
+    std::ifstream maps_file(maps_file_path);
+    if (!maps_file.is_open())
+    {
+        debug_log(LOG_ERROR, "Failed to open file: %s\n", maps_file_path);
+        return nullptr;
+    }
+
+    std::string out;
+    size_t count = 0;
+    std::string line;
+    while (std::getline(maps_file, line) && count < max_count)
+    {
+        std::istringstream iss(line);
+        uintptr_t start, end;
+        std::string perms, offset, dev, inode, path;
+        iss >> std::hex >> start;
+        iss.ignore(1, '-');
+        iss >> std::hex >> end;
+        iss >> perms >> offset >> dev >> inode;
+        std::getline(iss >> std::ws, path);
+
+        if (perms[0] != 'r')
+        {
+            continue;
+        }
+        if (path == "[heap]")
+        {
+            count += walk_glibc_heap(pid, start, end, max_count - count, out);
+        }
+        else if (path.rfind("[anon:libc_malloc", 0) == 0 || path.rfind("[anon:scudo:", 0) == 0 ||
+                 path.rfind("[anon:jemalloc", 0) == 0)
+        {
+            // Bionic allocators keep their metadata out of line, so only whole arenas are reported
+            append_allocation(out, start, end - start, "arena");
+            count++;
+        }
+    }
+
+    return strdup(out.c_str());
+}
+
+// Fills one PAGE_* flag byte per page from /proc/<pid>/pagemap. The dirty flag is the soft-dirty
+// bit, which is set on every write since the last clear_refs.
+int query_page_info_native(int pid, uintptr_t address, size_t page_count, unsigned char *flags,
+                           size_t *page_size)
+{
+    *page_size = static_cast<size_t>(sysconf(_SC_PAGESIZE));
+    char pagemap_path[64];
+    snprintf(pagemap_path, sizeof(pagemap_path), "/proc/%d/pagemap", pid);
+
+    int fd = open(pagemap_path, O_RDONLY);
+    if (fd < 0)
+    {
+        debug_log(LOG_ERROR, "Failed to open file: %s (%s)\n", pagemap_path, strerror(errno));
+        return -1;
+    }
+
+    std::vector<uint64_t> entries(page_count);
+    off_t offset = static_cast<off_t>(address / *page_size * sizeof(uint64_t));
+    ssize_t nread = pread(fd, entries.data(), page_count * sizeof(uint64_t), offset);
+    close(fd);
+    if (nread < 0)
+    {
+        debug_log(LOG_ERROR, "Failed to read pagemap for pid %d (%s)\n", pid, strerror(errno));
+        return -1;
+    }
+
+    size_t filled = static_cast<size_t>(nread) / sizeof(uint64_t);
+    for (size_t i = 0; i < filled; i++)
+    {
+        unsigned char page_flags = 0;
+        if (entries[i] & (1ULL << 63)) page_flags |= PAGE_INFO_RESIDENT;
+        if (entries[i] & (1ULL << 62)) page_flags |= PAGE_INFO_SWAPPED;
+        if (entries[i] & (1ULL << 55)) page_flags |= PAGE_INFO_DIRTY;
+        flags[i] = page_flags;
+    }
+    return static_cast<int>(filled);
+}
+
+// protection uses the PROT_READ/PROT_WRITE/PROT_EXEC bit values
+int set_protection_native(int pid, uintptr_t address, size_t size, int protection)
+{
+    if (pid != get_pid_native())
+    {
+        // Foreign mappings cannot be reprotected without injecting a syscall; ptrace and
+        // /proc/pid/mem writes ignore page protection anyway
+        debug_log(LOG_ERROR, "Changing protection is only supported in embedded mode\n");
+        return -1;
+    }
+
+    uintptr_t page_size = getpagesize();
+    uintptr_t page_start = address & ~(page_size - 1);
+    uintptr_t page_end = (address + size + page_size - 1) & ~(page_size - 1);
+    if (mprotect(reinterpret_cast<void *>(page_start), page_end - page_start, protection) != 0)
+    {
+        debug_log(LOG_ERROR, "mprotect failed with error %d (%s)\n", errno, strerror(errno));
+        return -1;
+    }
+    return 0;
+}
+
+int set_stealth_options_native(int options)
+{
+    stealth_options = options & STEALTH_NO_PTRACE;
+    return stealth_options;
+}
+
+int get_pointer_size_native(int pid)
+{
+    // ELF class of the executable: 32-bit apps still run on 64-bit Android devices
+    char path[64];
+    snprintf(path, sizeof(path), "/proc/%d/exe", pid);
+    int fd = open(path, O_RDONLY);
+    if (fd < 0)
+    {
+        return sizeof(void *);
+    }
+    unsigned char ident[EI_NIDENT];
+    ssize_t nread = read(fd, ident, sizeof(ident));
+    close(fd);
+    if (nread != EI_NIDENT || memcmp(ident, ELFMAG, SELFMAG) != 0)
+    {
+        return sizeof(void *);
+    }
+    return ident[EI_CLASS] == ELFCLASS32 ? 4 : 8;
+}
+
 int native_init(int mode)
 {
 #ifdef TARGET_IS_ANDROID
@@ -469,4 +818,236 @@ int native_init(int mode)
     dlclose(handle);
 #endif
     return 1;
-}
\ No newline at end of file
+}
+// One "tid name" line per thread, named from /proc/<pid>/task/<tid>/comm
+char *enumerate_threads_native(int pid)
+{
+    char task_path[64];
+    snprintf(task_path, sizeof(task_path), "/proc/%d/task", pid);
+    DIR *dir = opendir(task_path);
+    if (dir == nullptr)
+    {
+        debug_log(LOG_ERROR, "Failed to open directory: %s (%s)\n", task_path, strerror(errno));
+        return nullptr;
+    }
+
+    std::string out;
+    struct dirent *entry;
+    while ((entry = readdir(dir)) != nullptr)
+    {
+        if (entry->d_name[0] < '0' || entry->d_name[0] > '9')
+        {
+            continue;
+        }
+        std::ifstream comm(std::string(task_path) + "/" + entry->d_name + "/comm");
+        std::string name;
+        std::getline(comm, name);
+        out += std::string(entry->d_name) + " " + name + "\n";
+    }
+    closedir(dir);
+    return strdup(out.c_str());
+}
+
+// One "tid sp start end" line (hex after the tid) per thread whose stack pointer is known.
+// The stack pointer comes from /proc/<pid>/task/<tid>/syscall, which has it for every thread
+// that is not running on a CPU right now; the stack is the mapping containing it.
+char *enumerate_thread_stacks_native(int pid)
+{
+    char maps_path[64];
+    snprintf(maps_path, sizeof(maps_path), "/proc/%d/maps", pid);
+    std::ifstream maps_file(maps_path);
+    if (!maps_file.is_open())
+    {
+        debug_log(LOG_ERROR, "Failed to open file: %s\n", maps_path);
+        return nullptr;
+    }
+    std::vector<std::pair<uintptr_t, uintptr_t>> mappings;
+    uintptr_t main_stack_start = 0;
+    uintptr_t main_stack_end = 0;
+    std::string line;
+    while (std::getline(maps_file, line))
+    {
+        uintptr_t start = 0;
+        uintptr_t end = 0;
+        if (sscanf(line.c_str(), "%lx-%lx", &start, &end) != 2)
+        {
+            continue;
+        }
+        mappings.emplace_back(start, end);
+        if (line.find("[stack]") != std::string::npos)
+        {
+            main_stack_start = start;
+            main_stack_end = end;
+        }
+    }
+
+    char task_path[64];
+    snprintf(task_path, sizeof(task_path), "/proc/%d/task", pid);
+    DIR *dir = opendir(task_path);
+    if (dir == nullptr)
+    {
+        debug_log(LOG_ERROR, "Failed to open directory: %s (%s)\n", task_path, strerror(errno));
+        return nullptr;
+    }
+
+    std::string out;
+    struct dirent *entry;
+    while ((entry = readdir(dir)) != nullptr)
+    {
+        if (entry->d_name[0] < '0' || entry->d_name[0] > '9')
+        {
+            continue;
+        }
+        int tid = atoi(entry->d_name);
+
+        // "nr arg1 .. arg6 sp pc" inside a syscall, "-1 sp pc" when blocked outside one
+        std::ifstream syscall_file(std::string(task_path) + "/" + entry->d_name + "/syscall");
+        std::vector<std::string> fields;
+        std::string field;
+        while (syscall_file >> field)
+        {
+            fields.push_back(field);
+        }
+        uintptr_t sp = 0;
+        if (fields.size() >= 3 && fields[0] != "running")
+        {
+            sp = strtoull(fields[fields.size() - 2].c_str(), nullptr, 16);
+        }
+
+        uintptr_t start = 0;
+        uintptr_t end = 0;
+        for (const auto &mapping : mappings)
+        {
+            if (sp >= mapping.first && sp < mapping.second)
+            {
+                start = mapping.first;
+                end = mapping.second;
+                break;
+            }
+        }
+        if (start == 0 && tid == pid && main_stack_start != 0)
+        {
+            // The main thread is on a CPU; fall back to the whole [stack] mapping
+            start = main_stack_start;
+            end = main_stack_end;
+            sp = start;
+        }
+        if (start == 0)
+        {
+            continue;
+        }
+        char stack_line[96];
+        snprintf(stack_line, sizeof(stack_line), "%d %lx %lx %lx\n", tid,
+                 static_cast<unsigned long>(sp), static_cast<unsigned long>(start),
+                 static_cast<unsigned long>(end));
+        out += stack_line;
+    }
+    closedir(dir);
+    return strdup(out.c_str());
+}
+
+// The thread pointer is only exposed through ptrace, so each thread is seized, interrupted
+// for the register read and released again
+static bool read_thread_pointer(int tid, uintptr_t *thread_pointer)
+{
+    if (ptrace(PTRACE_SEIZE, tid, NULL, NULL) == -1)
+    {
+        return false;
+    }
+    bool found = false;
+    if (ptrace(PTRACE_INTERRUPT, tid, NULL, NULL) != -1 && waitpid(tid, NULL, __WALL) == tid)
+    {
+#if defined(__x86_64__)
+        struct user_regs_struct regs;
+        if (ptrace(PTRACE_GETREGS, tid, NULL, &regs) != -1)
+        {
+            *thread_pointer = regs.fs_base;
+            found = true;
+        }
+#elif defined(__aarch64__)
+        uint64_t tpidr = 0;
+        struct iovec iov = {&tpidr, sizeof(tpidr)};
+        if (ptrace(PTRACE_GETREGSET, tid, NT_ARM_TLS, &iov) != -1)
+        {
+            *thread_pointer = tpidr;
+            found = true;
+        }
+#endif
+    }
+    ptrace(PTRACE_DETACH, tid, NULL, NULL);
+    return found && *thread_pointer != 0;
+}
+
+// "tid thread_pointer start end" per thread. Static TLS sits right below the thread pointer
+// on x86-64 and right above it on ARM64, with the thread control block on the other side, so
+// the block is block_size bytes either way, clipped to the mapping holding the pointer.
+char *enumerate_tls_blocks_native(int pid, size_t block_size)
+{
+    if (stealth_options & STEALTH_NO_PTRACE)
+    {
+        debug_log(LOG_ERROR, "TLS enumeration needs ptrace, which stealth options forbid\n");
+        return nullptr;
+    }
+    char maps_path[64];
+    snprintf(maps_path, sizeof(maps_path), "/proc/%d/maps", pid);
+    std::ifstream maps_file(maps_path);
+    if (!maps_file.is_open())
+    {
+        debug_log(LOG_ERROR, "Failed to open file: %s\n", maps_path);
+        return nullptr;
+    }
+    std::vector<std::pair<uintptr_t, uintptr_t>> mappings;
+    std::string line;
+    while (std::getline(maps_file, line))
+    {
+        uintptr_t start = 0;
+        uintptr_t end = 0;
+        if (sscanf(line.c_str(), "%lx-%lx", &start, &end) == 2)
+        {
+            mappings.emplace_back(start, end);
+        }
+    }
+
+    char task_path[64];
+    snprintf(task_path, sizeof(task_path), "/proc/%d/task", pid);
+    DIR *dir = opendir(task_path);
+    if (dir == nullptr)
+    {
+        debug_log(LOG_ERROR, "Failed to open directory: %s (%s)\n", task_path, strerror(errno));
+        return nullptr;
+    }
+
+    std::string out;
+    struct dirent *entry;
+    while ((entry = readdir(dir)) != nullptr)
+    {
+        if (entry->d_name[0] < '0' || entry->d_name[0] > '9')
+        {
+            continue;
+        }
+        int tid = atoi(entry->d_name);
+        uintptr_t thread_pointer = 0;
+        if (!read_thread_pointer(tid, &thread_pointer))
+        {
+            debug_log(LOG_DEBUG, "No thread pointer for thread %d (%s)\n", tid, strerror(errno));
+            continue;
+        }
+        for (const auto &mapping : mappings)
+        {
+            if (thread_pointer >= mapping.first && thread_pointer < mapping.second)
+            {
+                uintptr_t start =
+                    std::max(mapping.first, thread_pointer - std::min(thread_pointer, block_size));
+                uintptr_t end = std::min(mapping.second, thread_pointer + block_size);
+                char block_line[96];
+                snprintf(block_line, sizeof(block_line), "%d %lx %lx %lx\n", tid,
+                         static_cast<unsigned long>(thread_pointer),
+                         static_cast<unsigned long>(start), static_cast<unsigned long>(end));
+                out += block_line;
+                break;
+            }
+        }
+    }
+    closedir(dir);
+    return strdup(out.c_str());
+}
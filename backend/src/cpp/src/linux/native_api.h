@@ -14,9 +14,12 @@
 #include <sys/stat.h>
 #include <sys/types.h>
 #include <sys/uio.h>
+#include <sys/user.h>
 #include <sys/wait.h>
 #include <unistd.h>
 
+#include <algorithm>
+#include <atomic>
 #include <cstdarg>
 #include <cstdio>
 #include <cstring>
@@ -49,17 +52,55 @@ typedef struct
     char *modulename;
 } ModuleInfo;
 
+enum PageInfoFlags
+{
+    PAGE_INFO_RESIDENT = 1,
+    PAGE_INFO_DIRTY = 2,
+    PAGE_INFO_SWAPPED = 4
+};
+
+enum StealthOptions
+{
+    STEALTH_NO_PTRACE = 1,
+    STEALTH_MINIMAL_EXCEPTION_PORTS = 2
+};
+
+// Ways of reading another process, tried in this order until one is permitted
+enum ReadMethod
+{
+    READ_PROCESS_VM_READV,
+    READ_PROC_MEM,
+    READ_PTRACE,
+    READ_METHOD_COUNT
+};
+
 extern "C" void native_log(int level, const char *message);
 int debug_log(LogLevel level, const char *format, ...);
+ssize_t read_memory_with(ReadMethod method, int pid, uintptr_t address, size_t size,
+                         unsigned char *buffer);
+const char *read_method_name(int method);
+int current_read_method();
 extern "C" pid_t get_pid_native();
 extern "C" ssize_t read_memory_native(int pid, uintptr_t address, size_t size,
                                       unsigned char *buffer);
 extern "C" ssize_t write_memory_native(int pid, void *address, size_t size, unsigned char *buffer);
+extern "C" ssize_t write_memory_stealth_native(int pid, void *address, size_t size,
+                                               unsigned char *buffer);
 extern "C" void enumerate_regions_to_buffer(pid_t pid, char *buffer, size_t buffer_size);
 extern "C" ProcessInfo *enumprocess_native(size_t *count);
 extern "C" bool suspend_process(pid_t pid);
 extern "C" bool resume_process(pid_t pid);
 extern "C" ModuleInfo *enummodule_native(pid_t pid, size_t *count);
+extern "C" char *enumerate_allocations_native(int pid, size_t max_count);
+extern "C" int query_page_info_native(int pid, uintptr_t address, size_t page_count,
+                                      unsigned char *flags, size_t *page_size);
+extern "C" int set_stealth_options_native(int options);
+extern "C" int set_protection_native(int pid, uintptr_t address, size_t size, int protection);
 extern "C" int native_init(int mode);
+extern "C" int get_pointer_size_native(int pid);
+extern "C" char *diagnose_native(int pid);
+extern "C" char *enumerate_threads_native(int pid);
+extern "C" char *enumerate_thread_stacks_native(int pid);
+extern "C" char *enumerate_tls_blocks_native(int pid, size_t block_size);
 
 #endif
\ No newline at end of file
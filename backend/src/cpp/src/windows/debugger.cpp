@@ -1,29 +1,741 @@
 #include "debugger.h"
 
+#include <condition_variable>
+#include <map>
+#include <mutex>
+#include <thread>
+
+// Hardware watchpoints and breakpoints through the x86 debug registers. The debug registers
+// of every thread hold the same four slots; a dedicated thread attaches with
+// DebugActiveProcess and reports hits from its debug event loop. 32-bit (WOW64) targets
+// are handled through the Wow64 thread context functions.
+//
+// A watchpoint can be scoped to some threads, by id or by a substring of the thread
+// description. Other threads get the slot disabled, and a name filter is re-checked
+// periodically so threads that start or get renamed later are armed too.
+
+namespace
+{
+const int SLOT_COUNT = 4;
+const DWORD RESUME_FLAG = 0x10000;
+const DWORD SCOPE_REFRESH_MS = 250;
+
+enum class SlotKind
+{
+    NONE,
+    WATCHPOINT,
+    BREAKPOINT
+};
+
+struct Slot
+{
+    SlotKind kind = SlotKind::NONE;
+    uint64_t address = 0;
+    int size = 0;
+    // DR7 R/W bits: 0 = execute, 1 = write, 3 = read/write
+    int access = 0;
+    // Breakpoints only; removed after this many hits when positive
+    int remaining_hits = 0;
+    // Watchpoints only; both empty means every thread
+    std::vector<uint64_t> thread_ids;
+    std::string thread_filter;
+
+    // Run on every hit before the thread resumes; register_index -1 leaves registers alone
+    int register_index = -1;
+    uint64_t register_value = 0;
+    // Breakpoints only: bytes the instruction pointer moves forward, skipping the instruction
+    size_t skip_length = 0;
+
+    bool is_scoped() const
+    {
+        return !thread_ids.empty() || !thread_filter.empty();
+    }
+
+    bool applies_to(DWORD thread_id, const std::string &name) const
+    {
+        if (!is_scoped())
+        {
+            return true;
+        }
+        for (uint64_t id : thread_ids)
+        {
+            if (id == thread_id)
+            {
+                return true;
+            }
+        }
+        return !thread_filter.empty() && name.find(thread_filter) != std::string::npos;
+    }
+};
+
+struct DebuggerState
+{
+    std::mutex mutex;
+    std::condition_variable attached_changed;
+    int pid = 0;
+    bool attached = false;
+    bool attach_failed = false;
+    bool wow64 = false;
+    Slot slots[SLOT_COUNT];
+    // Bit per slot enabled on each thread, so refreshes only touch threads that change
+    std::map<DWORD, unsigned> thread_masks;
+};
+
+DebuggerState state;
+
+int length_bits(int size)
+{
+    switch (size)
+    {
+        case 1:
+            return 0;
+        case 2:
+            return 1;
+        case 8:
+            return 2;
+        default:
+            return 3;
+    }
+}
+
+DWORD64 build_dr7(const Slot *slots, unsigned mask)
+{
+    DWORD64 dr7 = 0;
+    for (int i = 0; i < SLOT_COUNT; i++)
+    {
+        if (slots[i].kind == SlotKind::NONE || (mask & (1u << i)) == 0)
+        {
+            continue;
+        }
+        dr7 |= 1ULL << (i * 2);
+        dr7 |= static_cast<DWORD64>(slots[i].access) << (16 + i * 4);
+        dr7 |= static_cast<DWORD64>(length_bits(slots[i].size)) << (18 + i * 4);
+    }
+    return dr7;
+}
+
+const char *REGISTERS_64[] = {"rax", "rbx", "rcx", "rdx", "rsi", "rdi", "rbp", "rsp", "r8",
+                              "r9",  "r10", "r11", "r12", "r13", "r14", "r15", "pc"};
+const char *REGISTERS_32[] = {"eax", "ebx", "ecx", "edx", "esi", "edi", "ebp", "esp", "pc"};
+
+// Index into the register list of the target's architecture, or -1
+int register_index(const char *name, bool wow64)
+{
+    const char **names = wow64 ? REGISTERS_32 : REGISTERS_64;
+    size_t count = wow64 ? sizeof(REGISTERS_32) / sizeof(REGISTERS_32[0])
+                         : sizeof(REGISTERS_64) / sizeof(REGISTERS_64[0]);
+    for (size_t i = 0; i < count; i++)
+    {
+        if (strcmp(name, names[i]) == 0 || (strcmp(names[i], "pc") == 0 &&
+                                            strcmp(name, wow64 ? "eip" : "rip") == 0))
+        {
+            return static_cast<int>(i);
+        }
+    }
+    return -1;
+}
+
+// Overwrites the register and skips the instruction as configured for the slot
+void apply_hit_action(HANDLE thread, bool wow64, const Slot &slot)
+{
+    if (slot.register_index < 0 && slot.skip_length == 0)
+    {
+        return;
+    }
+    if (wow64)
+    {
+        WOW64_CONTEXT context = {};
+        context.ContextFlags = WOW64_CONTEXT_FULL;
+        if (!Wow64GetThreadContext(thread, &context))
+        {
+            return;
+        }
+        DWORD *registers[] = {&context.Eax, &context.Ebx, &context.Ecx, &context.Edx, &context.Esi,
+                              &context.Edi, &context.Ebp, &context.Esp, &context.Eip};
+        if (slot.register_index >= 0)
+        {
+            *registers[slot.register_index] = static_cast<DWORD>(slot.register_value);
+        }
+        context.Eip += static_cast<DWORD>(slot.skip_length);
+        Wow64SetThreadContext(thread, &context);
+    }
+    else
+    {
+        CONTEXT context = {};
+        context.ContextFlags = CONTEXT_FULL;
+        if (!GetThreadContext(thread, &context))
+        {
+            return;
+        }
+        DWORD64 *registers[] = {&context.Rax, &context.Rbx, &context.Rcx, &context.Rdx,
+                                &context.Rsi, &context.Rdi, &context.Rbp, &context.Rsp,
+                                &context.R8,  &context.R9,  &context.R10, &context.R11,
+                                &context.R12, &context.R13, &context.R14, &context.R15,
+                                &context.Rip};
+        if (slot.register_index >= 0)
+        {
+            *registers[slot.register_index] = slot.register_value;
+        }
+        context.Rip += slot.skip_length;
+        SetThreadContext(thread, &context);
+    }
+}
+
+// Slots enabled on a thread. Called with the state locked.
+unsigned thread_mask(DWORD thread_id)
+{
+    unsigned mask = 0;
+    std::string name;
+    bool named = false;
+    for (int i = 0; i < SLOT_COUNT; i++)
+    {
+        const Slot &slot = state.slots[i];
+        if (slot.kind == SlotKind::NONE)
+        {
+            continue;
+        }
+        if (!slot.thread_filter.empty() && !named)
+        {
+            name = get_thread_name(thread_id);
+            named = true;
+        }
+        if (slot.applies_to(thread_id, name))
+        {
+            mask |= 1u << i;
+        }
+    }
+    return mask;
+}
+
+// Copies the enabled slots into the debug registers of one thread. Called with the state
+// locked.
+bool apply_to_thread(HANDLE thread, bool wow64, const Slot *slots, unsigned mask)
+{
+    bool suspended = SuspendThread(thread) != (DWORD)-1;
+    bool applied;
+    if (wow64)
+    {
+        WOW64_CONTEXT context = {};
+        context.ContextFlags = WOW64_CONTEXT_DEBUG_REGISTERS;
+        applied = Wow64GetThreadContext(thread, &context);
+        if (applied)
+        {
+            context.Dr0 = static_cast<DWORD>(slots[0].address);
+            context.Dr1 = static_cast<DWORD>(slots[1].address);
+            context.Dr2 = static_cast<DWORD>(slots[2].address);
+            context.Dr3 = static_cast<DWORD>(slots[3].address);
+            context.Dr7 = static_cast<DWORD>(build_dr7(slots, mask));
+            applied = Wow64SetThreadContext(thread, &context);
+        }
+    }
+    else
+    {
+        CONTEXT context = {};
+        context.ContextFlags = CONTEXT_DEBUG_REGISTERS;
+        applied = GetThreadContext(thread, &context);
+        if (applied)
+        {
+            context.Dr0 = slots[0].address;
+            context.Dr1 = slots[1].address;
+            context.Dr2 = slots[2].address;
+            context.Dr3 = slots[3].address;
+            context.Dr7 = build_dr7(slots, mask);
+            applied = SetThreadContext(thread, &context);
+        }
+    }
+    if (suspended)
+    {
+        ResumeThread(thread);
+    }
+    return applied;
+}
+
+// Applies the slots to every thread, or with `changed_only` just to threads whose enabled
+// slots differ from what they last received
+bool apply_to_all_threads(bool changed_only = false)
+{
+    HANDLE snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0);
+    if (snapshot == INVALID_HANDLE_VALUE)
+    {
+        debug_log(LOG_ERROR, "Failed to create thread snapshot. Error code: %lu", GetLastError());
+        return false;
+    }
+
+    THREADENTRY32 entry;
+    entry.dwSize = sizeof(entry);
+    bool applied_any = false;
+    std::map<DWORD, unsigned> masks;
+    if (Thread32First(snapshot, &entry))
+    {
+        do
+        {
+            if (entry.th32OwnerProcessID != static_cast<DWORD>(state.pid))
+            {
+                continue;
+            }
+            unsigned mask = thread_mask(entry.th32ThreadID);
+            auto previous = state.thread_masks.find(entry.th32ThreadID);
+            if (changed_only && previous != state.thread_masks.end() && previous->second == mask)
+            {
+                masks[entry.th32ThreadID] = mask;
+                applied_any = true;
+                continue;
+            }
+            HANDLE thread = OpenThread(
+                THREAD_GET_CONTEXT | THREAD_SET_CONTEXT | THREAD_SUSPEND_RESUME, FALSE,
+                entry.th32ThreadID);
+            if (thread == NULL)
+            {
+                continue;
+            }
+            if (apply_to_thread(thread, state.wow64, state.slots, mask))
+            {
+                masks[entry.th32ThreadID] = mask;
+                applied_any = true;
+            }
+            else
+            {
+                debug_log(LOG_WARN, "Failed to set debug registers of thread %lu. Error code: %lu",
+                          entry.th32ThreadID, GetLastError());
+            }
+            CloseHandle(thread);
+        } while (Thread32Next(snapshot, &entry));
+    }
+    CloseHandle(snapshot);
+    // Exited threads drop out of the map
+    state.thread_masks = masks;
+    return applied_any;
+}
+
+void append_register(std::string &json, const char *name, uint64_t value)
+{
+    char field[64];
+    snprintf(field, sizeof(field), "%s\"%s\":\"0x%016llX\"", json.size() > 1 ? "," : "", name,
+             static_cast<unsigned long long>(value));
+    json += field;
+}
+
+// Same shape as the other platforms: register names mapped to hex strings, plus "pc"
+std::string registers_json(HANDLE thread, bool wow64, uint64_t *dr6, uint64_t *pc)
+{
+    std::string json = "{";
+    if (wow64)
+    {
+        WOW64_CONTEXT context = {};
+        context.ContextFlags = WOW64_CONTEXT_FULL | WOW64_CONTEXT_DEBUG_REGISTERS;
+        if (Wow64GetThreadContext(thread, &context))
+        {
+            append_register(json, "eax", context.Eax);
+            append_register(json, "ebx", context.Ebx);
+            append_register(json, "ecx", context.Ecx);
+            append_register(json, "edx", context.Edx);
+            append_register(json, "esi", context.Esi);
+            append_register(json, "edi", context.Edi);
+            append_register(json, "ebp", context.Ebp);
+            append_register(json, "esp", context.Esp);
+            append_register(json, "eflags", context.EFlags);
+            append_register(json, "pc", context.Eip);
+            *dr6 = context.Dr6;
+            *pc = context.Eip;
+        }
+    }
+    else
+    {
+        CONTEXT context = {};
+        context.ContextFlags = CONTEXT_FULL | CONTEXT_DEBUG_REGISTERS;
+        if (GetThreadContext(thread, &context))
+        {
+            append_register(json, "rax", context.Rax);
+            append_register(json, "rbx", context.Rbx);
+            append_register(json, "rcx", context.Rcx);
+            append_register(json, "rdx", context.Rdx);
+            append_register(json, "rsi", context.Rsi);
+            append_register(json, "rdi", context.Rdi);
+            append_register(json, "rbp", context.Rbp);
+            append_register(json, "rsp", context.Rsp);
+            append_register(json, "r8", context.R8);
+            append_register(json, "r9", context.R9);
+            append_register(json, "r10", context.R10);
+            append_register(json, "r11", context.R11);
+            append_register(json, "r12", context.R12);
+            append_register(json, "r13", context.R13);
+            append_register(json, "r14", context.R14);
+            append_register(json, "r15", context.R15);
+            append_register(json, "eflags", context.EFlags);
+            append_register(json, "pc", context.Rip);
+            *dr6 = context.Dr6;
+            *pc = context.Rip;
+        }
+    }
+    return json;
+}
+
+// Clears DR6 and, for breakpoints, sets the resume flag so the instruction can execute
+void acknowledge(HANDLE thread, bool wow64, bool resume)
+{
+    if (wow64)
+    {
+        WOW64_CONTEXT context = {};
+        context.ContextFlags = WOW64_CONTEXT_CONTROL | WOW64_CONTEXT_DEBUG_REGISTERS;
+        if (Wow64GetThreadContext(thread, &context))
+        {
+            context.Dr6 = 0;
+            if (resume)
+            {
+                context.EFlags |= RESUME_FLAG;
+            }
+            Wow64SetThreadContext(thread, &context);
+        }
+    }
+    else
+    {
+        CONTEXT context = {};
+        context.ContextFlags = CONTEXT_CONTROL | CONTEXT_DEBUG_REGISTERS;
+        if (GetThreadContext(thread, &context))
+        {
+            context.Dr6 = 0;
+            if (resume)
+            {
+                context.EFlags |= RESUME_FLAG;
+            }
+            SetThreadContext(thread, &context);
+        }
+    }
+}
+
+// Returns true when the single step came from one of the slots
+bool handle_single_step(DWORD thread_id)
+{
+    HANDLE thread = OpenThread(THREAD_GET_CONTEXT | THREAD_SET_CONTEXT | THREAD_SUSPEND_RESUME,
+                               FALSE, thread_id);
+    if (thread == NULL)
+    {
+        return false;
+    }
+
+    std::unique_lock<std::mutex> lock(state.mutex);
+    uint64_t dr6 = 0;
+    uint64_t pc = 0;
+    std::string json = registers_json(thread, state.wow64, &dr6, &pc);
+
+    int hit = -1;
+    for (int i = 0; i < SLOT_COUNT; i++)
+    {
+        if ((dr6 & (1ULL << i)) && state.slots[i].kind != SlotKind::NONE)
+        {
+            hit = i;
+            break;
+        }
+    }
+    if (hit < 0)
+    {
+        CloseHandle(thread);
+        return false;
+    }
+
+    Slot &slot = state.slots[hit];
+    bool is_breakpoint = slot.kind == SlotKind::BREAKPOINT;
+    if (!is_breakpoint)
+    {
+        append_register(json, "memory", slot.address);
+    }
+    append_register(json, "thread_id", thread_id);
+    json += "}";
+    acknowledge(thread, state.wow64, is_breakpoint);
+    // Copied because a finished breakpoint clears its slot below
+    Slot hit_slot = slot;
+    bool wow64 = state.wow64;
+
+    if (is_breakpoint && slot.remaining_hits > 0 && --slot.remaining_hits == 0)
+    {
+        slot = Slot();
+        apply_to_all_threads();
+    }
+    int pid = state.pid;
+    lock.unlock();
+
+    if (send_register_json(json.c_str(), pid))
+    {
+        apply_hit_action(thread, wow64, hit_slot);
+    }
+    CloseHandle(thread);
+    return true;
+}
+
+void debug_loop(int pid)
+{
+    bool attached = DebugActiveProcess(pid);
+    {
+        std::lock_guard<std::mutex> lock(state.mutex);
+        state.attached = attached;
+        state.attach_failed = !attached;
+    }
+    state.attached_changed.notify_all();
+    if (!attached)
+    {
+        debug_log(LOG_ERROR, "DebugActiveProcess failed for process %d. Error code: %lu", pid,
+                  GetLastError());
+        return;
+    }
+    // Detaching must never take the target down with the server
+    DebugSetProcessKillOnExit(FALSE);
+
+    DEBUG_EVENT event;
+    bool running = true;
+    while (running)
+    {
+        if (!WaitForDebugEvent(&event, SCOPE_REFRESH_MS))
+        {
+            if (GetLastError() != ERROR_SEM_TIMEOUT)
+            {
+                break;
+            }
+            std::lock_guard<std::mutex> lock(state.mutex);
+            bool filtered = false;
+            for (const Slot &slot : state.slots)
+            {
+                filtered = filtered || !slot.thread_filter.empty();
+            }
+            if (filtered)
+            {
+                apply_to_all_threads(true);
+            }
+            continue;
+        }
+        DWORD status = DBG_CONTINUE;
+        switch (event.dwDebugEventCode)
+        {
+            case EXCEPTION_DEBUG_EVENT:
+            {
+                DWORD code = event.u.Exception.ExceptionRecord.ExceptionCode;
+                if (code == EXCEPTION_SINGLE_STEP || code == STATUS_WX86_SINGLE_STEP)
+                {
+                    if (!handle_single_step(event.dwThreadId))
+                    {
+                        status = DBG_EXCEPTION_NOT_HANDLED;
+                    }
+                }
+                else if (code != EXCEPTION_BREAKPOINT && code != STATUS_WX86_BREAKPOINT)
+                {
+                    // Everything else belongs to the target's own handlers
+                    status = DBG_EXCEPTION_NOT_HANDLED;
+                }
+                break;
+            }
+            case CREATE_THREAD_DEBUG_EVENT:
+            {
+                std::lock_guard<std::mutex> lock(state.mutex);
+                // New threads are usually still unnamed; the periodic refresh catches names
+                unsigned mask = thread_mask(event.dwThreadId);
+                if (apply_to_thread(event.u.CreateThread.hThread, state.wow64, state.slots, mask))
+                {
+                    state.thread_masks[event.dwThreadId] = mask;
+                }
+                break;
+            }
+            case CREATE_PROCESS_DEBUG_EVENT:
+                if (event.u.CreateProcessInfo.hFile != NULL)
+                {
+                    CloseHandle(event.u.CreateProcessInfo.hFile);
+                }
+                break;
+            case LOAD_DLL_DEBUG_EVENT:
+                if (event.u.LoadDll.hFile != NULL)
+                {
+                    CloseHandle(event.u.LoadDll.hFile);
+                }
+                break;
+            case EXIT_PROCESS_DEBUG_EVENT:
+                running = false;
+                break;
+            default:
+                break;
+        }
+        ContinueDebugEvent(event.dwProcessId, event.dwThreadId, status);
+    }
+
+    std::lock_guard<std::mutex> lock(state.mutex);
+    state.attached = false;
+    state.thread_masks.clear();
+    for (Slot &slot : state.slots)
+    {
+        slot = Slot();
+    }
+}
+
+int find_slot(uint64_t address, SlotKind kind)
+{
+    for (int i = 0; i < SLOT_COUNT; i++)
+    {
+        if (state.slots[i].kind == kind && state.slots[i].address == address)
+        {
+            return i;
+        }
+    }
+    return -1;
+}
+
+int set_slot(uint64_t address, int size, int access, SlotKind kind, int hit_count,
+             const std::vector<uint64_t> &thread_ids = {}, const std::string &thread_filter = "")
+{
+    std::lock_guard<std::mutex> lock(state.mutex);
+    if (!state.attached)
+    {
+        return -1;
+    }
+    if (state.wow64 && address > 0xFFFFFFFFULL)
+    {
+        debug_log(LOG_ERROR, "Address 0x%llx is outside a 32-bit target",
+                  static_cast<unsigned long long>(address));
+        return -1;
+    }
+    int index = find_slot(address, kind);
+    if (index < 0)
+    {
+        index = find_slot(0, SlotKind::NONE);
+    }
+    if (index < 0)
+    {
+        debug_log(LOG_ERROR, "All %d debug registers are in use", SLOT_COUNT);
+        return -1;
+    }
+    Slot &slot = state.slots[index];
+    slot.kind = kind;
+    slot.address = address;
+    slot.size = size;
+    slot.access = access;
+    slot.remaining_hits = hit_count;
+    slot.thread_ids = thread_ids;
+    slot.thread_filter = thread_filter;
+    bool applied = apply_to_all_threads();
+    // A name filter may only match threads started later
+    return applied || !thread_filter.empty() ? 0 : -1;
+}
+
+int clear_slot(uint64_t address, SlotKind kind)
+{
+    std::lock_guard<std::mutex> lock(state.mutex);
+    int index = find_slot(address, kind);
+    if (index < 0)
+    {
+        return -1;
+    }
+    state.slots[index] = Slot();
+    return apply_to_all_threads() ? 0 : -1;
+}
+
+int set_action(uint64_t address, SlotKind kind, const char *register_name, uint64_t value,
+               size_t skip_length)
+{
+    std::lock_guard<std::mutex> lock(state.mutex);
+    int index = find_slot(address, kind);
+    if (index < 0)
+    {
+        debug_log(LOG_ERROR, "No watchpoint or breakpoint at 0x%llx",
+                  static_cast<unsigned long long>(address));
+        return -1;
+    }
+    int register_number = -1;
+    if (register_name != NULL)
+    {
+        register_number = register_index(register_name, state.wow64);
+        if (register_number < 0)
+        {
+            debug_log(LOG_ERROR, "Unknown register: %s", register_name);
+            return -1;
+        }
+    }
+    // Data breakpoints trap after the access, so there is nothing left to skip
+    if (kind == SlotKind::WATCHPOINT && skip_length > 0)
+    {
+        debug_log(LOG_ERROR, "x86 watchpoints report after the access and cannot skip it");
+        return -1;
+    }
+    Slot &slot = state.slots[index];
+    slot.register_index = register_number;
+    slot.register_value = value;
+    slot.skip_length = skip_length;
+    return 0;
+}
+}  // namespace
+
 extern "C"
 {
     bool debugger_new(int pid)
     {
-        return true;
+        std::unique_lock<std::mutex> lock(state.mutex);
+        if (state.attached && state.pid == pid)
+        {
+            return true;
+        }
+        if (state.attached)
+        {
+            debug_log(LOG_ERROR, "Already debugging process %d", state.pid);
+            return false;
+        }
+
+        HANDLE process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid);
+        BOOL wow64 = FALSE;
+        if (process != NULL)
+        {
+            IsWow64Process(process, &wow64);
+            CloseHandle(process);
+        }
+        state.pid = pid;
+        state.wow64 = wow64;
+        state.attach_failed = false;
+
+        std::thread(debug_loop, pid).detach();
+        state.attached_changed.wait(lock, [] { return state.attached || state.attach_failed; });
+        return state.attached;
     }
 
-    int set_watchpoint_native(uint64_t address, int size, WatchpointType type)
+    int set_watchpoint_native(uint64_t address, int size, WatchpointType type,
+                              const uint64_t *thread_ids, size_t thread_count,
+                              const char *thread_filter)
     {
-        return 0;
+        if (size != 1 && size != 2 && size != 4 && size != 8)
+        {
+            debug_log(LOG_ERROR, "Hardware watchpoints cover 1, 2, 4 or 8 bytes, not %d", size);
+            return -1;
+        }
+        if (address % size != 0)
+        {
+            debug_log(LOG_ERROR, "Watchpoint address must be aligned to its size");
+            return -1;
+        }
+        // x86 has no read-only watchpoints, so reads also stop on writes
+        int access = type == WatchpointType::WRITE ? 1 : 3;
+        return set_slot(address, size, access, SlotKind::WATCHPOINT, 0,
+                        std::vector<uint64_t>(thread_ids, thread_ids + thread_count),
+                        thread_filter ? thread_filter : "");
     }
 
     int remove_watchpoint_native(uint64_t address)
     {
-        return 0;
+        return clear_slot(address, SlotKind::WATCHPOINT);
+    }
+
+    int set_page_watchpoint_native(uint64_t address, size_t size, WatchpointType type)
+    {
+        debug_log(LOG_ERROR, "Page watchpoints are not supported on Windows");
+        return -1;
     }
 
     int set_breakpoint_native(uint64_t address, int hit_count)
     {
-        return 0;
+        return set_slot(address, 1, 0, SlotKind::BREAKPOINT, hit_count);
     }
 
     int remove_breakpoint_native(uint64_t address)
     {
-        return 0;
+        return clear_slot(address, SlotKind::BREAKPOINT);
+    }
+
+    int set_hit_action_native(uint64_t address, int is_breakpoint, const char *register_name,
+                              uint64_t value, size_t skip_length)
+    {
+        return set_action(address, is_breakpoint ? SlotKind::BREAKPOINT : SlotKind::WATCHPOINT,
+                          register_name, value, skip_length);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,113 @@
+#include <stdlib.h>
+#include <string.h>
+
+#include <string>
+
+#include "native_api.h"
+
+// Explains why the server cannot inspect a process: protected processes and processes of
+// other users need an elevated server with SeDebugPrivilege.
+
+namespace
+{
+std::string quote(const std::string &value)
+{
+    std::string quoted = "\"";
+    for (char c : value)
+    {
+        if (c == '"' || c == '\\')
+        {
+            quoted += '\\';
+        }
+        if (static_cast<unsigned char>(c) >= 0x20)
+        {
+            quoted += c;
+        }
+    }
+    return quoted + "\"";
+}
+
+const char *boolean(bool value)
+{
+    return value ? "true" : "false";
+}
+
+bool is_elevated()
+{
+    HANDLE token = NULL;
+    if (!OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &token))
+    {
+        return false;
+    }
+    TOKEN_ELEVATION elevation = {};
+    DWORD size = 0;
+    bool elevated = GetTokenInformation(token, TokenElevation, &elevation, sizeof(elevation),
+                                        &size) &&
+                    elevation.TokenIsElevated;
+    CloseHandle(token);
+    return elevated;
+}
+
+void add_problem(std::string &problems, const char *code, const std::string &message,
+                 const std::string &fix)
+{
+    if (problems.size() > 1)
+    {
+        problems += ",";
+    }
+    problems += "{\"code\":" + quote(code) + ",\"message\":" + quote(message) +
+                ",\"fix\":" + quote(fix) + "}";
+}
+}  // namespace
+
+char *diagnose_native(int pid)
+{
+    bool elevated = is_elevated();
+    HANDLE process =
+        OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ | PROCESS_VM_WRITE |
+                        PROCESS_VM_OPERATION,
+                    FALSE, pid);
+    DWORD error = process == NULL ? GetLastError() : ERROR_SUCCESS;
+    bool attachable = process != NULL;
+    if (process != NULL)
+    {
+        CloseHandle(process);
+    }
+
+    std::string problems = "[";
+    if (error == ERROR_INVALID_PARAMETER)
+    {
+        add_problem(problems, "no_such_process",
+                    "Process " + std::to_string(pid) + " does not exist",
+                    "Refresh the process list and pick a running process");
+    }
+    else if (error == ERROR_ACCESS_DENIED && !elevated)
+    {
+        add_problem(problems, "not_elevated",
+                    "The process belongs to another user or runs elevated",
+                    "Run the server as administrator");
+    }
+    else if (error == ERROR_ACCESS_DENIED)
+    {
+        add_problem(problems, "protected_process",
+                    "The process is protected (PPL or anti-cheat) and refuses memory access",
+                    "Use the driver backend, or pick an unprotected process");
+    }
+    else if (!attachable)
+    {
+        add_problem(problems, "open_failed",
+                    "OpenProcess failed with error " + std::to_string(error),
+                    "Check the error code against the Windows system error list");
+    }
+    problems += "]";
+
+    std::string json = "{";
+    json += "\"platform\":\"windows\"";
+    json += ",\"pid\":" + std::to_string(pid);
+    json += std::string(",\"attachable\":") + boolean(attachable);
+    json += std::string(",\"elevated\":") + boolean(elevated);
+    json += ",\"open_error\":" + std::to_string(error);
+    json += ",\"problems\":" + problems;
+    json += "}";
+    return _strdup(json.c_str());
+}
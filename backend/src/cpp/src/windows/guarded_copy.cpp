@@ -0,0 +1,55 @@
+#include <errno.h>
+#include <windows.h>
+
+#include <algorithm>
+
+#include "native_api.h"
+
+static size_t to_page_end(uintptr_t address, size_t page_size)
+{
+    return page_size - (address & (page_size - 1));
+}
+
+// Kept free of C++ objects, which cannot share a function with __try
+static bool copy_chunk(void *destination, const void *source, size_t size)
+{
+    __try
+    {
+        memcpy(destination, source, size);
+        return true;
+    }
+    __except (GetExceptionCode() == EXCEPTION_ACCESS_VIOLATION ||
+                      GetExceptionCode() == EXCEPTION_IN_PAGE_ERROR
+                  ? EXCEPTION_EXECUTE_HANDLER
+                  : EXCEPTION_CONTINUE_SEARCH)
+    {
+        return false;
+    }
+}
+
+SSIZE_T guarded_copy_native(void *destination, const void *source, size_t size)
+{
+    SYSTEM_INFO info;
+    GetSystemInfo(&info);
+    size_t page_size = info.dwPageSize;
+
+    size_t copied = 0;
+    while (copied < size)
+    {
+        uintptr_t from = reinterpret_cast<uintptr_t>(source) + copied;
+        uintptr_t to = reinterpret_cast<uintptr_t>(destination) + copied;
+        size_t chunk =
+            std::min({size - copied, to_page_end(from, page_size), to_page_end(to, page_size)});
+        if (!copy_chunk(reinterpret_cast<void *>(to), reinterpret_cast<const void *>(from), chunk))
+        {
+            break;
+        }
+        copied += chunk;
+    }
+
+    if (copied == 0 && size > 0)
+    {
+        return -EFAULT;
+    }
+    return static_cast<SSIZE_T>(copied);
+}
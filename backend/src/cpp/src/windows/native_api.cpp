@@ -100,6 +100,12 @@ SSIZE_T write_memory_native(int pid, void *address, size_t size, unsigned char *
     return bytesWritten;
 }
 
+SSIZE_T write_memory_stealth_native(int pid, void *address, size_t size, unsigned char *buffer)
+{
+    // WriteProcessMemory never attaches a debugger, so the regular path already qualifies
+    return write_memory_native(pid, address, size, buffer);
+}
+
 void setMemoryProtection(DWORD protect, DWORD type, char *permissions)
 {
     permissions[0] = '-';
@@ -134,6 +140,13 @@ void setMemoryProtection(DWORD protect, DWORD type, char *permissions)
             break;
     }
 
+    // Guard pages fault on first access, so scans must treat them as unreadable
+    if (protect & PAGE_GUARD)
+    {
+        permissions[0] = '-';
+        permissions[1] = '-';
+    }
+
     if (type & MEM_PRIVATE || type & MEM_IMAGE)
     {
         permissions[3] = 'p';  // private
@@ -403,7 +416,8 @@ bool IsPE64Bit(HANDLE hProcess, LPVOID baseAddress)
         return false;
     }
 
-    return ntHeaders.FileHeader.Machine == IMAGE_FILE_MACHINE_AMD64;
+    WORD machine = ntHeaders.FileHeader.Machine;
+    return machine == IMAGE_FILE_MACHINE_AMD64 || machine == IMAGE_FILE_MACHINE_ARM64;
 }
 
 ModuleInfo *enummodule_native(DWORD pid, size_t *count)
@@ -461,7 +475,411 @@ ModuleInfo *enummodule_native(DWORD pid, size_t *count)
     return result;
 }
 
+// HeapWalk only works on the calling process, so remote heaps are walked through a toolhelp
+// snapshot instead.
+char *enumerate_allocations_native(int pid, size_t max_count)
+{
+    HANDLE hSnapshot = CreateToolhelp32Snapshot(TH32CS_SNAPHEAPLIST, pid);
+    if (hSnapshot == INVALID_HANDLE_VALUE)
+    {
+        debug_log(LOG_ERROR, "Failed to create heap snapshot. Error code: %lu", GetLastError());
+        return nullptr;
+    }
+
+    std::string out;
+    size_t count = 0;
+    HEAPLIST32 heapList;
+    heapList.dwSize = sizeof(HEAPLIST32);
+
+    if (Heap32ListFirst(hSnapshot, &heapList))
+    {
+        do
+        {
+            HEAPENTRY32 heapEntry;
+            heapEntry.dwSize = sizeof(HEAPENTRY32);
+            if (!Heap32First(&heapEntry, pid, heapList.th32HeapID))
+            {
+                continue;
+            }
+            do
+            {
+                if (heapEntry.dwFlags & LF32_FREE)
+                {
+                    continue;
+                }
+                char line[128];
+                snprintf(line, sizeof(line), "%llx %llx heap\n",
+                         static_cast<unsigned long long>(heapEntry.dwAddress),
+                         static_cast<unsigned long long>(heapEntry.dwBlockSize));
+                out += line;
+                count++;
+                heapEntry.dwSize = sizeof(HEAPENTRY32);
+            } while (count < max_count && Heap32Next(&heapEntry));
+        } while (count < max_count && Heap32ListNext(hSnapshot, &heapList));
+    }
+
+    CloseHandle(hSnapshot);
+    return strdup(out.c_str());
+}
+
+// The working set only tracks residency; Windows exposes no per-page dirty state to other
+// processes, so PAGE_INFO_DIRTY is never set.
+int query_page_info_native(int pid, uintptr_t address, size_t page_count, unsigned char *flags,
+                           size_t *page_size)
+{
+    SYSTEM_INFO systemInfo;
+    GetSystemInfo(&systemInfo);
+    *page_size = systemInfo.dwPageSize;
+    address -= address % systemInfo.dwPageSize;
+
+    HANDLE processHandle = OpenProcess(PROCESS_QUERY_INFORMATION, FALSE, pid);
+    if (processHandle == NULL)
+    {
+        debug_log(LOG_ERROR, "Failed to open process. Error code: %lu", GetLastError());
+        return -1;
+    }
+
+    std::vector<PSAPI_WORKING_SET_EX_INFORMATION> info(page_count);
+    for (size_t i = 0; i < page_count; i++)
+    {
+        info[i].VirtualAddress = reinterpret_cast<PVOID>(address + i * systemInfo.dwPageSize);
+    }
+    DWORD infoSize = static_cast<DWORD>(info.size() * sizeof(PSAPI_WORKING_SET_EX_INFORMATION));
+    if (!QueryWorkingSetEx(processHandle, info.data(), infoSize))
+    {
+        debug_log(LOG_ERROR, "QueryWorkingSetEx failed. Error code: %lu", GetLastError());
+        CloseHandle(processHandle);
+        return -1;
+    }
+    CloseHandle(processHandle);
+
+    for (size_t i = 0; i < page_count; i++)
+    {
+        flags[i] = info[i].VirtualAttributes.Valid ? PAGE_INFO_RESIDENT : 0;
+    }
+    return static_cast<int>(page_count);
+}
+
+// protection uses 1 = read, 2 = write, 4 = execute
+int set_protection_native(int pid, uintptr_t address, size_t size, int protection)
+{
+    // Indexed by the protection bits; write-only has no Windows equivalent and maps to read/write
+    static const DWORD page_protections[8] = {
+        PAGE_NOACCESS, PAGE_READONLY,     PAGE_READWRITE,         PAGE_READWRITE,
+        PAGE_EXECUTE,  PAGE_EXECUTE_READ, PAGE_EXECUTE_READWRITE, PAGE_EXECUTE_READWRITE};
+
+    HANDLE processHandle = OpenProcess(PROCESS_VM_OPERATION, FALSE, pid);
+    if (processHandle == NULL)
+    {
+        debug_log(LOG_ERROR, "Failed to open process. Error code: %lu", GetLastError());
+        return -1;
+    }
+
+    DWORD oldProtect;
+    BOOL result = VirtualProtectEx(processHandle, (LPVOID)address, size,
+                                   page_protections[protection & 7], &oldProtect);
+    CloseHandle(processHandle);
+    if (!result)
+    {
+        debug_log(LOG_ERROR, "VirtualProtectEx failed. Error code: %lu", GetLastError());
+        return -1;
+    }
+    return 0;
+}
+
+int set_stealth_options_native(int options)
+{
+    // Memory access goes through ReadProcessMemory/WriteProcessMemory without attaching
+    return 0;
+}
+
+int get_pointer_size_native(int pid)
+{
+    HANDLE process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid);
+    if (process == NULL)
+    {
+        return sizeof(void *);
+    }
+    BOOL wow64 = FALSE;
+    IsWow64Process(process, &wow64);
+    CloseHandle(process);
+    // WOW64 processes are 32-bit x86 (or ARM) code running on a 64-bit kernel
+    return wow64 ? 4 : sizeof(void *);
+}
+
 int native_init(int mode)
 {
     return 1;
-}
\ No newline at end of file
+}
+// GetThreadDescription exists from Windows 10 1607 on, so it is looked up at runtime
+std::string get_thread_name(DWORD thread_id)
+{
+    typedef HRESULT(WINAPI * GET_THREAD_DESCRIPTION)(HANDLE thread, PWSTR * description);
+    static GET_THREAD_DESCRIPTION get_thread_description = (GET_THREAD_DESCRIPTION)GetProcAddress(
+        GetModuleHandleW(L"kernel32.dll"), "GetThreadDescription");
+    if (get_thread_description == NULL)
+    {
+        return "";
+    }
+    HANDLE thread = OpenThread(THREAD_QUERY_LIMITED_INFORMATION, FALSE, thread_id);
+    if (thread == NULL)
+    {
+        return "";
+    }
+    std::string name;
+    PWSTR description = NULL;
+    if (SUCCEEDED(get_thread_description(thread, &description)) && description != NULL)
+    {
+        int length =
+            WideCharToMultiByte(CP_UTF8, 0, description, -1, NULL, 0, NULL, NULL);
+        if (length > 1)
+        {
+            name.resize(length - 1);
+            WideCharToMultiByte(CP_UTF8, 0, description, -1, &name[0], length, NULL, NULL);
+        }
+        LocalFree(description);
+    }
+    CloseHandle(thread);
+    return name;
+}
+
+// One "tid name" line per thread
+char *enumerate_threads_native(int pid)
+{
+    HANDLE snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0);
+    if (snapshot == INVALID_HANDLE_VALUE)
+    {
+        debug_log(LOG_ERROR, "Failed to create thread snapshot. Error code: %lu", GetLastError());
+        return nullptr;
+    }
+
+    std::string out;
+    THREADENTRY32 entry;
+    entry.dwSize = sizeof(entry);
+    if (Thread32First(snapshot, &entry))
+    {
+        do
+        {
+            if (entry.th32OwnerProcessID == static_cast<DWORD>(pid))
+            {
+                out += std::to_string(entry.th32ThreadID) + " " +
+                       get_thread_name(entry.th32ThreadID) + "\n";
+            }
+        } while (Thread32Next(snapshot, &entry));
+    }
+    CloseHandle(snapshot);
+    return _strdup(out.c_str());
+}
+
+// One "tid sp start end" line (hex after the tid) per thread. The stack runs from the
+// allocation base of the reservation holding the stack pointer to the end of its committed
+// part.
+char *enumerate_thread_stacks_native(int pid)
+{
+    HANDLE process = OpenProcess(PROCESS_QUERY_INFORMATION, FALSE, pid);
+    if (process == NULL)
+    {
+        debug_log(LOG_ERROR, "Failed to open process. Error code: %lu", GetLastError());
+        return nullptr;
+    }
+    BOOL wow64 = FALSE;
+    IsWow64Process(process, &wow64);
+
+    HANDLE snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0);
+    if (snapshot == INVALID_HANDLE_VALUE)
+    {
+        debug_log(LOG_ERROR, "Failed to create thread snapshot. Error code: %lu", GetLastError());
+        CloseHandle(process);
+        return nullptr;
+    }
+
+    std::string out;
+    THREADENTRY32 entry;
+    entry.dwSize = sizeof(entry);
+    if (Thread32First(snapshot, &entry))
+    {
+        do
+        {
+            if (entry.th32OwnerProcessID != static_cast<DWORD>(pid))
+            {
+                continue;
+            }
+            HANDLE thread = OpenThread(THREAD_GET_CONTEXT | THREAD_SUSPEND_RESUME, FALSE,
+                                       entry.th32ThreadID);
+            if (thread == NULL)
+            {
+                continue;
+            }
+            uint64_t sp = 0;
+            bool suspended = SuspendThread(thread) != (DWORD)-1;
+            if (wow64)
+            {
+                WOW64_CONTEXT context = {};
+                context.ContextFlags = WOW64_CONTEXT_CONTROL;
+                if (Wow64GetThreadContext(thread, &context))
+                {
+                    sp = context.Esp;
+                }
+            }
+            else
+            {
+                CONTEXT context = {};
+                context.ContextFlags = CONTEXT_CONTROL;
+                if (GetThreadContext(thread, &context))
+                {
+#ifdef _M_ARM64
+                    sp = context.Sp;
+#else
+                    sp = context.Rsp;
+#endif
+                }
+            }
+            if (suspended)
+            {
+                ResumeThread(thread);
+            }
+            CloseHandle(thread);
+
+            MEMORY_BASIC_INFORMATION info;
+            if (sp == 0 || VirtualQueryEx(process, reinterpret_cast<LPCVOID>(sp), &info,
+                                          sizeof(info)) == 0)
+            {
+                continue;
+            }
+            char line[96];
+            snprintf(line, sizeof(line), "%lu %llx %llx %llx\n", entry.th32ThreadID,
+                     static_cast<unsigned long long>(sp),
+                     reinterpret_cast<unsigned long long>(info.AllocationBase),
+                     reinterpret_cast<unsigned long long>(info.BaseAddress) + info.RegionSize);
+            out += line;
+        } while (Thread32Next(snapshot, &entry));
+    }
+    CloseHandle(snapshot);
+    CloseHandle(process);
+    return _strdup(out.c_str());
+}
+
+// "tid teb start end" per block. TlsAlloc values live in the TEB's TlsSlots, and static TLS of
+// modules built with __declspec(thread) hangs off ThreadLocalStoragePointer, one block per
+// module. Offsets follow the public TEB layout; a WOW64 thread's 32-bit TEB follows its
+// 64-bit one.
+char *enumerate_tls_blocks_native(int pid, size_t block_size)
+{
+    typedef LONG(NTAPI * NT_QUERY_INFORMATION_THREAD)(HANDLE thread, ULONG information_class,
+                                                      PVOID information, ULONG length,
+                                                      PULONG return_length);
+    struct THREAD_BASIC_INFORMATION_
+    {
+        LONG ExitStatus;
+        PVOID TebBaseAddress;
+        HANDLE UniqueProcess;
+        HANDLE UniqueThread;
+        ULONG_PTR AffinityMask;
+        LONG Priority;
+        LONG BasePriority;
+    };
+    const ULONG THREAD_BASIC_INFORMATION_CLASS = 0;
+    const size_t TLS_SLOT_COUNT = 64;
+    const size_t MAX_TLS_MODULES = 64;
+
+    static NT_QUERY_INFORMATION_THREAD nt_query_information_thread =
+        (NT_QUERY_INFORMATION_THREAD)GetProcAddress(GetModuleHandleW(L"ntdll.dll"),
+                                                    "NtQueryInformationThread");
+    if (nt_query_information_thread == NULL)
+    {
+        debug_log(LOG_ERROR, "NtQueryInformationThread is not available");
+        return nullptr;
+    }
+    HANDLE process = OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, FALSE, pid);
+    if (process == NULL)
+    {
+        debug_log(LOG_ERROR, "Failed to open process. Error code: %lu", GetLastError());
+        return nullptr;
+    }
+    BOOL wow64 = FALSE;
+    IsWow64Process(process, &wow64);
+    size_t pointer_size = wow64 ? 4 : sizeof(void *);
+    uint64_t tls_pointer_offset = wow64 ? 0x2C : 0x58;
+    uint64_t tls_slots_offset = wow64 ? 0xE10 : 0x1480;
+
+    auto read_pointer = [&](uint64_t address, uint64_t *value) {
+        *value = 0;
+        SIZE_T nread = 0;
+        return ReadProcessMemory(process, reinterpret_cast<LPCVOID>(address), value,
+                                 pointer_size, &nread) &&
+               nread == pointer_size;
+    };
+
+    HANDLE snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0);
+    if (snapshot == INVALID_HANDLE_VALUE)
+    {
+        debug_log(LOG_ERROR, "Failed to create thread snapshot. Error code: %lu", GetLastError());
+        CloseHandle(process);
+        return nullptr;
+    }
+
+    std::string out;
+    THREADENTRY32 entry;
+    entry.dwSize = sizeof(entry);
+    if (Thread32First(snapshot, &entry))
+    {
+        do
+        {
+            if (entry.th32OwnerProcessID != static_cast<DWORD>(pid))
+            {
+                continue;
+            }
+            HANDLE thread = OpenThread(THREAD_QUERY_INFORMATION, FALSE, entry.th32ThreadID);
+            if (thread == NULL)
+            {
+                continue;
+            }
+            THREAD_BASIC_INFORMATION_ info = {};
+            LONG status = nt_query_information_thread(thread, THREAD_BASIC_INFORMATION_CLASS,
+                                                      &info, sizeof(info), NULL);
+            CloseHandle(thread);
+            if (status < 0 || info.TebBaseAddress == NULL)
+            {
+                continue;
+            }
+            uint64_t teb =
+                reinterpret_cast<uint64_t>(info.TebBaseAddress) + (wow64 ? 0x2000 : 0);
+            auto add_block = [&](uint64_t start, uint64_t end) {
+                char line[96];
+                snprintf(line, sizeof(line), "%lu %llx %llx %llx\n", entry.th32ThreadID,
+                         static_cast<unsigned long long>(teb),
+                         static_cast<unsigned long long>(start),
+                         static_cast<unsigned long long>(end));
+                out += line;
+            };
+            uint64_t tls_slots = teb + tls_slots_offset;
+            add_block(tls_slots, tls_slots + TLS_SLOT_COUNT * pointer_size);
+
+            uint64_t tls_array = 0;
+            if (!read_pointer(teb + tls_pointer_offset, &tls_array) || tls_array == 0)
+            {
+                continue;
+            }
+            for (size_t i = 0; i < MAX_TLS_MODULES; i++)
+            {
+                uint64_t block = 0;
+                if (!read_pointer(tls_array + i * pointer_size, &block) || block == 0)
+                {
+                    break;
+                }
+                MEMORY_BASIC_INFORMATION region;
+                if (VirtualQueryEx(process, reinterpret_cast<LPCVOID>(block), &region,
+                                   sizeof(region)) == 0)
+                {
+                    continue;
+                }
+                uint64_t region_end =
+                    reinterpret_cast<uint64_t>(region.BaseAddress) + region.RegionSize;
+                add_block(block, (std::min)(region_end, block + block_size));
+            }
+        } while (Thread32Next(snapshot, &entry));
+    }
+    CloseHandle(snapshot);
+    CloseHandle(process);
+    return _strdup(out.c_str());
+}
@@ -7,10 +7,12 @@
 #include <stdio.h>
 #include <tlhelp32.h>
 
+#include <algorithm>
 #include <cstdint>
 #include <cstdio>
 #include <cstring>
 #include <iostream>
+#include <string>
 #include <vector>
 
 enum LogLevel {
@@ -21,6 +23,17 @@ enum LogLevel {
     LOG_ERROR
 };
 
+enum PageInfoFlags {
+    PAGE_INFO_RESIDENT = 1,
+    PAGE_INFO_DIRTY = 2,
+    PAGE_INFO_SWAPPED = 4
+};
+
+enum StealthOptions {
+    STEALTH_NO_PTRACE = 1,
+    STEALTH_MINIMAL_EXCEPTION_PORTS = 2
+};
+
 typedef struct
 {
     int pid;
@@ -41,11 +54,27 @@ extern "C" int get_pid_native();
 extern "C" SSIZE_T read_memory_native(int pid, uintptr_t address, size_t size,
                                       unsigned char *buffer);
 extern "C" SSIZE_T write_memory_native(int pid, void *address, size_t size, unsigned char *buffer);
+extern "C" SSIZE_T write_memory_stealth_native(int pid, void *address, size_t size,
+                                               unsigned char *buffer);
+extern "C" SSIZE_T guarded_copy_native(void *destination, const void *source, size_t size);
 extern "C" void enumerate_regions_to_buffer(DWORD pid, char *buffer, size_t buffer_size);
 extern "C" ProcessInfo *enumprocess_native(size_t *count);
 extern "C" bool suspend_process(int pid);
 extern "C" bool resume_process(int pid);
 extern "C" ModuleInfo *enummodule_native(DWORD pid, size_t *count);
+extern "C" char *enumerate_allocations_native(int pid, size_t max_count);
+extern "C" char *enumerate_threads_native(int pid);
+extern "C" char *enumerate_thread_stacks_native(int pid);
+extern "C" char *enumerate_tls_blocks_native(int pid, size_t block_size);
+extern "C" int query_page_info_native(int pid, uintptr_t address, size_t page_count,
+                                      unsigned char *flags, size_t *page_size);
+extern "C" int set_stealth_options_native(int options);
+extern "C" int set_protection_native(int pid, uintptr_t address, size_t size, int protection);
 extern "C" int native_init(int mode);
+extern "C" int get_pointer_size_native(int pid);
+extern "C" char *diagnose_native(int pid);
+// Returns false when the hit fails its watchpoint condition
+extern "C" bool send_register_json(const char *register_json, int pid);
+std::string get_thread_name(DWORD thread_id);
 
 #endif
\ No newline at end of file
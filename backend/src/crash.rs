@@ -0,0 +1,156 @@
+use futures_util::FutureExt;
+use hyper::server::conn::AddrStream;
+use hyper::service::{make_service_fn, service_fn, Service};
+use hyper::{Body, Request, Response, StatusCode};
+use lazy_static::lazy_static;
+use log::error;
+use serde_json::{json, Value};
+use std::backtrace::Backtrace;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+use warp::Filter;
+
+const MAX_CRASH_REPORTS: usize = 100;
+
+lazy_static! {
+    static ref CRASHES: Mutex<CrashLog> = Mutex::new(CrashLog {
+        next_id: 1,
+        reports: VecDeque::new(),
+    });
+}
+
+thread_local! {
+    // The report of the last panic on this thread, claimed by the request it interrupted
+    static LAST_PANIC: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+struct CrashLog {
+    next_id: u64,
+    reports: VecDeque<Value>,
+}
+
+// The client address, which warp::addr::remote() no longer sees behind the guarded server
+#[derive(Clone)]
+struct RemoteAddr(SocketAddr);
+
+pub fn remote() -> impl Filter<Extract = (Option<SocketAddr>,), Error = Infallible> + Clone {
+    warp::ext::optional::<RemoteAddr>().map(|remote: Option<RemoteAddr>| remote.map(|r| r.0))
+}
+
+// Records every panic, including those on scan and monitor threads, before it unwinds
+pub fn install_hook() {
+    panic::set_hook(Box::new(|info| {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+        let thread = std::thread::current().name().map(str::to_string);
+        error!(
+            target: "panic",
+            "thread {:?} panicked at {}: {}",
+            thread.as_deref().unwrap_or("<unnamed>"),
+            location.as_deref().unwrap_or("<unknown>"),
+            message
+        );
+
+        let mut crashes = CRASHES.lock().unwrap_or_else(|e| e.into_inner());
+        let id = crashes.next_id;
+        crashes.next_id += 1;
+        if crashes.reports.len() >= MAX_CRASH_REPORTS {
+            crashes.reports.pop_front();
+        }
+        crashes.reports.push_back(json!({
+            "id": id,
+            "timestamp": chrono::Local::now().timestamp_millis(),
+            "thread": thread,
+            "message": message,
+            "location": location,
+            "backtrace": Backtrace::force_capture().to_string(),
+        }));
+        LAST_PANIC.with(|last| last.set(Some(id)));
+    }));
+}
+
+fn attach_request(id: u64, method: &str, path: &str) {
+    let mut crashes = CRASHES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(report) = crashes.reports.iter_mut().find(|r| r["id"] == id) {
+        report["request"] = json!({ "method": method, "path": path });
+    }
+}
+
+pub fn reports() -> Vec<Value> {
+    let crashes = CRASHES.lock().unwrap_or_else(|e| e.into_inner());
+    crashes.reports.iter().rev().cloned().collect()
+}
+
+pub fn clear() -> usize {
+    let mut crashes = CRASHES.lock().unwrap_or_else(|e| e.into_inner());
+    let cleared = crashes.reports.len();
+    crashes.reports.clear();
+    cleared
+}
+
+// Like warp::serve, but a handler that panics answers 500 with the crash id instead of
+// dropping the connection. `recover` then runs to clear the poison from state shared by
+// every handler, which the panic may have held locked.
+pub async fn serve<S, R>(service: S, address: SocketAddr, recover: R)
+where
+    S: Service<Request<Body>, Response = Response<Body>, Error = Infallible>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    R: Fn() + Clone + Send + Sync + 'static,
+{
+    let make_service = make_service_fn(move |connection: &AddrStream| {
+        let remote = RemoteAddr(connection.remote_addr());
+        let service = service.clone();
+        let recover = recover.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |mut request: Request<Body>| {
+                let method = request.method().to_string();
+                let path = request.uri().path().to_string();
+                request.extensions_mut().insert(remote.clone());
+                let response = service.clone().call(request);
+                let recover = recover.clone();
+                AssertUnwindSafe(response)
+                    .catch_unwind()
+                    .map(move |result| match result {
+                        Ok(response) => response,
+                        Err(_) => {
+                            recover();
+                            Ok(internal_error(&method, &path))
+                        }
+                    })
+            }))
+        }
+    });
+    if let Err(e) = hyper::Server::bind(&address).serve(make_service).await {
+        error!("Server error: {}", e);
+    }
+}
+
+fn internal_error(method: &str, path: &str) -> Response<Body> {
+    let crash_id = LAST_PANIC.with(|last| last.take());
+    if let Some(id) = crash_id {
+        attach_request(id, method, path);
+    }
+    let body = json!({
+        "error": "Internal error: the handler panicked",
+        "crash_id": crash_id,
+    });
+    Response::builder()
+        .status(StatusCode::INTERNAL_SERVER_ERROR)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
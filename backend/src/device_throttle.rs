@@ -0,0 +1,128 @@
+// Optional throttling of scan threads on phones and tablets: under thermal pressure or on a
+// low battery that is not charging, scans and filters use fewer threads so long unattended
+// automation does not overheat or drain the device. Off unless MEMORY_SERVER_DEVICE_THROTTLE
+// is set, and only Android and iOS report a condition.
+use serde::Serialize;
+
+const DEFAULT_LOW_BATTERY_PERCENT: u8 = 20;
+
+#[derive(Serialize, Default)]
+pub struct Condition {
+    // "nominal", "fair", "serious" or "critical"
+    pub thermal: Option<&'static str>,
+    pub battery_percent: Option<u8>,
+    pub charging: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct Status {
+    pub enabled: bool,
+    pub low_battery_percent: u8,
+    #[serde(flatten)]
+    pub condition: Condition,
+    // Threads scans are limited to right now, or null when not throttled
+    pub thread_limit: Option<usize>,
+}
+
+pub fn enabled() -> bool {
+    std::env::var("MEMORY_SERVER_DEVICE_THROTTLE")
+        .is_ok_and(|value| value == "true" || value == "1")
+}
+
+fn low_battery_percent() -> u8 {
+    std::env::var("MEMORY_SERVER_LOW_BATTERY")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_LOW_BATTERY_PERCENT)
+}
+
+#[cfg(any(target_os = "android", target_os = "ios"))]
+const THERMAL_STATES: [&str; 4] = ["nominal", "fair", "serious", "critical"];
+
+#[cfg(target_os = "android")]
+fn read_sysfs(name: &str) -> Option<String> {
+    let path = format!("/sys/class/power_supply/battery/{}", name);
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+// Android does not expose its thermal status outside the framework, so the battery
+// temperature, in tenths of a degree, stands in for it with the framework's skin thresholds
+#[cfg(target_os = "android")]
+pub fn condition() -> Condition {
+    let thermal = read_sysfs("temp")
+        .and_then(|value| value.parse::<i32>().ok())
+        .map(|tenths| match tenths {
+            ..=390 => THERMAL_STATES[0],
+            391..=430 => THERMAL_STATES[1],
+            431..=470 => THERMAL_STATES[2],
+            _ => THERMAL_STATES[3],
+        });
+    Condition {
+        thermal,
+        battery_percent: read_sysfs("capacity").and_then(|value| value.parse().ok()),
+        charging: read_sysfs("status").map(|status| status == "Charging" || status == "Full"),
+    }
+}
+
+#[cfg(target_os = "ios")]
+pub fn condition() -> Condition {
+    let (mut thermal_state, mut battery_percent, mut charging) = (0, -1, 0);
+    unsafe {
+        crate::native_bridge::device_condition_native(
+            &mut thermal_state,
+            &mut battery_percent,
+            &mut charging,
+        );
+    }
+    Condition {
+        thermal: THERMAL_STATES.get(thermal_state as usize).copied(),
+        battery_percent: u8::try_from(battery_percent).ok(),
+        charging: (battery_percent >= 0).then_some(charging != 0),
+    }
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub fn condition() -> Condition {
+    Condition::default()
+}
+
+fn limit_for(condition: &Condition, low_battery_percent: u8) -> Option<usize> {
+    let cores = rayon::current_num_threads();
+    let low_battery = condition.charging == Some(false)
+        && condition
+            .battery_percent
+            .is_some_and(|percent| percent <= low_battery_percent);
+    match condition.thermal {
+        Some("critical") => Some(1),
+        Some("serious") => Some((cores / 4).max(1)),
+        _ if low_battery => Some((cores / 2).max(1)),
+        _ => None,
+    }
+}
+
+// Most threads a scan may use now, or None to use them all
+pub fn thread_limit() -> Option<usize> {
+    if !enabled() {
+        return None;
+    }
+    limit_for(&condition(), low_battery_percent())
+}
+
+pub fn status() -> Status {
+    let enabled = enabled();
+    let low_battery_percent = low_battery_percent();
+    let condition = condition();
+    let thread_limit = if enabled {
+        limit_for(&condition, low_battery_percent)
+    } else {
+        None
+    };
+    Status {
+        enabled,
+        low_battery_percent,
+        condition,
+        thread_limit,
+    }
+}
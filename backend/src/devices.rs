@@ -0,0 +1,177 @@
+// Registry of other memory server instances that scans and filters can be fanned out to, for
+// comparing the same game across devices or builds.
+use crate::proxy;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+use warp::http::Method;
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct Device {
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct FanOutRequest {
+    // Defaults to every registered device
+    pub devices: Option<Vec<String>>,
+    // Scan or filter request sent to every device
+    pub request: Value,
+    // Per-device fields merged over `request`, e.g. address_ranges
+    pub overrides: Option<HashMap<String, Value>>,
+    // Processes opened on each device before the request is sent
+    pub pids: Option<HashMap<String, i32>>,
+}
+
+lazy_static! {
+    static ref DEVICES: RwLock<BTreeMap<String, Device>> = RwLock::new(BTreeMap::new());
+}
+
+pub fn register(device: Device) -> Result<(), String> {
+    if device.name.is_empty() {
+        return Err("Device name must not be empty".to_string());
+    }
+    if !device.url.starts_with("http://") {
+        return Err("Device url must start with http://".to_string());
+    }
+    let device = Device {
+        name: device.name,
+        url: device.url.trim_end_matches('/').to_string(),
+    };
+    DEVICES.write().unwrap().insert(device.name.clone(), device);
+    Ok(())
+}
+
+pub fn remove(name: &str) -> bool {
+    DEVICES.write().unwrap().remove(name).is_some()
+}
+
+pub fn list() -> Vec<Device> {
+    DEVICES.read().unwrap().values().cloned().collect()
+}
+
+async fn readable_writable_ranges(device: &Device) -> Result<Value, String> {
+    let regions = proxy::send_json(Method::GET, &format!("{}/regions", device.url), None).await?;
+    let ranges: Vec<(usize, usize)> = regions["regions"]
+        .as_array()
+        .map(|regions| {
+            regions
+                .iter()
+                .filter(|region| {
+                    let protection = region["protection"].as_str().unwrap_or("");
+                    protection.contains('r') && protection.contains('w')
+                })
+                .filter_map(|region| {
+                    let start =
+                        usize::from_str_radix(region["start_address"].as_str()?, 16).ok()?;
+                    let end = usize::from_str_radix(region["end_address"].as_str()?, 16).ok()?;
+                    Some((start, end))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(json!(ranges))
+}
+
+async fn run_on_device(
+    device: Device,
+    path: &'static str,
+    mut request: Value,
+    pid: Option<i32>,
+) -> Result<Value, String> {
+    if let Some(pid) = pid {
+        proxy::send_json(
+            Method::POST,
+            &format!("{}/process", device.url),
+            Some(&json!({ "pid": pid })),
+        )
+        .await?;
+    }
+    // Scans without explicit ranges cover the device's own writable regions
+    if path == "memoryscan" && request.get("address_ranges").is_none() {
+        request["address_ranges"] = readable_writable_ranges(&device).await?;
+    }
+    proxy::send_json(
+        Method::POST,
+        &format!("{}/{}", device.url, path),
+        Some(&request),
+    )
+    .await
+}
+
+pub async fn fan_out(path: &'static str, fan_out_request: FanOutRequest) -> Result<Value, String> {
+    let registered = DEVICES.read().unwrap().clone();
+    let names: Vec<String> = match &fan_out_request.devices {
+        Some(names) => names.clone(),
+        None => registered.keys().cloned().collect(),
+    };
+    if names.is_empty() {
+        return Err("No devices registered".to_string());
+    }
+
+    let mut handles = Vec::new();
+    for name in &names {
+        let device = registered
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Unknown device '{}'", name))?;
+        let mut request = fan_out_request.request.clone();
+        if let (Some(Value::Object(fields)), Value::Object(target)) = (
+            fan_out_request
+                .overrides
+                .as_ref()
+                .and_then(|overrides| overrides.get(name)),
+            &mut request,
+        ) {
+            for (key, value) in fields {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+        let pid = fan_out_request
+            .pids
+            .as_ref()
+            .and_then(|pids| pids.get(name).copied());
+        handles.push((
+            name.clone(),
+            tokio::spawn(run_on_device(device, path, request, pid)),
+        ));
+    }
+
+    let mut per_device = Map::new();
+    let mut matched_addresses = Vec::new();
+    for (name, handle) in handles {
+        let result = handle
+            .await
+            .unwrap_or_else(|e| Err(format!("Device task failed: {}", e)));
+        match result {
+            Ok(result) => {
+                if let Some(matches) = result["matched_addresses"].as_array() {
+                    for entry in matches {
+                        let mut entry = entry.clone();
+                        entry["device"] = json!(name);
+                        matched_addresses.push(entry);
+                    }
+                }
+                per_device.insert(
+                    name,
+                    json!({
+                        "found": result["found"],
+                        "is_rounded": result["is_rounded"]
+                    }),
+                );
+            }
+            Err(e) => {
+                per_device.insert(name, json!({ "error": e }));
+            }
+        }
+    }
+
+    Ok(json!({
+        "devices": per_device,
+        "matched_addresses": matched_addresses
+    }))
+}
@@ -0,0 +1,140 @@
+// Read/write backend that forwards memory access to a kernel driver or root daemon for targets
+// where ptrace or task_for_pid is blocked.
+//
+// Every request is a little-endian header followed by the payload for writes:
+//   u32 magic ("FYDR"), u32 op (1 = read, 2 = write), i32 pid, u64 address, u64 size
+// and is answered with an i64 result (bytes transferred, or a negative errno) followed by the
+// bytes read for successful reads.
+use byteorder::{ByteOrder, LittleEndian};
+use lazy_static::lazy_static;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::sync::{Mutex, RwLock};
+
+const DRIVER_MAGIC: u32 = 0x5244_5946;
+const OP_READ: u32 = 1;
+const OP_WRITE: u32 = 2;
+const HEADER_SIZE: usize = 28;
+
+trait DriverStream: Read + Write + Send {}
+impl<T: Read + Write + Send> DriverStream for T {}
+
+#[derive(Clone, PartialEq)]
+pub enum Backend {
+    Native,
+    Driver(String),
+}
+
+lazy_static! {
+    static ref BACKEND: RwLock<Backend> = RwLock::new(Backend::Native);
+    static ref CONNECTION: Mutex<Option<Box<dyn DriverStream>>> = Mutex::new(None);
+}
+
+fn connect(path: &str) -> Result<Box<dyn DriverStream>, Error> {
+    #[cfg(unix)]
+    {
+        let stream = std::os::unix::net::UnixStream::connect(path)?;
+        Ok(Box::new(stream))
+    }
+    #[cfg(not(unix))]
+    {
+        // Named pipes and device objects both open as plain files on Windows
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)?;
+        Ok(Box::new(file))
+    }
+}
+
+pub fn configure(backend: &str, driver_path: Option<String>) -> Result<(), String> {
+    let selected = match backend {
+        "native" => Backend::Native,
+        "driver" => {
+            let path = driver_path.ok_or("The driver backend requires a driver path")?;
+            let stream = connect(&path).map_err(|e| format!("{}: {}", path, e))?;
+            *CONNECTION.lock().unwrap() = Some(stream);
+            Backend::Driver(path)
+        }
+        _ => return Err(format!("Unknown backend '{}'", backend)),
+    };
+    *BACKEND.write().unwrap() = selected;
+    Ok(())
+}
+
+pub fn backend_name() -> String {
+    match &*BACKEND.read().unwrap() {
+        Backend::Native => "native".to_string(),
+        Backend::Driver(path) => format!("driver:{}", path),
+    }
+}
+
+pub fn is_enabled() -> bool {
+    *BACKEND.read().unwrap() != Backend::Native
+}
+
+fn transact(
+    op: u32,
+    pid: i32,
+    address: usize,
+    size: usize,
+    payload: &[u8],
+    response: &mut [u8],
+) -> Result<isize, Error> {
+    let path = match &*BACKEND.read().unwrap() {
+        Backend::Driver(path) => path.clone(),
+        Backend::Native => return Err(Error::other("Driver backend not active")),
+    };
+
+    let mut header = [0u8; HEADER_SIZE];
+    LittleEndian::write_u32(&mut header[0..4], DRIVER_MAGIC);
+    LittleEndian::write_u32(&mut header[4..8], op);
+    LittleEndian::write_i32(&mut header[8..12], pid);
+    LittleEndian::write_u64(&mut header[12..20], address as u64);
+    LittleEndian::write_u64(&mut header[20..28], size as u64);
+
+    let mut connection = CONNECTION.lock().unwrap();
+    // A dropped daemon connection is re-established once before giving up
+    for attempt in 0..2 {
+        if connection.is_none() {
+            *connection = Some(connect(&path)?);
+        }
+        let stream = connection.as_mut().unwrap();
+        let result = (|| -> Result<isize, Error> {
+            stream.write_all(&header)?;
+            stream.write_all(payload)?;
+            stream.flush()?;
+            let mut result = [0u8; 8];
+            stream.read_exact(&mut result)?;
+            let result = LittleEndian::read_i64(&result);
+            if result < 0 {
+                return Err(Error::from_raw_os_error(-result as i32));
+            }
+            if result as usize > size {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Driver returned too many bytes",
+                ));
+            }
+            if op == OP_READ {
+                stream.read_exact(&mut response[..result as usize])?;
+            }
+            Ok(result as isize)
+        })();
+        match result {
+            Err(e) if attempt == 0 && e.raw_os_error().is_none() => {
+                *connection = None;
+            }
+            other => return other,
+        }
+    }
+    Err(Error::other("Driver connection lost"))
+}
+
+pub fn read_memory(pid: i32, address: usize, buffer: &mut [u8]) -> Result<isize, Error> {
+    let size = buffer.len();
+    transact(OP_READ, pid, address, size, &[], buffer)
+}
+
+pub fn write_memory(pid: i32, address: usize, buffer: &[u8]) -> Result<isize, Error> {
+    transact(OP_WRITE, pid, address, buffer.len(), buffer, &mut [])
+}
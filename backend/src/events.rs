@@ -0,0 +1,302 @@
+use crate::alerts;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+const MAX_QUEUED_EVENTS: usize = 10_000;
+const DEFAULT_STREAM_CAPACITY: usize = 1000;
+
+// What a stream subscriber's queue does when the client reads slower than events arrive
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    DropOldest,
+    // A new event replaces the queued one of the same type and address, dropping the oldest
+    // when there is none
+    Coalesce,
+    // The stream ends with an "events_overflow" event
+    Disconnect,
+}
+
+// Which events a stream subscriber receives; an empty filter passes everything
+#[derive(Deserialize, Serialize, Clone, Default, JsonSchema)]
+pub struct EventFilter {
+    // Comma-separated event types, e.g. "watchpoint_hit,trigger_fired"
+    pub types: Option<String>,
+    // Only events about this address: a watchpoint's accessed memory, or a region or dump
+    // that covers it
+    pub address: Option<usize>,
+    // Only events whose data "id", "job_id" or "scan_id" equals this
+    pub id: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct StreamQuery {
+    // Defaults to drop_oldest
+    pub overflow: Option<OverflowPolicy>,
+    // Events queued for the client; defaults to 1000
+    pub capacity: Option<usize>,
+    pub types: Option<String>,
+    pub address: Option<usize>,
+    pub id: Option<String>,
+}
+
+impl StreamQuery {
+    pub fn filter(&self) -> EventFilter {
+        EventFilter {
+            types: self.types.clone(),
+            address: self.address,
+            id: self.id.clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Queue {
+    events: VecDeque<Value>,
+    // Numbers the events accepted for this subscriber, so dropped and coalesced ones show as
+    // gaps in "seq"
+    next_seq: u64,
+    dropped: u64,
+    coalesced: u64,
+    closed: bool,
+}
+
+pub struct Subscriber {
+    id: u64,
+    policy: OverflowPolicy,
+    capacity: usize,
+    filter: Mutex<EventFilter>,
+    queue: Mutex<Queue>,
+    ready: Notify,
+}
+
+lazy_static! {
+    static ref EVENT_QUEUE: Mutex<VecDeque<Value>> = Mutex::new(VecDeque::new());
+    static ref SUBSCRIBERS: Mutex<BTreeMap<u64, Arc<Subscriber>>> = Mutex::new(BTreeMap::new());
+    static ref NEXT_SUBSCRIBER_ID: AtomicU64 = AtomicU64::new(1);
+}
+
+// Addresses appear as numbers or as hex strings with or without "0x"
+fn parse_address(value: &Value) -> Option<usize> {
+    match value {
+        Value::Number(number) => number.as_u64().map(|n| n as usize),
+        Value::String(text) => usize::from_str_radix(text.trim_start_matches("0x"), 16).ok(),
+        _ => None,
+    }
+}
+
+fn event_address(event: &Value) -> Option<usize> {
+    let data = &event["data"];
+    ["address", "memory", "start_address"]
+        .iter()
+        .find_map(|field| parse_address(&data[*field]))
+}
+
+// Events of the same type about the same address coalesce
+fn coalesce_key(event: &Value) -> (Value, Option<usize>) {
+    (event["type"].clone(), event_address(event))
+}
+
+impl EventFilter {
+    fn matches(&self, event: &Value) -> bool {
+        if let Some(types) = &self.types {
+            let event_type = event["type"].as_str().unwrap_or_default();
+            if !types.split(',').any(|t| t.trim() == event_type) {
+                return false;
+            }
+        }
+        if let Some(address) = self.address {
+            let Some(start) = event_address(event) else {
+                return false;
+            };
+            let size = event["data"]["size"].as_u64().unwrap_or(1).max(1) as usize;
+            if address < start || address - start >= size {
+                return false;
+            }
+        }
+        if let Some(id) = &self.id {
+            let data = &event["data"];
+            let matched = ["id", "job_id", "scan_id"]
+                .iter()
+                .any(|field| match &data[*field] {
+                    Value::String(text) => text == id,
+                    Value::Number(number) => number.to_string() == *id,
+                    _ => false,
+                });
+            if !matched {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl Subscriber {
+    fn deliver(&self, event: &Value) {
+        // Filtered events take no sequence number, so "seq" gaps still mean lost events
+        if !self.filter.lock().unwrap().matches(event) {
+            return;
+        }
+        let mut queue = self.queue.lock().unwrap();
+        if queue.closed {
+            return;
+        }
+        let mut event = event.clone();
+        event["seq"] = json!(queue.next_seq);
+        queue.next_seq += 1;
+
+        if queue.events.len() >= self.capacity {
+            match self.policy {
+                OverflowPolicy::DropOldest => {
+                    queue.events.pop_front();
+                    queue.dropped += 1;
+                }
+                OverflowPolicy::Coalesce => {
+                    let key = coalesce_key(&event);
+                    let same = queue
+                        .events
+                        .iter()
+                        .rposition(|queued| coalesce_key(queued) == key);
+                    // The newer event goes to the back so "seq" stays increasing
+                    if let Some(index) = same {
+                        queue.events.remove(index);
+                        queue.coalesced += 1;
+                    } else {
+                        queue.events.pop_front();
+                        queue.dropped += 1;
+                    }
+                }
+                OverflowPolicy::Disconnect => {
+                    queue.dropped += queue.events.len() as u64 + 1;
+                    queue.events.clear();
+                    let overflow = json!({
+                        "type": "events_overflow",
+                        "timestamp": chrono::Local::now().timestamp_millis(),
+                        "seq": queue.next_seq,
+                        "data": { "capacity": self.capacity, "dropped": queue.dropped },
+                    });
+                    queue.events.push_back(overflow);
+                    queue.closed = true;
+                    drop(queue);
+                    self.ready.notify_one();
+                    return;
+                }
+            }
+        }
+        queue.events.push_back(event);
+        drop(queue);
+        self.ready.notify_one();
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    // Replaces what the subscriber receives; already queued events stay
+    pub fn set_filter(&self, filter: EventFilter) {
+        *self.filter.lock().unwrap() = filter;
+    }
+
+    // Waits for the next event; None once a disconnect-policy stream has overflowed
+    pub async fn next(&self) -> Option<Value> {
+        loop {
+            {
+                let mut queue = self.queue.lock().unwrap();
+                if let Some(event) = queue.events.pop_front() {
+                    return Some(event);
+                }
+                if queue.closed {
+                    return None;
+                }
+            }
+            self.ready.notified().await;
+        }
+    }
+
+    fn status(&self) -> Value {
+        let queue = self.queue.lock().unwrap();
+        json!({
+            "id": self.id,
+            "overflow": self.policy,
+            "capacity": self.capacity,
+            "filter": &*self.filter.lock().unwrap(),
+            "queued": queue.events.len(),
+            "next_seq": queue.next_seq,
+            "dropped": queue.dropped,
+            "coalesced": queue.coalesced,
+            "closed": queue.closed,
+        })
+    }
+}
+
+pub fn subscribe(query: &StreamQuery) -> Arc<Subscriber> {
+    let subscriber = Arc::new(Subscriber {
+        id: NEXT_SUBSCRIBER_ID.fetch_add(1, Ordering::SeqCst),
+        policy: query.overflow.unwrap_or(OverflowPolicy::DropOldest),
+        capacity: query
+            .capacity
+            .unwrap_or(DEFAULT_STREAM_CAPACITY)
+            .clamp(1, MAX_QUEUED_EVENTS),
+        filter: Mutex::new(query.filter()),
+        queue: Mutex::new(Queue::default()),
+        ready: Notify::new(),
+    });
+    SUBSCRIBERS
+        .lock()
+        .unwrap()
+        .insert(subscriber.id, subscriber.clone());
+    subscriber
+}
+
+pub fn unsubscribe(id: u64) {
+    SUBSCRIBERS.lock().unwrap().remove(&id);
+}
+
+pub fn subscribers() -> Vec<Value> {
+    SUBSCRIBERS
+        .lock()
+        .unwrap()
+        .values()
+        .map(|subscriber| subscriber.status())
+        .collect()
+}
+
+// Hands the event to alert rules and stream subscribers
+fn dispatch(event_type: &str, data: Value) -> Value {
+    let event = json!({
+        "type": event_type,
+        "timestamp": chrono::Local::now().timestamp_millis(),
+        "data": data,
+    });
+    alerts::notify(&event);
+    let subscribers: Vec<_> = SUBSCRIBERS.lock().unwrap().values().cloned().collect();
+    for subscriber in subscribers {
+        subscriber.deliver(&event);
+    }
+    event
+}
+
+// For events polled elsewhere, e.g. watchpoint hits from /exceptioninfo
+pub fn publish(event_type: &str, data: Value) {
+    dispatch(event_type, data);
+}
+
+pub fn push_event(event_type: &str, data: Value) {
+    let event = dispatch(event_type, data);
+    let mut queue = EVENT_QUEUE.lock().unwrap();
+    // Oldest events are dropped when nobody is polling
+    if queue.len() >= MAX_QUEUED_EVENTS {
+        queue.pop_front();
+    }
+    queue.push_back(event);
+}
+
+pub fn drain_events() -> Vec<Value> {
+    EVENT_QUEUE.lock().unwrap().drain(..).collect()
+}
@@ -0,0 +1,41 @@
+// Address ranges skipped by every scan in this server session, e.g. graphics or audio
+// buffers that are known to be noisy.
+use crate::util;
+use lazy_static::lazy_static;
+use std::sync::RwLock;
+
+lazy_static! {
+    static ref EXCLUDED: RwLock<Vec<(usize, usize)>> = RwLock::new(Vec::new());
+}
+
+pub fn list() -> Vec<(usize, usize)> {
+    EXCLUDED.read().unwrap().clone()
+}
+
+pub fn add(ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut excluded = EXCLUDED.write().unwrap();
+    let mut combined = excluded.clone();
+    combined.extend_from_slice(ranges);
+    *excluded = util::merge_ranges(&combined);
+    excluded.clone()
+}
+
+// Removes the given ranges from the list, or clears it when none are given.
+pub fn remove(ranges: Option<&[(usize, usize)]>) -> Vec<(usize, usize)> {
+    let mut excluded = EXCLUDED.write().unwrap();
+    *excluded = match ranges {
+        Some(ranges) => util::subtract_ranges(&excluded, &util::merge_ranges(ranges)),
+        None => Vec::new(),
+    };
+    excluded.clone()
+}
+
+// Drops the session exclusions and the request's own `extra` ranges from `address_ranges`.
+pub fn apply(address_ranges: &[(usize, usize)], extra: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut excluded = list();
+    if excluded.is_empty() && extra.is_empty() {
+        return address_ranges.to_vec();
+    }
+    excluded.extend_from_slice(extra);
+    util::subtract_ranges(address_ranges, &util::merge_ranges(&excluded))
+}
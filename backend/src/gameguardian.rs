@@ -0,0 +1,82 @@
+// Reads GameGuardian saved lists (.txt) into table entries. Each saved item is one line of
+// "|"-separated fields: name|address (hex)|type flags|value|freeze flag|...; the other
+// lines (item count, package name) are skipped.
+use crate::table::{Preset, TableEntry};
+use crate::util;
+use serde_json::{json, Value};
+
+// The value an item was saved with becomes a preset of this name
+pub const SAVED_PRESET: &str = "saved";
+
+pub struct ImportedItem {
+    pub entry: TableEntry,
+    pub frozen: bool,
+}
+
+fn data_type(flags: u32) -> Result<&'static str, String> {
+    match flags {
+        1 => Ok("int8"),
+        2 => Ok("int16"),
+        4 => Ok("int32"),
+        16 => Ok("float"),
+        32 => Ok("int64"),
+        64 => Ok("double"),
+        8 => Err("XOR-encoded values are not supported".to_string()),
+        _ => Err(format!("Unknown type flags {}", flags)),
+    }
+}
+
+// GameGuardian shows unsigned values for negative numbers of the same width
+fn encode(data_type: &str, value: &str) -> Result<Vec<u8>, String> {
+    util::encode_value(data_type, &json!(value)).or_else(|e| {
+        util::other_signedness(data_type)
+            .ok_or(e.clone())
+            .and_then(|unsigned| util::encode_value(unsigned, &json!(value)).map_err(|_| e))
+    })
+}
+
+fn parse_item(fields: &[&str]) -> Result<ImportedItem, String> {
+    let address = usize::from_str_radix(fields[1].trim().trim_start_matches("0x"), 16)
+        .map_err(|e| format!("Invalid address '{}': {}", fields[1], e))?;
+    let flags = fields[2]
+        .trim()
+        .parse::<u32>()
+        .map_err(|e| format!("Invalid type '{}': {}", fields[2], e))?;
+    let data_type = data_type(flags)?;
+    let buffer = encode(data_type, fields[3].trim())?;
+    Ok(ImportedItem {
+        entry: TableEntry {
+            id: 0,
+            description: fields[0].trim().to_string(),
+            address,
+            data_type: data_type.to_string(),
+            module: None,
+            module_offset: None,
+            presets: vec![Preset {
+                name: SAVED_PRESET.to_string(),
+                buffer,
+            }],
+            signature: None,
+            signature_offset: None,
+            pointer_offsets: None,
+        },
+        frozen: fields[4].trim() == "1",
+    })
+}
+
+// Returns the parsed items and a {line, error} object for every item that was skipped.
+pub fn parse_list(content: &str) -> (Vec<ImportedItem>, Vec<Value>) {
+    let mut items = Vec::new();
+    let mut skipped = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let fields: Vec<&str> = line.split('|').collect();
+        if fields.len() < 5 {
+            continue;
+        }
+        match parse_item(&fields) {
+            Ok(item) => items.push(item),
+            Err(e) => skipped.push(json!({ "line": index + 1, "error": e })),
+        }
+    }
+    (items, skipped)
+}
@@ -0,0 +1,187 @@
+use crate::native_bridge;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DEFAULT_DURATION_MS: u64 = 5000;
+const MAX_DURATION_MS: u64 = 600_000;
+const DEFAULT_BUCKET_SIZE: usize = 4;
+// Distinct instructions remembered per bucket
+const MAX_PCS_PER_BUCKET: usize = 8;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct HeatmapRequest {
+    pub address: usize,
+    pub size: usize,
+    pub duration_ms: Option<u64>,
+    pub bucket_size: Option<usize>,
+}
+
+#[derive(Default, Clone)]
+struct Bucket {
+    reads: u64,
+    writes: u64,
+    pcs: BTreeSet<u64>,
+}
+
+struct Session {
+    pid: i32,
+    address: usize,
+    size: usize,
+    bucket_size: usize,
+    duration: Duration,
+    started: Instant,
+    finished: Option<Instant>,
+    buckets: Vec<Bucket>,
+    stop: Arc<AtomicBool>,
+}
+
+lazy_static! {
+    static ref SESSION: Mutex<Option<Session>> = Mutex::new(None);
+}
+
+fn parse_hex(value: &Value) -> Option<u64> {
+    u64::from_str_radix(value.as_str()?.trim_start_matches("0x"), 16).ok()
+}
+
+// Called for every exception reported by the debugger. Returns true when the hit
+// belongs to the running heatmap so it is kept out of the exception queue.
+pub fn record_access(exception: &Value) -> bool {
+    let Some(address) = parse_hex(&exception["memory"]) else {
+        return false;
+    };
+    let mut session = SESSION.lock().unwrap();
+    let Some(session) = session.as_mut().filter(|s| s.finished.is_none()) else {
+        return false;
+    };
+    let address = address as usize;
+    if address < session.address || address >= session.address + session.size {
+        return false;
+    }
+
+    let bucket = &mut session.buckets[(address - session.address) / session.bucket_size];
+    if parse_hex(&exception["write"]).unwrap_or(0) != 0 {
+        bucket.writes += 1;
+    } else {
+        bucket.reads += 1;
+    }
+    if let Some(pc) = parse_hex(&exception["pc"]) {
+        if bucket.pcs.len() < MAX_PCS_PER_BUCKET {
+            bucket.pcs.insert(pc);
+        }
+    }
+    true
+}
+
+pub fn start(pid: i32, request: HeatmapRequest) -> Result<(), String> {
+    if request.size == 0 {
+        return Err("size must be greater than zero".to_string());
+    }
+    let bucket_size = request.bucket_size.unwrap_or(DEFAULT_BUCKET_SIZE).max(1);
+    let duration = Duration::from_millis(
+        request
+            .duration_ms
+            .unwrap_or(DEFAULT_DURATION_MS)
+            .min(MAX_DURATION_MS),
+    );
+
+    stop();
+    // Read and write accesses are both sampled, so the watch covers every access type
+    native_bridge::set_page_watchpoint(pid, request.address, request.size, 3)
+        .map_err(|e| format!("Failed to watch region: {}", e))?;
+
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    *SESSION.lock().unwrap() = Some(Session {
+        pid,
+        address: request.address,
+        size: request.size,
+        bucket_size,
+        duration,
+        started: Instant::now(),
+        finished: None,
+        buckets: vec![Bucket::default(); request.size.div_ceil(bucket_size)],
+        stop: stop_flag.clone(),
+    });
+
+    thread::spawn(move || {
+        let deadline = Instant::now() + duration;
+        while !stop_flag.load(Ordering::SeqCst) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+        }
+        let mut session = SESSION.lock().unwrap();
+        if let Some(session) = session.as_mut() {
+            if Arc::ptr_eq(&session.stop, &stop_flag) {
+                finish(session);
+            }
+        }
+    });
+    Ok(())
+}
+
+fn finish(session: &mut Session) {
+    if session.finished.is_some() {
+        return;
+    }
+    session.stop.store(true, Ordering::SeqCst);
+    session.finished = Some(Instant::now());
+    if let Err(e) = native_bridge::remove_watchpoint(session.address) {
+        log::error!("Failed to remove heatmap watch: {}", e);
+    }
+}
+
+// Ends sampling early; the collected heatmap stays available until the next start.
+pub fn stop() -> bool {
+    match SESSION.lock().unwrap().as_mut() {
+        Some(session) if session.finished.is_none() => {
+            finish(session);
+            true
+        }
+        _ => false,
+    }
+}
+
+pub fn report() -> Value {
+    let session = SESSION.lock().unwrap();
+    let Some(session) = session.as_ref() else {
+        return json!({ "running": false });
+    };
+
+    let elapsed = session.finished.unwrap_or_else(Instant::now) - session.started;
+    let buckets: Vec<Value> = session
+        .buckets
+        .iter()
+        .enumerate()
+        .filter(|(_, bucket)| bucket.reads + bucket.writes > 0)
+        .map(|(index, bucket)| {
+            json!({
+                "offset": index * session.bucket_size,
+                "reads": bucket.reads,
+                "writes": bucket.writes,
+                "pcs": bucket
+                    .pcs
+                    .iter()
+                    .map(|pc| format!("0x{:x}", pc))
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+
+    json!({
+        "running": session.finished.is_none(),
+        "pid": session.pid,
+        "address": session.address,
+        "size": session.size,
+        "bucket_size": session.bucket_size,
+        "duration_ms": session.duration.as_millis() as u64,
+        "elapsed_ms": elapsed.as_millis() as u64,
+        "total_reads": session.buckets.iter().map(|b| b.reads).sum::<u64>(),
+        "total_writes": session.buckets.iter().map(|b| b.writes).sum::<u64>(),
+        "buckets": buckets,
+    })
+}
@@ -0,0 +1,135 @@
+// Synthetic touch and keyboard input, so scripts can close a dialog or press a button between
+// memory operations. Android goes through the `input` shell command, which needs the shell
+// or root user, and Linux desktops through xdotool on the focused window.
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InputEvent {
+    // Left click on desktops
+    Tap {
+        x: i32,
+        y: i32,
+    },
+    // Drag with the left button held on desktops
+    Swipe {
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+        duration_ms: Option<u64>,
+    },
+    // Android key code name or number, e.g. "KEYCODE_BACK", or an X keysym, e.g. "Escape"
+    Key {
+        key: String,
+    },
+    Text {
+        text: String,
+    },
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct InputRequest {
+    pub events: Vec<InputEvent>,
+    // Pause between events, for UIs that drop input arriving too fast
+    pub delay_ms: Option<u64>,
+}
+
+#[cfg(target_os = "android")]
+fn command(event: &InputEvent) -> Command {
+    let mut command = Command::new("input");
+    match event {
+        InputEvent::Tap { x, y } => command.args(["tap", &x.to_string(), &y.to_string()]),
+        InputEvent::Swipe {
+            x1,
+            y1,
+            x2,
+            y2,
+            duration_ms,
+        } => {
+            command.args(["swipe", &x1.to_string(), &y1.to_string()]);
+            command.args([x2.to_string(), y2.to_string()]);
+            command.args(duration_ms.map(|duration| duration.to_string()))
+        }
+        InputEvent::Key { key } => command.args(["keyevent", key]),
+        // `input text` splits its argument on spaces unless they are written as %s
+        InputEvent::Text { text } => command.args(["text", &text.replace(' ', "%s")]),
+    };
+    command
+}
+
+#[cfg(target_os = "linux")]
+fn command(event: &InputEvent) -> Command {
+    let mut command = Command::new("xdotool");
+    match event {
+        InputEvent::Tap { x, y } => {
+            command.args(["mousemove", &x.to_string(), &y.to_string(), "click", "1"])
+        }
+        InputEvent::Swipe {
+            x1,
+            y1,
+            x2,
+            y2,
+            duration_ms,
+        } => {
+            let seconds = duration_ms.unwrap_or(300) as f64 / 1000.0;
+            command.args([
+                "mousemove",
+                &x1.to_string(),
+                &y1.to_string(),
+                "mousedown",
+                "1",
+            ]);
+            command.args(["sleep", &seconds.to_string()]);
+            command.args([
+                "mousemove",
+                &x2.to_string(),
+                &y2.to_string(),
+                "mouseup",
+                "1",
+            ])
+        }
+        InputEvent::Key { key } => command.args(["key", key]),
+        InputEvent::Text { text } => command.args(["type", "--", text]),
+    };
+    command
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn send(event: &InputEvent) -> Result<(), String> {
+    let mut command = command(event);
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "android", target_os = "linux")))]
+fn send(_event: &InputEvent) -> Result<(), String> {
+    Err("Input injection is not supported on this platform".to_string())
+}
+
+// Sends the events in order and stops at the first failure; returns how many were sent
+pub fn send_all(request: &InputRequest) -> Result<usize, (usize, String)> {
+    for (index, event) in request.events.iter().enumerate() {
+        if index > 0 {
+            if let Some(delay) = request.delay_ms {
+                thread::sleep(Duration::from_millis(delay));
+            }
+        }
+        send(event).map_err(|e| (index, e))?;
+    }
+    Ok(request.events.len())
+}
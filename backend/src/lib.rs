@@ -1,32 +1,224 @@
 use ctor::ctor;
+use lazy_static::lazy_static;
+use libc::c_char;
+use serde::Deserialize;
+use std::ffi::CStr;
 use std::net::IpAddr;
+use std::sync::Mutex;
 use std::thread;
+use tokio::sync::oneshot;
 
+mod alerts;
 mod allocator;
+mod annotations;
 mod api;
+mod batch;
+mod callers;
+mod conditions;
+mod crash;
+mod device_throttle;
+mod devices;
+mod driver;
+mod events;
+mod exclusions;
+mod gameguardian;
+mod heatmap;
+mod input;
 mod logger;
+mod mapping_watch;
+mod memory_backend;
 mod native_bridge;
+mod openapi;
+mod plugins;
+mod profiles;
+mod proxy;
 mod ptrscan;
+#[cfg(feature = "python")]
+mod python;
+mod recipes;
+mod recorder;
+mod region_dump;
+mod region_monitor;
 mod request;
+mod result_archive;
+mod scan_engine;
+mod scan_estimate;
+mod scan_file;
+mod scan_priority;
+mod scan_sessions;
+mod scan_stats;
+mod scan_validation;
+mod scheduler;
+mod screen;
+mod screen_diff;
+mod selftest;
 mod serve;
+mod session_gc;
+mod snapshot;
+mod stacks;
+mod structs;
+mod table;
+mod text_search;
+mod timestamps;
+mod tls;
+mod tracer;
+mod triggers;
 mod util;
+mod value_format;
+mod vec3;
+mod wasm_predicates;
+mod write_history;
 
-#[ctor]
-fn main() {
-    thread::spawn(|| {
+const DEFAULT_PORT: u16 = 3030;
+
+lazy_static! {
+    // Shutdown signal and thread of the running server
+    static ref SERVER: Mutex<Option<(oneshot::Sender<()>, thread::JoinHandle<()>)>> =
+        Mutex::new(None);
+}
+
+// Options of start_server, mirroring the command line of the standalone server
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct EmbeddedConfig {
+    host: Option<IpAddr>,
+    backend: Option<String>,
+    driver_path: Option<String>,
+    write_log: Option<String>,
+    proxy: Option<String>,
+    session_ttl: Option<u64>,
+    max_sessions: Option<u64>,
+    snapshot_ttl: Option<u64>,
+    device_throttle: Option<bool>,
+    low_battery: Option<u64>,
+    plugin_dir: Option<String>,
+    scan_threads: Option<u64>,
+    scan_chunk_size: Option<u64>,
+}
+
+fn stop() -> bool {
+    let Some((shutdown, handle)) = SERVER.lock().unwrap().take() else {
+        return false;
+    };
+    let _ = shutdown.send(());
+    let _ = handle.join();
+    true
+}
+
+// `mode` is passed to native_api_init: 1 when injected into the target, 0 when inspecting
+// other processes. A running server is stopped first.
+fn spawn_server(
+    mode: i32,
+    running_mode: &'static str,
+    host: IpAddr,
+    port: u16,
+) -> Result<(), String> {
+    stop();
+    // warp panics on a port in use, so check it here where the caller can be told
+    std::net::TcpListener::bind((host, port)).map_err(|e| format!("{}:{}: {}", host, port, e))?;
+
+    let (shutdown, stopped) = oneshot::channel::<()>();
+    let handle = thread::spawn(move || {
         let runtime = tokio::runtime::Runtime::new().unwrap();
 
         runtime.block_on(async {
-            std::env::set_var("MEMORY_SERVER_RUNNING_MODE", "embedded");
+            std::env::set_var("MEMORY_SERVER_RUNNING_MODE", running_mode);
 
-            let host: IpAddr = "0.0.0.0".parse().unwrap();
-            let port: u16 = 3030;
             println!(
                 "memory_spy has started listening on host {} and port {}.",
                 host, port
             );
             logger::init_log();
-            serve::serve(1, host, port).await;
+            tokio::select! {
+                _ = serve::serve(mode, host, port) => {}
+                _ = stopped => {}
+            }
         });
     });
+    *SERVER.lock().unwrap() = Some((shutdown, handle));
+    Ok(())
+}
+
+/// Starts (or restarts) the embedded server. `config_json` may be null, or an object with
+/// host, backend, driver_path, write_log, proxy, session_ttl, max_sessions, snapshot_ttl,
+/// device_throttle, low_battery, plugin_dir, scan_threads and scan_chunk_size. Returns 0 on
+/// success, -1 for an invalid config and -2 when the server cannot listen on the port.
+///
+/// # Safety
+///
+/// `config_json` must be null or point to a null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn start_server(port: u16, config_json: *const c_char) -> i32 {
+    let config: EmbeddedConfig = if config_json.is_null() {
+        EmbeddedConfig::default()
+    } else {
+        let json = unsafe { CStr::from_ptr(config_json) }.to_string_lossy();
+        match serde_json::from_str(&json) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Invalid embedded server config: {}", e);
+                return -1;
+            }
+        }
+    };
+
+    let session_ttl = config.session_ttl.map(|ttl| ttl.to_string());
+    let max_sessions = config.max_sessions.map(|max| max.to_string());
+    let snapshot_ttl = config.snapshot_ttl.map(|ttl| ttl.to_string());
+    let device_throttle = config.device_throttle.map(|enabled| enabled.to_string());
+    let low_battery = config.low_battery.map(|percent| percent.to_string());
+    let scan_threads = config.scan_threads.map(|threads| threads.to_string());
+    let scan_chunk_size = config.scan_chunk_size.map(|size| size.to_string());
+    let variables = [
+        ("MEMORY_SERVER_BACKEND", &config.backend),
+        ("MEMORY_SERVER_DRIVER_PATH", &config.driver_path),
+        ("MEMORY_SERVER_WRITE_LOG", &config.write_log),
+        ("MEMORY_SERVER_PROXY", &config.proxy),
+        ("MEMORY_SERVER_SESSION_TTL", &session_ttl),
+        ("MEMORY_SERVER_MAX_SESSIONS", &max_sessions),
+        ("MEMORY_SERVER_SNAPSHOT_TTL", &snapshot_ttl),
+        ("MEMORY_SERVER_DEVICE_THROTTLE", &device_throttle),
+        ("MEMORY_SERVER_LOW_BATTERY", &low_battery),
+        ("MEMORY_SERVER_PLUGIN_DIR", &config.plugin_dir),
+        ("MEMORY_SERVER_SCAN_THREADS", &scan_threads),
+        ("MEMORY_SERVER_SCAN_CHUNK_SIZE", &scan_chunk_size),
+    ];
+    for (name, value) in variables {
+        match value {
+            Some(value) => std::env::set_var(name, value),
+            None => std::env::remove_var(name),
+        }
+    }
+
+    let host = config.host.unwrap_or_else(|| "0.0.0.0".parse().unwrap());
+    match spawn_server(1, "embedded", host, port) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Failed to start the embedded server: {}", e);
+            -2
+        }
+    }
+}
+
+/// Returns 0 once the server has stopped, or 1 when none was running.
+#[no_mangle]
+pub extern "C" fn stop_server() -> i32 {
+    if stop() {
+        0
+    } else {
+        1
+    }
+}
+
+// Starts on load unless the loader set MEMORY_SERVER_NO_AUTOSTART, in which case it calls
+// start_server itself. The Python extension starts only through memory_inspector.serve().
+#[cfg(not(feature = "python"))]
+#[ctor]
+fn main() {
+    if std::env::var_os("MEMORY_SERVER_NO_AUTOSTART").is_none() {
+        let host: IpAddr = "0.0.0.0".parse().unwrap();
+        if let Err(e) = spawn_server(1, "embedded", host, DEFAULT_PORT) {
+            eprintln!("Failed to start the embedded server: {}", e);
+        }
+    }
 }
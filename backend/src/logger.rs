@@ -1,11 +1,188 @@
 use chrono::Local;
 use colored::*;
-use env_logger::{Builder, Env};
+use env_logger::Builder;
+use lazy_static::lazy_static;
 use log::info;
-use log::LevelFilter;
+use log::kv::{Key, VisitSource};
+use log::{LevelFilter, Log, Metadata, Record};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::VecDeque;
 use std::io::Write;
+use std::sync::{Mutex, RwLock};
 use warp::log::Info;
 
+const MAX_LOG_ENTRIES: usize = 5000;
+const DEFAULT_LOG_SPEC: &str = "info";
+
+lazy_static! {
+    static ref LOG_BUFFER: Mutex<LogBuffer> = Mutex::new(LogBuffer::default());
+    static ref LEVELS: RwLock<LevelSpec> = RwLock::new(LevelSpec::parse(DEFAULT_LOG_SPEC).unwrap());
+}
+
+#[derive(Default)]
+struct LogBuffer {
+    next_seq: u64,
+    entries: VecDeque<Value>,
+}
+
+// env_logger style: a default level followed by "target=level" overrides, e.g.
+// "info,memory_server::api=debug,http=warn". The longest matching target prefix wins.
+#[derive(Clone)]
+struct LevelSpec {
+    default: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl LevelSpec {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let mut parsed = LevelSpec {
+            default: LevelFilter::Info,
+            modules: Vec::new(),
+        };
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    let level = level
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("Unknown log level: {}", level))?;
+                    parsed.modules.push((target.trim().to_string(), level));
+                }
+                None => {
+                    parsed.default = directive
+                        .parse()
+                        .map_err(|_| format!("Unknown log level: {}", directive))?;
+                }
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.modules
+            .iter()
+            .filter(|(prefix, _)| {
+                target == prefix
+                    || target
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+
+    fn to_json(&self) -> Value {
+        let modules: Map<String, Value> = self
+            .modules
+            .iter()
+            .map(|(target, level)| (target.clone(), json!(level.to_string().to_lowercase())))
+            .collect();
+        json!({
+            "default": self.default.to_string().to_lowercase(),
+            "modules": modules,
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct LogQuery {
+    // Least severe level returned, e.g. "warn" returns warnings and errors
+    pub level: Option<String>,
+    // Only records whose target starts with this, e.g. "memory_server::api" or "http"
+    pub target: Option<String>,
+    // Only records after this sequence number, to follow the log by polling
+    pub since: Option<u64>,
+    // The newest records up to this count; defaults to 200
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct LogLevelRequest {
+    // Replaces all levels, in the --log-level syntax: "info,memory_server::api=debug"
+    pub spec: String,
+}
+
+// Collects the key-values of a record, e.g. `info!(pid = pid; "...")`
+struct FieldCollector(Map<String, Value>);
+
+impl<'kvs> VisitSource<'kvs> for FieldCollector {
+    fn visit_pair(
+        &mut self,
+        key: Key<'kvs>,
+        value: log::kv::Value<'kvs>,
+    ) -> Result<(), log::kv::Error> {
+        let value = if let Some(n) = value.to_u64() {
+            json!(n)
+        } else if let Some(n) = value.to_i64() {
+            json!(n)
+        } else if let Some(n) = value.to_f64() {
+            json!(n)
+        } else if let Some(b) = value.to_bool() {
+            json!(b)
+        } else {
+            json!(value.to_string())
+        };
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+// Keeps every record in the ring buffer served by /logs, then prints it to the console as
+// text or, with --log-format json, as one JSON object per line
+struct StructuredLogger {
+    console: env_logger::Logger,
+    json_console: bool,
+}
+
+impl Log for StructuredLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= LEVELS.read().unwrap().level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let mut fields = FieldCollector(Map::new());
+        let _ = record.key_values().visit(&mut fields);
+        let mut entry = json!({
+            "timestamp": Local::now().timestamp_millis(),
+            "level": record.level().to_string().to_lowercase(),
+            "target": record.target(),
+            "message": record.args().to_string(),
+            "fields": fields.0,
+        });
+
+        if self.json_console {
+            eprintln!("{}", entry);
+        } else {
+            self.console.log(record);
+        }
+
+        let mut buffer = LOG_BUFFER.lock().unwrap();
+        entry["seq"] = json!(buffer.next_seq);
+        buffer.next_seq += 1;
+        if buffer.entries.len() >= MAX_LOG_ENTRIES {
+            buffer.entries.pop_front();
+        }
+        buffer.entries.push_back(entry);
+    }
+
+    fn flush(&self) {
+        self.console.flush();
+    }
+}
+
 static EXCLUDED_PATHS: &[&str] = &["/_next", "/exceptioninfo", "/resolveaddr"];
 static EXCLUDED_EXTENSIONS: &[&str] = &[
     ".png", ".jpg", ".jpeg", ".gif", ".ico", ".svg", ".webp", ".bmp", ".tiff",
@@ -25,7 +202,16 @@ fn color_native_prefix(message: &str) -> String {
 }
 
 pub fn init_log() {
-    Builder::new()
+    // MEMORY_SERVER_LOG_LEVEL comes from --log-level; RUST_LOG is still honored
+    let spec = std::env::var("MEMORY_SERVER_LOG_LEVEL")
+        .or_else(|_| std::env::var("RUST_LOG"))
+        .unwrap_or_else(|_| DEFAULT_LOG_SPEC.to_string());
+    if let Err(e) = set_levels(&spec) {
+        eprintln!("Ignoring log level \"{}\": {}", spec, e);
+    }
+    let json_console = std::env::var("MEMORY_SERVER_LOG_FORMAT").is_ok_and(|f| f == "json");
+
+    let console = Builder::new()
         .format(|buf, record| {
             let level = record.level();
             let (level_string, level_color) = match level {
@@ -66,9 +252,63 @@ pub fn init_log() {
                 colored_args
             )
         })
-        .filter_level(LevelFilter::Info)
-        .parse_env(Env::default().default_filter_or("info"))
-        .init();
+        // Levels are decided by StructuredLogger, so they can change at runtime
+        .filter_level(LevelFilter::Trace)
+        .build();
+
+    // An embedded server can be restarted, and keeps the logger of its first start
+    let _ = log::set_boxed_logger(Box::new(StructuredLogger {
+        console,
+        json_console,
+    }));
+}
+
+pub fn set_levels(spec: &str) -> Result<Value, String> {
+    let parsed = LevelSpec::parse(spec)?;
+    log::set_max_level(parsed.max_level());
+    let levels = parsed.to_json();
+    *LEVELS.write().unwrap() = parsed;
+    Ok(levels)
+}
+
+pub fn levels() -> Value {
+    LEVELS.read().unwrap().to_json()
+}
+
+pub fn tail(query: &LogQuery) -> Result<Vec<Value>, String> {
+    let level = match &query.level {
+        Some(level) => level
+            .parse::<LevelFilter>()
+            .map_err(|_| format!("Unknown log level: {}", level))?,
+        None => LevelFilter::Trace,
+    };
+    let buffer = LOG_BUFFER.lock().unwrap();
+    let mut entries: Vec<Value> = buffer
+        .entries
+        .iter()
+        .rev()
+        .filter(|entry| {
+            query
+                .since
+                .is_none_or(|since| entry["seq"].as_u64() > Some(since))
+        })
+        .filter(|entry| {
+            entry["level"]
+                .as_str()
+                .and_then(|l| l.parse::<log::Level>().ok())
+                .is_some_and(|l| l <= level)
+        })
+        .filter(|entry| match &query.target {
+            Some(target) => entry["target"]
+                .as_str()
+                .is_some_and(|t| t.starts_with(target.as_str())),
+            None => true,
+        })
+        .take(query.limit.unwrap_or(200))
+        .cloned()
+        .collect();
+    entries.reverse();
+    Ok(entries)
 }
 
 pub fn http_log(info: Info) {
@@ -91,6 +331,11 @@ pub fn http_log(info: Info) {
     }
 
     info!(
+        target: "http",
+        method = info.method().as_str(),
+        path = info.path(),
+        status = info.status().as_u16(),
+        elapsed_ms = info.elapsed().as_millis() as u64;
         "{} {} {} {}ms",
         info.method(),
         info.path(),
@@ -1,17 +1,69 @@
 use ctor::ctor;
 
-use clap::{Arg, Command};
+use clap::{Arg, ArgAction, Command};
 use std::env;
 use std::net::IpAddr;
 
+mod alerts;
 mod allocator;
+mod annotations;
 mod api;
+mod batch;
+mod benchmark;
+mod callers;
+mod conditions;
+mod crash;
+mod device_throttle;
+mod devices;
+mod driver;
+mod events;
+mod exclusions;
+mod gameguardian;
+mod heatmap;
+mod input;
 mod logger;
+mod mapping_watch;
+mod memory_backend;
 mod native_bridge;
+mod openapi;
+mod plugins;
+mod profiles;
+mod proxy;
 mod ptrscan;
+mod recipes;
+mod recorder;
+mod region_dump;
+mod region_monitor;
 mod request;
+mod result_archive;
+mod scan_engine;
+mod scan_estimate;
+mod scan_file;
+mod scan_priority;
+mod scan_sessions;
+mod scan_stats;
+mod scan_validation;
+mod scheduler;
+mod screen;
+mod screen_diff;
+mod selftest;
 mod serve;
+mod session_gc;
+mod snapshot;
+mod stacks;
+mod structs;
+mod table;
+mod text_search;
+mod timestamps;
+mod tls;
+mod tracer;
+mod triggers;
+mod tunnel;
 mod util;
+mod value_format;
+mod vec3;
+mod wasm_predicates;
+mod write_history;
 
 #[ctor]
 fn init() {
@@ -41,6 +93,126 @@ async fn main() {
                 .value_name("HOST")
                 .help("Sets the host to listen on"),
         )
+        .arg(
+            Arg::new("backend")
+                .short('b')
+                .long("backend")
+                .num_args(1)
+                .value_name("BACKEND")
+                .value_parser(["native", "driver"])
+                .help("Selects the memory read/write backend"),
+        )
+        .arg(
+            Arg::new("driver_path")
+                .long("driver-path")
+                .num_args(1)
+                .value_name("PATH")
+                .help("Sets the socket or device used by the driver backend"),
+        )
+        .arg(
+            Arg::new("write_log")
+                .long("write-log")
+                .num_args(1)
+                .value_name("PATH")
+                .help("Appends every memory write to this file as JSON lines"),
+        )
+        .arg(
+            Arg::new("session_ttl")
+                .long("session-ttl")
+                .num_args(1)
+                .value_name("SECONDS")
+                .help("Drops scan sessions left unused this long (0 keeps them)"),
+        )
+        .arg(
+            Arg::new("max_sessions")
+                .long("max-sessions")
+                .num_args(1)
+                .value_name("COUNT")
+                .help("Keeps at most this many scan sessions, dropping the least recently used"),
+        )
+        .arg(
+            Arg::new("snapshot_ttl")
+                .long("snapshot-ttl")
+                .num_args(1)
+                .value_name("SECONDS")
+                .help("Deletes snapshots older than this (0, the default, keeps them)"),
+        )
+        .arg(
+            Arg::new("device_throttle")
+                .long("device-throttle")
+                .action(ArgAction::SetTrue)
+                .help("Uses fewer scan threads under thermal pressure or on low battery (Android/iOS)"),
+        )
+        .arg(
+            Arg::new("low_battery")
+                .long("low-battery")
+                .num_args(1)
+                .value_name("PERCENT")
+                .help("Battery level at or below which --device-throttle applies (default 20)"),
+        )
+        .arg(
+            Arg::new("plugin_dir")
+                .long("plugin-dir")
+                .num_args(1)
+                .value_name("PATH")
+                .help("Loads every plugin library in this directory at startup"),
+        )
+        .arg(
+            Arg::new("log_level")
+                .long("log-level")
+                .num_args(1)
+                .value_name("SPEC")
+                .help("Sets log levels, e.g. \"info,memory_server::api=debug,http=warn\""),
+        )
+        .arg(
+            Arg::new("log_format")
+                .long("log-format")
+                .num_args(1)
+                .value_name("FORMAT")
+                .value_parser(["text", "json"])
+                .help("Prints logs as colored text or as one JSON object per line"),
+        )
+        .arg(
+            Arg::new("scan_threads")
+                .long("scan-threads")
+                .num_args(1)
+                .value_name("COUNT")
+                .help("Threads of a normal-priority scan (default one per core)"),
+        )
+        .arg(
+            Arg::new("scan_chunk_size")
+                .long("scan-chunk-size")
+                .num_args(1)
+                .value_name("BYTES")
+                .help("Bytes a scan reads at once from each range (default 16 MiB)"),
+        )
+        .arg(
+            Arg::new("benchmark")
+                .long("benchmark")
+                .action(ArgAction::SetTrue)
+                .help("Measures read, match and compression speed, prints recommended scan settings and exits"),
+        )
+        .arg(
+            Arg::new("proxy")
+                .long("proxy")
+                .num_args(1)
+                .value_name("URL")
+                .help("Forwards all requests to another memory server"),
+        )
+        .arg(
+            Arg::new("tunnel")
+                .long("tunnel")
+                .num_args(1)
+                .value_name("adb[:SERIAL]|usbmux[:UDID]")
+                .help("Tunnels to a memory server on a USB device and proxies to it"),
+        )
+        .arg(
+            Arg::new("remote_port")
+                .long("remote-port")
+                .num_args(1)
+                .value_name("PORT")
+                .help("Sets the port of the memory server on the tunneled device"),
+        )
         .get_matches();
 
     let port: u16 = matches
@@ -53,6 +225,77 @@ async fn main() {
         .map(|s: &String| s.parse().expect("Valid IP address"))
         .unwrap_or_else(|| "0.0.0.0".parse().unwrap());
 
+    if let Some(backend) = matches.get_one::<String>("backend") {
+        std::env::set_var("MEMORY_SERVER_BACKEND", backend);
+    }
+    if let Some(driver_path) = matches.get_one::<String>("driver_path") {
+        std::env::set_var("MEMORY_SERVER_DRIVER_PATH", driver_path);
+    }
+    if let Some(write_log) = matches.get_one::<String>("write_log") {
+        std::env::set_var("MEMORY_SERVER_WRITE_LOG", write_log);
+    }
+    if let Some(plugin_dir) = matches.get_one::<String>("plugin_dir") {
+        std::env::set_var("MEMORY_SERVER_PLUGIN_DIR", plugin_dir);
+    }
+    if let Some(log_level) = matches.get_one::<String>("log_level") {
+        std::env::set_var("MEMORY_SERVER_LOG_LEVEL", log_level);
+    }
+    if let Some(log_format) = matches.get_one::<String>("log_format") {
+        std::env::set_var("MEMORY_SERVER_LOG_FORMAT", log_format);
+    }
+    let limits = [
+        ("session_ttl", "MEMORY_SERVER_SESSION_TTL"),
+        ("max_sessions", "MEMORY_SERVER_MAX_SESSIONS"),
+        ("snapshot_ttl", "MEMORY_SERVER_SNAPSHOT_TTL"),
+        ("low_battery", "MEMORY_SERVER_LOW_BATTERY"),
+        ("scan_threads", "MEMORY_SERVER_SCAN_THREADS"),
+        ("scan_chunk_size", "MEMORY_SERVER_SCAN_CHUNK_SIZE"),
+    ];
+    for (arg, variable) in limits {
+        if let Some(value) = matches.get_one::<String>(arg) {
+            value.parse::<u64>().expect("Valid number");
+            std::env::set_var(variable, value);
+        }
+    }
+    if matches.get_flag("device_throttle") {
+        std::env::set_var("MEMORY_SERVER_DEVICE_THROTTLE", "true");
+    }
+    if let Some(proxy) = matches.get_one::<String>("proxy") {
+        std::env::set_var("MEMORY_SERVER_PROXY", proxy);
+    }
+
+    if matches.get_flag("benchmark") {
+        native_bridge::native_api_init(0);
+        benchmark::print(&benchmark::run());
+        return;
+    }
+
+    if let Some(spec) = matches.get_one::<String>("tunnel") {
+        let remote_port: u16 = matches
+            .get_one("remote_port")
+            .map(|s: &String| s.parse().expect("Valid port number"))
+            .unwrap_or(3030);
+        let spec = tunnel::TunnelSpec::parse(spec).expect("Valid tunnel specification");
+        match tunnel::establish(&spec, remote_port).await {
+            Ok(local_port) => {
+                println!(
+                    "Tunnel established on 127.0.0.1:{} (device port {}).",
+                    local_port, remote_port
+                );
+                if std::env::var("MEMORY_SERVER_PROXY").is_err() {
+                    std::env::set_var(
+                        "MEMORY_SERVER_PROXY",
+                        format!("http://127.0.0.1:{}", local_port),
+                    );
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to establish tunnel: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
     println!(
         "memory_server has started listening on host {} and port {}.",
         host, port
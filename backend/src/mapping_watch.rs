@@ -0,0 +1,247 @@
+// Watches for a file to be mapped into the target, e.g. a plugin library loaded late. Each
+// time a watched file appears its base is recorded, the optional signature is searched in
+// its mappings and the optional trigger fires, so patches can be applied as soon as the
+// code exists.
+use crate::events;
+use crate::native_bridge;
+use crate::stacks;
+use crate::triggers;
+use crate::util;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+const POLL_INTERVAL_MS: u64 = 500;
+// Appearances remembered per watch
+const MAX_HITS: usize = 32;
+const MAX_SIGNATURE_MATCHES: usize = 64;
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct MappingWatchRequest {
+    // Full path or file name of the mapped file
+    pub path: String,
+    // Trigger id from /triggers, fired each time the file is mapped
+    pub trigger: Option<String>,
+    // Hex bytes with ?? wildcards, searched in the file's mappings when it appears
+    pub signature: Option<String>,
+}
+
+struct Watch {
+    pid: i32,
+    request: MappingWatchRequest,
+    signature: Option<Vec<Option<u8>>>,
+    // Session the trigger runs under, for the write history
+    session: String,
+    base: Option<usize>,
+    hits: Vec<Value>,
+}
+
+struct Watches {
+    next_id: u64,
+    watches: BTreeMap<u64, Watch>,
+}
+
+lazy_static! {
+    static ref WATCHES: Mutex<Watches> = Mutex::new(Watches {
+        next_id: 1,
+        watches: BTreeMap::new(),
+    });
+}
+
+static POLLING: AtomicBool = AtomicBool::new(false);
+
+fn matches_path(watched: &str, path: &str) -> bool {
+    path == watched || path.rsplit(['/', '\\']).next() == Some(watched)
+}
+
+// Mappings of the watched file, in address order
+fn file_ranges(regions: &[(usize, usize, String)], watched: &str) -> Vec<(usize, usize)> {
+    regions
+        .iter()
+        .filter(|(_, _, path)| matches_path(watched, path))
+        .map(|&(start, end, _)| (start, end))
+        .collect()
+}
+
+fn search(
+    pid: i32,
+    ranges: &[(usize, usize)],
+    signature: &[Option<u8>],
+    base: usize,
+) -> Vec<Value> {
+    let mut matches = Vec::new();
+    for &(start, end) in ranges {
+        let mut buffer = vec![0u8; end - start];
+        let Ok(nread) = native_bridge::read_process_memory(
+            pid,
+            start as *mut libc::c_void,
+            buffer.len(),
+            &mut buffer,
+        ) else {
+            continue;
+        };
+        buffer.truncate(nread.max(0) as usize);
+        for offset in util::find_signature(&buffer, signature) {
+            if matches.len() >= MAX_SIGNATURE_MATCHES {
+                return matches;
+            }
+            matches.push(json!({
+                "address": start + offset,
+                "offset": start + offset - base,
+            }));
+        }
+    }
+    matches
+}
+
+fn on_mapped(id: u64, watch: &mut Watch, ranges: &[(usize, usize)]) {
+    let base = ranges[0].0;
+    watch.base = Some(base);
+    let mut hit = json!({
+        "id": id,
+        "path": watch.request.path,
+        "base": base,
+        "end": ranges[ranges.len() - 1].1,
+        "timestamp": chrono::Local::now().timestamp_millis(),
+    });
+    if let Some(signature) = &watch.signature {
+        hit["matches"] = json!(search(watch.pid, ranges, signature, base));
+    }
+    if let Some(trigger) = &watch.request.trigger {
+        match triggers::fire(watch.pid, trigger, &watch.session) {
+            Ok(result) => hit["trigger_result"] = result,
+            Err(e) => hit["trigger_error"] = json!(e),
+        }
+    }
+    events::push_event("mapping_appeared", hit.clone());
+    if watch.hits.len() >= MAX_HITS {
+        watch.hits.remove(0);
+    }
+    watch.hits.push(hit);
+}
+
+fn poll() {
+    let mut state = WATCHES.lock().unwrap();
+    let mut mappings_by_pid: BTreeMap<i32, Vec<(usize, usize, String)>> = BTreeMap::new();
+    for (&id, watch) in state.watches.iter_mut() {
+        let regions = mappings_by_pid
+            .entry(watch.pid)
+            .or_insert_with(|| stacks::file_mappings(watch.pid));
+        // A live process always maps some file, so nothing means it is gone or unreadable.
+        // The last state is kept until it can be read again.
+        if regions.is_empty() {
+            continue;
+        }
+        let ranges = file_ranges(regions, &watch.request.path);
+        match (watch.base, ranges.is_empty()) {
+            (None, false) => on_mapped(id, watch, &ranges),
+            (Some(base), true) => {
+                watch.base = None;
+                events::push_event(
+                    "mapping_removed",
+                    json!({ "id": id, "path": watch.request.path, "base": base }),
+                );
+            }
+            // Unloaded and reloaded elsewhere between two polls
+            (Some(base), false) if ranges[0].0 != base => on_mapped(id, watch, &ranges),
+            _ => {}
+        }
+    }
+}
+
+fn ensure_polling() {
+    if POLLING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(|| loop {
+        thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+        // Checked under the lock so an add racing with the exit restarts the thread
+        let state = WATCHES.lock().unwrap();
+        if state.watches.is_empty() {
+            POLLING.store(false, Ordering::SeqCst);
+            return;
+        }
+        drop(state);
+        poll();
+    });
+}
+
+fn to_json(id: u64, watch: &Watch) -> Value {
+    json!({
+        "id": id,
+        "pid": watch.pid,
+        "path": watch.request.path,
+        "trigger": watch.request.trigger,
+        "signature": watch.request.signature,
+        "mapped": watch.base.is_some(),
+        "base": watch.base,
+        "hits": watch.hits,
+    })
+}
+
+// A file that is already mapped counts as present, so only a later load fires the watch
+pub fn add(pid: i32, request: MappingWatchRequest, session: &str) -> Result<Value, String> {
+    if request.path.is_empty() {
+        return Err("path must not be empty".to_string());
+    }
+    let signature = request
+        .signature
+        .as_deref()
+        .map(util::parse_signature)
+        .transpose()?;
+    let base = file_ranges(&stacks::file_mappings(pid), &request.path)
+        .first()
+        .map(|&(start, _)| start);
+
+    let mut state = WATCHES.lock().unwrap();
+    let id = state.next_id;
+    state.next_id += 1;
+    let watch = Watch {
+        pid,
+        request,
+        signature,
+        session: session.to_string(),
+        base,
+        hits: Vec::new(),
+    };
+    let value = to_json(id, &watch);
+    state.watches.insert(id, watch);
+    drop(state);
+    ensure_polling();
+    Ok(value)
+}
+
+pub fn remove(id: u64) -> bool {
+    WATCHES.lock().unwrap().watches.remove(&id).is_some()
+}
+
+// Drops the watches on processes that exited, returning their ids
+pub fn remove_detached() -> Vec<u64> {
+    let mut state = WATCHES.lock().unwrap();
+    let detached: Vec<u64> = state
+        .watches
+        .iter()
+        .filter(|(_, watch)| !util::process_alive(watch.pid))
+        .map(|(&id, _)| id)
+        .collect();
+    for id in &detached {
+        state.watches.remove(id);
+    }
+    detached
+}
+
+pub fn list() -> Vec<Value> {
+    WATCHES
+        .lock()
+        .unwrap()
+        .watches
+        .iter()
+        .map(|(&id, watch)| to_json(id, watch))
+        .collect()
+}
@@ -0,0 +1,294 @@
+// Where reads, writes and region lists are served from. The live backends differ in how they
+// reach the target; a snapshot backend answers from a dump as if it were the process, so
+// scans and filters run on either.
+use crate::driver;
+use crate::native_bridge;
+use crate::snapshot;
+use serde_json::{json, Value};
+use std::io::{Error, ErrorKind};
+
+pub trait MemoryBackend: Send + Sync {
+    fn name(&self) -> String;
+
+    // Bytes read into the start of `buffer`, which may be fewer than its length
+    fn read(&self, pid: i32, address: usize, buffer: &mut [u8]) -> Result<usize, Error>;
+
+    fn write(&self, pid: i32, address: usize, data: &[u8]) -> Result<usize, Error>;
+
+    // Writes without attaching to the target, for backends where that makes a difference
+    fn write_stealth(&self, pid: i32, address: usize, data: &[u8]) -> Result<usize, Error> {
+        self.write(pid, address, data)
+    }
+
+    // Regions as listed by /enumregions: hex start_address and end_address, protection and
+    // file_path
+    fn regions(&self, pid: i32) -> Result<Vec<Value>, String>;
+}
+
+// ptrace, task_for_pid or ReadProcessMemory through the C++ layer
+pub struct NativeBackend;
+
+// The injected library reading its own process by plain copies that stop at a faulting page
+pub struct InProcessBackend;
+
+// Forwards reads and writes to the kernel driver or root daemon set up by --backend driver
+pub struct DriverBackend;
+
+// A saved snapshot or dump file, read-only
+pub struct SnapshotBackend {
+    chunks: Vec<snapshot::ChunkRef>,
+}
+
+fn io_result(result: isize) -> Result<usize, Error> {
+    if result >= 0 {
+        Ok(result as usize)
+    } else {
+        Err(Error::last_os_error())
+    }
+}
+
+impl MemoryBackend for NativeBackend {
+    fn name(&self) -> String {
+        "native".to_string()
+    }
+
+    fn read(&self, pid: i32, address: usize, buffer: &mut [u8]) -> Result<usize, Error> {
+        io_result(unsafe {
+            native_bridge::read_memory_native(pid, address, buffer.len(), buffer.as_mut_ptr())
+        } as isize)
+    }
+
+    fn write(&self, pid: i32, address: usize, data: &[u8]) -> Result<usize, Error> {
+        io_result(unsafe {
+            native_bridge::write_memory_native(pid, address, data.len(), data.as_ptr())
+        } as isize)
+    }
+
+    fn write_stealth(&self, pid: i32, address: usize, data: &[u8]) -> Result<usize, Error> {
+        io_result(unsafe {
+            native_bridge::write_memory_stealth_native(pid, address, data.len(), data.as_ptr())
+        } as isize)
+    }
+
+    fn regions(&self, pid: i32) -> Result<Vec<Value>, String> {
+        native_bridge::enum_native_regions(pid)
+    }
+}
+
+impl MemoryBackend for InProcessBackend {
+    fn name(&self) -> String {
+        "in-process".to_string()
+    }
+
+    fn read(&self, _pid: i32, address: usize, buffer: &mut [u8]) -> Result<usize, Error> {
+        native_bridge::guarded_copy(buffer.as_mut_ptr(), address as *const u8, buffer.len())
+            .map(|copied| copied as usize)
+    }
+
+    // Read-only pages fault here and are left to the native path, which can unprotect them
+    fn write(&self, pid: i32, address: usize, data: &[u8]) -> Result<usize, Error> {
+        match native_bridge::guarded_copy(address as *mut u8, data.as_ptr(), data.len()) {
+            Ok(written) if written as usize == data.len() => Ok(data.len()),
+            _ => NativeBackend.write(pid, address, data),
+        }
+    }
+
+    fn write_stealth(&self, pid: i32, address: usize, data: &[u8]) -> Result<usize, Error> {
+        match native_bridge::guarded_copy(address as *mut u8, data.as_ptr(), data.len()) {
+            Ok(written) if written as usize == data.len() => Ok(data.len()),
+            _ => NativeBackend.write_stealth(pid, address, data),
+        }
+    }
+
+    fn regions(&self, pid: i32) -> Result<Vec<Value>, String> {
+        NativeBackend.regions(pid)
+    }
+}
+
+impl MemoryBackend for DriverBackend {
+    fn name(&self) -> String {
+        driver::backend_name()
+    }
+
+    fn read(&self, pid: i32, address: usize, buffer: &mut [u8]) -> Result<usize, Error> {
+        driver::read_memory(pid, address, buffer).map(|nread| nread as usize)
+    }
+
+    fn write(&self, pid: i32, address: usize, data: &[u8]) -> Result<usize, Error> {
+        driver::write_memory(pid, address, data).map(|written| written as usize)
+    }
+
+    // The driver only moves memory; the mappings are still public
+    fn regions(&self, pid: i32) -> Result<Vec<Value>, String> {
+        NativeBackend.regions(pid)
+    }
+}
+
+impl SnapshotBackend {
+    pub fn new(chunks: Vec<snapshot::ChunkRef>) -> Self {
+        SnapshotBackend { chunks }
+    }
+
+    pub fn chunks(&self) -> &[snapshot::ChunkRef] {
+        &self.chunks
+    }
+}
+
+impl MemoryBackend for SnapshotBackend {
+    fn name(&self) -> String {
+        "snapshot".to_string()
+    }
+
+    fn read(&self, _pid: i32, address: usize, buffer: &mut [u8]) -> Result<usize, Error> {
+        snapshot::read(&self.chunks, address, buffer).map_err(Error::other)
+    }
+
+    fn write(&self, _pid: i32, _address: usize, _data: &[u8]) -> Result<usize, Error> {
+        Err(Error::new(
+            ErrorKind::PermissionDenied,
+            "Snapshots are read-only",
+        ))
+    }
+
+    fn regions(&self, _pid: i32) -> Result<Vec<Value>, String> {
+        Ok(self
+            .chunks
+            .iter()
+            .map(|chunk| {
+                json!({
+                    "start_address": format!("{:x}", chunk.address),
+                    "end_address": format!("{:x}", chunk.address + chunk.size),
+                    "protection": "r--",
+                    "file_path": "",
+                })
+            })
+            .collect())
+    }
+}
+
+// The live backend for `pid`: direct copies for our own process, else the driver when one is
+// configured, else the native API
+pub fn for_pid(pid: i32) -> &'static dyn MemoryBackend {
+    if native_bridge::is_own_process(pid) {
+        &InProcessBackend
+    } else if driver::is_enabled() {
+        &DriverBackend
+    } else {
+        &NativeBackend
+    }
+}
+
+// An address space held in memory, for tests without a target process
+#[cfg(test)]
+pub struct MockBackend {
+    regions: std::sync::RwLock<std::collections::BTreeMap<usize, Vec<u8>>>,
+}
+
+#[cfg(test)]
+impl MockBackend {
+    pub fn new() -> Self {
+        MockBackend {
+            regions: Default::default(),
+        }
+    }
+
+    pub fn map(&self, address: usize, bytes: Vec<u8>) {
+        self.regions.write().unwrap().insert(address, bytes);
+    }
+
+    // The region containing `address`, as (start, offset into it)
+    fn locate(
+        regions: &std::collections::BTreeMap<usize, Vec<u8>>,
+        address: usize,
+    ) -> Option<(usize, usize)> {
+        let (&start, bytes) = regions.range(..=address).next_back()?;
+        (address - start < bytes.len()).then_some((start, address - start))
+    }
+}
+
+#[cfg(test)]
+impl MemoryBackend for MockBackend {
+    fn name(&self) -> String {
+        "mock".to_string()
+    }
+
+    fn read(&self, _pid: i32, address: usize, buffer: &mut [u8]) -> Result<usize, Error> {
+        let regions = self.regions.read().unwrap();
+        let (start, offset) = Self::locate(&regions, address)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "unmapped address"))?;
+        let bytes = &regions[&start][offset..];
+        let length = buffer.len().min(bytes.len());
+        buffer[..length].copy_from_slice(&bytes[..length]);
+        Ok(length)
+    }
+
+    fn write(&self, _pid: i32, address: usize, data: &[u8]) -> Result<usize, Error> {
+        let mut regions = self.regions.write().unwrap();
+        let (start, offset) = Self::locate(&regions, address)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "unmapped address"))?;
+        let bytes = &mut regions.get_mut(&start).unwrap()[offset..];
+        let length = data.len().min(bytes.len());
+        bytes[..length].copy_from_slice(&data[..length]);
+        Ok(length)
+    }
+
+    fn regions(&self, _pid: i32) -> Result<Vec<Value>, String> {
+        Ok(self
+            .regions
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(start, bytes)| {
+                json!({
+                    "start_address": format!("{:x}", start),
+                    "end_address": format!("{:x}", start + bytes.len()),
+                    "protection": "rw-",
+                    "file_path": "",
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mock_reads_stop_at_the_region_end() {
+        let backend = MockBackend::new();
+        backend.map(0x1000, vec![1, 2, 3, 4]);
+        let mut buffer = [0u8; 8];
+        assert_eq!(backend.read(0, 0x1002, &mut buffer).unwrap(), 2);
+        assert_eq!(&buffer[..2], &[3, 4]);
+        assert!(backend.read(0, 0x1004, &mut buffer).is_err());
+        assert!(backend.read(0, 0xfff, &mut buffer).is_err());
+    }
+
+    #[test]
+    fn mock_writes_are_read_back() {
+        let backend = MockBackend::new();
+        backend.map(0x2000, vec![0; 16]);
+        assert_eq!(backend.write(0, 0x2004, &[0xaa, 0xbb]).unwrap(), 2);
+        let mut buffer = [0u8; 4];
+        backend.read(0, 0x2003, &mut buffer).unwrap();
+        assert_eq!(buffer, [0, 0xaa, 0xbb, 0]);
+        let regions = backend.regions(0).unwrap();
+        assert_eq!(regions[0]["start_address"], "2000");
+        assert_eq!(regions[0]["end_address"], "2010");
+    }
+
+    #[test]
+    fn own_process_is_read_in_process() {
+        let pid = std::process::id() as i32;
+        let backend = for_pid(pid);
+        assert_eq!(backend.name(), "in-process");
+        let value: u64 = 0x1122334455667788;
+        let mut buffer = [0u8; 8];
+        let nread = backend
+            .read(pid, &value as *const u64 as usize, &mut buffer)
+            .unwrap();
+        assert_eq!(nread, 8);
+        assert_eq!(u64::from_ne_bytes(buffer), value);
+    }
+}
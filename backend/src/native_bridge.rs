@@ -1,3 +1,6 @@
+use crate::driver;
+use crate::memory_backend;
+use crate::util;
 use libc::{self, c_char, c_int, c_void};
 use serde_json::json;
 use std::ffi::{CStr, CString};
@@ -23,9 +26,23 @@ extern "C" {
         size: libc::size_t,
         buffer: *const u8,
     ) -> libc::ssize_t;
+    pub fn write_memory_stealth_native(
+        pid: i32,
+        address: libc::uintptr_t,
+        size: libc::size_t,
+        buffer: *const u8,
+    ) -> libc::ssize_t;
     pub fn suspend_process(pid: i32) -> bool;
     pub fn resume_process(pid: i32) -> bool;
     pub fn native_init(mode: i32) -> libc::c_int;
+    pub fn get_pointer_size_native(pid: i32) -> libc::c_int;
+    pub fn diagnose_native(pid: i32) -> *mut c_char;
+    #[cfg(target_vendor = "apple")]
+    pub fn device_condition_native(
+        thermal_state: *mut c_int,
+        battery_percent: *mut c_int,
+        charging: *mut c_int,
+    );
     pub fn explore_directory(path: *const c_char, max_depth: i32) -> *mut libc::c_char;
     pub fn read_file(
         path: *const c_char,
@@ -38,10 +55,67 @@ extern "C" {
         address: libc::uintptr_t,
         size: libc::size_t,
         _type: libc::c_int,
+        thread_ids: *const u64,
+        thread_count: libc::size_t,
+        thread_filter: *const c_char,
     ) -> libc::c_int;
     pub fn remove_watchpoint_native(address: libc::uintptr_t) -> libc::c_int;
+    pub fn set_page_watchpoint_native(
+        address: libc::uintptr_t,
+        size: libc::size_t,
+        _type: libc::c_int,
+    ) -> libc::c_int;
     pub fn set_breakpoint_native(address: usize, hit_count: i32) -> i32;
     pub fn remove_breakpoint_native(address: usize) -> i32;
+    pub fn set_hit_action_native(
+        address: usize,
+        is_breakpoint: i32,
+        register_name: *const c_char,
+        value: u64,
+        skip_length: usize,
+    ) -> i32;
+    pub fn enumerate_allocations_native(pid: i32, max_count: usize) -> *mut c_char;
+    pub fn enumerate_threads_native(pid: i32) -> *mut c_char;
+    pub fn enumerate_thread_stacks_native(pid: i32) -> *mut c_char;
+    pub fn enumerate_tls_blocks_native(pid: i32, block_size: usize) -> *mut c_char;
+    pub fn query_page_info_native(
+        pid: i32,
+        address: libc::uintptr_t,
+        page_count: usize,
+        flags: *mut u8,
+        page_size: *mut usize,
+    ) -> i32;
+    pub fn set_stealth_options_native(options: i32) -> i32;
+    pub fn set_protection_native(
+        pid: i32,
+        address: libc::uintptr_t,
+        size: usize,
+        protection: i32,
+    ) -> i32;
+    pub fn guarded_copy_native(
+        destination: *mut c_void,
+        source: *const c_void,
+        size: usize,
+    ) -> isize;
+}
+
+pub const PAGE_INFO_RESIDENT: u8 = 1;
+pub const PAGE_INFO_DIRTY: u8 = 2;
+pub const PAGE_INFO_SWAPPED: u8 = 4;
+
+pub const PROTECTION_READ: i32 = 1;
+pub const PROTECTION_WRITE: i32 = 2;
+pub const PROTECTION_EXECUTE: i32 = 4;
+
+pub const STEALTH_NO_PTRACE: i32 = 1;
+pub const STEALTH_MINIMAL_EXCEPTION_PORTS: i32 = 2;
+
+pub fn stealth_option(name: &str) -> Option<i32> {
+    match name {
+        "no_ptrace" => Some(STEALTH_NO_PTRACE),
+        "minimal_exception_ports" => Some(STEALTH_MINIMAL_EXCEPTION_PORTS),
+        _ => None,
+    }
 }
 
 #[repr(C)]
@@ -58,19 +132,32 @@ pub struct ModuleInfo {
     pub modulename: *mut c_char,
 }
 
+// True when the server runs inside its target, as the injected cdylib does. Memory of the
+// own process is copied directly instead of going through the remote-process syscalls.
+pub fn is_own_process(pid: i32) -> bool {
+    pid >= 0 && pid as u32 == std::process::id()
+}
+
+// Stops at the first page that faults instead of crashing the target
+pub fn guarded_copy(destination: *mut u8, source: *const u8, size: usize) -> Result<isize, Error> {
+    let result =
+        unsafe { guarded_copy_native(destination as *mut c_void, source as *const c_void, size) };
+    if result >= 0 {
+        Ok(result)
+    } else {
+        Err(Error::from_raw_os_error(-result as i32))
+    }
+}
+
 pub fn read_process_memory(
     pid: i32,
     address: *mut libc::c_void,
     size: usize,
     buffer: &mut [u8],
 ) -> Result<isize, Error> {
-    let result =
-        unsafe { read_memory_native(pid, address as libc::uintptr_t, size, buffer.as_mut_ptr()) };
-    if result >= 0 {
-        Ok(result as isize)
-    } else {
-        Err(Error::last_os_error())
-    }
+    memory_backend::for_pid(pid)
+        .read(pid, address as usize, &mut buffer[..size])
+        .map(|nread| nread as isize)
 }
 
 pub fn write_process_memory(
@@ -79,16 +166,36 @@ pub fn write_process_memory(
     size: usize,
     buffer: &[u8],
 ) -> Result<isize, Error> {
-    let result =
-        unsafe { write_memory_native(pid, address as libc::uintptr_t, size, buffer.as_ptr()) };
-    if result >= 0 {
-        Ok(result as isize)
-    } else {
-        Err(Error::last_os_error())
-    }
+    memory_backend::for_pid(pid)
+        .write(pid, address as usize, &buffer[..size])
+        .map(|written| written as isize)
+}
+
+// Writes without ever attaching to the target (no ptrace, suspend or protection change).
+pub fn write_process_memory_stealth(
+    pid: i32,
+    address: *mut libc::c_void,
+    size: usize,
+    buffer: &[u8],
+) -> Result<isize, Error> {
+    memory_backend::for_pid(pid)
+        .write_stealth(pid, address as usize, &buffer[..size])
+        .map(|written| written as isize)
 }
 
-pub fn set_watchpoint(pid: i32, address: usize, size: usize, type_: i32) -> Result<i32, Error> {
+// An empty `threads` with no `thread_filter` arms the watchpoint without a thread scope
+pub fn set_watchpoint(
+    pid: i32,
+    address: usize,
+    size: usize,
+    type_: i32,
+    threads: &[u64],
+    thread_filter: Option<&str>,
+) -> Result<i32, Error> {
+    let thread_filter = thread_filter
+        .map(CString::new)
+        .transpose()
+        .map_err(|_| Error::new(std::io::ErrorKind::InvalidInput, "Invalid thread filter"))?;
     let result: bool = unsafe { debugger_new(pid) };
 
     if !result {
@@ -97,7 +204,18 @@ pub fn set_watchpoint(pid: i32, address: usize, size: usize, type_: i32) -> Resu
             "Failed to create debugger instance",
         ));
     }
-    let result = unsafe { set_watchpoint_native(address, size, type_) };
+    let result = unsafe {
+        set_watchpoint_native(
+            address,
+            size,
+            type_,
+            threads.as_ptr(),
+            threads.len(),
+            thread_filter
+                .as_ref()
+                .map_or(std::ptr::null(), |filter| filter.as_ptr()),
+        )
+    };
     if result == 0 {
         Ok(result as i32)
     } else {
@@ -105,6 +223,41 @@ pub fn set_watchpoint(pid: i32, address: usize, size: usize, type_: i32) -> Resu
     }
 }
 
+pub fn set_protection(
+    pid: i32,
+    address: usize,
+    size: usize,
+    protection: i32,
+) -> Result<(), String> {
+    let result = unsafe { set_protection_native(pid, address, size, protection) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(format!("Failed to change protection at 0x{:x}", address))
+    }
+}
+
+// Page-protection watch for buffers beyond the hardware watchpoint length limit.
+// Removal goes through remove_watchpoint like any other watchpoint.
+pub fn set_page_watchpoint(
+    pid: i32,
+    address: usize,
+    size: usize,
+    type_: i32,
+) -> Result<i32, Error> {
+    let result: bool = unsafe { debugger_new(pid) };
+
+    if !result {
+        return Err(Error::other("Failed to create debugger instance"));
+    }
+    let result = unsafe { set_page_watchpoint_native(address, size, type_) };
+    if result == 0 {
+        Ok(result)
+    } else {
+        Err(Error::last_os_error())
+    }
+}
+
 pub fn remove_watchpoint(address: usize) -> Result<i32, Error> {
     let result = unsafe { remove_watchpoint_native(address) };
     if result == 0 {
@@ -130,6 +283,50 @@ pub fn set_breakpoint(pid: i32, address: usize, hit_count: i32) -> Result<i32, E
     }
 }
 
+// Attaches the action to the armed watchpoint or breakpoint at `address`. A skipped
+// breakpoint instruction is decoded here for its length; ARM64 ignores the length since
+// every instruction is four bytes.
+pub fn set_hit_action(
+    pid: i32,
+    address: usize,
+    is_breakpoint: bool,
+    register: Option<&str>,
+    value: u64,
+    skip: bool,
+) -> Result<(), String> {
+    let register = register
+        .map(|name| CString::new(name.to_lowercase()))
+        .transpose()
+        .map_err(|_| "Invalid register name".to_string())?;
+    let skip_length = match (skip, is_breakpoint) {
+        (false, _) => 0,
+        (true, false) => 4,
+        (true, true) => {
+            let mut buffer = [0u8; 16];
+            let nread = read_process_memory(pid, address as *mut c_void, 16, &mut buffer)
+                .map_err(|e| format!("Failed to read the instruction: {}", e))?;
+            util::instruction_length(&buffer[..nread as usize], address as u64)
+                .ok_or_else(|| format!("No valid instruction at 0x{:x}", address))?
+        }
+    };
+    let result = unsafe {
+        set_hit_action_native(
+            address,
+            is_breakpoint as i32,
+            register
+                .as_ref()
+                .map_or(std::ptr::null(), |name| name.as_ptr()),
+            value,
+            skip_length,
+        )
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err("The debugger rejected the hit action".to_string())
+    }
+}
+
 pub fn remove_breakpoint(address: usize) -> Result<i32, Error> {
     let result = unsafe { remove_breakpoint_native(address) };
     if result == 0 {
@@ -139,10 +336,28 @@ pub fn remove_breakpoint(address: usize) -> Result<i32, Error> {
     }
 }
 
+// Returns the subset of the requested options supported on this platform.
+pub fn set_stealth_options(options: i32) -> i32 {
+    unsafe { set_stealth_options_native(options) }
+}
+
+// Pointer width of the target, which is 4 for 32-bit processes under a 64-bit server
+pub fn pointer_size(pid: i32) -> usize {
+    match unsafe { get_pointer_size_native(pid) } {
+        4 => 4,
+        _ => 8,
+    }
+}
+
 pub fn native_api_init(mode: i32) {
     unsafe {
         native_init(mode);
     }
+    let backend = std::env::var("MEMORY_SERVER_BACKEND").unwrap_or_else(|_| "native".to_string());
+    let driver_path = std::env::var("MEMORY_SERVER_DRIVER_PATH").ok();
+    if let Err(e) = driver::configure(&backend, driver_path) {
+        log::error!("Failed to select {} backend, using native: {}", backend, e);
+    }
 }
 
 pub fn enum_modules(pid: i32) -> Result<Vec<serde_json::Value>, String> {
@@ -180,6 +395,11 @@ pub fn enum_modules(pid: i32) -> Result<Vec<serde_json::Value>, String> {
 }
 
 pub fn enum_regions(pid: i32) -> Result<Vec<serde_json::Value>, String> {
+    memory_backend::for_pid(pid).regions(pid)
+}
+
+// Parses the maps-style listing of the C++ layer, whichever backend moves the memory
+pub fn enum_native_regions(pid: i32) -> Result<Vec<serde_json::Value>, String> {
     let mut buffer = vec![0u8; 1024 * 1024]; // 1MB buffer
 
     unsafe {
@@ -225,6 +445,123 @@ pub fn enum_regions(pid: i32) -> Result<Vec<serde_json::Value>, String> {
     }
 }
 
+// Why the process can or cannot be inspected, with platform checks and suggested fixes
+pub fn diagnose(pid: i32) -> Result<serde_json::Value, String> {
+    let output = unsafe {
+        let raw_ptr = diagnose_native(pid);
+        if raw_ptr.is_null() {
+            return Err("Failed to diagnose process".to_string());
+        }
+        let output = CStr::from_ptr(raw_ptr).to_string_lossy().into_owned();
+        libc::free(raw_ptr as *mut libc::c_void);
+        output
+    };
+    serde_json::from_str(&output).map_err(|e| format!("Invalid diagnosis: {}", e))
+}
+
+pub fn enum_threads(pid: i32) -> Result<Vec<serde_json::Value>, String> {
+    let output = unsafe {
+        let raw_ptr = enumerate_threads_native(pid);
+        if raw_ptr.is_null() {
+            return Err("Failed to enumerate threads".to_string());
+        }
+        let output = CStr::from_ptr(raw_ptr).to_string_lossy().into_owned();
+        libc::free(raw_ptr as *mut libc::c_void);
+        output
+    };
+
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let (thread_id, name) = line.split_once(' ').unwrap_or((line, ""));
+            Some(json!({
+                "thread_id": thread_id.parse::<u64>().ok()?,
+                "name": name
+            }))
+        })
+        .collect())
+}
+
+// One "thread_id sp start end" line per thread, addresses in hex
+pub fn enum_thread_stacks(pid: i32) -> Result<String, String> {
+    unsafe {
+        let raw_ptr = enumerate_thread_stacks_native(pid);
+        if raw_ptr.is_null() {
+            return Err("Failed to enumerate thread stacks".to_string());
+        }
+        let output = CStr::from_ptr(raw_ptr).to_string_lossy().into_owned();
+        libc::free(raw_ptr as *mut libc::c_void);
+        Ok(output)
+    }
+}
+
+pub fn enum_tls_blocks(pid: i32, block_size: usize) -> Result<String, String> {
+    unsafe {
+        let raw_ptr = enumerate_tls_blocks_native(pid, block_size);
+        if raw_ptr.is_null() {
+            return Err("Failed to enumerate TLS blocks".to_string());
+        }
+        let output = CStr::from_ptr(raw_ptr).to_string_lossy().into_owned();
+        libc::free(raw_ptr as *mut libc::c_void);
+        Ok(output)
+    }
+}
+
+pub fn enum_allocations(pid: i32, max_count: usize) -> Result<Vec<serde_json::Value>, String> {
+    let output = unsafe {
+        let raw_ptr = enumerate_allocations_native(pid, max_count);
+        if raw_ptr.is_null() {
+            return Err("Failed to enumerate allocations".to_string());
+        }
+        let output = CStr::from_ptr(raw_ptr).to_string_lossy().into_owned();
+        libc::free(raw_ptr as *mut libc::c_void);
+        output
+    };
+
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 3 {
+                return None;
+            }
+            let address = usize::from_str_radix(parts[0], 16).ok()?;
+            let size = usize::from_str_radix(parts[1], 16).ok()?;
+            Some(json!({
+                "address": address,
+                "size": size,
+                "kind": parts[2..].join(" ")
+            }))
+        })
+        .collect())
+}
+
+// Returns the page size and one PAGE_INFO_* flag byte per page covering the range.
+pub fn query_page_info(pid: i32, address: usize, size: usize) -> Result<(usize, Vec<u8>), String> {
+    // Sized for the smallest supported page size and trimmed once the real one is known
+    const MIN_PAGE_SIZE: usize = 4096;
+    let max_pages = size / MIN_PAGE_SIZE + 2;
+    let mut flags = vec![0u8; max_pages];
+    let mut page_size: usize = MIN_PAGE_SIZE;
+    let filled = unsafe {
+        query_page_info_native(
+            pid,
+            address as libc::uintptr_t,
+            max_pages,
+            flags.as_mut_ptr(),
+            &mut page_size,
+        )
+    };
+    if filled < 0 {
+        return Err("Failed to query page information".to_string());
+    }
+
+    let first_page = address / page_size * page_size;
+    let page_count = (address + size - first_page).div_ceil(page_size);
+    flags.truncate(page_count.min(filled as usize));
+    Ok((page_size, flags))
+}
+
 pub fn get_application_info(pid: i32) -> Result<String, Error> {
     let result = unsafe {
         let raw_ptr = get_application_info_native(pid as c_int);
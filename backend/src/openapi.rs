@@ -0,0 +1,1096 @@
+// OpenAPI 3 description of the HTTP API served at /openapi.json. Request schemas are
+// derived from the serde types, so they follow any change to the request structs; new
+// routes still need an entry in `endpoints`.
+use crate::{
+    alerts, annotations, batch, callers, devices, events, heatmap, input, logger, mapping_watch,
+    profiles, recipes, recorder, region_monitor, request, scheduler, screen_diff, selftest,
+    session_gc, structs, table, tracer, triggers, wasm_predicates, write_history,
+};
+use lazy_static::lazy_static;
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+
+enum Input {
+    None,
+    Body(Value),
+    Query(Vec<Value>),
+}
+
+struct Endpoint {
+    method: &'static str,
+    path: &'static str,
+    summary: &'static str,
+    // Shape of a successful response
+    response: &'static str,
+    input: Input,
+}
+
+fn body<T: JsonSchema>(gen: &mut SchemaGenerator) -> Input {
+    Input::Body(serde_json::to_value(gen.subschema_for::<T>()).unwrap_or_default())
+}
+
+// Query structs are flattened into one parameter per field
+fn query<T: JsonSchema>() -> Input {
+    let root = SchemaSettings::openapi3()
+        .into_generator()
+        .into_root_schema_for::<T>();
+    let object = root.schema.object.unwrap_or_default();
+    let parameters = object
+        .properties
+        .iter()
+        .map(|(name, schema)| {
+            json!({
+                "name": name,
+                "in": "query",
+                "required": object.required.contains(name),
+                "schema": schema,
+            })
+        })
+        .collect();
+    Input::Query(parameters)
+}
+
+fn endpoint(
+    method: &'static str,
+    path: &'static str,
+    summary: &'static str,
+    response: &'static str,
+    input: Input,
+) -> Endpoint {
+    Endpoint {
+        method,
+        path,
+        summary,
+        response,
+        input,
+    }
+}
+
+fn endpoints(gen: &mut SchemaGenerator) -> Vec<Endpoint> {
+    vec![
+        endpoint(
+            "get",
+            "/processes",
+            "List running processes",
+            "[{pid, processname}]",
+            Input::None,
+        ),
+        endpoint(
+            "get",
+            "/modules",
+            "List modules of the opened process",
+            "{modules: [{base, size, is_64bit, modulename}]}",
+            Input::None,
+        ),
+        endpoint(
+            "get",
+            "/threads",
+            "List threads of the opened process, for scoping watchpoints",
+            "{threads: [{thread_id, name}]}",
+            Input::None,
+        ),
+        endpoint(
+            "get",
+            "/tls",
+            "List thread-local storage blocks per thread",
+            "{threads: [{thread_id, thread_pointer, blocks: [{start, end}]}]}",
+            query::<request::TlsRequest>(),
+        ),
+        endpoint(
+            "post",
+            "/process",
+            "Open a process",
+            "Plain text status, or {error, diagnosis} with 403 when it cannot be inspected",
+            body::<request::OpenProcessRequest>(gen),
+        ),
+        endpoint(
+            "put",
+            "/process",
+            "Suspend or resume the opened process",
+            "{success, message}",
+            body::<request::ChangeProcessStateRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/memory",
+            "Read memory",
+            "Raw bytes (application/octet-stream)",
+            query::<request::ReadMemoryRequest>(),
+        ),
+        endpoint(
+            "post",
+            "/memory",
+            "Write memory",
+            "Plain text status, or the protection change report",
+            body::<request::WriteMemoryRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/memories",
+            "Read several ranges in one request",
+            "Per range: status (0 none, 1 complete, 2 partial), bytes read, first failure offset, compressed size, then the lz4 block",
+            body::<Vec<request::ReadMemoryRequest>>(gen),
+        ),
+        endpoint(
+            "post",
+            "/readregion",
+            "Stream a whole region, compressed chunk by chunk",
+            "Frames of status, offset, raw length, payload length and payload (application/octet-stream); X-Region-Start and X-Region-Size headers",
+            body::<request::ReadRegionRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/memoryscan",
+            "Start a scan",
+            "{found, matched_addresses: [{address, value, ...}], is_rounded, stats}",
+            body::<request::MemoryScanRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/memoryscan/estimate",
+            "Dry run of a scan: regions and bytes it would read and the expected duration",
+            "{requested_ranges, requested_bytes, ranges, bytes, largest_range, bytes_per_sec, throughput_source, estimated_ms}",
+            body::<request::MemoryScanRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/ocrscan",
+            "Read a number off the screen (or take it from the client) and scan or filter for it",
+            "scan or filter results with ocr: {text, value, pass}",
+            body::<request::OcrScanRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/screendiff/start",
+            "Capture the screen and memory before an action, then learn which memory changes on its own",
+            "{bytes, ranges, noise_runs, screen}",
+            body::<screen_diff::ScreenDiffStartRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/screendiff/finish",
+            "Capture again and list the regions that changed in the window, most changed first",
+            "{window_ms, screen_change, screen_error, noise_changes, region_count, truncated, regions}",
+            body::<screen_diff::ScreenDiffFinishRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/memoryfilter",
+            "Narrow down the results of a scan",
+            "{found, matched_addresses: [{address, value, previous, delta, ...}], is_rounded}",
+            body::<request::MemoryFilterRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/memoryfilter/repeat",
+            "Repeat a filter on an interval",
+            "Filter response plus {iterations}",
+            body::<request::RepeatFilterRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/memoryscan/import",
+            "Create scan results from external candidates",
+            "{found, unreadable}",
+            body::<request::ImportScanRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/memoryscan/timestamps",
+            "Find values close to the current time and keep them as scan results",
+            "{found, now, data_type, matched_addresses: [{address, value, decoded}]}",
+            body::<request::TimestampScanRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/memoryscan/vec3",
+            "Find three values within x/y/z bounds and keep them as scan results",
+            "{found, matched_addresses: [{address, x, y, z}]}",
+            body::<request::Vec3ScanRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/memoryscan/archive",
+            "Download the results of a scan as a binary archive",
+            "Result archive (application/octet-stream)",
+            query::<request::ExportResultsRequest>(),
+        ),
+        endpoint(
+            "post",
+            "/memoryscan/archive",
+            "Restore scan results from an archive sent as the request body",
+            "{scan_id, found}",
+            query::<request::ImportResultsRequest>(),
+        ),
+        endpoint(
+            "get",
+            "/listscans",
+            "List scan sessions with their result counts and metadata",
+            "{scans: [{scan_id, data_type, find_type, results, name, notes, pid, process_name, created, history: [{kind, time, found, parameters}]}]}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/scanmetadata",
+            "Set the name and notes of a scan session",
+            "{name, notes, pid, process_name, created, history}",
+            body::<request::ScanMetadataRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/gc",
+            "Limits of the stale session collection and what its last run removed",
+            "{config, last_run: {time, dropped_scans: [{scan_id, reason}], removed_snapshots, removed_watches}}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/gc",
+            "Change the stale session limits and collect now",
+            "{config, last_run}",
+            body::<session_gc::GcConfigRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/resultsummary",
+            "Group scan results by mapping and protection",
+            "{scan_id, total, unmapped, groups: [{mapping, module, protection, count, ...}]}",
+            query::<request::ResultSummaryRequest>(),
+        ),
+        endpoint(
+            "post",
+            "/stackscan",
+            "Scan only thread stacks for a value or for pointers into a range or module",
+            "{stacks: [{thread_id, sp, start, end}], matches: [{thread_id, address, sp_offset, pointer, target}], truncated}",
+            body::<request::StackScanRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/exclusions",
+            "List excluded address ranges",
+            "{ranges}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/exclusions",
+            "Exclude address ranges from scans",
+            "{ranges}",
+            body::<request::ExclusionRequest>(gen),
+        ),
+        endpoint(
+            "delete",
+            "/exclusions",
+            "Remove excluded ranges, or all without ranges",
+            "{ranges}",
+            body::<request::ExclusionRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/regions",
+            "List memory regions",
+            "{regions: [{start_address, end_address, protection, file_path}]}",
+            Input::None,
+        ),
+        endpoint(
+            "get",
+            "/resolveaddr",
+            "Resolve a symbolic address",
+            "{address}",
+            query::<request::ResolveAddrRequest>(),
+        ),
+        endpoint(
+            "get",
+            "/directory",
+            "List a directory on the target",
+            "Directory tree",
+            query::<request::ExploreDirectoryRequest>(),
+        ),
+        endpoint(
+            "get",
+            "/file",
+            "Download a file from the target",
+            "Raw file contents",
+            query::<request::ReadFileRequest>(),
+        ),
+        endpoint(
+            "get",
+            "/appinfo",
+            "Information about the opened application",
+            "Platform specific object",
+            Input::None,
+        ),
+        endpoint(
+            "get",
+            "/serverinfo",
+            "Server platform and mode",
+            "{git_hash, target_os, arch, pid, mode, backend, restricted, capabilities, device_throttle: {enabled, low_battery_percent, thermal, battery_percent, charging, thread_limit}}",
+            Input::None,
+        ),
+        endpoint(
+            "get",
+            "/diagnose",
+            "Why a process can or cannot be inspected, with suggested fixes",
+            "{platform, pid, attachable, problems: [{code, message, fix}], ...platform checks}",
+            query::<request::DiagnoseRequest>(),
+        ),
+        endpoint(
+            "post",
+            "/watchpoint",
+            "Set a hardware watchpoint",
+            "{success, message}",
+            body::<request::SetWatchPointRequest>(gen),
+        ),
+        endpoint(
+            "delete",
+            "/watchpoint",
+            "Remove a watchpoint",
+            "{success, message}",
+            body::<request::RemoveWatchPointRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/breakpoint",
+            "Set a hardware breakpoint",
+            "{success, message}",
+            body::<request::SetBreakPointRequest>(gen),
+        ),
+        endpoint(
+            "delete",
+            "/breakpoint",
+            "Remove a breakpoint",
+            "{success, message}",
+            body::<request::RemoveBreakPointRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/exceptioninfo",
+            "Collected watchpoint and breakpoint hits",
+            "[{thread_id, pc, registers, ...}]",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/pointermap",
+            "Generate a pointer map",
+            "Compressed pointer map (application/octet-stream)",
+            body::<request::PointerMapGenerateRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/pointermap/save",
+            "Save a pointer map under a name",
+            "{name, size}",
+            body::<request::PointerMapSaveRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/pointermaps",
+            "List saved pointer maps",
+            "{pointermaps}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/pointermap/compare",
+            "Find pointer paths stable across two maps",
+            "{count, paths: [{module, module_offset, offsets}]}",
+            body::<request::PointerMapCompareRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/pointerpath/validate",
+            "Follow a pointer chain live and in a snapshot",
+            "{base, live: {valid, broken_at, final_address, steps}, snapshot}",
+            body::<request::PointerPathValidateRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/hexdump",
+            "Annotated hex dump",
+            "{lines, annotations}",
+            query::<request::HexDumpRequest>(),
+        ),
+        endpoint(
+            "post",
+            "/struct",
+            "Register a struct layout",
+            "{success, message}",
+            body::<structs::StructLayout>(gen),
+        ),
+        endpoint(
+            "get",
+            "/structs",
+            "List struct layouts",
+            "{structs}",
+            Input::None,
+        ),
+        endpoint(
+            "delete",
+            "/struct",
+            "Remove a struct layout",
+            "{success, message}",
+            body::<request::RemoveStructRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/readstruct",
+            "Read memory as a registered struct",
+            "{address, name, size, fields}",
+            query::<request::ReadStructRequest>(),
+        ),
+        endpoint(
+            "get",
+            "/readarray",
+            "Read an array of values",
+            "{address, data_type, values}",
+            query::<request::ReadArrayRequest>(),
+        ),
+        endpoint(
+            "post",
+            "/writearray",
+            "Write an array of values",
+            "{address, written}",
+            body::<request::WriteArrayRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/guesstype",
+            "Guess the type of the value at an address",
+            "{address, bytes, guesses}",
+            query::<request::GuessTypeRequest>(),
+        ),
+        endpoint(
+            "post",
+            "/formatvalue",
+            "Convert between bytes and a typed value, including float16, fixed-point and bitfields",
+            "{data_type, value, hex, binary, bytes}",
+            body::<request::FormatValueRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/table",
+            "List saved addresses",
+            "{entries}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/table",
+            "Save an address",
+            "TableEntry",
+            body::<table::TableEntry>(gen),
+        ),
+        endpoint(
+            "delete",
+            "/table",
+            "Remove a saved address",
+            "{success}",
+            body::<request::RemoveTableEntryRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/table/presets",
+            "Add or replace a preset value of a saved address",
+            "TableEntry",
+            body::<request::SetPresetRequest>(gen),
+        ),
+        endpoint(
+            "delete",
+            "/table/presets",
+            "Remove a preset value",
+            "TableEntry",
+            body::<request::PresetRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/table/apply",
+            "Write a preset value",
+            "{success, address, preset, written}",
+            body::<request::PresetRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/table/setvalue",
+            "Convert a typed value to an entry's data type and write it through its pointer chain",
+            "{success, id, description, address, data_type, bytes}",
+            body::<request::SetTableValueRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/table/import/gameguardian",
+            "Import a GameGuardian saved list",
+            "{imported: [{entry, frozen, job_id}], skipped}",
+            body::<request::GameGuardianImportRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/table/rebase",
+            "Move module-relative entries to the current module bases",
+            "{entries}",
+            Input::None,
+        ),
+        endpoint(
+            "get",
+            "/table/rebase",
+            "Report of the last rebase",
+            "{entries}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/table/relocate",
+            "Find entry signatures again and move the entries to the matches",
+            "{entries: [{id, relocated, new_address, new_module_offset, moved, reason}]}",
+            body::<request::TableRelocateRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/annotations",
+            "List annotations",
+            "{annotations}",
+            query::<request::ListAnnotationsRequest>(),
+        ),
+        endpoint(
+            "post",
+            "/annotations",
+            "Add or update an annotation",
+            "Annotation",
+            body::<annotations::Annotation>(gen),
+        ),
+        endpoint(
+            "delete",
+            "/annotations",
+            "Remove an annotation",
+            "{success}",
+            body::<request::RemoveAnnotationRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/schedulewrite",
+            "Schedule repeated or delayed writes",
+            "{id}",
+            body::<scheduler::ScheduledWrite>(gen),
+        ),
+        endpoint(
+            "get",
+            "/scheduledwrites",
+            "List scheduled write jobs",
+            "{jobs}",
+            Input::None,
+        ),
+        endpoint(
+            "delete",
+            "/schedulewrite",
+            "Cancel a scheduled write",
+            "{success}",
+            body::<request::CancelScheduledWriteRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/events",
+            "Events queued since the last poll",
+            "[event]",
+            Input::None,
+        ),
+        endpoint(
+            "get",
+            "/events/stream",
+            "Server-sent event stream with a per-client queue, overflow policy and filter",
+            "text/event-stream",
+            query::<events::StreamQuery>(),
+        ),
+        endpoint(
+            "get",
+            "/events/ws",
+            "WebSocket event stream; messages carrying a filter replace the subscription",
+            "JSON text messages",
+            query::<events::StreamQuery>(),
+        ),
+        endpoint(
+            "get",
+            "/events/subscribers",
+            "Connected stream clients with their queue depth and dropped events",
+            "{subscribers}",
+            Input::None,
+        ),
+        endpoint(
+            "get",
+            "/logs",
+            "Newest structured log records from the in-memory ring buffer",
+            "{logs: [{seq, timestamp, level, target, message, fields}]}",
+            query::<logger::LogQuery>(),
+        ),
+        endpoint(
+            "get",
+            "/logs/levels",
+            "Default and per-module log levels",
+            "{default, modules}",
+            Input::None,
+        ),
+        endpoint(
+            "put",
+            "/logs/levels",
+            "Replace the log levels at runtime",
+            "{default, modules}",
+            body::<logger::LogLevelRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/crashes",
+            "Recent panics, newest first, with backtraces and the request they interrupted",
+            "{crashes: [{id, timestamp, thread, message, location, backtrace, request}]}",
+            Input::None,
+        ),
+        endpoint(
+            "delete",
+            "/crashes",
+            "Clear the crash reports",
+            "{cleared}",
+            Input::None,
+        ),
+        endpoint(
+            "get",
+            "/selftest",
+            "Read, write, scan and watchpoint smoke tests on a harmless target and the opened process",
+            "{diagnosis: ok|backend_broken|target_protected, passed, targets: [{kind, pid, backend, passed, checks}]}",
+            query::<selftest::SelfTestQuery>(),
+        ),
+        endpoint(
+            "post",
+            "/regionmonitor/start",
+            "Start watching for mapping changes",
+            "Region monitor state",
+            body::<region_monitor::RegionMonitorRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/regionmonitor/stop",
+            "Stop the region monitor",
+            "{success}",
+            Input::None,
+        ),
+        endpoint(
+            "get",
+            "/regionmonitor",
+            "Region monitor state",
+            "{running, pid, interval_ms, ...}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/mappingwatch",
+            "Watch for a file to be mapped, then record its base, search a signature and fire a trigger",
+            "{id, pid, path, trigger, signature, mapped, base, hits}",
+            body::<mapping_watch::MappingWatchRequest>(gen),
+        ),
+        endpoint(
+            "delete",
+            "/mappingwatch",
+            "Remove a mapping watch",
+            "{removed}",
+            body::<request::RemoveMappingWatchRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/mappingwatch",
+            "List mapping watches with the recorded appearances",
+            "{watches: [{id, path, mapped, base, hits: [{base, end, timestamp, matches, trigger_result}]}]}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/trace",
+            "Single-step trace from an address",
+            "{address, requested, complete, steps: [{index, pc, instruction, changed}]}",
+            body::<tracer::TraceRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/callers",
+            "Watch an address and rank the return addresses on the hitting stacks",
+            "{address, size, hits, elapsed_ms, accessors, candidates: [{return_address, module, offset, hits, share, min_depth, threads}], truncated}",
+            body::<callers::CallerRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/heatmap/start",
+            "Start an access heatmap",
+            "Heatmap report",
+            body::<heatmap::HeatmapRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/heatmap/stop",
+            "Stop the heatmap",
+            "Heatmap report",
+            Input::None,
+        ),
+        endpoint(
+            "get",
+            "/heatmap",
+            "Heatmap buckets",
+            "{running, address, size, bucket_size, total_reads, total_writes, buckets}",
+            Input::None,
+        ),
+        endpoint(
+            "get",
+            "/enumerate_allocations",
+            "List heap allocations",
+            "{count, allocations}",
+            query::<request::EnumerateAllocationsRequest>(),
+        ),
+        endpoint(
+            "get",
+            "/pageinfo",
+            "Residency and protection of pages",
+            "{page_size, resident_count, dirty_count, swapped_count, pages}",
+            query::<request::PageInfoRequest>(),
+        ),
+        endpoint(
+            "post",
+            "/prefetch",
+            "Page in a range before scanning",
+            "Read statistics, as in the scan response",
+            body::<request::PrefetchRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/snapshot",
+            "Save memory to a named snapshot",
+            "{name, bytes, ranges, failed_chunks}",
+            body::<request::TakeSnapshotRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/snapshots",
+            "List snapshots",
+            "{snapshots}",
+            Input::None,
+        ),
+        endpoint(
+            "delete",
+            "/snapshot",
+            "Remove a snapshot",
+            "{success}",
+            body::<request::RemoveSnapshotRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/snapshot/changed",
+            "Compare a snapshot against live memory",
+            "{changed_count, truncated, changes}",
+            body::<request::SnapshotChangedRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/snapshot/diff",
+            "Compare two snapshots",
+            "{old, new, changed_count, truncated, regions, only_in_old, only_in_new}",
+            body::<request::SnapshotDiffRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/batch",
+            "Run several operations in one request",
+            "{results: [{index, op, success, ...}], failed}",
+            body::<batch::BatchRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/triggers",
+            "List trigger bindings",
+            "{triggers}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/triggers",
+            "Bind an action to a trigger id",
+            "Trigger",
+            body::<triggers::Trigger>(gen),
+        ),
+        endpoint(
+            "delete",
+            "/triggers",
+            "Remove a trigger binding",
+            "{removed}",
+            body::<request::UnbindTriggerRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/trigger/{id}",
+            "Fire a trigger",
+            "{id, result}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/input",
+            "Send taps, swipes, key presses and text to the device (Android) or focused window (Linux)",
+            "{sent, error}",
+            body::<input::InputRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/alerts",
+            "List alert rules with their delivery counts",
+            "{alerts}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/alerts",
+            "Push matching events to a webhook or the embedded host's callback",
+            "AlertRule",
+            body::<alerts::AlertRule>(gen),
+        ),
+        endpoint(
+            "delete",
+            "/alerts",
+            "Remove an alert rule",
+            "{removed}",
+            body::<alerts::RemoveAlertRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/profiles",
+            "List saved profiles and what the last attach applied",
+            "{profiles, active, applied}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/profiles",
+            "Save a profile applied on every attach to a process of that name",
+            "Profile",
+            body::<profiles::Profile>(gen),
+        ),
+        endpoint(
+            "delete",
+            "/profiles",
+            "Remove a profile",
+            "{removed}",
+            body::<profiles::RemoveProfileRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/predicates",
+            "List uploaded WASM scan predicates",
+            "{predicates}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/predicates/{name}",
+            "Upload a WASM module exporting match(ptr, len) as a scan and filter predicate; the body is the module",
+            "{name, size, module_size}",
+            query::<wasm_predicates::AddPredicateQuery>(),
+        ),
+        endpoint(
+            "delete",
+            "/predicates",
+            "Remove a WASM scan predicate",
+            "{removed}",
+            body::<wasm_predicates::RemovePredicateRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/plugins",
+            "List loaded plugins with their routes, data types and filter methods",
+            "{plugins}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/plugins/{name}/{route}",
+            "Call a route served by a plugin; the body is passed to it as is",
+            "Defined by the plugin",
+            Input::None,
+        ),
+        endpoint(
+            "get",
+            "/recipes",
+            "List saved scan recipes",
+            "{recipes}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/recipes",
+            "Save a named sequence of scan, filter and wait steps",
+            "Recipe",
+            body::<recipes::Recipe>(gen),
+        ),
+        endpoint(
+            "delete",
+            "/recipes",
+            "Remove a scan recipe",
+            "{removed}",
+            body::<recipes::RemoveRecipeRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/recipes/run",
+            "Run a scan recipe with parameters; stops early once no matches are left",
+            "Result of the last step with {recipe, scan_id, steps}",
+            body::<recipes::RunRecipeRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/undowrite",
+            "Undo the most recent writes",
+            "{restored, failed, remaining}",
+            body::<request::UndoWriteRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/undoallwrites",
+            "Undo every recorded write",
+            "{restored, failed, remaining}",
+            Input::None,
+        ),
+        endpoint(
+            "get",
+            "/writelog",
+            "Audit log of writes",
+            "{writes: [{id, timestamp, pid, address, old, new, source, session}]}",
+            query::<write_history::WriteLogQuery>(),
+        ),
+        endpoint(
+            "post",
+            "/devices",
+            "Register a remote device",
+            "{success}",
+            body::<devices::Device>(gen),
+        ),
+        endpoint(
+            "get",
+            "/devices",
+            "List remote devices",
+            "{devices}",
+            Input::None,
+        ),
+        endpoint(
+            "delete",
+            "/devices",
+            "Remove a remote device",
+            "{success}",
+            body::<request::RemoveDeviceRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/devices/memoryscan",
+            "Run a scan on several devices",
+            "{devices: {name: {found, is_rounded}}, matched_addresses}",
+            body::<devices::FanOutRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/devices/memoryfilter",
+            "Run a filter on several devices",
+            "{devices: {name: {found, is_rounded}}, matched_addresses}",
+            body::<devices::FanOutRequest>(gen),
+        ),
+        endpoint(
+            "post",
+            "/record/start",
+            "Start recording mutating calls",
+            "{success}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/record/stop",
+            "Stop recording and return the calls",
+            "{calls}",
+            Input::None,
+        ),
+        endpoint(
+            "get",
+            "/record",
+            "Recording state",
+            "{recording, calls}",
+            Input::None,
+        ),
+        endpoint(
+            "post",
+            "/replay",
+            "Replay recorded calls",
+            "{results}",
+            body::<recorder::ReplayRequest>(gen),
+        ),
+        endpoint(
+            "get",
+            "/openapi.json",
+            "This document",
+            "OpenAPI 3 document",
+            Input::None,
+        ),
+    ]
+}
+
+fn path_parameters(path: &str) -> Vec<Value> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| {
+            json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" },
+            })
+        })
+        .collect()
+}
+
+fn build() -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+    let mut paths = Map::new();
+    for endpoint in endpoints(&mut gen) {
+        let mut operation = json!({
+            "summary": endpoint.summary,
+            "responses": {
+                "200": { "description": endpoint.response },
+                "400": { "description": "Invalid request, or no process opened" }
+            }
+        });
+        let mut parameters = path_parameters(endpoint.path);
+        match endpoint.input {
+            Input::None => {}
+            Input::Body(schema) => {
+                operation["requestBody"] = json!({
+                    "required": true,
+                    "content": { "application/json": { "schema": schema } }
+                });
+            }
+            Input::Query(query) => parameters.extend(query),
+        }
+        if !parameters.is_empty() {
+            operation["parameters"] = json!(parameters);
+        }
+        let item = paths
+            .entry(endpoint.path)
+            .or_insert_with(|| Value::Object(Map::new()));
+        item[endpoint.method] = operation;
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "memory-server",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": { "schemas": gen.take_definitions() },
+    })
+}
+
+lazy_static! {
+    static ref DOCUMENT: Value = build();
+}
+
+pub fn document() -> &'static Value {
+    &DOCUMENT
+}
@@ -0,0 +1,329 @@
+// Plugins loaded from shared libraries at startup, so niche features can live outside the
+// core crate. Every .so/.dylib/.dll in MEMORY_SERVER_PLUGIN_DIR is loaded and asked for its
+// descriptor through the C ABI below, which stays stable across server versions; a plugin
+// can serve routes under /plugins/<name>/, add fixed-size data types and add filter methods.
+use lazy_static::lazy_static;
+use libc::c_char;
+use libloading::Library;
+use serde_json::{json, Value};
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use std::sync::RwLock;
+
+// Bumped on any incompatible change to PluginDescriptor
+pub const ABI_VERSION: u32 = 1;
+
+// Exported by every plugin as `extern "C" fn memory_server_plugin() -> *const PluginDescriptor`
+const ENTRY_SYMBOL: &[u8] = b"memory_server_plugin\0";
+
+#[repr(C)]
+pub struct PluginDataType {
+    pub name: *const c_char,
+    pub size: usize,
+}
+
+// Returns a buffer released with free_buffer and sets the HTTP status, or null for an unknown
+// route
+type HandleFn = unsafe extern "C" fn(
+    route: *const c_char,
+    body: *const u8,
+    body_len: usize,
+    out_len: *mut usize,
+    status: *mut u16,
+) -> *mut u8;
+// 1 when the value passes the filter method, 0 when not; both buffers hold `len` bytes
+type CompareFn = unsafe extern "C" fn(
+    method: *const c_char,
+    data_type: *const c_char,
+    value: *const u8,
+    previous: *const u8,
+    len: usize,
+) -> i32;
+type FreeFn = unsafe extern "C" fn(buffer: *mut u8, len: usize);
+
+// Must stay valid for as long as the library is loaded, e.g. a static
+#[repr(C)]
+pub struct PluginDescriptor {
+    pub abi_version: u32,
+    pub name: *const c_char,
+    pub routes: *const *const c_char,
+    pub route_count: usize,
+    pub data_types: *const PluginDataType,
+    pub data_type_count: usize,
+    pub comparators: *const *const c_char,
+    pub comparator_count: usize,
+    pub handle: Option<HandleFn>,
+    pub compare: Option<CompareFn>,
+    pub free_buffer: Option<FreeFn>,
+}
+
+// What the server needs from a plugin, whether loaded from a library or built in
+pub trait Plugin: Send + Sync {
+    fn name(&self) -> &str;
+    fn routes(&self) -> &[String];
+    // Name and size in bytes
+    fn data_types(&self) -> &[(String, usize)];
+    // Filter methods, used like "changed" or "increased"
+    fn comparators(&self) -> &[String];
+    // HTTP status and response body
+    fn handle(&self, route: &str, body: &[u8]) -> Option<(u16, Vec<u8>)>;
+    fn compare(&self, method: &str, data_type: &str, value: &[u8], previous: &[u8]) -> bool;
+}
+
+struct NativePlugin {
+    name: String,
+    routes: Vec<String>,
+    data_types: Vec<(String, usize)>,
+    comparators: Vec<String>,
+    handle: Option<HandleFn>,
+    compare: Option<CompareFn>,
+    free_buffer: Option<FreeFn>,
+    // Keeps the functions above mapped
+    _library: Library,
+}
+
+lazy_static! {
+    static ref PLUGINS: RwLock<Vec<Box<dyn Plugin>>> = RwLock::new(Vec::new());
+}
+
+unsafe fn string_list(items: *const *const c_char, count: usize) -> Vec<String> {
+    if items.is_null() {
+        return Vec::new();
+    }
+    std::slice::from_raw_parts(items, count)
+        .iter()
+        .filter(|item| !item.is_null())
+        .map(|item| CStr::from_ptr(*item).to_string_lossy().into_owned())
+        .collect()
+}
+
+impl NativePlugin {
+    unsafe fn load(path: &Path) -> Result<NativePlugin, String> {
+        let library = Library::new(path).map_err(|e| e.to_string())?;
+        let entry = library
+            .get::<unsafe extern "C" fn() -> *const PluginDescriptor>(ENTRY_SYMBOL)
+            .map_err(|e| e.to_string())?;
+        let descriptor = entry()
+            .as_ref()
+            .ok_or("memory_server_plugin returned null")?;
+        if descriptor.abi_version != ABI_VERSION {
+            return Err(format!(
+                "Plugin ABI version {} is not supported, expected {}",
+                descriptor.abi_version, ABI_VERSION
+            ));
+        }
+        if descriptor.name.is_null() {
+            return Err("Plugin has no name".to_string());
+        }
+        let data_types = if descriptor.data_types.is_null() {
+            Vec::new()
+        } else {
+            std::slice::from_raw_parts(descriptor.data_types, descriptor.data_type_count)
+                .iter()
+                .filter(|data_type| !data_type.name.is_null() && data_type.size > 0)
+                .map(|data_type| {
+                    let name = CStr::from_ptr(data_type.name).to_string_lossy();
+                    (name.into_owned(), data_type.size)
+                })
+                .collect()
+        };
+        Ok(NativePlugin {
+            name: CStr::from_ptr(descriptor.name)
+                .to_string_lossy()
+                .into_owned(),
+            routes: string_list(descriptor.routes, descriptor.route_count),
+            data_types,
+            comparators: string_list(descriptor.comparators, descriptor.comparator_count),
+            handle: descriptor.handle,
+            compare: descriptor.compare,
+            free_buffer: descriptor.free_buffer,
+            _library: library,
+        })
+    }
+}
+
+impl Plugin for NativePlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn routes(&self) -> &[String] {
+        &self.routes
+    }
+
+    fn data_types(&self) -> &[(String, usize)] {
+        &self.data_types
+    }
+
+    fn comparators(&self) -> &[String] {
+        &self.comparators
+    }
+
+    fn handle(&self, route: &str, body: &[u8]) -> Option<(u16, Vec<u8>)> {
+        let handle = self.handle?;
+        let route = CString::new(route).ok()?;
+        let (mut len, mut status) = (0usize, 200u16);
+        unsafe {
+            let buffer = handle(
+                route.as_ptr(),
+                body.as_ptr(),
+                body.len(),
+                &mut len,
+                &mut status,
+            );
+            if buffer.is_null() {
+                return None;
+            }
+            let response = std::slice::from_raw_parts(buffer, len).to_vec();
+            if let Some(free_buffer) = self.free_buffer {
+                free_buffer(buffer, len);
+            }
+            Some((status, response))
+        }
+    }
+
+    fn compare(&self, method: &str, data_type: &str, value: &[u8], previous: &[u8]) -> bool {
+        let Some(compare) = self.compare else {
+            return false;
+        };
+        let (Ok(method), Ok(data_type)) = (CString::new(method), CString::new(data_type)) else {
+            return false;
+        };
+        let len = value.len().min(previous.len());
+        unsafe {
+            compare(
+                method.as_ptr(),
+                data_type.as_ptr(),
+                value.as_ptr(),
+                previous.as_ptr(),
+                len,
+            ) == 1
+        }
+    }
+}
+
+// Plugins compiled into the server, or into an app embedding it, register here
+pub fn register(plugin: Box<dyn Plugin>) -> Result<(), String> {
+    let mut plugins = PLUGINS.write().unwrap();
+    if plugins.iter().any(|other| other.name() == plugin.name()) {
+        return Err(format!(
+            "A plugin named {} is already loaded",
+            plugin.name()
+        ));
+    }
+    plugins.push(plugin);
+    Ok(())
+}
+
+fn is_library(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("so" | "dylib" | "dll")
+    )
+}
+
+// Loads the libraries in MEMORY_SERVER_PLUGIN_DIR; one that fails is reported and skipped
+pub fn load_from_env() {
+    let Ok(directory) = std::env::var("MEMORY_SERVER_PLUGIN_DIR") else {
+        return;
+    };
+    let entries = match std::fs::read_dir(&directory) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Failed to read plugin directory {}: {}", directory, e);
+            return;
+        }
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| is_library(path))
+        .collect();
+    paths.sort();
+    for path in paths {
+        let loaded = unsafe { NativePlugin::load(&path) }.and_then(|plugin| {
+            let name = plugin.name.clone();
+            register(Box::new(plugin)).map(|_| name)
+        });
+        match loaded {
+            Ok(name) => println!("Loaded plugin {} from {}.", name, path.display()),
+            Err(e) => eprintln!("Failed to load plugin {}: {}", path.display(), e),
+        }
+    }
+}
+
+pub fn list() -> Vec<Value> {
+    PLUGINS
+        .read()
+        .unwrap()
+        .iter()
+        .map(|plugin| {
+            json!({
+                "name": plugin.name(),
+                "routes": plugin.routes(),
+                "data_types": plugin
+                    .data_types()
+                    .iter()
+                    .map(|(name, size)| json!({ "name": name, "size": size }))
+                    .collect::<Vec<_>>(),
+                "comparators": plugin.comparators(),
+            })
+        })
+        .collect()
+}
+
+pub fn data_type_size(data_type: &str) -> Option<usize> {
+    PLUGINS.read().unwrap().iter().find_map(|plugin| {
+        plugin
+            .data_types()
+            .iter()
+            .find(|(name, _)| name == data_type)
+            .map(|(_, size)| *size)
+    })
+}
+
+pub fn has_comparator(method: &str) -> bool {
+    PLUGINS
+        .read()
+        .unwrap()
+        .iter()
+        .any(|plugin| plugin.comparators().iter().any(|name| name == method))
+}
+
+// True when a plugin decides the filter: the method is one of its comparators, or the data
+// type is one of its own
+pub fn handles_filter(method: &str, data_type: &str) -> bool {
+    has_comparator(method)
+        || (method != "exact"
+            && PLUGINS.read().unwrap().iter().any(|plugin| {
+                plugin
+                    .data_types()
+                    .iter()
+                    .any(|(name, _)| name == data_type)
+            }))
+}
+
+pub fn compare(method: &str, data_type: &str, value: &[u8], previous: &[u8]) -> bool {
+    let plugins = PLUGINS.read().unwrap();
+    let plugin = plugins
+        .iter()
+        .find(|plugin| plugin.comparators().iter().any(|name| name == method))
+        .or_else(|| {
+            plugins.iter().find(|plugin| {
+                plugin
+                    .data_types()
+                    .iter()
+                    .any(|(name, _)| name == data_type)
+            })
+        });
+    plugin.is_some_and(|plugin| plugin.compare(method, data_type, value, previous))
+}
+
+// None when there is no such plugin or route
+pub fn handle(name: &str, route: &str, body: &[u8]) -> Option<(u16, Vec<u8>)> {
+    let plugins = PLUGINS.read().unwrap();
+    let plugin = plugins.iter().find(|plugin| plugin.name() == name)?;
+    if !plugin.routes().iter().any(|known| known == route) {
+        return None;
+    }
+    plugin.handle(route, body)
+}
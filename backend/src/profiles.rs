@@ -0,0 +1,280 @@
+// Per-game profiles: scan defaults, modules left out of scans, saved table entries and
+// freezes, stored by process name and applied on every attach to a matching process so a
+// routine session starts where the last one left off. Android apps run as app_process, so
+// their package name from the command line also matches.
+use crate::batch;
+use crate::exclusions;
+use crate::native_bridge;
+use crate::request;
+use crate::scan_sessions;
+use crate::scheduler;
+use crate::table::{self, TableEntry};
+use crate::util;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+// Used for scans whose request leaves the field out
+#[derive(Deserialize, Serialize, Clone, Default, JsonSchema)]
+pub struct ScanDefaults {
+    pub skip_nonresident: Option<bool>,
+    pub simple_values: Option<bool>,
+    pub decode_values: Option<bool>,
+    pub static_only: Option<bool>,
+    pub priority: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct ProfileFreeze {
+    // Description of a table entry, e.g. one of the profile's own
+    pub entry: String,
+    // Typed as for /table/setvalue, e.g. "999999"
+    pub value: String,
+    pub interval_ms: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct Profile {
+    // Executable file name or Android package name, compared ignoring case
+    pub process: String,
+    #[serde(default)]
+    pub scan_defaults: ScanDefaults,
+    // Module file names added to the exclusion list, e.g. ["libGLESv2_adreno.so"]
+    #[serde(default)]
+    pub excluded_modules: Vec<String>,
+    // Module-relative entries are rebased to the new load address
+    #[serde(default)]
+    pub entries: Vec<TableEntry>,
+    #[serde(default)]
+    pub freezes: Vec<ProfileFreeze>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct RemoveProfileRequest {
+    pub process: String,
+}
+
+// What the last attach applied, undone before the next one applies its own
+#[derive(Default)]
+struct Applied {
+    process: Option<String>,
+    scan_defaults: ScanDefaults,
+    excluded: Vec<(usize, usize)>,
+    freeze_jobs: Vec<u64>,
+    report: Value,
+}
+
+lazy_static! {
+    static ref ACTIVE: Mutex<Applied> = Mutex::new(Applied::default());
+    // Serializes the read-modify-write cycles on the profile file
+    static ref FILE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+fn profile_path() -> PathBuf {
+    let mut path = util::get_data_directory(std::process::id() as i32);
+    path.push("profiles.json");
+    path
+}
+
+fn load() -> BTreeMap<String, Profile> {
+    fs::read_to_string(profile_path())
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save(profiles: &BTreeMap<String, Profile>) -> Result<(), String> {
+    let path = profile_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create profile directory: {}", e))?;
+    }
+    let raw = serde_json::to_string_pretty(profiles).map_err(|e| e.to_string())?;
+    fs::write(&path, raw).map_err(|e| format!("Failed to save profiles: {}", e))
+}
+
+fn key(process: &str) -> String {
+    process.trim().to_lowercase()
+}
+
+pub fn list() -> Value {
+    let active = ACTIVE.lock().unwrap();
+    json!({
+        "profiles": load().into_values().collect::<Vec<_>>(),
+        "active": active.process,
+        "applied": active.report,
+    })
+}
+
+pub fn upsert(profile: Profile) -> Result<Profile, String> {
+    if profile.process.trim().is_empty() {
+        return Err("process must not be empty".to_string());
+    }
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut profiles = load();
+    profiles.insert(key(&profile.process), profile.clone());
+    save(&profiles)?;
+    Ok(profile)
+}
+
+pub fn remove(process: &str) -> Result<bool, String> {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut profiles = load();
+    let removed = profiles.remove(&key(process)).is_some();
+    if removed {
+        save(&profiles)?;
+    }
+    Ok(removed)
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn command_line_name(pid: i32) -> Option<String> {
+    let raw = fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+    let first = raw.split(|byte| *byte == 0).next()?;
+    let name = String::from_utf8_lossy(first);
+    Some(name.rsplit('/').next().unwrap_or(&name).to_string())
+}
+
+#[cfg(not(any(target_os = "android", target_os = "linux")))]
+fn command_line_name(_pid: i32) -> Option<String> {
+    None
+}
+
+fn matching_profile(pid: i32) -> Option<Profile> {
+    let profiles = load();
+    if profiles.is_empty() {
+        return None;
+    }
+    [scan_sessions::process_name(pid), command_line_name(pid)]
+        .into_iter()
+        .flatten()
+        .find_map(|name| profiles.get(&key(&name)).cloned())
+}
+
+fn module_ranges(pid: i32, names: &[String]) -> Vec<(usize, usize)> {
+    let modules = native_bridge::enum_modules(pid).unwrap_or_default();
+    modules
+        .iter()
+        .filter_map(|module| {
+            let path = module["modulename"].as_str()?;
+            let file_name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+            if !names
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(file_name))
+            {
+                return None;
+            }
+            let base = module["base"].as_u64()? as usize;
+            Some((base, base + module["size"].as_i64()? as usize))
+        })
+        .collect()
+}
+
+fn add_entries(pid: i32, entries: &[TableEntry]) -> usize {
+    let existing = table::list_entries();
+    let mut added = 0;
+    for entry in entries {
+        // Attaching again must not pile up copies of the same entries
+        let duplicate = existing.iter().any(|other| {
+            other.description == entry.description
+                && other.module == entry.module
+                && other.module_offset == entry.module_offset
+                && (entry.module.is_some() || other.address == entry.address)
+        });
+        if !duplicate {
+            table::add_entry(Some(pid), entry.clone());
+            added += 1;
+        }
+    }
+    added
+}
+
+fn start_freeze(pid: i32, freeze: &ProfileFreeze) -> Result<u64, String> {
+    let entry = table::find_entry(None, Some(&freeze.entry))?;
+    let buffer = util::parse_typed_value(&entry.data_type, &freeze.value)?;
+    let write = scheduler::ScheduledWrite {
+        address: table::resolve_address(pid, &entry)?,
+        buffer,
+        sequence: None,
+        interval_ms: Some(
+            freeze
+                .interval_ms
+                .unwrap_or(batch::DEFAULT_FREEZE_INTERVAL_MS),
+        ),
+        duration_ms: None,
+        restore_after_ms: None,
+    };
+    scheduler::schedule(pid, write, "profile")
+}
+
+// Undoes what the previous attach applied, applies the profile matching `pid`, if any, and
+// rebases the table onto the modules of `pid`, profile entries included.
+pub fn apply_on_attach(pid: i32) {
+    let mut active = ACTIVE.lock().unwrap();
+    for id in active.freeze_jobs.drain(..) {
+        scheduler::cancel(id);
+    }
+    if !active.excluded.is_empty() {
+        exclusions::remove(Some(&active.excluded));
+    }
+    *active = Applied::default();
+
+    let Some(profile) = matching_profile(pid) else {
+        if !table::is_empty() {
+            table::rebase_entries(pid);
+        }
+        return;
+    };
+    let excluded = module_ranges(pid, &profile.excluded_modules);
+    if !excluded.is_empty() {
+        exclusions::add(&excluded);
+    }
+    let added = add_entries(pid, &profile.entries);
+    if !table::is_empty() {
+        table::rebase_entries(pid);
+    }
+
+    let mut freezes = Vec::new();
+    let mut freeze_jobs = Vec::new();
+    for freeze in &profile.freezes {
+        match start_freeze(pid, freeze) {
+            Ok(id) => {
+                freeze_jobs.push(id);
+                freezes.push(json!({ "entry": freeze.entry, "job_id": id }));
+            }
+            Err(e) => freezes.push(json!({ "entry": freeze.entry, "error": e })),
+        }
+    }
+
+    active.report = json!({
+        "pid": pid,
+        "excluded_ranges": excluded.len(),
+        "entries_added": added,
+        "freezes": freezes,
+    });
+    active.process = Some(profile.process);
+    active.scan_defaults = profile.scan_defaults;
+    active.excluded = excluded;
+    active.freeze_jobs = freeze_jobs;
+}
+
+// Fills the fields the request left out from the active profile's scan defaults
+pub fn apply_scan_defaults(scan_request: &mut request::MemoryScanRequest) {
+    let active = ACTIVE.lock().unwrap();
+    if active.process.is_none() {
+        return;
+    }
+    let defaults = &active.scan_defaults;
+    scan_request.skip_nonresident = scan_request.skip_nonresident.or(defaults.skip_nonresident);
+    scan_request.simple_values = scan_request.simple_values.or(defaults.simple_values);
+    scan_request.decode_values = scan_request.decode_values.or(defaults.decode_values);
+    scan_request.static_only = scan_request.static_only.or(defaults.static_only);
+    if scan_request.priority.is_none() {
+        scan_request.priority = defaults.priority.clone();
+    }
+}
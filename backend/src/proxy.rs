@@ -0,0 +1,193 @@
+// Proxy mode forwards every request to another memory server (for example a phone reached
+// through adb forward), so frontends only ever talk to the local instance.
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Request, Response, StatusCode};
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::time::Duration;
+use warp::http::{HeaderMap, Method};
+use warp::path::FullPath;
+use warp::{Filter, Rejection, Reply};
+
+// Sent by the proxy and echoed by the upstream when the response body is gzip compressed
+pub const COMPRESSION_HEADER: &str = "x-memory-server-compression";
+const MIN_COMPRESS_SIZE: usize = 1024;
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(600);
+
+lazy_static! {
+    static ref CLIENT: Client<HttpConnector> = Client::builder()
+        .pool_idle_timeout(Duration::from_secs(90))
+        .build_http();
+}
+
+pub fn proxy_target() -> Option<String> {
+    std::env::var("MEMORY_SERVER_PROXY")
+        .ok()
+        .map(|target| target.trim_end_matches('/').to_string())
+}
+
+pub async fn send_json(method: Method, url: &str, body: Option<&Value>) -> Result<Value, String> {
+    let mut builder = Request::builder().method(method).uri(url);
+    if body.is_some() {
+        builder = builder.header("Content-Type", "application/json");
+    }
+    let body = body
+        .map(|body| Body::from(body.to_string()))
+        .unwrap_or_else(Body::empty);
+    let request = builder.body(body).map_err(|e| e.to_string())?;
+
+    let response = tokio::time::timeout(UPSTREAM_TIMEOUT, CLIENT.request(request))
+        .await
+        .map_err(|_| format!("{} timed out", url))?
+        .map_err(|e| format!("{} unreachable: {}", url, e))?;
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body())
+        .await
+        .map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!(
+            "{} returned {}: {}",
+            url,
+            status,
+            String::from_utf8_lossy(&body)
+        ));
+    }
+    // Some endpoints answer with plain text such as "OK"
+    Ok(serde_json::from_slice(&body)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).into_owned())))
+}
+
+fn error_response(status: StatusCode, message: String) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(json!({ "error": message }).to_string()))
+        .unwrap()
+}
+
+async fn forward(
+    target: String,
+    method: Method,
+    path: FullPath,
+    query: String,
+    headers: HeaderMap,
+    body: hyper::body::Bytes,
+) -> Result<Response<Body>, Rejection> {
+    let uri = if query.is_empty() {
+        format!("{}{}", target, path.as_str())
+    } else {
+        format!("{}{}?{}", target, path.as_str(), query)
+    };
+
+    let mut builder = Request::builder().method(method).uri(&uri);
+    for (name, value) in headers.iter() {
+        if name == "host" || name == "content-length" || name == "accept-encoding" {
+            continue;
+        }
+        builder = builder.header(name, value);
+    }
+    let request = match builder
+        .header(COMPRESSION_HEADER, "gzip")
+        .body(Body::from(body))
+    {
+        Ok(request) => request,
+        Err(e) => return Ok(error_response(StatusCode::BAD_REQUEST, e.to_string())),
+    };
+
+    let response = match tokio::time::timeout(UPSTREAM_TIMEOUT, CLIENT.request(request)).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) => {
+            return Ok(error_response(
+                StatusCode::BAD_GATEWAY,
+                format!("Upstream {} unreachable: {}", target, e),
+            ))
+        }
+        Err(_) => {
+            return Ok(error_response(
+                StatusCode::GATEWAY_TIMEOUT,
+                format!("Upstream {} timed out", target),
+            ))
+        }
+    };
+
+    let (mut parts, body) = response.into_parts();
+    let compressed = parts.headers.remove(COMPRESSION_HEADER).is_some();
+    // The local CORS filter adds its own headers
+    let cors_headers: Vec<_> = parts
+        .headers
+        .keys()
+        .filter(|name| name.as_str().starts_with("access-control-"))
+        .cloned()
+        .collect();
+    for name in cors_headers {
+        parts.headers.remove(name);
+    }
+
+    if !compressed {
+        return Ok(Response::from_parts(parts, body));
+    }
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(e) => return Ok(error_response(StatusCode::BAD_GATEWAY, e.to_string())),
+    };
+    let mut decompressed = Vec::new();
+    if let Err(e) = GzDecoder::new(&body[..]).read_to_end(&mut decompressed) {
+        return Ok(error_response(StatusCode::BAD_GATEWAY, e.to_string()));
+    }
+    parts.headers.remove("content-length");
+    Ok(Response::from_parts(parts, Body::from(decompressed)))
+}
+
+pub fn routes(
+    target: String,
+) -> impl Filter<Extract = (Response<Body>,), Error = Rejection> + Clone {
+    let query = warp::query::raw().or(warp::any().map(String::new)).unify();
+    warp::method()
+        .and(warp::path::full())
+        .and(query)
+        .and(warp::header::headers_cloned())
+        .and(warp::body::bytes())
+        .and_then(move |method, path, query, headers, body| {
+            forward(target.clone(), method, path, query, headers, body)
+        })
+}
+
+// Compresses replies for requests that come through a proxy instance
+pub async fn compress_reply(
+    compression: Option<String>,
+    reply: impl Reply,
+) -> Result<Response<Body>, Rejection> {
+    let response = reply.into_response();
+    let is_stream = response
+        .headers()
+        .get("content-type")
+        .map(|value| value.as_bytes().starts_with(b"text/event-stream"))
+        .unwrap_or(false);
+    if compression.as_deref() != Some("gzip") || is_stream {
+        return Ok(response);
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(_) => return Ok(Response::from_parts(parts, Body::empty())),
+    };
+    if body.len() < MIN_COMPRESS_SIZE {
+        return Ok(Response::from_parts(parts, Body::from(body)));
+    }
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+    match encoder.write_all(&body).and_then(|_| encoder.finish()) {
+        Ok(compressed) => {
+            parts.headers.remove("content-length");
+            parts
+                .headers
+                .insert(COMPRESSION_HEADER, "gzip".parse().unwrap());
+            Ok(Response::from_parts(parts, Body::from(compressed)))
+        }
+        Err(_) => Ok(Response::from_parts(parts, Body::from(body))),
+    }
+}
@@ -1,12 +1,14 @@
 use crate::native_bridge;
+use crate::util;
+use flate2::read::ZlibDecoder;
 use flate2::write::ZlibEncoder;
 use flate2::Compression;
 use libc;
-use rayon::prelude::*;
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::collections::HashMap;
-use std::io::{Cursor, Write};
-use std::sync::{Arc, Mutex};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::PathBuf;
 
 #[repr(C)]
 struct ModuleEntry {
@@ -110,6 +112,8 @@ pub fn generate_pointermap(pid: i32) -> Result<Vec<u8>, String> {
     };
 
     let mut pointer_map: HashMap<u64, Vec<(u64, Option<StaticData>)>> = HashMap::new();
+    // 32-bit targets store 4-byte pointers at 4-byte alignment
+    let pointer_size = native_bridge::pointer_size(pid);
 
     // Process each memory region
     for region in regions {
@@ -129,21 +133,25 @@ pub fn generate_pointermap(pid: i32) -> Result<Vec<u8>, String> {
             let chunk_end = (current_address + CHUNK_SIZE).min(end_address);
             let chunk_size = chunk_end - current_address;
 
-            if chunk_size < 8 {
+            if chunk_size < pointer_size {
                 current_address = chunk_end;
                 continue;
             }
 
             if let Ok(memory) = read_memory(pid, current_address, chunk_size) {
-                let aligned_start = (current_address + 7) & !7;
-                let offset = (aligned_start - current_address) as usize;
+                let aligned_start = (current_address + pointer_size - 1) & !(pointer_size - 1);
+                let offset = aligned_start - current_address;
 
-                for i in (offset..memory.len()).step_by(8) {
-                    if i + 8 > memory.len() {
+                for i in (offset..memory.len()).step_by(pointer_size) {
+                    if i + pointer_size > memory.len() {
                         break;
                     }
 
-                    let value = u64::from_le_bytes(memory[i..i + 8].try_into().unwrap());
+                    let value = if pointer_size == 4 {
+                        u32::from_le_bytes(memory[i..i + 4].try_into().unwrap()) as u64
+                    } else {
+                        u64::from_le_bytes(memory[i..i + 8].try_into().unwrap())
+                    };
                     if value >= min_valid_addr && value < max_valid_addr && value % 4 == 0 {
                         let source_address = current_address + i;
                         let static_data = find_static_data(source_address, &modules);
@@ -228,3 +236,338 @@ pub fn generate_pointermap(pid: i32) -> Result<Vec<u8>, String> {
 
     Ok(compressed)
 }
+
+// A pointer's address, with its module index and offset when it lies in a module
+pub type StoredPointer = (u64, Option<(u32, u32)>);
+
+pub struct PointerMapData {
+    pub modules: Vec<(String, u64)>,
+    // Sorted by target value; each entry lists the addresses holding that value
+    pub entries: Vec<(u64, Vec<StoredPointer>)>,
+}
+
+struct ByteReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, size: usize) -> Result<&'a [u8], String> {
+        if self.offset + size > self.data.len() {
+            return Err("Unexpected end of pointer map".to_string());
+        }
+        let slice = &self.data[self.offset..self.offset + size];
+        self.offset += size;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+pub fn parse_pointermap(compressed: &[u8]) -> Result<PointerMapData, String> {
+    let mut data = Vec::new();
+    ZlibDecoder::new(compressed)
+        .read_to_end(&mut data)
+        .map_err(|e| format!("Failed to decompress pointer map: {}", e))?;
+
+    let mut reader = ByteReader {
+        data: &data,
+        offset: 0,
+    };
+    if reader.take(2)? != [0xCE, 0x01] {
+        return Err("Invalid pointer map magic".to_string());
+    }
+
+    let module_count = reader.u32()?;
+    let mut modules = Vec::with_capacity(module_count as usize);
+    for _ in 0..module_count {
+        let length = reader.u32()? as usize;
+        let name = String::from_utf8_lossy(reader.take(length)?).into_owned();
+        let base = reader.u64()?;
+        modules.push((name, base));
+    }
+
+    // Separator and max level
+    reader.u8()?;
+    reader.u32()?;
+
+    let total_count = reader.u64()?;
+    let mut entries = Vec::new();
+    let mut read_count = 0;
+    while read_count < total_count {
+        let target = reader.u64()?;
+        let count = reader.u32()?;
+        let mut pointers = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let address = reader.u64()?;
+            let static_data = if reader.u8()? == 1 {
+                Some((reader.u32()?, reader.u32()?))
+            } else {
+                None
+            };
+            pointers.push((address, static_data));
+        }
+        read_count += count as u64;
+        entries.push((target, pointers));
+    }
+
+    Ok(PointerMapData { modules, entries })
+}
+
+fn pointermap_directory(pid: i32) -> PathBuf {
+    let mut path = util::get_data_directory(pid);
+    path.push("pointermaps");
+    path
+}
+
+fn pointermap_path(pid: i32, name: &str) -> PathBuf {
+    let mut path = pointermap_directory(pid);
+    path.push(format!(
+        "{}.ptrmap",
+        name.trim().replace([' ', '/', '\\'], "_")
+    ));
+    path
+}
+
+pub fn save_pointermap(pid: i32, name: &str) -> Result<usize, String> {
+    let compressed = generate_pointermap(pid)?;
+    fs::create_dir_all(pointermap_directory(pid))
+        .map_err(|e| format!("Failed to create directory: {}", e))?;
+    fs::write(pointermap_path(pid, name), &compressed)
+        .map_err(|e| format!("Failed to write pointer map: {}", e))?;
+    Ok(compressed.len())
+}
+
+pub fn load_pointermap(pid: i32, name: &str) -> Result<PointerMapData, String> {
+    let compressed = fs::read(pointermap_path(pid, name))
+        .map_err(|e| format!("Failed to read pointer map '{}': {}", name, e))?;
+    parse_pointermap(&compressed)
+}
+
+pub fn list_pointermaps(pid: i32) -> Vec<Value> {
+    let entries = match fs::read_dir(pointermap_directory(pid)) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut maps: Vec<Value> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "ptrmap" {
+                return None;
+            }
+            let metadata = entry.metadata().ok()?;
+            Some(json!({
+                "name": path.file_stem()?.to_string_lossy(),
+                "size": metadata.len()
+            }))
+        })
+        .collect();
+    maps.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+    maps
+}
+
+pub struct PointerPath {
+    pub module: String,
+    pub module_offset: u64,
+    pub offsets: Vec<u64>,
+}
+
+// Static paths to `target`, shortest first, keeping only those `keep` accepts so that
+// rejected candidates never pile up
+pub fn find_pointer_paths(
+    map: &PointerMapData,
+    target: u64,
+    max_depth: usize,
+    max_offset: u64,
+    max_results: usize,
+    keep: impl Fn(&PointerPath) -> bool,
+) -> Vec<PointerPath> {
+    const MAX_NODES_PER_LEVEL: usize = 200_000;
+    let mut results = Vec::new();
+    let mut frontier: Vec<(u64, Vec<u64>)> = vec![(target, Vec::new())];
+
+    for _ in 0..max_depth {
+        let mut next_frontier = Vec::new();
+        for (address, offsets) in &frontier {
+            let lowest = address.saturating_sub(max_offset);
+            let start = map.entries.partition_point(|(value, _)| *value < lowest);
+            for (value, pointers) in map.entries[start..]
+                .iter()
+                .take_while(|(value, _)| *value <= *address)
+            {
+                let mut path_offsets = Vec::with_capacity(offsets.len() + 1);
+                path_offsets.push(address - value);
+                path_offsets.extend_from_slice(offsets);
+                for (pointer_address, static_data) in pointers {
+                    if let Some((module_index, module_offset)) = static_data {
+                        if let Some((module, _)) = map.modules.get(*module_index as usize) {
+                            let path = PointerPath {
+                                module: module.clone(),
+                                module_offset: *module_offset as u64,
+                                offsets: path_offsets.clone(),
+                            };
+                            if !keep(&path) {
+                                continue;
+                            }
+                            results.push(path);
+                            if results.len() >= max_results {
+                                return results;
+                            }
+                        }
+                    } else if next_frontier.len() < MAX_NODES_PER_LEVEL {
+                        next_frontier.push((*pointer_address, path_offsets.clone()));
+                    }
+                }
+            }
+        }
+        if next_frontier.is_empty() {
+            break;
+        }
+        frontier = next_frontier;
+    }
+    results
+}
+
+pub fn compare_pointermaps(
+    map_a: &PointerMapData,
+    target_a: u64,
+    map_b: &PointerMapData,
+    target_b: u64,
+    max_depth: usize,
+    max_offset: u64,
+    max_results: usize,
+) -> Vec<Value> {
+    // Index map B by pointer address so each path resolves in O(depth)
+    let values_b: HashMap<u64, u64> = map_b
+        .entries
+        .iter()
+        .flat_map(|(value, pointers)| pointers.iter().map(move |(address, _)| (*address, *value)))
+        .collect();
+
+    // Paths that break in map B are dropped as they are found, so at most max_results are
+    // ever held
+    let mut stable = find_pointer_paths(
+        map_a,
+        target_a,
+        max_depth,
+        max_offset,
+        max_results,
+        |path| {
+            let base = match map_b.modules.iter().find(|(name, _)| *name == path.module) {
+                Some((_, base)) => *base,
+                None => return false,
+            };
+            let mut address = base + path.module_offset;
+            for offset in &path.offsets {
+                match values_b.get(&address) {
+                    Some(value) => address = value + offset,
+                    None => return false,
+                }
+            }
+            address == target_b
+        },
+    );
+
+    stable.sort_by_key(|path| (path.offsets.len(), path.offsets.iter().sum::<u64>()));
+    stable
+        .into_iter()
+        .map(|path| {
+            json!({
+                "module": path.module,
+                "module_offset": path.module_offset,
+                "offsets": path.offsets
+            })
+        })
+        .collect()
+}
+
+// Follows a pointer chain from `base` the way paths from `find_pointer_paths` are applied,
+// reporting every dereference and the level where the chain breaks, if it does.
+pub fn validate_pointer_path(
+    base: u64,
+    offsets: &[u64],
+    expected: Option<u64>,
+    read_pointer: impl Fn(u64) -> Result<u64, String>,
+) -> Value {
+    let mut steps = Vec::with_capacity(offsets.len());
+    let mut address = base;
+    for (level, offset) in offsets.iter().enumerate() {
+        let error = match read_pointer(address) {
+            Ok(0) => "Null pointer".to_string(),
+            Ok(value) => {
+                let next = value.wrapping_add(*offset);
+                steps.push(json!({
+                    "level": level,
+                    "address": address,
+                    "value": value,
+                    "offset": offset,
+                    "next": next
+                }));
+                address = next;
+                continue;
+            }
+            Err(e) => e,
+        };
+        return json!({
+            "valid": false,
+            "broken_at": level,
+            "broken_address": address,
+            "error": error,
+            "steps": steps
+        });
+    }
+    json!({
+        "valid": expected.is_none_or(|expected| expected == address),
+        "broken_at": null,
+        "final_address": address,
+        "matches_expected": expected.map(|expected| expected == address),
+        "steps": steps
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_keeps_paths_that_hold_in_both_maps() {
+        // In A, [game+0x10]+0x10 and [game+0x100+i]+i reach the target; only the first is
+        // still there in B
+        let unstable = (1..100u32).map(|i| {
+            (
+                0x9000 - i as u64,
+                vec![(0x1100 + i as u64, Some((0, 0x100 + i)))],
+            )
+        });
+        let mut entries: Vec<(u64, Vec<StoredPointer>)> = unstable
+            .chain([(0x8ff0, vec![(0x1010, Some((0, 0x10)))])])
+            .collect();
+        entries.sort_by_key(|(value, _)| *value);
+        let map_a = PointerMapData {
+            modules: vec![("game".to_string(), 0x1000)],
+            entries,
+        };
+        let map_b = PointerMapData {
+            modules: vec![("game".to_string(), 0x5000)],
+            entries: vec![(0x9ff0, vec![(0x5010, Some((0, 0x10)))])],
+        };
+
+        let paths = compare_pointermaps(&map_a, 0x9000, &map_b, 0xa000, 2, 0x1000, 1);
+        assert_eq!(
+            paths,
+            [json!({ "module": "game", "module_offset": 0x10, "offsets": [0x10] })]
+        );
+        assert!(find_pointer_paths(&map_a, 0x9000, 2, 0x1000, 1, |_| false).is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+// Python bindings for the core engine, built with `--features python`. The resulting
+// library is imported as `memory_inspector`:
+//
+//     import memory_inspector
+//     process = memory_inspector.attach(1234)
+//     addresses = process.scan_value(100, "int32", align=4)
+//     process.write(addresses[0], (999).to_bytes(4, "little"))
+use crate::native_bridge;
+use crate::util;
+use memchr::memmem;
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use serde_json::Value;
+
+const CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+// Hands JSON values to Python through its own json module, as dicts and lists
+fn to_python(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    Ok(py
+        .import_bound("json")?
+        .call_method1("loads", (value.to_string(),))?
+        .unbind())
+}
+
+fn from_python(value: &Bound<'_, PyAny>) -> PyResult<Value> {
+    if let Ok(value) = value.extract::<i64>() {
+        Ok(Value::from(value))
+    } else if let Ok(value) = value.extract::<u64>() {
+        Ok(Value::from(value))
+    } else if let Ok(value) = value.extract::<f64>() {
+        Ok(Value::from(value))
+    } else if let Ok(value) = value.extract::<String>() {
+        Ok(Value::from(value))
+    } else {
+        Err(PyValueError::new_err("value must be an int, float or str"))
+    }
+}
+
+#[pyclass(module = "memory_inspector")]
+struct Process {
+    #[pyo3(get)]
+    pid: i32,
+}
+
+impl Process {
+    fn readable_ranges(&self) -> PyResult<Vec<(usize, usize)>> {
+        let regions = native_bridge::enum_regions(self.pid).map_err(PyOSError::new_err)?;
+        Ok(util::parse_regions(&regions)
+            .into_iter()
+            .filter(|(_, _, protection)| protection.starts_with('r'))
+            .map(|(start, end, _)| (start, end))
+            .collect())
+    }
+
+    fn find(&self, needle: &[u8], ranges: &[(usize, usize)], align: usize) -> Vec<usize> {
+        let finder = memmem::Finder::new(needle);
+        let mut found = Vec::new();
+        for &(start, end) in ranges {
+            let mut address = start;
+            while address < end {
+                // Chunks overlap by the pattern length so matches across a boundary are kept
+                let size = (end - address).min(CHUNK_SIZE + needle.len() - 1);
+                let mut buffer = vec![0u8; size];
+                let nread = native_bridge::read_process_memory(
+                    self.pid,
+                    address as *mut libc::c_void,
+                    size,
+                    &mut buffer,
+                )
+                .unwrap_or(0);
+                buffer.truncate(nread.max(0) as usize);
+                found.extend(
+                    finder
+                        .find_iter(&buffer)
+                        .map(|offset| address + offset)
+                        .filter(|found| found % align == 0),
+                );
+                address += CHUNK_SIZE;
+            }
+        }
+        found.dedup();
+        found
+    }
+}
+
+#[pymethods]
+impl Process {
+    fn read<'py>(
+        &self,
+        py: Python<'py>,
+        address: usize,
+        size: usize,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let mut buffer = vec![0u8; size];
+        let nread = native_bridge::read_process_memory(
+            self.pid,
+            address as *mut libc::c_void,
+            size,
+            &mut buffer,
+        )
+        .map_err(|e| PyOSError::new_err(format!("ReadProcessMemory error: {}", e)))?;
+        buffer.truncate(nread.max(0) as usize);
+        Ok(PyBytes::new_bound(py, &buffer))
+    }
+
+    fn write(&self, address: usize, data: &[u8]) -> PyResult<isize> {
+        native_bridge::write_process_memory(
+            self.pid,
+            address as *mut libc::c_void,
+            data.len(),
+            data,
+        )
+        .map(|written| written as isize)
+        .map_err(|e| PyOSError::new_err(format!("WriteProcessMemory error: {}", e)))
+    }
+
+    fn modules(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let modules = native_bridge::enum_modules(self.pid).map_err(PyOSError::new_err)?;
+        to_python(py, &Value::from(modules))
+    }
+
+    fn regions(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let regions = native_bridge::enum_regions(self.pid).map_err(PyOSError::new_err)?;
+        to_python(py, &Value::from(regions))
+    }
+
+    // Addresses of every occurrence of `pattern`, in readable memory or the given ranges
+    #[pyo3(signature = (pattern, align = 1, ranges = None))]
+    fn scan(
+        &self,
+        py: Python<'_>,
+        pattern: &[u8],
+        align: usize,
+        ranges: Option<Vec<(usize, usize)>>,
+    ) -> PyResult<Vec<usize>> {
+        if pattern.is_empty() || align == 0 {
+            return Err(PyValueError::new_err("pattern and align must not be empty"));
+        }
+        let ranges = match ranges {
+            Some(ranges) => ranges,
+            None => self.readable_ranges()?,
+        };
+        Ok(py.allow_threads(|| self.find(pattern, &ranges, align)))
+    }
+
+    // Like `scan`, with the value encoded as `data_type` the same way the HTTP API does
+    #[pyo3(signature = (value, data_type, align = 1, ranges = None))]
+    fn scan_value(
+        &self,
+        py: Python<'_>,
+        value: &Bound<'_, PyAny>,
+        data_type: &str,
+        align: usize,
+        ranges: Option<Vec<(usize, usize)>>,
+    ) -> PyResult<Vec<usize>> {
+        let pattern =
+            util::encode_value(data_type, &from_python(value)?).map_err(PyValueError::new_err)?;
+        self.scan(py, &pattern, align, ranges)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Process pid={}>", self.pid)
+    }
+}
+
+#[pyfunction]
+fn attach(pid: i32) -> PyResult<Process> {
+    // Fails early for processes that cannot be inspected instead of on the first read
+    native_bridge::enum_regions(pid).map_err(PyOSError::new_err)?;
+    Ok(Process { pid })
+}
+
+// Starts the HTTP server in the background, for the web UI next to a notebook
+#[pyfunction]
+#[pyo3(signature = (port = 3030, host = "0.0.0.0"))]
+fn serve(port: u16, host: &str) -> PyResult<()> {
+    let host = host
+        .parse()
+        .map_err(|e| PyValueError::new_err(format!("Invalid host: {}", e)))?;
+    crate::spawn_server(0, "python", host, port).map_err(PyOSError::new_err)
+}
+
+#[pyfunction]
+fn stop() -> bool {
+    crate::stop()
+}
+
+#[pymodule]
+fn memory_inspector(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    native_bridge::native_api_init(0);
+    module.add_class::<Process>()?;
+    module.add_function(wrap_pyfunction!(attach, module)?)?;
+    module.add_function(wrap_pyfunction!(serve, module)?)?;
+    module.add_function(wrap_pyfunction!(stop, module)?)?;
+    Ok(())
+}
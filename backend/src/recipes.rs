@@ -0,0 +1,232 @@
+// Scan recipes: named sequences of scan, filter and wait steps kept as data, so a known way
+// of finding a value, e.g. "Unity player health finder", can be shared as JSON and run on
+// the server in one call. Step requests are /memoryscan and /memoryfilter bodies in which
+// "{{name}}" is replaced by the run's parameters.
+use crate::util;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecipeStep {
+    // address_ranges, align, return_as_json and do_suspend are filled in when left out
+    Scan { request: Value },
+    Filter { request: Value },
+    // Time for the value to change in the game between filters
+    Wait { ms: u64 },
+}
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct Recipe {
+    pub name: String,
+    pub description: Option<String>,
+    // Parameters the run must supply, with an optional default value
+    #[serde(default)]
+    pub parameters: BTreeMap<String, Option<Value>>,
+    pub steps: Vec<RecipeStep>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct RunRecipeRequest {
+    pub name: String,
+    #[serde(default)]
+    pub parameters: Map<String, Value>,
+    // Defaults to "recipe-<name>"
+    pub scan_id: Option<String>,
+    // Matches of the last step are returned unless false
+    pub return_as_json: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct RemoveRecipeRequest {
+    pub name: String,
+}
+
+lazy_static! {
+    // Serializes the read-modify-write cycles on the recipe file
+    static ref FILE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+fn recipe_path() -> PathBuf {
+    let mut path = util::get_data_directory(std::process::id() as i32);
+    path.push("recipes.json");
+    path
+}
+
+// Recipes in a recipe file, without those that would not pass upsert, as a hand-edited or
+// downloaded file may hold
+fn parse(raw: &str) -> BTreeMap<String, Recipe> {
+    let mut recipes: BTreeMap<String, Recipe> = serde_json::from_str(raw).unwrap_or_default();
+    recipes.retain(|name, recipe| match validate(recipe) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("Skipping recipe {}: {}", name, e);
+            false
+        }
+    });
+    recipes
+}
+
+fn load() -> BTreeMap<String, Recipe> {
+    fs::read_to_string(recipe_path())
+        .map(|raw| parse(&raw))
+        .unwrap_or_default()
+}
+
+fn save(recipes: &BTreeMap<String, Recipe>) -> Result<(), String> {
+    let path = recipe_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create recipe directory: {}", e))?;
+    }
+    let raw = serde_json::to_string_pretty(recipes).map_err(|e| e.to_string())?;
+    fs::write(&path, raw).map_err(|e| format!("Failed to save recipes: {}", e))
+}
+
+pub fn list() -> Vec<Recipe> {
+    load().into_values().collect()
+}
+
+pub fn get(name: &str) -> Option<Recipe> {
+    load().remove(name)
+}
+
+fn validate(recipe: &Recipe) -> Result<(), String> {
+    if recipe.name.trim().is_empty() {
+        return Err("name must not be empty".to_string());
+    }
+    if recipe.steps.is_empty() {
+        return Err("A recipe needs at least one step".to_string());
+    }
+    if !matches!(recipe.steps[0], RecipeStep::Scan { .. }) {
+        return Err("The first step must be a scan".to_string());
+    }
+    for (index, step) in recipe.steps.iter().enumerate() {
+        if let RecipeStep::Scan { request } | RecipeStep::Filter { request } = step {
+            if !request.is_object() {
+                return Err(format!("Step {}: request must be an object", index));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn upsert(recipe: Recipe) -> Result<Recipe, String> {
+    validate(&recipe)?;
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut recipes = load();
+    recipes.insert(recipe.name.clone(), recipe.clone());
+    save(&recipes)?;
+    Ok(recipe)
+}
+
+pub fn remove(name: &str) -> Result<bool, String> {
+    let _guard = FILE_LOCK.lock().unwrap();
+    let mut recipes = load();
+    let removed = recipes.remove(name).is_some();
+    if removed {
+        save(&recipes)?;
+    }
+    Ok(removed)
+}
+
+// Supplied parameters over the recipe's defaults; an error names any that are missing
+pub fn resolve_parameters(
+    recipe: &Recipe,
+    supplied: &Map<String, Value>,
+) -> Result<Map<String, Value>, String> {
+    let mut parameters = supplied.clone();
+    for (name, default) in &recipe.parameters {
+        if !parameters.contains_key(name) {
+            let value = default
+                .clone()
+                .ok_or_else(|| format!("Missing parameter: {}", name))?;
+            parameters.insert(name.clone(), value);
+        }
+    }
+    Ok(parameters)
+}
+
+fn placeholder_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+// A string that is only a placeholder takes the parameter's JSON value, so numbers and lists
+// keep their type; placeholders inside longer strings are replaced as text
+pub fn expand(template: &Value, parameters: &Map<String, Value>) -> Value {
+    match template {
+        Value::String(text) => {
+            let whole = text
+                .strip_prefix("{{")
+                .and_then(|rest| rest.strip_suffix("}}"))
+                .and_then(|name| parameters.get(name.trim()));
+            if let Some(value) = whole {
+                return value.clone();
+            }
+            let mut expanded = text.clone();
+            for (name, value) in parameters {
+                expanded = expanded.replace(&format!("{{{{{}}}}}", name), &placeholder_text(value));
+            }
+            Value::String(expanded)
+        }
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|item| expand(item, parameters)).collect())
+        }
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), expand(value, parameters)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+// Fills the bookkeeping fields a step request may leave out. Every step works on the run's
+// scan_id so filters narrow down the scan of the same run.
+pub fn complete_request(request: &mut Value, scan_id: &str, return_as_json: bool) {
+    let defaults = json!({
+        "return_as_json": return_as_json,
+        "do_suspend": false,
+    });
+    if let (Some(fields), Some(defaults)) = (request.as_object_mut(), defaults.as_object()) {
+        for (key, value) in defaults {
+            fields.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        fields.insert("scan_id".to_string(), json!(scan_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_recipes_in_the_file_are_skipped() {
+        let raw = json!({
+            "empty": { "name": "empty", "steps": [] },
+            "filter first": {
+                "name": "filter first",
+                "steps": [{ "kind": "filter", "request": {} }]
+            },
+            "health": {
+                "name": "health",
+                "steps": [
+                    { "kind": "scan", "request": { "pattern": "{{value}}" } },
+                    { "kind": "wait", "ms": 100 }
+                ]
+            }
+        });
+        let recipes = parse(&raw.to_string());
+        assert_eq!(recipes.keys().collect::<Vec<_>>(), ["health"]);
+    }
+}
@@ -0,0 +1,146 @@
+// Records mutating API calls with their timing so a setup can be replayed against a fresh
+// process instance.
+use crate::proxy;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use warp::http::Method;
+use warp::path::FullPath;
+use warp::{Filter, Rejection};
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct RecordedCall {
+    pub offset_ms: u64,
+    pub method: String,
+    pub path: String,
+    pub body: Value,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ReplayRequest {
+    pub calls: Vec<RecordedCall>,
+    // 1.0 replays with the recorded timing, 0 sends every call immediately
+    pub speed: Option<f64>,
+    // Stops at the first call that fails
+    pub stop_on_error: Option<bool>,
+}
+
+struct Recording {
+    active: bool,
+    started: Instant,
+    calls: Vec<RecordedCall>,
+}
+
+lazy_static! {
+    static ref RECORDING: Mutex<Recording> = Mutex::new(Recording {
+        active: false,
+        started: Instant::now(),
+        calls: Vec::new(),
+    });
+    static ref LOCAL_ADDRESS: Mutex<Option<SocketAddr>> = Mutex::new(None);
+}
+
+pub fn set_local_address(address: SocketAddr) {
+    *LOCAL_ADDRESS.lock().unwrap() = Some(address);
+}
+
+// Drop-in replacement for warp::body::json() on routes whose calls should be recorded
+pub fn recorded_json<T>() -> impl Filter<Extract = (T,), Error = Rejection> + Clone
+where
+    T: DeserializeOwned + Serialize + Send,
+{
+    warp::method()
+        .and(warp::path::full())
+        .and(warp::body::json())
+        .map(|method: Method, path: FullPath, body: T| {
+            record(method.as_str(), path.as_str(), &body);
+            body
+        })
+}
+
+fn record<T: Serialize>(method: &str, path: &str, body: &T) {
+    let mut recording = RECORDING.lock().unwrap();
+    if !recording.active {
+        return;
+    }
+    let call = RecordedCall {
+        offset_ms: recording.started.elapsed().as_millis() as u64,
+        method: method.to_string(),
+        path: path.to_string(),
+        body: serde_json::to_value(body).unwrap_or(Value::Null),
+    };
+    recording.calls.push(call);
+}
+
+pub fn start() {
+    let mut recording = RECORDING.lock().unwrap();
+    recording.active = true;
+    recording.started = Instant::now();
+    recording.calls.clear();
+}
+
+pub fn stop() -> Value {
+    let mut recording = RECORDING.lock().unwrap();
+    recording.active = false;
+    json!({ "calls": recording.calls })
+}
+
+pub fn status() -> Value {
+    let recording = RECORDING.lock().unwrap();
+    json!({
+        "recording": recording.active,
+        "calls": recording.calls
+    })
+}
+
+pub async fn replay(replay_request: ReplayRequest) -> Result<Vec<Value>, String> {
+    let address = LOCAL_ADDRESS
+        .lock()
+        .unwrap()
+        .ok_or("Server address is not known")?;
+    let host = if address.ip().is_unspecified() {
+        "127.0.0.1".to_string()
+    } else if address.is_ipv6() {
+        format!("[{}]", address.ip())
+    } else {
+        address.ip().to_string()
+    };
+    let speed = replay_request.speed.unwrap_or(1.0);
+    let stop_on_error = replay_request.stop_on_error.unwrap_or(false);
+
+    let started = Instant::now();
+    let mut results = Vec::new();
+    for call in replay_request.calls {
+        if speed > 0.0 {
+            let due = started + Duration::from_secs_f64(call.offset_ms as f64 / 1000.0 / speed);
+            tokio::time::sleep_until(due.into()).await;
+        }
+        let method = Method::from_bytes(call.method.as_bytes()).map_err(|e| e.to_string())?;
+        let url = format!("http://{}:{}{}", host, address.port(), call.path);
+        let result = proxy::send_json(method, &url, Some(&call.body)).await;
+        let failed = result.is_err();
+        results.push(match result {
+            Ok(response) => json!({
+                "method": call.method,
+                "path": call.path,
+                "success": true,
+                "response": response
+            }),
+            Err(e) => json!({
+                "method": call.method,
+                "path": call.path,
+                "success": false,
+                "error": e
+            }),
+        });
+        if failed && stop_on_error {
+            break;
+        }
+    }
+    Ok(results)
+}
@@ -0,0 +1,147 @@
+// Whole-region transfer for /readregion. The range is read a chunk at a time and every chunk
+// is sent as soon as it is compressed, so a client gets one streamed response instead of
+// splitting the region into read_memory_multiple calls with its own framing.
+//
+// Each frame is: status (u32, 1 read / 0 unreadable), offset from the start of the range
+// (u64), raw length (u32), payload length (u32), payload; all little-endian. Unreadable
+// chunks carry no payload. With lz4 every payload is an independent block of its chunk;
+// with gzip the payloads concatenate into one gzip stream, finished by a last frame with raw
+// length 0 that carries the trailer.
+use crate::events;
+use crate::native_bridge;
+use crate::request;
+use crate::util;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::json;
+use std::io::Write;
+
+pub const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024;
+const MAX_CHUNK_SIZE: usize = 64 * 1024 * 1024;
+
+enum Codec {
+    Lz4,
+    Gzip(GzEncoder<Vec<u8>>),
+}
+
+pub struct RegionDump {
+    pid: i32,
+    pub start: usize,
+    pub size: usize,
+    chunk_size: usize,
+    offset: usize,
+    codec: Codec,
+    finished: bool,
+}
+
+impl RegionDump {
+    pub fn new(pid: i32, read_request: &request::ReadRegionRequest) -> Result<Self, String> {
+        let (start, size) = match read_request.size {
+            Some(size) => (read_request.address, size),
+            None => {
+                let regions = util::parse_regions(&native_bridge::enum_regions(pid)?);
+                let index = util::find_region(&regions, read_request.address)
+                    .ok_or_else(|| format!("No region contains 0x{:x}", read_request.address))?;
+                let (start, end, _) = regions[index];
+                (start, end - start)
+            }
+        };
+        if size == 0 {
+            return Err("Nothing to read".to_string());
+        }
+        let chunk_size = read_request
+            .chunk_size
+            .unwrap_or(DEFAULT_CHUNK_SIZE)
+            .clamp(1, MAX_CHUNK_SIZE);
+        let codec = match read_request.codec.as_deref().unwrap_or("lz4") {
+            "lz4" => Codec::Lz4,
+            "gzip" => {
+                let level = read_request.level.unwrap_or(6);
+                if level > 9 {
+                    return Err(format!("Invalid gzip level {}; expected 0-9", level));
+                }
+                Codec::Gzip(GzEncoder::new(Vec::new(), Compression::new(level)))
+            }
+            other => return Err(format!("Unknown codec '{}'; expected lz4 or gzip", other)),
+        };
+        Ok(RegionDump {
+            pid,
+            start,
+            size,
+            chunk_size,
+            offset: 0,
+            codec,
+            finished: false,
+        })
+    }
+
+    fn compress(&mut self, buffer: &[u8]) -> Vec<u8> {
+        match &mut self.codec {
+            Codec::Lz4 => lz4_flex::block::compress(buffer),
+            Codec::Gzip(encoder) => {
+                // A sync flush pushes out everything written so far without ending the stream
+                let _ = encoder.write_all(buffer).and_then(|_| encoder.flush());
+                std::mem::take(encoder.get_mut())
+            }
+        }
+    }
+
+    fn frame(status: u32, offset: usize, raw_len: usize, payload: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(20 + payload.len());
+        frame.extend_from_slice(&status.to_le_bytes());
+        frame.extend_from_slice(&(offset as u64).to_le_bytes());
+        frame.extend_from_slice(&(raw_len as u32).to_le_bytes());
+        frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+}
+
+impl Iterator for RegionDump {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        if self.offset >= self.size {
+            if self.finished {
+                return None;
+            }
+            self.finished = true;
+            let codec = std::mem::replace(&mut self.codec, Codec::Lz4);
+            return match codec {
+                Codec::Gzip(encoder) => {
+                    let trailer = encoder.finish().unwrap_or_default();
+                    Some(Self::frame(1, self.size, 0, &trailer))
+                }
+                Codec::Lz4 => None,
+            };
+        }
+
+        let offset = self.offset;
+        let length = self.chunk_size.min(self.size - offset);
+        self.offset += length;
+
+        let mut buffer = vec![0u8; length];
+        let nread = native_bridge::read_process_memory(
+            self.pid,
+            (self.start + offset) as *mut libc::c_void,
+            length,
+            &mut buffer,
+        );
+        let frame = match nread {
+            Ok(_) => {
+                let payload = self.compress(&buffer);
+                Self::frame(1, offset, length, &payload)
+            }
+            Err(_) => Self::frame(0, offset, length, &[]),
+        };
+        events::push_event(
+            "read_region_progress",
+            json!({
+                "address": self.start,
+                "size": self.size,
+                "done": self.offset,
+            }),
+        );
+        Some(frame)
+    }
+}
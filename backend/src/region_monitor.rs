@@ -0,0 +1,207 @@
+use crate::events;
+use crate::native_bridge;
+use lazy_static::lazy_static;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const DEFAULT_INTERVAL_MS: u64 = 500;
+const MIN_INTERVAL_MS: u64 = 50;
+
+#[derive(Deserialize, Clone, JsonSchema)]
+pub struct RegionMonitorRequest {
+    pub interval_ms: Option<u64>,
+    pub min_size: Option<usize>,
+    pub max_size: Option<usize>,
+    // Every listed permission character must be present, e.g. "rx"
+    pub protection: Option<String>,
+    // Regex matched against the backing file path
+    pub path: Option<String>,
+}
+
+#[derive(Clone, PartialEq)]
+struct RegionInfo {
+    end: usize,
+    protection: String,
+    path: String,
+}
+
+struct Monitor {
+    pid: i32,
+    options: RegionMonitorRequest,
+    stop: Arc<AtomicBool>,
+}
+
+lazy_static! {
+    static ref MONITOR: Mutex<Option<Monitor>> = Mutex::new(None);
+}
+
+struct RegionFilter {
+    min_size: usize,
+    max_size: usize,
+    protection: String,
+    path: Option<Regex>,
+}
+
+impl RegionFilter {
+    fn new(options: &RegionMonitorRequest) -> Result<Self, String> {
+        let path = match &options.path {
+            Some(pattern) => Some(Regex::new(pattern).map_err(|e| e.to_string())?),
+            None => None,
+        };
+        Ok(RegionFilter {
+            min_size: options.min_size.unwrap_or(0),
+            max_size: options.max_size.unwrap_or(usize::MAX),
+            protection: options.protection.clone().unwrap_or_default(),
+            path,
+        })
+    }
+
+    fn matches(&self, start: usize, region: &RegionInfo) -> bool {
+        let size = region.end - start;
+        size >= self.min_size
+            && size <= self.max_size
+            && self
+                .protection
+                .chars()
+                .all(|c| region.protection.contains(c))
+            && self
+                .path
+                .as_ref()
+                .is_none_or(|path| path.is_match(&region.path))
+    }
+}
+
+fn snapshot_regions(pid: i32) -> Option<BTreeMap<usize, RegionInfo>> {
+    let regions = native_bridge::enum_regions(pid).ok()?;
+    Some(
+        regions
+            .iter()
+            .filter_map(|region| {
+                let start = usize::from_str_radix(region["start_address"].as_str()?, 16).ok()?;
+                let end = usize::from_str_radix(region["end_address"].as_str()?, 16).ok()?;
+                Some((
+                    start,
+                    RegionInfo {
+                        end,
+                        protection: region["protection"].as_str()?.to_string(),
+                        path: region["file_path"].as_str().unwrap_or("").to_string(),
+                    },
+                ))
+            })
+            .collect(),
+    )
+}
+
+fn region_json(start: usize, region: &RegionInfo) -> Value {
+    json!({
+        "start_address": format!("{:x}", start),
+        "end_address": format!("{:x}", region.end),
+        "size": region.end - start,
+        "protection": region.protection,
+        "file_path": region.path,
+    })
+}
+
+fn emit_changes(
+    previous: &BTreeMap<usize, RegionInfo>,
+    current: &BTreeMap<usize, RegionInfo>,
+    filter: &RegionFilter,
+) {
+    for (start, region) in current {
+        match previous.get(start) {
+            None if filter.matches(*start, region) => {
+                events::push_event("region_added", region_json(*start, region));
+            }
+            Some(old)
+                if old != region
+                    && (filter.matches(*start, region) || filter.matches(*start, old)) =>
+            {
+                let mut data = region_json(*start, region);
+                data["old_end_address"] = json!(format!("{:x}", old.end));
+                data["old_protection"] = json!(old.protection);
+                let event_type = if old.protection != region.protection {
+                    "region_protection_changed"
+                } else {
+                    "region_resized"
+                };
+                events::push_event(event_type, data);
+            }
+            _ => {}
+        }
+    }
+    for (start, region) in previous {
+        if !current.contains_key(start) && filter.matches(*start, region) {
+            events::push_event("region_removed", region_json(*start, region));
+        }
+    }
+}
+
+pub fn start(pid: i32, options: RegionMonitorRequest) -> Result<(), String> {
+    let filter = RegionFilter::new(&options)?;
+    let interval = Duration::from_millis(
+        options
+            .interval_ms
+            .unwrap_or(DEFAULT_INTERVAL_MS)
+            .max(MIN_INTERVAL_MS),
+    );
+    let mut previous = snapshot_regions(pid).ok_or("Failed to enumerate regions")?;
+
+    stop();
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    *MONITOR.lock().unwrap() = Some(Monitor {
+        pid,
+        options,
+        stop: stop_flag.clone(),
+    });
+
+    thread::spawn(move || {
+        while !stop_flag.load(Ordering::SeqCst) {
+            thread::sleep(interval);
+            match snapshot_regions(pid) {
+                Some(current) => {
+                    emit_changes(&previous, &current, &filter);
+                    previous = current;
+                }
+                None => {
+                    // Target exited or became unreadable
+                    events::push_event("region_monitor_stopped", json!({ "pid": pid }));
+                    stop_flag.store(true, Ordering::SeqCst);
+                    break;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+pub fn stop() -> bool {
+    match MONITOR.lock().unwrap().take() {
+        Some(monitor) => {
+            monitor.stop.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+pub fn status() -> Value {
+    match MONITOR.lock().unwrap().as_ref() {
+        Some(monitor) if !monitor.stop.load(Ordering::SeqCst) => json!({
+            "running": true,
+            "pid": monitor.pid,
+            "interval_ms": monitor.options.interval_ms.unwrap_or(DEFAULT_INTERVAL_MS),
+            "min_size": monitor.options.min_size,
+            "max_size": monitor.options.max_size,
+            "protection": monitor.options.protection,
+            "path": monitor.options.path,
+        }),
+        _ => json!({ "running": false }),
+    }
+}
@@ -0,0 +1,84 @@
+// Scan results as a standalone file, to archive a result set or move it to another machine
+// without the JSON overhead. Layout: magic "FYRS", format version (u16), then one lz4 block
+// with its size prepended, holding the scan options as JSON (u32 length, bytes), the result
+// count (u64) and per result its address (u64), value length (u32) and value bytes. All
+// integers are little-endian.
+use crate::request::MemoryScanRequest;
+
+const MAGIC: &[u8; 4] = b"FYRS";
+pub const VERSION: u16 = 1;
+
+pub fn encode(scan_option: &MemoryScanRequest, positions: &[(usize, String)]) -> Vec<u8> {
+    let options = serde_json::to_vec(scan_option).unwrap_or_default();
+    let mut body = Vec::with_capacity(12 + options.len() + positions.len() * 16);
+    body.extend_from_slice(&(options.len() as u32).to_le_bytes());
+    body.extend_from_slice(&options);
+    body.extend_from_slice(&(positions.len() as u64).to_le_bytes());
+    for (address, value) in positions {
+        let value = hex::decode(value).unwrap_or_default();
+        body.extend_from_slice(&(*address as u64).to_le_bytes());
+        body.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        body.extend_from_slice(&value);
+    }
+
+    let mut archive = Vec::with_capacity(6 + body.len() / 2);
+    archive.extend_from_slice(MAGIC);
+    archive.extend_from_slice(&VERSION.to_le_bytes());
+    archive.extend_from_slice(&lz4_flex::compress_prepend_size(&body));
+    archive
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, size: usize) -> Result<&'a [u8], String> {
+        if self.data.len() - self.offset < size {
+            return Err("Unexpected end of result archive".to_string());
+        }
+        let slice = &self.data[self.offset..self.offset + size];
+        self.offset += size;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+pub fn decode(archive: &[u8]) -> Result<(MemoryScanRequest, Vec<(usize, String)>), String> {
+    if archive.len() < 6 || &archive[..4] != MAGIC {
+        return Err("Not a result archive".to_string());
+    }
+    let version = u16::from_le_bytes([archive[4], archive[5]]);
+    if version != VERSION {
+        return Err(format!(
+            "Unsupported result archive version {}; expected {}",
+            version, VERSION
+        ));
+    }
+    let body = lz4_flex::decompress_size_prepended(&archive[6..])
+        .map_err(|e| format!("Failed to decompress result archive: {}", e))?;
+
+    let mut reader = Reader {
+        data: &body,
+        offset: 0,
+    };
+    let options_length = reader.u32()? as usize;
+    let scan_option: MemoryScanRequest = serde_json::from_slice(reader.take(options_length)?)
+        .map_err(|e| format!("Invalid scan options in result archive: {}", e))?;
+    let count = reader.u64()?;
+    let mut positions = Vec::new();
+    for _ in 0..count {
+        let address = reader.u64()? as usize;
+        let length = reader.u32()? as usize;
+        positions.push((address, hex::encode(reader.take(length)?)));
+    }
+    Ok((scan_option, positions))
+}
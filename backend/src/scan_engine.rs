@@ -0,0 +1,494 @@
+// The matching cores of /memoryscan and /memoryfilter as pure functions over byte slices.
+// The handlers read memory and keep results; everything that decides whether bytes match
+// lives here, where it runs without a target process.
+use crate::util;
+use byteorder::{ByteOrder, LittleEndian};
+use memchr::memmem;
+use std::str;
+
+// Width of one value of `data_type`; 1 for the byte-string types
+pub fn element_size(data_type: &str) -> usize {
+    util::data_type_size(data_type).unwrap_or(1)
+}
+
+// Offsets into a buffer that starts at `base` where a `size`-byte value fits and its
+// address is a multiple of `align`
+pub fn aligned_offsets(
+    base: usize,
+    len: usize,
+    size: usize,
+    align: usize,
+) -> impl Iterator<Item = usize> {
+    let align = align.max(1);
+    let first = (align - base % align) % align;
+    (first..(len + 1).saturating_sub(size.max(1))).step_by(align)
+}
+
+// Offsets of `pattern` in `buffer` whose address is aligned
+pub fn find_exact(buffer: &[u8], base: usize, pattern: &[u8], align: usize) -> Vec<usize> {
+    let align = align.max(1);
+    memmem::find_iter(buffer, pattern)
+        .filter(|pos| (base + pos).is_multiple_of(align))
+        .collect()
+}
+
+// Offsets of the aligned `size`-byte values that `matches` accepts
+pub fn find_matching(
+    buffer: &[u8],
+    base: usize,
+    size: usize,
+    align: usize,
+    matches: impl Fn(&[u8]) -> bool,
+) -> Vec<usize> {
+    aligned_offsets(base, buffer.len(), size, align)
+        .filter(|&pos| matches(&buffer[pos..pos + size]))
+        .collect()
+}
+
+// Offsets of the aligned values equal to `pattern` in the bits set in `mask`
+pub fn find_masked(
+    buffer: &[u8],
+    base: usize,
+    pattern: &[u8],
+    mask: &[u8],
+    align: usize,
+) -> Vec<usize> {
+    let expected = util::masked_flags(pattern, Some(mask));
+    find_matching(buffer, base, mask.len(), align, |value| {
+        util::masked_flags(value, Some(mask)) == expected
+    })
+}
+
+// The "exact" filter: equal bytes, or equal in the masked bits of a flags type
+pub fn matches_exact(value: &[u8], expected: &[u8], bit_mask: Option<&[u8]>) -> bool {
+    value == expected
+        || bit_mask.is_some_and(|mask| {
+            util::masked_flags(value, Some(mask)) == util::masked_flags(expected, Some(mask))
+        })
+}
+
+pub fn compare_ordered<T: PartialOrd>(new: T, old: T, filter_method: &str) -> bool {
+    match filter_method {
+        "changed" => new != old,
+        "unchanged" => new == old,
+        "increased" => new > old,
+        "decreased" => new < old,
+        _ => false,
+    }
+}
+
+// Byte strings only support changed and unchanged
+fn compare_equality<T: PartialEq>(new: T, old: T, filter_method: &str) -> bool {
+    match filter_method {
+        "changed" => new != old,
+        "unchanged" => new == old,
+        _ => false,
+    }
+}
+
+fn compare_fixed<const N: usize, T: PartialOrd>(
+    new: &[u8],
+    old: &[u8],
+    filter_method: &str,
+    decode: fn([u8; N]) -> T,
+) -> bool {
+    match (<[u8; N]>::try_from(new), <[u8; N]>::try_from(old)) {
+        (Ok(new), Ok(old)) => compare_ordered(decode(new), decode(old), filter_method),
+        // A value of the wrong width never passes
+        _ => false,
+    }
+}
+
+// Compares the current value `new` to the previous one `old` as `data_type`, e.g. whether
+// an int32 "increased". Values of the wrong width compare false instead of panicking.
+pub fn compare(
+    data_type: &str,
+    filter_method: &str,
+    new: &[u8],
+    old: &[u8],
+    bit_mask: Option<&[u8]>,
+) -> bool {
+    match data_type {
+        "int8" => compare_fixed(new, old, filter_method, i8::from_le_bytes),
+        "uint8" => compare_fixed(new, old, filter_method, u8::from_le_bytes),
+        "int16" => compare_fixed(new, old, filter_method, i16::from_le_bytes),
+        "uint16" => compare_fixed(new, old, filter_method, u16::from_le_bytes),
+        "int32" | "decimal32" => compare_fixed(new, old, filter_method, i32::from_le_bytes),
+        "uint32" => compare_fixed(new, old, filter_method, u32::from_le_bytes),
+        "int64" | "decimal64" => compare_fixed(new, old, filter_method, i64::from_le_bytes),
+        "uint64" => compare_fixed(new, old, filter_method, u64::from_le_bytes),
+        "float" if new.len() == 4 && old.len() == 4 => compare_ordered(
+            LittleEndian::read_f32(new),
+            LittleEndian::read_f32(old),
+            filter_method,
+        ),
+        "double" if new.len() == 8 && old.len() == 8 => compare_ordered(
+            LittleEndian::read_f64(new),
+            LittleEndian::read_f64(old),
+            filter_method,
+        ),
+        "float16" | "bfloat16" if new.len() == 2 && old.len() == 2 => compare_ordered(
+            util::decode_half(data_type, new),
+            util::decode_half(data_type, old),
+            filter_method,
+        ),
+        "flags8" | "flags16" | "flags32" | "flags64" => compare_ordered(
+            util::masked_flags(new, bit_mask),
+            util::masked_flags(old, bit_mask),
+            filter_method,
+        ),
+        "pointer" if new.len() == old.len() => compare_ordered(
+            util::wide_to_u128(new),
+            util::wide_to_u128(old),
+            filter_method,
+        ),
+        "utf-8" => compare_equality(
+            str::from_utf8(new).unwrap_or(""),
+            str::from_utf8(old).unwrap_or(""),
+            filter_method,
+        ),
+        "utf-16" | "aob" => compare_equality(new, old, filter_method),
+        data_type => match util::wide_integer(data_type) {
+            Some((size, signed)) if new.len() == size && old.len() == size => {
+                if signed {
+                    compare_ordered(
+                        util::wide_to_i128(new),
+                        util::wide_to_i128(old),
+                        filter_method,
+                    )
+                } else {
+                    compare_ordered(
+                        util::wide_to_u128(new),
+                        util::wide_to_u128(old),
+                        filter_method,
+                    )
+                }
+            }
+            _ => false,
+        },
+    }
+}
+
+// Property tests: each property runs over generated cases that include the boundary values
+// of every data type, and proptest shrinks a failing case to a minimal one.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::sample::{select, Index};
+
+    // Mostly random values, sometimes all-zero, all-ones or the signed extremes
+    fn value(len: usize) -> impl Strategy<Value = Vec<u8>> {
+        let mut max_signed = vec![0xff; len];
+        max_signed[len - 1] = 0x7f;
+        let mut min_signed = vec![0; len];
+        min_signed[len - 1] = 0x80;
+        prop_oneof![
+            1 => Just(vec![0; len]),
+            1 => Just(vec![0xff; len]),
+            1 => Just(max_signed),
+            1 => Just(min_signed),
+            4 => vec(any::<u8>(), len),
+        ]
+    }
+
+    fn value_size() -> impl Strategy<Value = usize> {
+        select(vec![1, 2, 4, 8])
+    }
+
+    // A fake address space: bytes at `base` with values planted at chosen offsets, standing
+    // in for a target process
+    #[derive(Clone, Debug)]
+    struct MemoryImage {
+        base: usize,
+        bytes: Vec<u8>,
+    }
+
+    impl MemoryImage {
+        fn plant(&mut self, offset: usize, value: &[u8]) {
+            self.bytes[offset..offset + value.len()].copy_from_slice(value);
+        }
+    }
+
+    fn memory_image() -> impl Strategy<Value = MemoryImage> {
+        (0..0x10000usize, vec(any::<u8>(), 0..512)).prop_map(|(base, bytes)| MemoryImage {
+            base: base * 2 + 0x1000,
+            bytes,
+        })
+    }
+
+    fn naive_find(
+        image: &MemoryImage,
+        size: usize,
+        align: usize,
+        matches: impl Fn(&[u8]) -> bool,
+    ) -> Vec<usize> {
+        (0..image.bytes.len())
+            .filter(|pos| (image.base + pos).is_multiple_of(align))
+            .filter(|pos| pos + size <= image.bytes.len())
+            .filter(|&pos| matches(&image.bytes[pos..pos + size]))
+            .collect()
+    }
+
+    fn expected_order<T: PartialOrd>(new: T, old: T) -> [bool; 4] {
+        [new != old, new == old, new > old, new < old]
+    }
+
+    fn typed_order(data_type: &str, new: &[u8], old: &[u8]) -> [bool; 4] {
+        macro_rules! order {
+            ($t:ty) => {
+                expected_order(
+                    <$t>::from_le_bytes(new.try_into().unwrap()),
+                    <$t>::from_le_bytes(old.try_into().unwrap()),
+                )
+            };
+        }
+        match data_type {
+            "int8" => order!(i8),
+            "uint8" => order!(u8),
+            "int16" => order!(i16),
+            "uint16" => order!(u16),
+            "int32" | "decimal32" => order!(i32),
+            "uint32" => order!(u32),
+            "int64" | "decimal64" => order!(i64),
+            "uint64" => order!(u64),
+            "float" => order!(f32),
+            "double" => order!(f64),
+            "int128" => order!(i128),
+            "uint128" => order!(u128),
+            _ => unreachable!(),
+        }
+    }
+
+    const METHODS: [&str; 4] = ["changed", "unchanged", "increased", "decreased"];
+    const ORDERED_TYPES: [&str; 14] = [
+        "int8",
+        "uint8",
+        "int16",
+        "uint16",
+        "int32",
+        "uint32",
+        "int64",
+        "uint64",
+        "float",
+        "double",
+        "decimal32",
+        "decimal64",
+        "int128",
+        "uint128",
+    ];
+
+    // One of `data_types` with a current and a previous value of its width
+    fn typed_values(
+        data_types: Vec<&'static str>,
+    ) -> impl Strategy<Value = (&'static str, Vec<u8>, Vec<u8>)> {
+        select(data_types).prop_flat_map(|data_type| {
+            let size = element_size(data_type);
+            (Just(data_type), value(size), value(size))
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn aligned_offsets_are_exactly_the_aligned_windows(
+            base in 0..1usize << 20,
+            len in 0..300usize,
+            size in 0..20usize,
+            align_bits in 0..5u32,
+        ) {
+            let align = 1 << align_bits;
+            let offsets: Vec<usize> = aligned_offsets(base, len, size, align).collect();
+            let expected: Vec<usize> = (0..len)
+                .filter(|pos| (base + pos).is_multiple_of(align) && pos + size.max(1) <= len)
+                .collect();
+            prop_assert_eq!(offsets, expected);
+        }
+
+        #[test]
+        fn find_exact_matches_naive_search(
+            mut image in memory_image(),
+            pattern in vec(any::<u8>(), 1..5),
+            at in any::<Index>(),
+            align_bits in 0..4u32,
+        ) {
+            let align = 1 << align_bits;
+            if image.bytes.len() >= pattern.len() {
+                let offset = at.index(image.bytes.len() - pattern.len() + 1);
+                image.plant(offset, &pattern);
+            }
+            let found = find_exact(&image.bytes, image.base, &pattern, align);
+            let expected = naive_find(&image, pattern.len(), align, |value| value == pattern);
+            prop_assert_eq!(found, expected);
+        }
+
+        #[test]
+        fn find_masked_compares_only_masked_bits(
+            image in memory_image(),
+            (pattern, mask) in value_size()
+                .prop_flat_map(|size| (vec(any::<u8>(), size), vec(any::<u8>(), size))),
+            align_bits in 0..4u32,
+        ) {
+            let align = 1 << align_bits;
+            let mask: Vec<u8> = mask.iter().map(|b| b & 0x11).collect();
+            let found = find_masked(&image.bytes, image.base, &pattern, &mask, align);
+            let expected = naive_find(&image, pattern.len(), align, |value| {
+                value
+                    .iter()
+                    .zip(&pattern)
+                    .zip(&mask)
+                    .all(|((v, p), m)| v & m == p & m)
+            });
+            prop_assert_eq!(found, expected);
+        }
+
+        #[test]
+        fn planted_values_are_found_at_their_address(
+            mut image in memory_image(),
+            value in value_size().prop_flat_map(|size| vec(any::<u8>(), size)),
+            at in any::<Index>(),
+        ) {
+            let size = value.len();
+            prop_assume!(image.bytes.len() >= size + 8);
+            let aligned: Vec<usize> =
+                aligned_offsets(image.base, image.bytes.len(), size, size).collect();
+            let offset = aligned[at.index(aligned.len())];
+            image.plant(offset, &value);
+            let found = find_matching(&image.bytes, image.base, size, size, |v| v == value);
+            prop_assert!(found.contains(&offset));
+        }
+
+        #[test]
+        fn compare_orders_values_like_the_data_type(
+            (data_type, new, old) in typed_values(ORDERED_TYPES.to_vec()),
+            // Equal values are rare at random, so make some
+            same in prop::bool::weighted(0.25),
+        ) {
+            let old = if same { new.clone() } else { old };
+            let expected = typed_order(data_type, &new, &old);
+            for (method, expected) in METHODS.iter().zip(expected) {
+                prop_assert_eq!(
+                    compare(data_type, method, &new, &old, None),
+                    expected,
+                    "{} {}",
+                    data_type,
+                    method
+                );
+            }
+        }
+
+        #[test]
+        fn compare_wide_integers_sign_extends(
+            (new, old) in select(vec![3usize, 5, 6, 7])
+                .prop_flat_map(|size| (value(size), value(size))),
+        ) {
+            let size = new.len();
+            let widen = |bytes: &[u8], signed: bool| {
+                let fill = if signed && bytes[size - 1] & 0x80 != 0 {
+                    0xff
+                } else {
+                    0
+                };
+                let mut wide = [fill; 16];
+                wide[..size].copy_from_slice(bytes);
+                wide
+            };
+            for (data_type, signed) in [
+                (format!("int{}", size * 8), true),
+                (format!("uint{}", size * 8), false),
+            ] {
+                let expected = if signed {
+                    expected_order(
+                        i128::from_le_bytes(widen(&new, true)),
+                        i128::from_le_bytes(widen(&old, true)),
+                    )
+                } else {
+                    expected_order(
+                        u128::from_le_bytes(widen(&new, false)),
+                        u128::from_le_bytes(widen(&old, false)),
+                    )
+                };
+                for (method, expected) in METHODS.iter().zip(expected) {
+                    prop_assert_eq!(
+                        compare(&data_type, method, &new, &old, None),
+                        expected,
+                        "{} {}",
+                        data_type,
+                        method
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn compare_is_consistent_in_both_directions(
+            (data_type, a, b, mask) in select(
+                ORDERED_TYPES
+                    .iter()
+                    .chain(&["float16", "bfloat16", "flags32"])
+                    .copied()
+                    .collect::<Vec<_>>(),
+            )
+            .prop_flat_map(|data_type| {
+                let size = element_size(data_type);
+                (
+                    Just(data_type),
+                    value(size),
+                    value(size),
+                    proptest::option::of(vec(any::<u8>(), size)),
+                )
+            }),
+        ) {
+            let mask = mask.as_deref();
+            let cmp = |method, x: &[u8], y: &[u8]| compare(data_type, method, x, y, mask);
+            prop_assert_eq!(cmp("increased", &a, &b), cmp("decreased", &b, &a), "{}", data_type);
+            prop_assert_eq!(cmp("changed", &a, &b), !cmp("unchanged", &a, &b), "{}", data_type);
+            prop_assert!(!cmp("predicate", &a, &b), "{}", data_type);
+        }
+
+        #[test]
+        fn compare_rejects_values_of_the_wrong_width(
+            (data_type, new, old) in select(
+                ORDERED_TYPES
+                    .iter()
+                    .chain(&["float16", "pointer", "int24"])
+                    .copied()
+                    .collect::<Vec<_>>(),
+            )
+            .prop_flat_map(|data_type| {
+                let size = element_size(data_type);
+                (
+                    Just(data_type),
+                    vec(any::<u8>(), 0..2 * size + 2)
+                        .prop_filter("a different width", move |new| new.len() != size),
+                    vec(any::<u8>(), size),
+                )
+            }),
+        ) {
+            for method in METHODS {
+                prop_assert!(!compare(data_type, method, &new, &old, None), "{}", data_type);
+                prop_assert!(!compare(data_type, method, &old, &new, None), "{}", data_type);
+            }
+        }
+
+        #[test]
+        fn exact_match_with_mask_ignores_unmasked_bits(
+            (expected, mask, noise) in value_size().prop_flat_map(|size| {
+                (
+                    vec(any::<u8>(), size),
+                    vec(any::<u8>(), size),
+                    vec(any::<u8>(), size),
+                )
+            }),
+        ) {
+            let value: Vec<u8> = expected
+                .iter()
+                .zip(&mask)
+                .zip(&noise)
+                .map(|((e, m), n)| (e & m) | (n & !m))
+                .collect();
+            prop_assert!(matches_exact(&value, &expected, Some(&mask)));
+            prop_assert!(matches_exact(&expected, &expected, None));
+        }
+    }
+}
@@ -0,0 +1,62 @@
+// Dry run of a scan: how many regions and bytes it would read and roughly how long that
+// takes, so the scope can be trimmed before starting a long scan. The rate comes from the
+// last scan, or before any scan from a short sample read of the ranges themselves.
+use crate::native_bridge;
+use crate::scan_stats;
+use serde_json::{json, Value};
+use std::time::Instant;
+
+const SAMPLE_CHUNK_SIZE: usize = 1024 * 1024;
+const SAMPLE_CHUNKS: usize = 8;
+
+fn total_size(ranges: &[(usize, usize)]) -> usize {
+    ranges.iter().map(|&(start, end)| end - start).sum()
+}
+
+// Reads the start of up to SAMPLE_CHUNKS ranges spread over the list. Scans read with one
+// thread per core, so the single-threaded rate is scaled by the thread count.
+fn sample_throughput(pid: i32, ranges: &[(usize, usize)]) -> Option<f64> {
+    let step = ranges.len().div_ceil(SAMPLE_CHUNKS).max(1);
+    let started = Instant::now();
+    let mut bytes_read = 0;
+    for &(start, end) in ranges.iter().step_by(step) {
+        let length = SAMPLE_CHUNK_SIZE.min(end - start);
+        let mut buffer = vec![0u8; length];
+        if let Ok(nread) =
+            native_bridge::read_process_memory(pid, start as *mut libc::c_void, length, &mut buffer)
+        {
+            bytes_read += nread.max(0) as usize;
+        }
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+    (bytes_read > 0 && elapsed > 0.0)
+        .then(|| bytes_read as f64 / elapsed * rayon::current_num_threads() as f64)
+}
+
+pub fn estimate(
+    pid: i32,
+    requested: &[(usize, usize)],
+    ranges: &[(usize, usize)],
+    from_snapshot: bool,
+) -> Value {
+    let bytes = total_size(ranges);
+    let (throughput, source) = match scan_stats::last_throughput() {
+        Some(throughput) => (Some(throughput), Some("last_scan")),
+        // Sampling live memory says nothing about decompressing a snapshot
+        None if !from_snapshot => match sample_throughput(pid, ranges) {
+            Some(throughput) => (Some(throughput), Some("sample")),
+            None => (None, None),
+        },
+        None => (None, None),
+    };
+    json!({
+        "requested_ranges": requested.len(),
+        "requested_bytes": total_size(requested),
+        "ranges": ranges.len(),
+        "bytes": bytes,
+        "largest_range": ranges.iter().map(|&(start, end)| end - start).max().unwrap_or(0),
+        "bytes_per_sec": throughput,
+        "throughput_source": source,
+        "estimated_ms": throughput.map(|throughput| bytes as f64 / throughput * 1000.0),
+    })
+}
@@ -0,0 +1,136 @@
+// The on-disk format of unknown-value scan files under memory-server-data-dir. It uses
+// nothing from the rest of the crate, so the fuzz targets in fuzz/ build it on its own.
+const USIZE_SIZE: usize = std::mem::size_of::<usize>();
+
+// One region chunk of an unknown-value scan file: its address and lz4 block
+pub struct DumpChunk<'a> {
+    pub address: usize,
+    pub uncompressed_size: usize,
+    pub compressed: &'a [u8],
+}
+
+// Chunks of an unknown-value scan file after its status flag, each written as address,
+// compressed size and uncompressed size (little-endian usize) then the block. A truncated
+// chunk ends the list.
+pub fn parse_dump_chunks(data: &[u8]) -> Vec<DumpChunk<'_>> {
+    let mut chunks = Vec::new();
+    let mut offset = 0;
+    while let Some(header) = data.get(offset..offset + 3 * USIZE_SIZE) {
+        let field = |i: usize| {
+            usize::from_le_bytes(
+                header[i * USIZE_SIZE..(i + 1) * USIZE_SIZE]
+                    .try_into()
+                    .unwrap(),
+            )
+        };
+        let (address, compressed_size, uncompressed_size) = (field(0), field(1), field(2));
+        offset += 3 * USIZE_SIZE;
+        let Some(compressed) = offset
+            .checked_add(compressed_size)
+            .and_then(|end| data.get(offset..end))
+        else {
+            break;
+        };
+        offset += compressed_size;
+        chunks.push(DumpChunk {
+            address,
+            uncompressed_size,
+            compressed,
+        });
+    }
+    chunks
+}
+
+// Filtered results as written back to an unknown-value scan file: address then the
+// `size`-byte value, repeated. A truncated record ends the list.
+pub fn parse_records(data: &[u8], size: usize) -> Vec<(usize, &[u8])> {
+    data.chunks_exact(USIZE_SIZE + size)
+        .map(|record| {
+            let (address, value) = record.split_at(USIZE_SIZE);
+            (usize::from_le_bytes(address.try_into().unwrap()), value)
+        })
+        .collect()
+}
+
+pub fn encode_record(records: &mut Vec<u8>, address: usize, value: &[u8]) {
+    records.extend_from_slice(&address.to_le_bytes());
+    records.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    // Value size, records and the bytes of a torn record after them
+    type RecordFile = (usize, Vec<(usize, Vec<u8>)>, Vec<u8>);
+
+    fn records() -> impl Strategy<Value = RecordFile> {
+        (1..17usize).prop_flat_map(|size| {
+            (
+                Just(size),
+                vec((any::<usize>(), vec(any::<u8>(), size)), 0..20),
+                vec(any::<u8>(), 0..USIZE_SIZE + size),
+            )
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn records_round_trip((size, records, torn) in records()) {
+            let mut data = Vec::new();
+            for (address, value) in &records {
+                encode_record(&mut data, *address, value);
+            }
+            // A torn write leaves a partial record behind
+            data.extend(torn);
+            let parsed: Vec<(usize, Vec<u8>)> = parse_records(&data, size)
+                .into_iter()
+                .map(|(address, value)| (address, value.to_vec()))
+                .collect();
+            prop_assert_eq!(parsed, records);
+        }
+
+        #[test]
+        fn dump_chunks_round_trip(
+            blocks in vec((any::<usize>(), vec(any::<u8>(), 0..64)), 0..5),
+        ) {
+            let mut data = Vec::new();
+            for (address, block) in &blocks {
+                let compressed = lz4_flex::block::compress(block);
+                data.extend(address.to_le_bytes());
+                data.extend(compressed.len().to_le_bytes());
+                data.extend(block.len().to_le_bytes());
+                data.extend(compressed);
+            }
+            let chunks = parse_dump_chunks(&data);
+            prop_assert_eq!(chunks.len(), blocks.len());
+            for (chunk, (address, block)) in chunks.iter().zip(&blocks) {
+                prop_assert_eq!(chunk.address, *address);
+                let decompressed =
+                    lz4_flex::block::decompress(chunk.compressed, chunk.uncompressed_size);
+                prop_assert_eq!(decompressed.ok(), Some(block.clone()));
+            }
+        }
+
+        // Arbitrary bytes, including sizes near usize::MAX, never panic the parsers. The
+        // fuzz targets in fuzz/ run the same checks for longer.
+        #[test]
+        fn parsers_accept_garbage(
+            mut data in vec(any::<u8>(), 0..200),
+            huge_size in any::<bool>(),
+            size in 0..16usize,
+        ) {
+            if huge_size && data.len() >= 2 * USIZE_SIZE {
+                data[USIZE_SIZE..2 * USIZE_SIZE].copy_from_slice(&usize::MAX.to_le_bytes());
+            }
+            let chunks = parse_dump_chunks(&data);
+            prop_assert!(chunks.iter().all(|chunk| chunk.compressed.len() <= data.len()));
+            prop_assert_eq!(
+                parse_records(&data, size).len(),
+                data.len() / (USIZE_SIZE + size)
+            );
+        }
+    }
+}
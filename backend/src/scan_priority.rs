@@ -0,0 +1,140 @@
+// Reduced priority for scans and filters running on the same device as the target, e.g. a
+// game that stutters while every core reads memory. Lower levels use fewer threads, sleep
+// between chunks and, on Linux and Android, run their threads at a higher nice value.
+use crate::device_throttle;
+use std::thread;
+use std::time::Duration;
+
+pub const LEVELS: &[&str] = &["normal", "low", "background"];
+
+pub const DEFAULT_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+// Threads of a normal-priority scan, from --scan-threads; rayon's one per core otherwise
+pub fn configured_threads() -> Option<usize> {
+    std::env::var("MEMORY_SERVER_SCAN_THREADS")
+        .ok()
+        .and_then(|threads| threads.parse().ok())
+        .filter(|&threads| threads > 0)
+}
+
+// Bytes a scan reads at once from each range, from --scan-chunk-size
+pub fn chunk_size() -> usize {
+    std::env::var("MEMORY_SERVER_SCAN_CHUNK_SIZE")
+        .ok()
+        .and_then(|size| size.parse().ok())
+        .filter(|&size| size > 0)
+        .unwrap_or(DEFAULT_CHUNK_SIZE)
+}
+
+pub struct Priority {
+    threads: Option<usize>,
+    pause: Duration,
+    nice: i32,
+}
+
+impl Priority {
+    pub fn parse(level: Option<&str>) -> Result<Priority, String> {
+        let configured = configured_threads();
+        let cores = configured.unwrap_or_else(rayon::current_num_threads);
+        match level.unwrap_or("normal") {
+            "normal" => Ok(Priority {
+                threads: configured,
+                pause: Duration::ZERO,
+                nice: 0,
+            }),
+            "low" => Ok(Priority {
+                threads: Some((cores / 2).max(1)),
+                pause: Duration::from_millis(2),
+                nice: 5,
+            }),
+            "background" => Ok(Priority {
+                threads: Some(1),
+                pause: Duration::from_millis(20),
+                nice: 10,
+            }),
+            level => Err(format!("Unknown priority '{}'", level)),
+        }
+    }
+
+    // Runs the parallel work in a pool of its own when the thread count is limited, by the
+    // level or by device_throttle
+    pub fn run<T: Send>(&self, work: impl FnOnce() -> T + Send) -> T {
+        let threads = match (self.threads, device_throttle::thread_limit()) {
+            (Some(threads), Some(limit)) => threads.min(limit),
+            (threads, limit) => match threads.or(limit) {
+                Some(threads) => threads,
+                None => return work(),
+            },
+        };
+        let nice = self.nice;
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .start_handler(move |_| lower_thread_priority(nice))
+            .build();
+        match pool {
+            Ok(pool) => pool.install(work),
+            Err(_) => work(),
+        }
+    }
+
+    // Called after each chunk so the target gets the CPU back in between
+    pub fn pause(&self) {
+        if !self.pause.is_zero() {
+            thread::sleep(self.pause);
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn lower_thread_priority(nice: i32) {
+    // With who = 0 Linux applies the nice value to the calling thread only
+    unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, 0, nice);
+    }
+}
+
+// Elsewhere the nice value is shared by the whole process, so only the thread count and the
+// pauses apply
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn lower_thread_priority(_nice: i32) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rayon::prelude::*;
+    use std::time::Instant;
+
+    #[test]
+    fn lower_levels_run_on_fewer_threads() {
+        let threads = |level| {
+            Priority::parse(Some(level))
+                .unwrap()
+                .run(rayon::current_num_threads)
+        };
+        assert_eq!(threads("background"), 1);
+        assert!(threads("low") <= (rayon::current_num_threads() / 2).max(1));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn background_threads_are_niced() {
+        let nice = Priority::parse(Some("background"))
+            .unwrap()
+            .run(|| unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) });
+        assert!(nice >= 10, "nice {}", nice);
+    }
+
+    // Each thread pauses on its own, so a pause per chunk costs one pause per chunk a thread
+    // handles rather than one per chunk of the whole scan
+    #[test]
+    fn threads_pause_in_parallel() {
+        let priority = Priority {
+            threads: Some(4),
+            pause: Duration::from_millis(50),
+            nice: 0,
+        };
+        let started = Instant::now();
+        priority.run(|| (0..8).into_par_iter().for_each(|_| priority.pause()));
+        assert!(started.elapsed() < Duration::from_millis(8 * 50));
+    }
+}
@@ -0,0 +1,121 @@
+// Metadata kept next to each scan_id: the process it ran against, a name and notes from the
+// user, when it was created and the parameters of the scan and every filter pass since.
+use crate::native_bridge;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// Oldest passes are dropped first, e.g. from long repeated filters
+const MAX_HISTORY: usize = 200;
+const MAX_LISTED_ITEMS: usize = 16;
+
+#[derive(Serialize, Clone, Default)]
+pub struct Session {
+    pub name: Option<String>,
+    pub notes: Option<String>,
+    pub pid: Option<i32>,
+    pub process_name: Option<String>,
+    pub created: i64,
+    // Last scan, filter or export, for the stale session collection
+    pub last_used: i64,
+    pub history: Vec<Value>,
+}
+
+lazy_static! {
+    static ref SESSIONS: Mutex<HashMap<String, Session>> = Mutex::new(HashMap::new());
+}
+
+// File name of the main image, which comes first in the module list
+pub fn process_name(pid: i32) -> Option<String> {
+    let modules = native_bridge::enum_modules(pid).ok()?;
+    let path = modules.first()?["modulename"].as_str()?;
+    Some(path.rsplit(['/', '\\']).next().unwrap_or(path).to_string())
+}
+
+fn history_entry<T: Serialize>(kind: &str, parameters: &T, found: usize) -> Value {
+    let mut parameters = serde_json::to_value(parameters).unwrap_or(Value::Null);
+    // Range and candidate lists can run into thousands of entries; their length is enough
+    if let Some(fields) = parameters.as_object_mut() {
+        for value in fields.values_mut() {
+            if let Some(length) = value.as_array().map(|items| items.len()) {
+                if length > MAX_LISTED_ITEMS {
+                    *value = json!(length);
+                }
+            }
+        }
+    }
+    json!({
+        "kind": kind,
+        "time": chrono::Local::now().timestamp_millis(),
+        "found": found,
+        "parameters": parameters,
+    })
+}
+
+fn session_mut<'a>(sessions: &'a mut HashMap<String, Session>, scan_id: &str) -> &'a mut Session {
+    let now = chrono::Local::now().timestamp_millis();
+    let session = sessions
+        .entry(scan_id.to_string())
+        .or_insert_with(|| Session {
+            created: now,
+            ..Default::default()
+        });
+    session.last_used = now;
+    session
+}
+
+// A new scan replaces the history of the scan_id but keeps its name and notes
+pub fn start<T: Serialize>(
+    scan_id: &str,
+    pid: Option<i32>,
+    kind: &str,
+    parameters: &T,
+    found: usize,
+) {
+    let entry = history_entry(kind, parameters, found);
+    let process_name = pid.and_then(process_name);
+    let mut sessions = SESSIONS.lock().unwrap();
+    let session = session_mut(&mut sessions, scan_id);
+    session.pid = pid;
+    session.process_name = process_name;
+    session.created = chrono::Local::now().timestamp_millis();
+    session.history = vec![entry];
+}
+
+pub fn record<T: Serialize>(scan_id: &str, kind: &str, parameters: &T, found: usize) {
+    let entry = history_entry(kind, parameters, found);
+    let mut sessions = SESSIONS.lock().unwrap();
+    let session = session_mut(&mut sessions, scan_id);
+    session.history.push(entry);
+    if session.history.len() > MAX_HISTORY {
+        let excess = session.history.len() - MAX_HISTORY;
+        session.history.drain(..excess);
+    }
+}
+
+pub fn annotate(scan_id: &str, name: Option<String>, notes: Option<String>) {
+    let mut sessions = SESSIONS.lock().unwrap();
+    let session = session_mut(&mut sessions, scan_id);
+    if name.is_some() {
+        session.name = name;
+    }
+    if notes.is_some() {
+        session.notes = notes;
+    }
+}
+
+pub fn touch(scan_id: &str) {
+    if let Some(session) = SESSIONS.lock().unwrap().get_mut(scan_id) {
+        session.last_used = chrono::Local::now().timestamp_millis();
+    }
+}
+
+pub fn get(scan_id: &str) -> Option<Session> {
+    SESSIONS.lock().unwrap().get(scan_id).cloned()
+}
+
+pub fn remove(scan_id: &str) {
+    SESSIONS.lock().unwrap().remove(scan_id);
+}
@@ -0,0 +1,101 @@
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+// Wall-clock bytes per second of the last scan that read enough to be representative,
+// stored as f64 bits; 0 until then
+static LAST_THROUGHPUT: AtomicU64 = AtomicU64::new(0);
+const MIN_MEASURED_BYTES: usize = 16 * 1024 * 1024;
+
+pub fn last_throughput() -> Option<f64> {
+    let bits = LAST_THROUGHPUT.load(Ordering::Relaxed);
+    (bits != 0).then(|| f64::from_bits(bits))
+}
+
+// Counters shared by the scan worker threads. Phase times are summed across threads,
+// so read_ms + match_ms can exceed the wall clock elapsed_ms on multi-core scans.
+pub struct ScanStats {
+    started: Instant,
+    requested_ranges: usize,
+    requested_bytes: usize,
+    scanned_ranges: usize,
+    scanned_bytes: usize,
+    bytes_read: AtomicUsize,
+    chunks_read: AtomicUsize,
+    chunks_failed: AtomicUsize,
+    read_nanos: AtomicU64,
+    match_nanos: AtomicU64,
+}
+
+fn total_size(ranges: &[(usize, usize)]) -> usize {
+    ranges.iter().map(|&(start, end)| end - start).sum()
+}
+
+impl ScanStats {
+    pub fn new(requested: &[(usize, usize)], scanned: &[(usize, usize)]) -> Self {
+        ScanStats {
+            started: Instant::now(),
+            requested_ranges: requested.len(),
+            requested_bytes: total_size(requested),
+            scanned_ranges: scanned.len(),
+            scanned_bytes: total_size(scanned),
+            bytes_read: AtomicUsize::new(0),
+            chunks_read: AtomicUsize::new(0),
+            chunks_failed: AtomicUsize::new(0),
+            read_nanos: AtomicU64::new(0),
+            match_nanos: AtomicU64::new(0),
+        }
+    }
+
+    pub fn record_read(&self, nread: Option<usize>, elapsed: Duration) {
+        match nread {
+            Some(nread) => {
+                self.bytes_read.fetch_add(nread, Ordering::Relaxed);
+                self.chunks_read.fetch_add(1, Ordering::Relaxed);
+            }
+            None => {
+                self.chunks_failed.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.read_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_match(&self, elapsed: Duration) {
+        self.match_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn remember_throughput(&self) {
+        let bytes_read = self.bytes_read.load(Ordering::Relaxed);
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if bytes_read >= MIN_MEASURED_BYTES && elapsed > 0.0 {
+            let throughput = bytes_read as f64 / elapsed;
+            LAST_THROUGHPUT.store(throughput.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn report(&self) -> Value {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let bytes_read = self.bytes_read.load(Ordering::Relaxed);
+        let mb_per_sec = if elapsed > 0.0 {
+            bytes_read as f64 / (1024.0 * 1024.0) / elapsed
+        } else {
+            0.0
+        };
+        json!({
+            "requested_ranges": self.requested_ranges,
+            "requested_bytes": self.requested_bytes,
+            "scanned_ranges": self.scanned_ranges,
+            // Bytes dropped by the exclusion, allocation and residency filters before reading
+            "filtered_bytes": self.requested_bytes.saturating_sub(self.scanned_bytes),
+            "bytes_read": bytes_read,
+            "chunks_read": self.chunks_read.load(Ordering::Relaxed),
+            "chunks_failed": self.chunks_failed.load(Ordering::Relaxed),
+            "read_ms": self.read_nanos.load(Ordering::Relaxed) as f64 / 1e6,
+            "match_ms": self.match_nanos.load(Ordering::Relaxed) as f64 / 1e6,
+            "elapsed_ms": elapsed * 1000.0,
+            "mb_per_sec": mb_per_sec,
+        })
+    }
+}
@@ -0,0 +1,215 @@
+// Upfront checks of scan and filter parameters. A pattern that does not decode or a regex
+// that does not compile otherwise fails inside every scan thread and the scan quietly finds
+// nothing, so problems are reported per field before any memory is read.
+use crate::plugins;
+use crate::request::{MemoryFilterRequest, MemoryScanRequest};
+use crate::scan_priority;
+use crate::util;
+use crate::wasm_predicates;
+use regex::bytes::Regex;
+use serde::Serialize;
+
+const FIND_TYPES: &[&str] = &["exact", "unknown", "predicate"];
+const FILTER_METHODS: &[&str] = &[
+    "exact",
+    "changed",
+    "unchanged",
+    "increased",
+    "decreased",
+    "predicate",
+];
+
+#[derive(Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Default)]
+struct Errors(Vec<FieldError>);
+
+impl Errors {
+    fn push(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+}
+
+fn known_data_type(data_type: &str) -> bool {
+    matches!(data_type, "aob" | "regex" | "utf-8" | "utf-16")
+        || util::data_type_size(data_type).is_some()
+}
+
+fn check_pattern(errors: &mut Errors, field: &str, data_type: &str, pattern: &str) {
+    if data_type == "regex" {
+        if let Err(e) = Regex::new(pattern) {
+            errors.push(field, format!("Invalid regex: {}", e));
+        }
+        return;
+    }
+    match hex::decode(pattern) {
+        Err(e) => errors.push(field, format!("Invalid hex: {}", e)),
+        Ok(bytes) if bytes.is_empty() => errors.push(field, "must not be empty"),
+        Ok(bytes) => {
+            if let Some(size) = util::data_type_size(data_type) {
+                if bytes.len() != size {
+                    errors.push(
+                        field,
+                        format!("{} takes {} bytes, got {}", data_type, size, bytes.len()),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn check_priority(errors: &mut Errors, priority: Option<&str>) {
+    if let Some(priority) = priority {
+        if !scan_priority::LEVELS.contains(&priority) {
+            errors.push(
+                "priority",
+                format!(
+                    "Unknown priority '{}', expected one of {}",
+                    priority,
+                    scan_priority::LEVELS.join(", ")
+                ),
+            );
+        }
+    }
+}
+
+// The named predicate must be uploaded, and given when `required` by the find type or method
+fn check_predicate(
+    errors: &mut Errors,
+    predicate: Option<&str>,
+    required: bool,
+) -> Option<wasm_predicates::Predicate> {
+    match predicate {
+        Some(name) => {
+            let found = wasm_predicates::get(name);
+            if found.is_none() {
+                errors.push("predicate", format!("Unknown predicate '{}'", name));
+            }
+            found
+        }
+        None => {
+            if required {
+                errors.push("predicate", "is required");
+            }
+            None
+        }
+    }
+}
+
+fn check_ranges(errors: &mut Errors, field: &str, ranges: &[(usize, usize)]) {
+    for (index, &(start, end)) in ranges.iter().enumerate() {
+        if start >= end {
+            errors.push(
+                format!("{}[{}]", field, index),
+                format!("start 0x{:x} is not below end 0x{:x}", start, end),
+            );
+        }
+    }
+}
+
+pub fn validate_scan(scan_request: &MemoryScanRequest) -> Vec<FieldError> {
+    let mut errors = Errors::default();
+    let data_type = scan_request.data_type.as_str();
+    if !known_data_type(data_type) {
+        errors.push("data_type", format!("Unknown data type '{}'", data_type));
+    }
+    if !FIND_TYPES.contains(&scan_request.find_type.as_str()) {
+        errors.push(
+            "find_type",
+            format!("Unknown find type '{}'", scan_request.find_type),
+        );
+    }
+    if scan_request.align == 0 {
+        errors.push("align", "must be at least 1");
+    }
+    if scan_request.address_ranges.is_empty() {
+        errors.push("address_ranges", "must not be empty");
+    }
+    check_ranges(&mut errors, "address_ranges", &scan_request.address_ranges);
+    if let Some(excluded) = &scan_request.excluded_ranges {
+        check_ranges(&mut errors, "excluded_ranges", excluded);
+    }
+
+    // text, display_value and patterns are encoded server-side and checked where they are
+    let encoded_server_side = scan_request.text.is_some()
+        || scan_request.display_value.is_some()
+        || scan_request.patterns.is_some();
+    if scan_request.find_type == "exact" && !encoded_server_side && known_data_type(data_type) {
+        check_pattern(&mut errors, "pattern", data_type, &scan_request.pattern);
+    }
+    if let Some(nearby) = &scan_request.nearby {
+        if hex::decode(&nearby.pattern).map_or(true, |bytes| bytes.is_empty()) {
+            errors.push("nearby.pattern", "must be non-empty hex");
+        }
+    }
+    if let Err(e) = util::parse_bit_mask(data_type, scan_request.bit_mask.as_deref()) {
+        errors.push("bit_mask", e);
+    }
+    check_priority(&mut errors, scan_request.priority.as_deref());
+    let is_predicate_scan = scan_request.find_type == "predicate";
+    let predicate = check_predicate(
+        &mut errors,
+        scan_request.predicate.as_deref(),
+        is_predicate_scan,
+    );
+    if let Some(predicate) = predicate {
+        if is_predicate_scan
+            && util::data_type_size(data_type).is_none()
+            && predicate.size().is_none()
+        {
+            errors.push(
+                "data_type",
+                format!(
+                    "{} has no size; upload the predicate with one to scan it",
+                    data_type
+                ),
+            );
+        }
+    }
+    errors.0
+}
+
+pub fn validate_filter(filter_request: &MemoryFilterRequest, scan_exists: bool) -> Vec<FieldError> {
+    let mut errors = Errors::default();
+    if !scan_exists {
+        errors.push(
+            "scan_id",
+            format!("Unknown scan_id '{}'", filter_request.scan_id),
+        );
+    }
+    let data_type = filter_request.data_type.as_str();
+    if !known_data_type(data_type) {
+        errors.push("data_type", format!("Unknown data type '{}'", data_type));
+    }
+    if !FILTER_METHODS.contains(&filter_request.filter_method.as_str())
+        && !plugins::has_comparator(&filter_request.filter_method)
+    {
+        errors.push(
+            "filter_method",
+            format!("Unknown filter method '{}'", filter_request.filter_method),
+        );
+    }
+    if filter_request.filter_method == "exact"
+        && filter_request.display_value.is_none()
+        && known_data_type(data_type)
+    {
+        check_pattern(&mut errors, "pattern", data_type, &filter_request.pattern);
+    }
+    if let Err(e) = util::parse_bit_mask(data_type, filter_request.bit_mask.as_deref()) {
+        errors.push("bit_mask", e);
+    }
+    check_priority(&mut errors, filter_request.priority.as_deref());
+    check_predicate(
+        &mut errors,
+        filter_request.predicate.as_deref(),
+        filter_request.filter_method == "predicate",
+    );
+    errors.0
+}
@@ -0,0 +1,227 @@
+use crate::native_bridge;
+use crate::write_history;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const MAX_FINISHED_JOBS: usize = 256;
+const POLL_SLICE: Duration = Duration::from_millis(20);
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct ScheduledWrite {
+    pub address: usize,
+    #[serde(default)]
+    pub buffer: Vec<u8>,
+    // Values written in turn on every interval tick; defaults to [buffer]
+    pub sequence: Option<Vec<Vec<u8>>>,
+    pub interval_ms: Option<u64>,
+    pub duration_ms: Option<u64>,
+    // Original bytes are written back this long after the job starts
+    pub restore_after_ms: Option<u64>,
+}
+
+struct Job {
+    address: usize,
+    state: &'static str,
+    writes: usize,
+    error: Option<String>,
+    cancel: Arc<AtomicBool>,
+}
+
+lazy_static! {
+    static ref JOBS: Mutex<BTreeMap<u64, Job>> = Mutex::new(BTreeMap::new());
+    static ref NEXT_JOB_ID: Mutex<u64> = Mutex::new(1);
+}
+
+fn write(pid: i32, address: usize, buffer: &[u8]) -> Result<(), String> {
+    native_bridge::write_process_memory(pid, address as *mut libc::c_void, buffer.len(), buffer)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn update_job(id: u64, f: impl FnOnce(&mut Job)) {
+    if let Some(job) = JOBS.lock().unwrap().get_mut(&id) {
+        f(job);
+    }
+}
+
+fn prune_finished(jobs: &mut BTreeMap<u64, Job>) {
+    let finished: Vec<u64> = jobs
+        .iter()
+        .filter(|(_, job)| job.state != "running")
+        .map(|(id, _)| *id)
+        .collect();
+    if finished.len() > MAX_FINISHED_JOBS {
+        for id in &finished[..finished.len() - MAX_FINISHED_JOBS] {
+            jobs.remove(id);
+        }
+    }
+}
+
+pub fn schedule(pid: i32, request: ScheduledWrite, session: &str) -> Result<u64, String> {
+    let values = request
+        .sequence
+        .clone()
+        .unwrap_or_else(|| vec![request.buffer.clone()]);
+    if values.is_empty() || values.iter().any(|value| value.is_empty()) {
+        return Err("Write values must not be empty".to_string());
+    }
+    if request.interval_ms == Some(0) {
+        return Err("interval_ms must be greater than zero".to_string());
+    }
+
+    let original_size = values.iter().map(|value| value.len()).max().unwrap();
+    let mut original = vec![0u8; original_size];
+    native_bridge::read_process_memory(
+        pid,
+        request.address as *mut libc::c_void,
+        original_size,
+        &mut original,
+    )
+    .map_err(|e| format!("Failed to read original bytes: {}", e))?;
+
+    write(pid, request.address, &values[0])?;
+    // Only the first write is tracked; undoing it while the job still runs is overwritten
+    write_history::record(
+        pid,
+        request.address,
+        Some(original[..values[0].len()].to_vec()),
+        &values[0],
+        "schedulewrite",
+        session,
+    );
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let id = {
+        let mut next_id = NEXT_JOB_ID.lock().unwrap();
+        let id = *next_id;
+        *next_id += 1;
+        id
+    };
+    {
+        let mut jobs = JOBS.lock().unwrap();
+        prune_finished(&mut jobs);
+        jobs.insert(
+            id,
+            Job {
+                address: request.address,
+                state: "running",
+                writes: 1,
+                error: None,
+                cancel: cancel.clone(),
+            },
+        );
+    }
+
+    thread::spawn(move || run_job(pid, id, request, values, original, cancel));
+    Ok(id)
+}
+
+fn run_job(
+    pid: i32,
+    id: u64,
+    request: ScheduledWrite,
+    values: Vec<Vec<u8>>,
+    original: Vec<u8>,
+    cancel: Arc<AtomicBool>,
+) {
+    let start = Instant::now();
+    let restore_at = request.restore_after_ms.map(Duration::from_millis);
+    let repeat_until = request
+        .interval_ms
+        .map(|_| request.duration_ms.map(Duration::from_millis));
+    let interval = request.interval_ms.map(Duration::from_millis);
+    let mut next_write = interval.map(|interval| start + interval);
+    let mut index = 1;
+
+    let state = loop {
+        if cancel.load(Ordering::SeqCst) {
+            if restore_at.is_some() {
+                let _ = write(pid, request.address, &original);
+            }
+            break "cancelled";
+        }
+        let now = Instant::now();
+        if let Some(restore_at) = restore_at {
+            if now >= start + restore_at {
+                if let Err(e) = write(pid, request.address, &original) {
+                    update_job(id, |job| job.error = Some(e));
+                }
+                break "finished";
+            }
+        }
+        let repeating = match repeat_until {
+            Some(Some(until)) => now < start + until,
+            Some(None) => true,
+            None => false,
+        };
+        if !repeating && restore_at.is_none() {
+            break "finished";
+        }
+        if let (true, Some(at), Some(interval)) = (repeating, next_write, interval) {
+            if now >= at {
+                let value = &values[index % values.len()];
+                index += 1;
+                match write(pid, request.address, value) {
+                    Ok(_) => update_job(id, |job| job.writes += 1),
+                    Err(e) => {
+                        update_job(id, |job| job.error = Some(e));
+                        break "failed";
+                    }
+                }
+                next_write = Some(at + interval);
+                continue;
+            }
+        }
+
+        let mut wake = now + POLL_SLICE;
+        if let Some(restore_at) = restore_at {
+            wake = wake.min(start + restore_at);
+        }
+        if let (true, Some(at)) = (repeating, next_write) {
+            wake = wake.min(at);
+        }
+        thread::sleep(wake.saturating_duration_since(now));
+    };
+
+    update_job(id, |job| job.state = state);
+}
+
+pub fn cancel(id: u64) -> bool {
+    match JOBS.lock().unwrap().get(&id) {
+        Some(job) => {
+            job.cancel.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+pub fn is_running(id: u64) -> bool {
+    JOBS.lock()
+        .unwrap()
+        .get(&id)
+        .is_some_and(|job| job.state == "running")
+}
+
+pub fn list_jobs() -> Vec<Value> {
+    JOBS.lock()
+        .unwrap()
+        .iter()
+        .map(|(id, job)| {
+            json!({
+                "id": id,
+                "address": job.address,
+                "state": job.state,
+                "writes": job.writes,
+                "error": job.error,
+            })
+        })
+        .collect()
+}
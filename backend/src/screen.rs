@@ -0,0 +1,215 @@
+// Screen capture and OCR of numbers shown on screen. Android captures with `screencap` as
+// root or shell, Linux desktops with ImageMagick's `import`; text is read by the tesseract
+// command, or the one set in MEMORY_SERVER_TESSERACT.
+use crate::request::ScreenRegion;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// Channel difference below which pixels count as unchanged, for compression and dithering
+const PIXEL_TOLERANCE: u8 = 16;
+
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    // 3 bytes per pixel, rows top to bottom
+    pub rgb: Vec<u8>,
+}
+
+impl Image {
+    pub fn crop(&self, region: &ScreenRegion) -> Result<Image, String> {
+        if region.width == 0
+            || region.height == 0
+            || region.x + region.width > self.width
+            || region.y + region.height > self.height
+        {
+            return Err(format!(
+                "Region {}x{}+{}+{} is outside the {}x{} screen",
+                region.width, region.height, region.x, region.y, self.width, self.height
+            ));
+        }
+        let mut rgb = Vec::with_capacity(region.width * region.height * 3);
+        for row in region.y..region.y + region.height {
+            let start = (row * self.width + region.x) * 3;
+            rgb.extend_from_slice(&self.rgb[start..start + region.width * 3]);
+        }
+        Ok(Image {
+            width: region.width,
+            height: region.height,
+            rgb,
+        })
+    }
+
+    // Binary PPM, which tesseract reads without any image library on our side
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        ppm.extend_from_slice(&self.rgb);
+        ppm
+    }
+
+    // Share of pixels that visibly differ from `other`, from 0.0 to 1.0, or None when the
+    // sizes differ, e.g. after a rotation
+    pub fn changed_fraction(&self, other: &Image) -> Option<f64> {
+        if self.width != other.width || self.height != other.height || self.rgb.is_empty() {
+            return None;
+        }
+        let changed = self
+            .rgb
+            .chunks_exact(3)
+            .zip(other.rgb.chunks_exact(3))
+            .filter(|(a, b)| {
+                a.iter()
+                    .zip(b.iter())
+                    .any(|(a, b)| a.abs_diff(*b) > PIXEL_TOLERANCE)
+            })
+            .count();
+        Some(changed as f64 / (self.width * self.height) as f64)
+    }
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn run(command: &mut Command) -> Result<Vec<u8>, String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+// Raw screencap output: width, height and pixel format as u32, a color space on Android 9
+// and later, then 4 bytes per pixel
+#[cfg(target_os = "android")]
+fn capture_screen() -> Result<Image, String> {
+    let raw = run(&mut Command::new("screencap"))?;
+    let field = |index: usize| {
+        raw.get(index * 4..index * 4 + 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+            .ok_or("Truncated screencap output")
+    };
+    let (width, height, format) = (field(0)?, field(1)?, field(2)?);
+    let pixels = width * height * 4;
+    let header = raw
+        .len()
+        .checked_sub(pixels)
+        .filter(|header| *header == 12 || *header == 16)
+        .ok_or("Unexpected screencap output size")?;
+    // 1 and 2 are RGBA and RGBX; 5 is BGRA
+    let swap = match format {
+        1 | 2 => false,
+        5 => true,
+        format => return Err(format!("Unsupported screencap pixel format {}", format)),
+    };
+    let mut rgb = Vec::with_capacity(width * height * 3);
+    for pixel in raw[header..].chunks_exact(4) {
+        if swap {
+            rgb.extend_from_slice(&[pixel[2], pixel[1], pixel[0]]);
+        } else {
+            rgb.extend_from_slice(&pixel[..3]);
+        }
+    }
+    Ok(Image { width, height, rgb })
+}
+
+#[cfg(target_os = "linux")]
+fn capture_screen() -> Result<Image, String> {
+    let ppm = run(Command::new("import").args(["-window", "root", "-depth", "8", "ppm:-"]))?;
+    parse_ppm(&ppm)
+}
+
+#[cfg(not(any(target_os = "android", target_os = "linux")))]
+fn capture_screen() -> Result<Image, String> {
+    Err("Screen capture is not supported on this platform".to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn parse_ppm(ppm: &[u8]) -> Result<Image, String> {
+    // Magic, width, height and maximum value, each followed by one whitespace byte
+    let mut fields = Vec::new();
+    let mut offset = 0;
+    while fields.len() < 4 {
+        let start = offset;
+        while offset < ppm.len() && !ppm[offset].is_ascii_whitespace() {
+            offset += 1;
+        }
+        if offset >= ppm.len() {
+            return Err("Truncated PPM header".to_string());
+        }
+        if offset > start {
+            fields.push(String::from_utf8_lossy(&ppm[start..offset]).into_owned());
+        }
+        offset += 1;
+    }
+    if fields[0] != "P6" || fields[3] != "255" {
+        return Err("Only 8-bit binary PPM screenshots are supported".to_string());
+    }
+    let width: usize = fields[1].parse().map_err(|_| "Invalid PPM width")?;
+    let height: usize = fields[2].parse().map_err(|_| "Invalid PPM height")?;
+    let rgb = ppm
+        .get(offset..offset + width * height * 3)
+        .ok_or("Truncated PPM data")?
+        .to_vec();
+    Ok(Image { width, height, rgb })
+}
+
+pub fn capture(region: Option<&ScreenRegion>) -> Result<Image, String> {
+    let screen = capture_screen()?;
+    match region {
+        Some(region) => screen.crop(region),
+        None => Ok(screen),
+    }
+}
+
+// First number in the text, with thousands separators removed, e.g. "HP 1,234/5000" reads
+// as 1234
+fn first_number(text: &str) -> Option<String> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let negative = text[..start].ends_with('-');
+    let number: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.' || *c == ',')
+        .filter(|c| *c != ',')
+        .collect();
+    let number = number.trim_end_matches('.');
+    Some(if negative {
+        format!("-{}", number)
+    } else {
+        number.to_string()
+    })
+}
+
+// Returns the recognized text and the number read from it
+pub fn read_number(image: &Image) -> Result<(String, String), String> {
+    let program =
+        std::env::var("MEMORY_SERVER_TESSERACT").unwrap_or_else(|_| "tesseract".to_string());
+    // A single line of text
+    let mut child = Command::new(&program)
+        .args(["stdin", "stdout", "--psm", "7"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    let ppm = image.to_ppm();
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&ppm).map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    match first_number(&text) {
+        Some(number) => Ok((text, number)),
+        None => Err(format!("No number found in '{}'", text)),
+    }
+}
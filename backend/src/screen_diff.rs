@@ -0,0 +1,204 @@
+// "What changed when I did X": /screendiff/start captures the screen and a memory snapshot,
+// the user acts in the game, and /screendiff/finish captures both again. Bytes that already
+// changed while idle during a short baseline right after the start are counted as noise,
+// so the regions reported are the ones whose changes fall in the window of the action.
+use crate::request::ScreenRegion;
+use crate::screen;
+use crate::snapshot;
+use crate::util;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const BEFORE_SNAPSHOT: &str = "screendiff-before";
+const AFTER_SNAPSHOT: &str = "screendiff-after";
+const DEFAULT_BASELINE_MS: u64 = 1000;
+const DEFAULT_MAX_REGIONS: usize = 50;
+const MAX_NOISE_RUNS: usize = 1_000_000;
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ScreenDiffStartRequest {
+    // Defaults to every writable region
+    pub address_ranges: Option<Vec<(usize, usize)>>,
+    // Part of the screen compared; the whole screen when omitted
+    pub region: Option<ScreenRegion>,
+    // Idle time after the first capture used to find memory that changes on its own; 0
+    // skips it. Defaults to one second.
+    pub baseline_ms: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ScreenDiffFinishRequest {
+    // Changed runs closer than this many bytes are merged; defaults to 0
+    pub merge_gap: Option<usize>,
+    // Regions returned, most changed first; defaults to 50
+    pub max_regions: Option<usize>,
+}
+
+struct Session {
+    pid: i32,
+    started: Instant,
+    ranges: Vec<(usize, usize)>,
+    region: Option<ScreenRegion>,
+    screen: Result<screen::Image, String>,
+    // Sorted runs that changed during the baseline
+    noise: Vec<(usize, usize)>,
+}
+
+lazy_static! {
+    static ref SESSION: Mutex<Option<Session>> = Mutex::new(None);
+}
+
+fn overlaps(ranges: &[(usize, usize)], start: usize, end: usize) -> bool {
+    let index = ranges.partition_point(|&(_, range_end)| range_end <= start);
+    index < ranges.len() && ranges[index].0 < end
+}
+
+fn noise_runs(pid: i32) -> Result<Vec<(usize, usize)>, String> {
+    let options = snapshot::CompareOptions {
+        data_type: None,
+        align: 1,
+        merge_gap: 0,
+        max_results: MAX_NOISE_RUNS,
+        max_bytes: 0,
+    };
+    let changes = snapshot::changed_since(pid, BEFORE_SNAPSHOT, &options)?;
+    let runs: Vec<(usize, usize)> = changes["changes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|change| {
+            let address = change["address"].as_u64()? as usize;
+            Some((address, address + change["size"].as_u64()? as usize))
+        })
+        .collect();
+    Ok(util::merge_ranges(&runs))
+}
+
+pub fn start(pid: i32, request: ScreenDiffStartRequest) -> Result<Value, String> {
+    let ranges = match request.address_ranges {
+        Some(ranges) => ranges,
+        None => util::writable_ranges(pid)?,
+    };
+    // Memory is still useful on platforms without screen capture
+    let screen = screen::capture(request.region.as_ref());
+    let taken = snapshot::take(pid, BEFORE_SNAPSHOT, &ranges)?;
+    let baseline_ms = request.baseline_ms.unwrap_or(DEFAULT_BASELINE_MS);
+    let noise = if baseline_ms > 0 {
+        thread::sleep(Duration::from_millis(baseline_ms));
+        noise_runs(pid)?
+    } else {
+        Vec::new()
+    };
+    let result = json!({
+        "bytes": taken["bytes"],
+        "ranges": ranges.len(),
+        "noise_runs": noise.len(),
+        "screen": match &screen {
+            Ok(image) => json!({ "width": image.width, "height": image.height }),
+            Err(e) => json!({ "error": e }),
+        },
+    });
+    *SESSION.lock().unwrap() = Some(Session {
+        pid,
+        started: Instant::now(),
+        ranges,
+        region: request.region,
+        screen,
+        noise,
+    });
+    Ok(result)
+}
+
+pub fn finish(pid: i32, request: ScreenDiffFinishRequest) -> Result<Value, String> {
+    let session = SESSION
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or("No screen diff started")?;
+    if session.pid != pid {
+        return Err(format!(
+            "The screen diff was started on pid {}",
+            session.pid
+        ));
+    }
+    let window_ms = session.started.elapsed().as_millis() as u64;
+    let screen_after = screen::capture(session.region.as_ref());
+    let screen_change = match (&session.screen, &screen_after) {
+        (Ok(before), Ok(after)) => json!(before.changed_fraction(after)),
+        _ => Value::Null,
+    };
+
+    let taken = snapshot::take(pid, AFTER_SNAPSHOT, &session.ranges);
+    let options = snapshot::CompareOptions {
+        data_type: None,
+        align: 1,
+        merge_gap: request.merge_gap.unwrap_or(0),
+        max_results: MAX_NOISE_RUNS,
+        max_bytes: 16,
+    };
+    let diff = taken.and_then(|_| snapshot::diff(pid, BEFORE_SNAPSHOT, AFTER_SNAPSHOT, &options));
+    let _ = snapshot::remove(pid, BEFORE_SNAPSHOT);
+    let _ = snapshot::remove(pid, AFTER_SNAPSHOT);
+    let diff = diff?;
+
+    let mut noise_changes = 0;
+    let mut regions: Vec<Value> = diff["regions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|region| {
+            let mut changed_bytes = 0;
+            let changes: Vec<Value> = region["changes"]
+                .as_array()?
+                .iter()
+                .filter(|change| {
+                    let start = change["address"].as_u64().unwrap_or(0) as usize;
+                    let size = change["size"].as_u64().unwrap_or(0) as usize;
+                    if overlaps(&session.noise, start, start + size) {
+                        noise_changes += 1;
+                        return false;
+                    }
+                    changed_bytes += size;
+                    true
+                })
+                .cloned()
+                .collect();
+            if changes.is_empty() {
+                return None;
+            }
+            let start = usize::from_str_radix(region["start_address"].as_str()?, 16).ok()?;
+            let end = usize::from_str_radix(region["end_address"].as_str()?, 16).ok()?;
+            Some(json!({
+                "start_address": region["start_address"],
+                "end_address": region["end_address"],
+                "changed_bytes": changed_bytes,
+                // Share of the region that changed in the window
+                "magnitude": changed_bytes as f64 / (end - start).max(1) as f64,
+                "change_count": changes.len(),
+                "changes": changes,
+            }))
+        })
+        .collect();
+    regions.sort_by(|a, b| {
+        b["changed_bytes"]
+            .as_u64()
+            .cmp(&a["changed_bytes"].as_u64())
+    });
+    let region_count = regions.len();
+    regions.truncate(request.max_regions.unwrap_or(DEFAULT_MAX_REGIONS));
+
+    Ok(json!({
+        "window_ms": window_ms,
+        "screen_change": screen_change,
+        "screen_error": screen_after.err().or(session.screen.err()),
+        "noise_changes": noise_changes,
+        "region_count": region_count,
+        "truncated": diff["truncated"],
+        "regions": regions,
+    }))
+}
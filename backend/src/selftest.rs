@@ -0,0 +1,387 @@
+// Smoke tests of the memory backend against a harmless target, and optionally a real one, so a
+// failure can be told apart as "backend broken" (the harmless target fails too) or "target
+// protected" (only the real one fails).
+use crate::driver;
+use crate::memory_backend::{self, MemoryBackend};
+use crate::native_bridge;
+use crate::scan_engine;
+use crate::util;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::time::Instant;
+
+const PROBE_SIZE: usize = 4096;
+const WATCHPOINT_WRITE: i32 = 2;
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct SelfTestQuery {
+    // Process to check besides the harmless target; defaults to the opened process
+    pub pid: Option<i32>,
+    // Also write to that process. The bytes just read are written back, which loses any change
+    // the process makes in between, so this is off unless asked for.
+    pub write: Option<bool>,
+}
+
+struct Checks {
+    results: Vec<Value>,
+}
+
+impl Checks {
+    fn new() -> Self {
+        Checks {
+            results: Vec::new(),
+        }
+    }
+
+    fn run<T>(&mut self, name: &str, check: impl FnOnce() -> Result<T, String>) -> Option<T> {
+        let started = Instant::now();
+        let result = check();
+        let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+        let (status, detail, value) = match result {
+            Ok(value) => ("passed", Value::Null, Some(value)),
+            Err(e) => ("failed", json!(e), None),
+        };
+        self.results.push(json!({
+            "name": name,
+            "status": status,
+            "detail": detail,
+            "elapsed_ms": elapsed_ms,
+        }));
+        value
+    }
+
+    fn skip(&mut self, name: &str, reason: &str) {
+        self.results.push(json!({
+            "name": name,
+            "status": "skipped",
+            "detail": reason,
+            "elapsed_ms": 0.0,
+        }));
+    }
+
+    fn failed(&self) -> bool {
+        self.results.iter().any(|r| r["status"] == "failed")
+    }
+
+    fn report(self, kind: &str, pid: i32, backend: &dyn MemoryBackend) -> Value {
+        json!({
+            "kind": kind,
+            "pid": pid,
+            "backend": backend.name(),
+            "passed": !self.failed(),
+            "checks": self.results,
+        })
+    }
+}
+
+// memory-test-target from next to the server binary, killed when dropped, with the address
+// and bytes of its uint64 value
+struct TestTarget {
+    child: Child,
+    address: usize,
+    bytes: Vec<u8>,
+}
+
+impl TestTarget {
+    fn spawn() -> Result<Self, String> {
+        let path = std::env::current_exe()
+            .map_err(|e| e.to_string())?
+            .with_file_name(format!(
+                "memory-test-target{}",
+                std::env::consts::EXE_SUFFIX
+            ));
+        let mut child = Command::new(&path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", path.display(), e))?;
+        let mut line = String::new();
+        let read = BufReader::new(child.stdout.take().unwrap()).read_line(&mut line);
+        let mut target = TestTarget {
+            child,
+            address: 0,
+            bytes: Vec::new(),
+        };
+        read.map_err(|e| e.to_string())?;
+        let manifest: Value = serde_json::from_str(&line).map_err(|e| e.to_string())?;
+        let value = manifest["values"]
+            .as_array()
+            .and_then(|values| values.iter().find(|v| v["name"] == "uint64"))
+            .ok_or("The test target lists no uint64 value")?;
+        target.address = value["address"].as_u64().unwrap_or_default() as usize;
+        target.bytes =
+            hex::decode(value["hex"].as_str().unwrap_or_default()).map_err(|e| e.to_string())?;
+        Ok(target)
+    }
+
+    fn pid(&self) -> i32 {
+        self.child.id() as i32
+    }
+}
+
+impl Drop for TestTarget {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn read_exact(
+    backend: &dyn MemoryBackend,
+    pid: i32,
+    address: usize,
+    size: usize,
+) -> Result<Vec<u8>, String> {
+    let mut buffer = vec![0u8; size];
+    let nread = backend
+        .read(pid, address, &mut buffer)
+        .map_err(|e| e.to_string())?;
+    if nread < size {
+        return Err(format!("Read {} of {} bytes at {:x}", nread, size, address));
+    }
+    Ok(buffer)
+}
+
+// Reads the value back, writes a different one, checks it landed, and restores it
+fn check_write(
+    backend: &dyn MemoryBackend,
+    pid: i32,
+    address: usize,
+    original: &[u8],
+) -> Result<(), String> {
+    let changed: Vec<u8> = original.iter().map(|b| !b).collect();
+    backend
+        .write(pid, address, &changed)
+        .map_err(|e| e.to_string())?;
+    let written = read_exact(backend, pid, address, changed.len());
+    backend
+        .write(pid, address, original)
+        .map_err(|e| format!("Failed to restore the value: {}", e))?;
+    if written? != changed {
+        return Err("The written value did not read back".to_string());
+    }
+    Ok(())
+}
+
+// Reads the window around `address` and scans it for `pattern` as a 1-aligned exact scan would
+fn check_scan(
+    backend: &dyn MemoryBackend,
+    pid: i32,
+    address: usize,
+    pattern: &[u8],
+) -> Result<(), String> {
+    let base = address - address % PROBE_SIZE;
+    let mut buffer = vec![0u8; PROBE_SIZE];
+    let nread = backend
+        .read(pid, base, &mut buffer)
+        .map_err(|e| e.to_string())?;
+    let offsets = scan_engine::find_exact(&buffer[..nread], base, pattern, 1);
+    if offsets.contains(&(address - base)) {
+        Ok(())
+    } else {
+        Err(format!(
+            "The scan did not find {} at {:x}",
+            hex::encode(pattern),
+            address
+        ))
+    }
+}
+
+fn check_harmless_target() -> Value {
+    let mut checks = Checks::new();
+    // Without a test target, as in the embedded server or where none was installed, the
+    // server checks itself
+    let target = if util::is_embedded() {
+        checks.skip("spawn", "the embedded server cannot reach other processes");
+        None
+    } else {
+        match TestTarget::spawn() {
+            Ok(target) => Some(target),
+            Err(e) => {
+                checks.skip("spawn", &e);
+                None
+            }
+        }
+    };
+    let own_value: Box<[u8; 8]> = Box::new(0x1122_3344_5566_7788u64.to_le_bytes());
+    let (kind, pid, address, bytes) = match &target {
+        Some(target) => (
+            "test_target",
+            target.pid(),
+            target.address,
+            target.bytes.clone(),
+        ),
+        None => (
+            "self",
+            std::process::id() as i32,
+            own_value.as_ptr() as usize,
+            own_value.to_vec(),
+        ),
+    };
+    let backend = memory_backend::for_pid(pid);
+
+    checks.run("regions", || {
+        let regions = backend.regions(pid)?;
+        Ok(regions.len())
+    });
+    let read = checks.run("read", || {
+        let read = read_exact(backend, pid, address, bytes.len())?;
+        if read == bytes {
+            Ok(())
+        } else {
+            Err(format!(
+                "Read {} where {} was expected",
+                hex::encode(read),
+                hex::encode(&bytes)
+            ))
+        }
+    });
+    if read.is_some() {
+        checks.run("scan", || check_scan(backend, pid, address, &bytes));
+        checks.run("write", || check_write(backend, pid, address, &bytes));
+    } else {
+        checks.skip("scan", "read failed");
+        checks.skip("write", "read failed");
+    }
+    // The debugger stays attached to the process it watches, which would keep it from the
+    // process the user opens later
+    checks.skip(
+        "watchpoint",
+        "checked only on the opened process, which the debugger stays attached to",
+    );
+    checks.report(kind, pid, backend)
+}
+
+fn check_process(pid: i32, write: bool, watchpoint: bool) -> Value {
+    let mut checks = Checks::new();
+    let backend = memory_backend::for_pid(pid);
+
+    // The driver backend bypasses the OS permission checks the diagnosis is about
+    if driver::is_enabled() {
+        checks.skip("attach", "the driver backend does not attach");
+    } else {
+        checks.run("attach", || {
+            let diagnosis = native_bridge::diagnose(pid)?;
+            if diagnosis["attachable"] == false {
+                Err(diagnosis["problems"][0]["message"]
+                    .as_str()
+                    .unwrap_or("Process cannot be inspected")
+                    .to_string())
+            } else {
+                Ok(())
+            }
+        });
+    }
+    let regions = checks.run("regions", || backend.regions(pid));
+    // The start of the first readable region stands in for a known value
+    let probe = regions.as_ref().and_then(|regions| {
+        regions.iter().find_map(|region| {
+            let readable = region["protection"].as_str()?.starts_with('r');
+            let start = usize::from_str_radix(region["start_address"].as_str()?, 16).ok()?;
+            readable.then_some(start)
+        })
+    });
+    let read = match probe {
+        Some(address) => checks
+            .run("read", || read_exact(backend, pid, address, 8))
+            .map(|bytes| (address, bytes)),
+        None => {
+            checks.skip("read", "no readable region");
+            None
+        }
+    };
+    match &read {
+        Some((address, bytes)) => {
+            checks.run("scan", || check_scan(backend, pid, *address, bytes));
+        }
+        None => checks.skip("scan", "read failed"),
+    }
+    match (&read, write) {
+        (Some((address, bytes)), true) => {
+            checks.run("write", || {
+                backend
+                    .write(pid, *address, bytes)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            });
+        }
+        (None, true) => checks.skip("write", "read failed"),
+        (_, false) => checks.skip("write", "not requested"),
+    }
+    match (&read, watchpoint) {
+        (Some((address, _)), true) => {
+            checks.run("watchpoint", || {
+                native_bridge::set_watchpoint(pid, *address, 4, WATCHPOINT_WRITE, &[], None)
+                    .map_err(|e| e.to_string())?;
+                native_bridge::remove_watchpoint(*address).map_err(|e| e.to_string())?;
+                Ok(())
+            });
+        }
+        (None, true) => checks.skip("watchpoint", "read failed"),
+        (_, false) => checks.skip("watchpoint", "only checked on the opened process"),
+    }
+    checks.report("process", pid, backend)
+}
+
+// `opened` is the process attached with /process, the only one the watchpoint check may use
+pub fn run(query: &SelfTestQuery, opened: Option<i32>) -> Value {
+    let harmless = check_harmless_target();
+    let harmless_passed = harmless["passed"] == true;
+    let mut targets = vec![harmless];
+
+    let pid = query.pid.or(opened);
+    let mut process_passed = true;
+    if let Some(pid) = pid {
+        let report = check_process(pid, query.write.unwrap_or(false), Some(pid) == opened);
+        process_passed = report["passed"] == true;
+        targets.push(report);
+    }
+
+    let diagnosis = match (harmless_passed, process_passed) {
+        (true, true) => "ok",
+        (false, _) => "backend_broken",
+        (true, false) => "target_protected",
+    };
+    json!({
+        "diagnosis": diagnosis,
+        "passed": harmless_passed && process_passed,
+        "targets": targets,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_record_each_outcome() {
+        let mut checks = Checks::new();
+        assert_eq!(checks.run("read", || Ok::<_, String>(3)), Some(3));
+        assert_eq!(
+            checks.run("write", || Err::<(), _>("denied".to_string())),
+            None
+        );
+        checks.skip("watchpoint", "not requested");
+        assert!(checks.failed());
+        let statuses: Vec<&str> = checks
+            .results
+            .iter()
+            .map(|r| r["status"].as_str().unwrap())
+            .collect();
+        assert_eq!(statuses, ["passed", "failed", "skipped"]);
+    }
+
+    #[test]
+    fn own_process_passes_the_harmless_checks() {
+        let pid = std::process::id() as i32;
+        let value = 0x0bad_cafe_u32.to_le_bytes();
+        let backend = memory_backend::for_pid(pid);
+        let address = value.as_ptr() as usize;
+        assert_eq!(read_exact(backend, pid, address, 4).unwrap(), value);
+        check_scan(backend, pid, address, &value).unwrap();
+    }
+}
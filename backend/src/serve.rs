@@ -7,18 +7,44 @@ use warp::path::Tail;
 use warp::Filter;
 
 use crate::api;
+use crate::crash;
+use crate::events;
 use crate::logger;
 use crate::native_bridge;
+use crate::plugins;
+use crate::proxy;
+use crate::recorder;
 use crate::request;
+use crate::selftest;
+use crate::session_gc;
+use crate::util;
+use crate::wasm_predicates;
+use crate::write_history;
 
 pub async fn serve(mode: i32, host: IpAddr, port: u16) {
-    let pid_state = Arc::new(Mutex::new(None));
+    crash::install_hook();
+
+    // The embedded server starts attached to its own process, the only one it may inspect
+    let pid_state = Arc::new(Mutex::new(if util::is_embedded() {
+        Some(std::process::id() as i32)
+    } else {
+        None
+    }));
 
     let cors = warp::cors()
         .allow_any_origin()
         .allow_headers(vec!["*", "Content-Type"])
         .allow_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"]);
 
+    if let Some(target) = proxy::proxy_target() {
+        println!("Proxying all requests to {}.", target);
+        let routes = proxy::routes(target)
+            .with(cors)
+            .with(warp::log::custom(logger::http_log));
+        warp::serve(routes).run((host, port)).await;
+        return;
+    }
+
     let static_files = warp::path::tail()
         .map(|tail: Tail| tail.as_str().to_string())
         .and_then(serve_static);
@@ -32,6 +58,19 @@ pub async fn serve(mode: i32, host: IpAddr, port: u16) {
         .and(api::with_state(pid_state.clone()))
         .and_then(|pid_state| async move { api::enummodule_handler(pid_state).await });
 
+    let enum_threads = warp::path!("threads")
+        .and(warp::get())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|pid_state| async move { api::enumerate_threads_handler(pid_state).await });
+
+    let enum_tls = warp::path!("tls")
+        .and(warp::get())
+        .and(warp::query::<request::TlsRequest>())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|tls_request, pid_state| async move {
+            api::enumerate_tls_handler(pid_state, tls_request).await
+        });
+
     let open_process = warp::path!("process")
         .and(warp::post())
         .and(warp::body::json())
@@ -50,10 +89,11 @@ pub async fn serve(mode: i32, host: IpAddr, port: u16) {
 
     let write_memory = warp::path!("memory")
         .and(warp::post())
-        .and(warp::body::json())
+        .and(recorder::recorded_json())
         .and(api::with_state(pid_state.clone()))
-        .and_then(|write_memory, pid_state| async move {
-            api::write_memory_handler(pid_state, write_memory).await
+        .and(write_history::session())
+        .and_then(|write_memory, pid_state, session| async move {
+            api::write_memory_handler(pid_state, write_memory, session).await
         });
 
     let read_memory_multiple = warp::path!("memories")
@@ -65,6 +105,14 @@ pub async fn serve(mode: i32, host: IpAddr, port: u16) {
             api::read_memory_multiple_handler(pid_state, read_memory_requests).await
         });
 
+    let read_region = warp::path!("readregion")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|read_region_request, pid_state| async move {
+            api::read_region_handler(pid_state, read_region_request).await
+        });
+
     let memory_scan = warp::path!("memoryscan")
         .and(warp::post())
         .and(warp::body::json())
@@ -73,6 +121,38 @@ pub async fn serve(mode: i32, host: IpAddr, port: u16) {
             api::memory_scan_handler(pid_state, scan_request).await
         });
 
+    let scan_estimate = warp::path!("memoryscan" / "estimate")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|scan_request, pid_state| async move {
+            api::scan_estimate_handler(pid_state, scan_request).await
+        });
+
+    let ocr_scan = warp::path!("ocrscan")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|ocr_request, pid_state| async move {
+            api::ocr_scan_handler(pid_state, ocr_request).await
+        });
+
+    let screen_diff_start = warp::path!("screendiff" / "start")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|start_request, pid_state| async move {
+            api::screen_diff_start_handler(pid_state, start_request).await
+        });
+
+    let screen_diff_finish = warp::path!("screendiff" / "finish")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|finish_request, pid_state| async move {
+            api::screen_diff_finish_handler(pid_state, finish_request).await
+        });
+
     let memory_filter = warp::path!("memoryfilter")
         .and(warp::post())
         .and(warp::body::json())
@@ -81,6 +161,120 @@ pub async fn serve(mode: i32, host: IpAddr, port: u16) {
             api::memory_filter_handler(pid_state, filter_request).await
         });
 
+    let repeat_filter = warp::path!("memoryfilter" / "repeat")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|repeat_request, pid_state| async move {
+            api::repeat_filter_handler(pid_state, repeat_request).await
+        });
+
+    let import_scan = warp::path!("memoryscan" / "import")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|import_request, pid_state| async move {
+            api::import_scan_handler(pid_state, import_request).await
+        });
+
+    let timestamp_scan = warp::path!("memoryscan" / "timestamps")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|timestamp_request, pid_state| async move {
+            api::timestamp_scan_handler(pid_state, timestamp_request).await
+        });
+
+    let vec3_scan = warp::path!("memoryscan" / "vec3")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|vec3_request, pid_state| async move {
+            api::vec3_scan_handler(pid_state, vec3_request).await
+        });
+
+    let export_results = warp::path!("memoryscan" / "archive")
+        .and(warp::get())
+        .and(warp::query::<request::ExportResultsRequest>())
+        .and_then(api::export_results_handler);
+
+    let import_results = warp::path!("memoryscan" / "archive")
+        .and(warp::post())
+        .and(warp::query::<request::ImportResultsRequest>())
+        .and(warp::body::content_length_limit(1024 * 1024 * 1024)) // 1GB
+        .and(warp::body::bytes())
+        .and_then(api::import_results_handler);
+
+    let list_scans = warp::path!("listscans")
+        .and(warp::get())
+        .and_then(api::list_scans_handler);
+
+    let scan_metadata = warp::path!("scanmetadata")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(api::scan_metadata_handler);
+
+    let gc_status = warp::path!("gc")
+        .and(warp::get())
+        .and_then(api::gc_status_handler);
+
+    let gc = warp::path!("gc")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(api::gc_handler);
+
+    let result_summary = warp::path!("resultsummary")
+        .and(warp::get())
+        .and(warp::query::<request::ResultSummaryRequest>())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|summary_request, pid_state| async move {
+            api::result_summary_handler(pid_state, summary_request).await
+        });
+
+    let stack_scan = warp::path!("stackscan")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|stack_scan_request, pid_state| async move {
+            api::stack_scan_handler(pid_state, stack_scan_request).await
+        });
+
+    let undo_write = warp::path!("undowrite")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and(write_history::session())
+        .and_then(|undo_request, pid_state, session| async move {
+            api::undo_write_handler(pid_state, undo_request, session).await
+        });
+
+    let undo_all_writes = warp::path!("undoallwrites")
+        .and(warp::post())
+        .and(api::with_state(pid_state.clone()))
+        .and(write_history::session())
+        .and_then(|pid_state, session| async move {
+            api::undo_all_writes_handler(pid_state, session).await
+        });
+
+    let write_log = warp::path!("writelog")
+        .and(warp::get())
+        .and(warp::query::<write_history::WriteLogQuery>())
+        .and_then(api::write_log_handler);
+
+    let list_exclusions = warp::path!("exclusions")
+        .and(warp::get())
+        .and_then(api::list_exclusions_handler);
+
+    let add_exclusions = warp::path!("exclusions")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(api::add_exclusions_handler);
+
+    let remove_exclusions = warp::path!("exclusions")
+        .and(warp::delete())
+        .and(warp::body::json())
+        .and_then(api::remove_exclusions_handler);
+
     let enum_regions = warp::path!("regions")
         .and(warp::get())
         .and(api::with_state(pid_state.clone()))
@@ -117,9 +311,21 @@ pub async fn serve(mode: i32, host: IpAddr, port: u16) {
         .and(warp::get())
         .and_then(api::server_info_handler);
 
+    let diagnose = warp::path!("diagnose")
+        .and(warp::get())
+        .and(warp::query::<request::DiagnoseRequest>())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|diagnose_request, pid_state| async move {
+            api::diagnose_handler(pid_state, diagnose_request).await
+        });
+
+    let openapi = warp::path!("openapi.json")
+        .and(warp::get())
+        .and_then(api::openapi_handler);
+
     let set_watchpoint = warp::path!("watchpoint")
         .and(warp::post())
-        .and(warp::body::json())
+        .and(recorder::recorded_json())
         .and(api::with_state(pid_state.clone()))
         .and_then(|set_watchpoint_request, pid_state| async move {
             api::set_watchpoint_handler(pid_state, set_watchpoint_request).await
@@ -127,7 +333,7 @@ pub async fn serve(mode: i32, host: IpAddr, port: u16) {
 
     let remove_watchpoint = warp::path!("watchpoint")
         .and(warp::delete())
-        .and(warp::body::json())
+        .and(recorder::recorded_json())
         .and(api::with_state(pid_state.clone()))
         .and_then(|remove_watchpoint_request, pid_state| async move {
             api::remove_watchpoint_handler(pid_state, remove_watchpoint_request).await
@@ -135,7 +341,7 @@ pub async fn serve(mode: i32, host: IpAddr, port: u16) {
 
     let set_breakpoint = warp::path!("breakpoint")
         .and(warp::post())
-        .and(warp::body::json())
+        .and(recorder::recorded_json())
         .and(api::with_state(pid_state.clone()))
         .and_then(|set_breakpoint_request, pid_state| async move {
             api::set_breakpoint_handler(pid_state, set_breakpoint_request).await
@@ -143,7 +349,7 @@ pub async fn serve(mode: i32, host: IpAddr, port: u16) {
 
     let remove_breakpoint = warp::path!("breakpoint")
         .and(warp::delete())
-        .and(warp::body::json())
+        .and(recorder::recorded_json())
         .and(api::with_state(pid_state.clone()))
         .and_then(|remove_breakpoint_request, pid_state| async move {
             api::remove_breakpoint_handler(pid_state, remove_breakpoint_request).await
@@ -155,7 +361,7 @@ pub async fn serve(mode: i32, host: IpAddr, port: u16) {
 
     let change_process_state = warp::path!("process")
         .and(warp::put())
-        .and(warp::body::json())
+        .and(recorder::recorded_json())
         .and(api::with_state(pid_state.clone()))
         .and_then(|state_request, pid_state| async move {
             api::change_process_state_handler(pid_state, state_request).await
@@ -169,33 +375,717 @@ pub async fn serve(mode: i32, host: IpAddr, port: u16) {
             api::pointermap_generate_handler(pid_state, request).await
         });
 
-    let routes = open_process
+    let hexdump = warp::path!("hexdump")
+        .and(warp::get())
+        .and(warp::query::<request::HexDumpRequest>())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|hexdump_request, pid_state| async move {
+            api::hexdump_handler(pid_state, hexdump_request).await
+        });
+
+    let register_struct = warp::path!("struct")
+        .and(warp::post())
+        .and(recorder::recorded_json())
+        .and_then(api::register_struct_handler);
+
+    let list_structs = warp::path!("structs")
+        .and(warp::get())
+        .and_then(api::list_structs_handler);
+
+    let remove_struct = warp::path!("struct")
+        .and(warp::delete())
+        .and(recorder::recorded_json())
+        .and_then(api::remove_struct_handler);
+
+    let read_struct = warp::path!("readstruct")
+        .and(warp::get())
+        .and(warp::query::<request::ReadStructRequest>())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|read_struct_request, pid_state| async move {
+            api::read_struct_handler(pid_state, read_struct_request).await
+        });
+
+    let read_array = warp::path!("readarray")
+        .and(warp::get())
+        .and(warp::query::<request::ReadArrayRequest>())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|read_array_request, pid_state| async move {
+            api::read_array_handler(pid_state, read_array_request).await
+        });
+
+    let write_array = warp::path!("writearray")
+        .and(warp::post())
+        .and(recorder::recorded_json())
+        .and(api::with_state(pid_state.clone()))
+        .and(write_history::session())
+        .and_then(|write_array_request, pid_state, session| async move {
+            api::write_array_handler(pid_state, write_array_request, session).await
+        });
+
+    let guess_type = warp::path!("guesstype")
+        .and(warp::get())
+        .and(warp::query::<request::GuessTypeRequest>())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|guess_type_request, pid_state| async move {
+            api::guess_type_handler(pid_state, guess_type_request).await
+        });
+
+    let format_value = warp::path!("formatvalue")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(api::format_value_handler);
+
+    let pointermap_save = warp::path!("pointermap" / "save")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|request, pid_state| async move {
+            api::pointermap_save_handler(pid_state, request).await
+        });
+
+    let pointermap_list = warp::path!("pointermaps")
+        .and(warp::get())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|pid_state| async move { api::pointermap_list_handler(pid_state).await });
+
+    let pointermap_compare = warp::path!("pointermap" / "compare")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|request, pid_state| async move {
+            api::pointermap_compare_handler(pid_state, request).await
+        });
+
+    let pointer_path_validate = warp::path!("pointerpath" / "validate")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|request, pid_state| async move {
+            api::pointer_path_validate_handler(pid_state, request).await
+        });
+
+    let table_list = warp::path!("table")
+        .and(warp::get())
+        .and_then(api::table_list_handler);
+
+    let table_add = warp::path!("table")
+        .and(warp::post())
+        .and(recorder::recorded_json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|entry, pid_state| async move { api::table_add_handler(pid_state, entry).await });
+
+    let table_remove = warp::path!("table")
+        .and(warp::delete())
+        .and(recorder::recorded_json())
+        .and_then(api::table_remove_handler);
+
+    let table_set_preset = warp::path!("table" / "presets")
+        .and(warp::post())
+        .and(recorder::recorded_json())
+        .and_then(api::table_set_preset_handler);
+
+    let table_remove_preset = warp::path!("table" / "presets")
+        .and(warp::delete())
+        .and(recorder::recorded_json())
+        .and_then(api::table_remove_preset_handler);
+
+    let table_apply_preset = warp::path!("table" / "apply")
+        .and(warp::post())
+        .and(recorder::recorded_json())
+        .and(api::with_state(pid_state.clone()))
+        .and(write_history::session())
+        .and_then(|preset_request, pid_state, session| async move {
+            api::table_apply_preset_handler(pid_state, preset_request, session).await
+        });
+
+    let table_set_value = warp::path!("table" / "setvalue")
+        .and(warp::post())
+        .and(recorder::recorded_json())
+        .and(api::with_state(pid_state.clone()))
+        .and(write_history::session())
+        .and_then(|set_request, pid_state, session| async move {
+            api::table_set_value_handler(pid_state, set_request, session).await
+        });
+
+    let table_import_gameguardian = warp::path!("table" / "import" / "gameguardian")
+        .and(warp::post())
+        .and(recorder::recorded_json())
+        .and(api::with_state(pid_state.clone()))
+        .and(write_history::session())
+        .and_then(|import_request, pid_state, session| async move {
+            api::table_import_gameguardian_handler(pid_state, import_request, session).await
+        });
+
+    let table_rebase = warp::path!("table" / "rebase")
+        .and(warp::post())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|pid_state| async move { api::table_rebase_handler(pid_state).await });
+
+    let table_rebase_report = warp::path!("table" / "rebase")
+        .and(warp::get())
+        .and_then(api::table_rebase_report_handler);
+
+    let table_relocate = warp::path!("table" / "relocate")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|relocate_request, pid_state| async move {
+            api::table_relocate_handler(pid_state, relocate_request).await
+        });
+
+    let list_annotations = warp::path!("annotations")
+        .and(warp::get())
+        .and(warp::query::<request::ListAnnotationsRequest>())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|list_request, pid_state| async move {
+            api::list_annotations_handler(pid_state, list_request).await
+        });
+
+    let add_annotation = warp::path!("annotations")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|annotation, pid_state| async move {
+            api::add_annotation_handler(pid_state, annotation).await
+        });
+
+    let remove_annotation = warp::path!("annotations")
+        .and(warp::delete())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|remove_request, pid_state| async move {
+            api::remove_annotation_handler(pid_state, remove_request).await
+        });
+
+    let schedule_write = warp::path!("schedulewrite")
+        .and(warp::post())
+        .and(recorder::recorded_json())
+        .and(api::with_state(pid_state.clone()))
+        .and(write_history::session())
+        .and_then(|schedule_request, pid_state, session| async move {
+            api::schedule_write_handler(pid_state, schedule_request, session).await
+        });
+
+    let list_scheduled_writes = warp::path!("scheduledwrites")
+        .and(warp::get())
+        .and_then(api::list_scheduled_writes_handler);
+
+    let cancel_scheduled_write = warp::path!("schedulewrite")
+        .and(warp::delete())
+        .and(recorder::recorded_json())
+        .and_then(api::cancel_scheduled_write_handler);
+
+    let events = warp::path!("events")
+        .and(warp::get())
+        .and_then(api::events_handler);
+
+    let event_stream = warp::path!("events" / "stream")
+        .and(warp::get())
+        .and(warp::query::<events::StreamQuery>())
+        .and_then(api::event_stream_handler);
+
+    let event_socket = warp::path!("events" / "ws")
+        .and(warp::query::<events::StreamQuery>())
+        .and(warp::ws())
+        .and_then(api::event_socket_handler);
+
+    let event_subscribers = warp::path!("events" / "subscribers")
+        .and(warp::get())
+        .and_then(api::event_subscribers_handler);
+
+    let logs = warp::path!("logs")
+        .and(warp::get())
+        .and(warp::query::<logger::LogQuery>())
+        .and_then(api::logs_handler);
+
+    let log_levels = warp::path!("logs" / "levels")
+        .and(warp::get())
+        .and_then(api::log_levels_handler);
+
+    let set_log_levels = warp::path!("logs" / "levels")
+        .and(warp::put())
+        .and(warp::body::json())
+        .and_then(api::set_log_levels_handler);
+
+    let crashes = warp::path!("crashes")
+        .and(warp::get())
+        .and_then(api::crashes_handler);
+
+    let clear_crashes = warp::path!("crashes")
+        .and(warp::delete())
+        .and_then(api::clear_crashes_handler);
+
+    let selftest = warp::path!("selftest")
+        .and(warp::get())
+        .and(warp::query::<selftest::SelfTestQuery>())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|query, pid_state| async move { api::selftest_handler(pid_state, query).await });
+
+    let region_monitor_start = warp::path!("regionmonitor" / "start")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|monitor_request, pid_state| async move {
+            api::region_monitor_start_handler(pid_state, monitor_request).await
+        });
+
+    let add_mapping_watch = warp::path!("mappingwatch")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and(write_history::session())
+        .and_then(|watch_request, pid_state, session| async move {
+            api::add_mapping_watch_handler(pid_state, watch_request, session).await
+        });
+
+    let remove_mapping_watch = warp::path!("mappingwatch")
+        .and(warp::delete())
+        .and(warp::body::json())
+        .and_then(api::remove_mapping_watch_handler);
+
+    let list_mapping_watches = warp::path!("mappingwatch")
+        .and(warp::get())
+        .and_then(api::list_mapping_watches_handler);
+
+    let region_monitor_stop = warp::path!("regionmonitor" / "stop")
+        .and(warp::post())
+        .and_then(api::region_monitor_stop_handler);
+
+    let region_monitor_status = warp::path!("regionmonitor")
+        .and(warp::get())
+        .and_then(api::region_monitor_status_handler);
+
+    let trace = warp::path!("trace")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|trace_request, pid_state| async move {
+            api::trace_handler(pid_state, trace_request).await
+        });
+
+    let callers = warp::path!("callers")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|caller_request, pid_state| async move {
+            api::callers_handler(pid_state, caller_request).await
+        });
+
+    let heatmap_start = warp::path!("heatmap" / "start")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|heatmap_request, pid_state| async move {
+            api::heatmap_start_handler(pid_state, heatmap_request).await
+        });
+
+    let heatmap_stop = warp::path!("heatmap" / "stop")
+        .and(warp::post())
+        .and_then(api::heatmap_stop_handler);
+
+    let heatmap_report = warp::path!("heatmap")
+        .and(warp::get())
+        .and_then(api::heatmap_report_handler);
+
+    let enumerate_allocations = warp::path!("enumerate_allocations")
+        .and(warp::get())
+        .and(warp::query::<request::EnumerateAllocationsRequest>())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|allocations_request, pid_state| async move {
+            api::enumerate_allocations_handler(pid_state, allocations_request).await
+        });
+
+    let page_info = warp::path!("pageinfo")
+        .and(warp::get())
+        .and(warp::query::<request::PageInfoRequest>())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|page_info_request, pid_state| async move {
+            api::page_info_handler(pid_state, page_info_request).await
+        });
+
+    let prefetch = warp::path!("prefetch")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|prefetch_request, pid_state| async move {
+            api::prefetch_handler(pid_state, prefetch_request).await
+        });
+
+    let take_snapshot = warp::path!("snapshot")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|snapshot_request, pid_state| async move {
+            api::take_snapshot_handler(pid_state, snapshot_request).await
+        });
+
+    let list_snapshots = warp::path!("snapshots")
+        .and(warp::get())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|pid_state| async move { api::list_snapshots_handler(pid_state).await });
+
+    let remove_snapshot = warp::path!("snapshot")
+        .and(warp::delete())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|remove_request, pid_state| async move {
+            api::remove_snapshot_handler(pid_state, remove_request).await
+        });
+
+    let snapshot_changed = warp::path!("snapshot" / "changed")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|changed_request, pid_state| async move {
+            api::snapshot_changed_handler(pid_state, changed_request).await
+        });
+
+    let snapshot_diff = warp::path!("snapshot" / "diff")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|diff_request, pid_state| async move {
+            api::snapshot_diff_handler(pid_state, diff_request).await
+        });
+
+    let batch = warp::path!("batch")
+        .and(warp::post())
+        .and(recorder::recorded_json())
+        .and(api::with_state(pid_state.clone()))
+        .and(write_history::session())
+        .and_then(|batch_request, pid_state, session| async move {
+            api::batch_handler(pid_state, batch_request, session).await
+        });
+
+    let list_triggers = warp::path!("triggers")
+        .and(warp::get())
+        .and_then(api::list_triggers_handler);
+
+    let bind_trigger = warp::path!("triggers")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(api::bind_trigger_handler);
+
+    let unbind_trigger = warp::path!("triggers")
+        .and(warp::delete())
+        .and(warp::body::json())
+        .and_then(api::unbind_trigger_handler);
+
+    let input = warp::path!("input")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(api::input_handler);
+
+    let list_alerts = warp::path!("alerts")
+        .and(warp::get())
+        .and_then(api::list_alerts_handler);
+
+    let add_alert = warp::path!("alerts")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(api::add_alert_handler);
+
+    let remove_alert = warp::path!("alerts")
+        .and(warp::delete())
+        .and(warp::body::json())
+        .and_then(api::remove_alert_handler);
+
+    let list_predicates = warp::path!("predicates")
+        .and(warp::get())
+        .and_then(api::list_predicates_handler);
+
+    // The body is the module itself, e.g. curl --data-binary @predicate.wasm
+    let add_predicate = warp::path!("predicates" / String)
+        .and(warp::post())
+        .and(warp::query::<wasm_predicates::AddPredicateQuery>())
+        .and(warp::body::content_length_limit(
+            wasm_predicates::MAX_MODULE_SIZE,
+        ))
+        .and(warp::body::bytes())
+        .and_then(api::add_predicate_handler);
+
+    let remove_predicate = warp::path!("predicates")
+        .and(warp::delete())
+        .and(warp::body::json())
+        .and_then(api::remove_predicate_handler);
+
+    let list_plugins = warp::path!("plugins")
+        .and(warp::get())
+        .and_then(api::list_plugins_handler);
+
+    let plugin_route = warp::path!("plugins" / String / String)
+        .and(warp::post())
+        .and(warp::body::bytes())
+        .and_then(api::plugin_route_handler);
+
+    let list_recipes = warp::path!("recipes")
+        .and(warp::get())
+        .and_then(api::list_recipes_handler);
+
+    let save_recipe = warp::path!("recipes")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(api::save_recipe_handler);
+
+    let remove_recipe = warp::path!("recipes")
+        .and(warp::delete())
+        .and(warp::body::json())
+        .and_then(api::remove_recipe_handler);
+
+    let run_recipe = warp::path!("recipes" / "run")
+        .and(warp::post())
+        .and(recorder::recorded_json())
+        .and(api::with_state(pid_state.clone()))
+        .and_then(|run_request, pid_state| async move {
+            api::run_recipe_handler(pid_state, run_request).await
+        });
+
+    let list_profiles = warp::path!("profiles")
+        .and(warp::get())
+        .and_then(api::list_profiles_handler);
+
+    let save_profile = warp::path!("profiles")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(api::save_profile_handler);
+
+    let remove_profile = warp::path!("profiles")
+        .and(warp::delete())
+        .and(warp::body::json())
+        .and_then(api::remove_profile_handler);
+
+    let fire_trigger = warp::path!("trigger" / String)
+        .and(warp::post())
+        .and(api::with_state(pid_state.clone()))
+        .and(write_history::session())
+        .and_then(|id, pid_state, session| async move {
+            api::fire_trigger_handler(pid_state, id, session).await
+        });
+
+    let register_device = warp::path!("devices")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(api::register_device_handler);
+
+    let list_devices = warp::path!("devices")
+        .and(warp::get())
+        .and_then(api::list_devices_handler);
+
+    let remove_device = warp::path!("devices")
+        .and(warp::delete())
+        .and(warp::body::json())
+        .and_then(api::remove_device_handler);
+
+    let devices_scan = warp::path!("devices" / "memoryscan")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(|fan_out_request| async move {
+            api::devices_fan_out_handler("memoryscan", fan_out_request).await
+        });
+
+    let devices_filter = warp::path!("devices" / "memoryfilter")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(|fan_out_request| async move {
+            api::devices_fan_out_handler("memoryfilter", fan_out_request).await
+        });
+
+    let record_start = warp::path!("record" / "start")
+        .and(warp::post())
+        .and_then(api::record_start_handler);
+
+    let record_stop = warp::path!("record" / "stop")
+        .and(warp::post())
+        .and_then(api::record_stop_handler);
+
+    let record_status = warp::path!("record")
+        .and(warp::get())
+        .and_then(api::record_status_handler);
+
+    let replay = warp::path!("replay")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(api::replay_handler);
+
+    // Routes are grouped and boxed to keep the filter type depth manageable
+    let core_routes = open_process
         .or(read_memory)
         .or(read_memory_multiple)
+        .or(read_region)
         .or(write_memory)
         .or(memory_scan)
+        .or(scan_estimate)
+        .or(ocr_scan)
+        .or(screen_diff_start)
+        .or(screen_diff_finish)
         .or(memory_filter)
         .or(enum_regions)
         .or(enum_process)
         .or(enum_module)
+        .or(enum_threads)
+        .or(enum_tls)
         .or(resolve_addr)
         .or(explore_directory)
         .or(read_file)
         .or(get_app_info)
         .or(server_info)
+        .or(diagnose)
+        .or(openapi)
         .or(set_watchpoint)
         .or(remove_watchpoint)
         .or(set_breakpoint)
         .or(remove_breakpoint)
         .or(get_exception_info)
         .or(change_process_state)
-        .or(pointermap_generate)
-        .or(static_files)
+        .boxed();
+
+    let analysis_routes = pointermap_generate
+        .or(hexdump)
+        .or(register_struct)
+        .or(list_structs)
+        .or(remove_struct)
+        .or(read_struct)
+        .or(read_array)
+        .or(write_array)
+        .or(guess_type)
+        .or(format_value)
+        .or(pointermap_save)
+        .or(pointermap_list)
+        .or(pointermap_compare)
+        .or(pointer_path_validate)
+        .or(table_list)
+        .or(table_add)
+        .or(table_remove)
+        .or(table_set_preset)
+        .or(table_remove_preset)
+        .or(table_apply_preset)
+        .or(table_set_value)
+        .or(table_import_gameguardian)
+        .or(table_rebase)
+        .or(table_rebase_report)
+        .or(table_relocate)
+        .or(list_annotations)
+        .or(add_annotation)
+        .or(remove_annotation)
+        .or(enumerate_allocations)
+        .or(page_info)
+        .or(prefetch)
+        .boxed();
+
+    let scan_routes = repeat_filter
+        .or(import_scan)
+        .or(timestamp_scan)
+        .or(vec3_scan)
+        .or(export_results)
+        .or(import_results)
+        .or(list_scans)
+        .or(scan_metadata)
+        .or(gc_status)
+        .or(gc)
+        .or(result_summary)
+        .or(stack_scan)
+        .or(list_exclusions)
+        .or(add_exclusions)
+        .or(remove_exclusions)
+        .boxed();
+
+    let write_routes = schedule_write
+        .or(list_scheduled_writes)
+        .or(cancel_scheduled_write)
+        .or(batch)
+        .or(list_triggers)
+        .or(bind_trigger)
+        .or(unbind_trigger)
+        .or(fire_trigger)
+        .or(input)
+        .or(list_alerts)
+        .or(add_alert)
+        .or(remove_alert)
+        .or(list_profiles)
+        .or(save_profile)
+        .or(remove_profile)
+        .or(list_predicates)
+        .or(add_predicate)
+        .or(remove_predicate)
+        .or(list_plugins)
+        .or(plugin_route)
+        .or(list_recipes)
+        .or(save_recipe)
+        .or(remove_recipe)
+        .or(run_recipe)
+        .or(undo_write)
+        .or(undo_all_writes)
+        .or(write_log)
+        .boxed();
+
+    let monitor_routes = events
+        .or(event_stream)
+        .or(event_socket)
+        .or(event_subscribers)
+        .or(logs)
+        .or(log_levels)
+        .or(set_log_levels)
+        .or(crashes)
+        .or(clear_crashes)
+        .or(selftest)
+        .or(region_monitor_start)
+        .or(region_monitor_stop)
+        .or(add_mapping_watch)
+        .or(remove_mapping_watch)
+        .or(list_mapping_watches)
+        .or(region_monitor_status)
+        .or(heatmap_start)
+        .or(heatmap_stop)
+        .or(heatmap_report)
+        .or(trace)
+        .or(callers)
+        .boxed();
+
+    let snapshot_routes = take_snapshot
+        .or(list_snapshots)
+        .or(remove_snapshot)
+        .or(snapshot_changed)
+        .or(snapshot_diff)
+        .boxed();
+
+    let device_routes = register_device
+        .or(list_devices)
+        .or(remove_device)
+        .or(devices_scan)
+        .or(devices_filter)
+        .boxed();
+
+    let record_routes = record_start
+        .or(record_stop)
+        .or(record_status)
+        .or(replay)
+        .boxed();
+
+    let routes = warp::header::optional::<String>(proxy::COMPRESSION_HEADER)
+        .and(
+            core_routes
+                .or(analysis_routes)
+                .or(scan_routes)
+                .or(write_routes)
+                .or(monitor_routes)
+                .or(snapshot_routes)
+                .or(device_routes)
+                .or(record_routes)
+                .or(static_files),
+        )
+        .and_then(proxy::compress_reply)
         .with(cors)
         .with(warp::log::custom(logger::http_log));
 
     native_bridge::native_api_init(mode);
-    warp::serve(routes).run((host, port)).await;
+    plugins::load_from_env();
+    session_gc::start(pid_state.clone());
+    recorder::set_local_address((host, port).into());
+    let shared_pid = pid_state.clone();
+    crash::serve(warp::service(routes), (host, port).into(), move || {
+        shared_pid.clear_poison()
+    })
+    .await;
 }
 
 static STATIC_DIR: Dir = include_dir!("../frontend/out");
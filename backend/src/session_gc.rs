@@ -0,0 +1,199 @@
+// Periodic cleanup for long-running servers, e.g. an agent left on a phone: scan sessions of
+// exited processes or idle past their TTL, the least recently used sessions beyond a count
+// limit, snapshots past their age limit and mapping watches of exited processes.
+use crate::api;
+use crate::events;
+use crate::mapping_watch;
+use crate::scan_sessions;
+use crate::snapshot;
+use crate::util;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const DEFAULT_SESSION_TTL_SECS: u64 = 60 * 60;
+const DEFAULT_MAX_SESSIONS: usize = 32;
+const DEFAULT_INTERVAL_SECS: u64 = 60;
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct GcConfig {
+    // Scan sessions unused for this long are dropped; 0 keeps idle sessions
+    pub session_ttl_secs: u64,
+    // Most scan sessions kept, the least recently used are dropped first; 0 for no limit
+    pub max_sessions: usize,
+    // Snapshots older than this are deleted; 0 (the default) keeps them
+    pub snapshot_ttl_secs: u64,
+    // Drop the scan sessions and mapping watches of processes that exited
+    pub drop_detached: bool,
+    pub interval_secs: u64,
+}
+
+// Fields left out keep their current value
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct GcConfigRequest {
+    pub session_ttl_secs: Option<u64>,
+    pub max_sessions: Option<usize>,
+    pub snapshot_ttl_secs: Option<u64>,
+    pub drop_detached: Option<bool>,
+    pub interval_secs: Option<u64>,
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        GcConfig {
+            session_ttl_secs: env_or("MEMORY_SERVER_SESSION_TTL", DEFAULT_SESSION_TTL_SECS),
+            max_sessions: env_or("MEMORY_SERVER_MAX_SESSIONS", DEFAULT_MAX_SESSIONS),
+            snapshot_ttl_secs: env_or("MEMORY_SERVER_SNAPSHOT_TTL", 0),
+            drop_detached: true,
+            interval_secs: DEFAULT_INTERVAL_SECS,
+        }
+    }
+}
+
+lazy_static! {
+    static ref CONFIG: Mutex<Option<GcConfig>> = Mutex::new(None);
+    static ref LAST_REPORT: Mutex<Value> = Mutex::new(Value::Null);
+    // Attached process of the running server, whose data directory holds the snapshots
+    static ref PID_STATE: Mutex<Option<Arc<Mutex<Option<i32>>>>> = Mutex::new(None);
+}
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+pub fn config() -> GcConfig {
+    CONFIG
+        .lock()
+        .unwrap()
+        .get_or_insert_with(GcConfig::default)
+        .clone()
+}
+
+pub fn configure(request: GcConfigRequest) -> GcConfig {
+    let mut config = CONFIG.lock().unwrap();
+    let config = config.get_or_insert_with(GcConfig::default);
+    if let Some(ttl) = request.session_ttl_secs {
+        config.session_ttl_secs = ttl;
+    }
+    if let Some(max) = request.max_sessions {
+        config.max_sessions = max;
+    }
+    if let Some(ttl) = request.snapshot_ttl_secs {
+        config.snapshot_ttl_secs = ttl;
+    }
+    if let Some(drop_detached) = request.drop_detached {
+        config.drop_detached = drop_detached;
+    }
+    if let Some(interval) = request.interval_secs {
+        config.interval_secs = interval.max(1);
+    }
+    config.clone()
+}
+
+fn attached_pid() -> Option<i32> {
+    let pid_state = PID_STATE.lock().unwrap().clone()?;
+    let pid = *pid_state.lock().unwrap();
+    pid
+}
+
+pub fn collect() -> Value {
+    let config = config();
+    let now = chrono::Local::now().timestamp_millis();
+    // Scan files and snapshots live in the data directory, which depends on the pid only for
+    // the embedded server, where it is always our own
+    let data_pid = attached_pid().unwrap_or(std::process::id() as i32);
+
+    let mut dropped = Vec::new();
+    let mut kept = Vec::new();
+    for scan_id in api::scan_ids() {
+        let Some(session) = scan_sessions::get(&scan_id) else {
+            continue;
+        };
+        let idle_ms = now - session.last_used;
+        let reason = if config.drop_detached
+            && session.pid.is_some_and(|pid| !util::process_alive(pid))
+        {
+            Some("detached")
+        } else if config.session_ttl_secs > 0 && idle_ms > config.session_ttl_secs as i64 * 1000 {
+            Some("idle")
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => dropped.push(json!({ "scan_id": scan_id, "reason": reason })),
+            None => kept.push((session.last_used, scan_id)),
+        }
+    }
+    if config.max_sessions > 0 && kept.len() > config.max_sessions {
+        kept.sort();
+        let excess = kept.len() - config.max_sessions;
+        for (_, scan_id) in kept.drain(..excess) {
+            dropped.push(json!({ "scan_id": scan_id, "reason": "limit" }));
+        }
+    }
+    for entry in &dropped {
+        api::drop_scan(data_pid, entry["scan_id"].as_str().unwrap_or(""));
+    }
+
+    let mut removed_snapshots = Vec::new();
+    if config.snapshot_ttl_secs > 0 {
+        for metadata in snapshot::list(data_pid) {
+            let created = metadata["created"].as_i64().unwrap_or(now);
+            let Some(name) = metadata["name"].as_str() else {
+                continue;
+            };
+            if now - created > config.snapshot_ttl_secs as i64 * 1000
+                && snapshot::remove(data_pid, name).is_ok()
+            {
+                removed_snapshots.push(name.to_string());
+            }
+        }
+    }
+
+    let removed_watches = if config.drop_detached {
+        mapping_watch::remove_detached()
+    } else {
+        Vec::new()
+    };
+
+    let report = json!({
+        "time": now,
+        "dropped_scans": dropped,
+        "removed_snapshots": removed_snapshots,
+        "removed_watches": removed_watches,
+    });
+    if !dropped.is_empty() || !removed_snapshots.is_empty() || !removed_watches.is_empty() {
+        events::push_event("gc", report.clone());
+    }
+    *LAST_REPORT.lock().unwrap() = report.clone();
+    report
+}
+
+pub fn status() -> Value {
+    json!({
+        "config": config(),
+        "last_run": LAST_REPORT.lock().unwrap().clone(),
+    })
+}
+
+// A restarted server hands over its pid state to the thread that is already running
+pub fn start(pid_state: Arc<Mutex<Option<i32>>>) {
+    *PID_STATE.lock().unwrap() = Some(pid_state);
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(|| loop {
+        thread::sleep(Duration::from_secs(config().interval_secs.max(1)));
+        collect();
+    });
+}
@@ -0,0 +1,488 @@
+use crate::native_bridge;
+use crate::util;
+use rayon::prelude::*;
+use serde_json::{json, Value};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+// Snapshots are stored as one "<range index>.snap" file per scanned range, each a
+// sequence of records: u64 address, u64 compressed size, u64 size, lz4 block data.
+const RECORD_HEADER_SIZE: u64 = 24;
+const METADATA_FILE: &str = "snapshot.json";
+const CHUNK_SIZE: usize = 1024 * 1024 * 16;
+
+pub struct ChunkRef {
+    pub address: usize,
+    pub size: usize,
+    path: PathBuf,
+    offset: u64,
+    compressed_size: usize,
+}
+
+fn snapshot_directory(pid: i32) -> PathBuf {
+    let mut path = util::get_data_directory(pid);
+    path.push("snapshots");
+    path
+}
+
+pub fn snapshot_path(pid: i32, name: &str) -> PathBuf {
+    let mut path = snapshot_directory(pid);
+    path.push(name.trim().replace([' ', '/', '\\'], "_"));
+    path
+}
+
+// Starts a new snapshot, replacing any previous one with the same name.
+pub fn create(pid: i32, name: &str) -> Result<PathBuf, String> {
+    let path = snapshot_path(pid, name);
+    if path.exists() {
+        fs::remove_dir_all(&path).map_err(|e| format!("Failed to remove snapshot: {}", e))?;
+    }
+    fs::create_dir_all(&path).map_err(|e| format!("Failed to create snapshot: {}", e))?;
+    let metadata = json!({
+        "name": name,
+        "pid": pid,
+        "created": chrono::Local::now().timestamp_millis(),
+    });
+    fs::write(path.join(METADATA_FILE), metadata.to_string())
+        .map_err(|e| format!("Failed to write snapshot metadata: {}", e))?;
+    Ok(path)
+}
+
+pub fn append_chunk(dir: &Path, index: usize, address: usize, data: &[u8]) -> Result<(), String> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(format!("{}.snap", index)))
+        .map_err(|e| format!("Failed to open snapshot file: {}", e))?;
+    let compressed = lz4_flex::block::compress(data);
+    let mut writer = BufWriter::new(file);
+    writer
+        .write_all(&(address as u64).to_le_bytes())
+        .and_then(|_| writer.write_all(&(compressed.len() as u64).to_le_bytes()))
+        .and_then(|_| writer.write_all(&(data.len() as u64).to_le_bytes()))
+        .and_then(|_| writer.write_all(&compressed))
+        .and_then(|_| writer.flush())
+        .map_err(|e| format!("Failed to write snapshot chunk: {}", e))
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes.try_into().unwrap())
+}
+
+fn index_file(path: &Path, chunks: &mut Vec<ChunkRef>) -> Result<(), String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open snapshot file: {}", e))?;
+    let length = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let mut offset = 0u64;
+    let mut header = [0u8; RECORD_HEADER_SIZE as usize];
+    while offset + RECORD_HEADER_SIZE <= length {
+        file.seek(SeekFrom::Start(offset))
+            .and_then(|_| file.read_exact(&mut header))
+            .map_err(|e| format!("Failed to read snapshot file: {}", e))?;
+        let compressed_size = read_u64(&header[8..16]) as usize;
+        chunks.push(ChunkRef {
+            address: read_u64(&header[0..8]) as usize,
+            size: read_u64(&header[16..24]) as usize,
+            path: path.to_path_buf(),
+            offset: offset + RECORD_HEADER_SIZE,
+            compressed_size,
+        });
+        offset += RECORD_HEADER_SIZE + compressed_size as u64;
+    }
+    Ok(())
+}
+
+// Indexes the chunks of a snapshot directory, or of a single ".snap" file, without
+// decompressing them. The result is sorted by address.
+pub fn open_path(path: &Path) -> Result<Vec<ChunkRef>, String> {
+    let mut chunks = Vec::new();
+    if path.is_file() {
+        index_file(path, &mut chunks)?;
+    } else {
+        let entries = fs::read_dir(path)
+            .map_err(|e| format!("Failed to open snapshot '{}': {}", path.display(), e))?;
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "snap") {
+                index_file(&path, &mut chunks)?;
+            }
+        }
+    }
+    chunks.sort_by_key(|chunk| chunk.address);
+    Ok(chunks)
+}
+
+pub fn open(pid: i32, name: &str) -> Result<Vec<ChunkRef>, String> {
+    open_path(&snapshot_path(pid, name))
+}
+
+pub fn load_chunk(chunk: &ChunkRef) -> Result<Vec<u8>, String> {
+    let mut file =
+        File::open(&chunk.path).map_err(|e| format!("Failed to open snapshot file: {}", e))?;
+    let mut compressed = vec![0u8; chunk.compressed_size];
+    file.seek(SeekFrom::Start(chunk.offset))
+        .and_then(|_| file.read_exact(&mut compressed))
+        .map_err(|e| format!("Failed to read snapshot chunk: {}", e))?;
+    lz4_flex::block::decompress(&compressed, chunk.size)
+        .map_err(|e| format!("Failed to decompress snapshot chunk: {}", e))
+}
+
+// Fills `buffer` from the snapshot chunk containing `address`, like a memory read.
+pub fn read(chunks: &[ChunkRef], address: usize, buffer: &mut [u8]) -> Result<usize, String> {
+    let index = chunks.partition_point(|chunk| chunk.address <= address);
+    let chunk = index
+        .checked_sub(1)
+        .map(|i| &chunks[i])
+        .filter(|chunk| address < chunk.address + chunk.size)
+        .ok_or_else(|| format!("0x{:x} is not in the snapshot", address))?;
+    let data = load_chunk(chunk)?;
+    let offset = address - chunk.address;
+    let length = buffer.len().min(data.len() - offset);
+    buffer[..length].copy_from_slice(&data[offset..offset + length]);
+    Ok(length)
+}
+
+// Address ranges covered by the snapshot, clipped to `requested` unless it is empty.
+pub fn covered_ranges(chunks: &[ChunkRef], requested: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    chunks
+        .iter()
+        .flat_map(|chunk| {
+            let chunk_end = chunk.address + chunk.size;
+            if requested.is_empty() {
+                return vec![(chunk.address, chunk_end)];
+            }
+            requested
+                .iter()
+                .filter_map(|&(start, end)| {
+                    let clipped_start = chunk.address.max(start);
+                    let clipped_end = chunk_end.min(end);
+                    (clipped_start < clipped_end).then_some((clipped_start, clipped_end))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// Reads the given ranges into a new snapshot. Unreadable chunks are skipped and counted.
+pub fn take(pid: i32, name: &str, ranges: &[(usize, usize)]) -> Result<Value, String> {
+    let dir = create(pid, name)?;
+    let results: Vec<Result<(usize, usize), String>> = ranges
+        .par_iter()
+        .enumerate()
+        .map(|(index, &(start, end))| {
+            let mut stored = 0;
+            let mut failed = 0;
+            let mut buffer = vec![0u8; CHUNK_SIZE.min(end.saturating_sub(start))];
+            let mut chunk_start = start;
+            while chunk_start < end {
+                let size = CHUNK_SIZE.min(end - chunk_start);
+                match native_bridge::read_process_memory(
+                    pid,
+                    chunk_start as *mut libc::c_void,
+                    size,
+                    &mut buffer,
+                ) {
+                    Ok(nread) if nread > 0 => {
+                        append_chunk(&dir, index, chunk_start, &buffer[..nread as usize])?;
+                        stored += nread as usize;
+                    }
+                    _ => failed += 1,
+                }
+                chunk_start += size;
+            }
+            Ok((stored, failed))
+        })
+        .collect();
+
+    let mut bytes = 0;
+    let mut failed_chunks = 0;
+    for result in results {
+        let (stored, failed) = result?;
+        bytes += stored;
+        failed_chunks += failed;
+    }
+    Ok(json!({
+        "name": name,
+        "ranges": ranges.len(),
+        "bytes": bytes,
+        "failed_chunks": failed_chunks,
+    }))
+}
+
+pub fn list(pid: i32) -> Vec<Value> {
+    let entries = match fs::read_dir(snapshot_directory(pid)) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut snapshots: Vec<Value> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let raw = fs::read_to_string(path.join(METADATA_FILE)).ok()?;
+            let mut metadata: Value = serde_json::from_str(&raw).ok()?;
+            let stored_size: u64 = fs::read_dir(&path)
+                .ok()?
+                .filter_map(|file| file.ok()?.metadata().ok())
+                .map(|m| m.len())
+                .sum();
+            metadata["stored_size"] = json!(stored_size);
+            Some(metadata)
+        })
+        .collect();
+    snapshots.sort_by_key(|snapshot| snapshot["created"].as_i64().unwrap_or(0));
+    snapshots
+}
+
+pub fn remove(pid: i32, name: &str) -> Result<(), String> {
+    fs::remove_dir_all(snapshot_path(pid, name))
+        .map_err(|e| format!("Failed to remove snapshot '{}': {}", name, e))
+}
+
+// Offsets of the bytes that differ, as [start, end) runs. Runs closer than
+// `merge_gap` bytes are merged into one.
+pub fn diff_runs(old: &[u8], new: &[u8], merge_gap: usize) -> Vec<(usize, usize)> {
+    let length = old.len().min(new.len());
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    let mut offset = 0;
+    while offset < length {
+        if old[offset] == new[offset] {
+            offset += 1;
+            continue;
+        }
+        let start = offset;
+        while offset < length && old[offset] != new[offset] {
+            offset += 1;
+        }
+        match runs.last_mut() {
+            Some(last) if start - last.1 <= merge_gap => last.1 = offset,
+            _ => runs.push((start, offset)),
+        }
+    }
+    runs
+}
+
+pub struct CompareOptions {
+    // None compares raw bytes, otherwise values of this type at `align` steps
+    pub data_type: Option<String>,
+    pub align: usize,
+    pub merge_gap: usize,
+    pub max_results: usize,
+    // Longest old/new byte string returned per change
+    pub max_bytes: usize,
+}
+
+// Integer and float readings of a changed run that has a primitive width.
+fn interpret(bytes: &[u8]) -> Value {
+    let types: &[&str] = match bytes.len() {
+        1 => &["int8", "uint8"],
+        2 => &["int16", "uint16"],
+        4 => &["int32", "uint32", "float"],
+        8 => &["int64", "uint64", "double"],
+        _ => return Value::Null,
+    };
+    types
+        .iter()
+        .filter_map(|data_type| {
+            Some((data_type.to_string(), util::decode_value(data_type, bytes)?))
+        })
+        .collect::<serde_json::Map<String, Value>>()
+        .into()
+}
+
+// Describes how `new` differs from `old`, both starting at `address`.
+pub fn compare_chunk(
+    address: usize,
+    old: &[u8],
+    new: &[u8],
+    options: &CompareOptions,
+) -> Vec<Value> {
+    // One extra so callers can tell the result was cut off
+    let limit = options.max_results.saturating_add(1);
+    match &options.data_type {
+        None => diff_runs(old, new, options.merge_gap)
+            .into_iter()
+            .take(limit)
+            .map(|(start, end)| {
+                let clipped_end = end.min(start + options.max_bytes);
+                let mut change = json!({
+                    "address": address + start,
+                    "size": end - start,
+                    "old": hex::encode(&old[start..clipped_end]),
+                    "new": hex::encode(&new[start..clipped_end]),
+                });
+                if clipped_end < end {
+                    change["clipped"] = json!(true);
+                }
+                let (old_value, new_value) =
+                    (interpret(&old[start..end]), interpret(&new[start..end]));
+                if !old_value.is_null() {
+                    change["old_value"] = old_value;
+                    change["new_value"] = new_value;
+                }
+                change
+            })
+            .collect(),
+        Some(data_type) => {
+            let size = util::data_type_size(data_type).unwrap_or(1);
+            let length = old.len().min(new.len());
+            (0..length.saturating_sub(size - 1))
+                .step_by(options.align.max(1))
+                .filter(|&offset| old[offset..offset + size] != new[offset..offset + size])
+                .take(limit)
+                .map(|offset| {
+                    json!({
+                        "address": address + offset,
+                        "old": util::decode_value(data_type, &old[offset..offset + size]),
+                        "new": util::decode_value(data_type, &new[offset..offset + size]),
+                    })
+                })
+                .collect()
+        }
+    }
+}
+
+// Compares a snapshot against the current memory of the process.
+pub fn changed_since(pid: i32, name: &str, options: &CompareOptions) -> Result<Value, String> {
+    let chunks = open(pid, name)?;
+    let results: Vec<Option<Vec<Value>>> = chunks
+        .par_iter()
+        .map(|chunk| {
+            let old = load_chunk(chunk).ok()?;
+            let mut new = vec![0u8; chunk.size];
+            let nread = native_bridge::read_process_memory(
+                pid,
+                chunk.address as *mut libc::c_void,
+                chunk.size,
+                &mut new,
+            )
+            .ok()?;
+            Some(compare_chunk(
+                chunk.address,
+                &old,
+                &new[..nread as usize],
+                options,
+            ))
+        })
+        .collect();
+
+    let unreadable_chunks = results.iter().filter(|r| r.is_none()).count();
+    let mut changes: Vec<Value> = results.into_iter().flatten().flatten().collect();
+    let truncated = changes.len() > options.max_results;
+    changes.truncate(options.max_results);
+    Ok(json!({
+        "name": name,
+        "changed_count": changes.len(),
+        "truncated": truncated,
+        "unreadable_chunks": unreadable_chunks,
+        "changes": changes,
+    }))
+}
+
+// Contiguous address ranges covered by the chunks, which are sorted by address.
+fn coverage(chunks: &[ChunkRef]) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for chunk in chunks {
+        let end = chunk.address + chunk.size;
+        match ranges.last_mut() {
+            Some(last) if chunk.address <= last.1 => last.1 = last.1.max(end),
+            _ => ranges.push((chunk.address, end)),
+        }
+    }
+    ranges
+}
+
+// Diffs two snapshots, grouping the changes by the contiguous ranges of the newer one.
+pub fn diff(
+    pid: i32,
+    old_name: &str,
+    new_name: &str,
+    options: &CompareOptions,
+) -> Result<Value, String> {
+    let old_chunks = open(pid, old_name)?;
+    let new_chunks = open(pid, new_name)?;
+
+    let results: Vec<Result<Vec<Value>, String>> = new_chunks
+        .par_iter()
+        .map(|new_chunk| {
+            let new_end = new_chunk.address + new_chunk.size;
+            let overlapping: Vec<&ChunkRef> = old_chunks
+                .iter()
+                .filter(|old| old.address < new_end && old.address + old.size > new_chunk.address)
+                .collect();
+            if overlapping.is_empty() {
+                return Ok(Vec::new());
+            }
+            let new_data = load_chunk(new_chunk)?;
+            let mut changes = Vec::new();
+            for old_chunk in overlapping {
+                let old_data = load_chunk(old_chunk)?;
+                let start = old_chunk.address.max(new_chunk.address);
+                let end =
+                    (old_chunk.address + old_data.len()).min(new_chunk.address + new_data.len());
+                if start >= end {
+                    continue;
+                }
+                changes.extend(compare_chunk(
+                    start,
+                    &old_data[start - old_chunk.address..end - old_chunk.address],
+                    &new_data[start - new_chunk.address..end - new_chunk.address],
+                    options,
+                ));
+            }
+            Ok(changes)
+        })
+        .collect();
+
+    let mut changes = Vec::new();
+    for result in results {
+        changes.extend(result?);
+    }
+    changes.sort_by_key(|change| change["address"].as_u64().unwrap_or(0));
+    let truncated = changes.len() > options.max_results;
+    changes.truncate(options.max_results);
+    let changed_count = changes.len();
+
+    let old_coverage = coverage(&old_chunks);
+    let new_coverage = coverage(&new_chunks);
+    let mut changes = changes.into_iter().peekable();
+    let regions: Vec<Value> = new_coverage
+        .iter()
+        .filter_map(|&(start, end)| {
+            let mut region_changes = Vec::new();
+            while let Some(change) =
+                changes.next_if(|change| (change["address"].as_u64().unwrap_or(0) as usize) < end)
+            {
+                region_changes.push(change);
+            }
+            (!region_changes.is_empty()).then(|| {
+                json!({
+                    "start_address": format!("{:x}", start),
+                    "end_address": format!("{:x}", end),
+                    "change_count": region_changes.len(),
+                    "changes": region_changes,
+                })
+            })
+        })
+        .collect();
+
+    let format_ranges = |ranges: Vec<(usize, usize)>| -> Vec<Value> {
+        ranges
+            .into_iter()
+            .map(|(start, end)| {
+                json!({
+                    "start_address": format!("{:x}", start),
+                    "end_address": format!("{:x}", end),
+                })
+            })
+            .collect()
+    };
+    Ok(json!({
+        "old": old_name,
+        "new": new_name,
+        "changed_count": changed_count,
+        "truncated": truncated,
+        "regions": regions,
+        "only_in_old": format_ranges(util::subtract_ranges(&old_coverage, &new_coverage)),
+        "only_in_new": format_ranges(util::subtract_ranges(&new_coverage, &old_coverage)),
+    }))
+}
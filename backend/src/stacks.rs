@@ -0,0 +1,203 @@
+// Thread stacks of the target and a scan restricted to them. Locals and return addresses
+// on a stack lead straight to the code using a value, so this is often quicker than a full
+// scan. Only the live part of each stack, from the stack pointer up, is searched.
+use crate::native_bridge;
+use crate::request::StackScanRequest;
+use crate::util;
+use serde_json::{json, Value};
+
+const DEFAULT_MAX_RESULTS: usize = 10_000;
+
+pub struct Stack {
+    pub thread_id: u64,
+    pub sp: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+enum Needle {
+    Bytes(Vec<u8>),
+    // Pointer-sized slots holding an address inside one of the ranges
+    Pointer(Vec<(usize, usize)>),
+}
+
+pub fn enumerate(pid: i32) -> Result<Vec<Stack>, String> {
+    let output = native_bridge::enum_thread_stacks(pid)?;
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let thread_id = fields.next()?.parse().ok()?;
+            let mut hex = || usize::from_str_radix(fields.next()?, 16).ok();
+            Some(Stack {
+                thread_id,
+                sp: hex()?,
+                start: hex()?,
+                end: hex()?,
+            })
+        })
+        .collect())
+}
+
+// File-backed mappings as (start, end, path). Module sizes only cover the first mapping on
+// some platforms, while return addresses point into the code mapping after it.
+pub fn file_mappings(pid: i32) -> Vec<(usize, usize, String)> {
+    let mut mappings: Vec<(usize, usize, String)> = native_bridge::enum_regions(pid)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|region| {
+            let start =
+                usize::from_str_radix(region["start_address"].as_str().unwrap_or(""), 16).ok()?;
+            let end =
+                usize::from_str_radix(region["end_address"].as_str().unwrap_or(""), 16).ok()?;
+            let path = region["file_path"].as_str().unwrap_or("");
+            (!path.is_empty()).then(|| (start, end, path.to_string()))
+        })
+        .collect();
+    mappings.sort_by_key(|&(start, _, _)| start);
+    mappings
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+fn needle(
+    request: &StackScanRequest,
+    mappings: &[(usize, usize, String)],
+) -> Result<Needle, String> {
+    if let Some(module) = &request.module {
+        let ranges: Vec<(usize, usize)> = mappings
+            .iter()
+            .filter(|(_, _, path)| path == module || file_name(path) == module)
+            .map(|&(start, end, _)| (start, end))
+            .collect();
+        if ranges.is_empty() {
+            return Err(format!("Module '{}' is not loaded", module));
+        }
+        return Ok(Needle::Pointer(util::merge_ranges(&ranges)));
+    }
+    if let Some(range) = request.pointer_range {
+        return Ok(Needle::Pointer(vec![range]));
+    }
+    match &request.value {
+        Some(value) => {
+            let data_type = request.data_type.as_deref().unwrap_or("int32");
+            let bytes = util::encode_value(data_type, value)?;
+            if bytes.is_empty() {
+                return Err("value must not be empty".to_string());
+            }
+            Ok(Needle::Bytes(bytes))
+        }
+        None => Err("Set one of value, pointer_range or module".to_string()),
+    }
+}
+
+// Module name and offset from its first mapping, for annotating return addresses
+pub fn locate(mappings: &[(usize, usize, String)], address: usize) -> Option<Value> {
+    let (_, _, path) = mappings
+        .iter()
+        .find(|&&(start, end, _)| address >= start && address < end)?;
+    let base = mappings.iter().find(|(_, _, other)| other == path)?.0;
+    Some(json!({
+        "module": file_name(path),
+        "offset": address - base,
+    }))
+}
+
+pub fn scan(pid: i32, request: &StackScanRequest) -> Result<Value, String> {
+    // Only pointer scans need the mappings, to resolve modules and annotate matches
+    let mappings = if request.module.is_some() || request.pointer_range.is_some() {
+        file_mappings(pid)
+    } else {
+        Vec::new()
+    };
+    let needle = needle(request, &mappings)?;
+    let pointer_size = util::pointer_size();
+    let align = request.align.unwrap_or(match &needle {
+        Needle::Bytes(bytes) => bytes.len().clamp(1, pointer_size),
+        Needle::Pointer(_) => pointer_size,
+    });
+    if align == 0 {
+        return Err("align must be at least 1".to_string());
+    }
+    let max_results = request.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+
+    let stacks: Vec<Stack> = enumerate(pid)?
+        .into_iter()
+        .filter(|stack| {
+            request
+                .threads
+                .as_ref()
+                .is_none_or(|threads| threads.contains(&stack.thread_id))
+        })
+        .collect();
+
+    let mut matches = Vec::new();
+    let mut truncated = false;
+    'stacks: for stack in &stacks {
+        let start = stack.sp.max(stack.start);
+        if start >= stack.end {
+            continue;
+        }
+        let mut buffer = vec![0u8; stack.end - start];
+        let nread = match native_bridge::read_process_memory(
+            pid,
+            start as *mut libc::c_void,
+            buffer.len(),
+            &mut buffer,
+        ) {
+            Ok(nread) if nread > 0 => nread as usize,
+            _ => continue,
+        };
+        buffer.truncate(nread);
+
+        // Offsets are aligned to the address, not to the (possibly unaligned) stack pointer
+        let mut offset = (align - start % align) % align;
+        while offset < buffer.len() {
+            let address = start + offset;
+            // Pointer matches carry the pointer; byte matches have nothing to add
+            let found = match &needle {
+                Needle::Bytes(bytes) => buffer[offset..].starts_with(bytes).then_some(None),
+                Needle::Pointer(ranges) => buffer
+                    .get(offset..offset + pointer_size)
+                    .and_then(util::pointer_from_bytes)
+                    .filter(|&pointer| util::in_ranges(ranges, pointer))
+                    .map(Some),
+            };
+            if let Some(pointer) = found {
+                if matches.len() >= max_results {
+                    truncated = true;
+                    break 'stacks;
+                }
+                let mut entry = json!({
+                    "thread_id": stack.thread_id,
+                    "address": address,
+                    "sp_offset": address - stack.sp,
+                });
+                if let Some(pointer) = pointer {
+                    entry["pointer"] = json!(pointer);
+                    if let Some(location) = locate(&mappings, pointer) {
+                        entry["target"] = location;
+                    }
+                }
+                matches.push(entry);
+            }
+            offset += align;
+        }
+    }
+
+    Ok(json!({
+        "stacks": stacks
+            .iter()
+            .map(|stack| json!({
+                "thread_id": stack.thread_id,
+                "sp": stack.sp,
+                "start": stack.start,
+                "end": stack.end,
+            }))
+            .collect::<Vec<_>>(),
+        "matches": matches,
+        "truncated": truncated,
+    }))
+}
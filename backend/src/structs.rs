@@ -0,0 +1,182 @@
+use crate::native_bridge;
+use crate::util;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+const MAX_NESTING_DEPTH: usize = 16;
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct StructField {
+    pub name: String,
+    pub offset: usize,
+    pub data_type: String,
+    // Byte length for utf-8/utf-16/bytes fields
+    pub size: Option<usize>,
+    // Element count for array fields
+    pub count: Option<usize>,
+    // Layout name when data_type is "struct"
+    pub struct_name: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct StructLayout {
+    pub name: String,
+    pub fields: Vec<StructField>,
+    pub size: Option<usize>,
+}
+
+lazy_static! {
+    static ref STRUCT_LAYOUTS: RwLock<HashMap<String, StructLayout>> = RwLock::new(HashMap::new());
+}
+
+pub fn register_layout(layout: StructLayout) -> Result<(), String> {
+    if layout.name.is_empty() {
+        return Err("Struct name is empty".to_string());
+    }
+    let layouts = STRUCT_LAYOUTS.read().unwrap();
+    for field in &layout.fields {
+        if field.data_type == "struct" {
+            match &field.struct_name {
+                Some(name) if name == &layout.name => {
+                    return Err(format!("Field '{}' embeds its own struct", field.name))
+                }
+                Some(name) if !layouts.contains_key(name) => {
+                    return Err(format!(
+                        "Unknown struct '{}' in field '{}'",
+                        name, field.name
+                    ))
+                }
+                Some(_) => {}
+                None => return Err(format!("Field '{}' is missing struct_name", field.name)),
+            }
+        } else if util::data_type_size(&field.data_type).is_none() && field.size.is_none() {
+            return Err(format!(
+                "Field '{}' of type '{}' requires a size",
+                field.name, field.data_type
+            ));
+        }
+    }
+    drop(layouts);
+    STRUCT_LAYOUTS
+        .write()
+        .unwrap()
+        .insert(layout.name.clone(), layout);
+    Ok(())
+}
+
+pub fn remove_layout(name: &str) -> bool {
+    STRUCT_LAYOUTS.write().unwrap().remove(name).is_some()
+}
+
+pub fn list_layouts() -> Vec<StructLayout> {
+    let layouts = STRUCT_LAYOUTS.read().unwrap();
+    let mut result: Vec<StructLayout> = layouts.values().cloned().collect();
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+    result
+}
+
+fn element_size(
+    field: &StructField,
+    layouts: &HashMap<String, StructLayout>,
+    depth: usize,
+) -> Option<usize> {
+    if field.data_type == "struct" {
+        let layout = layouts.get(field.struct_name.as_ref()?)?;
+        layout_size(layout, layouts, depth + 1)
+    } else {
+        field
+            .size
+            .or_else(|| util::data_type_size(&field.data_type))
+    }
+}
+
+fn layout_size(
+    layout: &StructLayout,
+    layouts: &HashMap<String, StructLayout>,
+    depth: usize,
+) -> Option<usize> {
+    if depth > MAX_NESTING_DEPTH {
+        return None;
+    }
+    if let Some(size) = layout.size {
+        return Some(size);
+    }
+    let mut size = 0;
+    for field in &layout.fields {
+        let element = element_size(field, layouts, depth)?;
+        size = size.max(field.offset + element * field.count.unwrap_or(1));
+    }
+    Some(size)
+}
+
+fn decode_layout(
+    layout: &StructLayout,
+    layouts: &HashMap<String, StructLayout>,
+    bytes: &[u8],
+    depth: usize,
+) -> Value {
+    let mut object = Map::new();
+    for field in &layout.fields {
+        let element = match element_size(field, layouts, depth) {
+            Some(element) => element,
+            None => {
+                object.insert(field.name.clone(), Value::Null);
+                continue;
+            }
+        };
+        let decode_one = |offset: usize| -> Value {
+            if offset + element > bytes.len() {
+                return Value::Null;
+            }
+            let slice = &bytes[offset..offset + element];
+            if field.data_type == "struct" {
+                match field
+                    .struct_name
+                    .as_ref()
+                    .and_then(|name| layouts.get(name))
+                {
+                    Some(nested) => decode_layout(nested, layouts, slice, depth + 1),
+                    None => Value::Null,
+                }
+            } else {
+                util::decode_value(&field.data_type, slice).unwrap_or(Value::Null)
+            }
+        };
+        let value = match field.count {
+            Some(count) => Value::Array(
+                (0..count)
+                    .map(|i| decode_one(field.offset + i * element))
+                    .collect(),
+            ),
+            None => decode_one(field.offset),
+        };
+        object.insert(field.name.clone(), value);
+    }
+    Value::Object(object)
+}
+
+pub fn read_struct(pid: i32, address: usize, name: &str) -> Result<Value, String> {
+    let layouts = STRUCT_LAYOUTS.read().unwrap();
+    let layout = layouts
+        .get(name)
+        .ok_or_else(|| format!("Unknown struct '{}'", name))?;
+    let size = layout_size(layout, &layouts, 0)
+        .ok_or_else(|| format!("Failed to compute size of struct '{}'", name))?;
+
+    let mut buffer = vec![0u8; size];
+    match native_bridge::read_process_memory(pid, address as *mut libc::c_void, size, &mut buffer) {
+        Ok(nread) if nread > 0 => buffer.truncate(nread as usize),
+        _ => return Err(format!("Failed to read memory at 0x{:x}", address)),
+    }
+
+    Ok(json!({
+        "address": address,
+        "name": name,
+        "size": size,
+        "fields": decode_layout(layout, &layouts, &buffer, 0)
+    }))
+}
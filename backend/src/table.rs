@@ -0,0 +1,358 @@
+use crate::native_bridge;
+use crate::stacks;
+use crate::util;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct TableEntry {
+    #[serde(default)]
+    pub id: u64,
+    #[serde(default)]
+    pub description: String,
+    pub address: usize,
+    pub data_type: String,
+    // Module file name and offset for module-relative entries
+    pub module: Option<String>,
+    pub module_offset: Option<usize>,
+    // Named values that can be written to the entry in one call
+    #[serde(default)]
+    pub presets: Vec<Preset>,
+    // AOB signature (hex bytes, ?? for wildcards) found again by /table/relocate after an
+    // update moves the code, and the distance from the match to the address
+    pub signature: Option<String>,
+    pub signature_offset: Option<i64>,
+    // Pointer chain from `address`: each offset is added to the pointer read at the previous
+    // step, e.g. [0x18, 0x40] for [[address] + 0x18] + 0x40
+    pub pointer_offsets: Option<Vec<i64>>,
+}
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct Preset {
+    pub name: String,
+    pub buffer: Vec<u8>,
+}
+
+struct TableState {
+    entries: Vec<TableEntry>,
+    next_id: u64,
+    last_rebase: Vec<Value>,
+}
+
+lazy_static! {
+    static ref TABLE: RwLock<TableState> = RwLock::new(TableState {
+        entries: Vec::new(),
+        next_id: 1,
+        last_rebase: Vec::new(),
+    });
+}
+
+fn module_file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn find_module<'a>(modules: &'a [Value], name: &str) -> Option<&'a Value> {
+    modules.iter().find(|module| {
+        module["modulename"]
+            .as_str()
+            .map(|path| module_file_name(path) == name)
+            .unwrap_or(false)
+    })
+}
+
+fn find_containing_module(modules: &[Value], address: usize) -> Option<(String, usize)> {
+    modules.iter().find_map(|module| {
+        let base = module["base"].as_u64()? as usize;
+        let size = module["size"].as_i64()? as usize;
+        if address >= base && address < base + size {
+            Some((
+                module_file_name(module["modulename"].as_str()?),
+                address - base,
+            ))
+        } else {
+            None
+        }
+    })
+}
+
+pub fn list_entries() -> Vec<TableEntry> {
+    TABLE.read().unwrap().entries.clone()
+}
+
+pub fn add_entry(pid: Option<i32>, mut entry: TableEntry) -> TableEntry {
+    // Record module-relative placement so the entry survives ASLR on the next attach
+    if entry.module.is_none() {
+        if let Some(pid) = pid {
+            if let Ok(modules) = native_bridge::enum_modules(pid) {
+                if let Some((module, offset)) = find_containing_module(&modules, entry.address) {
+                    entry.module = Some(module);
+                    entry.module_offset = Some(offset);
+                }
+            }
+        }
+    }
+    let mut table = TABLE.write().unwrap();
+    entry.id = table.next_id;
+    table.next_id += 1;
+    table.entries.push(entry.clone());
+    entry
+}
+
+pub fn remove_entry(id: u64) -> bool {
+    let mut table = TABLE.write().unwrap();
+    let before = table.entries.len();
+    table.entries.retain(|entry| entry.id != id);
+    table.entries.len() != before
+}
+
+// Adds a preset to an entry, replacing any preset with the same name.
+pub fn set_preset(id: u64, preset: Preset) -> Result<TableEntry, String> {
+    if preset.buffer.is_empty() {
+        return Err("Preset buffer must not be empty".to_string());
+    }
+    let mut table = TABLE.write().unwrap();
+    let entry = table
+        .entries
+        .iter_mut()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| format!("Unknown entry id {}", id))?;
+    match entry.presets.iter_mut().find(|p| p.name == preset.name) {
+        Some(existing) => *existing = preset,
+        None => entry.presets.push(preset),
+    }
+    Ok(entry.clone())
+}
+
+pub fn remove_preset(id: u64, name: &str) -> Result<TableEntry, String> {
+    let mut table = TABLE.write().unwrap();
+    let entry = table
+        .entries
+        .iter_mut()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| format!("Unknown entry id {}", id))?;
+    let before = entry.presets.len();
+    entry.presets.retain(|preset| preset.name != name);
+    if entry.presets.len() == before {
+        return Err(format!("Unknown preset '{}'", name));
+    }
+    Ok(entry.clone())
+}
+
+// Entry by id, or else by description ignoring case, as a voice front end would name it
+pub fn find_entry(id: Option<u64>, description: Option<&str>) -> Result<TableEntry, String> {
+    let table = TABLE.read().unwrap();
+    let found = match (id, description) {
+        (Some(id), _) => table.entries.iter().find(|entry| entry.id == id),
+        (None, Some(description)) => table
+            .entries
+            .iter()
+            .find(|entry| entry.description.eq_ignore_ascii_case(description.trim())),
+        (None, None) => return Err("id or description is required".to_string()),
+    };
+    found
+        .cloned()
+        .ok_or_else(|| "No matching table entry".to_string())
+}
+
+// Address of the entry's value, following its pointer chain if it has one
+pub fn resolve_address(pid: i32, entry: &TableEntry) -> Result<usize, String> {
+    let mut address = entry.address as u64;
+    for offset in entry.pointer_offsets.iter().flatten() {
+        let pointer = util::read_pointer(pid, address)?;
+        if pointer == 0 {
+            return Err(format!("Null pointer at {:#x}", address));
+        }
+        address = pointer.wrapping_add_signed(*offset);
+    }
+    Ok(address as usize)
+}
+
+// Address and bytes to write for a preset of an entry, following its pointer chain in `pid`.
+pub fn preset_write(pid: i32, id: u64, name: &str) -> Result<(usize, Vec<u8>), String> {
+    let entry = find_entry(Some(id), None).map_err(|_| format!("Unknown entry id {}", id))?;
+    let buffer = entry
+        .presets
+        .iter()
+        .find(|preset| preset.name == name)
+        .map(|preset| preset.buffer.clone())
+        .ok_or_else(|| format!("Unknown preset '{}'", name))?;
+    Ok((resolve_address(pid, &entry)?, buffer))
+}
+
+pub fn rebase_entries(pid: i32) -> Vec<Value> {
+    let modules = native_bridge::enum_modules(pid).unwrap_or_default();
+    let mut table = TABLE.write().unwrap();
+    let mut report = Vec::new();
+
+    for entry in table.entries.iter_mut() {
+        let (module, offset) = match (&entry.module, entry.module_offset) {
+            (Some(module), Some(offset)) => (module.clone(), offset),
+            _ => {
+                report.push(json!({
+                    "id": entry.id,
+                    "description": entry.description,
+                    "resolved": false,
+                    "reason": "Entry is not module-relative"
+                }));
+                continue;
+            }
+        };
+        match find_module(&modules, &module).and_then(|m| m["base"].as_u64()) {
+            Some(base) => {
+                let old_address = entry.address;
+                let new_address = base as usize + offset;
+                entry.address = new_address;
+                report.push(json!({
+                    "id": entry.id,
+                    "description": entry.description,
+                    "module": module,
+                    "old_address": old_address,
+                    "new_address": new_address,
+                    "slide": new_address as i64 - old_address as i64,
+                    "resolved": true
+                }));
+            }
+            None => {
+                report.push(json!({
+                    "id": entry.id,
+                    "description": entry.description,
+                    "module": module,
+                    "resolved": false,
+                    "reason": format!("Module '{}' is not loaded", module)
+                }));
+            }
+        }
+    }
+
+    table.last_rebase = report.clone();
+    report
+}
+
+// Mappings of a module as (start, bytes), read once per relocation
+fn read_module(
+    pid: i32,
+    mappings: &[(usize, usize, String)],
+    module: &str,
+) -> Vec<(usize, Vec<u8>)> {
+    mappings
+        .iter()
+        .filter(|(_, _, path)| module_file_name(path) == module)
+        .filter_map(|&(start, end, _)| {
+            let mut buffer = vec![0u8; end - start];
+            let nread = native_bridge::read_process_memory(
+                pid,
+                start as *mut libc::c_void,
+                buffer.len(),
+                &mut buffer,
+            )
+            .ok()?;
+            buffer.truncate(nread.max(0) as usize);
+            Some((start, buffer))
+        })
+        .collect()
+}
+
+fn relocate_entry(
+    pid: i32,
+    entry: &mut TableEntry,
+    modules: &[Value],
+    mappings: &[(usize, usize, String)],
+    module_memory: &mut HashMap<String, Vec<(usize, Vec<u8>)>>,
+) -> Result<Value, String> {
+    let signature = util::parse_signature(entry.signature.as_deref().unwrap_or(""))?;
+    let module = entry
+        .module
+        .clone()
+        .ok_or("Entry has no module to search")?;
+    let base = find_module(modules, &module)
+        .and_then(|m| m["base"].as_u64())
+        .ok_or_else(|| format!("Module '{}' is not loaded", module))? as usize;
+    let memory = module_memory
+        .entry(module.clone())
+        .or_insert_with(|| read_module(pid, mappings, &module));
+    let matches: Vec<usize> = memory
+        .iter()
+        .flat_map(|(start, buffer)| {
+            util::find_signature(buffer, &signature)
+                .into_iter()
+                .map(move |offset| start + offset)
+        })
+        .collect();
+    let found = match matches.as_slice() {
+        [found] => *found,
+        [] => return Err("Signature not found".to_string()),
+        _ => {
+            return Err(format!(
+                "Signature matches {} places; make it longer",
+                matches.len()
+            ))
+        }
+    };
+
+    let address = (found as i64 + entry.signature_offset.unwrap_or(0)) as usize;
+    let offset = address
+        .checked_sub(base)
+        .ok_or("Relocated address is below the module base")?;
+    let report = json!({
+        "old_address": entry.address,
+        "new_address": address,
+        "old_module_offset": entry.module_offset,
+        "new_module_offset": offset,
+        "moved": entry.module_offset != Some(offset),
+    });
+    entry.address = address;
+    entry.module_offset = Some(offset);
+    Ok(report)
+}
+
+// Finds each entry's signature again in its module and moves the entry to the match. A
+// signature must match exactly once; entries with none or several matches are left alone.
+pub fn relocate_entries(pid: i32, ids: Option<&[u64]>) -> Vec<Value> {
+    let modules = native_bridge::enum_modules(pid).unwrap_or_default();
+    let mappings = stacks::file_mappings(pid);
+    let mut module_memory = HashMap::new();
+    let mut table = TABLE.write().unwrap();
+
+    table
+        .entries
+        .iter_mut()
+        .filter(|entry| entry.signature.is_some())
+        .filter(|entry| ids.is_none_or(|ids| ids.contains(&entry.id)))
+        .map(|entry| {
+            let mut report = json!({
+                "id": entry.id,
+                "description": entry.description,
+                "module": entry.module,
+            });
+            match relocate_entry(pid, entry, &modules, &mappings, &mut module_memory) {
+                Ok(result) => {
+                    report["relocated"] = json!(true);
+                    for (key, value) in result.as_object().into_iter().flatten() {
+                        report[key] = value.clone();
+                    }
+                }
+                Err(e) => {
+                    report["relocated"] = json!(false);
+                    report["reason"] = json!(e);
+                }
+            }
+            report
+        })
+        .collect()
+}
+
+pub fn last_rebase_report() -> Vec<Value> {
+    TABLE.read().unwrap().last_rebase.clone()
+}
+
+pub fn is_empty() -> bool {
+    TABLE.read().unwrap().entries.is_empty()
+}
@@ -0,0 +1,144 @@
+// Matches plain text in utf-8/utf-16 memory with case folding and Unicode normalization,
+// decoding characters in place instead of going through a generated regex.
+use unicode_normalization::UnicodeNormalization;
+
+pub struct TextMatcher {
+    utf16: bool,
+    case_insensitive: bool,
+    // One character sequence per accepted form of the query (composed, decomposed)
+    variants: Vec<Vec<char>>,
+    // Bytes a match can start with, to skip most positions without decoding
+    first_bytes: [bool; 256],
+}
+
+fn fold(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(folded), None) => folded,
+        _ => c,
+    }
+}
+
+fn decode_utf8(bytes: &[u8]) -> Option<(char, usize)> {
+    let window = &bytes[..bytes.len().min(4)];
+    let valid = match std::str::from_utf8(window) {
+        Ok(text) => text,
+        Err(e) => std::str::from_utf8(&window[..e.valid_up_to()]).ok()?,
+    };
+    valid.chars().next().map(|c| (c, c.len_utf8()))
+}
+
+fn decode_utf16(bytes: &[u8]) -> Option<(char, usize)> {
+    let units = bytes
+        .chunks_exact(2)
+        .take(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]));
+    let c = char::decode_utf16(units).next()?.ok()?;
+    Some((c, c.len_utf16() * 2))
+}
+
+impl TextMatcher {
+    pub fn new(
+        data_type: &str,
+        text: &str,
+        case_insensitive: bool,
+        normalize: bool,
+        null_terminated: bool,
+    ) -> Result<Self, String> {
+        let utf16 = match data_type {
+            "utf-8" => false,
+            "utf-16" => true,
+            _ => return Err(format!("text is not supported for data type {}", data_type)),
+        };
+        if text.is_empty() {
+            return Err("text must not be empty".to_string());
+        }
+
+        let mut forms: Vec<String> = vec![text.to_string()];
+        if normalize {
+            forms = vec![text.nfc().collect(), text.nfd().collect()];
+            forms.dedup();
+        }
+        let variants: Vec<Vec<char>> = forms
+            .iter()
+            .map(|form| {
+                let mut chars: Vec<char> = form
+                    .chars()
+                    .map(|c| if case_insensitive { fold(c) } else { c })
+                    .collect();
+                if null_terminated {
+                    chars.push('\0');
+                }
+                chars
+            })
+            .collect();
+
+        let mut first_bytes = [false; 256];
+        let mut mark = |c: char| {
+            let byte = if utf16 {
+                (c.encode_utf16(&mut [0u16; 2])[0] & 0xff) as u8
+            } else {
+                c.encode_utf8(&mut [0u8; 4]).as_bytes()[0]
+            };
+            first_bytes[byte as usize] = true;
+        };
+        if case_insensitive {
+            // Any character folding to a first character can start a match
+            let firsts: Vec<char> = variants.iter().map(|chars| chars[0]).collect();
+            (0..=char::MAX as u32)
+                .filter_map(char::from_u32)
+                .filter(|c| firsts.contains(&fold(*c)))
+                .for_each(&mut mark);
+        } else {
+            variants.iter().for_each(|chars| mark(chars[0]));
+        }
+
+        Ok(TextMatcher {
+            utf16,
+            case_insensitive,
+            variants,
+            first_bytes,
+        })
+    }
+
+    fn match_at(&self, buffer: &[u8], start: usize) -> Option<usize> {
+        'variants: for chars in &self.variants {
+            let mut offset = start;
+            for &expected in chars {
+                let decoded = if self.utf16 {
+                    decode_utf16(&buffer[offset..])
+                } else {
+                    decode_utf8(&buffer[offset..])
+                };
+                let Some((c, len)) = decoded else {
+                    continue 'variants;
+                };
+                let c = if self.case_insensitive { fold(c) } else { c };
+                if c != expected {
+                    continue 'variants;
+                }
+                offset += len;
+            }
+            return Some(offset);
+        }
+        None
+    }
+
+    // Non-overlapping matches in `buffer` as [start, end) byte offsets.
+    pub fn find_iter(&self, buffer: &[u8]) -> Vec<(usize, usize)> {
+        let step = if self.utf16 { 2 } else { 1 };
+        let mut matches = Vec::new();
+        let mut start = 0;
+        while start < buffer.len() {
+            if self.first_bytes[buffer[start] as usize] {
+                if let Some(end) = self.match_at(buffer, start) {
+                    matches.push((start, end));
+                    start = end;
+                    continue;
+                }
+            }
+            start += step;
+        }
+        matches
+    }
+}
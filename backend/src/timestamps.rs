@@ -0,0 +1,116 @@
+// Scan for values that look like a recent point in time: unix epoch seconds or milliseconds
+// close to now, or readings of the monotonic clock. Cooldowns and timers usually store when
+// they started or when they end, so this finds them without knowing the exact value.
+use crate::native_bridge;
+use crate::request::TimestampScanRequest;
+use crate::util;
+use rayon::prelude::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CHUNK_SIZE: usize = 16 * 1024 * 1024;
+const DAY_SECONDS: u64 = 24 * 60 * 60;
+// Uptime can be short, so a wide window around a tick count would take in small integers
+const TICKS_WINDOW_MILLIS: u64 = 10 * 60 * 1000;
+
+pub struct Timestamps {
+    pub data_type: String,
+    // Reference time the values were compared against, in the unit of the kind
+    pub now: f64,
+    pub positions: Vec<(usize, String)>,
+}
+
+#[cfg(unix)]
+fn monotonic_millis() -> Result<f64, String> {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    if unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut time) } != 0 {
+        return Err("Failed to read the monotonic clock".to_string());
+    }
+    Ok(time.tv_sec as f64 * 1000.0 + time.tv_nsec as f64 / 1_000_000.0)
+}
+
+#[cfg(not(unix))]
+fn monotonic_millis() -> Result<f64, String> {
+    Err("ticks scans need now on this platform".to_string())
+}
+
+// Current time and the default window, both in the unit of `kind`
+fn reference(kind: &str) -> Result<(f64, u64), String> {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?;
+    match kind {
+        "unix_seconds" => Ok((since_epoch.as_secs_f64(), DAY_SECONDS)),
+        "unix_millis" => Ok((since_epoch.as_millis() as f64, DAY_SECONDS * 1000)),
+        "ticks" => Ok((monotonic_millis()?, TICKS_WINDOW_MILLIS)),
+        _ => Err(format!(
+            "Unknown kind '{}'; expected unix_seconds, unix_millis or ticks",
+            kind
+        )),
+    }
+}
+
+pub fn scan(pid: i32, request: &TimestampScanRequest) -> Result<Timestamps, String> {
+    let kind = request.kind.as_deref().unwrap_or("unix_seconds");
+    let (clock, default_within) = reference(kind)?;
+    let now = request.now.unwrap_or(clock);
+    let within = request.within.unwrap_or(default_within) as f64;
+    let data_type = request.data_type.clone().unwrap_or_else(|| match kind {
+        "unix_seconds" => "int32".to_string(),
+        _ => "int64".to_string(),
+    });
+    let size = match data_type.as_str() {
+        "int32" | "uint32" | "int64" | "uint64" | "float" | "double" => {
+            util::data_type_size(&data_type).unwrap_or(4)
+        }
+        _ => return Err(format!("Timestamps cannot be stored as {}", data_type)),
+    };
+    let align = request.align.unwrap_or(size);
+    if align == 0 {
+        return Err("align must be at least 1".to_string());
+    }
+
+    let mut positions: Vec<(usize, String)> = request
+        .address_ranges
+        .par_iter()
+        .flat_map(|&(start, end)| {
+            let mut found = Vec::new();
+            let mut buffer = vec![0u8; CHUNK_SIZE.min(end.saturating_sub(start))];
+            let mut chunk_start = start;
+            while chunk_start < end {
+                let length = CHUNK_SIZE.min(end - chunk_start);
+                let nread = native_bridge::read_process_memory(
+                    pid,
+                    chunk_start as *mut libc::c_void,
+                    length,
+                    &mut buffer,
+                );
+                if nread.is_ok() {
+                    let first = (align - chunk_start % align) % align;
+                    for offset in (first..length.saturating_sub(size - 1)).step_by(align) {
+                        let bytes = &buffer[offset..offset + size];
+                        let Some(value) =
+                            util::decode_value(&data_type, bytes).and_then(|v| v.as_f64())
+                        else {
+                            continue;
+                        };
+                        if (value - now).abs() <= within {
+                            found.push((chunk_start + offset, hex::encode(bytes)));
+                        }
+                    }
+                }
+                chunk_start += length;
+            }
+            found
+        })
+        .collect();
+    positions.sort_by_key(|&(address, _)| address);
+
+    Ok(Timestamps {
+        data_type,
+        now,
+        positions,
+    })
+}
@@ -0,0 +1,71 @@
+// Thread-local storage blocks of the target. Engines keep per-player or per-worker state in
+// thread-locals, which region lists show as anonymous memory like any other, so /tls names
+// the blocks per thread and scans can be limited to them with tls_only.
+use crate::native_bridge;
+use crate::util;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+pub const DEFAULT_BLOCK_SIZE: usize = 0x1000;
+
+pub struct TlsBlock {
+    pub thread_id: u64,
+    // fs base or TPIDR_EL0 on Linux, the TEB on Windows, the pthread structure on Darwin
+    pub thread_pointer: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+pub fn enumerate(pid: i32, block_size: usize) -> Result<Vec<TlsBlock>, String> {
+    let output = native_bridge::enum_tls_blocks(pid, block_size)?;
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let thread_id = fields.next()?.parse().ok()?;
+            let mut hex = || usize::from_str_radix(fields.next()?, 16).ok();
+            Some(TlsBlock {
+                thread_id,
+                thread_pointer: hex()?,
+                start: hex()?,
+                end: hex()?,
+            })
+        })
+        .filter(|block| block.start < block.end)
+        .collect())
+}
+
+pub fn ranges(pid: i32) -> Result<Vec<(usize, usize)>, String> {
+    let blocks = enumerate(pid, DEFAULT_BLOCK_SIZE)?;
+    if blocks.is_empty() {
+        return Err("No thread-local storage blocks found".to_string());
+    }
+    let ranges: Vec<(usize, usize)> = blocks
+        .iter()
+        .map(|block| (block.start, block.end))
+        .collect();
+    Ok(util::merge_ranges(&ranges))
+}
+
+// One entry per thread, with its blocks
+pub fn report(blocks: &[TlsBlock]) -> Value {
+    let mut threads: BTreeMap<u64, (usize, Vec<Value>)> = BTreeMap::new();
+    for block in blocks {
+        threads
+            .entry(block.thread_id)
+            .or_insert_with(|| (block.thread_pointer, Vec::new()))
+            .1
+            .push(json!({
+                "start": block.start,
+                "end": block.end,
+            }));
+    }
+    json!(threads
+        .into_iter()
+        .map(|(thread_id, (thread_pointer, blocks))| json!({
+            "thread_id": thread_id,
+            "thread_pointer": thread_pointer,
+            "blocks": blocks,
+        }))
+        .collect::<Vec<_>>())
+}
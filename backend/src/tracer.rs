@@ -0,0 +1,118 @@
+use crate::native_bridge;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const DEFAULT_STEP_COUNT: usize = 32;
+const MAX_STEP_COUNT: usize = 4096;
+const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TraceRequest {
+    pub address: usize,
+    pub count: Option<usize>,
+    pub timeout_ms: Option<u64>,
+}
+
+struct Trace {
+    count: usize,
+    steps: Vec<Value>,
+    previous: Map<String, Value>,
+}
+
+lazy_static! {
+    static ref TRACE: Mutex<Option<Trace>> = Mutex::new(None);
+}
+
+fn registers(exception: &Value) -> Map<String, Value> {
+    exception
+        .as_object()
+        .map(|object| {
+            object
+                .iter()
+                .filter(|(key, _)| !matches!(key.as_str(), "instruction" | "memory" | "write"))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Called for every exception reported by the debugger. Returns true when the report
+// was consumed as a step of the running trace.
+pub fn record_step(exception: &Value) -> bool {
+    let mut trace = TRACE.lock().unwrap();
+    let Some(trace) = trace.as_mut().filter(|t| t.steps.len() < t.count) else {
+        return false;
+    };
+
+    let current = registers(exception);
+    // The first step carries the full register set, later ones only what changed
+    let changed: Map<String, Value> = current
+        .iter()
+        .filter(|(key, value)| key.as_str() != "pc" && trace.previous.get(*key) != Some(value))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    trace.steps.push(json!({
+        "index": trace.steps.len(),
+        "pc": exception["pc"],
+        "instruction": exception["instruction"],
+        "changed": changed,
+    }));
+    trace.previous = current;
+    true
+}
+
+pub async fn run(pid: i32, request: TraceRequest) -> Result<Value, String> {
+    let count = request
+        .count
+        .unwrap_or(DEFAULT_STEP_COUNT)
+        .clamp(1, MAX_STEP_COUNT);
+    {
+        let mut trace = TRACE.lock().unwrap();
+        if trace.is_some() {
+            return Err("A trace is already running".to_string());
+        }
+        *trace = Some(Trace {
+            count,
+            steps: Vec::new(),
+            previous: Map::new(),
+        });
+    }
+
+    // The breakpoint single-steps `count` instructions past the hit, reporting each one
+    if let Err(e) = native_bridge::set_breakpoint(pid, request.address, count as i32) {
+        TRACE.lock().unwrap().take();
+        return Err(format!("Failed to set breakpoint: {}", e));
+    }
+
+    let deadline =
+        Instant::now() + Duration::from_millis(request.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+    loop {
+        let done = TRACE
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_none_or(|t| t.steps.len() >= t.count);
+        if done || Instant::now() >= deadline {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    let trace = TRACE.lock().unwrap().take();
+    let steps = trace.map(|t| t.steps).unwrap_or_default();
+    let complete = steps.len() >= count;
+    if steps.is_empty() {
+        // Never hit, so the breakpoint is still armed
+        let _ = native_bridge::remove_breakpoint(request.address);
+    }
+    Ok(json!({
+        "address": request.address,
+        "requested": count,
+        "complete": complete,
+        "steps": steps,
+    }))
+}
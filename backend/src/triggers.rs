@@ -0,0 +1,188 @@
+// Named actions bound to trigger ids, so button mappers and macro pads can fire them
+// with a single call without knowing any addresses.
+use crate::batch::{self, BatchRequest, Operation};
+use crate::scheduler;
+use crate::table;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    // Starts freezing the value, or stops the freeze started by the previous trigger
+    FreezeToggle {
+        address: Option<usize>,
+        query: Option<String>,
+        buffer: Vec<u8>,
+        interval_ms: Option<u64>,
+    },
+    Write {
+        address: Option<usize>,
+        query: Option<String>,
+        buffer: Vec<u8>,
+    },
+    // Writes a named preset of a saved table entry
+    Preset {
+        entry_id: u64,
+        preset: String,
+    },
+    // Runs a list of batch operations, as sent to /batch
+    Batch {
+        operations: Vec<Operation>,
+        stop_on_error: Option<bool>,
+    },
+}
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct Trigger {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+struct BoundTrigger {
+    trigger: Trigger,
+    // Scheduler job of a running freeze toggle
+    freeze_job: Option<u64>,
+}
+
+lazy_static! {
+    static ref TRIGGERS: Mutex<BTreeMap<String, BoundTrigger>> = Mutex::new(BTreeMap::new());
+}
+
+fn to_json(bound: &BoundTrigger) -> Value {
+    let mut value = serde_json::to_value(&bound.trigger).unwrap_or(Value::Null);
+    if let Action::FreezeToggle { .. } = bound.trigger.action {
+        value["frozen"] = json!(bound.freeze_job.is_some_and(scheduler::is_running));
+    }
+    value
+}
+
+pub fn list() -> Vec<Value> {
+    TRIGGERS.lock().unwrap().values().map(to_json).collect()
+}
+
+// Binds or rebinds a trigger id. Rebinding stops a freeze the old binding started.
+pub fn bind(trigger: Trigger) -> Result<Value, String> {
+    if trigger.id.is_empty() {
+        return Err("id must not be empty".to_string());
+    }
+    let mut triggers = TRIGGERS.lock().unwrap();
+    if let Some(job) = triggers.get(&trigger.id).and_then(|bound| bound.freeze_job) {
+        scheduler::cancel(job);
+    }
+    let bound = BoundTrigger {
+        trigger,
+        freeze_job: None,
+    };
+    let value = to_json(&bound);
+    triggers.insert(bound.trigger.id.clone(), bound);
+    Ok(value)
+}
+
+pub fn unbind(id: &str) -> bool {
+    match TRIGGERS.lock().unwrap().remove(id) {
+        Some(bound) => {
+            if let Some(job) = bound.freeze_job {
+                scheduler::cancel(job);
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+fn run_single(pid: i32, operation: Operation, session: &str) -> Result<Value, String> {
+    let request = BatchRequest {
+        operations: vec![operation],
+        stop_on_error: None,
+    };
+    let mut result = batch::run(pid, &request, session)["results"][0].take();
+    match result["error"].as_str() {
+        Some(e) => Err(e.to_string()),
+        None => {
+            if let Some(entry) = result.as_object_mut() {
+                entry.remove("index");
+                entry.remove("op");
+                entry.remove("success");
+            }
+            Ok(result)
+        }
+    }
+}
+
+pub fn fire(pid: i32, id: &str, session: &str) -> Result<Value, String> {
+    let mut triggers = TRIGGERS.lock().unwrap();
+    let bound = triggers
+        .get_mut(id)
+        .ok_or_else(|| format!("Unknown trigger {}", id))?;
+    match bound.trigger.action.clone() {
+        Action::FreezeToggle {
+            address,
+            query,
+            buffer,
+            interval_ms,
+        } => {
+            if let Some(job) = bound.freeze_job.take() {
+                if scheduler::is_running(job) {
+                    scheduler::cancel(job);
+                    return Ok(json!({ "frozen": false, "job_id": job }));
+                }
+            }
+            let result = run_single(
+                pid,
+                Operation::Freeze {
+                    address,
+                    query,
+                    buffer,
+                    interval_ms,
+                },
+                session,
+            )?;
+            bound.freeze_job = result["id"].as_u64();
+            Ok(json!({ "frozen": true, "job_id": bound.freeze_job, "address": result["address"] }))
+        }
+        Action::Write {
+            address,
+            query,
+            buffer,
+        } => run_single(
+            pid,
+            Operation::Write {
+                address,
+                query,
+                buffer,
+            },
+            session,
+        ),
+        Action::Preset { entry_id, preset } => {
+            let (address, buffer) = table::preset_write(pid, entry_id, &preset)?;
+            run_single(
+                pid,
+                Operation::Write {
+                    address: Some(address),
+                    query: None,
+                    buffer,
+                },
+                session,
+            )
+        }
+        Action::Batch {
+            operations,
+            stop_on_error,
+        } => Ok(batch::run(
+            pid,
+            &BatchRequest {
+                operations,
+                stop_on_error,
+            },
+            session,
+        )),
+    }
+}
@@ -0,0 +1,196 @@
+// Establishes the desktop-to-device tunnel (adb forward or usbmuxd) so the device backend can be
+// proxied without manual port forwarding.
+use regex::Regex;
+use std::process::Command;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const USBMUX_VERSION: u32 = 1;
+const USBMUX_PLIST_MESSAGE: u32 = 8;
+
+pub enum TunnelSpec {
+    Adb(Option<String>),
+    Usbmux(Option<String>),
+}
+
+impl TunnelSpec {
+    // Accepts "adb", "adb:<serial>", "usbmux" or "usbmux:<udid>"
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (kind, device) = match spec.split_once(':') {
+            Some((kind, device)) => (kind, Some(device.to_string())),
+            None => (spec, None),
+        };
+        match kind {
+            "adb" => Ok(TunnelSpec::Adb(device)),
+            "usbmux" => Ok(TunnelSpec::Usbmux(device)),
+            _ => Err(format!("Unknown tunnel type '{}'", kind)),
+        }
+    }
+}
+
+pub async fn establish(spec: &TunnelSpec, remote_port: u16) -> Result<u16, String> {
+    match spec {
+        TunnelSpec::Adb(serial) => adb_forward(serial.as_deref(), remote_port),
+        TunnelSpec::Usbmux(udid) => usbmux_forward(udid.clone(), remote_port).await,
+    }
+}
+
+fn adb_forward(serial: Option<&str>, remote_port: u16) -> Result<u16, String> {
+    let mut command = Command::new("adb");
+    if let Some(serial) = serial {
+        command.args(["-s", serial]);
+    }
+    // "tcp:0" lets adb pick a free local port and print it
+    let output = command
+        .args(["forward", "tcp:0", &format!("tcp:{}", remote_port)])
+        .output()
+        .map_err(|e| format!("Failed to run adb: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "adb forward failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .map_err(|_| "adb forward did not report a local port".to_string())
+}
+
+fn usbmux_plist(body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \
+         \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\"><dict>\
+         <key>ClientVersionString</key><string>memory-server</string>\
+         <key>ProgName</key><string>memory-server</string>{}</dict></plist>",
+        body
+    )
+}
+
+async fn usbmux_exchange<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    body: &str,
+) -> Result<String, String> {
+    let payload = usbmux_plist(body);
+    let mut packet = Vec::with_capacity(16 + payload.len());
+    packet.extend_from_slice(&((16 + payload.len()) as u32).to_le_bytes());
+    packet.extend_from_slice(&USBMUX_VERSION.to_le_bytes());
+    packet.extend_from_slice(&USBMUX_PLIST_MESSAGE.to_le_bytes());
+    packet.extend_from_slice(&1u32.to_le_bytes());
+    packet.extend_from_slice(payload.as_bytes());
+    stream.write_all(&packet).await.map_err(|e| e.to_string())?;
+
+    let mut header = [0u8; 16];
+    stream
+        .read_exact(&mut header)
+        .await
+        .map_err(|e| e.to_string())?;
+    let length = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+    let mut response = vec![0u8; length.saturating_sub(16)];
+    stream
+        .read_exact(&mut response)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(String::from_utf8_lossy(&response).into_owned())
+}
+
+#[cfg(unix)]
+async fn usbmux_connect() -> Result<tokio::net::UnixStream, String> {
+    tokio::net::UnixStream::connect("/var/run/usbmuxd")
+        .await
+        .map_err(|e| format!("Failed to connect to usbmuxd: {}", e))
+}
+
+#[cfg(not(unix))]
+async fn usbmux_connect() -> Result<tokio::net::TcpStream, String> {
+    tokio::net::TcpStream::connect("127.0.0.1:27015")
+        .await
+        .map_err(|e| format!("Failed to connect to usbmuxd: {}", e))
+}
+
+async fn usbmux_device_id(udid: Option<&str>) -> Result<u64, String> {
+    let mut stream = usbmux_connect().await?;
+    let response = usbmux_exchange(
+        &mut stream,
+        "<key>MessageType</key><string>ListDevices</string>",
+    )
+    .await?;
+
+    // Each device dictionary lists its DeviceID before the SerialNumber in its properties
+    let device_id = Regex::new(r"<key>DeviceID</key>\s*<integer>(\d+)</integer>").unwrap();
+    let serial = Regex::new(r"<key>SerialNumber</key>\s*<string>([^<]+)</string>").unwrap();
+    let devices: Vec<(u64, String)> = serial
+        .captures_iter(&response)
+        .filter_map(|captures| {
+            let position = captures.get(0)?.start();
+            let id = device_id
+                .captures_iter(&response[..position])
+                .last()?
+                .get(1)?
+                .as_str()
+                .parse()
+                .ok()?;
+            Some((id, captures[1].to_string()))
+        })
+        .collect();
+
+    devices
+        .iter()
+        .find(|(_, serial)| udid.is_none_or(|udid| serial == udid))
+        .map(|(id, _)| *id)
+        .ok_or_else(|| match udid {
+            Some(udid) => format!("Device {} is not connected", udid),
+            None => "No device connected through usbmuxd".to_string(),
+        })
+}
+
+async fn usbmux_forward(udid: Option<String>, remote_port: u16) -> Result<u16, String> {
+    let device_id = usbmux_device_id(udid.as_deref()).await?;
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| e.to_string())?;
+    let local_port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let connect_body = format!(
+        "<key>MessageType</key><string>Connect</string>\
+         <key>DeviceID</key><integer>{}</integer>\
+         <key>PortNumber</key><integer>{}</integer>",
+        device_id,
+        // usbmuxd expects the port in network byte order
+        remote_port.swap_bytes() as u32
+    );
+
+    let connect_result = Regex::new(r"<key>Number</key>\s*<integer>0</integer>").unwrap();
+
+    tokio::spawn(async move {
+        loop {
+            let (mut client, _) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    log::error!("usbmux tunnel accept failed: {}", e);
+                    continue;
+                }
+            };
+            let connect_body = connect_body.clone();
+            let connect_result = connect_result.clone();
+            tokio::spawn(async move {
+                let mut device = match usbmux_connect().await {
+                    Ok(device) => device,
+                    Err(e) => {
+                        log::error!("{}", e);
+                        return;
+                    }
+                };
+                match usbmux_exchange(&mut device, &connect_body).await {
+                    Ok(response) if connect_result.is_match(&response) => {
+                        let _ = tokio::io::copy_bidirectional(&mut client, &mut device).await;
+                    }
+                    Ok(_) => log::error!("usbmuxd refused the connection to port {}", remote_port),
+                    Err(e) => log::error!("usbmux connect failed: {}", e),
+                }
+            });
+        }
+    });
+    Ok(local_port)
+}
@@ -1,13 +1,34 @@
 use crate::native_bridge;
+use crate::plugins;
 use capstone::prelude::*;
 use libc::{self};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::num::ParseIntError;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::slice;
 use std::str;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Pointer width of the opened process; 4 for 32-bit targets such as WOW64 processes
+static TARGET_POINTER_SIZE: AtomicUsize = AtomicUsize::new(std::mem::size_of::<usize>());
+
+pub fn set_target_pointer_size(size: usize) {
+    TARGET_POINTER_SIZE.store(size, Ordering::Relaxed);
+}
+
+pub fn pointer_size() -> usize {
+    TARGET_POINTER_SIZE.load(Ordering::Relaxed)
+}
+
+// Reads a target pointer from the start of `bytes`, honouring the target's pointer width
+pub fn pointer_from_bytes(bytes: &[u8]) -> Option<usize> {
+    match pointer_size() {
+        4 => Some(u32::from_le_bytes(bytes.get(..4)?.try_into().ok()?) as usize),
+        _ => Some(u64::from_le_bytes(bytes.get(..8)?.try_into().ok()?) as usize),
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FileItem {
@@ -31,6 +52,71 @@ pub fn read_memory_64(pid: i32, address: u64) -> Result<u64, String> {
     Ok(u64::from_le_bytes(buffer))
 }
 
+pub fn read_pointer(pid: i32, address: u64) -> Result<u64, String> {
+    if pointer_size() == 4 {
+        return _read_memory_32(pid, address as u32).map(|value| value as u64);
+    }
+    read_memory_64(pid, address)
+}
+
+// Granularity of the fallback in `read_sparse`, the smallest supported page size
+const SPARSE_READ_PAGE: usize = 0x1000;
+
+pub struct SparseRead {
+    // Unreadable bytes are left zero
+    pub buffer: Vec<u8>,
+    pub bytes_read: usize,
+    // Offset of the first unreadable byte, None when everything was read
+    pub first_failure: Option<usize>,
+}
+
+// Reads a range that may be only partly mapped. A failed or short read of the whole range is
+// retried page by page so the mapped parts still come back.
+pub fn read_sparse(pid: i32, address: usize, size: usize) -> SparseRead {
+    let mut buffer = vec![0u8; size];
+    let nread =
+        native_bridge::read_process_memory(pid, address as *mut libc::c_void, size, &mut buffer);
+    if let Ok(n) = nread {
+        if n as usize >= size {
+            return SparseRead {
+                buffer,
+                bytes_read: size,
+                first_failure: None,
+            };
+        }
+    }
+
+    buffer.fill(0);
+    let mut bytes_read = 0;
+    let mut first_failure = None;
+    let mut offset = 0;
+    while offset < size {
+        let page_end = ((address + offset) / SPARSE_READ_PAGE + 1) * SPARSE_READ_PAGE;
+        let length = (page_end - address - offset).min(size - offset);
+        let chunk = &mut buffer[offset..offset + length];
+        match native_bridge::read_process_memory(
+            pid,
+            (address + offset) as *mut libc::c_void,
+            length,
+            chunk,
+        ) {
+            Ok(n) if n as usize >= length => bytes_read += length,
+            result => {
+                let n = result.map(|n| n.max(0) as usize).unwrap_or(0).min(length);
+                chunk[n..].fill(0);
+                bytes_read += n;
+                first_failure.get_or_insert(offset + n);
+            }
+        }
+        offset += length;
+    }
+    SparseRead {
+        buffer,
+        bytes_read,
+        first_failure,
+    }
+}
+
 pub fn _read_memory_32(pid: i32, address: u32) -> Result<u32, String> {
     let mut buffer = [0u8; 4];
     native_bridge::read_process_memory(pid, address as *mut libc::c_void, 4, &mut buffer).map_err(
@@ -84,7 +170,7 @@ pub fn resolve_nested_address(
         } else if cap.get(2).is_some() {
             if !current_expr.is_empty() {
                 let inner_value = resolve_single_level_address(&current_expr, modules)?;
-                let memory_value = read_memory_64(pid, inner_value)?;
+                let memory_value = read_pointer(pid, inner_value)?;
                 if let Some(mut prev_expr) = stack.pop() {
                     prev_expr.push_str(&format!("0x{:X}", memory_value));
                     current_expr = prev_expr;
@@ -268,14 +354,34 @@ pub fn get_cache_directory(pid: i32) -> String {
     }
 }
 
+// Disassembler for the host architecture at the target's pointer width
+fn capstone() -> Capstone {
+    if cfg!(target_arch = "x86_64") || cfg!(target_arch = "x86") {
+        let mode = if pointer_size() == 4 {
+            arch::x86::ArchMode::Mode32
+        } else {
+            arch::x86::ArchMode::Mode64
+        };
+        Capstone::new().x86().mode(mode).detail(true).build()
+    } else if pointer_size() == 4 {
+        Capstone::new()
+            .arm()
+            .mode(arch::arm::ArchMode::Arm)
+            .detail(true)
+            .build()
+    } else {
+        Capstone::new()
+            .arm64()
+            .mode(arch::arm64::ArchMode::Arm)
+            .detail(true)
+            .build()
+    }
+    .expect("Failed to create Capstone object")
+}
+
 pub fn disassemble(bytecode: *const u8, length: usize, address: u64) -> String {
     let bytes = unsafe { slice::from_raw_parts(bytecode, length) };
-    let cs = Capstone::new()
-        .arm64()
-        .mode(arch::arm64::ArchMode::Arm)
-        .detail(true)
-        .build()
-        .expect("Failed to create Capstone object");
+    let cs = capstone();
 
     let instructions = cs
         .disasm_all(bytes, address)
@@ -290,3 +396,1098 @@ pub fn disassemble(bytecode: *const u8, length: usize, address: u64) -> String {
 
     result
 }
+
+// Length of the first instruction in `bytes`, or None when it does not decode
+pub fn instruction_length(bytes: &[u8], address: u64) -> Option<usize> {
+    let cs = capstone();
+    let instructions = cs.disasm_count(bytes, address, 1).ok()?;
+    let length = instructions.iter().next()?.len();
+    Some(length)
+}
+
+pub fn parse_regions(regions: &[serde_json::Value]) -> Vec<(usize, usize, String)> {
+    let mut ranges: Vec<(usize, usize, String)> = regions
+        .iter()
+        .filter_map(|region| {
+            let start =
+                usize::from_str_radix(region["start_address"].as_str().unwrap_or(""), 16).ok()?;
+            let end =
+                usize::from_str_radix(region["end_address"].as_str().unwrap_or(""), 16).ok()?;
+            let protection = region["protection"].as_str().unwrap_or("").to_string();
+            Some((start, end, protection))
+        })
+        .collect();
+    ranges.sort_by_key(|&(start, _, _)| start);
+    ranges
+}
+
+// Readable and writable regions, where game state lives
+pub fn writable_ranges(pid: i32) -> Result<Vec<(usize, usize)>, String> {
+    let regions = native_bridge::enum_regions(pid)?;
+    Ok(parse_regions(&regions)
+        .into_iter()
+        .filter(|(_, _, protection)| protection.starts_with("rw"))
+        .map(|(start, end, _)| (start, end))
+        .collect())
+}
+
+pub fn find_region(ranges: &[(usize, usize, String)], address: usize) -> Option<usize> {
+    let idx = ranges.partition_point(|&(start, _, _)| start <= address);
+    if idx == 0 {
+        return None;
+    }
+    let (_, end, _) = &ranges[idx - 1];
+    if address < *end {
+        Some(idx - 1)
+    } else {
+        None
+    }
+}
+
+pub fn format_hexdump(address: usize, buffer: &[u8], width: usize) -> Vec<serde_json::Value> {
+    buffer
+        .chunks(width)
+        .enumerate()
+        .map(|(i, row)| {
+            let hex = row
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let ascii: String = row
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            serde_json::json!({
+                "address": address + i * width,
+                "hex": hex,
+                "ascii": ascii
+            })
+        })
+        .collect()
+}
+
+// IEEE 754 half precision, the 16-bit float used by GPU-facing engine data
+pub fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = (bits >> 10) & 0x1f;
+    let mantissa = (bits & 0x3ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent as i32 - 15),
+    }
+}
+
+// Rounds to the nearest half, ties to even, saturating to infinity
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let magnitude = value.abs();
+    if value.is_nan() {
+        return sign | 0x7e00;
+    }
+    if magnitude >= 65520.0 {
+        return sign | 0x7c00;
+    }
+    if magnitude < 2f32.powi(-14) {
+        return sign | (magnitude * 2f32.powi(24)).round_ties_even() as u16;
+    }
+    let exponent = ((bits >> 23) & 0xff) - 127 + 15;
+    let mantissa = bits & 0x7f_ffff;
+    let mut half = (exponent << 10) | (mantissa >> 13);
+    let rest = mantissa & 0x1fff;
+    if rest > 0x1000 || (rest == 0x1000 && half & 1 == 1) {
+        // A carry out of the mantissa correctly bumps the exponent
+        half += 1;
+    }
+    sign | half as u16
+}
+
+// bfloat16 is the upper half of an f32
+pub fn bf16_to_f32(bits: u16) -> f32 {
+    f32::from_bits((bits as u32) << 16)
+}
+
+pub fn f32_to_bf16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    if value.is_nan() {
+        return ((bits >> 16) | 0x40) as u16;
+    }
+    ((bits + 0x7fff + ((bits >> 16) & 1)) >> 16) as u16
+}
+
+// Value of a float16 or bfloat16 stored little-endian at the start of `bytes`
+pub fn decode_half(data_type: &str, bytes: &[u8]) -> f32 {
+    let bits = u16::from_le_bytes([bytes[0], bytes[1]]);
+    if data_type == "bfloat16" {
+        bf16_to_f32(bits)
+    } else {
+        f16_to_f32(bits)
+    }
+}
+
+pub fn data_type_size(data_type: &str) -> Option<usize> {
+    match data_type {
+        "int8" | "uint8" | "flags8" => Some(1),
+        "int16" | "uint16" | "flags16" | "float16" | "bfloat16" => Some(2),
+        "int32" | "uint32" | "float" | "flags32" | "decimal32" => Some(4),
+        "int64" | "uint64" | "double" | "flags64" | "decimal64" => Some(8),
+        "pointer" => Some(pointer_size()),
+        _ => wide_integer(data_type)
+            .map(|(size, _)| size)
+            .or_else(|| plugins::data_type_size(data_type)),
+    }
+}
+
+// int128/uint128 and integers of any other whole byte width up to 16, e.g. uint24 or int48,
+// as (size in bytes, signed). The fixed 8/16/32/64-bit types are not included.
+pub fn wide_integer(data_type: &str) -> Option<(usize, bool)> {
+    let (signed, bits) = match data_type.strip_prefix("uint") {
+        Some(bits) => (false, bits),
+        None => (true, data_type.strip_prefix("int")?),
+    };
+    let bits: usize = bits.parse().ok()?;
+    if !bits.is_multiple_of(8) || !(8..=128).contains(&bits) || matches!(bits, 8 | 16 | 32 | 64) {
+        return None;
+    }
+    Some((bits / 8, signed))
+}
+
+pub fn wide_to_u128(bytes: &[u8]) -> u128 {
+    bytes
+        .iter()
+        .take(16)
+        .rev()
+        .fold(0u128, |value, &byte| (value << 8) | byte as u128)
+}
+
+// Sign-extends from the width of `bytes`
+pub fn wide_to_i128(bytes: &[u8]) -> i128 {
+    let shift = 128 - bytes.len().min(16) as u32 * 8;
+    ((wide_to_u128(bytes) << shift) as i128) >> shift
+}
+
+// JSON numbers stop at 64 bits, so wider values are decimal strings
+fn wide_json(bytes: &[u8], signed: bool) -> serde_json::Value {
+    if signed {
+        let value = wide_to_i128(bytes);
+        i64::try_from(value).map_or_else(|_| value.to_string().into(), Into::into)
+    } else {
+        let value = wide_to_u128(bytes);
+        u64::try_from(value).map_or_else(|_| value.to_string().into(), Into::into)
+    }
+}
+
+fn encode_wide(value: &serde_json::Value, size: usize, signed: bool) -> Result<Vec<u8>, String> {
+    let v = value_as_integer(value)?;
+    let unused_bits = 128 - size as u32 * 8;
+    let in_range = if signed {
+        (i128::MIN >> unused_bits..=i128::MAX >> unused_bits).contains(&v)
+    } else {
+        v >= 0 && v as u128 <= u128::MAX >> unused_bits
+    };
+    if !in_range {
+        return Err(format!(
+            "Value {} out of range for {}-byte integer",
+            v, size
+        ));
+    }
+    Ok(v.to_le_bytes()[..size].to_vec())
+}
+
+pub fn decode_value(data_type: &str, bytes: &[u8]) -> Option<serde_json::Value> {
+    let size = data_type_size(data_type).unwrap_or(bytes.len());
+    if bytes.len() < size {
+        return None;
+    }
+    let b = &bytes[..size];
+    let value = match data_type {
+        "int8" => serde_json::json!(b[0] as i8),
+        "uint8" => serde_json::json!(b[0]),
+        "int16" => serde_json::json!(i16::from_le_bytes(b.try_into().ok()?)),
+        "uint16" => serde_json::json!(u16::from_le_bytes(b.try_into().ok()?)),
+        "int32" | "decimal32" => serde_json::json!(i32::from_le_bytes(b.try_into().ok()?)),
+        "uint32" => serde_json::json!(u32::from_le_bytes(b.try_into().ok()?)),
+        "int64" | "decimal64" => serde_json::json!(i64::from_le_bytes(b.try_into().ok()?)),
+        "uint64" => serde_json::json!(u64::from_le_bytes(b.try_into().ok()?)),
+        "float" => serde_json::json!(f32::from_le_bytes(b.try_into().ok()?)),
+        "double" => serde_json::json!(f64::from_le_bytes(b.try_into().ok()?)),
+        "float16" | "bfloat16" => serde_json::json!(decode_half(data_type, b)),
+        "pointer" => serde_json::json!(format!("0x{:x}", pointer_from_bytes(b)?)),
+        "flags8" | "flags16" | "flags32" | "flags64" => {
+            serde_json::json!(format!(
+                "{:0width$b}",
+                masked_flags(b, None),
+                width = size * 8
+            ))
+        }
+        "utf-8" => {
+            let end = b.iter().position(|&c| c == 0).unwrap_or(b.len());
+            serde_json::json!(String::from_utf8_lossy(&b[..end]))
+        }
+        "utf-16" => {
+            let units: Vec<u16> = b
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .take_while(|&c| c != 0)
+                .collect();
+            serde_json::json!(String::from_utf16_lossy(&units))
+        }
+        "aob" | "bytes" => serde_json::json!(hex::encode(b)),
+        _ => {
+            let (_, signed) = wide_integer(data_type)?;
+            wide_json(b, signed)
+        }
+    };
+    Some(value)
+}
+
+pub fn value_as_integer(value: &serde_json::Value) -> Result<i128, String> {
+    match value {
+        serde_json::Value::Number(n) => n
+            .as_i64()
+            .map(|v| v as i128)
+            .or_else(|| n.as_u64().map(|v| v as i128))
+            .or_else(|| n.as_f64().map(|v| v as i128))
+            .ok_or_else(|| format!("Invalid integer: {}", n)),
+        serde_json::Value::String(s) => {
+            let s = s.trim();
+            let (negative, digits) = match s.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, s),
+            };
+            let parsed = if let Some(hex) = digits.strip_prefix("0x") {
+                i128::from_str_radix(hex, 16)
+            } else {
+                digits.parse::<i128>()
+            }
+            .map_err(|e| format!("Invalid integer '{}': {}", s, e))?;
+            Ok(if negative { -parsed } else { parsed })
+        }
+        _ => Err(format!("Invalid integer: {}", value)),
+    }
+}
+
+pub fn value_as_float(value: &serde_json::Value) -> Result<f64, String> {
+    match value {
+        serde_json::Value::Number(n) => n.as_f64().ok_or_else(|| format!("Invalid float: {}", n)),
+        serde_json::Value::String(s) => s
+            .trim()
+            .parse::<f64>()
+            .map_err(|e| format!("Invalid float '{}': {}", s, e)),
+        _ => Err(format!("Invalid float: {}", value)),
+    }
+}
+
+macro_rules! encode_integer {
+    ($ty:ty, $value:expr) => {{
+        let v = value_as_integer($value)?;
+        <$ty>::try_from(v)
+            .map(|v| v.to_le_bytes().to_vec())
+            .map_err(|_| format!("Value {} out of range for {}", v, stringify!($ty)))
+    }};
+}
+
+pub fn encode_value(data_type: &str, value: &serde_json::Value) -> Result<Vec<u8>, String> {
+    match data_type {
+        "int8" => encode_integer!(i8, value),
+        "uint8" => encode_integer!(u8, value),
+        "int16" => encode_integer!(i16, value),
+        "uint16" => encode_integer!(u16, value),
+        "int32" | "decimal32" => encode_integer!(i32, value),
+        "uint32" => encode_integer!(u32, value),
+        "int64" | "decimal64" => encode_integer!(i64, value),
+        "uint64" => encode_integer!(u64, value),
+        "pointer" if pointer_size() == 4 => encode_integer!(u32, value),
+        "pointer" => encode_integer!(u64, value),
+        "flags8" => encode_integer!(u8, value),
+        "flags16" => encode_integer!(u16, value),
+        "flags32" => encode_integer!(u32, value),
+        "flags64" => encode_integer!(u64, value),
+        "float" => Ok((value_as_float(value)? as f32).to_le_bytes().to_vec()),
+        "double" => Ok(value_as_float(value)?.to_le_bytes().to_vec()),
+        "float16" => Ok(f32_to_f16(value_as_float(value)? as f32)
+            .to_le_bytes()
+            .to_vec()),
+        "bfloat16" => Ok(f32_to_bf16(value_as_float(value)? as f32)
+            .to_le_bytes()
+            .to_vec()),
+        "utf-8" => match value {
+            serde_json::Value::String(s) => Ok(s.as_bytes().to_vec()),
+            _ => Err(format!("Invalid string: {}", value)),
+        },
+        "utf-16" => match value {
+            serde_json::Value::String(s) => {
+                Ok(s.encode_utf16().flat_map(|c| c.to_le_bytes()).collect())
+            }
+            _ => Err(format!("Invalid string: {}", value)),
+        },
+        "aob" | "bytes" => match value {
+            serde_json::Value::String(s) => {
+                hex::decode(s.replace(' ', "")).map_err(|e| format!("Invalid hex '{}': {}", s, e))
+            }
+            _ => Err(format!("Invalid hex string: {}", value)),
+        },
+        _ => match wide_integer(data_type) {
+            Some((size, signed)) => encode_wide(value, size, signed),
+            None => Err(format!("Unsupported data type: {}", data_type)),
+        },
+    }
+}
+
+// flags8..flags64 value with only the bits set in `mask` kept, or every bit without a mask
+pub fn masked_flags(bytes: &[u8], mask: Option<&[u8]>) -> u64 {
+    bytes
+        .iter()
+        .take(8)
+        .enumerate()
+        .fold(0, |value, (i, &byte)| {
+            let bits = mask.map_or(0xff, |mask| mask.get(i).copied().unwrap_or(0));
+            value | (((byte & bits) as u64) << (8 * i))
+        })
+}
+
+// Hex bit mask of a scan or filter, laid out like the pattern. Masks only apply to flags
+// types, so "bit 3 set" in a byte is pattern 08 with mask 08.
+pub fn parse_bit_mask(data_type: &str, mask: Option<&str>) -> Result<Option<Vec<u8>>, String> {
+    let Some(mask) = mask else {
+        return Ok(None);
+    };
+    if !data_type.starts_with("flags") {
+        return Err(format!(
+            "bit_mask requires a flags data type, not {}",
+            data_type
+        ));
+    }
+    let bytes = hex::decode(mask).map_err(|e| format!("Invalid bit_mask {}: {}", mask, e))?;
+    if Some(bytes.len()) != data_type_size(data_type) {
+        return Err(format!(
+            "bit_mask {} does not match the size of {}",
+            mask, data_type
+        ));
+    }
+    Ok(Some(bytes))
+}
+
+pub const DEFAULT_DECIMAL_SCALE: u32 = 100;
+
+// decimal32/decimal64 hold the displayed value times `scale` as a signed integer, such as a
+// price kept in cents. Reads and writes of these types use the stored integer.
+pub fn encode_decimal(
+    data_type: &str,
+    display_value: &str,
+    scale: Option<u32>,
+) -> Result<Vec<u8>, String> {
+    if !data_type.starts_with("decimal") {
+        return Err(format!(
+            "display_value requires a decimal data type, not {}",
+            data_type
+        ));
+    }
+    let value: f64 = display_value
+        .trim()
+        .parse()
+        .map_err(|e| format!("Invalid decimal '{}': {}", display_value, e))?;
+    let scaled = (value * scale.unwrap_or(DEFAULT_DECIMAL_SCALE) as f64).round();
+    encode_value(data_type, &serde_json::json!(scaled as i64))
+}
+
+// A value as typed by a person or read out by speech recognition, e.g. "999,999", "99.5",
+// "-1" for an unsigned type or plain text for utf-8/utf-16
+pub fn parse_typed_value(data_type: &str, text: &str) -> Result<Vec<u8>, String> {
+    if matches!(data_type, "utf-8" | "utf-16") {
+        return encode_text(data_type, text, false);
+    }
+    let cleaned: String = text
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ',' | '_' | ' '))
+        .collect();
+    if cleaned.is_empty() {
+        return Err("Value must not be empty".to_string());
+    }
+    if data_type.starts_with("decimal") {
+        return encode_decimal(data_type, &cleaned, None);
+    }
+    let value = serde_json::json!(cleaned);
+    encode_value(data_type, &value).or_else(|e| {
+        other_signedness(data_type)
+            .ok_or(e.clone())
+            .and_then(|other| encode_value(other, &value).map_err(|_| e))
+    })
+}
+
+pub fn decode_decimal(data_type: &str, bytes: &[u8], scale: Option<u32>) -> Option<f64> {
+    let stored = decode_value(data_type, bytes)?.as_i64()?;
+    Some(stored as f64 / scale.unwrap_or(DEFAULT_DECIMAL_SCALE) as f64)
+}
+
+// Encodes scan text as utf-8 or utf-16le, optionally with the terminator of that encoding.
+pub fn encode_text(data_type: &str, text: &str, null_terminated: bool) -> Result<Vec<u8>, String> {
+    let mut bytes: Vec<u8> = match data_type {
+        "utf-8" => text.as_bytes().to_vec(),
+        "utf-16" => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+        _ => return Err(format!("text is not supported for data type {}", data_type)),
+    };
+    if null_terminated {
+        bytes.extend(std::iter::repeat_n(
+            0,
+            if data_type == "utf-16" { 2 } else { 1 },
+        ));
+    }
+    Ok(bytes)
+}
+
+// True when `needle` occurs in `buffer` starting within `within` bytes of `offset`, not
+// counting an occurrence at `offset` itself.
+pub fn has_nearby(buffer: &[u8], offset: usize, needle: &[u8], within: usize) -> bool {
+    let window_start = offset.saturating_sub(within);
+    let window_end = buffer.len().min(offset + within + needle.len());
+    if window_start >= window_end {
+        return false;
+    }
+    memchr::memmem::find_iter(&buffer[window_start..window_end], needle)
+        .any(|pos| window_start + pos != offset)
+}
+
+// AOB signature such as "48 8B ?? ?? 05", one entry per byte with None for a wildcard.
+// Bytes may also be written without spaces, and "?" stands for a single wildcard byte.
+pub fn parse_signature(signature: &str) -> Result<Vec<Option<u8>>, String> {
+    let mut bytes = Vec::new();
+    for token in signature.split_whitespace() {
+        if token == "?" {
+            bytes.push(None);
+            continue;
+        }
+        if token.len() % 2 != 0 {
+            return Err(format!("Invalid signature byte '{}'", token));
+        }
+        for pair in token.as_bytes().chunks(2) {
+            let pair = str::from_utf8(pair).map_err(|e| e.to_string())?;
+            if pair == "??" {
+                bytes.push(None);
+            } else {
+                let byte = u8::from_str_radix(pair, 16)
+                    .map_err(|_| format!("Invalid signature byte '{}'", pair))?;
+                bytes.push(Some(byte));
+            }
+        }
+    }
+    if bytes.iter().all(Option::is_none) {
+        return Err("Signature needs at least one fixed byte".to_string());
+    }
+    Ok(bytes)
+}
+
+// Offsets of every match of a parsed signature, found by jumping between occurrences of
+// its first fixed byte
+pub fn find_signature(buffer: &[u8], signature: &[Option<u8>]) -> Vec<usize> {
+    let Some(anchor) = signature.iter().position(Option::is_some) else {
+        return Vec::new();
+    };
+    let first = signature[anchor].unwrap_or_default();
+    memchr::memchr_iter(first, buffer)
+        .filter_map(|position| position.checked_sub(anchor))
+        .filter(|&start| {
+            start + signature.len() <= buffer.len()
+                && signature
+                    .iter()
+                    .zip(&buffer[start..])
+                    .all(|(expected, byte)| expected.is_none_or(|expected| expected == *byte))
+        })
+        .collect()
+}
+
+// The same-width integer type with the opposite signedness.
+pub fn other_signedness(data_type: &str) -> Option<&'static str> {
+    match data_type {
+        "int8" => Some("uint8"),
+        "uint8" => Some("int8"),
+        "int16" => Some("uint16"),
+        "uint16" => Some("int16"),
+        "int32" => Some("uint32"),
+        "uint32" => Some("int32"),
+        "int64" => Some("uint64"),
+        "uint64" => Some("int64"),
+        _ => None,
+    }
+}
+
+// Magnitudes outside this range are treated as noise by "simple values" float scans
+const SIMPLE_FLOAT_MIN: f64 = 1e-5;
+const SIMPLE_FLOAT_MAX: f64 = 1e9;
+
+// True for zero and for normal float/double values of a plausible magnitude.
+pub fn is_simple_float(data_type: &str, bytes: &[u8]) -> bool {
+    let (value, normal) = match data_type {
+        // Half subnormals widen to normal f32 values, so check the exponent bits instead
+        "float16" if bytes.len() >= 2 => {
+            let exponent = (bytes[1] >> 2) & 0x1f;
+            (
+                decode_half(data_type, bytes) as f64,
+                exponent != 0 && exponent != 0x1f,
+            )
+        }
+        "bfloat16" if bytes.len() >= 2 => {
+            let value = decode_half(data_type, bytes);
+            (value as f64, value.is_normal())
+        }
+        "float" if bytes.len() >= 4 => {
+            let value = f32::from_le_bytes(bytes[..4].try_into().unwrap());
+            (value as f64, value.is_normal())
+        }
+        "double" if bytes.len() >= 8 => {
+            let value = f64::from_le_bytes(bytes[..8].try_into().unwrap());
+            (value, value.is_normal())
+        }
+        _ => return true,
+    };
+    value == 0.0 || (normal && (SIMPLE_FLOAT_MIN..=SIMPLE_FLOAT_MAX).contains(&value.abs()))
+}
+
+fn float_confidence(value: f64) -> f64 {
+    if !value.is_finite() {
+        return 0.0;
+    }
+    if value == 0.0 {
+        return 0.3;
+    }
+    let magnitude = value.abs();
+    if !(1e-4..=1e9).contains(&magnitude) {
+        return 0.05;
+    }
+    // Values a human would type (e.g. 100.0, 0.5) round-trip with few digits
+    let scaled = value * 1000.0;
+    if (scaled - scaled.round()).abs() < 1e-6 {
+        0.8
+    } else {
+        0.5
+    }
+}
+
+fn integer_confidence(value: i64) -> f64 {
+    let magnitude = value.unsigned_abs();
+    if magnitude == 0 {
+        0.3
+    } else if magnitude < 100_000 {
+        0.7
+    } else if magnitude < 100_000_000 {
+        0.4
+    } else {
+        0.1
+    }
+}
+
+pub fn guess_value_types(
+    bytes: &[u8],
+    ranges: &[(usize, usize, String)],
+) -> Vec<serde_json::Value> {
+    let mut guesses: Vec<(f64, serde_json::Value)> = Vec::new();
+
+    if bytes.len() >= 4 {
+        let raw: [u8; 4] = bytes[..4].try_into().unwrap();
+        let int_value = i32::from_le_bytes(raw);
+        guesses.push((
+            integer_confidence(int_value as i64),
+            serde_json::json!({ "data_type": "int32", "value": int_value }),
+        ));
+        let float_value = f32::from_le_bytes(raw);
+        // Small integers reinterpreted as float come out as denormals
+        let confidence = if float_value != 0.0 && !float_value.is_normal() {
+            0.0
+        } else {
+            float_confidence(float_value as f64)
+        };
+        guesses.push((
+            confidence,
+            serde_json::json!({ "data_type": "float", "value": float_value }),
+        ));
+    }
+
+    if bytes.len() >= 8 {
+        let raw: [u8; 8] = bytes[..8].try_into().unwrap();
+        let int_value = i64::from_le_bytes(raw);
+        guesses.push((
+            integer_confidence(int_value) * 0.9,
+            serde_json::json!({ "data_type": "int64", "value": int_value }),
+        ));
+        let double_value = f64::from_le_bytes(raw);
+        let confidence = if double_value != 0.0 && !double_value.is_normal() {
+            0.0
+        } else {
+            float_confidence(double_value)
+        };
+        guesses.push((
+            confidence,
+            serde_json::json!({ "data_type": "double", "value": double_value }),
+        ));
+    }
+
+    let pointer_size = pointer_size();
+    if bytes.len() >= pointer_size {
+        let pointer = pointer_from_bytes(bytes).unwrap_or(0);
+        if pointer != 0 {
+            if let Some(idx) = find_region(ranges, pointer) {
+                let protection = &ranges[idx].2;
+                let confidence = if pointer.is_multiple_of(pointer_size) {
+                    0.95
+                } else {
+                    0.6
+                };
+                guesses.push((
+                    confidence,
+                    serde_json::json!({
+                        "data_type": "pointer",
+                        "value": format!("0x{:x}", pointer),
+                        "protection": protection
+                    }),
+                ));
+            }
+        }
+    }
+
+    let printable = |b: u8| b.is_ascii_graphic() || b == b' ';
+    let utf16_chars = bytes
+        .chunks_exact(2)
+        .take_while(|c| c[1] == 0 && printable(c[0]))
+        .count();
+    if utf16_chars >= 2 {
+        let units: Vec<u16> = bytes[..utf16_chars * 2]
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        guesses.push((
+            (0.4 + utf16_chars as f64 * 0.1).min(0.9),
+            serde_json::json!({
+                "data_type": "utf-16",
+                "value": String::from_utf16_lossy(&units)
+            }),
+        ));
+    }
+
+    let utf8_chars = bytes.iter().take_while(|&&b| printable(b)).count();
+    if utf8_chars >= 4 {
+        guesses.push((
+            (0.3 + utf8_chars as f64 * 0.05).min(0.9),
+            serde_json::json!({
+                "data_type": "utf-8",
+                "value": String::from_utf8_lossy(&bytes[..utf8_chars])
+            }),
+        ));
+    }
+
+    guesses.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    guesses
+        .into_iter()
+        .map(|(confidence, mut guess)| {
+            guess["confidence"] = serde_json::json!((confidence * 100.0).round() / 100.0);
+            guess
+        })
+        .collect()
+}
+
+// True when the server runs inside its target as the embedded library. Without
+// task_for_pid (jailed iOS) the own process is then the only one it can inspect.
+pub fn is_embedded() -> bool {
+    std::env::var("MEMORY_SERVER_RUNNING_MODE").is_ok_and(|mode| mode == "embedded")
+}
+
+// Features available in the current mode, reported by /serverinfo so clients can hide the
+// rest. Embedded mode cannot pick or suspend processes, and jailed iOS keeps code pages
+// read-only.
+pub fn capabilities() -> Vec<&'static str> {
+    let mut capabilities = vec![
+        "read",
+        "write",
+        "scan",
+        "pointer_scan",
+        "watchpoints",
+        "breakpoints",
+        "file_explorer",
+        "diagnose",
+    ];
+    if !is_embedded() {
+        capabilities.extend(["process_list", "attach", "suspend"]);
+    }
+    if !is_embedded() || !cfg!(target_os = "ios") {
+        capabilities.push("code_patch");
+    }
+    if cfg!(any(target_os = "android", target_os = "linux")) {
+        capabilities.push("input");
+    }
+    capabilities
+}
+
+pub fn get_data_directory(pid: i32) -> PathBuf {
+    let mut path = PathBuf::from("");
+    if is_embedded() {
+        path = PathBuf::from(get_cache_directory(pid));
+    }
+    path.push("memory-server-data-dir");
+    path
+}
+
+// False once `pid` has exited. Platforms without a cheap check report every process alive.
+#[cfg(unix)]
+pub fn process_alive(pid: i32) -> bool {
+    let alive = unsafe { libc::kill(pid, 0) } == 0;
+    alive || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+pub fn process_alive(_pid: i32) -> bool {
+    true
+}
+
+// Sorts the ranges and joins the ones that overlap or touch.
+pub fn merge_ranges(ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut sorted: Vec<(usize, usize)> = ranges
+        .iter()
+        .copied()
+        .filter(|(start, end)| start < end)
+        .collect();
+    sorted.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(sorted.len());
+    for (start, end) in sorted {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+// Parts of `ranges` not covered by `other`, which must be sorted and non-overlapping.
+pub fn subtract_ranges(ranges: &[(usize, usize)], other: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut result = Vec::new();
+    for &(start, end) in ranges {
+        let mut cursor = start;
+        for &(other_start, other_end) in other {
+            if other_end <= cursor || other_start >= end {
+                continue;
+            }
+            if other_start > cursor {
+                result.push((cursor, other_start));
+            }
+            cursor = cursor.max(other_end);
+        }
+        if cursor < end {
+            result.push((cursor, end));
+        }
+    }
+    result
+}
+
+// Parts of `ranges` also covered by `other`, which must be sorted and non-overlapping.
+pub fn intersect_ranges(
+    ranges: &[(usize, usize)],
+    other: &[(usize, usize)],
+) -> Vec<(usize, usize)> {
+    subtract_ranges(ranges, &subtract_ranges(&[(0, usize::MAX)], other))
+}
+
+// True when `address` lies in one of the sorted, non-overlapping `ranges`.
+pub fn in_ranges(ranges: &[(usize, usize)], address: usize) -> bool {
+    let index = ranges.partition_point(|&(start, _)| start <= address);
+    index > 0 && address < ranges[index - 1].1
+}
+
+// Mappings of loaded modules, plus the anonymous mapping right after one (its .bss).
+// Addresses in these stay at the same offset from the module base across restarts.
+pub fn static_ranges(pid: i32) -> Vec<(usize, usize)> {
+    let modules = native_bridge::enum_modules(pid).unwrap_or_default();
+    let module_paths: Vec<&str> = modules
+        .iter()
+        .filter_map(|module| module["modulename"].as_str())
+        .collect();
+    let module_spans: Vec<(usize, usize)> = modules
+        .iter()
+        .filter_map(|module| {
+            let base = module["base"].as_u64()? as usize;
+            Some((base, base + module["size"].as_u64()? as usize))
+        })
+        .collect();
+
+    let mut regions: Vec<(usize, usize, String)> = native_bridge::enum_regions(pid)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|region| {
+            let start =
+                usize::from_str_radix(region["start_address"].as_str().unwrap_or(""), 16).ok()?;
+            let end =
+                usize::from_str_radix(region["end_address"].as_str().unwrap_or(""), 16).ok()?;
+            let path = region["file_path"].as_str().unwrap_or("").to_string();
+            Some((start, end, path))
+        })
+        .collect();
+    regions.sort_by_key(|&(start, _, _)| start);
+
+    let mut ranges = Vec::new();
+    let mut module_end = None;
+    for (start, end, path) in regions {
+        let in_module = (!path.is_empty() && module_paths.contains(&path.as_str()))
+            || module_spans
+                .iter()
+                .any(|&(base, limit)| start >= base && start < limit);
+        if in_module {
+            ranges.push((start, end));
+            module_end = Some(end);
+        } else if path.is_empty() && module_end == Some(start) {
+            ranges.push((start, end));
+            module_end = None;
+        } else {
+            module_end = None;
+        }
+    }
+    merge_ranges(&ranges)
+}
+
+// Module and mapping of addresses, with the region and module lists read once for all
+pub struct AddressLocator {
+    // (start, end, protection, mapped file)
+    regions: Vec<(usize, usize, String, String)>,
+    // (base, end, file name)
+    modules: Vec<(usize, usize, String)>,
+}
+
+impl AddressLocator {
+    pub fn new(pid: i32) -> Self {
+        let mut regions: Vec<(usize, usize, String, String)> = native_bridge::enum_regions(pid)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|region| {
+                let start =
+                    usize::from_str_radix(region["start_address"].as_str().unwrap_or(""), 16)
+                        .ok()?;
+                let end =
+                    usize::from_str_radix(region["end_address"].as_str().unwrap_or(""), 16).ok()?;
+                Some((
+                    start,
+                    end,
+                    region["protection"].as_str().unwrap_or("").to_string(),
+                    region["file_path"].as_str().unwrap_or("").to_string(),
+                ))
+            })
+            .collect();
+        regions.sort_by_key(|region| region.0);
+        let mut modules: Vec<(usize, usize, String)> = native_bridge::enum_modules(pid)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|module| {
+                let base = module["base"].as_u64()? as usize;
+                let size = module["size"].as_u64()? as usize;
+                let path = module["modulename"].as_str()?;
+                let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+                Some((base, base + size, name.to_string()))
+            })
+            .collect();
+        modules.sort_by_key(|module| module.0);
+        AddressLocator { regions, modules }
+    }
+
+    pub fn module(&self, address: usize) -> Option<&str> {
+        let index = self.modules.partition_point(|module| module.0 <= address);
+        let module = &self.modules[index.checked_sub(1)?];
+        (address < module.1).then_some(module.2.as_str())
+    }
+
+    // {protection, mapping} of the region holding `address`
+    pub fn region(&self, address: usize) -> Option<Value> {
+        let index = self.regions.partition_point(|region| region.0 <= address);
+        let region = &self.regions[index.checked_sub(1)?];
+        if address >= region.1 {
+            return None;
+        }
+        let mapping = if region.3.is_empty() {
+            "[anonymous]"
+        } else {
+            region.3.as_str()
+        };
+        Some(serde_json::json!({ "protection": region.2, "mapping": mapping }))
+    }
+}
+
+pub fn restrict_to_allocations(
+    address_ranges: &[(usize, usize)],
+    allocations: &[serde_json::Value],
+    size: usize,
+    tolerance: usize,
+) -> Vec<(usize, usize)> {
+    let mut blocks: Vec<(usize, usize)> = allocations
+        .iter()
+        .filter_map(|allocation| {
+            let address = allocation["address"].as_u64()? as usize;
+            let block_size = allocation["size"].as_u64()? as usize;
+            if block_size.abs_diff(size) > tolerance {
+                return None;
+            }
+            Some((address, address + block_size))
+        })
+        .collect();
+    blocks.sort_unstable();
+
+    blocks
+        .into_iter()
+        .flat_map(|(block_start, block_end)| {
+            address_ranges.iter().filter_map(move |&(start, end)| {
+                let clipped_start = block_start.max(start);
+                let clipped_end = block_end.min(end);
+                (clipped_start < clipped_end).then_some((clipped_start, clipped_end))
+            })
+        })
+        .collect()
+}
+
+// Splits each range into runs of resident pages so scans skip swapped-out or untouched memory.
+// Ranges whose page state cannot be queried are kept as they are.
+pub fn resident_ranges(pid: i32, address_ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut resident = Vec::new();
+    for &(start, end) in address_ranges {
+        let (page_size, flags) = match native_bridge::query_page_info(pid, start, end - start) {
+            Ok(info) => info,
+            Err(_) => {
+                resident.push((start, end));
+                continue;
+            }
+        };
+        let first_page = start / page_size * page_size;
+        let mut run_start: Option<usize> = None;
+        for (index, page_flags) in flags.iter().enumerate() {
+            let page_start = (first_page + index * page_size).max(start);
+            let is_resident = page_flags & native_bridge::PAGE_INFO_RESIDENT != 0;
+            match (is_resident, run_start) {
+                (true, None) => run_start = Some(page_start),
+                (false, Some(run)) => {
+                    resident.push((run, page_start));
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(run) = run_start {
+            let covered_end = (first_page + flags.len() * page_size).min(end);
+            resident.push((run, covered_end));
+        }
+    }
+    resident
+}
+
+pub fn protection_bits(protection: &str) -> i32 {
+    let mut bits = 0;
+    if protection.contains('r') {
+        bits |= native_bridge::PROTECTION_READ;
+    }
+    if protection.contains('w') {
+        bits |= native_bridge::PROTECTION_WRITE;
+    }
+    if protection.contains('x') {
+        bits |= native_bridge::PROTECTION_EXECUTE;
+    }
+    bits
+}
+
+fn protection_string(bits: i32) -> String {
+    [
+        (native_bridge::PROTECTION_READ, 'r'),
+        (native_bridge::PROTECTION_WRITE, 'w'),
+        (native_bridge::PROTECTION_EXECUTE, 'x'),
+    ]
+    .iter()
+    .map(|&(bit, c)| if bits & bit != 0 { c } else { '-' })
+    .collect()
+}
+
+// Makes the read-only regions under [address, address + buffer.len()) writable, writes,
+// then puts the original protection back. Returns a report of every region touched.
+pub fn write_with_protection_fallback(
+    pid: i32,
+    address: usize,
+    buffer: &[u8],
+    stealth: bool,
+) -> Result<Value, String> {
+    let end = address + buffer.len();
+    let regions = native_bridge::enum_regions(pid)?;
+    let ranges = parse_regions(&regions);
+
+    let mut covered = address;
+    let mut readonly = Vec::new();
+    for (start, region_end, protection) in &ranges {
+        if *region_end <= address || *start >= end {
+            continue;
+        }
+        if *start > covered {
+            break;
+        }
+        covered = covered.max(*region_end);
+        let bits = protection_bits(protection);
+        if bits & native_bridge::PROTECTION_WRITE == 0 {
+            readonly.push(((*start).max(address), (*region_end).min(end), bits));
+        }
+    }
+    if covered < end {
+        return Err(format!("0x{:x} is not mapped", covered));
+    }
+    if readonly.is_empty() {
+        return Err("The target pages are already writable".to_string());
+    }
+
+    let mut changed: Vec<(usize, usize, i32)> = Vec::new();
+    let restore = |changed: &[(usize, usize, i32)]| -> Vec<bool> {
+        changed
+            .iter()
+            .map(|&(start, end, bits)| {
+                native_bridge::set_protection(pid, start, end - start, bits).is_ok()
+            })
+            .collect()
+    };
+    for &(start, region_end, bits) in &readonly {
+        let writable = bits | native_bridge::PROTECTION_READ | native_bridge::PROTECTION_WRITE;
+        if let Err(e) = native_bridge::set_protection(pid, start, region_end - start, writable) {
+            restore(&changed);
+            return Err(e);
+        }
+        changed.push((start, region_end, bits));
+    }
+
+    let written = if stealth {
+        native_bridge::write_process_memory_stealth(
+            pid,
+            address as *mut libc::c_void,
+            buffer.len(),
+            buffer,
+        )
+    } else {
+        native_bridge::write_process_memory(pid, address as *mut libc::c_void, buffer.len(), buffer)
+    };
+    let restored = restore(&changed);
+    let written = written.map_err(|e| format!("Write failed after changing protection: {}", e))?;
+
+    let regions: Vec<Value> = changed
+        .iter()
+        .zip(restored)
+        .map(|(&(start, end, bits), restored)| {
+            serde_json::json!({
+                "start_address": format!("{:x}", start),
+                "end_address": format!("{:x}", end),
+                "original_protection": protection_string(bits),
+                "temporary_protection": protection_string(
+                    bits | native_bridge::PROTECTION_READ | native_bridge::PROTECTION_WRITE
+                ),
+                "restored": restored,
+            })
+        })
+        .collect();
+    Ok(serde_json::json!({
+        "written": written,
+        "protection_changed": true,
+        "regions": regions,
+    }))
+}
@@ -0,0 +1,202 @@
+// Conversion between raw bytes and typed values for /formatvalue, so thin frontends can show
+// and edit values without a conversion library of their own. Besides the scan data types it
+// understands fixed-point and bitfields, in either byte order.
+use crate::request;
+use crate::util;
+use serde_json::{json, Value};
+
+const DEFAULT_FIXED_WIDTH: usize = 4;
+const DEFAULT_FRACTION_BITS: u32 = 16;
+
+fn is_big_endian(endian: Option<&str>) -> Result<bool, String> {
+    match endian.unwrap_or("little") {
+        "little" | "le" => Ok(false),
+        "big" | "be" => Ok(true),
+        other => Err(format!(
+            "Unknown byte order '{}'; expected little or big",
+            other
+        )),
+    }
+}
+
+// Width in bytes of the integer holding the value, None for strings and byte arrays
+fn value_width(request: &request::FormatValueRequest) -> Result<Option<usize>, String> {
+    let width = match request.data_type.as_str() {
+        "fixed" => request.width.unwrap_or(DEFAULT_FIXED_WIDTH),
+        "bitfield" => request.width.unwrap_or(1),
+        data_type => return Ok(util::data_type_size(data_type)),
+    };
+    if !(1..=8).contains(&width) {
+        return Err(format!("Invalid width {}; expected 1-8 bytes", width));
+    }
+    Ok(Some(width))
+}
+
+fn raw_integer(le_bytes: &[u8]) -> u64 {
+    util::wide_to_u128(le_bytes) as u64
+}
+
+fn sign_extend(raw: u64, bits: u32) -> i64 {
+    let shift = 64 - bits;
+    ((raw << shift) as i64) >> shift
+}
+
+fn bit_mask(bits: u32) -> u64 {
+    if bits >= 64 {
+        u64::MAX
+    } else {
+        (1u64 << bits) - 1
+    }
+}
+
+fn bitfield_range(
+    request: &request::FormatValueRequest,
+    width: usize,
+) -> Result<(u32, u32), String> {
+    let offset = request.bit_offset.unwrap_or(0);
+    let count = request.bit_count.unwrap_or(1);
+    if count == 0 || offset + count > width as u32 * 8 {
+        return Err(format!(
+            "Bits {}..{} do not fit in {} bytes",
+            offset,
+            offset + count,
+            width
+        ));
+    }
+    Ok((offset, count))
+}
+
+fn decode(
+    request: &request::FormatValueRequest,
+    le_bytes: &[u8],
+    width: Option<usize>,
+) -> Result<Value, String> {
+    let raw = raw_integer(le_bytes);
+    let value = match request.data_type.as_str() {
+        "fixed" => {
+            let bits = width.unwrap_or(DEFAULT_FIXED_WIDTH) as u32 * 8;
+            let scale = 2f64.powi(request.fraction_bits.unwrap_or(DEFAULT_FRACTION_BITS) as i32);
+            if request.signed.unwrap_or(true) {
+                json!(sign_extend(raw, bits) as f64 / scale)
+            } else {
+                json!(raw as f64 / scale)
+            }
+        }
+        "bitfield" => {
+            let (offset, count) = bitfield_range(request, width.unwrap_or(1))?;
+            json!((raw >> offset) & bit_mask(count))
+        }
+        data_type => util::decode_value(data_type, le_bytes)
+            .ok_or_else(|| format!("Cannot decode {} bytes as {}", le_bytes.len(), data_type))?,
+    };
+    Ok(value)
+}
+
+// Little-endian bytes of `value`. Bitfields are written into `base`, the existing bytes.
+fn encode(
+    request: &request::FormatValueRequest,
+    value: &Value,
+    width: Option<usize>,
+    base: Option<&[u8]>,
+) -> Result<Vec<u8>, String> {
+    let to_width = |raw: u64, width: usize| raw.to_le_bytes()[..width].to_vec();
+    match request.data_type.as_str() {
+        "fixed" => {
+            let width = width.unwrap_or(DEFAULT_FIXED_WIDTH);
+            let bits = width as u32 * 8;
+            let scale = 2f64.powi(request.fraction_bits.unwrap_or(DEFAULT_FRACTION_BITS) as i32);
+            let scaled = (util::value_as_float(value)? * scale).round();
+            let (min, max) = if request.signed.unwrap_or(true) {
+                (
+                    -(2f64.powi(bits as i32 - 1)),
+                    2f64.powi(bits as i32 - 1) - 1.0,
+                )
+            } else {
+                (0.0, 2f64.powi(bits as i32) - 1.0)
+            };
+            if scaled < min || scaled > max {
+                return Err(format!(
+                    "Value {} out of range for {}-byte fixed-point",
+                    value, width
+                ));
+            }
+            Ok(to_width(scaled as i64 as u64, width))
+        }
+        "bitfield" => {
+            let width = width.unwrap_or(1);
+            let (offset, count) = bitfield_range(request, width)?;
+            let field = util::value_as_integer(value)?;
+            if field < 0 || field as u64 > bit_mask(count) {
+                return Err(format!("Value {} does not fit in {} bits", field, count));
+            }
+            let raw = base.map(raw_integer).unwrap_or(0);
+            let mask = bit_mask(count) << offset;
+            Ok(to_width((raw & !mask) | ((field as u64) << offset), width))
+        }
+        data_type => util::encode_value(data_type, value),
+    }
+}
+
+pub fn format_value(request: &request::FormatValueRequest) -> Result<Value, String> {
+    let big_endian = is_big_endian(request.endian.as_deref())?;
+    let width = value_width(request)?;
+    // Only numbers have a byte order; strings and byte arrays pass through unchanged
+    let swap = |bytes: &mut Vec<u8>| {
+        if big_endian && width.is_some() {
+            bytes.reverse();
+        }
+    };
+
+    let input = request
+        .bytes
+        .as_deref()
+        .map(|hex_bytes| {
+            hex::decode(hex_bytes.replace(' ', ""))
+                .map_err(|e| format!("Invalid hex '{}': {}", hex_bytes, e))
+        })
+        .transpose()?;
+    let input = match (input, width) {
+        (Some(bytes), Some(width)) if bytes.len() < width => {
+            return Err(format!(
+                "{} needs {} bytes, got {}",
+                request.data_type,
+                width,
+                bytes.len()
+            ))
+        }
+        (Some(mut bytes), Some(width)) => {
+            bytes.truncate(width);
+            Some(bytes)
+        }
+        (bytes, _) => bytes,
+    };
+
+    let mut le_bytes = match (&request.value, input) {
+        (Some(value), base) => {
+            let base = base.map(|mut bytes| {
+                swap(&mut bytes);
+                bytes
+            });
+            encode(request, value, width, base.as_deref())?
+        }
+        (None, Some(mut bytes)) => {
+            swap(&mut bytes);
+            bytes
+        }
+        (None, None) => return Err("Give bytes to decode or a value to encode".to_string()),
+    };
+    let value = decode(request, &le_bytes, width)?;
+
+    let mut result = json!({
+        "data_type": request.data_type,
+        "value": value,
+    });
+    if let Some(width) = width {
+        let raw = util::wide_to_u128(&le_bytes);
+        result["hex"] = json!(format!("0x{:0digits$x}", raw, digits = width * 2));
+        result["binary"] = json!(format!("{:0digits$b}", raw, digits = width * 8));
+    }
+    swap(&mut le_bytes);
+    result["bytes"] = json!(hex::encode(&le_bytes));
+    Ok(result)
+}
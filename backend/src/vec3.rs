@@ -0,0 +1,89 @@
+// Scan for three floats within given bounds, such as the x/y/z of a player position. The
+// components are `stride` bytes apart, which covers both packed vectors and engines that
+// interleave other fields between them.
+use crate::native_bridge;
+use crate::request::Vec3ScanRequest;
+use crate::util;
+use rayon::prelude::*;
+
+const CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+pub struct Vec3Match {
+    pub address: usize,
+    pub coordinates: [f64; 3],
+    // Bytes from the start of x to the end of z
+    pub bytes: Vec<u8>,
+}
+
+pub fn scan(pid: i32, request: &Vec3ScanRequest) -> Result<Vec<Vec3Match>, String> {
+    let data_type = request.data_type.as_deref().unwrap_or("float");
+    let size = match data_type {
+        "float" | "double" | "float16" => util::data_type_size(data_type).unwrap_or(4),
+        _ => return Err(format!("Coordinates cannot be stored as {}", data_type)),
+    };
+    let stride = request.stride.unwrap_or(size);
+    if stride < size {
+        return Err(format!("stride must be at least the value size {}", size));
+    }
+    let align = request.align.unwrap_or(size);
+    if align == 0 {
+        return Err("align must be at least 1".to_string());
+    }
+    let bounds = [request.x, request.y, request.z];
+    if bounds.iter().any(|&(min, max)| min > max) {
+        return Err("Each bound must be (min, max)".to_string());
+    }
+    // Chunks overlap by this much so vectors crossing a chunk boundary are still found
+    let span = 2 * stride + size;
+
+    let mut matches: Vec<Vec3Match> = request
+        .address_ranges
+        .par_iter()
+        .flat_map(|&(start, end)| {
+            let mut found = Vec::new();
+            let mut chunk_start = start;
+            while chunk_start < end {
+                let step = CHUNK_SIZE.min(end - chunk_start);
+                let length = (step + span - 1).min(end - chunk_start);
+                let mut buffer = vec![0u8; length];
+                let nread = native_bridge::read_process_memory(
+                    pid,
+                    chunk_start as *mut libc::c_void,
+                    length,
+                    &mut buffer,
+                );
+                if nread.is_ok() {
+                    let first = (align - chunk_start % align) % align;
+                    let last = step.min(length.saturating_sub(span - 1));
+                    for offset in (first..last).step_by(align) {
+                        let mut coordinates = [0f64; 3];
+                        let inside = (0..3).all(|axis| {
+                            let at = offset + axis * stride;
+                            let value = util::decode_value(data_type, &buffer[at..at + size])
+                                .and_then(|value| value.as_f64());
+                            let (min, max) = bounds[axis];
+                            match value {
+                                Some(value) if value >= min && value <= max => {
+                                    coordinates[axis] = value;
+                                    true
+                                }
+                                _ => false,
+                            }
+                        });
+                        if inside {
+                            found.push(Vec3Match {
+                                address: chunk_start + offset,
+                                coordinates,
+                                bytes: buffer[offset..offset + span].to_vec(),
+                            });
+                        }
+                    }
+                }
+                chunk_start += step;
+            }
+            found
+        })
+        .collect();
+    matches.sort_by_key(|found| found.address);
+    Ok(matches)
+}
@@ -0,0 +1,183 @@
+// User-defined scan predicates as small WASM modules, for encodings no built-in data type
+// covers, without the risks of a native plugin: modules run in an interpreter with nothing
+// imported and a fuel limit per call. A module exports its `memory` and
+// `match(ptr: i32, len: i32) -> i32`, nonzero for a match, and optionally `input(len: i32) ->
+// i32` returning where the server should write the bytes; offset 0 is used otherwise.
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use wasmi::{Config, Engine, Linker, Memory, Module, Store, TypedFunc};
+
+pub const MAX_MODULE_SIZE: u64 = 4 * 1024 * 1024;
+// Enough for a few thousand instructions per value; a module that runs out does not match
+const FUEL_PER_CALL: u64 = 100_000;
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct AddPredicateQuery {
+    // Bytes passed to match() by find_type "predicate" scans of data types without a size,
+    // e.g. aob
+    pub size: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct RemovePredicateRequest {
+    pub name: String,
+}
+
+#[derive(Clone)]
+pub struct Predicate {
+    name: String,
+    // Told apart from an earlier upload under the same name by the per-thread instances
+    generation: u64,
+    size: Option<usize>,
+    engine: Engine,
+    module: Arc<Module>,
+}
+
+// An instance is not shareable between threads, so every scan thread keeps its own
+struct Instance {
+    generation: u64,
+    store: Store<()>,
+    memory: Memory,
+    input: Option<TypedFunc<i32, i32>>,
+    matcher: TypedFunc<(i32, i32), i32>,
+}
+
+lazy_static! {
+    static ref PREDICATES: RwLock<BTreeMap<String, Predicate>> = RwLock::new(BTreeMap::new());
+    static ref NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+}
+
+thread_local! {
+    static INSTANCES: RefCell<HashMap<String, Instance>> = RefCell::new(HashMap::new());
+}
+
+impl Predicate {
+    fn instantiate(&self) -> Result<Instance, String> {
+        let mut store = Store::new(&self.engine, ());
+        store.set_fuel(FUEL_PER_CALL).map_err(|e| e.to_string())?;
+        // Nothing is linked, so a module importing anything is rejected here
+        let linker = <Linker<()>>::new(&self.engine);
+        let instance = linker
+            .instantiate(&mut store, &self.module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(|e| e.to_string())?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or("The module does not export its memory")?;
+        let matcher = instance
+            .get_typed_func::<(i32, i32), i32>(&store, "match")
+            .map_err(|e| format!("match(ptr: i32, len: i32) -> i32: {}", e))?;
+        let input = instance.get_typed_func::<i32, i32>(&store, "input").ok();
+        Ok(Instance {
+            generation: self.generation,
+            store,
+            memory,
+            input,
+            matcher,
+        })
+    }
+
+    pub fn size(&self) -> Option<usize> {
+        self.size
+    }
+
+    fn call(&self, instance: &mut Instance, bytes: &[u8]) -> Result<bool, String> {
+        instance
+            .store
+            .set_fuel(FUEL_PER_CALL)
+            .map_err(|e| e.to_string())?;
+        let len = bytes.len() as i32;
+        let pointer = match &instance.input {
+            Some(input) => input
+                .call(&mut instance.store, len)
+                .map_err(|e| e.to_string())?,
+            None => 0,
+        };
+        instance
+            .memory
+            .write(&mut instance.store, pointer as u32 as usize, bytes)
+            .map_err(|e| e.to_string())?;
+        let result = instance
+            .matcher
+            .call(&mut instance.store, (pointer, len))
+            .map_err(|e| e.to_string())?;
+        Ok(result != 0)
+    }
+
+    // False as well when the module traps or runs out of fuel
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        INSTANCES.with(|instances| {
+            let mut instances = instances.borrow_mut();
+            let current = instances
+                .get(&self.name)
+                .is_some_and(|instance| instance.generation == self.generation);
+            if !current {
+                match self.instantiate() {
+                    Ok(instance) => {
+                        instances.insert(self.name.clone(), instance);
+                    }
+                    Err(_) => return false,
+                }
+            }
+            let instance = instances.get_mut(&self.name).unwrap();
+            self.call(instance, bytes).unwrap_or(false)
+        })
+    }
+}
+
+// Compiles and test-instantiates the module, replacing any predicate of the same name
+pub fn add(name: &str, size: Option<usize>, wasm: &[u8]) -> Result<Value, String> {
+    if name.is_empty() {
+        return Err("name must not be empty".to_string());
+    }
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, wasm).map_err(|e| format!("Invalid module: {}", e))?;
+    let predicate = Predicate {
+        name: name.to_string(),
+        generation: NEXT_GENERATION.fetch_add(1, Ordering::SeqCst),
+        size,
+        engine,
+        module: Arc::new(module),
+    };
+    predicate.instantiate()?;
+    let summary = json!({
+        "name": name,
+        "size": size,
+        "module_size": wasm.len(),
+    });
+    PREDICATES
+        .write()
+        .unwrap()
+        .insert(name.to_string(), predicate);
+    Ok(summary)
+}
+
+pub fn list() -> Vec<Value> {
+    PREDICATES
+        .read()
+        .unwrap()
+        .values()
+        .map(|predicate| {
+            json!({
+                "name": predicate.name,
+                "size": predicate.size,
+            })
+        })
+        .collect()
+}
+
+pub fn get(name: &str) -> Option<Predicate> {
+    PREDICATES.read().unwrap().get(name).cloned()
+}
+
+pub fn remove(name: &str) -> bool {
+    PREDICATES.write().unwrap().remove(name).is_some()
+}
@@ -0,0 +1,213 @@
+// Keeps the original bytes of every write made through the API so they can be rolled
+// back in reverse order, and an audit log of who changed what.
+use crate::crash;
+use crate::native_bridge;
+use crate::util;
+use lazy_static::lazy_static;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use warp::{Filter, Rejection};
+
+const MAX_RECORDS: usize = 10_000;
+const MAX_AUDIT_ENTRIES: usize = 10_000;
+const DEFAULT_LOG_LIMIT: usize = 1000;
+
+struct WriteRecord {
+    pid: i32,
+    address: usize,
+    // None when the target bytes could not be read before the write
+    original: Option<Vec<u8>>,
+    written: Vec<u8>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct WriteLogQuery {
+    // Only writes that cover this address
+    pub address: Option<usize>,
+    pub session: Option<String>,
+    // Unix time in milliseconds
+    pub since: Option<i64>,
+    pub limit: Option<usize>,
+}
+
+struct AuditLog {
+    next_id: u64,
+    entries: VecDeque<Value>,
+}
+
+lazy_static! {
+    static ref HISTORY: Mutex<Vec<WriteRecord>> = Mutex::new(Vec::new());
+    static ref AUDIT: Mutex<AuditLog> = Mutex::new(AuditLog {
+        next_id: 1,
+        entries: VecDeque::new(),
+    });
+}
+
+// Identifies the client behind a write: the X-Session header when sent, else its IP.
+pub fn session() -> impl Filter<Extract = (String,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("x-session")
+        .and(crash::remote())
+        .map(|header: Option<String>, remote: Option<SocketAddr>| {
+            header.unwrap_or_else(|| {
+                remote
+                    .map(|address| address.ip().to_string())
+                    .unwrap_or_else(|| "local".to_string())
+            })
+        })
+}
+
+fn audit(pid: i32, address: usize, old: Option<&[u8]>, new: &[u8], source: &str, session: &str) {
+    let mut log = AUDIT.lock().unwrap();
+    let entry = json!({
+        "id": log.next_id,
+        "timestamp": chrono::Local::now().timestamp_millis(),
+        "pid": pid,
+        "address": address,
+        "old": old.map(hex::encode),
+        "new": hex::encode(new),
+        "source": source,
+        "session": session,
+    });
+    log.next_id += 1;
+
+    // Optional append-only copy, one JSON object per line
+    if let Ok(path) = std::env::var("MEMORY_SERVER_WRITE_LOG") {
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| writeln!(file, "{}", entry));
+        if let Err(e) = written {
+            log::error!("Failed to append to write log {}: {}", path, e);
+        }
+    }
+
+    if log.entries.len() >= MAX_AUDIT_ENTRIES {
+        log.entries.pop_front();
+    }
+    log.entries.push_back(entry);
+}
+
+pub fn query(query: &WriteLogQuery) -> Vec<Value> {
+    let log = AUDIT.lock().unwrap();
+    let mut entries: Vec<Value> = log
+        .entries
+        .iter()
+        .rev()
+        .filter(|entry| {
+            let start = entry["address"].as_u64().unwrap_or(0) as usize;
+            let size = entry["new"].as_str().map_or(0, |new| new.len() / 2);
+            query
+                .address
+                .is_none_or(|address| address >= start && address < start + size)
+                && query
+                    .session
+                    .as_ref()
+                    .is_none_or(|session| entry["session"] == session.as_str())
+                && query
+                    .since
+                    .is_none_or(|since| entry["timestamp"].as_i64().unwrap_or(0) >= since)
+        })
+        .take(query.limit.unwrap_or(DEFAULT_LOG_LIMIT))
+        .cloned()
+        .collect();
+    entries.reverse();
+    entries
+}
+
+// Reads the bytes about to be overwritten. Call before writing and pass the result to `record`.
+pub fn read_original(pid: i32, address: usize, size: usize) -> Option<Vec<u8>> {
+    let mut buffer = vec![0u8; size];
+    match native_bridge::read_process_memory(pid, address as *mut libc::c_void, size, &mut buffer) {
+        Ok(nread) if nread as usize == size => Some(buffer),
+        _ => None,
+    }
+}
+
+// `source` names the API that wrote, `session` the client as returned by `session()`.
+pub fn record(
+    pid: i32,
+    address: usize,
+    original: Option<Vec<u8>>,
+    written: &[u8],
+    source: &str,
+    session: &str,
+) {
+    audit(pid, address, original.as_deref(), written, source, session);
+    let mut history = HISTORY.lock().unwrap();
+    if history.len() >= MAX_RECORDS {
+        history.remove(0);
+    }
+    history.push(WriteRecord {
+        pid,
+        address,
+        original,
+        written: written.to_vec(),
+    });
+}
+
+fn restore(record: &WriteRecord) -> Result<(), String> {
+    let original = record
+        .original
+        .as_ref()
+        .ok_or("Original bytes were not readable before the write")?;
+    let result = native_bridge::write_process_memory(
+        record.pid,
+        record.address as *mut libc::c_void,
+        original.len(),
+        original,
+    );
+    match result {
+        Ok(_) => Ok(()),
+        // The write may have needed a protection change, and so does its undo
+        Err(_) => util::write_with_protection_fallback(record.pid, record.address, original, false)
+            .map(|_| ()),
+    }
+}
+
+// Undoes up to `count` of the most recent writes to `pid`, newest first. Stops at the
+// first write that cannot be restored, which stays in the history.
+pub fn undo(pid: i32, count: usize, session: &str) -> Value {
+    let mut history = HISTORY.lock().unwrap();
+    let mut restored = Vec::new();
+    let mut error = None;
+    while restored.len() < count {
+        let Some(index) = history.iter().rposition(|record| record.pid == pid) else {
+            break;
+        };
+        let record = &history[index];
+        if let Err(e) = restore(record) {
+            error = Some(json!({
+                "address": record.address,
+                "size": record.written.len(),
+                "error": e,
+            }));
+            break;
+        }
+        audit(
+            pid,
+            record.address,
+            Some(&record.written),
+            record.original.as_deref().unwrap_or_default(),
+            "undo",
+            session,
+        );
+        restored.push(json!({
+            "address": record.address,
+            "size": record.written.len(),
+            "restored": hex::encode(record.original.as_deref().unwrap_or_default()),
+        }));
+        history.remove(index);
+    }
+    json!({
+        "restored": restored,
+        "failed": error,
+        "remaining": history.iter().filter(|record| record.pid == pid).count(),
+    })
+}
@@ -0,0 +1,295 @@
+// Scan, filter, write and freeze against memory-test-target through a real server. Attaching
+// to another process needs the privileges the server itself needs, so these only run with
+// `cargo test -- --ignored`.
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+struct Server {
+    child: Child,
+    port: u16,
+}
+
+impl Server {
+    fn start() -> Self {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let child = Command::new(env!("CARGO_BIN_EXE_memory-server"))
+            .args(["--port", &port.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while TcpStream::connect(("127.0.0.1", port)).is_err() {
+            assert!(Instant::now() < deadline, "server did not start");
+            thread::sleep(Duration::from_millis(50));
+        }
+        Server { child, port }
+    }
+
+    fn call(&self, method: &str, path: &str, body: Option<Value>) -> (u16, Value) {
+        let body = body.map(|b| b.to_string()).unwrap_or_default();
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port)).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        let body = serde_json::from_str(body).unwrap_or_else(|_| json!(body));
+        (status, body)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+struct Target {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    manifest: Value,
+}
+
+impl Target {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_memory-test-target"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        let manifest = serde_json::from_str(&line).unwrap();
+        Target {
+            child,
+            stdin,
+            stdout,
+            manifest,
+        }
+    }
+
+    fn pid(&self) -> u64 {
+        self.manifest["pid"].as_u64().unwrap()
+    }
+
+    fn command(&mut self, command: &str) -> Value {
+        writeln!(self.stdin, "{}", command).unwrap();
+        let mut line = String::new();
+        self.stdout.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    fn value<'a>(values: &'a Value, name: &str) -> &'a Value {
+        values["values"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|v| v["name"] == name)
+            .unwrap()
+    }
+
+    fn address(&self, name: &str) -> usize {
+        Self::value(&self.manifest, name)["address"]
+            .as_u64()
+            .unwrap() as usize
+    }
+
+    fn current_hex(&mut self, name: &str) -> String {
+        let values = self.command("values");
+        Self::value(&values, name)["hex"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+}
+
+impl Drop for Target {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn attach() -> (Server, Target) {
+    let server = Server::start();
+    let target = Target::start();
+    let (status, body) = server.call("POST", "/process", Some(json!({ "pid": target.pid() })));
+    assert_eq!(status, 200, "{}", body);
+    (server, target)
+}
+
+fn scan(server: &Server, target: &Target, scan_id: &str, data_type: &str, pattern: &str) -> Value {
+    let start = target.address("int8");
+    let (status, body) = server.call(
+        "POST",
+        "/memoryscan",
+        Some(json!({
+            "pattern": pattern,
+            "address_ranges": [[start, start + 4096]],
+            "find_type": "exact",
+            "data_type": data_type,
+            "scan_id": scan_id,
+            "align": 1,
+            "return_as_json": true,
+            "do_suspend": false,
+        })),
+    );
+    assert_eq!(status, 200, "{}", body);
+    body
+}
+
+#[test]
+#[ignore]
+fn every_data_type_is_found_by_an_exact_scan() {
+    let (server, target) = attach();
+    for value in target.manifest["values"].as_array().unwrap() {
+        let name = value["name"].as_str().unwrap();
+        let data_type = value["data_type"].as_str().unwrap();
+        let hex = value["hex"].as_str().unwrap();
+        let result = scan(&server, &target, name, data_type, hex);
+        let address = target.address(name);
+        let found = result["matched_addresses"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|m| m["address"].as_u64() == Some(address as u64));
+        assert!(found, "{} {} not found: {}", name, hex, result);
+    }
+}
+
+#[test]
+#[ignore]
+fn filters_follow_the_counter() {
+    let (server, mut target) = attach();
+    let hex = target.current_hex("counter");
+    scan(&server, &target, "counter", "int32", &hex);
+    target.command("tick");
+    let filter = |method: &str| {
+        server.call(
+            "POST",
+            "/memoryfilter",
+            Some(json!({
+                "pattern": "",
+                "data_type": "int32",
+                "scan_id": "counter",
+                "filter_method": method,
+                "return_as_json": true,
+                "do_suspend": false,
+            })),
+        )
+    };
+    let (status, body) = filter("increased");
+    assert_eq!(status, 200, "{}", body);
+    let address = target.address("counter") as u64;
+    let matched = body["matched_addresses"].as_array().unwrap();
+    assert!(matched.iter().any(|m| m["address"] == address), "{}", body);
+}
+
+#[test]
+#[ignore]
+fn writes_and_freezes_are_seen_by_the_target() {
+    let (server, mut target) = attach();
+    let address = target.address("counter");
+    let (status, body) = server.call(
+        "POST",
+        "/memory",
+        Some(json!({ "address": address, "buffer": 7i32.to_le_bytes() })),
+    );
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(target.current_hex("counter"), "07000000");
+
+    let (status, body) = server.call(
+        "POST",
+        "/batch",
+        Some(json!({ "operations": [{
+            "op": "freeze",
+            "address": address,
+            "buffer": 42i32.to_le_bytes(),
+            "interval_ms": 10,
+        }] })),
+    );
+    assert_eq!(status, 200, "{}", body);
+    let id = body["results"][0]["id"].clone();
+    target.command("tick");
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while target.current_hex("counter") != "2a000000" {
+        assert!(Instant::now() < deadline, "freeze was not reapplied");
+        thread::sleep(Duration::from_millis(20));
+    }
+    let (status, body) = server.call(
+        "POST",
+        "/batch",
+        Some(json!({ "operations": [{ "op": "unfreeze", "id": id }] })),
+    );
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(body["failed"], 0, "{}", body);
+}
+
+#[test]
+#[ignore]
+fn presets_and_values_follow_a_pointer_chain() {
+    let (server, mut target) = attach();
+    let (status, entry) = server.call(
+        "POST",
+        "/table",
+        Some(json!({
+            "description": "chained int32",
+            "address": target.address("pointer_chain"),
+            "data_type": "int32",
+            "pointer_offsets": [0, 0],
+        })),
+    );
+    assert_eq!(status, 200, "{}", entry);
+    let id = entry["id"].clone();
+    let (status, body) = server.call(
+        "POST",
+        "/table/presets",
+        Some(json!({ "id": id, "name": "max", "buffer": 999i32.to_le_bytes() })),
+    );
+    assert_eq!(status, 200, "{}", body);
+
+    let (status, applied) = server.call(
+        "POST",
+        "/table/apply",
+        Some(json!({ "id": id, "name": "max" })),
+    );
+    assert_eq!(status, 200, "{}", applied);
+    assert_eq!(
+        target.current_hex("int32"),
+        hex::encode(999i32.to_le_bytes())
+    );
+
+    let (status, set) = server.call(
+        "POST",
+        "/table/setvalue",
+        Some(json!({ "id": id, "value": "1000" })),
+    );
+    assert_eq!(status, 200, "{}", set);
+    assert_eq!(
+        target.current_hex("int32"),
+        hex::encode(1000i32.to_le_bytes())
+    );
+    assert_eq!(applied["address"], target.address("int32"));
+    assert_eq!(applied["address"], set["address"]);
+}
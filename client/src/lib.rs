@@ -0,0 +1,162 @@
+// Thin async client for the memory-server HTTP API, built on the same request types the
+// server deserializes.
+//
+//     let client = Client::new("http://127.0.0.1:3030");
+//     client.open_process(1234).await?;
+//     let bytes = client.read(0x7f00_0000_1000, 16).await?;
+pub use memory_server_types as types;
+
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::fmt;
+use types::{
+    MemoryFilterRequest, MemoryScanRequest, OpenProcessRequest, ReadMemoryRequest,
+    RemoveWatchPointRequest, RemoveWatchPointResponse, SetWatchPointRequest, SetWatchPointResponse,
+    WriteMemoryRequest,
+};
+
+#[derive(Debug)]
+pub enum Error {
+    Http(reqwest::Error),
+    // The server answered with a non-success status; `message` is the response body
+    Server { status: StatusCode, message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "HTTP error: {}", e),
+            Error::Server { status, message } => write!(f, "server error {}: {}", status, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    // Sent as X-Session so writes show up under this name in /writelog
+    session: Option<String>,
+}
+
+impl Client {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Client {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            session: None,
+        }
+    }
+
+    pub fn with_session(mut self, session: impl Into<String>) -> Self {
+        self.session = Some(session.into());
+        self
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let builder = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path));
+        match &self.session {
+            Some(session) => builder.header("x-session", session),
+            None => builder,
+        }
+    }
+
+    async fn send(builder: RequestBuilder) -> Result<reqwest::Response> {
+        let response = builder.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(response)
+        } else {
+            let message = response.text().await.unwrap_or_default();
+            Err(Error::Server { status, message })
+        }
+    }
+
+    async fn send_json<T: DeserializeOwned>(builder: RequestBuilder) -> Result<T> {
+        Ok(Self::send(builder).await?.json().await?)
+    }
+
+    pub async fn processes(&self) -> Result<Vec<Value>> {
+        Self::send_json(self.request(Method::GET, "/processes")).await
+    }
+
+    pub async fn open_process(&self, pid: i32) -> Result<()> {
+        let body = OpenProcessRequest { pid, stealth: None };
+        Self::send(self.request(Method::POST, "/process").json(&body)).await?;
+        Ok(())
+    }
+
+    pub async fn modules(&self) -> Result<Vec<Value>> {
+        let response: Value = Self::send_json(self.request(Method::GET, "/modules")).await?;
+        Ok(response["modules"].as_array().cloned().unwrap_or_default())
+    }
+
+    // Bytes that could not be read come back as an empty buffer, as the server sends them
+    pub async fn read(&self, address: usize, size: usize) -> Result<Vec<u8>> {
+        let query = ReadMemoryRequest { address, size };
+        let response = Self::send(self.request(Method::GET, "/memory").query(&query)).await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    pub async fn write(&self, address: usize, buffer: &[u8]) -> Result<()> {
+        let body = WriteMemoryRequest {
+            address,
+            buffer: buffer.to_vec(),
+            stealth: None,
+            unprotect: None,
+        };
+        self.write_with(&body).await
+    }
+
+    // Like `write`, with the stealth and unprotect options of the request
+    pub async fn write_with(&self, request: &WriteMemoryRequest) -> Result<()> {
+        Self::send(self.request(Method::POST, "/memory").json(request)).await?;
+        Ok(())
+    }
+
+    pub async fn scan(&self, request: &MemoryScanRequest) -> Result<Value> {
+        Self::send_json(self.request(Method::POST, "/memoryscan").json(request)).await
+    }
+
+    pub async fn filter(&self, request: &MemoryFilterRequest) -> Result<Value> {
+        Self::send_json(self.request(Method::POST, "/memoryfilter").json(request)).await
+    }
+
+    pub async fn watch(&self, request: &SetWatchPointRequest) -> Result<SetWatchPointResponse> {
+        Self::send_json(self.request(Method::POST, "/watchpoint").json(request)).await
+    }
+
+    pub async fn unwatch(&self, address: usize) -> Result<RemoveWatchPointResponse> {
+        let body = RemoveWatchPointRequest { address };
+        Self::send_json(self.request(Method::DELETE, "/watchpoint").json(&body)).await
+    }
+
+    // Watchpoint and breakpoint hits collected since the last call
+    pub async fn exceptions(&self) -> Result<Vec<Value>> {
+        Self::send_json(self.request(Method::GET, "/exceptioninfo")).await
+    }
+
+    // Escape hatch for endpoints without a dedicated method
+    pub async fn call(&self, method: Method, path: &str, body: Option<Value>) -> Result<Value> {
+        let builder = self.request(method, path);
+        let builder = match body {
+            Some(body) => builder.json(&body),
+            None => builder,
+        };
+        let text = Self::send(builder).await?.text().await?;
+        Ok(serde_json::from_str(&text).unwrap_or_else(|_| json!(text)))
+    }
+}
@@ -0,0 +1,618 @@
+// Request and response bodies of the memory-server HTTP API, shared by the server and
+// the Rust client.
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct OpenProcessRequest {
+    pub pid: i32,
+    // Debugger observability mitigations, e.g. ["no_ptrace"]
+    pub stealth: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct DiagnoseRequest {
+    // Defaults to the opened process
+    pub pid: Option<i32>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ReadMemoryRequest {
+    pub address: usize,
+    pub size: usize,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ReadRegionRequest {
+    // Any address inside the region to send; with size, the start of the range instead
+    pub address: usize,
+    pub size: Option<usize>,
+    // lz4 (default) or gzip
+    pub codec: Option<String>,
+    // gzip compression level, 0-9
+    pub level: Option<u32>,
+    pub chunk_size: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ResolveAddrRequest {
+    pub query: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct WriteMemoryRequest {
+    pub address: usize,
+    pub buffer: Vec<u8>,
+    // Write without attaching to the target (/proc/pid/mem or plain mach_vm_write)
+    pub stealth: Option<bool>,
+    // On failure, temporarily make read-only pages writable and retry
+    pub unprotect: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default, JsonSchema)]
+pub struct MemoryScanRequest {
+    pub pattern: String,
+    pub address_ranges: Vec<(usize, usize)>,
+    pub find_type: String,
+    pub data_type: String,
+    pub scan_id: String,
+    pub align: usize,
+    pub return_as_json: bool,
+    pub do_suspend: bool,
+    pub struct_name: Option<String>,
+    pub struct_offset: Option<usize>,
+    pub allocation_size: Option<usize>,
+    pub allocation_tolerance: Option<usize>,
+    pub skip_nonresident: Option<bool>,
+    pub log_stats: Option<bool>,
+    // Keep the raw chunks read by this scan on disk under the given snapshot name
+    pub save_snapshot: Option<String>,
+    // Scan a saved snapshot instead of live memory
+    pub from_snapshot: Option<String>,
+    // Scan a snapshot directory or ".snap" file at this path, e.g. one kept from an earlier session
+    pub from_dump: Option<String>,
+    // Skipped in addition to the session exclusion list
+    pub excluded_ranges: Option<Vec<(usize, usize)>>,
+    // Float unknown scans: keep only finite, normal values of a plausible magnitude
+    pub simple_values: Option<bool>,
+    // Adds the decoded value next to the hex string in JSON results
+    pub decode_values: Option<bool>,
+    // utf-8/utf-16 exact scans: plain text encoded server-side instead of a hex pattern
+    pub text: Option<String>,
+    // Only match text followed by a null terminator
+    pub null_terminated: Option<bool>,
+    // Text scans: ignore case, using Unicode case folding
+    pub case_insensitive: Option<bool>,
+    // Text scans: match both the composed (NFC) and decomposed (NFD) forms of the text
+    pub normalize: Option<bool>,
+    // Exact scans: find all of these in one pass, as text for utf-8/utf-16 and hex otherwise
+    pub patterns: Option<Vec<String>>,
+    // Exact scans: keep only matches with this value close by
+    pub nearby: Option<NearbyValue>,
+    // Only scan mappings of loaded modules, whose addresses survive a restart
+    pub static_only: Option<bool>,
+    // Only scan the thread-local storage blocks from /tls
+    pub tls_only: Option<bool>,
+    // flags8..flags64 exact scans: hex mask of the bits compared against the pattern
+    pub bit_mask: Option<String>,
+    // decimal32/decimal64 exact scans: the value as displayed, e.g. "12.34", encoded
+    // server-side as the stored integer instead of a hex pattern
+    pub display_value: Option<String>,
+    // Stored integer per displayed unit for decimal types; defaults to 100
+    pub scale: Option<u32>,
+    // Columns kept in JSON results, e.g. ["address"]; the address is always kept. "module"
+    // and "region" ({protection, mapping}) are only looked up when listed here
+    pub fields: Option<Vec<String>>,
+    // "normal" (default), "low" or "background": fewer threads and pauses between chunks so
+    // the target keeps running smoothly on the same device
+    pub priority: Option<String>,
+    // Name of an uploaded WASM predicate: find_type "predicate" keeps every aligned value it
+    // accepts, and exact scans keep only the matches it accepts
+    pub predicate: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct NearbyValue {
+    // Hex bytes, encoded like the scan pattern
+    pub pattern: String,
+    // Maximum distance in bytes between the start of the match and the start of the value
+    pub within: usize,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default, JsonSchema)]
+pub struct MemoryFilterRequest {
+    pub pattern: String,
+    pub data_type: String,
+    pub scan_id: String,
+    pub filter_method: String,
+    pub return_as_json: bool,
+    pub do_suspend: bool,
+    // Compare against a saved snapshot or dump instead of live memory
+    pub from_snapshot: Option<String>,
+    pub from_dump: Option<String>,
+    // Same as MemoryScanRequest::simple_values, for this filter pass only
+    pub simple_values: Option<bool>,
+    // Adds the decoded value next to the hex string in JSON results
+    pub decode_values: Option<bool>,
+    // Drop results outside the mappings of loaded modules
+    pub static_only: Option<bool>,
+    // flags8..flags64: hex mask of the bits compared, e.g. to catch a single flag toggling
+    pub bit_mask: Option<String>,
+    // Same as MemoryScanRequest::display_value and scale; the scale defaults to the scan's
+    pub display_value: Option<String>,
+    pub scale: Option<u32>,
+    // Same as MemoryScanRequest::fields
+    pub fields: Option<Vec<String>>,
+    // Same as MemoryScanRequest::priority
+    pub priority: Option<String>,
+    // WASM predicate applied to the current value by filter_method "predicate"; the pattern
+    // still sets how many bytes are read, as for "changed"
+    pub predicate: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct RepeatFilterRequest {
+    #[serde(flatten)]
+    pub filter: MemoryFilterRequest,
+    pub interval_ms: Option<u64>,
+    pub duration_ms: Option<u64>,
+    // Upper bound on filter passes; the loop also ends early once nothing survives
+    pub iterations: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct SeedEntry {
+    pub address: usize,
+    // Hex encoded expected value; the current memory is used when omitted
+    pub value: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ImportScanRequest {
+    pub scan_id: String,
+    pub data_type: String,
+    // Value size for types without a fixed width, e.g. utf-8
+    pub size: Option<usize>,
+    pub entries: Vec<SeedEntry>,
+}
+
+// Rectangle of the screen in pixels, from the top left corner
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+pub struct ScreenRegion {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+// Reads a number off the screen and runs an exact scan for it, or an exact filter when
+// scan_id already has results
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct OcrScanRequest {
+    pub scan_id: String,
+    pub data_type: String,
+    // Part of the screen showing the number; the whole screen when omitted
+    pub region: Option<ScreenRegion>,
+    // The number as read by the client, which skips the capture and OCR
+    pub value: Option<String>,
+    // First scan only: defaults to every writable region
+    pub address_ranges: Option<Vec<(usize, usize)>>,
+    // First scan only: defaults to the size of data_type
+    pub align: Option<usize>,
+}
+
+// Finds values close to the current time and keeps them as the results of scan_id
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct TimestampScanRequest {
+    pub scan_id: String,
+    pub address_ranges: Vec<(usize, usize)>,
+    // unix_seconds (default), unix_millis, or ticks for milliseconds of the monotonic clock
+    pub kind: Option<String>,
+    // int32, uint32, int64, uint64, float or double; int32 for unix_seconds, else int64
+    pub data_type: Option<String>,
+    // Largest distance from now, in the unit of kind; defaults to one day, or ten minutes
+    // for ticks
+    pub within: Option<u64>,
+    // Reference time in the unit of kind instead of the server clock, e.g. a tick count
+    // read from the target
+    pub now: Option<f64>,
+    // Defaults to the value size
+    pub align: Option<usize>,
+    // Entries listed in the response; every match is kept for filtering
+    pub max_results: Option<usize>,
+}
+
+// Finds three values within the given bounds, e.g. a player position, and keeps them as the
+// results of scan_id
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct Vec3ScanRequest {
+    pub scan_id: String,
+    pub address_ranges: Vec<(usize, usize)>,
+    // Inclusive (min, max) bounds of each component
+    pub x: (f64, f64),
+    pub y: (f64, f64),
+    pub z: (f64, f64),
+    // float (default), double or float16
+    pub data_type: Option<String>,
+    // Bytes from one component to the next; defaults to the value size
+    pub stride: Option<usize>,
+    // Defaults to the value size
+    pub align: Option<usize>,
+    // Entries listed in the response; every match is kept for filtering
+    pub max_results: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ExportResultsRequest {
+    pub scan_id: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ImportResultsRequest {
+    // Keeps the results under this scan_id instead of the one they were exported from
+    pub scan_id: Option<String>,
+}
+
+// Name and notes of a scan_id; fields left out keep their current value
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ScanMetadataRequest {
+    pub scan_id: String,
+    pub name: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ResultSummaryRequest {
+    pub scan_id: String,
+}
+
+// Searches thread stacks for a value, or with pointer_range/module for slots pointing into
+// a range, which finds return addresses into a module
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct StackScanRequest {
+    pub value: Option<serde_json::Value>,
+    // Encoding of value, as in /memoryscan; defaults to int32
+    pub data_type: Option<String>,
+    pub pointer_range: Option<(usize, usize)>,
+    pub module: Option<String>,
+    // Thread ids from /threads; all threads when missing
+    pub threads: Option<Vec<u64>>,
+    // Defaults to the value size, at most the pointer size
+    pub align: Option<usize>,
+    pub max_results: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct TlsRequest {
+    // TLS sizes are not exposed, so each block is a window of this many bytes, taken on both
+    // sides of the thread pointer on Linux. Defaults to 4 KiB.
+    pub block_size: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ExploreDirectoryRequest {
+    pub path: String,
+    pub max_depth: i32,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ReadFileRequest {
+    pub path: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct SetWatchPointRequest {
+    pub address: usize,
+    pub size: usize,
+    pub _type: String,
+    // "hardware" or "page"; defaults to page protection when the size is not 1, 2, 4 or 8
+    pub mode: Option<String>,
+    // Hits are only reported while this holds, e.g. "value > 1000 && pc in \"libgame.so\""
+    pub condition: Option<String>,
+    // Arm only these threads (ids from /threads) and threads whose name contains
+    // thread_filter, including matching threads started later. Hardware watchpoints only.
+    pub threads: Option<Vec<u64>>,
+    pub thread_filter: Option<String>,
+    pub on_hit: Option<HitAction>,
+}
+
+// Applied by the debugger to the stopped thread on every hit that passes the condition,
+// before it resumes: e.g. {"skip": true} turns the instruction into a NOP
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct HitAction {
+    // Register set to `value`, such as "x0" or "rax"
+    pub register: Option<String>,
+    #[serde(default)]
+    pub value: u64,
+    // Advances the pc past the instruction. x86 watchpoints report after the access, so
+    // there only breakpoints can skip.
+    #[serde(default)]
+    pub skip: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SetWatchPointResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct RemoveWatchPointRequest {
+    pub address: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RemoveWatchPointResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct SetBreakPointRequest {
+    pub address: usize,
+    pub hit_count: i32,
+    pub on_hit: Option<HitAction>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SetBreakPointResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct RemoveBreakPointRequest {
+    pub address: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RemoveBreakPointResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ChangeProcessStateRequest {
+    pub do_play: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ChangeProcessStateResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct PointerMapGenerateRequest {
+    pub address: u64,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct HexDumpRequest {
+    pub address: usize,
+    pub size: usize,
+    pub width: Option<usize>,
+    pub scan_id: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RegisterStructResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct RemoveStructRequest {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ReadStructRequest {
+    pub address: usize,
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ReadArrayRequest {
+    pub address: usize,
+    pub data_type: String,
+    pub count: usize,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct WriteArrayRequest {
+    pub address: usize,
+    pub data_type: String,
+    pub values: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct FormatValueRequest {
+    // Any scan data type, or fixed or bitfield
+    pub data_type: String,
+    // Hex bytes in memory order to decode; with value, the bytes a bitfield is written into
+    pub bytes: Option<String>,
+    // Value to encode
+    pub value: Option<serde_json::Value>,
+    // little (default) or big
+    pub endian: Option<String>,
+    // Integer width in bytes for fixed (default 4) and bitfield (default 1)
+    pub width: Option<usize>,
+    // fixed: fractional bits (default 16) and signedness (default signed)
+    pub fraction_bits: Option<u32>,
+    pub signed: Option<bool>,
+    // bitfield: lowest bit of the field and its length (default 1)
+    pub bit_offset: Option<u32>,
+    pub bit_count: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct GuessTypeRequest {
+    pub address: usize,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct PointerMapSaveRequest {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct PointerPathValidateRequest {
+    // Base module of the chain, matched by full path or file name
+    pub module: Option<String>,
+    pub module_offset: Option<u64>,
+    // Absolute start address, instead of module + module_offset
+    pub base_address: Option<u64>,
+    pub offsets: Vec<u64>,
+    // Address the chain should end at
+    pub expected: Option<u64>,
+    // Also follow the chain through this saved snapshot
+    pub snapshot: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct PointerMapCompareRequest {
+    pub map_a: String,
+    pub target_a: u64,
+    pub map_b: String,
+    pub target_b: u64,
+    pub max_depth: Option<usize>,
+    pub max_offset: Option<u64>,
+    pub max_results: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct RemoveTableEntryRequest {
+    pub id: u64,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct TableRelocateRequest {
+    // Entries to relocate; every entry with a signature when missing
+    pub ids: Option<Vec<u64>>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct GameGuardianImportRequest {
+    // Contents of the saved list file
+    pub content: String,
+    // Start freezing the items saved as frozen, at their saved value
+    pub apply_freezes: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct SetPresetRequest {
+    pub id: u64,
+    pub name: String,
+    pub buffer: Vec<u8>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct PresetRequest {
+    pub id: u64,
+    pub name: String,
+}
+
+// Writes a value typed as text to a table entry, picked by id or by description
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct SetTableValueRequest {
+    pub id: Option<u64>,
+    pub description: Option<String>,
+    // Converted to the entry's data type, e.g. "999999", "99.5" or text
+    pub value: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ListAnnotationsRequest {
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct RemoveAnnotationRequest {
+    pub id: u64,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct UnbindTriggerRequest {
+    pub id: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct RemoveMappingWatchRequest {
+    pub id: u64,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct UndoWriteRequest {
+    pub count: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct ExclusionRequest {
+    pub ranges: Option<Vec<(usize, usize)>>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct CancelScheduledWriteRequest {
+    pub id: u64,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct EnumerateAllocationsRequest {
+    pub min_size: Option<usize>,
+    pub max_size: Option<usize>,
+    pub max_count: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct PageInfoRequest {
+    pub address: usize,
+    pub size: usize,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct PrefetchRequest {
+    pub address_ranges: Vec<(usize, usize)>,
+    pub chunk_size: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct RemoveDeviceRequest {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct TakeSnapshotRequest {
+    pub name: String,
+    pub address_ranges: Option<Vec<(usize, usize)>>,
+    // Region filter used when no ranges are given, every character must be present
+    pub protection: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct RemoveSnapshotRequest {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct SnapshotChangedRequest {
+    pub name: String,
+    // Compare values of this type instead of raw bytes
+    pub data_type: Option<String>,
+    pub align: Option<usize>,
+    pub merge_gap: Option<usize>,
+    pub max_results: Option<usize>,
+    pub max_bytes: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+pub struct SnapshotDiffRequest {
+    pub old: String,
+    pub new: String,
+    pub data_type: Option<String>,
+    pub align: Option<usize>,
+    pub merge_gap: Option<usize>,
+    pub max_results: Option<usize>,
+    pub max_bytes: Option<usize>,
+}